@@ -5,7 +5,7 @@ use rayon::prelude::*;
 use solana_measure::measure::Measure;
 use solana_runtime::{
     accounts::{create_test_accounts, update_accounts_bench, Accounts},
-    accounts_index::Ancestors,
+    accounts_index::{AccountsIndexConfig, Ancestors},
 };
 use solana_sdk::{genesis_config::ClusterType, pubkey::Pubkey};
 use std::{collections::HashSet, env, fs, path::PathBuf};
@@ -43,6 +43,20 @@ fn main() {
                 .takes_value(false)
                 .help("Run clean"),
         )
+        .arg(
+            Arg::with_name("disk_index")
+                .long("disk_index")
+                .takes_value(false)
+                .help("Keep the accounts index on disk, caching recently used entries in memory"),
+        )
+        .arg(
+            Arg::with_name("index_memory_capacity")
+                .long("index_memory_capacity")
+                .takes_value(true)
+                .value_name("ENTRIES")
+                .requires("disk_index")
+                .help("Number of accounts index entries kept in memory with --disk_index"),
+        )
         .get_matches();
 
     let num_slots = value_t!(matches, "num_slots", usize).unwrap_or(4);
@@ -50,6 +64,8 @@ fn main() {
     let iterations = value_t!(matches, "iterations", usize).unwrap_or(20);
     let clean = matches.is_present("clean");
     println!("clean: {:?}", clean);
+    let disk_index = matches.is_present("disk_index");
+    println!("disk_index: {:?}", disk_index);
 
     let path = PathBuf::from(env::var("FARF_DIR").unwrap_or_else(|_| "farf".to_owned()))
         .join("accounts-bench");
@@ -57,8 +73,22 @@ fn main() {
     if fs::remove_dir_all(path.clone()).is_err() {
         println!("Warning: Couldn't remove {:?}", path);
     }
-    let accounts =
-        Accounts::new_with_config(vec![path], &ClusterType::Testnet, HashSet::new(), false);
+    let accounts_index_config = if disk_index {
+        let mut config = AccountsIndexConfig::new(path.join("accounts_index"));
+        if let Ok(in_memory_capacity) = value_t!(matches, "index_memory_capacity", usize) {
+            config.in_memory_capacity = in_memory_capacity;
+        }
+        Some(config)
+    } else {
+        None
+    };
+    let accounts = Accounts::new_with_config(
+        vec![path],
+        &ClusterType::Testnet,
+        HashSet::new(),
+        false,
+        accounts_index_config,
+    );
     println!("Creating {} accounts", num_accounts);
     let mut create_time = Measure::start("create accounts");
     let pubkeys: Vec<_> = (0..num_slots)
@@ -87,6 +117,26 @@ fn main() {
         ancestors.insert(i as u64, i - 1);
         accounts.add_root(i as u64);
     }
+    let (in_memory_entries, on_disk_entries) = accounts.accounts_db.accounts_index.num_entries();
+    println!(
+        "accounts index: {} entries in memory, {} on disk",
+        in_memory_entries, on_disk_entries
+    );
+    let mut lookup_time = Measure::start("lookup accounts");
+    pubkeys.par_iter().for_each(|pubkey| {
+        accounts.load_slow(&ancestors, pubkey);
+    });
+    lookup_time.stop();
+    let mut scan_time = Measure::start("scan accounts");
+    let num_scanned = accounts.load_all(&ancestors).len();
+    scan_time.stop();
+    println!(
+        "looked up {} accounts {}, scanned {} accounts {}",
+        pubkeys.len(),
+        lookup_time,
+        num_scanned,
+        scan_time
+    );
     let mut elapsed = vec![0; iterations];
     let mut elapsed_store = vec![0; iterations];
     for x in 0..iterations {
@@ -7,7 +7,7 @@ use {
         clock::UnixTimestamp, hash::Hash, native_token::lamports_to_sol,
         program_utils::limited_deserialize, transaction::Transaction,
     },
-    solana_transaction_status::UiTransactionStatusMeta,
+    solana_transaction_status::{TransactionWithStatusMeta, UiTransactionStatusMeta},
     std::{collections::HashMap, fmt, io},
 };
 
@@ -294,6 +294,28 @@ pub fn println_transaction(
     }
 }
 
+/// Prints the transaction as it was executed, including the addresses its message loaded from
+/// address lookup tables
+pub fn println_transaction_with_meta(
+    transaction_with_meta: &TransactionWithStatusMeta,
+    prefix: &str,
+) {
+    let transaction_status = transaction_with_meta.meta.clone().map(|meta| meta.into());
+    match transaction_with_meta.resolved_transaction() {
+        Some(transaction) => println_transaction(&transaction, &transaction_status, prefix, None),
+        None => println!(
+            "{}Transaction {} loads addresses that were not recorded",
+            prefix,
+            transaction_with_meta
+                .transaction
+                .signatures
+                .get(0)
+                .map(|signature| signature.to_string())
+                .unwrap_or_default(),
+        ),
+    }
+}
+
 /// Creates a new process bar for processing that will take an unknown amount of time
 pub fn new_spinner_progress_bar() -> ProgressBar {
     let progress_bar = ProgressBar::new(42);
@@ -251,6 +251,7 @@ pub enum CliCommand {
     ShowStakeAccount {
         pubkey: Pubkey,
         use_lamports_unit: bool,
+        with_rewards: bool,
     },
     StakeAuthorize {
         stake_account_pubkey: Pubkey,
@@ -1510,11 +1511,13 @@ pub fn process_command(config: &CliConfig) -> ProcessResult {
         CliCommand::ShowStakeAccount {
             pubkey: stake_account_pubkey,
             use_lamports_unit,
+            with_rewards,
         } => process_show_stake_account(
             &rpc_client,
             config,
             &stake_account_pubkey,
             *use_lamports_unit,
+            *with_rewards,
         ),
         CliCommand::ShowStakeHistory { use_lamports_unit } => {
             process_show_stake_history(&rpc_client, config, *use_lamports_unit)
@@ -25,10 +25,12 @@ use solana_client::{
     pubsub_client::PubsubClient,
     rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
     rpc_config::{
-        RpcAccountInfoConfig, RpcLargestAccountsConfig, RpcLargestAccountsFilter,
-        RpcProgramAccountsConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+        RpcAccountInfoConfig, RpcBlockProductionConfig, RpcBlockProductionConfigRange,
+        RpcLargestAccountsConfig, RpcLargestAccountsFilter, RpcProgramAccountsConfig,
+        RpcTransactionLogsConfig, RpcTransactionLogsFilter,
     },
     rpc_filter,
+    rpc_request::RpcError,
     rpc_response::SlotInfo,
 };
 use solana_remote_wallet::remote_wallet::RemoteWalletManager;
@@ -69,6 +71,9 @@ use std::{
 static CHECK_MARK: Emoji = Emoji("✅ ", "");
 static CROSS_MARK: Emoji = Emoji("❌ ", "");
 
+// The JSON-RPC error code of requests for methods the node does not implement
+const JSON_RPC_METHOD_NOT_FOUND: i64 = -32601;
+
 pub trait ClusterQuerySubCommands {
     fn cluster_query_subcommands(self) -> Self;
 }
@@ -1071,6 +1076,72 @@ pub fn process_show_block_production(
         start_slot = minimum_ledger_slot;
     }
 
+    // Let the node compute the per-leader totals unless the status of each slot is displayed,
+    // which happens for `--verbose` and the JSON output formats, falling back to the
+    // client-side computation for nodes without `getBlockProduction`
+    if !config.verbose
+        && matches!(
+            config.output_format,
+            OutputFormat::Display | OutputFormat::DisplayQuiet
+        )
+    {
+        let block_production =
+            rpc_client.get_block_production_with_config(RpcBlockProductionConfig {
+                identity: None,
+                range: Some(RpcBlockProductionConfigRange {
+                    first_slot: start_slot,
+                    last_slot: Some(end_slot),
+                }),
+                commitment: Some(CommitmentConfig::finalized()),
+            });
+        match block_production {
+            Ok(block_production) => {
+                let block_production = block_production.value;
+                let mut leaders: Vec<CliBlockProductionEntry> = block_production
+                    .by_identity
+                    .iter()
+                    .map(
+                        |(identity, (leader_slots, blocks_produced))| CliBlockProductionEntry {
+                            identity_pubkey: format_labeled_address(
+                                identity,
+                                &config.address_labels,
+                            ),
+                            leader_slots: *leader_slots as u64,
+                            blocks_produced: *blocks_produced as u64,
+                            skipped_slots: (leader_slots - blocks_produced) as u64,
+                        },
+                    )
+                    .collect();
+                leaders.sort_by(|a, b| a.identity_pubkey.cmp(&b.identity_pubkey));
+                let total_slots = leaders
+                    .iter()
+                    .map(|leader| leader.leader_slots as usize)
+                    .sum();
+                let total_blocks_produced = leaders
+                    .iter()
+                    .map(|leader| leader.blocks_produced as usize)
+                    .sum();
+                let block_production = CliBlockProduction {
+                    epoch,
+                    start_slot: block_production.range.first_slot,
+                    end_slot: block_production.range.last_slot,
+                    total_slots,
+                    total_blocks_produced,
+                    total_slots_skipped: total_slots - total_blocks_produced,
+                    leaders,
+                    individual_slot_status: vec![],
+                    verbose: config.verbose,
+                };
+                return Ok(config.output_format.formatted_string(&block_production));
+            }
+            Err(err) => match err.kind() {
+                ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. })
+                    if *code == JSON_RPC_METHOD_NOT_FOUND => {}
+                _ => return Err(err.into()),
+            },
+        }
+    }
+
     let progress_bar = new_spinner_progress_bar();
     progress_bar.set_message(&format!(
         "Fetching confirmed blocks between slots {} and {}...",
@@ -1598,6 +1669,7 @@ pub fn process_show_stakes(
             encoding: Some(solana_account_decoder::UiAccountEncoding::Base64),
             ..RpcAccountInfoConfig::default()
         },
+        ..RpcProgramAccountsConfig::default()
     };
 
     if let Some(vote_account_pubkeys) = vote_account_pubkeys {
@@ -7,7 +7,6 @@ use crate::{
     nonce::check_nonce_account,
     spend_utils::{resolve_spend_tx_and_check_account_balances, SpendAmount},
 };
-use chrono::{Local, TimeZone};
 use clap::{App, Arg, ArgGroup, ArgMatches, SubCommand};
 use solana_clap_utils::{
     fee_payer::{fee_payer_arg, FEE_PAYER_ARG},
@@ -34,7 +33,8 @@ use solana_remote_wallet::remote_wallet::RemoteWalletManager;
 use solana_sdk::{
     account::from_account,
     account_utils::StateMut,
-    clock::{Clock, Epoch, Slot, UnixTimestamp, SECONDS_PER_DAY},
+    clock::{Clock, Epoch, Slot, SECONDS_PER_DAY},
+    epoch_schedule::EpochSchedule,
     feature, feature_set,
     message::Message,
     pubkey::Pubkey,
@@ -50,7 +50,7 @@ use solana_stake_program::{
     stake_state::{Authorized, Lockup, Meta, StakeAuthorize, StakeState},
 };
 use solana_vote_program::vote_state::VoteState;
-use std::{convert::TryInto, ops::Deref, sync::Arc};
+use std::{ops::Deref, sync::Arc};
 
 pub const STAKE_AUTHORITY_ARG: ArgConstant<'static> = ArgConstant {
     name: "stake_authority",
@@ -412,6 +412,12 @@ impl StakeSubCommands for App<'_, '_> {
                         .long("lamports")
                         .takes_value(false)
                         .help("Display balance in lamports instead of SOL")
+                )
+                .arg(
+                    Arg::with_name("with_rewards")
+                        .long("with-rewards")
+                        .takes_value(false)
+                        .help("Display inflation rewards")
                 ),
         )
         .subcommand(
@@ -833,10 +839,12 @@ pub fn parse_show_stake_account(
     let stake_account_pubkey =
         pubkey_of_signer(matches, "stake_account_pubkey", wallet_manager)?.unwrap();
     let use_lamports_unit = matches.is_present("lamports");
+    let with_rewards = matches.is_present("with_rewards");
     Ok(CliCommandInfo {
         command: CliCommand::ShowStakeAccount {
             pubkey: stake_account_pubkey,
             use_lamports_unit,
+            with_rewards,
         },
         signers: vec![],
     })
@@ -1592,28 +1600,12 @@ pub(crate) fn fetch_epoch_rewards(
     let mut all_epoch_rewards = vec![];
 
     let epoch_schedule = rpc_client.get_epoch_schedule()?;
-    let slot = rpc_client.get_slot()?;
-    let first_available_block = rpc_client.get_first_available_block()?;
-
-    let mut epoch = epoch_schedule.get_epoch_and_slot_index(slot).0;
-    let mut epoch_info: Option<(Slot, UnixTimestamp, solana_transaction_status::Rewards)> = None;
-    while epoch > lowest_epoch {
-        let first_slot_in_epoch = epoch_schedule.get_first_slot_in_epoch(epoch);
-        if first_slot_in_epoch < first_available_block {
-            // RPC node is out of history data
-            break;
-        }
+    let mut rewards_epoch = rpc_client.get_epoch_info()?.epoch;
+    while rewards_epoch > lowest_epoch {
+        rewards_epoch -= 1;
 
-        let first_confirmed_block_in_epoch = *rpc_client
-            .get_confirmed_blocks_with_limit(first_slot_in_epoch, 1)?
-            .get(0)
-            .ok_or_else(|| format!("Unable to fetch first confirmed block for epoch {}", epoch))?;
-
-        let first_confirmed_block = match rpc_client.get_confirmed_block_with_encoding(
-            first_confirmed_block_in_epoch,
-            solana_transaction_status::UiTransactionEncoding::Base64,
-        ) {
-            Ok(first_confirmed_block) => first_confirmed_block,
+        let reward = match rpc_client.get_inflation_reward(&[*address], Some(rewards_epoch)) {
+            Ok(mut rewards) => rewards.pop().flatten(),
             Err(ClientError {
                 kind:
                     ClientErrorKind::RpcError(rpc_request::RpcError::RpcResponseError {
@@ -1621,8 +1613,16 @@ pub(crate) fn fetch_epoch_rewards(
                         ..
                     }),
                 ..
+            })
+            | Err(ClientError {
+                kind:
+                    ClientErrorKind::RpcError(rpc_request::RpcError::RpcResponseError {
+                        code: rpc_custom_error::JSON_RPC_SERVER_ERROR_BLOCK_CLEANED_UP,
+                        ..
+                    }),
+                ..
             }) => {
-                // RPC node doesn't have this block
+                // RPC node is out of history data
                 break;
             }
             Err(err) => {
@@ -1630,68 +1630,69 @@ pub(crate) fn fetch_epoch_rewards(
             }
         };
 
-        let epoch_start_time = if let Some(block_time) = first_confirmed_block.block_time {
-            block_time
-        } else {
-            break;
-        };
-
-        // Rewards for the previous epoch are found in the first confirmed block of the current epoch
-        let previous_epoch_rewards = first_confirmed_block.rewards;
+        if let Some(reward) = reward {
+            if reward.post_balance > reward.amount {
+                let rate_change =
+                    reward.amount as f64 / (reward.post_balance - reward.amount) as f64;
 
-        if let Some((effective_slot, epoch_end_time, epoch_rewards)) = epoch_info {
-            let wallclock_epoch_duration = if epoch_end_time > epoch_start_time {
-                Some(
-                    { Local.timestamp(epoch_end_time, 0) - Local.timestamp(epoch_start_time, 0) }
-                        .to_std()?
-                        .as_secs_f64(),
+                let apr = epoch_wallclock_duration(
+                    rpc_client,
+                    &epoch_schedule,
+                    rewards_epoch,
+                    reward.effective_slot,
                 )
-            } else {
-                None
-            };
-
-            if let Some(reward) = epoch_rewards
-                .into_iter()
-                .find(|reward| reward.pubkey == address.to_string())
-            {
-                if reward.post_balance > reward.lamports.try_into().unwrap_or(0) {
-                    let rate_change = reward.lamports.abs() as f64
-                        / (reward.post_balance as f64 - reward.lamports as f64);
-
-                    let apr = wallclock_epoch_duration.map(|wallclock_epoch_duration| {
-                        let wallclock_epochs_per_year =
-                            (SECONDS_PER_DAY * 356) as f64 / wallclock_epoch_duration;
-                        rate_change * wallclock_epochs_per_year
-                    });
-
-                    all_epoch_rewards.push(CliEpochReward {
-                        epoch,
-                        effective_slot,
-                        amount: reward.lamports.abs() as u64,
-                        post_balance: reward.post_balance,
-                        percent_change: rate_change * 100.0,
-                        apr: apr.map(|r| r * 100.0),
-                    });
-                }
+                .map(|wallclock_epoch_duration| {
+                    let wallclock_epochs_per_year =
+                        (SECONDS_PER_DAY * 356) as f64 / wallclock_epoch_duration;
+                    rate_change * wallclock_epochs_per_year
+                });
+
+                all_epoch_rewards.push(CliEpochReward {
+                    epoch: rewards_epoch,
+                    effective_slot: reward.effective_slot,
+                    amount: reward.amount,
+                    post_balance: reward.post_balance,
+                    percent_change: rate_change * 100.0,
+                    apr: apr.map(|r| r * 100.0),
+                    commission: reward.commission,
+                });
             }
         }
-
-        epoch -= 1;
-        epoch_info = Some((
-            first_confirmed_block_in_epoch,
-            epoch_start_time,
-            previous_epoch_rewards,
-        ));
     }
 
     Ok(all_epoch_rewards)
 }
 
+// Wallclock seconds from the first confirmed block of `epoch` to the block its rewards were paid
+// out in, if the RPC node knows both block times
+fn epoch_wallclock_duration(
+    rpc_client: &RpcClient,
+    epoch_schedule: &EpochSchedule,
+    epoch: Epoch,
+    reward_slot: Slot,
+) -> Option<f64> {
+    let first_slot_in_epoch = epoch_schedule.get_first_slot_in_epoch(epoch);
+    let first_confirmed_block_in_epoch = *rpc_client
+        .get_confirmed_blocks_with_limit(first_slot_in_epoch, 1)
+        .ok()?
+        .get(0)?;
+    let epoch_start_time = rpc_client
+        .get_block_time(first_confirmed_block_in_epoch)
+        .ok()?;
+    let epoch_end_time = rpc_client.get_block_time(reward_slot).ok()?;
+    if epoch_end_time > epoch_start_time {
+        Some((epoch_end_time - epoch_start_time) as f64)
+    } else {
+        None
+    }
+}
+
 pub fn process_show_stake_account(
     rpc_client: &RpcClient,
     config: &CliConfig,
     stake_account_address: &Pubkey,
     use_lamports_unit: bool,
+    with_rewards: bool,
 ) -> ProcessResult {
     let stake_account = rpc_client.get_account(stake_account_address)?;
     if stake_account.owner != solana_stake_program::id() {
@@ -1721,7 +1722,7 @@ pub fn process_show_stake_account(
                 is_stake_program_v2_enabled(rpc_client)?, // At v1.6, this check can be removed and simply passed as `true`
             );
 
-            if state.stake_type == CliStakeType::Stake {
+            if state.stake_type == CliStakeType::Stake && with_rewards {
                 if let Some(activation_epoch) = state.activation_epoch {
                     let rewards =
                         fetch_epoch_rewards(rpc_client, stake_account_address, activation_epoch);
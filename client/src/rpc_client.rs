@@ -4,6 +4,7 @@ use crate::{
     mock_sender::{MockSender, Mocks},
     rpc_config::RpcAccountInfoConfig,
     rpc_config::{
+        RpcBlockProductionConfig, RpcConfirmedBlockConfig, RpcEpochConfig,
         RpcGetConfirmedSignaturesForAddress2Config, RpcLargestAccountsConfig,
         RpcProgramAccountsConfig, RpcSendTransactionConfig, RpcSimulateTransactionConfig,
         RpcTokenAccountsFilter,
@@ -22,7 +23,7 @@ use solana_account_decoder::{
 };
 use solana_sdk::{
     account::Account,
-    clock::{Slot, UnixTimestamp, DEFAULT_MS_PER_SLOT, MAX_HASH_AGE_IN_SECONDS},
+    clock::{Epoch, Slot, UnixTimestamp, DEFAULT_MS_PER_SLOT, MAX_HASH_AGE_IN_SECONDS},
     commitment_config::{CommitmentConfig, CommitmentLevel},
     epoch_info::EpochInfo,
     epoch_schedule::EpochSchedule,
@@ -33,7 +34,8 @@ use solana_sdk::{
     transaction::{self, uses_durable_nonce, Transaction},
 };
 use solana_transaction_status::{
-    EncodedConfirmedBlock, EncodedConfirmedTransaction, TransactionStatus, UiTransactionEncoding,
+    EncodedConfirmedBlock, EncodedConfirmedTransaction, TransactionStatus, UiConfirmedBlock,
+    UiTransactionEncoding,
 };
 use solana_vote_program::vote_state::MAX_LOCKOUT_HISTORY;
 use std::{
@@ -329,6 +331,16 @@ impl RpcClient {
         self.get_signature_status_with_commitment(signature, self.commitment_config)
     }
 
+    pub fn get_transaction_retry_status(
+        &self,
+        signature: &Signature,
+    ) -> ClientResult<Option<RpcTransactionRetryStatus>> {
+        self.send(
+            RpcRequest::GetTransactionRetryStatus,
+            json!([signature.to_string()]),
+        )
+    }
+
     pub fn get_signature_statuses(
         &self,
         signatures: &[Signature],
@@ -500,6 +512,14 @@ impl RpcClient {
         self.send(RpcRequest::GetConfirmedBlock, json!([slot, encoding]))
     }
 
+    pub fn get_confirmed_block_with_config(
+        &self,
+        slot: Slot,
+        config: RpcConfirmedBlockConfig,
+    ) -> ClientResult<UiConfirmedBlock> {
+        self.send(RpcRequest::GetConfirmedBlock, json!([slot, config]))
+    }
+
     pub fn get_confirmed_blocks(
         &self,
         start_slot: Slot,
@@ -622,6 +642,23 @@ impl RpcClient {
         self.get_leader_schedule_with_commitment(slot, self.commitment_config)
     }
 
+    pub fn get_block_production(&self) -> RpcResult<RpcBlockProduction> {
+        self.send(RpcRequest::GetBlockProduction, Value::Null)
+    }
+
+    pub fn get_block_production_with_config(
+        &self,
+        config: RpcBlockProductionConfig,
+    ) -> RpcResult<RpcBlockProduction> {
+        let commitment = config.commitment.unwrap_or(self.commitment_config);
+        let commitment = self.maybe_map_commitment(commitment)?;
+        let config = RpcBlockProductionConfig {
+            commitment: Some(commitment),
+            ..config
+        };
+        self.send(RpcRequest::GetBlockProduction, json!([config]))
+    }
+
     pub fn get_leader_schedule_with_commitment(
         &self,
         slot: Option<Slot>,
@@ -656,6 +693,27 @@ impl RpcClient {
         self.send(RpcRequest::GetInflationRate, Value::Null)
     }
 
+    pub fn get_inflation_reward(
+        &self,
+        addresses: &[Pubkey],
+        epoch: Option<Epoch>,
+    ) -> ClientResult<Vec<Option<RpcInflationReward>>> {
+        let addresses: Vec<_> = addresses
+            .iter()
+            .map(|address| address.to_string())
+            .collect();
+        self.send(
+            RpcRequest::GetInflationReward,
+            json!([
+                addresses,
+                RpcEpochConfig {
+                    epoch,
+                    commitment: Some(self.maybe_map_commitment(self.commitment_config)?),
+                }
+            ]),
+        )
+    }
+
     pub fn get_version(&self) -> ClientResult<RpcVersionInfo> {
         self.send(RpcRequest::GetVersion, Value::Null)
     }
@@ -730,6 +788,8 @@ impl RpcClient {
             encoding: Some(UiAccountEncoding::Base64),
             commitment: Some(self.maybe_map_commitment(commitment_config)?),
             data_slice: None,
+            min_context_slot: None,
+            slot: None,
         };
         let response = self.sender.send(
             RpcRequest::GetAccountInfo,
@@ -777,6 +837,8 @@ impl RpcClient {
             encoding: Some(UiAccountEncoding::Base64),
             commitment: Some(self.maybe_map_commitment(commitment_config)?),
             data_slice: None,
+            min_context_slot: None,
+            slot: None,
         };
         let pubkeys: Vec<_> = pubkeys.iter().map(|pubkey| pubkey.to_string()).collect();
         let response = self.send(RpcRequest::GetMultipleAccounts, json!([pubkeys, config]))?;
@@ -846,6 +908,7 @@ impl RpcClient {
                     commitment: Some(self.commitment_config),
                     ..RpcAccountInfoConfig::default()
                 },
+                ..RpcProgramAccountsConfig::default()
             },
         )
     }
@@ -861,15 +924,44 @@ impl RpcClient {
             commitment: Some(commitment),
             ..config.account_config
         };
-        let config = RpcProgramAccountsConfig {
+        let mut config = RpcProgramAccountsConfig {
             account_config,
             ..config
         };
-        let accounts: Vec<RpcKeyedAccount> = self.send(
-            RpcRequest::GetProgramAccounts,
-            json!([pubkey.to_string(), config]),
-        )?;
-        parse_keyed_accounts(accounts, RpcRequest::GetProgramAccounts)
+        // With a `limit`, keep requesting pages until the node stops returning a cursor. Later
+        // pages are read from the bank of the first page, so that together they form a
+        // consistent view of the program's accounts
+        let mut pubkey_accounts = vec![];
+        loop {
+            let accounts = match self.send(
+                RpcRequest::GetProgramAccounts,
+                json!([pubkey.to_string(), config]),
+            )? {
+                RpcProgramAccounts::Page(Response {
+                    context,
+                    value: RpcKeyedAccountsPage { accounts, cursor },
+                }) => {
+                    config.account_config.slot = Some(context.slot);
+                    config.cursor = cursor;
+                    accounts
+                }
+                RpcProgramAccounts::WithContext(Response { value, .. }) => {
+                    config.cursor = None;
+                    value
+                }
+                RpcProgramAccounts::Accounts(accounts) => {
+                    config.cursor = None;
+                    accounts
+                }
+            };
+            pubkey_accounts.extend(parse_keyed_accounts(
+                accounts,
+                RpcRequest::GetProgramAccounts,
+            )?);
+            if config.cursor.is_none() {
+                return Ok(pubkey_accounts);
+            }
+        }
     }
 
     /// Request the transaction count.
@@ -1043,6 +1135,8 @@ impl RpcClient {
             encoding: Some(UiAccountEncoding::JsonParsed),
             commitment: Some(self.maybe_map_commitment(commitment_config)?),
             data_slice: None,
+            min_context_slot: None,
+            slot: None,
         };
         let response = self.sender.send(
             RpcRequest::GetAccountInfo,
@@ -1140,6 +1234,8 @@ impl RpcClient {
             encoding: Some(UiAccountEncoding::JsonParsed),
             commitment: Some(self.maybe_map_commitment(commitment_config)?),
             data_slice: None,
+            min_context_slot: None,
+            slot: None,
         };
 
         self.send(
@@ -1179,6 +1275,8 @@ impl RpcClient {
             encoding: Some(UiAccountEncoding::JsonParsed),
             commitment: Some(self.maybe_map_commitment(commitment_config)?),
             data_slice: None,
+            min_context_slot: None,
+            slot: None,
         };
 
         self.send(
@@ -13,6 +13,11 @@ pub const JSON_RPC_SERVER_ERROR_TRANSACTION_PRECOMPILE_VERIFICATION_FAILURE: i64
 pub const JSON_RPC_SERVER_ERROR_SLOT_SKIPPED: i64 = -32007;
 pub const JSON_RPC_SERVER_ERROR_NO_SNAPSHOT: i64 = -32008;
 pub const JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED: i64 = -32009;
+pub const JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED: i64 = -32010;
+pub const JSON_RPC_SERVER_ERROR_BANK_NOT_AVAILABLE: i64 = -32011;
+pub const JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_QUEUE_FULL: i64 = -32012;
+pub const JSON_RPC_SERVER_ERROR_RESPONSE_TOO_LARGE: i64 = -32013;
+pub const JSON_RPC_SERVER_ERROR_RATE_LIMITED: i64 = -32014;
 
 pub enum RpcCustomError {
     BlockCleanedUp {
@@ -38,6 +43,20 @@ pub enum RpcCustomError {
     LongTermStorageSlotSkipped {
         slot: Slot,
     },
+    MinContextSlotNotReached {
+        context_slot: Slot,
+    },
+    BankNotAvailable {
+        slot: Slot,
+    },
+    SendTransactionQueueFull,
+    ResponseTooLarge {
+        max_response_bytes: usize,
+    },
+    RateLimited {
+        method: String,
+        retry_after_ms: u64,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -46,6 +65,18 @@ pub struct NodeUnhealthyErrorData {
     pub num_slots_behind: Option<Slot>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MinContextSlotNotReachedErrorData {
+    pub context_slot: Slot,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitedErrorData {
+    pub retry_after_ms: u64,
+}
+
 impl From<RpcCustomError> for Error {
     fn from(e: RpcCustomError) -> Self {
         match e {
@@ -115,6 +146,45 @@ impl From<RpcCustomError> for Error {
                 message: format!("Slot {} was skipped, or missing in long-term storage", slot),
                 data: None,
             },
+            RpcCustomError::MinContextSlotNotReached { context_slot } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED),
+                message: "Minimum context slot has not been reached".to_string(),
+                data: Some(serde_json::json!(MinContextSlotNotReachedErrorData {
+                    context_slot,
+                })),
+            },
+            RpcCustomError::BankNotAvailable { slot } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_BANK_NOT_AVAILABLE),
+                message: format!(
+                    "Bank for slot {} is not available, it may not be frozen yet or have been pruned from the node",
+                    slot
+                ),
+                data: None,
+            },
+            RpcCustomError::SendTransactionQueueFull => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_QUEUE_FULL),
+                message: "Transaction queue is full, try again later".to_string(),
+                data: None,
+            },
+            RpcCustomError::ResponseTooLarge { max_response_bytes } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_RESPONSE_TOO_LARGE),
+                message: format!(
+                    "Response exceeds the node's limit of {} bytes, use `limit` to paginate",
+                    max_response_bytes
+                ),
+                data: None,
+            },
+            RpcCustomError::RateLimited {
+                method,
+                retry_after_ms,
+            } => Self {
+                code: ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_RATE_LIMITED),
+                message: format!(
+                    "Too many requests for {}, retry after {}ms",
+                    method, retry_after_ms
+                ),
+                data: Some(serde_json::json!(RateLimitedErrorData { retry_after_ms })),
+            },
         }
     }
 }
@@ -56,23 +56,25 @@ pub struct Memcmp {
 }
 
 impl Memcmp {
-    pub fn bytes_match(&self, data: &[u8]) -> bool {
+    /// The decoded bytes to match, or `None` if they are not validly encoded
+    pub fn bytes(&self) -> Option<Vec<u8>> {
         match &self.bytes {
-            MemcmpEncodedBytes::Binary(bytes) => {
-                let bytes = bs58::decode(bytes).into_vec();
-                if bytes.is_err() {
-                    return false;
-                }
-                let bytes = bytes.unwrap();
-                if self.offset > data.len() {
-                    return false;
-                }
-                if data[self.offset..].len() < bytes.len() {
-                    return false;
-                }
-                data[self.offset..self.offset + bytes.len()] == bytes[..]
-            }
+            MemcmpEncodedBytes::Binary(bytes) => bs58::decode(bytes).into_vec().ok(),
+        }
+    }
+
+    pub fn bytes_match(&self, data: &[u8]) -> bool {
+        let bytes = match self.bytes() {
+            Some(bytes) => bytes,
+            None => return false,
+        };
+        if self.offset > data.len() {
+            return false;
+        }
+        if data[self.offset..].len() < bytes.len() {
+            return false;
         }
+        data[self.offset..self.offset + bytes.len()] == bytes[..]
     }
 }
 
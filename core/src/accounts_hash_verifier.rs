@@ -9,7 +9,7 @@ use crate::{
     snapshot_packager_service::PendingSnapshotPackage,
 };
 use solana_runtime::snapshot_package::{
-    AccountsPackage, AccountsPackagePre, AccountsPackageReceiver,
+    AccountsPackage, AccountsPackagePre, AccountsPackageReceiver, SnapshotType,
 };
 use solana_sdk::{clock::Slot, hash::Hash, pubkey::Pubkey};
 use std::collections::{HashMap, HashSet};
@@ -36,7 +36,6 @@ impl AccountsHashVerifier {
         trusted_validators: Option<HashSet<Pubkey>>,
         halt_on_trusted_validators_accounts_hash_mismatch: bool,
         fault_injection_rate_slots: u64,
-        snapshot_interval_slots: u64,
     ) -> Self {
         let exit = exit.clone();
         let cluster_info = cluster_info.clone();
@@ -60,7 +59,6 @@ impl AccountsHashVerifier {
                                 &mut hashes,
                                 &exit,
                                 fault_injection_rate_slots,
-                                snapshot_interval_slots,
                             );
                         }
                         Err(RecvTimeoutError::Disconnected) => break,
@@ -83,7 +81,6 @@ impl AccountsHashVerifier {
         hashes: &mut Vec<(Slot, Hash)>,
         exit: &Arc<AtomicBool>,
         fault_injection_rate_slots: u64,
-        snapshot_interval_slots: u64,
     ) {
         let accounts_package =
             solana_runtime::snapshot_utils::process_accounts_package_pre(accounts_package);
@@ -96,7 +93,6 @@ impl AccountsHashVerifier {
             hashes,
             exit,
             fault_injection_rate_slots,
-            snapshot_interval_slots,
         );
     }
 
@@ -109,7 +105,6 @@ impl AccountsHashVerifier {
         hashes: &mut Vec<(Slot, Hash)>,
         exit: &Arc<AtomicBool>,
         fault_injection_rate_slots: u64,
-        snapshot_interval_slots: u64,
     ) {
         let hash = accounts_package.hash;
         if fault_injection_rate_slots != 0
@@ -140,9 +135,20 @@ impl AccountsHashVerifier {
             }
         }
 
-        if accounts_package.block_height % snapshot_interval_slots == 0 {
+        if accounts_package.snapshot_type.is_some() {
             if let Some(pending_snapshot_package) = pending_snapshot_package.as_ref() {
-                *pending_snapshot_package.lock().unwrap() = Some(accounts_package);
+                let mut pending_snapshot_package = pending_snapshot_package.lock().unwrap();
+                // Never drop a pending full snapshot in favor of an incremental snapshot, since
+                // later incremental snapshots are based on it
+                let is_full_snapshot_pending =
+                    pending_snapshot_package.as_ref().map_or(false, |pending| {
+                        pending.snapshot_type == Some(SnapshotType::FullSnapshot)
+                    });
+                if !is_full_snapshot_pending
+                    || accounts_package.snapshot_type == Some(SnapshotType::FullSnapshot)
+                {
+                    *pending_snapshot_package = Some(accounts_package);
+                }
             }
         }
 
@@ -271,6 +277,7 @@ mod tests {
                 storages: vec![],
                 archive_format: ArchiveFormat::TarBzip2,
                 snapshot_version: SnapshotVersion::default(),
+                snapshot_type: None,
             };
 
             AccountsHashVerifier::process_accounts_package(
@@ -282,7 +289,6 @@ mod tests {
                 &mut hashes,
                 &exit,
                 0,
-                100,
             );
             // sleep for 1ms to create a newer timestmap for gossip entry
             // otherwise the timestamp won't be newer.
@@ -304,4 +310,66 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_pending_full_snapshot_not_replaced_by_incremental() {
+        use std::path::PathBuf;
+        use tempfile::TempDir;
+        let keypair = Keypair::new();
+
+        let contact_info = ContactInfo::new_localhost(&keypair.pubkey(), 0);
+        let cluster_info = ClusterInfo::new_with_invalid_keypair(contact_info);
+        let exit = Arc::new(AtomicBool::new(false));
+        let pending_snapshot_package = PendingSnapshotPackage::default();
+        let mut hashes = vec![];
+        let mut process_accounts_package = |slot: Slot, snapshot_type: Option<SnapshotType>| {
+            let accounts_package = AccountsPackage {
+                hash: hash(&[slot as u8]),
+                block_height: slot,
+                slot,
+                slot_deltas: vec![],
+                snapshot_links: TempDir::new().unwrap(),
+                tar_output_file: PathBuf::from("."),
+                storages: vec![],
+                archive_format: ArchiveFormat::TarBzip2,
+                snapshot_version: SnapshotVersion::default(),
+                snapshot_type,
+            };
+            AccountsHashVerifier::process_accounts_package(
+                accounts_package,
+                &cluster_info,
+                &None,
+                false,
+                &Some(pending_snapshot_package.clone()),
+                &mut hashes,
+                &exit,
+                0,
+            );
+        };
+        let pending_slot = || {
+            pending_snapshot_package
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|accounts_package| accounts_package.slot)
+        };
+
+        // Packages that are not snapshots are only used for the accounts hash
+        process_accounts_package(100, None);
+        assert_eq!(pending_slot(), None);
+
+        process_accounts_package(200, Some(SnapshotType::FullSnapshot));
+        assert_eq!(pending_slot(), Some(200));
+
+        process_accounts_package(300, Some(SnapshotType::IncrementalSnapshot(200)));
+        assert_eq!(pending_slot(), Some(200));
+
+        process_accounts_package(400, Some(SnapshotType::FullSnapshot));
+        assert_eq!(pending_slot(), Some(400));
+
+        // Once the full snapshot is archived, the next incremental snapshot can be pending
+        pending_snapshot_package.lock().unwrap().take();
+        process_accounts_package(500, Some(SnapshotType::IncrementalSnapshot(400)));
+        assert_eq!(pending_slot(), Some(500));
+    }
 }
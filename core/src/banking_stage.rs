@@ -36,10 +36,11 @@ use solana_sdk::{
         Slot, DEFAULT_TICKS_PER_SLOT, MAX_PROCESSING_AGE, MAX_TRANSACTION_FORWARDING_DELAY,
         MAX_TRANSACTION_FORWARDING_DELAY_GPU,
     },
+    compute_budget::ComputeBudgetRequest,
     poh_config::PohConfig,
     pubkey::Pubkey,
     timing::{duration_as_ms, timestamp},
-    transaction::{self, Transaction, TransactionError},
+    transaction::{self, Transaction, TransactionError, VersionedTransaction},
 };
 use solana_transaction_status::token_balances::{
     collect_token_balances, TransactionTokenBalancesSet,
@@ -59,6 +60,9 @@ use std::{
 
 type PacketsAndOffsets = (Packets, Vec<usize>);
 pub type UnprocessedPackets = Vec<PacketsAndOffsets>;
+/// Buffered batches of packets, each tagged with the compute unit price offered by all of its
+/// packets and ordered by that price, highest first
+pub type BufferedPackets = Vec<(u64, PacketsAndOffsets)>;
 
 /// Transaction forwarding
 pub const FORWARD_TRANSACTIONS_TO_LEADER_AT_SLOT_OFFSET: u64 = 2;
@@ -156,9 +160,10 @@ impl BankingStage {
         Self { bank_thread_hdls }
     }
 
-    fn filter_valid_packets_for_forwarding(all_packets: &[PacketsAndOffsets]) -> Vec<&Packet> {
+    fn filter_valid_packets_for_forwarding<'a>(
+        all_packets: impl Iterator<Item = &'a PacketsAndOffsets>,
+    ) -> Vec<&'a Packet> {
         all_packets
-            .iter()
             .flat_map(|(p, valid_indexes)| valid_indexes.iter().map(move |x| &p.packets[*x]))
             .collect()
     }
@@ -166,9 +171,11 @@ impl BankingStage {
     fn forward_buffered_packets(
         socket: &std::net::UdpSocket,
         tpu_forwards: &std::net::SocketAddr,
-        unprocessed_packets: &[PacketsAndOffsets],
+        buffered_packets: &[(u64, PacketsAndOffsets)],
     ) -> std::io::Result<()> {
-        let packets = Self::filter_valid_packets_for_forwarding(unprocessed_packets);
+        let packets = Self::filter_valid_packets_for_forwarding(
+            buffered_packets.iter().map(|(_, packets)| packets),
+        );
         inc_new_counter_info!("banking_stage-forwarded_packets", packets.len());
         for p in packets {
             socket.send_to(&p.data[..p.meta.size], &tpu_forwards)?;
@@ -180,29 +187,35 @@ impl BankingStage {
     pub fn consume_buffered_packets(
         my_pubkey: &Pubkey,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
-        buffered_packets: &mut Vec<PacketsAndOffsets>,
+        buffered_packets: &mut BufferedPackets,
         batch_limit: usize,
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: &ReplayVoteSender,
-    ) -> UnprocessedPackets {
+    ) -> BufferedPackets {
         let mut unprocessed_packets = vec![];
         let mut rebuffered_packets = 0;
         let mut new_tx_count = 0;
         let buffered_len = buffered_packets.len();
         let mut buffered_packets_iter = buffered_packets.drain(..);
         let mut dropped_batches_count = 0;
+        let mut unprocessed_packets_count = 0;
+        let packet_limit = batch_limit * PACKETS_PER_BATCH;
 
         let mut proc_start = Measure::start("consume_buffered_process");
-        while let Some((msgs, unprocessed_indexes)) = buffered_packets_iter.next() {
+        while let Some((compute_unit_price, (msgs, unprocessed_indexes))) =
+            buffered_packets_iter.next()
+        {
             let bank = poh_recorder.lock().unwrap().bank();
             if bank.is_none() {
                 rebuffered_packets += unprocessed_indexes.len();
-                Self::push_unprocessed(
+                Self::push_buffered(
                     &mut unprocessed_packets,
+                    &mut unprocessed_packets_count,
+                    compute_unit_price,
                     msgs,
                     unprocessed_indexes,
                     &mut dropped_batches_count,
-                    batch_limit,
+                    packet_limit,
                 );
                 continue;
             }
@@ -222,19 +235,23 @@ impl BankingStage {
 
             // Collect any unprocessed transactions in this batch for forwarding
             rebuffered_packets += new_unprocessed_indexes.len();
-            Self::push_unprocessed(
+            Self::push_buffered(
                 &mut unprocessed_packets,
+                &mut unprocessed_packets_count,
+                compute_unit_price,
                 msgs,
                 new_unprocessed_indexes,
                 &mut dropped_batches_count,
-                batch_limit,
+                packet_limit,
             );
 
             if processed < verified_txs_len {
                 let next_leader = poh_recorder.lock().unwrap().next_slot_leader();
                 // Walk thru rest of the transactions and filter out the invalid (e.g. too old) ones
                 #[allow(clippy::while_let_on_iterator)]
-                while let Some((msgs, unprocessed_indexes)) = buffered_packets_iter.next() {
+                while let Some((compute_unit_price, (msgs, unprocessed_indexes))) =
+                    buffered_packets_iter.next()
+                {
                     let unprocessed_indexes = Self::filter_unprocessed_packets(
                         &bank,
                         &msgs,
@@ -242,12 +259,14 @@ impl BankingStage {
                         my_pubkey,
                         next_leader,
                     );
-                    Self::push_unprocessed(
+                    Self::push_buffered(
                         &mut unprocessed_packets,
+                        &mut unprocessed_packets_count,
+                        compute_unit_price,
                         msgs,
                         unprocessed_indexes,
                         &mut dropped_batches_count,
-                        batch_limit,
+                        packet_limit,
                     );
                 }
             }
@@ -272,6 +291,99 @@ impl BankingStage {
         unprocessed_packets
     }
 
+    /// Add newly received `unprocessed_packets` to `buffered_packets`. Once the compute budget
+    /// program is enabled, the buffer is kept ordered by the compute unit price the transactions
+    /// offer, with packets offering the same price keeping their arrival order; until then
+    /// packets are buffered in arrival order. This is the only place the price of a packet is
+    /// computed.
+    fn buffer_packets(
+        buffered_packets: &mut BufferedPackets,
+        unprocessed_packets: UnprocessedPackets,
+        packet_limit: usize,
+        prioritize_by_price: bool,
+    ) {
+        let mut dropped_batches_count = 0;
+        let mut buffered_packets_count = buffered_packets
+            .iter()
+            .map(|(_, (_, indexes))| indexes.len())
+            .sum();
+        for (msgs, unprocessed_indexes) in unprocessed_packets {
+            if !prioritize_by_price {
+                Self::push_buffered(
+                    buffered_packets,
+                    &mut buffered_packets_count,
+                    0,
+                    msgs,
+                    unprocessed_indexes,
+                    &mut dropped_batches_count,
+                    packet_limit,
+                );
+                continue;
+            }
+
+            let mut prices: Vec<(u64, usize)> = unprocessed_indexes
+                .iter()
+                .map(|index| {
+                    (
+                        Self::packet_compute_unit_price(&msgs.packets[*index]),
+                        *index,
+                    )
+                })
+                .collect();
+            if prices.windows(2).all(|pair| pair[0].0 == pair[1].0) {
+                let compute_unit_price = prices.first().map(|(price, _)| *price);
+                Self::push_buffered(
+                    buffered_packets,
+                    &mut buffered_packets_count,
+                    compute_unit_price.unwrap_or_default(),
+                    msgs,
+                    unprocessed_indexes,
+                    &mut dropped_batches_count,
+                    packet_limit,
+                );
+                continue;
+            }
+
+            // A batch offering several prices is split into one batch per price.
+            // `sort_by_key` is stable, preserving arrival order among equal prices
+            prices.sort_by_key(|(compute_unit_price, _)| cmp::Reverse(*compute_unit_price));
+            for (compute_unit_price, group) in &prices.into_iter().group_by(|(price, _)| *price) {
+                let packets = Packets::new(
+                    group
+                        .map(|(_, index)| msgs.packets[index].clone())
+                        .collect(),
+                );
+                let packet_indexes = (0..packets.packets.len()).collect();
+                Self::push_buffered(
+                    buffered_packets,
+                    &mut buffered_packets_count,
+                    compute_unit_price,
+                    packets,
+                    packet_indexes,
+                    &mut dropped_batches_count,
+                    packet_limit,
+                );
+            }
+        }
+        inc_new_counter_debug!("banking_stage-dropped_batches_count", dropped_batches_count);
+    }
+
+    /// The compute unit price offered by the transaction in `packet`, or zero if it
+    /// cannot be determined
+    fn packet_compute_unit_price(packet: &Packet) -> u64 {
+        limited_deserialize::<VersionedTransaction>(&packet.data[0..packet.meta.size])
+            .ok()
+            .and_then(|tx| {
+                ComputeBudgetRequest::from_instructions(
+                    tx.message.static_account_keys(),
+                    tx.message.instructions(),
+                )
+                .ok()
+            })
+            .map(|request| request.compute_unit_price)
+            .unwrap_or_default()
+    }
+
     fn consume_or_forward_packets(
         my_pubkey: &Pubkey,
         leader_pubkey: Option<Pubkey>,
@@ -306,7 +418,7 @@ impl BankingStage {
         socket: &std::net::UdpSocket,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
         cluster_info: &ClusterInfo,
-        buffered_packets: &mut Vec<PacketsAndOffsets>,
+        buffered_packets: &mut BufferedPackets,
         enable_forwarding: bool,
         batch_limit: usize,
         transaction_status_sender: Option<TransactionStatusSender>,
@@ -387,6 +499,9 @@ impl BankingStage {
     ) {
         let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
         let mut buffered_packets = vec![];
+        // Features are never deactivated, so remember the last working bank's view of the
+        // compute budget program for the slots this node isn't leader
+        let mut prioritize_by_price = false;
         loop {
             while !buffered_packets.is_empty() {
                 let decision = Self::process_buffered_packets(
@@ -430,7 +545,7 @@ impl BankingStage {
             ) {
                 Err(RecvTimeoutError::Timeout) => (),
                 Err(RecvTimeoutError::Disconnected) => break,
-                Ok(mut unprocessed_packets) => {
+                Ok(unprocessed_packets) => {
                     if unprocessed_packets.is_empty() {
                         continue;
                     }
@@ -439,7 +554,21 @@ impl BankingStage {
                         .map(|(_, unprocessed)| unprocessed.len())
                         .sum();
                     inc_new_counter_info!("banking_stage-buffered_packets", num);
-                    buffered_packets.append(&mut unprocessed_packets);
+                    if let Some(bank) = poh_recorder.lock().unwrap().bank() {
+                        prioritize_by_price = bank.compute_budget_program_enabled();
+                    }
+                    let mut buffer_time = Measure::start("buffer_packets");
+                    Self::buffer_packets(
+                        &mut buffered_packets,
+                        unprocessed_packets,
+                        batch_limit * PACKETS_PER_BATCH,
+                        prioritize_by_price,
+                    );
+                    buffer_time.stop();
+                    inc_new_counter_debug!(
+                        "banking_stage-buffer_packets_us",
+                        buffer_time.as_us() as usize
+                    );
                 }
             }
         }
@@ -457,7 +586,7 @@ impl BankingStage {
     }
 
     /// Convert the transactions from a blob of binary data to a vector of transactions
-    fn deserialize_transactions(p: &Packets) -> Vec<Option<Transaction>> {
+    fn deserialize_transactions(p: &Packets) -> Vec<Option<VersionedTransaction>> {
         p.packets
             .iter()
             .map(|x| limited_deserialize(&x.data[0..x.meta.size]).ok())
@@ -467,7 +596,7 @@ impl BankingStage {
     #[allow(clippy::match_wild_err_arm)]
     fn record_transactions(
         bank_slot: Slot,
-        txs: &[Transaction],
+        txs: &[VersionedTransaction],
         results: &[TransactionExecutionResult],
         poh: &Arc<Mutex<PohRecorder>>,
     ) -> (Result<usize, PohRecorderError>, Vec<usize>) {
@@ -520,13 +649,15 @@ impl BankingStage {
         (Ok(num_to_commit), vec![])
     }
 
+    // Returns the indexes of the retryable transactions and of the committed transactions
     fn process_and_record_transactions_locked(
         bank: &Arc<Bank>,
         poh: &Arc<Mutex<PohRecorder>>,
+        versioned_txs: &[VersionedTransaction],
         batch: &TransactionBatch,
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: &ReplayVoteSender,
-    ) -> (Result<usize, PohRecorderError>, Vec<usize>) {
+    ) -> (Result<usize, PohRecorderError>, Vec<usize>, Vec<usize>) {
         let mut load_execute_time = Measure::start("load_execute_time");
         // Use a shorter maximum age when adding transactions into the pipeline.  This will reduce
         // the likelihood of any single thread getting starved and processing old ids.
@@ -554,6 +685,8 @@ impl BankingStage {
             results,
             inner_instructions,
             transaction_logs,
+            _,
+            transaction_return_data,
             mut retryable_txs,
             tx_count,
             signature_count,
@@ -570,12 +703,23 @@ impl BankingStage {
 
         let mut record_time = Measure::start("record_time");
         let (num_to_commit, retryable_record_txs) =
-            Self::record_transactions(bank.slot(), txs, &results, poh);
+            Self::record_transactions(bank.slot(), versioned_txs, &results, poh);
         retryable_txs.extend(retryable_record_txs);
         if num_to_commit.is_err() {
-            return (num_to_commit, retryable_txs);
+            return (num_to_commit, retryable_txs, vec![]);
         }
         record_time.stop();
+        let committed_txs: Vec<usize> = results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (result, _))| {
+                if Bank::can_commit(result) {
+                    Some(index)
+                } else {
+                    None
+                }
+            })
+            .collect();
 
         let mut commit_time = Measure::start("commit_time");
 
@@ -599,12 +743,14 @@ impl BankingStage {
                 send_transaction_status_batch(
                     bank.clone(),
                     batch.transactions(),
+                    batch.loaded_addresses(),
                     batch.iteration_order_vec(),
                     tx_results.execution_results,
                     TransactionBalancesSet::new(pre_balances, post_balances),
                     TransactionTokenBalancesSet::new(pre_token_balances, post_token_balances),
                     inner_instructions,
                     transaction_logs,
+                    transaction_return_data,
                     transaction_status_sender,
                 );
             }
@@ -622,30 +768,80 @@ impl BankingStage {
             txs.len(),
         );
 
-        (Ok(num_to_commit), retryable_txs)
+        (Ok(num_to_commit), retryable_txs, committed_txs)
     }
 
     pub fn process_and_record_transactions(
         bank: &Arc<Bank>,
-        txs: &[Transaction],
+        txs: &[VersionedTransaction],
         poh: &Arc<Mutex<PohRecorder>>,
         chunk_offset: usize,
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: &ReplayVoteSender,
     ) -> (Result<usize, PohRecorderError>, Vec<usize>) {
+        let mut resolve_time = Measure::start("resolve_time");
+        // Load the addresses of versioned messages from their lookup tables
+        let mut resolved = bank.resolve_transactions(txs);
+        resolve_time.stop();
+
+        let mut cost_tracking_time = Measure::start("cost_tracking_time");
+        // Reserve each transaction's share of the block and account cost limits, transactions
+        // that do not fit are retried once the next leader bank starts with fresh limits
+        let tx_costs: Vec<_> = resolved
+            .transactions
+            .iter()
+            .map(|tx| bank.calculate_transaction_cost(tx))
+            .collect();
+        let cost_results: Vec<transaction::Result<()>> = {
+            let mut cost_tracker = bank.write_cost_tracker();
+            resolved
+                .results
+                .iter()
+                .zip(&tx_costs)
+                .map(|(result, tx_cost)| {
+                    result.clone()?;
+                    cost_tracker
+                        .try_add(tx_cost)
+                        .map(|_| ())
+                        .map_err(TransactionError::from)
+                })
+                .collect()
+        };
+        resolved.results = cost_results.clone();
+        cost_tracking_time.stop();
+
         let mut lock_time = Measure::start("lock_time");
         // Once accounts are locked, other threads cannot encode transactions that will modify the
         // same account state
-        let batch = bank.prepare_batch(txs, None);
+        let batch = bank.prepare_resolved_batch(resolved, None);
         lock_time.stop();
 
-        let (result, mut retryable_txs) = Self::process_and_record_transactions_locked(
-            bank,
-            poh,
-            &batch,
-            transaction_status_sender,
-            gossip_vote_sender,
-        );
+        let (result, mut retryable_txs, committed_txs) =
+            Self::process_and_record_transactions_locked(
+                bank,
+                poh,
+                txs,
+                &batch,
+                transaction_status_sender,
+                gossip_vote_sender,
+            );
+
+        // Release the cost reserved by every transaction that did not make it into the block,
+        // whether it is retried or dropped
+        {
+            let mut committed = vec![false; txs.len()];
+            committed_txs
+                .iter()
+                .for_each(|index| committed[*index] = true);
+            let mut cost_tracker = bank.write_cost_tracker();
+            for ((cost_result, tx_cost), committed) in
+                cost_results.iter().zip(&tx_costs).zip(committed)
+            {
+                if cost_result.is_ok() && !committed {
+                    cost_tracker.remove(tx_cost);
+                }
+            }
+        }
         retryable_txs.iter_mut().for_each(|x| *x += chunk_offset);
 
         let mut unlock_time = Measure::start("unlock_time");
@@ -654,8 +850,10 @@ impl BankingStage {
         unlock_time.stop();
 
         debug!(
-            "bank: {} lock: {}us unlock: {}us txs_len: {}",
+            "bank: {} resolve: {}us cost_tracking: {}us lock: {}us unlock: {}us txs_len: {}",
             bank.slot(),
+            resolve_time.as_us(),
+            cost_tracking_time.as_us(),
             lock_time.as_us(),
             unlock_time.as_us(),
             txs.len(),
@@ -670,7 +868,7 @@ impl BankingStage {
     /// than the total number if max PoH height was reached and the bank halted
     fn process_transactions(
         bank: &Arc<Bank>,
-        transactions: &[Transaction],
+        transactions: &[VersionedTransaction],
         poh: &Arc<Mutex<PohRecorder>>,
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: &ReplayVoteSender,
@@ -718,9 +916,9 @@ impl BankingStage {
     // This function returns a vector of transactions that are not None. It also returns a vector
     // with position of the transaction in the input list
     fn filter_transaction_indexes(
-        transactions: Vec<Option<Transaction>>,
+        transactions: Vec<Option<VersionedTransaction>>,
         indexes: &[usize],
-    ) -> (Vec<Transaction>, Vec<usize>) {
+    ) -> (Vec<VersionedTransaction>, Vec<usize>) {
         transactions
             .into_iter()
             .zip(indexes)
@@ -762,7 +960,7 @@ impl BankingStage {
         msgs: &Packets,
         transaction_indexes: &[usize],
         secp256k1_program_enabled: bool,
-    ) -> (Vec<Transaction>, Vec<usize>) {
+    ) -> (Vec<VersionedTransaction>, Vec<usize>) {
         let packets = Packets::new(
             transaction_indexes
                 .iter()
@@ -800,11 +998,19 @@ impl BankingStage {
     /// * `pending_indexes` - identifies which indexes in the `transactions` list are still pending
     fn filter_pending_packets_from_pending_txs(
         bank: &Arc<Bank>,
-        transactions: &[Transaction],
+        transactions: &[VersionedTransaction],
         transaction_to_packet_indexes: &[usize],
         pending_indexes: &[usize],
     ) -> Vec<usize> {
-        let filter = Self::prepare_filter_for_pending_transactions(transactions, pending_indexes);
+        let resolved = bank.resolve_transactions(transactions);
+        let mut filter =
+            Self::prepare_filter_for_pending_transactions(&resolved.transactions, pending_indexes);
+        // Drop the transactions whose addresses can no longer be loaded
+        for (result, resolve_result) in filter.iter_mut().zip(resolved.results) {
+            if result.is_ok() {
+                *result = resolve_result;
+            }
+        }
 
         let mut error_counters = ErrorCounters::default();
         // The following code also checks if the blockhash for a transaction is too old
@@ -819,7 +1025,7 @@ impl BankingStage {
             MAX_TRANSACTION_FORWARDING_DELAY_GPU
         };
         let result = bank.check_transactions(
-            transactions,
+            &resolved.transactions,
             None,
             &filter,
             (MAX_PROCESSING_AGE)
@@ -1061,6 +1267,52 @@ impl BankingStage {
         }
     }
 
+    /// Insert a batch of packets offering `compute_unit_price` into `buffered_packets`, behind
+    /// the batches offering the same or a higher price. `buffered_packets_count` tracks the number
+    /// of packets in the buffer; while it is past `packet_limit`, the oldest of the lowest priced
+    /// batches is dropped.
+    fn push_buffered(
+        buffered_packets: &mut BufferedPackets,
+        buffered_packets_count: &mut usize,
+        compute_unit_price: u64,
+        packets: Packets,
+        packet_indexes: Vec<usize>,
+        dropped_batches_count: &mut usize,
+        packet_limit: usize,
+    ) {
+        if !packet_indexes.is_empty() {
+            let position =
+                Self::buffered_position(buffered_packets, |price| price >= compute_unit_price);
+            *buffered_packets_count += packet_indexes.len();
+            buffered_packets.insert(position, (compute_unit_price, (packets, packet_indexes)));
+            while *buffered_packets_count > packet_limit {
+                let lowest_price = buffered_packets.last().unwrap().0;
+                let oldest_lowest_priced =
+                    Self::buffered_position(buffered_packets, |price| price > lowest_price);
+                let (_, (_, dropped_indexes)) = buffered_packets.remove(oldest_lowest_priced);
+                *buffered_packets_count -= dropped_indexes.len();
+                *dropped_batches_count += 1;
+            }
+        }
+    }
+
+    /// Position of the first buffered batch whose price doesn't satisfy `is_ahead`, which must
+    /// hold for a prefix of the buffer
+    fn buffered_position(
+        buffered_packets: &[(u64, PacketsAndOffsets)],
+        is_ahead: impl Fn(u64) -> bool,
+    ) -> usize {
+        buffered_packets
+            .binary_search_by(|(price, _)| {
+                if is_ahead(*price) {
+                    cmp::Ordering::Less
+                } else {
+                    cmp::Ordering::Greater
+                }
+            })
+            .unwrap_err()
+    }
+
     pub fn join(self) -> thread::Result<()> {
         for bank_thread_hdl in self.bank_thread_hdls {
             bank_thread_hdl.join()?;
@@ -1122,10 +1374,13 @@ mod tests {
         get_tmp_ledger_path,
     };
     use solana_perf::packet::to_packets_chunked;
+    use solana_runtime::{cost_model::CostModel, cost_tracker::CostTracker};
     use solana_sdk::{
+        compute_budget,
+        hash::Hash,
         instruction::InstructionError,
         signature::{Keypair, Signer},
-        system_instruction::SystemError,
+        system_instruction::{self, SystemError, SystemInstruction},
         system_transaction,
         transaction::TransactionError,
     };
@@ -1329,7 +1584,7 @@ mod tests {
                 if !entries.is_empty() {
                     blockhash = entries.last().unwrap().hash;
                     for entry in entries {
-                        bank.process_transactions(&entry.transactions)
+                        bank.process_versioned_transactions(&entry.transactions)
                             .iter()
                             .for_each(|x| assert_eq!(*x, Ok(())));
                     }
@@ -1440,7 +1695,7 @@ mod tests {
 
             let bank = Bank::new(&genesis_config);
             for entry in &entries {
-                bank.process_transactions(&entry.transactions)
+                bank.process_versioned_transactions(&entry.transactions)
                     .iter()
                     .for_each(|x| assert_eq!(*x, Ok(())));
             }
@@ -1488,9 +1743,10 @@ mod tests {
             let keypair2 = Keypair::new();
             let pubkey2 = solana_sdk::pubkey::new_rand();
 
-            let transactions = vec![
-                system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash()),
-                system_transaction::transfer(&keypair2, &pubkey2, 1, genesis_config.hash()),
+            let transactions: Vec<VersionedTransaction> = vec![
+                system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash())
+                    .into(),
+                system_transaction::transfer(&keypair2, &pubkey2, 1, genesis_config.hash()).into(),
             ];
 
             let mut results = vec![(Ok(()), None), (Ok(()), None)];
@@ -1608,15 +1864,17 @@ mod tests {
             None,
             None,
         ];
+        let transactions: Vec<_> = transactions
+            .into_iter()
+            .map(|tx| tx.map(VersionedTransaction::from))
+            .collect();
 
-        let filtered_transactions = vec![
-            system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash()),
-            system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash()),
-            system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash()),
-            system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash()),
-            system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash()),
-            system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash()),
-        ];
+        let filtered_transactions: Vec<VersionedTransaction> =
+            vec![
+                system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash())
+                    .into();
+                6
+            ];
 
         assert_eq!(
             BankingStage::filter_transaction_indexes(
@@ -1762,12 +2020,11 @@ mod tests {
         let bank = Arc::new(Bank::new(&genesis_config));
         let pubkey = solana_sdk::pubkey::new_rand();
 
-        let transactions = vec![system_transaction::transfer(
-            &mint_keypair,
-            &pubkey,
-            1,
-            genesis_config.hash(),
-        )];
+        let transactions: Vec<VersionedTransaction> =
+            vec![
+                system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash())
+                    .into(),
+            ];
 
         let working_bank = WorkingBank {
             bank: bank.clone(),
@@ -1823,12 +2080,13 @@ mod tests {
 
             assert_eq!(done, true);
 
-            let transactions = vec![system_transaction::transfer(
+            let transactions: Vec<VersionedTransaction> = vec![system_transaction::transfer(
                 &mint_keypair,
                 &pubkey,
                 2,
                 genesis_config.hash(),
-            )];
+            )
+            .into()];
 
             assert_matches!(
                 BankingStage::process_and_record_transactions(
@@ -1849,7 +2107,7 @@ mod tests {
     }
 
     #[test]
-    fn test_bank_process_and_record_transactions_account_in_use() {
+    fn test_bank_process_and_record_transactions_cost_limit() {
         solana_logger::setup();
         let GenesisConfigInfo {
             genesis_config,
@@ -1865,6 +2123,137 @@ mod tests {
             system_transaction::transfer(&mint_keypair, &pubkey1, 1, genesis_config.hash()),
         ];
 
+        // Leave room in the block for only one of the transfers
+        let tx_cost = bank.calculate_transaction_cost(&transactions[0]).sum();
+        *bank.write_cost_tracker() = CostTracker::new(&CostModel::new(u64::MAX, tx_cost));
+        let transactions: Vec<VersionedTransaction> = transactions
+            .into_iter()
+            .map(VersionedTransaction::from)
+            .collect();
+
+        let working_bank = WorkingBank {
+            bank: bank.clone(),
+            min_tick_height: bank.tick_height(),
+            max_tick_height: std::u64::MAX,
+        };
+        let ledger_path = get_tmp_ledger_path!();
+        {
+            let blockstore = Blockstore::open(&ledger_path)
+                .expect("Expected to be able to open database ledger");
+            let (poh_recorder, _entry_receiver) = PohRecorder::new(
+                bank.tick_height(),
+                bank.last_blockhash(),
+                bank.slot(),
+                Some((4, 4)),
+                bank.ticks_per_slot(),
+                &pubkey,
+                &Arc::new(blockstore),
+                &Arc::new(LeaderScheduleCache::new_from_bank(&bank)),
+                &Arc::new(PohConfig::default()),
+            );
+            let poh_recorder = Arc::new(Mutex::new(poh_recorder));
+
+            poh_recorder.lock().unwrap().set_working_bank(working_bank);
+            let (gossip_vote_sender, _gossip_vote_receiver) = unbounded();
+
+            let (result, retryable_txs) = BankingStage::process_and_record_transactions(
+                &bank,
+                &transactions,
+                &poh_recorder,
+                0,
+                None,
+                &gossip_vote_sender,
+            );
+            assert!(result.is_ok());
+            assert_eq!(retryable_txs, vec![1]);
+            assert_eq!(bank.get_balance(&pubkey), 1);
+            assert_eq!(bank.get_balance(&pubkey1), 0);
+            assert_eq!(bank.read_cost_tracker().block_cost(), tx_cost);
+            assert_eq!(bank.read_cost_tracker().transaction_count(), 1);
+        }
+        Blockstore::destroy(&ledger_path).unwrap();
+    }
+
+    #[test]
+    fn test_bank_process_and_record_transactions_cost_released_on_failure() {
+        solana_logger::setup();
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let bank = Arc::new(Bank::new(&genesis_config));
+        let pubkey = solana_sdk::pubkey::new_rand();
+
+        // The second transfer is paid by an account that doesn't exist, it fails to load and is
+        // dropped without being retried
+        let success_tx =
+            system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash());
+        let fail_tx =
+            system_transaction::transfer(&Keypair::new(), &pubkey, 1, genesis_config.hash());
+        let success_tx_cost = bank.calculate_transaction_cost(&success_tx).sum();
+        let transactions: Vec<VersionedTransaction> = vec![success_tx.into(), fail_tx.into()];
+
+        let working_bank = WorkingBank {
+            bank: bank.clone(),
+            min_tick_height: bank.tick_height(),
+            max_tick_height: std::u64::MAX,
+        };
+        let ledger_path = get_tmp_ledger_path!();
+        {
+            let blockstore = Blockstore::open(&ledger_path)
+                .expect("Expected to be able to open database ledger");
+            let (poh_recorder, _entry_receiver) = PohRecorder::new(
+                bank.tick_height(),
+                bank.last_blockhash(),
+                bank.slot(),
+                Some((4, 4)),
+                bank.ticks_per_slot(),
+                &pubkey,
+                &Arc::new(blockstore),
+                &Arc::new(LeaderScheduleCache::new_from_bank(&bank)),
+                &Arc::new(PohConfig::default()),
+            );
+            let poh_recorder = Arc::new(Mutex::new(poh_recorder));
+
+            poh_recorder.lock().unwrap().set_working_bank(working_bank);
+            let (gossip_vote_sender, _gossip_vote_receiver) = unbounded();
+
+            let (result, retryable_txs) = BankingStage::process_and_record_transactions(
+                &bank,
+                &transactions,
+                &poh_recorder,
+                0,
+                None,
+                &gossip_vote_sender,
+            );
+            assert!(result.is_ok());
+            assert!(retryable_txs.is_empty());
+            assert_eq!(bank.get_balance(&pubkey), 1);
+            // Only the committed transfer keeps its share of the block
+            assert_eq!(bank.read_cost_tracker().block_cost(), success_tx_cost);
+            assert_eq!(bank.read_cost_tracker().transaction_count(), 1);
+        }
+        Blockstore::destroy(&ledger_path).unwrap();
+    }
+
+    #[test]
+    fn test_bank_process_and_record_transactions_account_in_use() {
+        solana_logger::setup();
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let bank = Arc::new(Bank::new(&genesis_config));
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let pubkey1 = solana_sdk::pubkey::new_rand();
+
+        let transactions: Vec<VersionedTransaction> = vec![
+            system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash()).into(),
+            system_transaction::transfer(&mint_keypair, &pubkey1, 1, genesis_config.hash()).into(),
+        ];
+
         let working_bank = WorkingBank {
             bank: bank.clone(),
             min_tick_height: bank.tick_height(),
@@ -1906,6 +2295,111 @@ mod tests {
         Blockstore::destroy(&ledger_path).unwrap();
     }
 
+    #[test]
+    fn test_buffer_packets() {
+        let payer = Keypair::new();
+        let to = solana_sdk::pubkey::new_rand();
+        let transaction_with_price = |lamports: u64, compute_unit_price: Option<u64>| {
+            let mut instructions =
+                vec![system_instruction::transfer(&payer.pubkey(), &to, lamports)];
+            if let Some(compute_unit_price) = compute_unit_price {
+                instructions.push(compute_budget::set_compute_unit_price(compute_unit_price));
+            }
+            Transaction::new_signed_with_payer(
+                &instructions,
+                Some(&payer.pubkey()),
+                &[&payer],
+                Hash::default(),
+            )
+        };
+        let to_unprocessed_packets = |transactions: &[Transaction]| {
+            to_packets_chunked(transactions, 2)
+                .into_iter()
+                .map(|msgs| {
+                    let indexes = BankingStage::generate_packet_indexes(&msgs.packets);
+                    (msgs, indexes)
+                })
+                .collect_vec()
+        };
+        // The lamports transferred identify each transaction, paired with the price of its batch
+        let buffered_transfers = |buffered_packets: &BufferedPackets| {
+            buffered_packets
+                .iter()
+                .flat_map(|(compute_unit_price, (msgs, indexes))| {
+                    let transactions = BankingStage::deserialize_transactions(msgs);
+                    indexes
+                        .iter()
+                        .map(|index| {
+                            let tx = transactions[*index].as_ref().unwrap();
+                            match limited_deserialize(&tx.message.instructions()[0].data).unwrap() {
+                                SystemInstruction::Transfer { lamports } => {
+                                    (lamports, *compute_unit_price)
+                                }
+                                _ => panic!("unexpected instruction"),
+                            }
+                        })
+                        .collect_vec()
+                })
+                .collect_vec()
+        };
+
+        let mut unprocessed_packets = to_unprocessed_packets(&[
+            transaction_with_price(0, None),
+            transaction_with_price(1, Some(10)),
+            transaction_with_price(2, Some(0)),
+            transaction_with_price(3, Some(30)),
+        ]);
+        // Drop the first transaction as if it had been processed
+        unprocessed_packets[0].1.remove(0);
+        let mut buffered_packets = vec![];
+        BankingStage::buffer_packets(&mut buffered_packets, unprocessed_packets, 10, true);
+        assert_eq!(
+            buffered_transfers(&buffered_packets),
+            vec![(3, 30), (1, 10), (2, 0)]
+        );
+
+        // Newly buffered packets are ordered behind buffered ones offering the same price, and
+        // the lowest priced batch is dropped once over the packet limit
+        let unprocessed_packets = to_unprocessed_packets(&[
+            transaction_with_price(4, Some(10)),
+            transaction_with_price(5, Some(5)),
+        ]);
+        BankingStage::buffer_packets(&mut buffered_packets, unprocessed_packets, 4, true);
+        assert_eq!(buffered_packets.len(), 4);
+        assert_eq!(
+            buffered_transfers(&buffered_packets),
+            vec![(3, 30), (1, 10), (4, 10), (5, 5)]
+        );
+
+        // Splitting batches by price doesn't shrink the buffer: the limit counts packets
+        let unprocessed_packets = to_unprocessed_packets(&[
+            transaction_with_price(6, Some(40)),
+            transaction_with_price(7, Some(20)),
+        ]);
+        BankingStage::buffer_packets(&mut buffered_packets, unprocessed_packets, 5, true);
+        assert_eq!(
+            buffered_transfers(&buffered_packets),
+            vec![(6, 40), (3, 30), (7, 20), (1, 10), (4, 10)]
+        );
+
+        // Before the compute budget program is enabled, packets are buffered in arrival order
+        // without being split, and the oldest batches are dropped once over the packet limit
+        let unprocessed_packets = to_unprocessed_packets(&[
+            transaction_with_price(8, Some(10)),
+            transaction_with_price(9, None),
+            transaction_with_price(10, Some(30)),
+            transaction_with_price(11, Some(20)),
+            transaction_with_price(12, None),
+        ]);
+        let mut buffered_packets = vec![];
+        BankingStage::buffer_packets(&mut buffered_packets, unprocessed_packets, 3, false);
+        assert_eq!(buffered_packets.len(), 2);
+        assert_eq!(
+            buffered_transfers(&buffered_packets),
+            vec![(10, 0), (11, 0), (12, 0)]
+        );
+    }
+
     #[test]
     fn test_filter_valid_packets() {
         solana_logger::setup();
@@ -1928,7 +2422,7 @@ mod tests {
             })
             .collect_vec();
 
-        let result = BankingStage::filter_valid_packets_for_forwarding(&all_packets);
+        let result = BankingStage::filter_valid_packets_for_forwarding(all_packets.iter());
 
         assert_eq!(result.len(), 256);
 
@@ -1955,9 +2449,10 @@ mod tests {
 
         let pubkey = solana_sdk::pubkey::new_rand();
 
-        let transactions =
+        let transactions: Vec<VersionedTransaction> =
             vec![
-                system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash(),);
+                system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash(),)
+                    .into();
                 3
             ];
 
@@ -2028,7 +2523,8 @@ mod tests {
         let entry_3 = next_entry(&entry_2.hash, 1, vec![fail_tx.clone()]);
         let entries = vec![entry_1, entry_2, entry_3];
 
-        let transactions = vec![success_tx, ix_error_tx, fail_tx];
+        let transactions: Vec<VersionedTransaction> =
+            vec![success_tx.into(), ix_error_tx.into(), fail_tx.into()];
         bank.transfer(4, &mint_keypair, &keypair1.pubkey()).unwrap();
 
         let working_bank = WorkingBank {
@@ -2063,6 +2559,8 @@ mod tests {
             let (transaction_status_sender, transaction_status_receiver) = unbounded();
             let transaction_status_service = TransactionStatusService::new(
                 transaction_status_receiver,
+                true,
+                None,
                 blockstore.clone(),
                 &Arc::new(AtomicBool::new(false)),
             );
@@ -18,8 +18,8 @@ use crate::{
     crds_gossip_error::CrdsGossipError,
     crds_gossip_pull::{CrdsFilter, ProcessPullStats, CRDS_GOSSIP_PULL_CRDS_TIMEOUT_MS},
     crds_value::{
-        self, CrdsData, CrdsValue, CrdsValueLabel, EpochSlotsIndex, LowestSlot, NodeInstance,
-        SnapshotHash, Version, Vote, MAX_WALLCLOCK,
+        self, CrdsData, CrdsValue, CrdsValueLabel, EpochSlotsIndex, IncrementalSnapshotHashes,
+        LowestSlot, NodeInstance, SnapshotHash, Version, Vote, MAX_WALLCLOCK,
     },
     data_budget::DataBudget,
     epoch_slots::EpochSlots,
@@ -103,6 +103,10 @@ const DUPLICATE_SHRED_MAX_PAYLOAD_SIZE: usize = PACKET_DATA_SIZE - 115;
 /// PACKET_DATA_SIZE.
 // TODO: Update this to 26 once payload sizes are upgraded across fleet.
 pub const MAX_SNAPSHOT_HASHES: usize = 16;
+/// Maximum number of hashes in IncrementalSnapshotHashes a node publishes.
+/// One less than MAX_SNAPSHOT_HASHES to leave room for the base full snapshot
+/// hash.
+pub const MAX_INCREMENTAL_SNAPSHOT_HASHES: usize = MAX_SNAPSHOT_HASHES - 1;
 /// Maximum number of origin nodes that a PruneData may contain, such that the
 /// serialized size of the PruneMessage stays below PACKET_DATA_SIZE.
 const MAX_PRUNE_DATA_NODES: usize = 32;
@@ -405,7 +409,7 @@ pub fn make_accounts_hashes_message(
 type Ping = ping_pong::Ping<[u8; GOSSIP_PING_TOKEN_SIZE]>;
 
 // TODO These messages should go through the gpu pipeline for spam filtering
-#[frozen_abi(digest = "CH5BWuhAyvUiUQYgu2Lcwu7eoiW6bQitvtLS1yFsdmrE")]
+#[frozen_abi(digest = "2hYLLAU62LEYZJDeqK1UiDgL87kmVwryVVd37gajVpDk")]
 #[derive(Serialize, Deserialize, Debug, AbiEnumVisitor, AbiExample)]
 #[allow(clippy::large_enum_variant)]
 enum Protocol {
@@ -1078,6 +1082,27 @@ impl ClusterInfo {
         self.push_message(CrdsValue::new_signed(message, &self.keypair));
     }
 
+    pub fn push_incremental_snapshot_hashes(
+        &self,
+        base: (Slot, Hash),
+        incremental_snapshot_hashes: Vec<(Slot, Hash)>,
+    ) {
+        if incremental_snapshot_hashes.len() > MAX_INCREMENTAL_SNAPSHOT_HASHES {
+            warn!(
+                "incremental snapshot hashes too large, ignored: {}",
+                incremental_snapshot_hashes.len(),
+            );
+            return;
+        }
+
+        let message = CrdsData::IncrementalSnapshotHashes(IncrementalSnapshotHashes::new(
+            self.id(),
+            base,
+            incremental_snapshot_hashes,
+        ));
+        self.push_message(CrdsValue::new_signed(message, &self.keypair));
+    }
+
     pub fn push_vote(&self, tower: &[Slot], vote: Transaction) {
         debug_assert!(tower.iter().tuple_windows().all(|(a, b)| a < b));
         let now = timestamp();
@@ -1201,6 +1226,25 @@ impl ClusterInfo {
             .map(map)
     }
 
+    /// Returns the full snapshot base and the incremental snapshot hashes
+    /// most recently published by the node.
+    pub fn get_incremental_snapshot_hashes_for_node<F, Y>(
+        &self,
+        pubkey: &Pubkey,
+        map: F,
+    ) -> Option<Y>
+    where
+        F: FnOnce(&(Slot, Hash), &Vec<(Slot, Hash)>) -> Y,
+    {
+        self.gossip
+            .read()
+            .unwrap()
+            .crds
+            .get(&CrdsValueLabel::IncrementalSnapshotHashes(*pubkey))
+            .map(|x| x.value.incremental_snapshot_hashes().unwrap())
+            .map(|hashes| map(&hashes.base, &hashes.hashes))
+    }
+
     pub fn get_lowest_slot_for_node<F, Y>(
         &self,
         pubkey: &Pubkey,
@@ -3465,6 +3509,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_max_incremental_snapshot_hashes_with_push_messages() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..256 {
+            let hashes = IncrementalSnapshotHashes::new_rand(&mut rng, None);
+            let crds_value =
+                CrdsValue::new_signed(CrdsData::IncrementalSnapshotHashes(hashes), &Keypair::new());
+            let message = Protocol::PushMessage(Pubkey::new_unique(), vec![crds_value]);
+            let socket = new_rand_socket_addr(&mut rng);
+            assert!(Packet::from_data(&socket, message).is_ok());
+        }
+    }
+
     #[test]
     fn test_max_prune_data_pubkeys() {
         let mut rng = rand::thread_rng();
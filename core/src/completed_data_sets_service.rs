@@ -1,9 +1,11 @@
 use crate::rpc_subscriptions::RpcSubscriptions;
 use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use solana_client::rpc_response::SlotUpdate;
 use solana_ledger::blockstore::{Blockstore, CompletedDataSetInfo};
 use solana_ledger::entry::Entry;
-use solana_sdk::signature::Signature;
+use solana_sdk::{clock::Slot, signature::Signature, timing::timestamp};
 use std::{
+    collections::BTreeSet,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -51,6 +53,7 @@ impl CompletedDataSetsService {
         rpc_subscriptions: &RpcSubscriptions,
     ) -> Result<(), RecvTimeoutError> {
         let completed_data_sets = completed_sets_receiver.recv_timeout(Duration::from_secs(1))?;
+        let mut slots = BTreeSet::new();
         for completed_set_info in std::iter::once(completed_data_sets)
             .chain(completed_sets_receiver.try_iter())
             .flatten()
@@ -60,6 +63,7 @@ impl CompletedDataSetsService {
                 start_index,
                 end_index,
             } = completed_set_info;
+            slots.insert(slot);
             match blockstore.get_entries_in_data_block(slot, start_index, end_index, None) {
                 Ok(entries) => {
                     let transactions = Self::get_transaction_signatures(entries);
@@ -70,10 +74,31 @@ impl CompletedDataSetsService {
                 Err(e) => warn!("completed-data-set-service deserialize error: {:?}", e),
             }
         }
+        Self::notify_completed_slots(slots, blockstore, rpc_subscriptions);
 
         Ok(())
     }
 
+    // The data set that fills the last gap in a slot is always among the completed data sets, so
+    // each slot is reported exactly once
+    fn notify_completed_slots(
+        slots: BTreeSet<Slot>,
+        blockstore: &Blockstore,
+        rpc_subscriptions: &RpcSubscriptions,
+    ) {
+        for slot in slots {
+            if let Ok(Some(slot_meta)) = blockstore.meta(slot) {
+                if slot_meta.is_full() {
+                    rpc_subscriptions.notify_slot_update(SlotUpdate::Completed {
+                        slot,
+                        parent: slot_meta.parent_slot,
+                        timestamp: timestamp(),
+                    });
+                }
+            }
+        }
+    }
+
     fn get_transaction_signatures(entries: Vec<Entry>) -> Vec<Signature> {
         entries
             .into_iter()
@@ -1,5 +1,5 @@
 use crate::{
-    cluster_info::MAX_SNAPSHOT_HASHES,
+    cluster_info::{MAX_INCREMENTAL_SNAPSHOT_HASHES, MAX_SNAPSHOT_HASHES},
     contact_info::ContactInfo,
     deprecated,
     duplicate_shred::{DuplicateShred, DuplicateShredIndex, MAX_DUPLICATE_SHREDS},
@@ -88,6 +88,10 @@ pub enum CrdsData {
     Version(Version),
     NodeInstance(NodeInstance),
     DuplicateShred(DuplicateShredIndex, DuplicateShred),
+    /// Nodes running a release that predates this variant can't deserialize it, and drop the
+    /// whole packet carrying it. It is only pushed by validators that generate incremental
+    /// snapshots, which should not be enabled until the cluster has upgraded.
+    IncrementalSnapshotHashes(IncrementalSnapshotHashes),
 }
 
 impl Sanitize for CrdsData {
@@ -124,6 +128,7 @@ impl Sanitize for CrdsData {
                     shred.sanitize()
                 }
             }
+            CrdsData::IncrementalSnapshotHashes(val) => val.sanitize(),
         }
     }
 }
@@ -137,7 +142,7 @@ pub(crate) fn new_rand_timestamp<R: Rng>(rng: &mut R) -> u64 {
 impl CrdsData {
     /// New random CrdsData for tests and benchmarks.
     fn new_rand<R: Rng>(rng: &mut R, pubkey: Option<Pubkey>) -> CrdsData {
-        let kind = rng.gen_range(0, 6);
+        let kind = rng.gen_range(0, 7);
         // TODO: Implement other kinds of CrdsData here.
         // TODO: Assign ranges to each arm proportional to their frequency in
         // the mainnet crds table.
@@ -147,6 +152,9 @@ impl CrdsData {
             2 => CrdsData::SnapshotHashes(SnapshotHash::new_rand(rng, pubkey)),
             3 => CrdsData::AccountsHashes(SnapshotHash::new_rand(rng, pubkey)),
             4 => CrdsData::Version(Version::new_rand(rng, pubkey)),
+            5 => CrdsData::IncrementalSnapshotHashes(IncrementalSnapshotHashes::new_rand(
+                rng, pubkey,
+            )),
             _ => CrdsData::Vote(rng.gen_range(0, MAX_VOTES), Vote::new_rand(rng, pubkey)),
         }
     }
@@ -197,6 +205,63 @@ impl SnapshotHash {
         }
     }
 }
+
+/// Hashes of the incremental snapshots a node has generated on top of its full snapshot `base`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, AbiExample)]
+pub struct IncrementalSnapshotHashes {
+    pub from: Pubkey,
+    pub base: (Slot, Hash),
+    pub hashes: Vec<(Slot, Hash)>,
+    pub wallclock: u64,
+}
+
+impl Sanitize for IncrementalSnapshotHashes {
+    fn sanitize(&self) -> Result<(), SanitizeError> {
+        sanitize_wallclock(self.wallclock)?;
+        if self.base.0 >= MAX_SLOT {
+            return Err(SanitizeError::ValueOutOfBounds);
+        }
+        for (slot, _) in &self.hashes {
+            if *slot >= MAX_SLOT {
+                return Err(SanitizeError::ValueOutOfBounds);
+            }
+            if *slot <= self.base.0 {
+                return Err(SanitizeError::InvalidValue);
+            }
+        }
+        self.from.sanitize()
+    }
+}
+
+impl IncrementalSnapshotHashes {
+    pub fn new(from: Pubkey, base: (Slot, Hash), hashes: Vec<(Slot, Hash)>) -> Self {
+        Self {
+            from,
+            base,
+            hashes,
+            wallclock: timestamp(),
+        }
+    }
+
+    /// New random IncrementalSnapshotHashes for tests and benchmarks.
+    pub(crate) fn new_rand<R: Rng>(rng: &mut R, pubkey: Option<Pubkey>) -> Self {
+        let base_slot = 47825632 + rng.gen_range(0, 512);
+        let num_hashes = rng.gen_range(0, MAX_INCREMENTAL_SNAPSHOT_HASHES) + 1;
+        let hashes = std::iter::repeat_with(|| {
+            let slot = base_slot + 1 + rng.gen_range(0, 512);
+            let hash = solana_sdk::hash::new_rand(rng);
+            (slot, hash)
+        })
+        .take(num_hashes)
+        .collect();
+        Self {
+            from: pubkey.unwrap_or_else(pubkey::new_rand),
+            base: (base_slot, solana_sdk::hash::new_rand(rng)),
+            hashes,
+            wallclock: new_rand_timestamp(rng),
+        }
+    }
+}
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, AbiExample)]
 pub struct LowestSlot {
     pub from: Pubkey,
@@ -443,6 +508,7 @@ pub enum CrdsValueLabel {
     Version(Pubkey),
     NodeInstance(Pubkey, u64 /*token*/),
     DuplicateShred(DuplicateShredIndex, Pubkey),
+    IncrementalSnapshotHashes(Pubkey),
 }
 
 impl fmt::Display for CrdsValueLabel {
@@ -458,6 +524,9 @@ impl fmt::Display for CrdsValueLabel {
             CrdsValueLabel::Version(_) => write!(f, "Version({})", self.pubkey()),
             CrdsValueLabel::NodeInstance(pk, token) => write!(f, "NodeInstance({}, {})", pk, token),
             CrdsValueLabel::DuplicateShred(ix, pk) => write!(f, "DuplicateShred({}, {})", ix, pk),
+            CrdsValueLabel::IncrementalSnapshotHashes(_) => {
+                write!(f, "IncrementalSnapshotHashes({})", self.pubkey())
+            }
         }
     }
 }
@@ -475,6 +544,7 @@ impl CrdsValueLabel {
             CrdsValueLabel::Version(p) => *p,
             CrdsValueLabel::NodeInstance(p, _ /*token*/) => *p,
             CrdsValueLabel::DuplicateShred(_, p) => *p,
+            CrdsValueLabel::IncrementalSnapshotHashes(p) => *p,
         }
     }
 
@@ -492,6 +562,7 @@ impl CrdsValueLabel {
             CrdsValueLabel::Version(_) => Some(1),
             CrdsValueLabel::NodeInstance(_, _) => None,
             CrdsValueLabel::DuplicateShred(_, _) => Some(MAX_DUPLICATE_SHREDS as usize),
+            CrdsValueLabel::IncrementalSnapshotHashes(_) => Some(1),
         }
     }
 }
@@ -540,6 +611,7 @@ impl CrdsValue {
             CrdsData::Version(version) => version.wallclock,
             CrdsData::NodeInstance(node) => node.wallclock,
             CrdsData::DuplicateShred(_, shred) => shred.wallclock,
+            CrdsData::IncrementalSnapshotHashes(hashes) => hashes.wallclock,
         }
     }
     pub fn pubkey(&self) -> Pubkey {
@@ -554,6 +626,7 @@ impl CrdsValue {
             CrdsData::Version(version) => version.from,
             CrdsData::NodeInstance(node) => node.from,
             CrdsData::DuplicateShred(_, shred) => shred.from,
+            CrdsData::IncrementalSnapshotHashes(hashes) => hashes.from,
         }
     }
     pub fn label(&self) -> CrdsValueLabel {
@@ -568,6 +641,9 @@ impl CrdsValue {
             CrdsData::Version(_) => CrdsValueLabel::Version(self.pubkey()),
             CrdsData::NodeInstance(node) => CrdsValueLabel::NodeInstance(node.from, node.token),
             CrdsData::DuplicateShred(ix, shred) => CrdsValueLabel::DuplicateShred(*ix, shred.from),
+            CrdsData::IncrementalSnapshotHashes(_) => {
+                CrdsValueLabel::IncrementalSnapshotHashes(self.pubkey())
+            }
         }
     }
     pub fn contact_info(&self) -> Option<&ContactInfo> {
@@ -599,6 +675,13 @@ impl CrdsValue {
         }
     }
 
+    pub fn incremental_snapshot_hashes(&self) -> Option<&IncrementalSnapshotHashes> {
+        match &self.data {
+            CrdsData::IncrementalSnapshotHashes(hashes) => Some(hashes),
+            _ => None,
+        }
+    }
+
     pub fn accounts_hash(&self) -> Option<&SnapshotHash> {
         match &self.data {
             CrdsData::AccountsHashes(slots) => Some(slots),
@@ -738,6 +821,36 @@ mod test {
         assert_eq!(v.sanitize(), Err(SanitizeError::InvalidValue));
     }
 
+    #[test]
+    fn test_incremental_snapshot_hashes_sanitize() {
+        let hashes = IncrementalSnapshotHashes::new(
+            Pubkey::default(),
+            (100, Hash::default()),
+            vec![(150, Hash::default()), (200, Hash::default())],
+        );
+        let v = CrdsValue::new_unsigned(CrdsData::IncrementalSnapshotHashes(hashes.clone()));
+        assert_eq!(v.sanitize(), Ok(()));
+        assert_eq!(
+            v.label(),
+            CrdsValueLabel::IncrementalSnapshotHashes(Pubkey::default())
+        );
+
+        let mut o = hashes.clone();
+        o.hashes.push((100, Hash::default()));
+        let v = CrdsValue::new_unsigned(CrdsData::IncrementalSnapshotHashes(o));
+        assert_eq!(v.sanitize(), Err(SanitizeError::InvalidValue));
+
+        let mut o = hashes.clone();
+        o.hashes.push((MAX_SLOT, Hash::default()));
+        let v = CrdsValue::new_unsigned(CrdsData::IncrementalSnapshotHashes(o));
+        assert_eq!(v.sanitize(), Err(SanitizeError::ValueOutOfBounds));
+
+        let mut o = hashes;
+        o.base.0 = MAX_SLOT;
+        let v = CrdsValue::new_unsigned(CrdsData::IncrementalSnapshotHashes(o));
+        assert_eq!(v.sanitize(), Err(SanitizeError::ValueOutOfBounds));
+    }
+
     #[test]
     fn test_signature() {
         let keypair = Keypair::new();
@@ -0,0 +1,264 @@
+//! The `geyser_plugin_file_sink` module implements a reference Geyser plugin
+//! that writes every notification as a line of JSON to a local file or Unix
+//! socket.  It is loaded with a config file such as:
+//!
+//! ```json
+//! {
+//!     "builtin": "file-sink",
+//!     "path": "/var/log/solana/geyser.jsonl",
+//!     "accounts": true,
+//!     "transactions": true
+//! }
+//! ```
+//!
+//! Use `"unix_socket": "/run/geyser.sock"` instead of `"path"` to stream to a
+//! process listening on a Unix socket.
+
+use serde_json::{json, Value};
+use solana_geyser_plugin_interface::geyser_plugin_interface::{
+    GeyserPlugin, GeyserPluginError, ReplicaAccountInfo, ReplicaTransactionInfo, Result, SlotStatus,
+};
+use solana_sdk::clock::Slot;
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Read, Write},
+    sync::Mutex,
+};
+
+pub struct FileSinkPlugin {
+    writer: Mutex<Option<Box<dyn Write + Send>>>,
+    accounts: bool,
+    transactions: bool,
+}
+
+impl Default for FileSinkPlugin {
+    fn default() -> Self {
+        Self {
+            writer: Mutex::new(None),
+            accounts: true,
+            transactions: false,
+        }
+    }
+}
+
+impl fmt::Debug for FileSinkPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FileSinkPlugin")
+            .field("accounts", &self.accounts)
+            .field("transactions", &self.transactions)
+            .finish()
+    }
+}
+
+impl FileSinkPlugin {
+    pub const NAME: &'static str = "file-sink";
+
+    /// Create a plugin writing to `writer`, bypassing the config file.
+    pub fn new_with_writer(
+        writer: Box<dyn Write + Send>,
+        accounts: bool,
+        transactions: bool,
+    ) -> Self {
+        Self {
+            writer: Mutex::new(Some(writer)),
+            accounts,
+            transactions,
+        }
+    }
+
+    fn open_writer(config: &Value) -> Result<Box<dyn Write + Send>> {
+        if let Some(path) = config["path"].as_str() {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            return Ok(Box::new(BufWriter::new(file)));
+        }
+        #[cfg(unix)]
+        {
+            if let Some(socket_path) = config["unix_socket"].as_str() {
+                let stream = std::os::unix::net::UnixStream::connect(socket_path)?;
+                return Ok(Box::new(BufWriter::new(stream)));
+            }
+        }
+        Err(GeyserPluginError::ConfigFileReadError {
+            msg: "either \"path\" or \"unix_socket\" must be set".to_string(),
+        })
+    }
+
+    fn write_line(&self, value: Value, flush: bool) -> Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        if let Some(writer) = writer.as_mut() {
+            writeln!(writer, "{}", value)
+                .map_err(|err| GeyserPluginError::Custom(Box::new(err)))?;
+            if flush {
+                writer
+                    .flush()
+                    .map_err(|err| GeyserPluginError::Custom(Box::new(err)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GeyserPlugin for FileSinkPlugin {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn on_load(&mut self, config_file: &str) -> Result<()> {
+        let mut contents = String::new();
+        File::open(config_file)?.read_to_string(&mut contents)?;
+        let config: Value = serde_json::from_str(&contents).map_err(|err| {
+            GeyserPluginError::ConfigFileReadError {
+                msg: err.to_string(),
+            }
+        })?;
+        self.accounts = config["accounts"].as_bool().unwrap_or(true);
+        self.transactions = config["transactions"].as_bool().unwrap_or(false);
+        *self.writer.lock().unwrap() = Some(Self::open_writer(&config)?);
+        Ok(())
+    }
+
+    fn on_unload(&self) {
+        if let Some(mut writer) = self.writer.lock().unwrap().take() {
+            let _ = writer.flush();
+        }
+    }
+
+    fn update_account(
+        &self,
+        account: ReplicaAccountInfo,
+        slot: Slot,
+        is_startup: bool,
+    ) -> Result<()> {
+        self.write_line(
+            json!({
+                "type": "account",
+                "slot": slot,
+                "pubkey": account.pubkey.to_string(),
+                "lamports": account.lamports,
+                "owner": account.owner.to_string(),
+                "executable": account.executable,
+                "rentEpoch": account.rent_epoch,
+                "data": base64::encode(account.data),
+                "isStartup": is_startup,
+            }),
+            false,
+        )
+    }
+
+    fn notify_end_of_startup(&self) -> Result<()> {
+        self.write_line(json!({ "type": "endOfStartup" }), true)
+    }
+
+    fn update_slot_status(
+        &self,
+        slot: Slot,
+        parent: Option<Slot>,
+        status: SlotStatus,
+    ) -> Result<()> {
+        // Slot boundaries are a natural point to push buffered lines out to the reader
+        self.write_line(
+            json!({
+                "type": "slot",
+                "slot": slot,
+                "parent": parent,
+                "status": status.as_str(),
+            }),
+            true,
+        )
+    }
+
+    fn notify_transaction(&self, transaction: ReplicaTransactionInfo, slot: Slot) -> Result<()> {
+        let meta = transaction.transaction_status_meta;
+        self.write_line(
+            json!({
+                "type": "transaction",
+                "slot": slot,
+                "signature": transaction.signature.to_string(),
+                "isVote": transaction.is_vote,
+                "err": meta.status.as_ref().err(),
+                "fee": meta.fee,
+            }),
+            false,
+        )
+    }
+
+    fn account_data_notifications_enabled(&self) -> bool {
+        self.accounts
+    }
+
+    fn transaction_notifications_enabled(&self) -> bool {
+        self.transactions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geyser_plugin_manager::GeyserPluginManager;
+    use solana_runtime::accounts_update_notifier_interface::AccountsUpdateNotifierInterface;
+    use solana_sdk::pubkey::Pubkey;
+    use std::io::{BufRead, BufReader};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_sink_plugin() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_path = temp_dir.path().join("geyser.jsonl");
+        let config_path = temp_dir.path().join("geyser.json");
+        std::fs::write(
+            &config_path,
+            json!({
+                "builtin": FileSinkPlugin::NAME,
+                "path": output_path,
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut plugin_manager = GeyserPluginManager::new();
+        plugin_manager.load_plugin(&config_path).unwrap();
+        assert!(plugin_manager.account_data_notifications_enabled());
+        assert!(!plugin_manager.transaction_notifications_enabled());
+
+        let pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let account = solana_sdk::account::Account {
+            lamports: 42,
+            data: vec![1, 2, 3],
+            owner,
+            executable: false,
+            rent_epoch: 7,
+        };
+        plugin_manager.notify_account_update(3, &pubkey, &account);
+        plugin_manager.notify_slot_status(3, Some(2), SlotStatus::Processed);
+        plugin_manager.unload();
+
+        let lines: Vec<Value> = BufReader::new(File::open(&output_path).unwrap())
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                json!({
+                    "type": "account",
+                    "slot": 3,
+                    "pubkey": pubkey.to_string(),
+                    "lamports": 42,
+                    "owner": owner.to_string(),
+                    "executable": false,
+                    "rentEpoch": 7,
+                    "data": base64::encode(&[1, 2, 3]),
+                    "isStartup": false,
+                }),
+                json!({
+                    "type": "slot",
+                    "slot": 3,
+                    "parent": 2,
+                    "status": "processed",
+                }),
+            ]
+        );
+    }
+}
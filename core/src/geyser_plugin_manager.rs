@@ -0,0 +1,437 @@
+//! The `geyser_plugin_manager` module loads Geyser plugins and fans validator
+//! notifications out to them: account writes from `AccountsDB`, transaction
+//! statuses from `TransactionStatusService` and slot status changes from
+//! `ReplayStage`.
+
+use crate::geyser_plugin_file_sink::FileSinkPlugin;
+use libloading::{Library, Symbol};
+use log::*;
+use serde_json::Value;
+use solana_geyser_plugin_interface::geyser_plugin_interface::{
+    GeyserPlugin, ReplicaAccountInfo, ReplicaTransactionInfo, SlotStatus,
+};
+use solana_measure::measure::Measure;
+use solana_runtime::accounts_update_notifier_interface::AccountsUpdateNotifierInterface;
+use solana_sdk::{
+    account::Account, clock::Slot, pubkey::Pubkey, signature::Signature, transaction::Transaction,
+};
+use solana_transaction_status::TransactionStatusMeta;
+use std::{
+    fs::File,
+    io::Read,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+use thiserror::Error;
+
+pub type GeyserPluginManagerLock = Arc<RwLock<GeyserPluginManager>>;
+
+/// Name of the constructor symbol a plugin dynamic library must export:
+///
+/// ```ignore
+/// #[no_mangle]
+/// #[allow(improper_ctypes_definitions)]
+/// pub unsafe extern "C" fn _create_plugin() -> *mut dyn GeyserPlugin {
+///     Box::into_raw(Box::new(MyPlugin::default()))
+/// }
+/// ```
+const CREATE_PLUGIN_SYMBOL: &[u8] = b"_create_plugin";
+
+type PluginConstructor = unsafe fn() -> *mut dyn GeyserPlugin;
+
+#[derive(Error, Debug)]
+pub enum GeyserPluginManagerError {
+    #[error("Cannot open the plugin config file: {0}")]
+    CannotOpenConfigFile(String),
+
+    #[error("Cannot read the plugin config file: {0}")]
+    CannotReadConfigFile(String),
+
+    #[error("The config file is not in a valid Json format: {0}")]
+    InvalidConfigFileFormat(String),
+
+    #[error("Plugin library path or builtin name is not specified in the config file")]
+    LibPathNotSet,
+
+    #[error("Unknown builtin plugin: {0}")]
+    UnknownBuiltinPlugin(String),
+
+    #[error("Error loading plugin library: {0}")]
+    PluginLoadError(String),
+
+    #[error("The plugin failed to initialize: {0}")]
+    PluginInitError(String),
+}
+
+#[derive(Default, Debug)]
+pub struct GeyserPluginManager {
+    // Plugins must be dropped before the libraries they were loaded from
+    plugins: Vec<Arc<dyn GeyserPlugin>>,
+    libs: Vec<Library>,
+}
+
+impl GeyserPluginManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the plugins described by each config file, in order.
+    pub fn new_from_config_files<P: AsRef<Path>>(
+        config_files: &[P],
+    ) -> Result<Self, GeyserPluginManagerError> {
+        let mut plugin_manager = Self::new();
+        for config_file in config_files {
+            plugin_manager.load_plugin(config_file.as_ref())?;
+        }
+        Ok(plugin_manager)
+    }
+
+    /// Load a plugin from a JSON config file.  The file must either contain a
+    /// `libpath` naming a dynamic library that exports `_create_plugin`, or a
+    /// `builtin` naming one of the plugins compiled into the validator.  The
+    /// whole file is handed to the plugin's `on_load`.
+    pub fn load_plugin(&mut self, config_file: &Path) -> Result<(), GeyserPluginManagerError> {
+        let mut file = File::open(config_file).map_err(|err| {
+            GeyserPluginManagerError::CannotOpenConfigFile(format!(
+                "Failed to open the plugin config file {:?}, error: {:?}",
+                config_file, err
+            ))
+        })?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).map_err(|err| {
+            GeyserPluginManagerError::CannotReadConfigFile(format!(
+                "Failed to read the plugin config file {:?}, error: {:?}",
+                config_file, err
+            ))
+        })?;
+        let config: Value = serde_json::from_str(&contents).map_err(|err| {
+            GeyserPluginManagerError::InvalidConfigFileFormat(format!(
+                "The config file {:?} is not in a valid Json format, error: {:?}",
+                config_file, err
+            ))
+        })?;
+
+        let mut plugin: Box<dyn GeyserPlugin> = if let Some(libpath) = config["libpath"].as_str() {
+            let (plugin, lib) = Self::load_plugin_library(libpath)?;
+            self.libs.push(lib);
+            plugin
+        } else if let Some(builtin) = config["builtin"].as_str() {
+            Self::new_builtin_plugin(builtin)?
+        } else {
+            return Err(GeyserPluginManagerError::LibPathNotSet);
+        };
+
+        let config_file = config_file.to_str().unwrap_or_default();
+        plugin.on_load(config_file).map_err(|err| {
+            GeyserPluginManagerError::PluginInitError(format!(
+                "Plugin {} failed to load from {:?}, error: {:?}",
+                plugin.name(),
+                config_file,
+                err
+            ))
+        })?;
+        info!(
+            "Loaded geyser plugin {} from {:?}",
+            plugin.name(),
+            config_file
+        );
+        self.plugins.push(Arc::from(plugin));
+        Ok(())
+    }
+
+    fn load_plugin_library(
+        libpath: &str,
+    ) -> Result<(Box<dyn GeyserPlugin>, Library), GeyserPluginManagerError> {
+        // Loading and calling into a foreign library is inherently unsafe; the
+        // plugin must be built with the same compiler and interface crate.
+        unsafe {
+            let lib = Library::new(libpath).map_err(|err| {
+                GeyserPluginManagerError::PluginLoadError(format!(
+                    "Failed to load plugin library {}, error: {:?}",
+                    libpath, err
+                ))
+            })?;
+            let constructor: Symbol<PluginConstructor> =
+                lib.get(CREATE_PLUGIN_SYMBOL).map_err(|err| {
+                    GeyserPluginManagerError::PluginLoadError(format!(
+                        "Plugin library {} does not export _create_plugin, error: {:?}",
+                        libpath, err
+                    ))
+                })?;
+            let plugin = Box::from_raw(constructor());
+            Ok((plugin, lib))
+        }
+    }
+
+    fn new_builtin_plugin(name: &str) -> Result<Box<dyn GeyserPlugin>, GeyserPluginManagerError> {
+        match name {
+            FileSinkPlugin::NAME => Ok(Box::new(FileSinkPlugin::default())),
+            _ => Err(GeyserPluginManagerError::UnknownBuiltinPlugin(
+                name.to_string(),
+            )),
+        }
+    }
+
+    /// Register an already initialized plugin, typically one supplied through
+    /// `ValidatorConfig::geyser_plugins`.
+    pub fn register_plugin(&mut self, plugin: Arc<dyn GeyserPlugin>) {
+        info!("Registered geyser plugin {}", plugin.name());
+        self.plugins.push(plugin);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Unload all plugins and the libraries they were loaded from.
+    pub fn unload(&mut self) {
+        for plugin in self.plugins.drain(..) {
+            info!("Unloading geyser plugin {}", plugin.name());
+            plugin.on_unload();
+        }
+        self.libs.clear();
+    }
+
+    pub fn account_data_notifications_enabled(&self) -> bool {
+        self.plugins
+            .iter()
+            .any(|plugin| plugin.account_data_notifications_enabled())
+    }
+
+    pub fn transaction_notifications_enabled(&self) -> bool {
+        self.plugins
+            .iter()
+            .any(|plugin| plugin.transaction_notifications_enabled())
+    }
+
+    fn notify_account(&self, slot: Slot, pubkey: &Pubkey, account: &Account, is_startup: bool) {
+        let mut measure = Measure::start("geyser-plugin-update-account");
+        for plugin in self
+            .plugins
+            .iter()
+            .filter(|plugin| plugin.account_data_notifications_enabled())
+        {
+            let account_info = ReplicaAccountInfo {
+                pubkey,
+                lamports: account.lamports,
+                owner: &account.owner,
+                executable: account.executable,
+                rent_epoch: account.rent_epoch,
+                data: &account.data,
+            };
+            if let Err(err) = plugin.update_account(account_info, slot, is_startup) {
+                error!(
+                    "Failed to update account {} at slot {}, error to plugin {}: {:?}",
+                    pubkey,
+                    slot,
+                    plugin.name(),
+                    err
+                );
+            }
+        }
+        measure.stop();
+        inc_new_counter_debug!(
+            "geyser-plugin-update-account-us",
+            measure.as_us() as usize,
+            100000,
+            100000
+        );
+    }
+
+    pub fn notify_slot_status(&self, slot: Slot, parent: Option<Slot>, status: SlotStatus) {
+        for plugin in &self.plugins {
+            if let Err(err) = plugin.update_slot_status(slot, parent, status) {
+                error!(
+                    "Failed to update slot status at slot {}, error to plugin {}: {:?}",
+                    slot,
+                    plugin.name(),
+                    err
+                );
+            }
+        }
+    }
+
+    pub fn notify_transaction(
+        &self,
+        slot: Slot,
+        signature: &Signature,
+        transaction: &Transaction,
+        transaction_status_meta: &TransactionStatusMeta,
+    ) {
+        let is_vote =
+            solana_vote_program::vote_transaction::parse_vote_transaction(transaction).is_some();
+        for plugin in self
+            .plugins
+            .iter()
+            .filter(|plugin| plugin.transaction_notifications_enabled())
+        {
+            let transaction_info = ReplicaTransactionInfo {
+                signature,
+                is_vote,
+                transaction,
+                transaction_status_meta,
+            };
+            if let Err(err) = plugin.notify_transaction(transaction_info, slot) {
+                error!(
+                    "Failed to notify transaction {} at slot {}, error to plugin {}: {:?}",
+                    signature,
+                    slot,
+                    plugin.name(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+impl AccountsUpdateNotifierInterface for GeyserPluginManager {
+    fn notify_account_update(&self, slot: Slot, pubkey: &Pubkey, account: &Account) {
+        self.notify_account(slot, pubkey, account, false);
+    }
+
+    fn notify_account_restore_from_snapshot(&self, slot: Slot, pubkey: &Pubkey, account: &Account) {
+        self.notify_account(slot, pubkey, account, true);
+    }
+
+    fn notify_end_of_restore_from_snapshot(&self) {
+        for plugin in &self.plugins {
+            if let Err(err) = plugin.notify_end_of_startup() {
+                error!(
+                    "Failed to notify the end of startup, error to plugin {}: {:?}",
+                    plugin.name(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_geyser_plugin_interface::geyser_plugin_interface::Result as PluginResult;
+    use solana_runtime::{
+        accounts_update_notifier_interface::AccountsUpdateNotifier,
+        bank::Bank,
+        genesis_utils::{create_genesis_config, GenesisConfigInfo},
+    };
+    use solana_sdk::signature::Signer;
+    use std::{io::Write, sync::Mutex};
+    use tempfile::NamedTempFile;
+
+    #[derive(Debug, Default)]
+    struct TestPlugin {
+        accounts: Mutex<Vec<(Slot, Pubkey, u64, bool)>>,
+        slots: Mutex<Vec<(Slot, SlotStatus)>>,
+        end_of_startup: Mutex<bool>,
+    }
+
+    impl GeyserPlugin for TestPlugin {
+        fn name(&self) -> &'static str {
+            "test-plugin"
+        }
+
+        fn update_account(
+            &self,
+            account: ReplicaAccountInfo,
+            slot: Slot,
+            is_startup: bool,
+        ) -> PluginResult<()> {
+            self.accounts.lock().unwrap().push((
+                slot,
+                *account.pubkey,
+                account.lamports,
+                is_startup,
+            ));
+            Ok(())
+        }
+
+        fn notify_end_of_startup(&self) -> PluginResult<()> {
+            *self.end_of_startup.lock().unwrap() = true;
+            Ok(())
+        }
+
+        fn update_slot_status(
+            &self,
+            slot: Slot,
+            _parent: Option<Slot>,
+            status: SlotStatus,
+        ) -> PluginResult<()> {
+            self.slots.lock().unwrap().push((slot, status));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_load_plugin_config_errors() {
+        let mut plugin_manager = GeyserPluginManager::new();
+        assert!(matches!(
+            plugin_manager.load_plugin(Path::new("/nonexistent/geyser.json")),
+            Err(GeyserPluginManagerError::CannotOpenConfigFile(_))
+        ));
+
+        let mut config_file = NamedTempFile::new().unwrap();
+        write!(config_file, "not json").unwrap();
+        assert!(matches!(
+            plugin_manager.load_plugin(config_file.path()),
+            Err(GeyserPluginManagerError::InvalidConfigFileFormat(_))
+        ));
+
+        let mut config_file = NamedTempFile::new().unwrap();
+        write!(config_file, "{{}}").unwrap();
+        assert!(matches!(
+            plugin_manager.load_plugin(config_file.path()),
+            Err(GeyserPluginManagerError::LibPathNotSet)
+        ));
+
+        let mut config_file = NamedTempFile::new().unwrap();
+        write!(config_file, "{{\"builtin\": \"unknown\"}}").unwrap();
+        assert!(matches!(
+            plugin_manager.load_plugin(config_file.path()),
+            Err(GeyserPluginManagerError::UnknownBuiltinPlugin(_))
+        ));
+        assert!(plugin_manager.is_empty());
+    }
+
+    #[test]
+    fn test_accounts_update_notifications() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let bank = Bank::new(&genesis_config);
+
+        let plugin = Arc::new(TestPlugin::default());
+        let mut plugin_manager = GeyserPluginManager::new();
+        plugin_manager.register_plugin(plugin.clone());
+        let plugin_manager = Arc::new(RwLock::new(plugin_manager));
+        let accounts_update_notifier: AccountsUpdateNotifier = plugin_manager.clone();
+        bank.set_accounts_update_notifier(Some(accounts_update_notifier));
+
+        bank.notify_account_restore_from_snapshot();
+        assert!(*plugin.end_of_startup.lock().unwrap());
+        assert!(plugin.accounts.lock().unwrap().iter().any(
+            |(slot, pubkey, lamports, is_startup)| *slot == 0
+                && *pubkey == mint_keypair.pubkey()
+                && *lamports == 10_000
+                && *is_startup
+        ));
+        plugin.accounts.lock().unwrap().clear();
+
+        let recipient = Pubkey::new_unique();
+        bank.transfer(1_000, &mint_keypair, &recipient).unwrap();
+        let accounts = plugin.accounts.lock().unwrap();
+        assert!(accounts.contains(&(0, recipient, 1_000, false)));
+        assert!(accounts
+            .iter()
+            .any(|(_, pubkey, _, is_startup)| *pubkey == mint_keypair.pubkey() && !is_startup));
+        drop(accounts);
+
+        plugin_manager
+            .read()
+            .unwrap()
+            .notify_slot_status(0, None, SlotStatus::Rooted);
+        assert_eq!(*plugin.slots.lock().unwrap(), vec![(0, SlotStatus::Rooted)]);
+    }
+}
@@ -0,0 +1,391 @@
+//! The `geyser_plugin_postgres` module implements a Geyser plugin writing
+//! account updates into the `account` table of the PostgreSQL ledger sink.
+//! Runtime updates are held per slot until the slot is rooted, then handed to
+//! a writer thread which batches them into database transactions, reconnecting
+//! whenever the database goes away.  The account update cursor records the last
+//! rooted slot written, so a restarted validator skips what is already stored.
+
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender};
+use solana_geyser_plugin_interface::geyser_plugin_interface::{
+    GeyserPlugin, ReplicaAccountInfo, Result, SlotStatus,
+};
+use solana_sdk::clock::Slot;
+use solana_storage_postgres::{
+    AccountUpdate, LedgerStorage, PostgresConfig, ACCOUNT_UPDATE_CURSOR,
+};
+use std::{
+    collections::BTreeMap,
+    fmt, mem,
+    sync::{Mutex, RwLock},
+    thread::{sleep, Builder, JoinHandle},
+    time::Duration,
+};
+
+// Messages queued for the writer thread.  Once full, notifications wait for
+// the database to catch up instead of dropping updates.
+const MAX_QUEUED_MESSAGES: usize = 10_000;
+
+// Write a partial batch if no message arrived for this long
+const FLUSH_TIMEOUT: Duration = Duration::from_millis(500);
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+enum WriterMessage {
+    /// An account restored from the snapshot at boot
+    Startup(AccountUpdate),
+    /// All accounts restored at boot, from the snapshot at this slot, were sent
+    EndOfStartup(Slot),
+    /// The updates of a newly rooted slot, in the order they were stored
+    Rooted(Slot, Vec<AccountUpdate>),
+}
+
+#[derive(Default)]
+struct PendingUpdates {
+    /// Updates of slots that are not rooted yet
+    unrooted: BTreeMap<Slot, Vec<AccountUpdate>>,
+    /// Parents of the slots above the last root, used to find forks a new root abandons
+    parents: BTreeMap<Slot, Slot>,
+    last_root: Option<Slot>,
+    startup_slot: Option<Slot>,
+}
+
+impl PendingUpdates {
+    fn add(&mut self, account_update: AccountUpdate) {
+        let slot = account_update.slot;
+        if self.last_root.map(|root| slot > root).unwrap_or(true) {
+            self.unrooted.entry(slot).or_default().push(account_update);
+        }
+    }
+
+    fn set_parent(&mut self, slot: Slot, parent: Slot) {
+        if self.last_root.map(|root| slot > root).unwrap_or(true) {
+            self.parents.insert(slot, parent);
+        }
+    }
+
+    fn remove(&mut self, slot: Slot) {
+        self.unrooted.remove(&slot);
+        self.parents.remove(&slot);
+    }
+
+    /// Root `slot` and return its updates, with the number of updates dropped
+    /// from the forks it abandons, or None if `slot` is not above the last root
+    fn root(&mut self, slot: Slot) -> Option<(Vec<AccountUpdate>, usize)> {
+        if self.last_root.map(|root| slot <= root).unwrap_or(false) {
+            return None;
+        }
+        self.last_root = Some(slot);
+        self.parents = self.parents.split_off(&(slot + 1));
+
+        // Unrooted slots at or below the new root, and those above it which
+        // don't descend from it, are on abandoned forks
+        let newer = self.unrooted.split_off(&(slot + 1));
+        let mut rooted = mem::replace(&mut self.unrooted, newer);
+        let account_updates = rooted.remove(&slot).unwrap_or_default();
+        let abandoned_slots: Vec<Slot> = self
+            .unrooted
+            .keys()
+            .filter(|unrooted_slot| !self.descends_from(**unrooted_slot, slot))
+            .copied()
+            .collect();
+        let mut abandoned: usize = rooted.values().map(Vec::len).sum();
+        for abandoned_slot in abandoned_slots {
+            abandoned += self.unrooted.remove(&abandoned_slot).map_or(0, Vec::len);
+            self.parents.remove(&abandoned_slot);
+        }
+        Some((account_updates, abandoned))
+    }
+
+    /// Whether `slot` descends from `root`.  Slots whose ancestry isn't known
+    /// yet are assumed to, and are kept until a later root passes them.
+    fn descends_from(&self, mut slot: Slot, root: Slot) -> bool {
+        loop {
+            match self.parents.get(&slot) {
+                Some(parent) if *parent > root => slot = *parent,
+                Some(parent) => return *parent == root,
+                None => return true,
+            }
+        }
+    }
+}
+
+pub struct PostgresAccountsPlugin {
+    sender: RwLock<Option<Sender<WriterMessage>>>,
+    pending: Mutex<PendingUpdates>,
+    writer_thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl fmt::Debug for PostgresAccountsPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PostgresAccountsPlugin").finish()
+    }
+}
+
+impl PostgresAccountsPlugin {
+    pub const NAME: &'static str = "postgres-accounts";
+
+    pub fn new(config: PostgresConfig) -> Self {
+        let (sender, receiver) = bounded(MAX_QUEUED_MESSAGES);
+        let writer_thread = Builder::new()
+            .name("postgres-accounts".to_string())
+            .spawn(move || Self::run_writer(config, receiver))
+            .unwrap();
+        Self {
+            sender: RwLock::new(Some(sender)),
+            pending: Mutex::new(PendingUpdates::default()),
+            writer_thread: Mutex::new(Some(writer_thread)),
+        }
+    }
+
+    fn send(&self, message: WriterMessage) {
+        if let Some(sender) = self.sender.read().unwrap().as_ref() {
+            // Blocks while the queue is full
+            let _ = sender.send(message);
+        }
+    }
+
+    /// Connect to the database and return the last slot whose updates are
+    /// already stored
+    fn connect(
+        config: &PostgresConfig,
+    ) -> solana_storage_postgres::Result<(LedgerStorage, Option<Slot>)> {
+        let mut ledger_storage = LedgerStorage::connect(config)?;
+        let cursor = ledger_storage.get_cursor(ACCOUNT_UPDATE_CURSOR)?;
+        info!("postgres: account updates stored up to slot {:?}", cursor);
+        Ok((ledger_storage, cursor))
+    }
+
+    fn run_writer(config: PostgresConfig, receiver: Receiver<WriterMessage>) {
+        let batch_size = config.batch_size.max(1);
+        let mut ledger_storage: Option<LedgerStorage> = None;
+        let mut cursor: Option<Slot> = None;
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut batch_cursor: Option<Slot> = None;
+        let mut disconnected = false;
+        loop {
+            if ledger_storage
+                .as_ref()
+                .map(LedgerStorage::is_closed)
+                .unwrap_or(true)
+            {
+                match Self::connect(&config) {
+                    Ok((new_ledger_storage, stored_cursor)) => {
+                        ledger_storage = Some(new_ledger_storage);
+                        cursor = stored_cursor;
+                    }
+                    Err(err) => {
+                        warn!("postgres: failed to connect: {}", err);
+                        inc_new_counter_info!("postgres-accounts-connection_error", 1);
+                        if disconnected {
+                            error!("postgres: dropped {} account updates on exit", batch.len());
+                            break;
+                        }
+                        sleep(RECONNECT_DELAY);
+                        continue;
+                    }
+                }
+            }
+
+            // Slots at or below the cursor were written before a restart
+            let is_stored = |slot: Slot| cursor.map(|cursor| slot <= cursor).unwrap_or(false);
+            while batch.len() < batch_size && !disconnected {
+                match receiver.recv_timeout(FLUSH_TIMEOUT) {
+                    Ok(WriterMessage::Startup(account_update)) => {
+                        if !is_stored(account_update.slot) {
+                            batch.push(account_update);
+                        }
+                    }
+                    Ok(WriterMessage::EndOfStartup(slot)) => {
+                        if !is_stored(slot) {
+                            batch_cursor = Some(slot);
+                        }
+                    }
+                    Ok(WriterMessage::Rooted(slot, account_updates)) => {
+                        if !is_stored(slot) {
+                            batch.extend(account_updates);
+                            batch_cursor = Some(slot);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => disconnected = true,
+                }
+            }
+            if batch.is_empty() && batch_cursor.is_none() {
+                if disconnected {
+                    break;
+                }
+                continue;
+            }
+
+            // The pending batch is retried after reconnecting, until written
+            // or the plugin is unloaded
+            match ledger_storage
+                .as_mut()
+                .unwrap()
+                .upload_account_updates(&batch, batch_cursor)
+            {
+                Ok(()) => {
+                    inc_new_counter_info!("postgres-accounts-uploaded", batch.len());
+                    cursor = batch_cursor.or(cursor);
+                    batch.clear();
+                    batch_cursor = None;
+                }
+                Err(err) => {
+                    warn!("postgres: upload_account_updates: {}", err);
+                    inc_new_counter_info!("postgres-accounts-upload_error", 1);
+                    ledger_storage = None;
+                    if disconnected {
+                        error!("postgres: dropped {} account updates on exit", batch.len());
+                        break;
+                    }
+                    sleep(RECONNECT_DELAY);
+                }
+            }
+        }
+    }
+}
+
+impl GeyserPlugin for PostgresAccountsPlugin {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn on_unload(&self) {
+        // Dropping the sender lets the writer thread flush and exit
+        self.sender.write().unwrap().take();
+        if let Some(writer_thread) = self.writer_thread.lock().unwrap().take() {
+            if writer_thread.join().is_err() {
+                error!("postgres: account writer thread panicked");
+            }
+        }
+    }
+
+    fn update_account(
+        &self,
+        account: ReplicaAccountInfo,
+        slot: Slot,
+        is_startup: bool,
+    ) -> Result<()> {
+        let account_update = AccountUpdate {
+            pubkey: *account.pubkey,
+            owner: *account.owner,
+            lamports: account.lamports,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: account.data.to_vec(),
+            slot,
+        };
+        if is_startup {
+            // Accounts restored at boot are rooted already
+            let mut pending = self.pending.lock().unwrap();
+            pending.startup_slot = pending.startup_slot.max(Some(slot));
+            drop(pending);
+            self.send(WriterMessage::Startup(account_update));
+        } else {
+            self.pending.lock().unwrap().add(account_update);
+        }
+        Ok(())
+    }
+
+    fn notify_end_of_startup(&self) -> Result<()> {
+        let startup_slot = self.pending.lock().unwrap().startup_slot;
+        if let Some(startup_slot) = startup_slot {
+            self.send(WriterMessage::EndOfStartup(startup_slot));
+        }
+        Ok(())
+    }
+
+    fn update_slot_status(
+        &self,
+        slot: Slot,
+        parent: Option<Slot>,
+        status: SlotStatus,
+    ) -> Result<()> {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(parent) = parent {
+            pending.set_parent(slot, parent);
+        }
+        match status {
+            SlotStatus::Processed => {}
+            SlotStatus::Dead => {
+                pending.remove(slot);
+            }
+            SlotStatus::Rooted => {
+                let rooted = pending.root(slot);
+                drop(pending);
+
+                if let Some((account_updates, abandoned)) = rooted {
+                    if abandoned > 0 {
+                        inc_new_counter_info!("postgres-accounts-abandoned", abandoned);
+                    }
+                    self.send(WriterMessage::Rooted(slot, account_updates));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn account_data_notifications_enabled(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn account_update(slot: Slot) -> AccountUpdate {
+        AccountUpdate {
+            pubkey: Pubkey::new_unique(),
+            owner: Pubkey::default(),
+            lamports: 1,
+            executable: false,
+            rent_epoch: 0,
+            data: vec![],
+            slot,
+        }
+    }
+
+    #[test]
+    fn test_pending_updates_prune_abandoned_forks() {
+        let mut pending = PendingUpdates::default();
+        // 0 <- 1 <- 2 <- 4
+        //        \
+        //         <- 3 <- 5
+        for (slot, parent) in &[(1, 0), (2, 1), (3, 1), (4, 2), (5, 3)] {
+            pending.set_parent(*slot, *parent);
+            pending.add(account_update(*slot));
+        }
+        // Slot 6's ancestry isn't known yet
+        pending.add(account_update(6));
+
+        let (account_updates, abandoned) = pending.root(1).unwrap();
+        assert_eq!(account_updates.len(), 1);
+        assert_eq!(abandoned, 0);
+
+        // Rooting 2 abandons 3 and its descendant 5
+        let (account_updates, abandoned) = pending.root(2).unwrap();
+        assert_eq!(account_updates[0].slot, 2);
+        assert_eq!(abandoned, 2);
+        assert_eq!(
+            pending.unrooted.keys().copied().collect::<Vec<_>>(),
+            vec![4, 6]
+        );
+        assert_eq!(pending.parents.keys().copied().collect::<Vec<_>>(), vec![4]);
+
+        // Old roots and updates at or below the last root are ignored
+        assert!(pending.root(1).is_none());
+        pending.add(account_update(2));
+        pending.set_parent(2, 1);
+        assert!(!pending.unrooted.contains_key(&2));
+        assert!(!pending.parents.contains_key(&2));
+
+        // Once a root passes slot 6, it is dropped too
+        let (account_updates, abandoned) = pending.root(7).unwrap();
+        assert!(account_updates.is_empty());
+        assert_eq!(abandoned, 2);
+        assert!(pending.unrooted.is_empty());
+        assert!(pending.parents.is_empty());
+    }
+}
@@ -36,6 +36,9 @@ pub mod epoch_slots;
 pub mod fetch_stage;
 pub mod fork_choice;
 pub mod gen_keys;
+pub mod geyser_plugin_file_sink;
+pub mod geyser_plugin_manager;
+pub mod geyser_plugin_postgres;
 pub mod gossip_service;
 pub mod heaviest_subtree_fork_choice;
 pub mod ledger_cleanup_service;
@@ -46,6 +49,7 @@ pub mod packet_hasher;
 pub mod ping_pong;
 pub mod poh_recorder;
 pub mod poh_service;
+pub mod postgres_upload_service;
 pub mod progress_map;
 pub mod pubkey_references;
 pub mod repair_response;
@@ -60,6 +64,7 @@ pub mod rpc;
 pub mod rpc_health;
 pub mod rpc_pubsub;
 pub mod rpc_pubsub_service;
+pub mod rpc_rate_limiter;
 pub mod rpc_service;
 pub mod rpc_subscriptions;
 pub mod send_transaction_service;
@@ -4,8 +4,9 @@
 
 use crate::rpc_subscriptions::RpcSubscriptions;
 use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use solana_client::rpc_response::SlotUpdate;
 use solana_runtime::{bank::Bank, bank_forks::BankForks};
-use solana_sdk::clock::Slot;
+use solana_sdk::{clock::Slot, timing::timestamp};
 use std::{
     collections::HashSet,
     sync::{
@@ -123,6 +124,7 @@ impl OptimisticallyConfirmedBankTracker {
                     if bank.slot() > w_optimistically_confirmed_bank.bank.slot() {
                         w_optimistically_confirmed_bank.bank = bank.clone();
                         subscriptions.notify_gossip_subscribers(slot);
+                        Self::notify_optimistic_confirmation(&bank, subscriptions);
                     }
                     drop(w_optimistically_confirmed_bank);
                 } else if slot > bank_forks.read().unwrap().root_bank().slot() {
@@ -137,6 +139,10 @@ impl OptimisticallyConfirmedBankTracker {
                     if frozen_slot > w_optimistically_confirmed_bank.bank.slot() {
                         w_optimistically_confirmed_bank.bank = bank;
                         subscriptions.notify_gossip_subscribers(frozen_slot);
+                        Self::notify_optimistic_confirmation(
+                            &w_optimistically_confirmed_bank.bank,
+                            subscriptions,
+                        );
                     }
                     drop(w_optimistically_confirmed_bank);
                 }
@@ -154,6 +160,14 @@ impl OptimisticallyConfirmedBankTracker {
         }
     }
 
+    fn notify_optimistic_confirmation(bank: &Bank, subscriptions: &RpcSubscriptions) {
+        subscriptions.notify_slot_update(SlotUpdate::OptimisticConfirmation {
+            slot: bank.slot(),
+            parent: bank.parent_slot(),
+            timestamp: timestamp(),
+        });
+    }
+
     pub fn close(self) -> thread::Result<()> {
         self.join()
     }
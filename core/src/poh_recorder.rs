@@ -21,7 +21,7 @@ use solana_sdk::hash::Hash;
 use solana_sdk::poh_config::PohConfig;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::timing;
-use solana_sdk::transaction::Transaction;
+use solana_sdk::transaction::VersionedTransaction;
 use std::cmp;
 use std::sync::mpsc::{channel, Receiver, SendError, Sender, SyncSender};
 use std::sync::{Arc, Mutex};
@@ -385,7 +385,7 @@ impl PohRecorder {
         &mut self,
         bank_slot: Slot,
         mixin: Hash,
-        transactions: Vec<Transaction>,
+        transactions: Vec<VersionedTransaction>,
     ) -> Result<()> {
         // Entries without transactions are used to track real-time passing in the ledger and
         // cannot be generated by `record()`
@@ -754,7 +754,9 @@ mod tests {
             poh_recorder.tick();
             let tx = test_tx();
             let h1 = hash(b"hello world!");
-            assert!(poh_recorder.record(bank.slot(), h1, vec![tx]).is_err());
+            assert!(poh_recorder
+                .record(bank.slot(), h1, vec![tx.into()])
+                .is_err());
             assert!(entry_receiver.try_recv().is_err());
         }
         Blockstore::destroy(&ledger_path).unwrap();
@@ -793,7 +795,7 @@ mod tests {
             let tx = test_tx();
             let h1 = hash(b"hello world!");
             assert_matches!(
-                poh_recorder.record(bank.slot() + 1, h1, vec![tx]),
+                poh_recorder.record(bank.slot() + 1, h1, vec![tx.into()]),
                 Err(PohRecorderError::MaxHeightReached)
             );
         }
@@ -832,7 +834,9 @@ mod tests {
             assert_eq!(poh_recorder.tick_height, 1);
             let tx = test_tx();
             let h1 = hash(b"hello world!");
-            assert!(poh_recorder.record(bank.slot(), h1, vec![tx]).is_ok());
+            assert!(poh_recorder
+                .record(bank.slot(), h1, vec![tx.into()])
+                .is_ok());
             assert_eq!(poh_recorder.tick_cache.len(), 0);
 
             //tick in the cache + entry
@@ -876,7 +880,9 @@ mod tests {
             assert_eq!(poh_recorder.tick_height, 2);
             let tx = test_tx();
             let h1 = hash(b"hello world!");
-            assert!(poh_recorder.record(bank.slot(), h1, vec![tx]).is_err());
+            assert!(poh_recorder
+                .record(bank.slot(), h1, vec![tx.into()])
+                .is_err());
 
             let (_bank, (entry, _tick_height)) = entry_receiver.recv().unwrap();
             assert!(entry.is_tick());
@@ -1111,7 +1117,9 @@ mod tests {
 
             let tx = test_tx();
             let h1 = hash(b"hello world!");
-            assert!(poh_recorder.record(bank.slot(), h1, vec![tx]).is_err());
+            assert!(poh_recorder
+                .record(bank.slot(), h1, vec![tx.into()])
+                .is_err());
             assert!(poh_recorder.working_bank.is_none());
             // Make sure the starting slot is updated
             assert_eq!(poh_recorder.start_slot, end_slot);
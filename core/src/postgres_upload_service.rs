@@ -0,0 +1,160 @@
+//! The `postgres_upload_service` writes confirmed blocks into PostgreSQL as
+//! they are rooted.  Progress is tracked by a cursor stored alongside the
+//! blocks, so uploads resume where they left off after a validator or
+//! database restart.
+
+use solana_ledger::blockstore::Blockstore;
+use solana_measure::measure::Measure;
+use solana_runtime::commitment::BlockCommitmentCache;
+use solana_sdk::clock::Slot;
+use solana_storage_postgres::{LedgerStorage, PostgresConfig, LEDGER_UPLOAD_CURSOR};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    sync::{Arc, RwLock},
+    thread::{self, sleep, Builder, JoinHandle},
+    time::Duration,
+};
+
+// Delay uploading the largest confirmed root for this many slots, to give the
+// `CacheBlockTimeService` time to add the block time for the root.  See
+// `BigTableUploadService`.
+const LARGEST_CONFIRMED_ROOT_UPLOAD_DELAY: u64 = 100;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+pub struct PostgresUploadService {
+    thread: JoinHandle<()>,
+}
+
+impl PostgresUploadService {
+    pub fn new(
+        config: PostgresConfig,
+        blockstore: Arc<Blockstore>,
+        block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+        exit: Arc<AtomicBool>,
+    ) -> Self {
+        info!("Starting Postgres upload service");
+        let thread = Builder::new()
+            .name("postgres-upload".to_string())
+            .spawn(move || Self::run(config, blockstore, block_commitment_cache, exit))
+            .unwrap();
+
+        Self { thread }
+    }
+
+    /// Connect to the database and return the first slot that has not been
+    /// uploaded yet
+    fn connect(config: &PostgresConfig) -> solana_storage_postgres::Result<(LedgerStorage, Slot)> {
+        let mut ledger_storage = LedgerStorage::connect(config)?;
+        let start_slot = ledger_storage
+            .get_cursor(LEDGER_UPLOAD_CURSOR)?
+            .map(|slot| slot + 1)
+            .unwrap_or_default();
+        info!(
+            "postgres: uploading confirmed blocks from slot {}",
+            start_slot
+        );
+        Ok((ledger_storage, start_slot))
+    }
+
+    fn run(
+        config: PostgresConfig,
+        blockstore: Arc<Blockstore>,
+        block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+        exit: Arc<AtomicBool>,
+    ) {
+        let mut ledger_storage: Option<LedgerStorage> = None;
+        let mut start_slot = 0;
+        loop {
+            if exit.load(Ordering::Relaxed) {
+                break;
+            }
+
+            if ledger_storage
+                .as_ref()
+                .map(LedgerStorage::is_closed)
+                .unwrap_or(true)
+            {
+                match Self::connect(&config) {
+                    Ok((new_ledger_storage, cursor_slot)) => {
+                        ledger_storage = Some(new_ledger_storage);
+                        start_slot = cursor_slot;
+                    }
+                    Err(err) => {
+                        warn!("postgres: failed to connect: {}", err);
+                        inc_new_counter_info!("postgres-upload-connection_error", 1);
+                        sleep(RECONNECT_DELAY);
+                        continue;
+                    }
+                }
+            }
+            let ledger_storage_ = ledger_storage.as_mut().unwrap();
+
+            let end_slot = block_commitment_cache
+                .read()
+                .unwrap()
+                .highest_confirmed_root()
+                .saturating_sub(LARGEST_CONFIRMED_ROOT_UPLOAD_DELAY);
+
+            let slots: Vec<Slot> = match blockstore.rooted_slot_iterator(start_slot) {
+                Ok(slots) => slots
+                    .take_while(|slot| *slot <= end_slot)
+                    .take(config.batch_size.max(1))
+                    .collect(),
+                Err(err) => {
+                    warn!("postgres: failed to read rooted slots: {:?}", err);
+                    vec![]
+                }
+            };
+
+            // Stop at the first block that fails to load, so the cursor never
+            // moves past it and it is retried with the next batch
+            let mut load_time = Measure::start("load_time");
+            let mut blocks = Vec::with_capacity(slots.len());
+            for slot in slots {
+                match blockstore.get_confirmed_block(slot) {
+                    Ok(confirmed_block) => blocks.push((slot, confirmed_block)),
+                    Err(err) => {
+                        warn!("postgres: failed to load block {}: {:?}", slot, err);
+                        inc_new_counter_info!("postgres-upload-load_error", 1);
+                        break;
+                    }
+                }
+            }
+            load_time.stop();
+            let last_slot = match blocks.last() {
+                Some((last_slot, _)) => *last_slot,
+                None => {
+                    sleep(Duration::from_secs(1));
+                    continue;
+                }
+            };
+
+            let mut upload_time = Measure::start("upload_time");
+            match ledger_storage_.upload_confirmed_blocks(&blocks) {
+                Ok(()) => {
+                    upload_time.stop();
+                    datapoint_info!(
+                        "postgres-upload",
+                        ("last_slot", last_slot, i64),
+                        ("num_blocks", blocks.len(), i64),
+                        ("load_time_us", load_time.as_us(), i64),
+                        ("upload_time_us", upload_time.as_us(), i64),
+                    );
+                    start_slot = last_slot + 1;
+                }
+                Err(err) => {
+                    // The cursor was not advanced, the batch is uploaded again once reconnected
+                    warn!("postgres: upload_confirmed_blocks: {}", err);
+                    inc_new_counter_info!("postgres-upload-upload_error", 1);
+                    ledger_storage = None;
+                    sleep(RECONNECT_DELAY);
+                }
+            }
+        }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread.join()
+    }
+}
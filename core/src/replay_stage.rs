@@ -9,6 +9,7 @@ use crate::{
     commitment_service::{AggregateCommitmentService, CommitmentAggregationData},
     consensus::{ComputedBankState, Stake, SwitchForkDecision, Tower, VotedStakes},
     fork_choice::{ForkChoice, SelectVoteAndResetForkResult},
+    geyser_plugin_manager::GeyserPluginManagerLock,
     heaviest_subtree_fork_choice::HeaviestSubtreeForkChoice,
     optimistically_confirmed_bank_tracker::{BankNotification, BankNotificationSender},
     poh_recorder::{PohRecorder, GRACE_TICKS_FACTOR, MAX_GRACE_SLOTS},
@@ -18,6 +19,8 @@ use crate::{
     rewards_recorder_service::RewardsRecorderSender,
     rpc_subscriptions::RpcSubscriptions,
 };
+use solana_client::rpc_response::SlotUpdate;
+use solana_geyser_plugin_interface::geyser_plugin_interface::SlotStatus;
 use solana_ledger::{
     block_error::BlockError,
     blockstore::Blockstore,
@@ -105,6 +108,7 @@ pub struct ReplayStageConfig {
     pub rewards_recorder_sender: Option<RewardsRecorderSender>,
     pub cache_block_time_sender: Option<CacheBlockTimeSender>,
     pub bank_notification_sender: Option<BankNotificationSender>,
+    pub geyser_plugin_manager: Option<GeyserPluginManagerLock>,
 }
 
 #[derive(Default)]
@@ -265,6 +269,7 @@ impl ReplayStage {
             rewards_recorder_sender,
             cache_block_time_sender,
             bank_notification_sender,
+            geyser_plugin_manager,
         } = config;
 
         trace!("replay stage");
@@ -332,6 +337,8 @@ impl ReplayStage {
                         &replay_vote_sender,
                         &bank_notification_sender,
                         &rewards_recorder_sender,
+                        &geyser_plugin_manager,
+                        &subscriptions,
                     );
                     replay_active_banks_time.stop();
                     Self::report_memory(&allocated, "replay_active_banks", start);
@@ -480,6 +487,7 @@ impl ReplayStage {
                             &mut heaviest_subtree_fork_choice,
                             &cache_block_time_sender,
                             &bank_notification_sender,
+                            &geyser_plugin_manager,
                         );
                     };
                     voting_time.stop();
@@ -1077,6 +1085,7 @@ impl ReplayStage {
         heaviest_subtree_fork_choice: &mut HeaviestSubtreeForkChoice,
         cache_block_time_sender: &Option<CacheBlockTimeSender>,
         bank_notification_sender: &Option<BankNotificationSender>,
+        geyser_plugin_manager: &Option<GeyserPluginManagerLock>,
     ) {
         if bank.is_empty() {
             inc_new_counter_info!("replay_stage-voted_empty_bank", 1);
@@ -1130,6 +1139,27 @@ impl ReplayStage {
                 highest_confirmed_root,
                 heaviest_subtree_fork_choice,
             );
+            if let Some(geyser_plugin_manager) = geyser_plugin_manager {
+                let geyser_plugin_manager = geyser_plugin_manager.read().unwrap();
+                // Plugins see roots in ascending order, so anything below a
+                // notified root that was not rooted itself is abandoned
+                let mut ascending_rooted_banks: Vec<_> = rooted_banks.iter().collect();
+                ascending_rooted_banks.sort_by_key(|bank| bank.slot());
+                for rooted_bank in ascending_rooted_banks {
+                    geyser_plugin_manager.notify_slot_status(
+                        rooted_bank.slot(),
+                        rooted_bank.parent().map(|parent| parent.slot()),
+                        SlotStatus::Rooted,
+                    );
+                }
+            }
+            for rooted_bank in &rooted_banks {
+                subscriptions.notify_slot_update(SlotUpdate::Root {
+                    slot: rooted_bank.slot(),
+                    parent: rooted_bank.parent_slot(),
+                    timestamp: timestamp(),
+                });
+            }
             subscriptions.notify_roots(rooted_slots);
             if let Some(sender) = bank_notification_sender {
                 sender
@@ -1305,6 +1335,8 @@ impl ReplayStage {
         replay_vote_sender: &ReplayVoteSender,
         bank_notification_sender: &Option<BankNotificationSender>,
         rewards_recorder_sender: &Option<RewardsRecorderSender>,
+        geyser_plugin_manager: &Option<GeyserPluginManagerLock>,
+        subscriptions: &Arc<RpcSubscriptions>,
     ) -> bool {
         let mut did_complete_bank = false;
         let mut tx_count = 0;
@@ -1357,6 +1389,13 @@ impl ReplayStage {
                     Ok(replay_tx_count) => tx_count += replay_tx_count,
                     Err(err) => {
                         trace!("replay_result err: {:?}, slot {}", err, bank_slot);
+                        Self::notify_slot_status(geyser_plugin_manager, &bank, SlotStatus::Dead);
+                        subscriptions.notify_slot_update(SlotUpdate::Dead {
+                            slot: bank.slot(),
+                            parent: bank.parent_slot(),
+                            timestamp: timestamp(),
+                            err: format!("error: {:?}", err),
+                        });
                         // If the bank was corrupted, don't try to run the below logic to check if the
                         // bank is completed
                         continue;
@@ -1374,6 +1413,11 @@ impl ReplayStage {
                     did_complete_bank = true;
                     info!("bank frozen: {}", bank.slot());
                     bank.freeze();
+                    subscriptions.notify_slot_update(SlotUpdate::Frozen {
+                        slot: bank.slot(),
+                        parent: bank.parent_slot(),
+                        timestamp: timestamp(),
+                    });
                     heaviest_subtree_fork_choice
                         .add_new_leaf_slot(bank.slot(), Some(bank.parent_slot()));
                     if let Some(sender) = bank_notification_sender {
@@ -1384,15 +1428,19 @@ impl ReplayStage {
                             });
                     }
 
+                    Self::notify_slot_status(geyser_plugin_manager, &bank, SlotStatus::Processed);
+
                     Self::record_rewards(&bank, &rewards_recorder_sender);
                 } else {
-                    Self::mark_dead_slot(
-                        blockstore,
-                        bank_progress,
-                        bank.slot(),
-                        &BlockstoreProcessorError::InvalidBlock(BlockError::DuplicateBlock),
-                        true,
-                    );
+                    let err = BlockstoreProcessorError::InvalidBlock(BlockError::DuplicateBlock);
+                    Self::mark_dead_slot(blockstore, bank_progress, bank.slot(), &err, true);
+                    Self::notify_slot_status(geyser_plugin_manager, &bank, SlotStatus::Dead);
+                    subscriptions.notify_slot_update(SlotUpdate::Dead {
+                        slot: bank.slot(),
+                        parent: bank.parent_slot(),
+                        timestamp: timestamp(),
+                        err: format!("error: {:?}", err),
+                    });
                     warn!(
                         "{} duplicate shreds detected, not freezing bank {}",
                         my_pubkey,
@@ -1927,7 +1975,27 @@ impl ReplayStage {
         subscriptions: &Arc<RpcSubscriptions>,
     ) -> Bank {
         subscriptions.notify_slot(slot, parent.slot(), root_slot);
-        Bank::new_from_parent(parent, leader, slot)
+        let bank = Bank::new_from_parent(parent, leader, slot);
+        subscriptions.notify_slot_update(SlotUpdate::CreatedBank {
+            slot,
+            parent: parent.slot(),
+            timestamp: timestamp(),
+        });
+        bank
+    }
+
+    fn notify_slot_status(
+        geyser_plugin_manager: &Option<GeyserPluginManagerLock>,
+        bank: &Bank,
+        status: SlotStatus,
+    ) {
+        if let Some(geyser_plugin_manager) = geyser_plugin_manager {
+            geyser_plugin_manager.read().unwrap().notify_slot_status(
+                bank.slot(),
+                Some(bank.parent_slot()),
+                status,
+            );
+        }
     }
 
     fn record_rewards(bank: &Bank, rewards_recorder_sender: &Option<RewardsRecorderSender>) {
@@ -2707,6 +2775,8 @@ pub(crate) mod tests {
         let (replay_vote_sender, _replay_vote_receiver) = unbounded();
         let transaction_status_service = TransactionStatusService::new(
             transaction_status_receiver,
+            true,
+            None,
             blockstore,
             &Arc::new(AtomicBool::new(false)),
         );
@@ -6,7 +6,11 @@ use crate::{
     non_circulating_supply::calculate_non_circulating_supply,
     optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
     rpc_health::*,
-    send_transaction_service::{SendTransactionService, TransactionInfo},
+    rpc_rate_limiter::RpcRateLimitConfig,
+    send_transaction_service::{
+        SendTransactionQueueStatus, SendTransactionService, TransactionInfo,
+        MAX_TRANSACTION_QUEUE_SIZE,
+    },
     validator::ValidatorExit,
 };
 use bincode::{config::Options, serialize};
@@ -25,7 +29,8 @@ use solana_client::{
     rpc_custom_error::RpcCustomError,
     rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
     rpc_request::{
-        TokenAccountsFilter, DELINQUENT_VALIDATOR_SLOT_DISTANCE, MAX_GET_CONFIRMED_BLOCKS_RANGE,
+        TokenAccountsFilter, DELINQUENT_VALIDATOR_SLOT_DISTANCE,
+        MAX_GET_BLOCK_PRODUCTION_SLOT_RANGE, MAX_GET_CONFIRMED_BLOCKS_RANGE,
         MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT,
         MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE,
         MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS, MAX_MULTIPLE_ACCOUNTS, NUM_LARGEST_ACCOUNTS,
@@ -34,13 +39,16 @@ use solana_client::{
     rpc_response::*,
 };
 use solana_faucet::faucet::request_airdrop_transaction;
-use solana_ledger::{blockstore::Blockstore, blockstore_db::BlockstoreError, get_tmp_ledger_path};
+use solana_ledger::{
+    blockstore::Blockstore, blockstore_db::BlockstoreError, get_tmp_ledger_path,
+    leader_schedule_cache::LeaderScheduleCache,
+};
 use solana_metrics::inc_new_counter_info;
 use solana_perf::packet::PACKET_DATA_SIZE;
 use solana_runtime::{
     accounts::AccountAddressFilter,
-    accounts_index::{AccountIndex, IndexKey},
-    bank::Bank,
+    accounts_index::{AccountIndex, IndexKey, MemcmpIndex},
+    bank::{Bank, TransactionSimulationResult},
     bank_forks::{BankForks, SnapshotConfig},
     commitment::{BlockCommitmentArray, BlockCommitmentCache, CommitmentSlots},
     inline_spl_token_v2_0::{SPL_TOKEN_ACCOUNT_MINT_OFFSET, SPL_TOKEN_ACCOUNT_OWNER_OFFSET},
@@ -54,18 +62,19 @@ use solana_sdk::{
     epoch_info::EpochInfo,
     epoch_schedule::EpochSchedule,
     hash::Hash,
-    pubkey::Pubkey,
+    pubkey::{Pubkey, PUBKEY_BYTES},
     sanitize::Sanitize,
     signature::Signature,
     stake_history::StakeHistory,
     system_instruction,
     sysvar::stake_history,
-    transaction::{self, Transaction},
+    transaction::{self, Transaction, VersionedTransaction},
 };
 use solana_stake_program::stake_state::StakeState;
 use solana_transaction_status::{
-    EncodedConfirmedBlock, EncodedConfirmedTransaction, TransactionConfirmationStatus,
-    TransactionStatus, UiTransactionEncoding,
+    EncodedConfirmedTransaction, InnerInstructions, Reward, RewardType,
+    TransactionConfirmationStatus, TransactionDetails, TransactionStatus, UiConfirmedBlock,
+    UiTransactionEncoding,
 };
 use solana_vote_program::vote_state::{VoteState, MAX_LOCKOUT_HISTORY};
 use spl_token_v2_0::{
@@ -75,11 +84,12 @@ use spl_token_v2_0::{
 use std::{
     cmp::{max, min},
     collections::{HashMap, HashSet},
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
     str::FromStr,
     sync::{
         atomic::{AtomicBool, Ordering},
-        mpsc::{channel, Receiver, Sender},
+        mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
         Arc, Mutex, RwLock,
     },
     time::Duration,
@@ -116,9 +126,12 @@ pub struct JsonRpcConfig {
     pub enable_bigtable_ledger_storage: bool,
     pub enable_bigtable_ledger_upload: bool,
     pub max_multiple_accounts: Option<usize>,
+    pub max_program_accounts_response_bytes: Option<usize>,
+    pub rate_limit: RpcRateLimitConfig,
     pub account_indexes: HashSet<AccountIndex>,
     pub rpc_threads: usize,
     pub rpc_bigtable_timeout: Option<Duration>,
+    pub bigtable_ledger_local_storage: Option<PathBuf>,
 }
 
 #[derive(Clone)]
@@ -126,16 +139,19 @@ pub struct JsonRpcRequestProcessor {
     bank_forks: Arc<RwLock<BankForks>>,
     block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
     blockstore: Arc<Blockstore>,
+    leader_schedule_cache: Arc<LeaderScheduleCache>,
     config: JsonRpcConfig,
     snapshot_config: Option<SnapshotConfig>,
     validator_exit: Arc<RwLock<Option<ValidatorExit>>>,
     health: Arc<RpcHealth>,
     cluster_info: Arc<ClusterInfo>,
     genesis_hash: Hash,
-    transaction_sender: Arc<Mutex<Sender<TransactionInfo>>>,
+    transaction_sender: Arc<Mutex<SyncSender<TransactionInfo>>>,
+    send_transaction_queue_status: Arc<SendTransactionQueueStatus>,
     runtime: Arc<Runtime>,
     bigtable_ledger_storage: Option<solana_storage_bigtable::LedgerStorage>,
     optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
+    client_ip: Option<IpAddr>,
 }
 impl Metadata for JsonRpcRequestProcessor {}
 
@@ -204,6 +220,33 @@ impl JsonRpcRequestProcessor {
         })
     }
 
+    /// Select a bank by slot if one was requested, or else by commitment, and
+    /// ensure it is at least as recent as the requested minimum context slot
+    fn get_bank_with_config(&self, config: RpcContextConfig) -> Result<Arc<Bank>> {
+        let bank = if let Some(slot) = config.slot {
+            debug!("RPC using requested slot: {:?}", slot);
+            // A bank that is still being processed doesn't have a final state to read yet
+            self.bank_forks
+                .read()
+                .unwrap()
+                .get(slot)
+                .filter(|bank| bank.is_frozen())
+                .cloned()
+                .ok_or(RpcCustomError::BankNotAvailable { slot })?
+        } else {
+            self.bank(config.commitment)
+        };
+        if let Some(min_context_slot) = config.min_context_slot {
+            if bank.slot() < min_context_slot {
+                return Err(RpcCustomError::MinContextSlotNotReached {
+                    context_slot: bank.slot(),
+                }
+                .into());
+            }
+        }
+        Ok(bank)
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         config: JsonRpcConfig,
@@ -218,8 +261,9 @@ impl JsonRpcRequestProcessor {
         runtime: Arc<Runtime>,
         bigtable_ledger_storage: Option<solana_storage_bigtable::LedgerStorage>,
         optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
+        leader_schedule_cache: Arc<LeaderScheduleCache>,
     ) -> (Self, Receiver<TransactionInfo>) {
-        let (sender, receiver) = channel();
+        let (sender, receiver) = sync_channel(MAX_TRANSACTION_QUEUE_SIZE);
         (
             Self {
                 config,
@@ -227,14 +271,17 @@ impl JsonRpcRequestProcessor {
                 bank_forks,
                 block_commitment_cache,
                 blockstore,
+                leader_schedule_cache,
                 validator_exit,
                 health,
                 cluster_info,
                 genesis_hash,
                 transaction_sender: Arc::new(Mutex::new(sender)),
+                send_transaction_queue_status: Arc::default(),
                 runtime,
                 bigtable_ledger_storage,
                 optimistically_confirmed_bank,
+                client_ip: None,
             },
             receiver,
         )
@@ -251,8 +298,17 @@ impl JsonRpcRequestProcessor {
         let exit = Arc::new(AtomicBool::new(false));
         let cluster_info = Arc::new(ClusterInfo::default());
         let tpu_address = cluster_info.my_contact_info().tpu;
-        let (sender, receiver) = channel();
-        SendTransactionService::new(tpu_address, &bank_forks, None, receiver, 1000, 1);
+        let (sender, receiver) = sync_channel(MAX_TRANSACTION_QUEUE_SIZE);
+        let send_transaction_queue_status = Arc::<SendTransactionQueueStatus>::default();
+        SendTransactionService::new(
+            tpu_address,
+            &bank_forks,
+            None,
+            receiver,
+            send_transaction_queue_status.clone(),
+            1000,
+            1,
+        );
 
         Self {
             config: JsonRpcConfig::default(),
@@ -264,26 +320,38 @@ impl JsonRpcRequestProcessor {
                 CommitmentSlots::new_from_slot(bank.slot()),
             ))),
             blockstore,
+            leader_schedule_cache: Arc::new(LeaderScheduleCache::new_from_bank(bank)),
             validator_exit: create_validator_exit(&exit),
             health: Arc::new(RpcHealth::new(cluster_info.clone(), None, 0, exit.clone())),
             cluster_info,
             genesis_hash,
             transaction_sender: Arc::new(Mutex::new(sender)),
+            send_transaction_queue_status,
             runtime: Arc::new(Runtime::new().expect("Runtime")),
             bigtable_ledger_storage: None,
             optimistically_confirmed_bank: Arc::new(RwLock::new(OptimisticallyConfirmedBank {
                 bank: bank.clone(),
             })),
+            client_ip: None,
         }
     }
 
+    /// Tag the processor handling a request with the address of the client that made it
+    pub(crate) fn with_client_ip(self, client_ip: Option<IpAddr>) -> Self {
+        Self { client_ip, ..self }
+    }
+
+    pub(crate) fn client_ip(&self) -> Option<IpAddr> {
+        self.client_ip
+    }
+
     pub fn get_account_info(
         &self,
         pubkey: &Pubkey,
         config: Option<RpcAccountInfoConfig>,
     ) -> Result<RpcResponse<Option<UiAccount>>> {
         let config = config.unwrap_or_default();
-        let bank = self.bank(config.commitment);
+        let bank = self.get_bank_with_config(config.context_config())?;
         let encoding = config.encoding.unwrap_or(UiAccountEncoding::Binary);
         check_slice_and_encoding(&encoding, config.data_slice.is_some())?;
 
@@ -299,7 +367,7 @@ impl JsonRpcRequestProcessor {
         let mut accounts: Vec<Option<UiAccount>> = vec![];
 
         let config = config.unwrap_or_default();
-        let bank = self.bank(config.commitment);
+        let bank = self.get_bank_with_config(config.context_config())?;
         let encoding = config.encoding.unwrap_or(UiAccountEncoding::Base64);
         check_slice_and_encoding(&encoding, config.data_slice.is_some())?;
 
@@ -325,37 +393,113 @@ impl JsonRpcRequestProcessor {
         program_id: &Pubkey,
         config: Option<RpcAccountInfoConfig>,
         filters: Vec<RpcFilterType>,
-    ) -> Result<Vec<RpcKeyedAccount>> {
+        with_context: bool,
+        limit: Option<usize>,
+        cursor: Option<String>,
+    ) -> Result<RpcProgramAccounts> {
         let config = config.unwrap_or_default();
-        let bank = self.bank(config.commitment);
+        // The cursor is the address of the last account of the previous page
+        let cursor = match (cursor, limit) {
+            (_, Some(0)) => {
+                return Err(Error::invalid_params("limit must be greater than 0"));
+            }
+            (Some(_), None) => {
+                return Err(Error::invalid_params("cursor requires a limit"));
+            }
+            (Some(cursor), Some(_)) => Some(
+                cursor
+                    .parse::<Pubkey>()
+                    .map_err(|_| Error::invalid_params(format!("Invalid cursor: {}", cursor)))?,
+            ),
+            (None, _) => None,
+        };
+        // Every page is read from the bank selected by the request, rather than the bank of the
+        // first page, which the node may have pruned by the time the next page is requested
+        let bank = self.get_bank_with_config(config.context_config())?;
         let encoding = config.encoding.unwrap_or(UiAccountEncoding::Binary);
         let data_slice_config = config.data_slice;
         check_slice_and_encoding(&encoding, data_slice_config.is_some())?;
-        let keyed_accounts = {
-            if let Some(owner) = get_spl_token_owner_filter(program_id, &filters) {
-                self.get_filtered_spl_token_accounts_by_owner(&bank, &owner, filters)
-            } else {
-                self.get_filtered_program_accounts(&bank, program_id, filters)
+        let mut next_cursor = None;
+        let keyed_accounts = if let Some(limit) = limit {
+            // Read one account past the page to tell whether another page follows
+            let mut keyed_accounts = self.get_filtered_program_accounts_page(
+                &bank,
+                program_id,
+                filters,
+                cursor.as_ref(),
+                limit + 1,
+            );
+            if keyed_accounts.len() > limit {
+                keyed_accounts.truncate(limit);
+                next_cursor = keyed_accounts.last().map(|(pubkey, _)| pubkey.to_string());
             }
+            keyed_accounts
+        } else if let Some(owner) = get_spl_token_owner_filter(program_id, &filters) {
+            self.get_filtered_spl_token_accounts_by_owner(&bank, &owner, filters)
+        } else {
+            self.get_filtered_program_accounts(&bank, program_id, filters)
         };
-        let result =
+        let slot = bank.slot();
+        let accounts: Box<dyn Iterator<Item = RpcKeyedAccount>> =
             if program_id == &spl_token_id_v2_0() && encoding == UiAccountEncoding::JsonParsed {
-                get_parsed_token_accounts(bank, keyed_accounts.into_iter()).collect()
+                Box::new(get_parsed_token_accounts(bank, keyed_accounts.into_iter()))
             } else {
-                keyed_accounts
-                    .into_iter()
-                    .map(|(pubkey, account)| RpcKeyedAccount {
-                        pubkey: pubkey.to_string(),
-                        account: UiAccount::encode(
-                            &pubkey,
-                            account,
-                            encoding.clone(),
-                            None,
-                            data_slice_config,
-                        ),
-                    })
-                    .collect()
+                Box::new(
+                    keyed_accounts
+                        .into_iter()
+                        .map(|(pubkey, account)| RpcKeyedAccount {
+                            pubkey: pubkey.to_string(),
+                            account: UiAccount::encode(
+                                &pubkey,
+                                account,
+                                encoding.clone(),
+                                None,
+                                data_slice_config,
+                            ),
+                        }),
+                )
             };
+        let accounts = self.collect_within_response_limit(accounts)?;
+        let context = RpcResponseContext { slot };
+        Ok(if limit.is_some() {
+            RpcProgramAccounts::Page(RpcResponse {
+                context,
+                value: RpcKeyedAccountsPage {
+                    accounts,
+                    cursor: next_cursor,
+                },
+            })
+        } else if with_context {
+            RpcProgramAccounts::WithContext(RpcResponse {
+                context,
+                value: accounts,
+            })
+        } else {
+            RpcProgramAccounts::Accounts(accounts)
+        })
+    }
+
+    /// Collect encoded accounts, failing as soon as their serialized size exceeds
+    /// `max_program_accounts_response_bytes`
+    fn collect_within_response_limit(
+        &self,
+        accounts: impl Iterator<Item = RpcKeyedAccount>,
+    ) -> Result<Vec<RpcKeyedAccount>> {
+        let max_response_bytes = match self.config.max_program_accounts_response_bytes {
+            Some(max_response_bytes) => max_response_bytes,
+            None => return Ok(accounts.collect()),
+        };
+        let mut response_bytes = 0;
+        let mut result = vec![];
+        for account in accounts {
+            response_bytes += serde_json::to_vec(&account)
+                .map_err(|_| Error::internal_error())?
+                .len();
+            if response_bytes > max_response_bytes {
+                return Err(RpcCustomError::ResponseTooLarge { max_response_bytes }.into());
+            }
+            result.push(account);
+        }
         Ok(result)
     }
 
@@ -380,6 +524,86 @@ impl JsonRpcRequestProcessor {
         }
     }
 
+    pub fn get_inflation_reward(
+        &self,
+        addresses: Vec<Pubkey>,
+        config: Option<RpcEpochConfig>,
+    ) -> Result<Vec<Option<RpcInflationReward>>> {
+        let config = config.unwrap_or_default();
+        let bank = self.bank(config.commitment);
+        let epoch = config
+            .epoch
+            .unwrap_or_else(|| bank.epoch().saturating_sub(1));
+
+        // Rewards for this epoch are found in the first confirmed block of the next epoch
+        let first_slot_in_epoch = bank
+            .epoch_schedule()
+            .get_first_slot_in_epoch(epoch.saturating_add(1));
+        if self.bigtable_ledger_storage.is_none() {
+            let first_available_block = self
+                .blockstore
+                .get_first_available_block()
+                .unwrap_or_default();
+            if first_slot_in_epoch < first_available_block {
+                return Err(RpcCustomError::BlockCleanedUp {
+                    slot: first_slot_in_epoch,
+                    first_available_block,
+                }
+                .into());
+            }
+        }
+
+        let first_confirmed_block_in_epoch = *self
+            .get_confirmed_blocks_with_limit(first_slot_in_epoch, 1)?
+            .get(0)
+            .ok_or(RpcCustomError::BlockNotAvailable {
+                slot: first_slot_in_epoch,
+            })?;
+        let first_confirmed_block = self
+            .get_confirmed_block(
+                first_confirmed_block_in_epoch,
+                Some(
+                    RpcConfirmedBlockConfig {
+                        encoding: Some(UiTransactionEncoding::Base64),
+                        transaction_details: Some(TransactionDetails::None),
+                        rewards: Some(true),
+                    }
+                    .into(),
+                ),
+            )?
+            .ok_or(RpcCustomError::BlockNotAvailable {
+                slot: first_confirmed_block_in_epoch,
+            })?;
+
+        let mut rewards: HashMap<String, Reward> = first_confirmed_block
+            .rewards
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|reward| {
+                matches!(
+                    reward.reward_type,
+                    Some(RewardType::Staking) | Some(RewardType::Voting)
+                )
+            })
+            .map(|reward| (reward.pubkey.clone(), reward))
+            .collect();
+
+        Ok(addresses
+            .iter()
+            .map(|address| {
+                rewards
+                    .remove(&address.to_string())
+                    .map(|reward| RpcInflationReward {
+                        epoch,
+                        effective_slot: first_confirmed_block_in_epoch,
+                        amount: reward.lamports.abs() as u64,
+                        post_balance: reward.post_balance,
+                        commission: reward.commission,
+                    })
+            })
+            .collect())
+    }
+
     pub fn get_epoch_schedule(&self) -> EpochSchedule {
         // Since epoch schedule data comes from the genesis config, any commitment level should be
         // fine
@@ -390,10 +614,10 @@ impl JsonRpcRequestProcessor {
     pub fn get_balance(
         &self,
         pubkey: &Pubkey,
-        commitment: Option<CommitmentConfig>,
-    ) -> RpcResponse<u64> {
-        let bank = self.bank(commitment);
-        new_response(&bank, bank.get_balance(pubkey))
+        config: Option<RpcContextConfig>,
+    ) -> Result<RpcResponse<u64>> {
+        let bank = self.get_bank_with_config(config.unwrap_or_default())?;
+        Ok(new_response(&bank, bank.get_balance(pubkey)))
     }
 
     fn get_recent_blockhash(
@@ -692,9 +916,14 @@ impl JsonRpcRequestProcessor {
     pub fn get_confirmed_block(
         &self,
         slot: Slot,
-        encoding: Option<UiTransactionEncoding>,
-    ) -> Result<Option<EncodedConfirmedBlock>> {
-        let encoding = encoding.unwrap_or(UiTransactionEncoding::Json);
+        config: Option<RpcEncodingConfigWrapper<RpcConfirmedBlockConfig>>,
+    ) -> Result<Option<UiConfirmedBlock>> {
+        let config = config
+            .map(|config| config.convert_to_current())
+            .unwrap_or_default();
+        let encoding = config.encoding.unwrap_or(UiTransactionEncoding::Json);
+        let transaction_details = config.transaction_details.unwrap_or_default();
+        let show_rewards = config.rewards.unwrap_or(true);
         if self.config.enable_rpc_transaction_history
             && slot
                 <= self
@@ -711,15 +940,15 @@ impl JsonRpcRequestProcessor {
                         .runtime
                         .block_on(bigtable_ledger_storage.get_confirmed_block(slot));
                     self.check_bigtable_result(&bigtable_result)?;
-                    return Ok(bigtable_result
-                        .ok()
-                        .map(|confirmed_block| confirmed_block.encode(encoding)));
+                    return Ok(bigtable_result.ok().map(|confirmed_block| {
+                        confirmed_block.configure(encoding, transaction_details, show_rewards)
+                    }));
                 }
             }
             self.check_slot_cleaned_up(&result, slot)?;
-            Ok(result
-                .ok()
-                .map(|confirmed_block| confirmed_block.encode(encoding)))
+            Ok(result.ok().map(|confirmed_block| {
+                confirmed_block.configure(encoding, transaction_details, show_rewards)
+            }))
         } else {
             Err(RpcCustomError::BlockNotAvailable { slot }.into())
         }
@@ -812,6 +1041,103 @@ impl JsonRpcRequestProcessor {
             .collect())
     }
 
+    pub fn get_block_production(
+        &self,
+        config: Option<RpcBlockProductionConfig>,
+    ) -> Result<RpcResponse<RpcBlockProduction>> {
+        let config = config.unwrap_or_default();
+        let bank = self.bank(config.commitment);
+        let identity = config.identity.map(verify_pubkey).transpose()?;
+
+        let lowest_blockstore_slot = self.blockstore.lowest_slot();
+        let (first_slot, last_slot) = match config.range {
+            Some(range) => {
+                let requested_last_slot = range.last_slot.unwrap_or_else(|| bank.slot());
+                if requested_last_slot.saturating_sub(range.first_slot)
+                    >= MAX_GET_BLOCK_PRODUCTION_SLOT_RANGE
+                {
+                    return Err(Error::invalid_params(format!(
+                        "Slot range too large; max {}",
+                        MAX_GET_BLOCK_PRODUCTION_SLOT_RANGE
+                    )));
+                }
+                let last_slot = min(requested_last_slot, bank.slot());
+                if last_slot < range.first_slot {
+                    return Err(Error::invalid_params(format!(
+                        "lastSlot, {}, cannot be less than firstSlot, {}",
+                        last_slot, range.first_slot
+                    )));
+                }
+                if range.first_slot < lowest_blockstore_slot {
+                    return Err(Error::invalid_params(format!(
+                        "Ledger data not available for slot {}; minimum ledger slot is {}",
+                        range.first_slot, lowest_blockstore_slot
+                    )));
+                }
+                (range.first_slot, last_slot)
+            }
+            None => {
+                // The current epoch, or as much of it as the blockstore holds
+                let first_slot_in_epoch =
+                    bank.epoch_schedule().get_first_slot_in_epoch(bank.epoch());
+                (
+                    max(first_slot_in_epoch, lowest_blockstore_slot),
+                    bank.slot(),
+                )
+            }
+        };
+
+        // A block was produced in a slot if it is rooted, or, past the root, if it is an
+        // ancestor of `bank`
+        let mut produced_slots: HashSet<Slot> = bank
+            .parents()
+            .iter()
+            .map(|bank| bank.slot())
+            .chain(std::iter::once(bank.slot()))
+            .collect();
+        produced_slots.extend(
+            self.blockstore
+                .rooted_slot_iterator(first_slot)
+                .map_err(|_| Error::internal_error())?
+                .take_while(|&slot| slot <= last_slot),
+        );
+
+        let mut block_production: HashMap<Pubkey, (usize, usize)> = HashMap::new();
+        for slot in first_slot..=last_slot {
+            let leader = self
+                .leader_schedule_cache
+                .slot_leader_at(slot, Some(&bank))
+                .ok_or_else(|| {
+                    Error::invalid_params(format!(
+                        "Invalid slot range: leader schedule for epoch {} is unavailable",
+                        bank.epoch_schedule().get_epoch(slot)
+                    ))
+                })?;
+            if identity.map_or(false, |identity| identity != leader) {
+                continue;
+            }
+            let (leader_slots, blocks_produced) = block_production.entry(leader).or_default();
+            *leader_slots += 1;
+            if produced_slots.contains(&slot) {
+                *blocks_produced += 1;
+            }
+        }
+
+        Ok(new_response(
+            &bank,
+            RpcBlockProduction {
+                by_identity: block_production
+                    .into_iter()
+                    .map(|(leader, production)| (leader.to_string(), production))
+                    .collect(),
+                range: RpcBlockProductionRange {
+                    first_slot,
+                    last_slot,
+                },
+            },
+        ))
+    }
+
     pub fn get_block_time(&self, slot: Slot) -> Result<Option<UnixTimestamp>> {
         if slot
             <= self
@@ -866,6 +1192,23 @@ impl JsonRpcRequestProcessor {
         Some(status)
     }
 
+    pub(crate) fn send_transaction_queue_status(&self) -> Arc<SendTransactionQueueStatus> {
+        self.send_transaction_queue_status.clone()
+    }
+
+    pub fn get_transaction_retry_status(
+        &self,
+        signature: &Signature,
+    ) -> Option<RpcTransactionRetryStatus> {
+        self.send_transaction_queue_status
+            .get(signature)
+            .map(|status| RpcTransactionRetryStatus {
+                retries: status.retries,
+                max_retries: status.max_retries,
+                last_valid_slot: status.last_valid_slot,
+            })
+    }
+
     pub fn get_signature_statuses(
         &self,
         signatures: Vec<Signature>,
@@ -1163,9 +1506,9 @@ impl JsonRpcRequestProcessor {
     pub fn get_token_account_balance(
         &self,
         pubkey: &Pubkey,
-        commitment: Option<CommitmentConfig>,
+        config: Option<RpcContextConfig>,
     ) -> Result<RpcResponse<UiTokenAmount>> {
-        let bank = self.bank(commitment);
+        let bank = self.get_bank_with_config(config.unwrap_or_default())?;
         let account = bank.get_account(pubkey).ok_or_else(|| {
             Error::invalid_params("Invalid param: could not find account".to_string())
         })?;
@@ -1188,9 +1531,9 @@ impl JsonRpcRequestProcessor {
     pub fn get_token_supply(
         &self,
         mint: &Pubkey,
-        commitment: Option<CommitmentConfig>,
+        config: Option<RpcContextConfig>,
     ) -> Result<RpcResponse<UiTokenAmount>> {
-        let bank = self.bank(commitment);
+        let bank = self.get_bank_with_config(config.unwrap_or_default())?;
         let mint_account = bank.get_account(mint).ok_or_else(|| {
             Error::invalid_params("Invalid param: could not find account".to_string())
         })?;
@@ -1210,9 +1553,9 @@ impl JsonRpcRequestProcessor {
     pub fn get_token_largest_accounts(
         &self,
         mint: &Pubkey,
-        commitment: Option<CommitmentConfig>,
+        config: Option<RpcContextConfig>,
     ) -> Result<RpcResponse<Vec<RpcTokenAccountBalance>>> {
-        let bank = self.bank(commitment);
+        let bank = self.get_bank_with_config(config.unwrap_or_default())?;
         let (mint_owner, decimals) = get_mint_owner_and_decimals(&bank, mint)?;
         if mint_owner != spl_token_id_v2_0() {
             return Err(Error::invalid_params(
@@ -1252,7 +1595,7 @@ impl JsonRpcRequestProcessor {
         config: Option<RpcAccountInfoConfig>,
     ) -> Result<RpcResponse<Vec<RpcKeyedAccount>>> {
         let config = config.unwrap_or_default();
-        let bank = self.bank(config.commitment);
+        let bank = self.get_bank_with_config(config.context_config())?;
         let encoding = config.encoding.unwrap_or(UiAccountEncoding::Binary);
         let data_slice_config = config.data_slice;
         check_slice_and_encoding(&encoding, data_slice_config.is_some())?;
@@ -1296,7 +1639,7 @@ impl JsonRpcRequestProcessor {
         config: Option<RpcAccountInfoConfig>,
     ) -> Result<RpcResponse<Vec<RpcKeyedAccount>>> {
         let config = config.unwrap_or_default();
-        let bank = self.bank(config.commitment);
+        let bank = self.get_bank_with_config(config.context_config())?;
         let encoding = config.encoding.unwrap_or(UiAccountEncoding::Binary);
         let data_slice_config = config.data_slice;
         check_slice_and_encoding(&encoding, data_slice_config.is_some())?;
@@ -1361,7 +1704,13 @@ impl JsonRpcRequestProcessor {
                 RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data),
             })
         };
-        if self
+        if let Some(index_key) = self.find_memcmp_index_key(program_id, &filters) {
+            bank.get_filtered_indexed_accounts(&index_key, |account| {
+                // As with the program-id account index below, stale entries are filtered out by
+                // re-checking the owner and filters
+                account.owner == *program_id && filter_closure(account)
+            })
+        } else if self
             .config
             .account_indexes
             .contains(&AccountIndex::ProgramId)
@@ -1379,6 +1728,95 @@ impl JsonRpcRequestProcessor {
         }
     }
 
+    /// Up to `limit` of the accounts `get_program_accounts` returns, in address order, starting
+    /// after `after`.  The keys of a secondary index are sorted and seeked to `after`, and the
+    /// accounts index is otherwise range-scanned from `after`; either way accounts are only
+    /// loaded until the page is full.
+    fn get_filtered_program_accounts_page(
+        &self,
+        bank: &Arc<Bank>,
+        program_id: &Pubkey,
+        mut filters: Vec<RpcFilterType>,
+        after: Option<&Pubkey>,
+        limit: usize,
+    ) -> Vec<(Pubkey, Account)> {
+        let index_key = match get_spl_token_owner_filter(program_id, &filters) {
+            Some(owner)
+                if self
+                    .config
+                    .account_indexes
+                    .contains(&AccountIndex::SplTokenOwner) =>
+            {
+                // As in `get_filtered_spl_token_accounts_by_owner`, stale entries of the by-owner
+                // index are filtered out by re-checking the Token Account state and Owner address
+                filters.push(RpcFilterType::DataSize(
+                    TokenAccount::get_packed_len() as u64
+                ));
+                filters.push(RpcFilterType::Memcmp(Memcmp {
+                    offset: SPL_TOKEN_ACCOUNT_OWNER_OFFSET,
+                    bytes: MemcmpEncodedBytes::Binary(owner.to_string()),
+                    encoding: None,
+                }));
+                Some(IndexKey::SplTokenOwner(owner))
+            }
+            _ => self
+                .find_memcmp_index_key(program_id, &filters)
+                .or_else(|| {
+                    if self
+                        .config
+                        .account_indexes
+                        .contains(&AccountIndex::ProgramId)
+                    {
+                        Some(IndexKey::ProgramId(*program_id))
+                    } else {
+                        None
+                    }
+                }),
+        };
+        // Stale entries of the account indexes are filtered out by re-checking the owner
+        let filter_closure = |account: &Account| {
+            account.owner == *program_id
+                && filters.iter().all(|filter_type| match filter_type {
+                    RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
+                    RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data),
+                })
+        };
+        if let Some(index_key) = index_key {
+            bank.get_filtered_indexed_accounts_from(&index_key, after, limit, filter_closure)
+        } else {
+            bank.get_filtered_program_accounts_from(program_id, after, limit, filter_closure)
+        }
+    }
+
+    /// Find a `Memcmp` filter covered by one of the configured memcmp account indexes for
+    /// `program_id`, and return the index key it selects
+    fn find_memcmp_index_key(
+        &self,
+        program_id: &Pubkey,
+        filters: &[RpcFilterType],
+    ) -> Option<IndexKey> {
+        filters.iter().find_map(|filter_type| match filter_type {
+            RpcFilterType::Memcmp(compare) => {
+                let memcmp_index = MemcmpIndex {
+                    program_id: *program_id,
+                    offset: compare.offset,
+                };
+                if !self
+                    .config
+                    .account_indexes
+                    .contains(&AccountIndex::Memcmp(memcmp_index))
+                {
+                    return None;
+                }
+                // Filters on a prefix of the key can't use the index
+                let bytes = compare.bytes()?;
+                let key = bytes.get(..PUBKEY_BYTES)?;
+                Some(IndexKey::Memcmp(memcmp_index, Pubkey::new(key)))
+            }
+            RpcFilterType::DataSize(_) => None,
+        })
+    }
+
     /// Get an iterator of spl-token accounts by owner address
     fn get_filtered_spl_token_accounts_by_owner(
         &self,
@@ -1459,7 +1897,7 @@ impl JsonRpcRequestProcessor {
     }
 }
 
-fn verify_transaction(transaction: &Transaction) -> Result<()> {
+fn verify_transaction(transaction: &VersionedTransaction) -> Result<()> {
     if transaction.verify().is_err() {
         return Err(RpcCustomError::TransactionSignatureVerificationFailure.into());
     }
@@ -1471,7 +1909,15 @@ fn verify_transaction(transaction: &Transaction) -> Result<()> {
     Ok(())
 }
 
-fn verify_filter(input: &RpcFilterType) -> Result<()> {
+/// The legacy form of `transaction`, with the addresses of a versioned message loaded from the
+/// lookup tables of `bank`
+fn resolve_transaction(bank: &Bank, transaction: &VersionedTransaction) -> Result<Transaction> {
+    bank.resolve_transaction(transaction)
+        .map(|(transaction, _)| transaction)
+        .map_err(|err| Error::invalid_params(format!("invalid transaction: {}", err)))
+}
+
+pub(crate) fn verify_filter(input: &RpcFilterType) -> Result<()> {
     input
         .verify()
         .map_err(|e| Error::invalid_params(format!("Invalid param: {:?}", e)))
@@ -1504,7 +1950,7 @@ fn verify_token_account_filter(
     }
 }
 
-fn check_slice_and_encoding(encoding: &UiAccountEncoding, data_slice_is_some: bool) -> Result<()> {
+pub(crate) fn check_slice_and_encoding(encoding: &UiAccountEncoding, data_slice_is_some: bool) -> Result<()> {
     match encoding {
         UiAccountEncoding::JsonParsed => {
             if data_slice_is_some {
@@ -1533,26 +1979,35 @@ fn get_encoded_account(
     encoding: UiAccountEncoding,
     data_slice: Option<UiDataSliceConfig>,
 ) -> Result<Option<UiAccount>> {
-    let mut response = None;
-    if let Some(account) = bank.get_account(pubkey) {
-        if account.owner == spl_token_id_v2_0() && encoding == UiAccountEncoding::JsonParsed {
-            response = Some(get_parsed_token_account(bank.clone(), pubkey, account));
-        } else if (encoding == UiAccountEncoding::Binary || encoding == UiAccountEncoding::Base58)
-            && account.data.len() > 128
-        {
-            let message = "Encoded binary (base 58) data should be less than 128 bytes, please use Base64 encoding.".to_string();
-            return Err(error::Error {
-                code: error::ErrorCode::InvalidRequest,
-                message,
-                data: None,
-            });
-        } else {
-            response = Some(UiAccount::encode(
-                pubkey, account, encoding, None, data_slice,
-            ));
-        }
+    match bank.get_account(pubkey) {
+        Some(account) => encode_account(bank, pubkey, account, encoding, data_slice).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn encode_account(
+    bank: &Arc<Bank>,
+    pubkey: &Pubkey,
+    account: Account,
+    encoding: UiAccountEncoding,
+    data_slice: Option<UiDataSliceConfig>,
+) -> Result<UiAccount> {
+    if account.owner == spl_token_id_v2_0() && encoding == UiAccountEncoding::JsonParsed {
+        Ok(get_parsed_token_account(bank.clone(), pubkey, account))
+    } else if (encoding == UiAccountEncoding::Binary || encoding == UiAccountEncoding::Base58)
+        && account.data.len() > 128
+    {
+        let message = "Encoded binary (base 58) data should be less than 128 bytes, please use Base64 encoding.".to_string();
+        Err(error::Error {
+            code: error::ErrorCode::InvalidRequest,
+            message,
+            data: None,
+        })
+    } else {
+        Ok(UiAccount::encode(
+            pubkey, account, encoding, None, data_slice,
+        ))
     }
-    Ok(response)
 }
 
 fn get_spl_token_owner_filter(program_id: &Pubkey, filters: &[RpcFilterType]) -> Option<Pubkey> {
@@ -1742,7 +2197,7 @@ pub trait RpcSol {
         meta: Self::Metadata,
         program_id_str: String,
         config: Option<RpcProgramAccountsConfig>,
-    ) -> Result<Vec<RpcKeyedAccount>>;
+    ) -> Result<RpcProgramAccounts>;
 
     #[rpc(meta, name = "getMinimumBalanceForRentExemption")]
     fn get_minimum_balance_for_rent_exemption(
@@ -1762,6 +2217,14 @@ pub trait RpcSol {
     #[rpc(meta, name = "getInflationRate")]
     fn get_inflation_rate(&self, meta: Self::Metadata) -> Result<RpcInflationRate>;
 
+    #[rpc(meta, name = "getInflationReward")]
+    fn get_inflation_reward(
+        &self,
+        meta: Self::Metadata,
+        address_strs: Vec<String>,
+        config: Option<RpcEpochConfig>,
+    ) -> Result<Vec<Option<RpcInflationReward>>>;
+
     #[rpc(meta, name = "getEpochSchedule")]
     fn get_epoch_schedule(&self, meta: Self::Metadata) -> Result<EpochSchedule>;
 
@@ -1770,7 +2233,7 @@ pub trait RpcSol {
         &self,
         meta: Self::Metadata,
         pubkey_str: String,
-        commitment: Option<CommitmentConfig>,
+        config: Option<RpcContextConfig>,
     ) -> Result<RpcResponse<u64>>;
 
     #[rpc(meta, name = "getClusterNodes")]
@@ -1797,6 +2260,13 @@ pub trait RpcSol {
         block: Slot,
     ) -> Result<RpcBlockCommitment<BlockCommitmentArray>>;
 
+    #[rpc(meta, name = "getBlockProduction")]
+    fn get_block_production(
+        &self,
+        meta: Self::Metadata,
+        config: Option<RpcBlockProductionConfig>,
+    ) -> Result<RpcResponse<RpcBlockProduction>>;
+
     #[rpc(meta, name = "getGenesisHash")]
     fn get_genesis_hash(&self, meta: Self::Metadata) -> Result<String>;
 
@@ -1850,6 +2320,13 @@ pub trait RpcSol {
         config: Option<RpcSignatureStatusConfig>,
     ) -> Result<RpcResponse<Vec<Option<TransactionStatus>>>>;
 
+    #[rpc(meta, name = "getTransactionRetryStatus")]
+    fn get_transaction_retry_status(
+        &self,
+        meta: Self::Metadata,
+        signature_str: String,
+    ) -> Result<Option<RpcTransactionRetryStatus>>;
+
     #[rpc(meta, name = "getSlot")]
     fn get_slot(&self, meta: Self::Metadata, commitment: Option<CommitmentConfig>) -> Result<Slot>;
 
@@ -1941,8 +2418,8 @@ pub trait RpcSol {
         &self,
         meta: Self::Metadata,
         slot: Slot,
-        encoding: Option<UiTransactionEncoding>,
-    ) -> Result<Option<EncodedConfirmedBlock>>;
+        config: Option<RpcEncodingConfigWrapper<RpcConfirmedBlockConfig>>,
+    ) -> Result<Option<UiConfirmedBlock>>;
 
     #[rpc(meta, name = "getBlockTime")]
     fn get_block_time(&self, meta: Self::Metadata, slot: Slot) -> Result<Option<UnixTimestamp>>;
@@ -2008,7 +2485,7 @@ pub trait RpcSol {
         &self,
         meta: Self::Metadata,
         pubkey_str: String,
-        commitment: Option<CommitmentConfig>,
+        config: Option<RpcContextConfig>,
     ) -> Result<RpcResponse<UiTokenAmount>>;
 
     #[rpc(meta, name = "getTokenSupply")]
@@ -2016,7 +2493,7 @@ pub trait RpcSol {
         &self,
         meta: Self::Metadata,
         mint_str: String,
-        commitment: Option<CommitmentConfig>,
+        config: Option<RpcContextConfig>,
     ) -> Result<RpcResponse<UiTokenAmount>>;
 
     #[rpc(meta, name = "getTokenLargestAccounts")]
@@ -2024,7 +2501,7 @@ pub trait RpcSol {
         &self,
         meta: Self::Metadata,
         mint_str: String,
-        commitment: Option<CommitmentConfig>,
+        config: Option<RpcContextConfig>,
     ) -> Result<RpcResponse<Vec<RpcTokenAccountBalance>>>;
 
     #[rpc(meta, name = "getTokenAccountsByOwner")]
@@ -2052,22 +2529,37 @@ fn _send_transaction(
     wire_transaction: Vec<u8>,
     last_valid_slot: Slot,
     durable_nonce_info: Option<(Pubkey, Hash)>,
+    max_retries: Option<usize>,
 ) -> Result<String> {
     if transaction.signatures.is_empty() {
         return Err(RpcCustomError::TransactionSignatureVerificationFailure.into());
     }
+    if meta.send_transaction_queue_status.is_full() {
+        inc_new_counter_info!("rpc-send-tx_queue-full", 1);
+        return Err(RpcCustomError::SendTransactionQueueFull.into());
+    }
     let signature = transaction.signatures[0];
     let transaction_info = TransactionInfo::new(
         signature,
         wire_transaction,
         last_valid_slot,
         durable_nonce_info,
+        max_retries,
     );
-    meta.transaction_sender
+    // Transactions not yet picked up by the retry thread count towards the queue too
+    match meta
+        .transaction_sender
         .lock()
         .unwrap()
-        .send(transaction_info)
-        .unwrap_or_else(|err| warn!("Failed to enqueue transaction: {}", err));
+        .try_send(transaction_info)
+    {
+        Ok(()) => {}
+        Err(TrySendError::Full(_)) => {
+            inc_new_counter_info!("rpc-send-tx_queue-full", 1);
+            return Err(RpcCustomError::SendTransactionQueueFull.into());
+        }
+        Err(TrySendError::Disconnected(_)) => warn!("Failed to enqueue transaction: disconnected"),
+    }
 
     Ok(signature.to_string())
 }
@@ -2147,24 +2639,27 @@ impl RpcSol for RpcSolImpl {
         meta: Self::Metadata,
         program_id_str: String,
         config: Option<RpcProgramAccountsConfig>,
-    ) -> Result<Vec<RpcKeyedAccount>> {
+    ) -> Result<RpcProgramAccounts> {
         debug!(
             "get_program_accounts rpc request received: {:?}",
             program_id_str
         );
         let program_id = verify_pubkey(program_id_str)?;
-        let (config, filters) = if let Some(config) = config {
+        let (config, filters, with_context, limit, cursor) = if let Some(config) = config {
             (
                 Some(config.account_config),
                 config.filters.unwrap_or_default(),
+                config.with_context.unwrap_or_default(),
+                config.limit,
+                config.cursor,
             )
         } else {
-            (None, vec![])
+            (None, vec![], false, None, None)
         };
         for filter in &filters {
             verify_filter(filter)?;
         }
-        meta.get_program_accounts(&program_id, config, filters)
+        meta.get_program_accounts(&program_id, config, filters, with_context, limit, cursor)
     }
 
     fn get_inflation_governor(
@@ -2181,6 +2676,34 @@ impl RpcSol for RpcSolImpl {
         Ok(meta.get_inflation_rate())
     }
 
+    fn get_inflation_reward(
+        &self,
+        meta: Self::Metadata,
+        address_strs: Vec<String>,
+        config: Option<RpcEpochConfig>,
+    ) -> Result<Vec<Option<RpcInflationReward>>> {
+        debug!(
+            "get_inflation_reward rpc request received: {:?}",
+            address_strs.len()
+        );
+
+        let max_multiple_accounts = meta
+            .config
+            .max_multiple_accounts
+            .unwrap_or(MAX_MULTIPLE_ACCOUNTS);
+        if address_strs.len() > max_multiple_accounts {
+            return Err(Error::invalid_params(format!(
+                "Too many inputs provided; max {}",
+                max_multiple_accounts
+            )));
+        }
+        let addresses = address_strs
+            .into_iter()
+            .map(verify_pubkey)
+            .collect::<Result<Vec<_>>>()?;
+        meta.get_inflation_reward(addresses, config)
+    }
+
     fn get_epoch_schedule(&self, meta: Self::Metadata) -> Result<EpochSchedule> {
         debug!("get_epoch_schedule rpc request received");
         Ok(meta.get_epoch_schedule())
@@ -2190,11 +2713,11 @@ impl RpcSol for RpcSolImpl {
         &self,
         meta: Self::Metadata,
         pubkey_str: String,
-        commitment: Option<CommitmentConfig>,
+        config: Option<RpcContextConfig>,
     ) -> Result<RpcResponse<u64>> {
         debug!("get_balance rpc request received: {:?}", pubkey_str);
         let pubkey = verify_pubkey(pubkey_str)?;
-        Ok(meta.get_balance(&pubkey, commitment))
+        meta.get_balance(&pubkey, config)
     }
 
     fn get_recent_performance_samples(
@@ -2288,6 +2811,15 @@ impl RpcSol for RpcSolImpl {
         Ok(meta.get_block_commitment(block))
     }
 
+    fn get_block_production(
+        &self,
+        meta: Self::Metadata,
+        config: Option<RpcBlockProductionConfig>,
+    ) -> Result<RpcResponse<RpcBlockProduction>> {
+        debug!("get_block_production rpc request received");
+        meta.get_block_production(config)
+    }
+
     fn get_genesis_hash(&self, meta: Self::Metadata) -> Result<String> {
         debug!("get_genesis_hash rpc request received");
         Ok(meta.genesis_hash.to_string())
@@ -2432,6 +2964,19 @@ impl RpcSol for RpcSolImpl {
         meta.get_signature_statuses(signatures, config)
     }
 
+    fn get_transaction_retry_status(
+        &self,
+        meta: Self::Metadata,
+        signature_str: String,
+    ) -> Result<Option<RpcTransactionRetryStatus>> {
+        debug!(
+            "get_transaction_retry_status rpc request received: {:?}",
+            signature_str
+        );
+        let signature = verify_signature(&signature_str)?;
+        Ok(meta.get_transaction_retry_status(&signature))
+    }
+
     fn get_slot(&self, meta: Self::Metadata, commitment: Option<CommitmentConfig>) -> Result<Slot> {
         debug!("get_slot rpc request received");
         Ok(meta.get_slot(commitment))
@@ -2512,7 +3057,14 @@ impl RpcSol for RpcSolImpl {
             Error::internal_error()
         })?;
 
-        _send_transaction(meta, transaction, wire_transaction, last_valid_slot, None)
+        _send_transaction(
+            meta,
+            transaction,
+            wire_transaction,
+            last_valid_slot,
+            None,
+            None,
+        )
     }
 
     fn send_transaction(
@@ -2524,12 +3076,13 @@ impl RpcSol for RpcSolImpl {
         debug!("send_transaction rpc request received");
         let config = config.unwrap_or_default();
         let encoding = config.encoding.unwrap_or(UiTransactionEncoding::Base58);
-        let (wire_transaction, transaction) = deserialize_transaction(data, encoding)?;
+        let (wire_transaction, versioned_transaction) = deserialize_transaction(data, encoding)?;
 
         let preflight_commitment = config
             .preflight_commitment
             .map(|commitment| CommitmentConfig { commitment });
         let preflight_bank = &*meta.bank(preflight_commitment);
+        let transaction = resolve_transaction(preflight_bank, &versioned_transaction)?;
 
         let mut last_valid_slot = preflight_bank
             .get_blockhash_last_valid_slot(&transaction.message.recent_blockhash)
@@ -2549,7 +3102,7 @@ impl RpcSol for RpcSolImpl {
         }
 
         if !config.skip_preflight {
-            if let Err(e) = verify_transaction(&transaction) {
+            if let Err(e) = verify_transaction(&versioned_transaction) {
                 return Err(e);
             }
 
@@ -2563,12 +3116,24 @@ impl RpcSol for RpcSolImpl {
                 }
             }
 
-            if let (Err(err), logs) = preflight_bank.simulate_transaction(transaction.clone()) {
+            let TransactionSimulationResult {
+                result,
+                logs,
+                units_consumed,
+                return_data,
+                ..
+            } = preflight_bank.simulate_transaction(transaction.clone());
+            if let Err(err) = result {
                 return Err(RpcCustomError::SendTransactionPreflightFailure {
                     message: format!("Transaction simulation failed: {}", err),
                     result: RpcSimulateTransactionResult {
                         err: Some(err),
                         logs: Some(logs),
+                        accounts: None,
+                        units_consumed: Some(units_consumed.iter().sum()),
+                        instruction_units_consumed: Some(units_consumed),
+                        inner_instructions: None,
+                        return_data: return_data.map(|return_data| return_data.into()),
                     },
                 }
                 .into());
@@ -2581,6 +3146,7 @@ impl RpcSol for RpcSolImpl {
             wire_transaction,
             last_valid_slot,
             durable_nonce_info,
+            config.max_retries,
         )
     }
 
@@ -2593,25 +3159,99 @@ impl RpcSol for RpcSolImpl {
         debug!("simulate_transaction rpc request received");
         let config = config.unwrap_or_default();
         let encoding = config.encoding.unwrap_or(UiTransactionEncoding::Base58);
-        let (_, transaction) = deserialize_transaction(data, encoding)?;
+        let (_, versioned_transaction) = deserialize_transaction(data, encoding)?;
 
         if config.sig_verify {
-            if let Err(e) = verify_transaction(&transaction) {
+            if config.replace_recent_blockhash {
+                return Err(Error::invalid_params(
+                    "sigVerify may not be used with replaceRecentBlockhash",
+                ));
+            }
+            if let Err(e) = verify_transaction(&versioned_transaction) {
                 return Err(e);
             }
         }
 
-        let bank = &*meta.bank(config.commitment);
-        let (result, logs) = bank.simulate_transaction(transaction);
-
-        Ok(new_response(
-            &bank,
-            RpcSimulateTransactionResult {
-                err: result.err(),
-                logs: Some(logs),
-            },
-        ))
-    }
+        let bank = meta.bank(config.commitment);
+        let mut transaction = resolve_transaction(&bank, &versioned_transaction)?;
+        if config.replace_recent_blockhash {
+            transaction.message.recent_blockhash = bank.last_blockhash();
+        }
+        let TransactionSimulationResult {
+            result,
+            logs,
+            post_simulation_accounts,
+            units_consumed,
+            inner_instructions,
+            return_data,
+        } = bank.simulate_transaction(transaction);
+
+        let accounts = if let Some(config_accounts) = config.accounts {
+            let accounts_encoding = config_accounts
+                .encoding
+                .unwrap_or(UiAccountEncoding::Base64);
+            if accounts_encoding == UiAccountEncoding::Binary
+                || accounts_encoding == UiAccountEncoding::Base58
+            {
+                return Err(Error::invalid_params("base58 encoding not supported"));
+            }
+
+            let mut accounts = vec![];
+            for address in &config_accounts.addresses {
+                let pubkey = verify_pubkey(address.clone())?;
+                // The post-execution state is only reported for accounts of a successful
+                // transaction
+                let account = if result.is_ok() {
+                    post_simulation_accounts
+                        .iter()
+                        .find(|(key, _)| *key == pubkey)
+                        .map(|(_, account)| account.clone())
+                } else {
+                    None
+                };
+                accounts.push(
+                    account
+                        .map(|account| {
+                            encode_account(&bank, &pubkey, account, accounts_encoding, None)
+                        })
+                        .transpose()?,
+                );
+            }
+            Some(accounts)
+        } else {
+            None
+        };
+
+        let inner_instructions = if config.inner_instructions {
+            inner_instructions.map(|inner_instructions| {
+                inner_instructions
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, instructions)| InnerInstructions {
+                        index: index as u8,
+                        instructions,
+                    })
+                    .filter(|i| !i.instructions.is_empty())
+                    .map(|inner_instructions| inner_instructions.into())
+                    .collect()
+            })
+        } else {
+            None
+        };
+
+        Ok(new_response(
+            &bank,
+            RpcSimulateTransactionResult {
+                err: result.err(),
+                logs: Some(logs),
+                accounts,
+                units_consumed: Some(units_consumed.iter().sum()),
+                instruction_units_consumed: Some(units_consumed),
+                inner_instructions,
+                return_data: return_data.map(|return_data| return_data.into()),
+            },
+        ))
+    }
 
     fn get_slot_leader(
         &self,
@@ -2667,10 +3307,10 @@ impl RpcSol for RpcSolImpl {
         &self,
         meta: Self::Metadata,
         slot: Slot,
-        encoding: Option<UiTransactionEncoding>,
-    ) -> Result<Option<EncodedConfirmedBlock>> {
+        config: Option<RpcEncodingConfigWrapper<RpcConfirmedBlockConfig>>,
+    ) -> Result<Option<UiConfirmedBlock>> {
         debug!("get_confirmed_block rpc request received: {:?}", slot);
-        meta.get_confirmed_block(slot, encoding)
+        meta.get_confirmed_block(slot, config)
     }
 
     fn get_confirmed_blocks(
@@ -2804,39 +3444,39 @@ impl RpcSol for RpcSolImpl {
         &self,
         meta: Self::Metadata,
         pubkey_str: String,
-        commitment: Option<CommitmentConfig>,
+        config: Option<RpcContextConfig>,
     ) -> Result<RpcResponse<UiTokenAmount>> {
         debug!(
             "get_token_account_balance rpc request received: {:?}",
             pubkey_str
         );
         let pubkey = verify_pubkey(pubkey_str)?;
-        meta.get_token_account_balance(&pubkey, commitment)
+        meta.get_token_account_balance(&pubkey, config)
     }
 
     fn get_token_supply(
         &self,
         meta: Self::Metadata,
         mint_str: String,
-        commitment: Option<CommitmentConfig>,
+        config: Option<RpcContextConfig>,
     ) -> Result<RpcResponse<UiTokenAmount>> {
         debug!("get_token_supply rpc request received: {:?}", mint_str);
         let mint = verify_pubkey(mint_str)?;
-        meta.get_token_supply(&mint, commitment)
+        meta.get_token_supply(&mint, config)
     }
 
     fn get_token_largest_accounts(
         &self,
         meta: Self::Metadata,
         mint_str: String,
-        commitment: Option<CommitmentConfig>,
+        config: Option<RpcContextConfig>,
     ) -> Result<RpcResponse<Vec<RpcTokenAccountBalance>>> {
         debug!(
             "get_token_largest_accounts rpc request received: {:?}",
             mint_str
         );
         let mint = verify_pubkey(mint_str)?;
-        meta.get_token_largest_accounts(&mint, commitment)
+        meta.get_token_largest_accounts(&mint, config)
     }
 
     fn get_token_accounts_by_owner(
@@ -2877,7 +3517,7 @@ const WORST_CASE_BASE64_TX: usize = 1644; // Golden, bump if PACKET_DATA_SIZE ch
 fn deserialize_transaction(
     encoded_transaction: String,
     encoding: UiTransactionEncoding,
-) -> Result<(Vec<u8>, Transaction)> {
+) -> Result<(Vec<u8>, VersionedTransaction)> {
     let wire_transaction = match encoding {
         UiTransactionEncoding::Base58 => {
             inc_new_counter_info!("rpc-base58_encoded_tx", 1);
@@ -2931,7 +3571,7 @@ fn deserialize_transaction(
             info!("transaction deserialize error: {:?}", err);
             Error::invalid_params(&err.to_string())
         })
-        .and_then(|transaction: Transaction| {
+        .and_then(|transaction: VersionedTransaction| {
             if let Err(err) = transaction.sanitize() {
                 Err(Error::invalid_params(format!(
                     "invalid transaction: {}",
@@ -2966,7 +3606,15 @@ pub mod tests {
     use bincode::deserialize;
     use jsonrpc_core::{futures, ErrorCode, MetaIoHandler, Output, Response, Value};
     use jsonrpc_core_client::transports::local;
-    use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes};
+    use solana_client::{
+        rpc_custom_error::{
+            JSON_RPC_SERVER_ERROR_BANK_NOT_AVAILABLE,
+            JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED,
+            JSON_RPC_SERVER_ERROR_RESPONSE_TOO_LARGE,
+            JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_QUEUE_FULL,
+        },
+        rpc_filter::{Memcmp, MemcmpEncodedBytes},
+    };
     use solana_ledger::{
         blockstore_meta::PerfSample,
         blockstore_processor::fill_blockstore_slot_with_ticks,
@@ -2988,7 +3636,7 @@ pub mod tests {
         transaction::{self, TransactionError},
     };
     use solana_transaction_status::{
-        EncodedTransaction, EncodedTransactionWithStatusMeta, UiMessage,
+        EncodedConfirmedBlock, EncodedTransaction, EncodedTransactionWithStatusMeta, UiMessage,
     };
     use solana_vote_program::{
         vote_instruction,
@@ -3155,8 +3803,19 @@ pub mod tests {
             Arc::new(tokio::runtime::Runtime::new().unwrap()),
             None,
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
+            Arc::new(LeaderScheduleCache::new_from_bank(
+                &bank_forks.read().unwrap().root_bank(),
+            )),
+        );
+        SendTransactionService::new(
+            tpu_address,
+            &bank_forks,
+            None,
+            receiver,
+            meta.send_transaction_queue_status(),
+            1000,
+            1,
         );
-        SendTransactionService::new(tpu_address, &bank_forks, None, receiver, 1000, 1);
 
         cluster_info.insert_info(ContactInfo::new_with_pubkey_socketaddr(
             &leader_pubkey,
@@ -3219,6 +3878,59 @@ pub mod tests {
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn test_rpc_get_balance_with_context_config() {
+        let genesis = create_genesis_config(20);
+        let mint_pubkey = genesis.mint_keypair.pubkey();
+        let bank = Arc::new(Bank::new(&genesis.genesis_config));
+        bank.freeze();
+        let meta = JsonRpcRequestProcessor::new_from_bank(&bank);
+        let child_bank = Bank::new_from_parent(&bank, &Pubkey::default(), 1);
+        meta.bank_forks.write().unwrap().insert(child_bank);
+
+        let mut io = MetaIoHandler::default();
+        io.extend_with(RpcSolImpl.to_delegate());
+
+        // A historical slot that is still in bank forks is served
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getBalance","params":["{}", {{"slot":0}}]}}"#,
+            mint_pubkey
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"]["value"], 20);
+
+        // A slot that is not available, or whose bank is not frozen yet, is rejected
+        for slot in &[5, 1] {
+            let req = format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"getBalance","params":["{}", {{"slot":{}}}]}}"#,
+                mint_pubkey, slot
+            );
+            let res = io.handle_request_sync(&req, meta.clone());
+            let result: Value = serde_json::from_str(&res.expect("actual response"))
+                .expect("actual response deserialization");
+            assert_eq!(
+                result["error"]["code"],
+                JSON_RPC_SERVER_ERROR_BANK_NOT_AVAILABLE
+            );
+        }
+
+        // A bank older than minContextSlot is rejected, reporting the context slot
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getBalance","params":["{}", {{"minContextSlot":1}}]}}"#,
+            mint_pubkey
+        );
+        let res = io.handle_request_sync(&req, meta);
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(
+            result["error"]["code"],
+            JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED
+        );
+        assert_eq!(result["error"]["data"]["contextSlot"], 0);
+    }
+
     #[test]
     fn test_rpc_get_balance_via_client() {
         let genesis = create_genesis_config(20);
@@ -4059,6 +4771,203 @@ pub mod tests {
         assert_eq!(accounts.len(), 0);
     }
 
+    #[test]
+    fn test_rpc_get_program_accounts_memcmp_index() {
+        let genesis = create_genesis_config(TEST_MINT_LAMPORTS);
+        let program_id = Pubkey::new_unique();
+        let memcmp_index = MemcmpIndex {
+            program_id,
+            offset: 4,
+        };
+        let mut account_indexes = HashSet::new();
+        account_indexes.insert(AccountIndex::Memcmp(memcmp_index));
+        let bank = Arc::new(Bank::new_with_paths(
+            &genesis.genesis_config,
+            vec![],
+            &[],
+            None,
+            None,
+            account_indexes.clone(),
+            false,
+            None,
+        ));
+
+        let key = Pubkey::new_unique();
+        let mut data = vec![0; 4 + PUBKEY_BYTES];
+        data[4..].copy_from_slice(key.as_ref());
+        let indexed_address = Pubkey::new_unique();
+        let indexed_account = Account {
+            lamports: 1,
+            data,
+            owner: program_id,
+            ..Account::default()
+        };
+        bank.store_account(&indexed_address, &indexed_account);
+        bank.store_account(
+            &Pubkey::new_unique(),
+            &Account::new(1, 4 + PUBKEY_BYTES, &program_id),
+        );
+
+        let mut meta = JsonRpcRequestProcessor::new_from_bank(&bank);
+        meta.config.account_indexes = account_indexes;
+
+        let memcmp_filter = |offset: usize, bytes: &[u8]| {
+            RpcFilterType::Memcmp(Memcmp {
+                offset,
+                bytes: MemcmpEncodedBytes::Binary(bs58::encode(bytes).into_string()),
+                encoding: None,
+            })
+        };
+
+        let filters = vec![memcmp_filter(4, key.as_ref())];
+        assert!(matches!(
+            meta.find_memcmp_index_key(&program_id, &filters),
+            Some(IndexKey::Memcmp(index, index_key)) if index == memcmp_index && index_key == key
+        ));
+        assert_eq!(
+            meta.get_filtered_program_accounts(&bank, &program_id, filters),
+            vec![(indexed_address, indexed_account)]
+        );
+
+        // Filters at other offsets, on a prefix of the key, or for other programs scan the
+        // program accounts instead
+        assert!(meta
+            .find_memcmp_index_key(&program_id, &[memcmp_filter(0, key.as_ref())])
+            .is_none());
+        assert!(meta
+            .find_memcmp_index_key(&program_id, &[memcmp_filter(4, &key.as_ref()[..8])])
+            .is_none());
+        assert!(meta
+            .find_memcmp_index_key(&Pubkey::new_unique(), &[memcmp_filter(4, key.as_ref())])
+            .is_none());
+    }
+
+    #[test]
+    fn test_rpc_get_program_accounts_pagination() {
+        let genesis = create_genesis_config(TEST_MINT_LAMPORTS);
+        let bank = Arc::new(Bank::new(&genesis.genesis_config));
+        let program_id = Pubkey::new_unique();
+        let mut addresses: Vec<_> = (0..5).map(|_| solana_sdk::pubkey::new_rand()).collect();
+        for address in &addresses {
+            bank.store_account(address, &Account::new(1, 8, &program_id));
+        }
+        addresses.sort();
+        let mut meta = JsonRpcRequestProcessor::new_from_bank(&bank);
+
+        let config = Some(RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        });
+        let get_page = |meta: &JsonRpcRequestProcessor, cursor: Option<String>| match meta
+            .get_program_accounts(&program_id, config.clone(), vec![], false, Some(2), cursor)
+        {
+            Ok(RpcProgramAccounts::Page(response)) => Ok(response),
+            Ok(_) => panic!("expected a page"),
+            Err(err) => Err(err),
+        };
+
+        // Pages are returned in address order until the cursor runs out
+        let mut cursor = None;
+        let mut paged_addresses = vec![];
+        loop {
+            let page = get_page(&meta, cursor).unwrap();
+            assert_eq!(page.context.slot, bank.slot());
+            assert!(page.value.accounts.len() <= 2);
+            paged_addresses.extend(
+                page.value
+                    .accounts
+                    .iter()
+                    .map(|keyed_account| keyed_account.pubkey.parse::<Pubkey>().unwrap()),
+            );
+            cursor = page.value.cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(paged_addresses, addresses);
+
+        // Without a limit, the result is only wrapped when context is requested
+        assert!(matches!(
+            meta.get_program_accounts(&program_id, config.clone(), vec![], false, None, None),
+            Ok(RpcProgramAccounts::Accounts(accounts)) if accounts.len() == 5
+        ));
+        assert!(matches!(
+            meta.get_program_accounts(&program_id, config.clone(), vec![], true, None, None),
+            Ok(RpcProgramAccounts::WithContext(response)) if response.value.len() == 5
+        ));
+
+        // Invalid limits and cursors are rejected
+        assert!(meta
+            .get_program_accounts(&program_id, config.clone(), vec![], false, Some(0), None)
+            .is_err());
+        let cursor = get_page(&meta, None).unwrap().value.cursor.unwrap();
+        assert!(meta
+            .get_program_accounts(
+                &program_id,
+                config.clone(),
+                vec![],
+                false,
+                None,
+                Some(cursor)
+            )
+            .is_err());
+        assert!(get_page(&meta, Some("not a cursor".to_string())).is_err());
+
+        // The cursor is the address of the last account returned, and is resolved against the
+        // bank selected by each request
+        let page = get_page(&meta, Some(addresses[1].to_string())).unwrap();
+        assert_eq!(
+            page.value
+                .accounts
+                .iter()
+                .map(|keyed_account| keyed_account.pubkey.clone())
+                .collect::<Vec<_>>(),
+            vec![addresses[2].to_string(), addresses[3].to_string()]
+        );
+        assert_eq!(page.value.cursor, Some(addresses[3].to_string()));
+
+        // Responses over the configured size fail unless paged below it
+        meta.config.max_program_accounts_response_bytes = Some(600);
+        assert_eq!(
+            meta.get_program_accounts(&program_id, config.clone(), vec![], false, None, None)
+                .unwrap_err()
+                .code,
+            ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_RESPONSE_TOO_LARGE)
+        );
+        assert!(get_page(&meta, None).is_ok());
+
+        // Pages of accounts selected through an account index start after the cursor too
+        let account_indexes: HashSet<_> = vec![AccountIndex::ProgramId].into_iter().collect();
+        let bank = Arc::new(Bank::new_with_paths(
+            &genesis.genesis_config,
+            vec![],
+            &[],
+            None,
+            None,
+            account_indexes.clone(),
+            false,
+            None,
+        ));
+        for address in &addresses {
+            bank.store_account(address, &Account::new(1, 8, &program_id));
+        }
+        let mut meta = JsonRpcRequestProcessor::new_from_bank(&bank);
+        meta.config.account_indexes = account_indexes;
+        let page = meta.get_filtered_program_accounts_page(
+            &bank,
+            &program_id,
+            vec![],
+            Some(&addresses[1]),
+            2,
+        );
+        assert_eq!(
+            page.into_iter()
+                .map(|(pubkey, _)| pubkey)
+                .collect::<Vec<_>>(),
+            addresses[2..4].to_vec()
+        );
+    }
+
     #[test]
     fn test_rpc_simulate_transaction() {
         let bob_pubkey = solana_sdk::pubkey::new_rand();
@@ -4088,10 +4997,18 @@ pub mod tests {
             "jsonrpc": "2.0",
             "result": {
                 "context":{"slot":0},
-                "value":{"err":null, "logs":[
-                    "Program 11111111111111111111111111111111 invoke [1]",
-                    "Program 11111111111111111111111111111111 success"
-                ]}
+                "value":{
+                    "err":null,
+                    "logs":[
+                        "Program 11111111111111111111111111111111 invoke [1]",
+                        "Program 11111111111111111111111111111111 success"
+                    ],
+                    "accounts":null,
+                    "unitsConsumed":0,
+                    "instructionUnitsConsumed":[0],
+                    "innerInstructions":null,
+                    "returnData":null
+                }
             },
             "id": 1,
         });
@@ -4132,10 +5049,18 @@ pub mod tests {
             "jsonrpc": "2.0",
             "result": {
                 "context":{"slot":0},
-                "value":{"err":null, "logs":[
-                    "Program 11111111111111111111111111111111 invoke [1]",
-                    "Program 11111111111111111111111111111111 success"
-                ]}
+                "value":{
+                    "err":null,
+                    "logs":[
+                        "Program 11111111111111111111111111111111 invoke [1]",
+                        "Program 11111111111111111111111111111111 success"
+                    ],
+                    "accounts":null,
+                    "unitsConsumed":0,
+                    "instructionUnitsConsumed":[0],
+                    "innerInstructions":null,
+                    "returnData":null
+                }
             },
             "id": 1,
         });
@@ -4150,15 +5075,23 @@ pub mod tests {
             r#"{{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":["{}"]}}"#,
             tx_serialized_encoded,
         );
-        let res = io.handle_request_sync(&req, meta);
+        let res = io.handle_request_sync(&req, meta.clone());
         let expected = json!({
             "jsonrpc": "2.0",
             "result": {
                 "context":{"slot":0},
-                "value":{"err":null, "logs":[
-                    "Program 11111111111111111111111111111111 invoke [1]",
-                    "Program 11111111111111111111111111111111 success"
-                ]}
+                "value":{
+                    "err":null,
+                    "logs":[
+                        "Program 11111111111111111111111111111111 invoke [1]",
+                        "Program 11111111111111111111111111111111 success"
+                    ],
+                    "accounts":null,
+                    "unitsConsumed":0,
+                    "instructionUnitsConsumed":[0],
+                    "innerInstructions":null,
+                    "returnData":null
+                }
             },
             "id": 1,
         });
@@ -4167,6 +5100,54 @@ pub mod tests {
         let result: Response = serde_json::from_str(&res.expect("actual response"))
             .expect("actual response deserialization");
         assert_eq!(expected, result);
+
+        // Post-execution accounts, with an address not in the transaction
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":["{}", {{"accounts": {{"encoding": "base64", "addresses": ["{}", "{}"]}}}}]}}"#,
+            tx_serialized_encoded,
+            bob_pubkey,
+            solana_sdk::pubkey::new_rand(),
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        let accounts = &result["result"]["value"]["accounts"];
+        assert_eq!(
+            accounts[0]["lamports"],
+            json!(bank.get_balance(&bob_pubkey) + 1234)
+        );
+        assert_eq!(accounts[0]["data"], json!(["", "base64"]));
+        assert_eq!(accounts[1], Value::Null);
+
+        // Stale blockhash, replaced with replaceRecentBlockhash=true
+        let tx = system_transaction::transfer(&alice, &bob_pubkey, 1234, Hash::default());
+        let tx_stale_serialized_encoded = bs58::encode(serialize(&tx).unwrap()).into_string();
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":["{}"]}}"#,
+            tx_stale_serialized_encoded,
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"]["value"]["err"], json!("BlockhashNotFound"));
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":["{}", {{"replaceRecentBlockhash": true}}]}}"#,
+            tx_stale_serialized_encoded,
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"]["value"]["err"], Value::Null);
+
+        // replaceRecentBlockhash conflicts with sigVerify
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":["{}", {{"sigVerify": true, "replaceRecentBlockhash": true}}]}}"#,
+            tx_serialized_encoded,
+        );
+        let res = io.handle_request_sync(&req, meta);
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["error"]["code"], json!(-32602));
     }
 
     #[test]
@@ -4564,8 +5545,19 @@ pub mod tests {
             Arc::new(tokio::runtime::Runtime::new().unwrap()),
             None,
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
+            Arc::new(LeaderScheduleCache::new_from_bank(
+                &bank_forks.read().unwrap().root_bank(),
+            )),
+        );
+        SendTransactionService::new(
+            tpu_address,
+            &bank_forks,
+            None,
+            receiver,
+            meta.send_transaction_queue_status(),
+            1000,
+            1,
         );
-        SendTransactionService::new(tpu_address, &bank_forks, None, receiver, 1000, 1);
 
         let mut bad_transaction = system_transaction::transfer(
             &mint_keypair,
@@ -4668,6 +5660,156 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_rpc_send_transaction_max_retries() {
+        let exit = Arc::new(AtomicBool::new(false));
+        let validator_exit = create_validator_exit(&exit);
+        let ledger_path = get_tmp_ledger_path!();
+        let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
+        let block_commitment_cache = Arc::new(RwLock::new(BlockCommitmentCache::default()));
+        let (bank_forks, mint_keypair, ..) = new_bank_forks();
+
+        let mut io = MetaIoHandler::default();
+        let rpc = RpcSolImpl;
+        io.extend_with(rpc.to_delegate());
+        // No SendTransactionService is started, so queued transactions stay in the receiver
+        let (meta, receiver) = JsonRpcRequestProcessor::new(
+            JsonRpcConfig::default(),
+            None,
+            bank_forks.clone(),
+            block_commitment_cache,
+            blockstore,
+            validator_exit,
+            RpcHealth::stub(),
+            Arc::new(ClusterInfo::default()),
+            Hash::default(),
+            Arc::new(tokio::runtime::Runtime::new().unwrap()),
+            None,
+            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
+            Arc::new(LeaderScheduleCache::new_from_bank(
+                &bank_forks.read().unwrap().root_bank(),
+            )),
+        );
+
+        let recent_blockhash = bank_forks.read().unwrap().root_bank().last_blockhash();
+        let transaction = system_transaction::transfer(
+            &mint_keypair,
+            &solana_sdk::pubkey::new_rand(),
+            42,
+            recent_blockhash,
+        );
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"sendTransaction","params":["{}", {{"skipPreflight": true, "maxRetries": 3}}]}}"#,
+            bs58::encode(serialize(&transaction).unwrap()).into_string()
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "result": transaction.signatures[0].to_string(),
+            "id": 1,
+        });
+        let result: Value = serde_json::from_str(&res.unwrap()).unwrap();
+        assert_eq!(result, expected);
+        let transaction_info = receiver.recv().unwrap();
+        assert_eq!(transaction_info.signature, transaction.signatures[0]);
+        assert_eq!(transaction_info.max_retries, Some(3));
+
+        // The transaction is not known to the (unstarted) send-transaction service
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getTransactionRetryStatus","params":["{}"]}}"#,
+            transaction.signatures[0]
+        );
+        let res = io.handle_request_sync(&req, meta);
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "result": null,
+            "id": 1,
+        });
+        let result: Value = serde_json::from_str(&res.unwrap()).unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_rpc_send_transaction_queue_full() {
+        let exit = Arc::new(AtomicBool::new(false));
+        let validator_exit = create_validator_exit(&exit);
+        let ledger_path = get_tmp_ledger_path!();
+        let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
+        let block_commitment_cache = Arc::new(RwLock::new(BlockCommitmentCache::default()));
+        let (bank_forks, mint_keypair, ..) = new_bank_forks();
+
+        let mut io = MetaIoHandler::default();
+        let rpc = RpcSolImpl;
+        io.extend_with(rpc.to_delegate());
+        // No SendTransactionService is started, so queued transactions stay in the receiver
+        let (meta, receiver) = JsonRpcRequestProcessor::new(
+            JsonRpcConfig::default(),
+            None,
+            bank_forks.clone(),
+            block_commitment_cache,
+            blockstore,
+            validator_exit,
+            RpcHealth::stub(),
+            Arc::new(ClusterInfo::default()),
+            Hash::default(),
+            Arc::new(tokio::runtime::Runtime::new().unwrap()),
+            None,
+            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
+            Arc::new(LeaderScheduleCache::new_from_bank(
+                &bank_forks.read().unwrap().root_bank(),
+            )),
+        );
+        for _ in 0..MAX_TRANSACTION_QUEUE_SIZE {
+            meta.transaction_sender
+                .lock()
+                .unwrap()
+                .try_send(TransactionInfo::new(
+                    Signature::default(),
+                    vec![],
+                    0,
+                    None,
+                    None,
+                ))
+                .unwrap();
+        }
+
+        let recent_blockhash = bank_forks.read().unwrap().root_bank().last_blockhash();
+        let transaction = system_transaction::transfer(
+            &mint_keypair,
+            &solana_sdk::pubkey::new_rand(),
+            42,
+            recent_blockhash,
+        );
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"sendTransaction","params":["{}", {{"skipPreflight": true}}]}}"#,
+            bs58::encode(serialize(&transaction).unwrap()).into_string()
+        );
+
+        // Transactions still waiting for the retry thread fill the queue
+        let res = io.handle_request_sync(&req, meta.clone());
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "error": {
+                "code": JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_QUEUE_FULL,
+                "message": "Transaction queue is full, try again later",
+            },
+            "id": 1,
+        });
+        let result: Value = serde_json::from_str(&res.unwrap()).unwrap();
+        assert_eq!(result, expected);
+
+        // Once the retry thread catches up, sends are accepted again
+        receiver.recv().unwrap();
+        let res = io.handle_request_sync(&req, meta);
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "result": transaction.signatures[0].to_string(),
+            "id": 1,
+        });
+        let result: Value = serde_json::from_str(&res.unwrap()).unwrap();
+        assert_eq!(result, expected);
+    }
+
     #[test]
     fn test_rpc_verify_filter() {
         let filter = RpcFilterType::Memcmp(Memcmp {
@@ -4760,8 +5902,19 @@ pub mod tests {
             Arc::new(tokio::runtime::Runtime::new().unwrap()),
             None,
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
+            Arc::new(LeaderScheduleCache::new_from_bank(
+                &bank_forks.read().unwrap().root_bank(),
+            )),
+        );
+        SendTransactionService::new(
+            tpu_address,
+            &bank_forks,
+            None,
+            receiver,
+            Arc::default(),
+            1000,
+            1,
         );
-        SendTransactionService::new(tpu_address, &bank_forks, None, receiver, 1000, 1);
         assert_eq!(request_processor.validator_exit(), false);
         assert_eq!(exit.load(Ordering::Relaxed), false);
     }
@@ -4793,8 +5946,19 @@ pub mod tests {
             Arc::new(tokio::runtime::Runtime::new().unwrap()),
             None,
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
+            Arc::new(LeaderScheduleCache::new_from_bank(
+                &bank_forks.read().unwrap().root_bank(),
+            )),
+        );
+        SendTransactionService::new(
+            tpu_address,
+            &bank_forks,
+            None,
+            receiver,
+            Arc::default(),
+            1000,
+            1,
         );
-        SendTransactionService::new(tpu_address, &bank_forks, None, receiver, 1000, 1);
         assert_eq!(request_processor.validator_exit(), true);
         assert_eq!(exit.load(Ordering::Relaxed), true);
     }
@@ -4885,8 +6049,19 @@ pub mod tests {
             Arc::new(tokio::runtime::Runtime::new().unwrap()),
             None,
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
+            Arc::new(LeaderScheduleCache::new_from_bank(
+                &bank_forks.read().unwrap().root_bank(),
+            )),
+        );
+        SendTransactionService::new(
+            tpu_address,
+            &bank_forks,
+            None,
+            receiver,
+            Arc::default(),
+            1000,
+            1,
         );
-        SendTransactionService::new(tpu_address, &bank_forks, None, receiver, 1000, 1);
         assert_eq!(
             request_processor.get_block_commitment(0),
             RpcBlockCommitment {
@@ -5020,7 +6195,7 @@ pub mod tests {
         }
 
         let req = r#"{"jsonrpc":"2.0","id":1,"method":"getConfirmedBlock","params":[0,"binary"]}"#;
-        let res = io.handle_request_sync(&req, meta);
+        let res = io.handle_request_sync(&req, meta.clone());
         let result: Value = serde_json::from_str(&res.expect("actual response"))
             .expect("actual response deserialization");
         let confirmed_block: Option<EncodedConfirmedBlock> =
@@ -5060,6 +6235,225 @@ pub mod tests {
                 }
             }
         }
+
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"getConfirmedBlock","params":[0,{"transactionDetails":"signatures","rewards":false}]}"#;
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        let confirmed_block: Option<UiConfirmedBlock> =
+            serde_json::from_value(result["result"].clone()).unwrap();
+        let confirmed_block = confirmed_block.unwrap();
+        assert!(confirmed_block.transactions.is_none());
+        assert!(confirmed_block.rewards.is_none());
+        let signatures = confirmed_block.signatures.unwrap();
+        assert_eq!(signatures.len(), 3);
+        for signature in confirmed_block_signatures.iter() {
+            assert!(signatures.contains(&signature.to_string()));
+        }
+
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"getConfirmedBlock","params":[0,{"transactionDetails":"none"}]}"#;
+        let res = io.handle_request_sync(&req, meta);
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        let confirmed_block: Option<UiConfirmedBlock> =
+            serde_json::from_value(result["result"].clone()).unwrap();
+        let confirmed_block = confirmed_block.unwrap();
+        assert!(confirmed_block.transactions.is_none());
+        assert!(confirmed_block.signatures.is_none());
+        assert!(confirmed_block.rewards.is_some());
+    }
+
+    #[test]
+    fn test_get_block_production() {
+        let bob_pubkey = solana_sdk::pubkey::new_rand();
+        let roots = vec![0, 1, 3, 4, 8];
+        let RpcHandler {
+            io,
+            meta,
+            block_commitment_cache,
+            leader_pubkey,
+            ..
+        } = start_rpc_handler_with_tx_and_blockstore(&bob_pubkey, roots);
+        block_commitment_cache
+            .write()
+            .unwrap()
+            .set_highest_confirmed_root(8);
+
+        let get_block_production = |params: Value| {
+            let req = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "getBlockProduction",
+                "params": params,
+            })
+            .to_string();
+            let res = io.handle_request_sync(&req, meta.clone());
+            serde_json::from_str::<Value>(&res.expect("actual response"))
+                .expect("actual response deserialization")
+        };
+
+        // The blockstore holds slots 1 through 8, all led by the single validator.  Slots 2, 5,
+        // 6 and 7 were skipped
+        let result = get_block_production(json!([]));
+        let block_production: RpcBlockProduction =
+            serde_json::from_value(result["result"]["value"].clone()).unwrap();
+        let mut expected_by_identity = HashMap::new();
+        expected_by_identity.insert(leader_pubkey.to_string(), (8, 4));
+        assert_eq!(
+            block_production,
+            RpcBlockProduction {
+                by_identity: expected_by_identity,
+                range: RpcBlockProductionRange {
+                    first_slot: 1,
+                    last_slot: 8,
+                },
+            }
+        );
+
+        let result = get_block_production(json!([{
+            "identity": leader_pubkey.to_string(),
+            "range": {"firstSlot": 2, "lastSlot": 4},
+        }]));
+        let block_production: RpcBlockProduction =
+            serde_json::from_value(result["result"]["value"].clone()).unwrap();
+        assert_eq!(
+            block_production.by_identity[&leader_pubkey.to_string()],
+            (3, 2)
+        );
+        assert_eq!(
+            block_production.range,
+            RpcBlockProductionRange {
+                first_slot: 2,
+                last_slot: 4,
+            }
+        );
+
+        let result = get_block_production(json!([{ "identity": bob_pubkey.to_string() }]));
+        let block_production: RpcBlockProduction =
+            serde_json::from_value(result["result"]["value"].clone()).unwrap();
+        assert!(block_production.by_identity.is_empty());
+
+        let result = get_block_production(json!([{ "range": {"firstSlot": 4, "lastSlot": 2} }]));
+        assert_eq!(
+            result["error"]["message"],
+            "lastSlot, 2, cannot be less than firstSlot, 4"
+        );
+
+        let result = get_block_production(json!([{ "range": {"firstSlot": 0} }]));
+        assert_eq!(
+            result["error"]["message"],
+            "Ledger data not available for slot 0; minimum ledger slot is 1"
+        );
+
+        let result = get_block_production(json!([{
+            "range": {"firstSlot": 1, "lastSlot": MAX_GET_BLOCK_PRODUCTION_SLOT_RANGE + 1},
+        }]));
+        assert_eq!(
+            result["error"]["message"],
+            format!(
+                "Slot range too large; max {}",
+                MAX_GET_BLOCK_PRODUCTION_SLOT_RANGE
+            )
+        );
+    }
+
+    #[test]
+    fn test_get_inflation_reward() {
+        let bob_pubkey = solana_sdk::pubkey::new_rand();
+        let roots = vec![0, 1, TEST_SLOTS_PER_EPOCH, TEST_SLOTS_PER_EPOCH + 1];
+        let RpcHandler {
+            io,
+            meta,
+            block_commitment_cache,
+            ..
+        } = start_rpc_handler_with_tx_and_blockstore(&bob_pubkey, roots);
+        block_commitment_cache
+            .write()
+            .unwrap()
+            .set_highest_confirmed_root(TEST_SLOTS_PER_EPOCH + 1);
+
+        // Epoch 0 rewards are paid out in the first block of epoch 1
+        let staker = solana_sdk::pubkey::new_rand();
+        let voter = solana_sdk::pubkey::new_rand();
+        meta.blockstore
+            .write_rewards(
+                TEST_SLOTS_PER_EPOCH,
+                vec![
+                    Reward {
+                        pubkey: staker.to_string(),
+                        lamports: 42,
+                        post_balance: 1_042,
+                        reward_type: Some(RewardType::Staking),
+                        commission: Some(10),
+                    },
+                    Reward {
+                        pubkey: voter.to_string(),
+                        lamports: 7,
+                        post_balance: 107,
+                        reward_type: Some(RewardType::Voting),
+                        commission: Some(10),
+                    },
+                    Reward {
+                        pubkey: bob_pubkey.to_string(),
+                        lamports: 5,
+                        post_balance: 105,
+                        reward_type: Some(RewardType::Fee),
+                        commission: None,
+                    },
+                ],
+            )
+            .unwrap();
+
+        let get_inflation_reward = |params: Value| {
+            let req = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "getInflationReward",
+                "params": params,
+            })
+            .to_string();
+            let res = io.handle_request_sync(&req, meta.clone());
+            serde_json::from_str::<Value>(&res.expect("actual response"))
+                .expect("actual response deserialization")
+        };
+
+        let result = get_inflation_reward(json!([
+            [staker.to_string(), voter.to_string(), bob_pubkey.to_string()],
+            {"epoch": 0},
+        ]));
+        let rewards: Vec<Option<RpcInflationReward>> =
+            serde_json::from_value(result["result"].clone()).unwrap();
+        assert_eq!(
+            rewards,
+            vec![
+                Some(RpcInflationReward {
+                    epoch: 0,
+                    effective_slot: TEST_SLOTS_PER_EPOCH,
+                    amount: 42,
+                    post_balance: 1_042,
+                    commission: Some(10),
+                }),
+                Some(RpcInflationReward {
+                    epoch: 0,
+                    effective_slot: TEST_SLOTS_PER_EPOCH,
+                    amount: 7,
+                    post_balance: 107,
+                    commission: Some(10),
+                }),
+                // Fee rewards are not inflation rewards
+                None,
+            ]
+        );
+
+        // Epoch 1 has not been paid out yet
+        let result = get_inflation_reward(json!([[staker.to_string()], {"epoch": 1}]));
+        assert_eq!(
+            result["error"]["message"],
+            format!("Block not available for slot {}", 2 * TEST_SLOTS_PER_EPOCH)
+        );
+
+        let result = get_inflation_reward(json!([["invalid"]]));
+        assert_eq!(result["error"]["code"], ErrorCode::InvalidParams.code());
     }
 
     #[test]
@@ -6114,6 +7508,9 @@ pub mod tests {
             Arc::new(tokio::runtime::Runtime::new().unwrap()),
             None,
             optimistically_confirmed_bank.clone(),
+            Arc::new(LeaderScheduleCache::new_from_bank(
+                &bank_forks.read().unwrap().root_bank(),
+            )),
         );
 
         let mut io = MetaIoHandler::default();
@@ -1,17 +1,22 @@
 //! The `pubsub` module implements a threaded subscription service on client RPC request
 
-use crate::rpc_subscriptions::{RpcSubscriptions, RpcVote};
+use crate::{
+    rpc::{check_slice_and_encoding, verify_filter},
+    rpc_subscriptions::{RpcSubscriptions, RpcVote},
+};
 use jsonrpc_core::{Error, ErrorCode, Result};
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, Session, SubscriptionId};
-use solana_account_decoder::UiAccount;
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
 use solana_client::{
     rpc_config::{
-        RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig,
-        RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+        RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter,
+        RpcProgramAccountsConfig, RpcSignatureSubscribeConfig, RpcTransactionLogsConfig,
+        RpcTransactionLogsFilter,
     },
     rpc_response::{
-        Response as RpcResponse, RpcKeyedAccount, RpcLogsResponse, RpcSignatureResult, SlotInfo,
+        Response as RpcResponse, RpcBlockUpdate, RpcLogsResponse, RpcProgramNotification,
+        RpcSignatureResult, SlotInfo, SlotUpdate,
     },
 };
 #[cfg(test)]
@@ -58,7 +63,8 @@ pub trait RpcSolPubSub {
     fn account_unsubscribe(&self, meta: Option<Self::Metadata>, id: SubscriptionId)
         -> Result<bool>;
 
-    // Get notification every time account data owned by a particular program is changed
+    // Get notification every time account data owned by a particular program is changed, and
+    // when an account previously notified no longer matches the filters
     // Accepts pubkey parameter as base-58 encoded string
     #[pubsub(
         subscription = "programNotification",
@@ -68,7 +74,7 @@ pub trait RpcSolPubSub {
     fn program_subscribe(
         &self,
         meta: Self::Metadata,
-        subscriber: Subscriber<RpcResponse<RpcKeyedAccount>>,
+        subscriber: Subscriber<RpcResponse<RpcProgramNotification>>,
         pubkey_str: String,
         config: Option<RpcProgramAccountsConfig>,
     );
@@ -127,6 +133,24 @@ pub trait RpcSolPubSub {
         id: SubscriptionId,
     ) -> Result<bool>;
 
+    // Get notification with the contents of each block once it reaches the requested commitment
+    #[pubsub(subscription = "blockNotification", subscribe, name = "blockSubscribe")]
+    fn block_subscribe(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<RpcResponse<RpcBlockUpdate>>,
+        filter: RpcBlockSubscribeFilter,
+        config: Option<RpcBlockSubscribeConfig>,
+    );
+
+    // Unsubscribe from block notification subscription.
+    #[pubsub(
+        subscription = "blockNotification",
+        unsubscribe,
+        name = "blockUnsubscribe"
+    )]
+    fn block_unsubscribe(&self, meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
+
     // Get notification when slot is encountered
     #[pubsub(subscription = "slotNotification", subscribe, name = "slotSubscribe")]
     fn slot_subscribe(&self, meta: Self::Metadata, subscriber: Subscriber<SlotInfo>);
@@ -139,6 +163,26 @@ pub trait RpcSolPubSub {
     )]
     fn slot_unsubscribe(&self, meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
 
+    // Get notification for each stage a slot goes through on this node
+    #[pubsub(
+        subscription = "slotsUpdatesNotification",
+        subscribe,
+        name = "slotsUpdatesSubscribe"
+    )]
+    fn slots_updates_subscribe(&self, meta: Self::Metadata, subscriber: Subscriber<SlotUpdate>);
+
+    // Unsubscribe from slots updates notification subscription.
+    #[pubsub(
+        subscription = "slotsUpdatesNotification",
+        unsubscribe,
+        name = "slotsUpdatesUnsubscribe"
+    )]
+    fn slots_updates_unsubscribe(
+        &self,
+        meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool>;
+
     // Get notification when vote is encountered
     #[pubsub(subscription = "voteNotification", subscribe, name = "voteSubscribe")]
     fn vote_subscribe(&self, meta: Self::Metadata, subscriber: Subscriber<RpcVote>);
@@ -207,6 +251,21 @@ fn param<T: FromStr>(param_str: &str, thing: &str) -> Result<T> {
     })
 }
 
+fn check_account_config(config: &RpcAccountInfoConfig) -> Result<()> {
+    // Notifications follow the banks selected by commitment as they are processed, there is no
+    // single bank for these to select
+    if config.min_context_slot.is_some() || config.slot.is_some() {
+        return Err(Error {
+            code: ErrorCode::InvalidParams,
+            message: "Invalid Request: minContextSlot and slot are not supported by subscriptions"
+                .into(),
+            data: None,
+        });
+    }
+    let encoding = config.encoding.clone().unwrap_or(UiAccountEncoding::Binary);
+    check_slice_and_encoding(&encoding, config.data_slice.is_some())
+}
+
 impl RpcSolPubSub for RpcSolPubSubImpl {
     type Metadata = Arc<Session>;
 
@@ -221,7 +280,13 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
             subscriber.reject(err).unwrap_or_default();
             return;
         }
-        match param::<Pubkey>(&pubkey_str, "pubkey") {
+        let pubkey = param::<Pubkey>(&pubkey_str, "pubkey").and_then(|pubkey| {
+            if let Some(config) = &config {
+                check_account_config(config)?;
+            }
+            Ok(pubkey)
+        });
+        match pubkey {
             Ok(pubkey) => {
                 let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
                 let sub_id = SubscriptionId::Number(id as u64);
@@ -253,7 +318,7 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
     fn program_subscribe(
         &self,
         _meta: Self::Metadata,
-        subscriber: Subscriber<RpcResponse<RpcKeyedAccount>>,
+        subscriber: Subscriber<RpcResponse<RpcProgramNotification>>,
         pubkey_str: String,
         config: Option<RpcProgramAccountsConfig>,
     ) {
@@ -261,7 +326,16 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
             subscriber.reject(err).unwrap_or_default();
             return;
         }
-        match param::<Pubkey>(&pubkey_str, "pubkey") {
+        let pubkey = param::<Pubkey>(&pubkey_str, "pubkey").and_then(|pubkey| {
+            if let Some(config) = &config {
+                check_account_config(&config.account_config)?;
+                for filter in config.filters.iter().flatten() {
+                    verify_filter(filter)?;
+                }
+            }
+            Ok(pubkey)
+        });
+        match pubkey {
             Ok(pubkey) => {
                 let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
                 let sub_id = SubscriptionId::Number(id as u64);
@@ -403,6 +477,50 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
         }
     }
 
+    fn block_subscribe(
+        &self,
+        _meta: Self::Metadata,
+        subscriber: Subscriber<RpcResponse<RpcBlockUpdate>>,
+        filter: RpcBlockSubscribeFilter,
+        config: Option<RpcBlockSubscribeConfig>,
+    ) {
+        info!("block_subscribe");
+        if let Err(err) = self.check_subscription_count() {
+            subscriber.reject(err).unwrap_or_default();
+            return;
+        }
+        let mentions = match filter {
+            RpcBlockSubscribeFilter::All => None,
+            RpcBlockSubscribeFilter::MentionsAccountOrProgram(address) => {
+                match param::<Pubkey>(&address, "mentionsAccountOrProgram") {
+                    Ok(address) => Some(address),
+                    Err(e) => {
+                        subscriber.reject(e).unwrap_or_default();
+                        return;
+                    }
+                }
+            }
+        };
+        let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
+        let sub_id = SubscriptionId::Number(id as u64);
+        info!("block_subscribe: mentions={:?} id={:?}", mentions, sub_id);
+        self.subscriptions
+            .add_block_subscription(mentions, config, sub_id, subscriber);
+    }
+
+    fn block_unsubscribe(&self, _meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+        info!("block_unsubscribe: id={:?}", id);
+        if self.subscriptions.remove_block_subscription(&id) {
+            Ok(true)
+        } else {
+            Err(Error {
+                code: ErrorCode::InvalidParams,
+                message: "Invalid Request: Subscription id does not exist".into(),
+                data: None,
+            })
+        }
+    }
+
     fn slot_subscribe(&self, _meta: Self::Metadata, subscriber: Subscriber<SlotInfo>) {
         info!("slot_subscribe");
         if let Err(err) = self.check_subscription_count() {
@@ -428,6 +546,36 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
         }
     }
 
+    fn slots_updates_subscribe(&self, _meta: Self::Metadata, subscriber: Subscriber<SlotUpdate>) {
+        info!("slots_updates_subscribe");
+        if let Err(err) = self.check_subscription_count() {
+            subscriber.reject(err).unwrap_or_default();
+            return;
+        }
+        let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
+        let sub_id = SubscriptionId::Number(id as u64);
+        info!("slots_updates_subscribe: id={:?}", sub_id);
+        self.subscriptions
+            .add_slots_updates_subscription(sub_id, subscriber);
+    }
+
+    fn slots_updates_unsubscribe(
+        &self,
+        _meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool> {
+        info!("slots_updates_unsubscribe");
+        if self.subscriptions.remove_slots_updates_subscription(&id) {
+            Ok(true)
+        } else {
+            Err(Error {
+                code: ErrorCode::InvalidParams,
+                message: "Invalid Request: Subscription id does not exist".into(),
+                data: None,
+            })
+        }
+    }
+
     fn vote_subscribe(&self, _meta: Self::Metadata, subscriber: Subscriber<RpcVote>) {
         info!("vote_subscribe");
         if let Err(err) = self.check_subscription_count() {
@@ -491,8 +639,11 @@ mod tests {
     use jsonrpc_core::{futures::channel::mpsc, Response};
     use jsonrpc_pubsub::{PubSubHandler, Session};
     use serial_test::serial;
-    use solana_account_decoder::{parse_account_data::parse_account_data, UiAccountEncoding};
-    use solana_client::rpc_response::{ProcessedSignatureResult, ReceivedSignatureResult};
+    use solana_account_decoder::{parse_account_data::parse_account_data, UiDataSliceConfig};
+    use solana_client::{
+        rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+        rpc_response::{ProcessedSignatureResult, ReceivedSignatureResult},
+    };
     use solana_runtime::{
         bank::Bank,
         bank_forks::BankForks,
@@ -724,6 +875,8 @@ mod tests {
                 commitment: Some(CommitmentConfig::processed()),
                 encoding: None,
                 data_slice: None,
+                min_context_slot: None,
+                slot: None,
             }),
         );
 
@@ -834,6 +987,8 @@ mod tests {
                 commitment: Some(CommitmentConfig::processed()),
                 encoding: Some(UiAccountEncoding::JsonParsed),
                 data_slice: None,
+                min_context_slot: None,
+                slot: None,
             }),
         );
 
@@ -956,6 +1111,8 @@ mod tests {
                 commitment: Some(CommitmentConfig::finalized()),
                 encoding: None,
                 data_slice: None,
+                min_context_slot: None,
+                slot: None,
             }),
         );
 
@@ -1010,6 +1167,8 @@ mod tests {
                 commitment: Some(CommitmentConfig::finalized()),
                 encoding: None,
                 data_slice: None,
+                min_context_slot: None,
+                slot: None,
             }),
         );
 
@@ -1197,6 +1356,94 @@ mod tests {
         );
     }
 
+    #[test]
+    #[serial]
+    fn test_subscribe_rejects_invalid_account_config() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank = Bank::new(&genesis_config);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+        let rpc = RpcSolPubSubImpl::default_with_bank_forks(bank_forks);
+        let sliced_json_parsed = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::JsonParsed),
+            data_slice: Some(UiDataSliceConfig {
+                offset: 0,
+                length: 8,
+            }),
+            ..RpcAccountInfoConfig::default()
+        };
+
+        let session = create_session();
+        let (subscriber, _id_receiver, _) = Subscriber::new_test("accountNotification");
+        rpc.account_subscribe(
+            session,
+            subscriber,
+            Pubkey::new_unique().to_string(),
+            Some(sliced_json_parsed.clone()),
+        );
+
+        let session = create_session();
+        let (subscriber, _id_receiver, _) = Subscriber::new_test("programNotification");
+        rpc.program_subscribe(
+            session,
+            subscriber,
+            Pubkey::new_unique().to_string(),
+            Some(RpcProgramAccountsConfig {
+                account_config: sliced_json_parsed,
+                ..RpcProgramAccountsConfig::default()
+            }),
+        );
+
+        let session = create_session();
+        let (subscriber, _id_receiver, _) = Subscriber::new_test("programNotification");
+        rpc.program_subscribe(
+            session,
+            subscriber,
+            Pubkey::new_unique().to_string(),
+            Some(RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::Memcmp(Memcmp {
+                    offset: 0,
+                    bytes: MemcmpEncodedBytes::Binary("I".to_string()),
+                    encoding: None,
+                })]),
+                ..RpcProgramAccountsConfig::default()
+            }),
+        );
+
+        for context_config in &[
+            RpcAccountInfoConfig {
+                min_context_slot: Some(0),
+                ..RpcAccountInfoConfig::default()
+            },
+            RpcAccountInfoConfig {
+                slot: Some(0),
+                ..RpcAccountInfoConfig::default()
+            },
+        ] {
+            let session = create_session();
+            let (subscriber, _id_receiver, _) = Subscriber::new_test("accountNotification");
+            rpc.account_subscribe(
+                session,
+                subscriber,
+                Pubkey::new_unique().to_string(),
+                Some(context_config.clone()),
+            );
+
+            let session = create_session();
+            let (subscriber, _id_receiver, _) = Subscriber::new_test("programNotification");
+            rpc.program_subscribe(
+                session,
+                subscriber,
+                Pubkey::new_unique().to_string(),
+                Some(RpcProgramAccountsConfig {
+                    account_config: context_config.clone(),
+                    ..RpcProgramAccountsConfig::default()
+                }),
+            );
+        }
+
+        assert_eq!(rpc.subscriptions.total(), 0);
+    }
+
     #[test]
     #[serial]
     fn test_vote_unsubscribe() {
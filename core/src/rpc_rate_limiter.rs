@@ -0,0 +1,516 @@
+//! The `rpc_rate_limiter` module throttles JSON RPC calls with token buckets kept per client
+//! address and per method.
+
+use crate::rpc::JsonRpcRequestProcessor;
+use jsonrpc_core::{
+    futures::future::{self, Either, Ready},
+    Call, MethodCall, Middleware, Notification, Output, Response,
+};
+use jsonrpc_http_server::hyper::HeaderMap;
+use lru::LruCache;
+use solana_client::rpc_custom_error::RpcCustomError;
+use solana_metrics::inc_new_counter_info;
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Methods that are never throttled: the admin-only methods, and the health check that load
+/// balancers poll
+const EXEMPT_METHODS: &[&str] = &["getHealth", "setLogFilter", "validatorExit"];
+
+/// Budget a call consumes from its client's bucket, for methods that are costlier to serve than
+/// the default of 1
+pub const DEFAULT_METHOD_COSTS: &[(&str, u64)] = &[
+    ("getConfirmedBlock", 5),
+    ("getConfirmedBlocks", 2),
+    ("getConfirmedBlocksWithLimit", 2),
+    ("getConfirmedSignaturesForAddress", 5),
+    ("getConfirmedSignaturesForAddress2", 5),
+    ("getConfirmedTransaction", 2),
+    ("getBlockProduction", 5),
+    ("getInflationReward", 10),
+    ("getLargestAccounts", 20),
+    ("getMultipleAccounts", 2),
+    ("getProgramAccounts", 20),
+    ("getSupply", 10),
+    ("getTokenAccountsByDelegate", 5),
+    ("getTokenAccountsByOwner", 5),
+    ("getTokenLargestAccounts", 10),
+    ("getVoteAccounts", 2),
+    ("simulateTransaction", 2),
+];
+
+/// Client buckets tracked at most; the least recently used one is evicted to make room
+const MAX_CLIENT_BUCKETS: usize = 10_000;
+
+#[derive(Debug, Default, Clone)]
+pub struct RpcRateLimitConfig {
+    /// Budget each client address may spend per second; `None` disables per-client limits
+    pub per_client_budget_per_second: Option<u64>,
+    /// Budget a client address may spend in a burst; defaults to one second's worth
+    pub per_client_burst: Option<u64>,
+    /// Calls per second accepted for a method across all clients
+    pub method_calls_per_second: HashMap<String, u64>,
+    /// Overrides of `DEFAULT_METHOD_COSTS`
+    pub method_costs: HashMap<String, u64>,
+    /// Reverse proxies whose `X-Forwarded-For` and `X-Real-IP` headers identify the client
+    pub trusted_proxies: HashSet<IpAddr>,
+}
+
+/// Parse a `METHOD=VALUE` command-line setting
+pub fn parse_method_setting(setting: &str) -> Result<(String, u64), String> {
+    let mut parts = setting.splitn(2, '=');
+    let method = parts.next().unwrap_or_default();
+    let value = parts
+        .next()
+        .ok_or_else(|| format!("expected METHOD=VALUE, got {}", setting))?;
+    let value = value
+        .parse::<u64>()
+        .map_err(|err| format!("invalid value for {}: {}", method, err))?;
+    if method.is_empty() || value == 0 {
+        return Err(format!(
+            "expected METHOD=VALUE with VALUE > 0, got {}",
+            setting
+        ));
+    }
+    Ok((method.to_string(), value))
+}
+
+/// The address a request is charged to: the peer's, unless the peer is a trusted proxy, in which
+/// case the client is the rightmost `X-Forwarded-For` entry not added by a trusted proxy, or the
+/// `X-Real-IP` header. Entries left of that one are supplied by the client and can't be trusted.
+///
+/// Headers from an unknown peer are never trusted. `None` means the client is unidentified.
+pub fn client_address(
+    peer: Option<SocketAddr>,
+    headers: &HeaderMap,
+    trusted_proxies: &HashSet<IpAddr>,
+) -> Option<IpAddr> {
+    let peer = peer.map(|peer| peer.ip());
+    if !peer.map_or(false, |peer| trusted_proxies.contains(&peer)) {
+        return peer;
+    }
+    let header_value = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    let forwarded_for = header_value("x-forwarded-for").map(|forwarded_for| {
+        forwarded_for
+            .rsplit(',')
+            .map(|address| address.trim().parse::<IpAddr>().ok())
+            .find(|address| {
+                address
+                    .map(|address| !trusted_proxies.contains(&address))
+                    .unwrap_or(true)
+            })
+            .flatten()
+    });
+    match forwarded_for {
+        Some(client) => client,
+        None => header_value("x-real-ip")
+            .and_then(|address| address.trim().parse().ok())
+            .or(peer),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RateLimitExceeded {
+    Client { retry_after: Duration },
+    Method { retry_after: Duration },
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u64, now: Instant) -> Self {
+        Self {
+            tokens: capacity as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, rate: u64, capacity: u64, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * rate as f64).min(capacity as f64);
+        self.last_refill = self.last_refill.max(now);
+    }
+
+    /// Time until `cost` tokens are available, or `None` if they already are
+    fn wait_time(&self, cost: u64, rate: u64) -> Option<Duration> {
+        let missing = cost as f64 - self.tokens;
+        if missing > 0.0 {
+            Some(Duration::from_secs_f64(missing / rate as f64))
+        } else {
+            None
+        }
+    }
+
+    fn take(&mut self, cost: u64) {
+        self.tokens -= cost as f64;
+    }
+}
+
+struct Buckets {
+    /// Unidentified clients all share the `None` bucket
+    clients: LruCache<Option<IpAddr>, TokenBucket>,
+    methods: HashMap<String, TokenBucket>,
+}
+
+impl Default for Buckets {
+    fn default() -> Self {
+        Self {
+            clients: LruCache::new(MAX_CLIENT_BUCKETS),
+            methods: HashMap::new(),
+        }
+    }
+}
+
+pub struct RpcRateLimiter {
+    config: RpcRateLimitConfig,
+    method_costs: HashMap<String, u64>,
+    buckets: Mutex<Buckets>,
+}
+
+impl RpcRateLimiter {
+    pub fn new(config: RpcRateLimitConfig) -> Self {
+        let mut method_costs: HashMap<_, _> = DEFAULT_METHOD_COSTS
+            .iter()
+            .map(|(method, cost)| (method.to_string(), *cost))
+            .collect();
+        method_costs.extend(config.method_costs.clone());
+        Self {
+            config,
+            method_costs,
+            buckets: Mutex::default(),
+        }
+    }
+
+    pub fn method_cost(&self, method: &str) -> u64 {
+        self.method_costs.get(method).copied().unwrap_or(1)
+    }
+
+    fn client_rate_and_capacity(&self) -> Option<(u64, u64)> {
+        self.config.per_client_budget_per_second.map(|rate| {
+            (
+                rate.max(1),
+                self.config.per_client_burst.unwrap_or(rate).max(1),
+            )
+        })
+    }
+
+    /// Charge a call to `method` from `client_ip` against the limits, or against the bucket shared
+    /// by all unidentified clients if `None`. Nothing is charged if the call is throttled.
+    pub fn check(
+        &self,
+        method: &str,
+        client_ip: Option<IpAddr>,
+        now: Instant,
+    ) -> Result<(), RateLimitExceeded> {
+        if EXEMPT_METHODS.contains(&method) {
+            return Ok(());
+        }
+        let client_limit = self.client_rate_and_capacity();
+        let method_rate = self.config.method_calls_per_second.get(method).copied();
+        if client_limit.is_none() && method_rate.is_none() {
+            return Ok(());
+        }
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let Buckets { clients, methods } = &mut *buckets;
+
+        let mut client_bucket = None;
+        if let Some((rate, capacity)) = client_limit {
+            if !clients.contains(&client_ip) {
+                clients.put(client_ip, TokenBucket::new(capacity, now));
+            }
+            let bucket = clients.get_mut(&client_ip).unwrap();
+            bucket.refill(rate, capacity, now);
+            // A call costlier than the whole burst is allowed once the bucket is full
+            let cost = self.method_cost(method).min(capacity);
+            if let Some(retry_after) = bucket.wait_time(cost, rate) {
+                return Err(RateLimitExceeded::Client { retry_after });
+            }
+            client_bucket = Some((bucket, cost));
+        }
+
+        if let Some(rate) = method_rate {
+            let rate = rate.max(1);
+            let bucket = methods
+                .entry(method.to_string())
+                .or_insert_with(|| TokenBucket::new(rate, now));
+            bucket.refill(rate, rate, now);
+            if let Some(retry_after) = bucket.wait_time(1, rate) {
+                return Err(RateLimitExceeded::Method { retry_after });
+            }
+            bucket.take(1);
+        }
+
+        if let Some((bucket, cost)) = client_bucket {
+            bucket.take(cost);
+        }
+        Ok(())
+    }
+}
+
+impl Middleware<JsonRpcRequestProcessor> for RpcRateLimiter {
+    type Future = Ready<Option<Response>>;
+    type CallFuture = Ready<Option<Output>>;
+
+    fn on_call<F, X>(
+        &self,
+        call: Call,
+        meta: JsonRpcRequestProcessor,
+        next: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, JsonRpcRequestProcessor) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        let method = match &call {
+            Call::MethodCall(MethodCall { method, .. })
+            | Call::Notification(Notification { method, .. }) => method,
+            Call::Invalid { .. } => return Either::Right(next(call, meta)),
+        };
+        let retry_after = match self.check(method, meta.client_ip(), Instant::now()) {
+            Ok(()) => return Either::Right(next(call, meta)),
+            Err(RateLimitExceeded::Client { retry_after }) => {
+                inc_new_counter_info!("rpc-rate_limited-client", 1);
+                retry_after
+            }
+            Err(RateLimitExceeded::Method { retry_after }) => {
+                inc_new_counter_info!("rpc-rate_limited-method", 1);
+                retry_after
+            }
+        };
+        debug!("rate limited {} call from {:?}", method, meta.client_ip());
+        let output = match call {
+            Call::MethodCall(MethodCall {
+                jsonrpc,
+                method,
+                id,
+                ..
+            }) => {
+                let error = RpcCustomError::RateLimited {
+                    method,
+                    retry_after_ms: retry_after.as_millis() as u64,
+                };
+                Some(Output::from(Err(error.into()), id, jsonrpc))
+            }
+            _ => None,
+        };
+        Either::Left(future::ready(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::MetaIoHandler;
+    use jsonrpc_http_server::hyper::header::HeaderValue;
+    use serde_json::Value;
+    use solana_client::rpc_custom_error::JSON_RPC_SERVER_ERROR_RATE_LIMITED;
+    use solana_ledger::genesis_utils::create_genesis_config;
+    use solana_runtime::bank::Bank;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse_method_setting() {
+        assert_eq!(
+            parse_method_setting("getProgramAccounts=5"),
+            Ok(("getProgramAccounts".to_string(), 5))
+        );
+        assert!(parse_method_setting("getProgramAccounts").is_err());
+        assert!(parse_method_setting("getProgramAccounts=0").is_err());
+        assert!(parse_method_setting("getProgramAccounts=x").is_err());
+        assert!(parse_method_setting("=5").is_err());
+    }
+
+    #[test]
+    fn test_client_address() {
+        let client: IpAddr = "1.1.1.1".parse().unwrap();
+        let proxy0: IpAddr = "10.0.0.1".parse().unwrap();
+        let proxy1: IpAddr = "10.0.0.2".parse().unwrap();
+        let peer = |ip| Some(SocketAddr::new(ip, 8899));
+        let no_proxies = HashSet::new();
+        let trusted_proxies: HashSet<_> = vec![proxy0, proxy1].into_iter().collect();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("2.2.2.2, 1.1.1.1, 10.0.0.2"),
+        );
+        headers.insert("x-real-ip", HeaderValue::from_static("3.3.3.3"));
+
+        // Headers are ignored unless the peer is a trusted proxy
+        assert_eq!(
+            client_address(peer(client), &headers, &no_proxies),
+            Some(client)
+        );
+        assert_eq!(
+            client_address(peer(proxy0), &headers, &no_proxies),
+            Some(proxy0)
+        );
+        assert_eq!(client_address(None, &headers, &no_proxies), None);
+        assert_eq!(
+            client_address(peer(client), &headers, &trusted_proxies),
+            Some(client)
+        );
+
+        // The rightmost X-Forwarded-For entry not added by a trusted proxy is the client
+        assert_eq!(
+            client_address(peer(proxy0), &headers, &trusted_proxies),
+            Some(client)
+        );
+        assert_eq!(client_address(None, &headers, &trusted_proxies), None);
+
+        // An unparsable entry can't be skipped to reach one the client chose
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("1.1.1.1, bogus, 10.0.0.2"),
+        );
+        assert_eq!(
+            client_address(peer(proxy0), &headers, &trusted_proxies),
+            None
+        );
+
+        headers.remove("x-forwarded-for");
+        assert_eq!(
+            client_address(peer(proxy0), &headers, &trusted_proxies),
+            Some("3.3.3.3".parse().unwrap())
+        );
+        headers.remove("x-real-ip");
+        assert_eq!(
+            client_address(peer(proxy0), &headers, &trusted_proxies),
+            Some(proxy0)
+        );
+    }
+
+    #[test]
+    fn test_client_limit() {
+        let rate_limiter = RpcRateLimiter::new(RpcRateLimitConfig {
+            per_client_budget_per_second: Some(10),
+            per_client_burst: Some(20),
+            ..RpcRateLimitConfig::default()
+        });
+        let client0: Option<IpAddr> = Some("10.0.0.1".parse().unwrap());
+        let client1: Option<IpAddr> = Some("10.0.0.2".parse().unwrap());
+        let now = Instant::now();
+
+        // getProgramAccounts costs the whole burst
+        assert_eq!(
+            rate_limiter.check("getProgramAccounts", client0, now),
+            Ok(())
+        );
+        assert_eq!(
+            rate_limiter.check("getBalance", client0, now),
+            Err(RateLimitExceeded::Client {
+                retry_after: Duration::from_millis(100)
+            })
+        );
+
+        // Other clients have their own budget, while unidentified clients share one
+        assert_eq!(rate_limiter.check("getBalance", client1, now), Ok(()));
+        assert_eq!(rate_limiter.check("getProgramAccounts", None, now), Ok(()));
+        assert!(rate_limiter.check("getBalance", None, now).is_err());
+
+        // Admin methods are never throttled
+        assert_eq!(rate_limiter.check("validatorExit", client0, now), Ok(()));
+        assert_eq!(rate_limiter.check("setLogFilter", client0, now), Ok(()));
+
+        // The budget refills over time
+        let later = now + Duration::from_millis(500);
+        for _ in 0..5 {
+            assert_eq!(rate_limiter.check("getBalance", client0, later), Ok(()));
+        }
+        assert!(rate_limiter.check("getBalance", client0, later).is_err());
+    }
+
+    #[test]
+    fn test_method_limit() {
+        let mut method_calls_per_second = HashMap::new();
+        method_calls_per_second.insert("getProgramAccounts".to_string(), 2);
+        let rate_limiter = RpcRateLimiter::new(RpcRateLimitConfig {
+            per_client_budget_per_second: Some(100),
+            method_calls_per_second,
+            ..RpcRateLimitConfig::default()
+        });
+        let client0: Option<IpAddr> = Some("10.0.0.1".parse().unwrap());
+        let client1: Option<IpAddr> = Some("10.0.0.2".parse().unwrap());
+        let now = Instant::now();
+
+        assert_eq!(
+            rate_limiter.check("getProgramAccounts", client0, now),
+            Ok(())
+        );
+        assert_eq!(rate_limiter.check("getProgramAccounts", None, now), Ok(()));
+        assert_eq!(
+            rate_limiter.check("getProgramAccounts", client1, now),
+            Err(RateLimitExceeded::Method {
+                retry_after: Duration::from_millis(500)
+            })
+        );
+        assert_eq!(rate_limiter.check("getBalance", client1, now), Ok(()));
+
+        // The throttled call was not charged to client1's budget
+        let client1_tokens = rate_limiter
+            .buckets
+            .lock()
+            .unwrap()
+            .clients
+            .peek(&client1)
+            .unwrap()
+            .tokens;
+        assert!((client1_tokens - 99.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_client_buckets_evicted() {
+        let rate_limiter = RpcRateLimiter::new(RpcRateLimitConfig {
+            per_client_budget_per_second: Some(1),
+            ..RpcRateLimitConfig::default()
+        });
+        let client = |i: usize| Some(IpAddr::from((i as u32).to_be_bytes()));
+        let now = Instant::now();
+
+        for i in 0..MAX_CLIENT_BUCKETS {
+            assert_eq!(rate_limiter.check("getBalance", client(i), now), Ok(()));
+        }
+        // Client 0 was used recently, so client 1 makes room for a new client
+        assert!(rate_limiter.check("getBalance", client(0), now).is_err());
+        assert_eq!(
+            rate_limiter.check("getBalance", client(MAX_CLIENT_BUCKETS), now),
+            Ok(())
+        );
+        let buckets = rate_limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.clients.len(), MAX_CLIENT_BUCKETS);
+        assert!(buckets.clients.contains(&client(0)));
+        assert!(!buckets.clients.contains(&client(1)));
+    }
+
+    #[test]
+    fn test_middleware() {
+        let genesis = create_genesis_config(100);
+        let bank = Arc::new(Bank::new(&genesis.genesis_config));
+        let meta = JsonRpcRequestProcessor::new_from_bank(&bank)
+            .with_client_ip(Some("10.0.0.1".parse().unwrap()));
+        let mut io = MetaIoHandler::with_middleware(RpcRateLimiter::new(RpcRateLimitConfig {
+            per_client_budget_per_second: Some(1),
+            ..RpcRateLimitConfig::default()
+        }));
+        io.add_method_with_meta("getSlot", |_params, _meta: JsonRpcRequestProcessor| {
+            future::ready(Ok(Value::from(0)))
+        });
+
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"getSlot"}"#;
+        let res: Value =
+            serde_json::from_str(&io.handle_request_sync(req, meta.clone()).unwrap()).unwrap();
+        assert_eq!(res["result"], 0);
+        let res: Value = serde_json::from_str(&io.handle_request_sync(req, meta).unwrap()).unwrap();
+        assert_eq!(res["error"]["code"], JSON_RPC_SERVER_ERROR_RATE_LIMITED);
+        assert_eq!(res["error"]["data"]["retryAfterMs"], 1000);
+    }
+}
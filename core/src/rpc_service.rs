@@ -7,32 +7,51 @@ use crate::{
     poh_recorder::PohRecorder,
     rpc::*,
     rpc_health::*,
+    rpc_rate_limiter::{client_address, RpcRateLimiter},
     send_transaction_service::{LeaderInfo, SendTransactionService},
     validator::ValidatorExit,
 };
 use jsonrpc_core::{futures::prelude::*, MetaIoHandler};
 use jsonrpc_http_server::{
-    hyper, AccessControlAllowOrigin, CloseHandle, DomainsValidation, RequestMiddleware,
-    RequestMiddlewareAction, ServerBuilder,
+    cors::AccessControlAllowHeaders,
+    hyper::{
+        self,
+        server::conn::Http,
+        service::{service_fn, Service},
+    },
+    AccessControlAllowOrigin, RequestMiddleware, RequestMiddlewareAction, RestApi, Rpc,
+    ServerHandler,
 };
 use regex::Regex;
-use solana_ledger::blockstore::Blockstore;
+use solana_ledger::{blockstore::Blockstore, leader_schedule_cache::LeaderScheduleCache};
 use solana_metrics::inc_new_counter_info;
 use solana_runtime::{
     bank_forks::{BankForks, SnapshotConfig},
     commitment::BlockCommitmentCache,
     snapshot_utils,
 };
-use solana_sdk::{hash::Hash, native_token::lamports_to_sol, pubkey::Pubkey};
+use solana_sdk::{
+    hash::{Hash, Hasher},
+    native_token::lamports_to_sol,
+    pubkey::Pubkey,
+};
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::SeekFrom,
     net::SocketAddr,
     path::{Path, PathBuf},
     sync::atomic::{AtomicBool, Ordering},
     sync::{mpsc::channel, Arc, Mutex, RwLock},
     thread::{self, Builder, JoinHandle},
+    time::{Duration, SystemTime},
 };
 use tokio::runtime;
+use tokio_02::{
+    io::{AsyncReadExt, AsyncSeekExt},
+    net::TcpListener,
+    sync::oneshot,
+};
 use tokio_util::codec::{BytesCodec, FramedRead};
 
 pub struct JsonRpcService {
@@ -41,15 +60,31 @@ pub struct JsonRpcService {
     #[cfg(test)]
     pub request_processor: JsonRpcRequestProcessor, // Used only by test_rpc_new()...
 
-    close_handle: Option<CloseHandle>,
+    close_handle: Option<RpcCloseHandle>,
 }
 
+/// Stops the JSON RPC server from accepting connections
+#[derive(Clone)]
+struct RpcCloseHandle(Arc<Mutex<Option<oneshot::Sender<()>>>>);
+
+impl RpcCloseHandle {
+    fn close(&self) {
+        if let Some(sender) = self.0.lock().unwrap().take() {
+            let _ = sender.send(());
+        }
+    }
+}
+
+/// Hashes of served files by path, length and modification time, `None` while being computed
+type ContentHashes = HashMap<(PathBuf, u64, SystemTime), Option<Hash>>;
+
 struct RpcRequestMiddleware {
     ledger_path: PathBuf,
     snapshot_archive_path_regex: Regex,
     snapshot_config: Option<SnapshotConfig>,
     bank_forks: Arc<RwLock<BankForks>>,
     health: Arc<RpcHealth>,
+    content_hashes: Arc<Mutex<ContentHashes>>,
 }
 
 impl RpcRequestMiddleware {
@@ -62,12 +97,13 @@ impl RpcRequestMiddleware {
         Self {
             ledger_path,
             snapshot_archive_path_regex: Regex::new(
-                r"/snapshot-\d+-[[:alnum:]]+\.(tar|tar\.bz2|tar\.zst|tar\.gz)$",
+                r"/(snapshot-\d+|incremental-snapshot-\d+-\d+)-[[:alnum:]]+\.(tar|tar\.bz2|tar\.zst|tar\.gz)$",
             )
             .unwrap(),
             snapshot_config,
             bank_forks,
             health,
+            content_hashes: Arc::default(),
         }
     }
 
@@ -107,7 +143,81 @@ impl RpcRequestMiddleware {
         }
     }
 
-    fn process_file_get(&self, path: &str) -> RequestMiddlewareAction {
+    /// Parses a `Range` header of the single `bytes=<start>-[<end>]` form used for resuming and
+    /// chunking downloads into an inclusive byte range of a file with `file_length` bytes
+    fn parse_byte_range(range: &str, file_length: u64) -> Option<(u64, u64)> {
+        if !range.starts_with("bytes=") {
+            return None;
+        }
+        let mut bounds = range["bytes=".len()..].splitn(2, '-');
+        let start = bounds.next()?.trim().parse::<u64>().ok()?;
+        let end = match bounds.next()?.trim() {
+            "" => file_length.saturating_sub(1),
+            end => end.parse::<u64>().ok()?.min(file_length.saturating_sub(1)),
+        };
+        if start > end || start >= file_length {
+            None
+        } else {
+            Some((start, end))
+        }
+    }
+
+    /// The ETag of a served file is the hash of its contents, so that a client downloading it from
+    /// several nodes can tell which of them serve identical bytes.  A file is hashed in the
+    /// background when first requested, and served without an ETag until the hash is known.
+    fn etag(&self, filename: &Path) -> Option<String> {
+        let metadata = std::fs::metadata(filename).ok()?;
+        let key = (
+            filename.to_path_buf(),
+            metadata.len(),
+            metadata.modified().ok()?,
+        );
+        let mut content_hashes = self.content_hashes.lock().unwrap();
+        if let Some(content_hash) = content_hashes.get(&key) {
+            return content_hash.map(|content_hash| format!("\"{}\"", content_hash));
+        }
+        // Forget files that were purged or replaced since they were hashed
+        content_hashes.retain(|(path, _, _), _| path != filename && path.exists());
+        content_hashes.insert(key.clone(), None);
+
+        let content_hashes = self.content_hashes.clone();
+        Builder::new()
+            .name("solana-rpc-file-hash".to_string())
+            .spawn(move || {
+                let content_hash = Self::hash_file(&key.0);
+                let mut content_hashes = content_hashes.lock().unwrap();
+                match content_hash {
+                    Ok(content_hash) => {
+                        if let Some(entry) = content_hashes.get_mut(&key) {
+                            *entry = Some(content_hash);
+                        }
+                    }
+                    Err(err) => {
+                        warn!("Unable to hash {:?}: {}", key.0, err);
+                        content_hashes.remove(&key);
+                    }
+                }
+            })
+            .unwrap();
+        None
+    }
+
+    fn hash_file(path: &Path) -> std::io::Result<Hash> {
+        use std::io::Read;
+
+        let mut file = File::open(path)?;
+        let mut hasher = Hasher::default();
+        let mut buf = vec![0; 1024 * 1024];
+        loop {
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                return Ok(hasher.result());
+            }
+            hasher.hash(&buf[..n]);
+        }
+    }
+
+    fn process_file_get(&self, path: &str, range: Option<&str>) -> RequestMiddlewareAction {
         let stem = path.split_at(1).1; // Drop leading '/' from path
         let filename = {
             match path {
@@ -126,24 +236,52 @@ impl RpcRequestMiddleware {
             }
         };
 
-        let file_length = std::fs::metadata(&filename)
-            .map(|m| m.len())
-            .unwrap_or(0)
-            .to_string();
-        info!("get {} -> {:?} ({} bytes)", path, filename, file_length);
+        let file_length = std::fs::metadata(&filename).map(|m| m.len()).unwrap_or(0);
+        let byte_range = match range {
+            None => None,
+            Some(range) => match Self::parse_byte_range(range, file_length) {
+                None => return Self::range_not_satisfiable(file_length).into(),
+                byte_range => byte_range,
+            },
+        };
+        let etag = self.etag(&filename);
+        info!(
+            "get {} -> {:?} ({} bytes, range {:?}, etag {:?})",
+            path, filename, file_length, byte_range, etag
+        );
         RequestMiddlewareAction::Respond {
             should_validate_hosts: true,
-            response: Box::pin(async {
+            response: Box::pin(async move {
                 // Stuck on tokio 0.2 until the jsonrpc crates upgrade
                 match tokio_02::fs::File::open(filename).await {
                     Err(_) => Ok(Self::internal_server_error()),
-                    Ok(file) => {
-                        let stream =
-                            FramedRead::new(file, BytesCodec::new()).map_ok(|b| b.freeze());
+                    Ok(mut file) => {
+                        let mut response = hyper::Response::builder()
+                            .header(hyper::header::ACCEPT_RANGES, "bytes");
+                        if let Some(etag) = etag {
+                            response = response.header(hyper::header::ETAG, etag);
+                        }
+                        let (response, content_length) = match byte_range {
+                            None => (response, file_length),
+                            Some((start, end)) => {
+                                if file.seek(SeekFrom::Start(start)).await.is_err() {
+                                    return Ok(Self::internal_server_error());
+                                }
+                                (
+                                    response.status(hyper::StatusCode::PARTIAL_CONTENT).header(
+                                        hyper::header::CONTENT_RANGE,
+                                        format!("bytes {}-{}/{}", start, end, file_length),
+                                    ),
+                                    end - start + 1,
+                                )
+                            }
+                        };
+                        let stream = FramedRead::new(file.take(content_length), BytesCodec::new())
+                            .map_ok(|b| b.freeze());
                         let body = hyper::Body::wrap_stream(stream);
 
-                        Ok(hyper::Response::builder()
-                            .header(hyper::header::CONTENT_LENGTH, file_length)
+                        Ok(response
+                            .header(hyper::header::CONTENT_LENGTH, content_length)
                             .body(body)
                             .unwrap())
                     }
@@ -152,6 +290,17 @@ impl RpcRequestMiddleware {
         }
     }
 
+    fn range_not_satisfiable(file_length: u64) -> hyper::Response<hyper::Body> {
+        hyper::Response::builder()
+            .status(hyper::StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(
+                hyper::header::CONTENT_RANGE,
+                format!("bytes */{}", file_length),
+            )
+            .body(hyper::Body::empty())
+            .unwrap()
+    }
+
     fn health_check(&self) -> &'static str {
         let response = match self.health.check() {
             RpcHealthStatus::Ok => "ok",
@@ -195,7 +344,11 @@ impl RequestMiddleware for RpcRequestMiddleware {
                 .unwrap()
                 .into()
         } else if self.is_file_get_path(request.uri().path()) {
-            self.process_file_get(request.uri().path())
+            let range = request
+                .headers()
+                .get(hyper::header::RANGE)
+                .and_then(|range| range.to_str().ok());
+            self.process_file_get(request.uri().path(), range)
         } else if request.uri().path() == "/health" {
             hyper::Response::builder()
                 .status(hyper::StatusCode::OK)
@@ -248,6 +401,7 @@ impl JsonRpcService {
         trusted_validators: Option<HashSet<Pubkey>>,
         override_health_check: Arc<AtomicBool>,
         optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
+        leader_schedule_cache: Arc<LeaderScheduleCache>,
         send_transaction_retry_ms: u64,
         send_transaction_leader_forward_count: u64,
     ) -> Self {
@@ -276,7 +430,8 @@ impl JsonRpcService {
         let (bigtable_ledger_storage, _bigtable_ledger_upload_service) =
             if config.enable_bigtable_ledger_storage || config.enable_bigtable_ledger_upload {
                 runtime
-                    .block_on(solana_storage_bigtable::LedgerStorage::new(
+                    .block_on(solana_storage_bigtable::LedgerStorage::new_with_local_path(
+                        config.bigtable_ledger_local_storage.as_deref(),
                         !config.enable_bigtable_ledger_upload,
                         config.rpc_bigtable_timeout,
                     ))
@@ -309,6 +464,9 @@ impl JsonRpcService {
                 (None, None)
             };
 
+        let rate_limiter = RpcRateLimiter::new(config.rate_limit.clone());
+        let trusted_proxies = config.rate_limit.trusted_proxies.clone();
+
         let (request_processor, receiver) = JsonRpcRequestProcessor::new(
             config,
             snapshot_config.clone(),
@@ -322,6 +480,7 @@ impl JsonRpcService {
             runtime,
             bigtable_ledger_storage,
             optimistically_confirmed_bank,
+            leader_schedule_cache,
         );
 
         let leader_info =
@@ -331,6 +490,7 @@ impl JsonRpcService {
             &bank_forks,
             leader_info,
             receiver,
+            request_processor.send_transaction_queue_status(),
             send_transaction_retry_ms,
             send_transaction_leader_forward_count,
         ));
@@ -343,10 +503,9 @@ impl JsonRpcService {
         // sadly, some parts of our current rpc implemention block the jsonrpc's
         // _socket-listening_ event loop for too long, due to (blocking) long IO or intesive CPU,
         // causing no further processing of incoming requests and ultimatily innocent clients timing-out.
-        // So create a (shared) multi-threaded event_loop for jsonrpc and set its .threads() to 1,
-        // so that we avoid the single-threaded event loops from being created automatically by
-        // jsonrpc for threads when .threads(N > 1) is given.
-        let event_loop = {
+        // So create a (shared) multi-threaded event_loop that both accepts connections and serves
+        // every connection's requests.
+        let mut event_loop = {
             // Stuck on tokio 0.2 until the jsonrpc crates upgrade
             tokio_02::runtime::Builder::new()
                 .core_threads(rpc_threads)
@@ -361,43 +520,48 @@ impl JsonRpcService {
         let thread_hdl = Builder::new()
             .name("solana-jsonrpc".to_string())
             .spawn(move || {
-                let mut io = MetaIoHandler::default();
+                let mut io = MetaIoHandler::with_middleware(rate_limiter);
                 let rpc = RpcSolImpl;
                 io.extend_with(rpc.to_delegate());
-
-                let request_middleware = RpcRequestMiddleware::new(
-                    ledger_path,
-                    snapshot_config,
-                    bank_forks.clone(),
-                    health.clone(),
-                );
-                let server = ServerBuilder::with_meta_extractor(
-                    io,
-                    move |_req: &hyper::Request<hyper::Body>| request_processor.clone(),
-                )
-                .event_loop_executor(event_loop.handle().clone())
-                .threads(1)
-                .cors(DomainsValidation::AllowOnly(vec![
-                    AccessControlAllowOrigin::Any,
-                ]))
-                .cors_max_age(86400)
-                .request_middleware(request_middleware)
-                .max_request_body_size(MAX_REQUEST_PAYLOAD_SIZE)
-                .start_http(&rpc_addr);
-
-                if let Err(e) = server {
-                    warn!(
-                        "JSON RPC service unavailable error: {:?}. \n\
-                           Also, check that port {} is not already in use by another application",
-                        e,
-                        rpc_addr.port()
-                    );
-                    return;
-                }
-
-                let server = server.unwrap();
-                close_handle_sender.send(server.close_handle()).unwrap();
-                server.wait();
+                let rpc = Rpc {
+                    handler: Arc::new(io),
+                    extractor: Arc::new(move |req: &hyper::Request<hyper::Body>| {
+                        let peer = req.extensions().get::<SocketAddr>().copied();
+                        let client_ip = client_address(peer, req.headers(), &trusted_proxies);
+                        request_processor.clone().with_client_ip(client_ip)
+                    }),
+                };
+
+                let request_middleware: Arc<dyn RequestMiddleware> =
+                    Arc::new(RpcRequestMiddleware::new(
+                        ledger_path,
+                        snapshot_config,
+                        bank_forks.clone(),
+                        health.clone(),
+                    ));
+                let listener = match event_loop.block_on(TcpListener::bind(&rpc_addr)) {
+                    Ok(listener) => listener,
+                    Err(e) => {
+                        warn!(
+                            "JSON RPC service unavailable error: {:?}. \n\
+                               Also, check that port {} is not already in use by another application",
+                            e,
+                            rpc_addr.port()
+                        );
+                        return;
+                    }
+                };
+
+                let (close_sender, close_receiver) = oneshot::channel();
+                close_handle_sender
+                    .send(RpcCloseHandle(Arc::new(Mutex::new(Some(close_sender)))))
+                    .unwrap();
+                event_loop.block_on(Self::serve(
+                    listener,
+                    rpc,
+                    request_middleware,
+                    close_receiver,
+                ));
                 exit_bigtable_ledger_upload_service.store(true, Ordering::Relaxed);
             })
             .unwrap();
@@ -417,6 +581,57 @@ impl JsonRpcService {
         }
     }
 
+    /// Serve JSON RPC requests over HTTP until `close_receiver` fires.  Connections are accepted
+    /// here rather than by `jsonrpc_http_server`'s server, which doesn't hand the peer address of
+    /// a request to the metadata extractor, so that rate limits can tell clients apart.
+    async fn serve(
+        mut listener: TcpListener,
+        rpc: Rpc<JsonRpcRequestProcessor, RpcRateLimiter>,
+        request_middleware: Arc<dyn RequestMiddleware>,
+        mut close_receiver: oneshot::Receiver<()>,
+    ) {
+        let mut http = Http::new();
+        http.http1_only(true).http1_keep_alive(true);
+        loop {
+            let (stream, peer) = tokio_02::select! {
+                _ = &mut close_receiver => break,
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        // Such as running out of file descriptors; back off instead of spinning
+                        warn!("JSON RPC service failed to accept a connection: {:?}", err);
+                        tokio_02::time::delay_for(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                },
+            };
+            let _ = stream.set_nodelay(true);
+            let mut handler = ServerHandler::new(
+                rpc.downgrade(),
+                Some(vec![AccessControlAllowOrigin::Any]),
+                Some(86400),
+                AccessControlAllowHeaders::Any,
+                None,
+                request_middleware.clone(),
+                RestApi::Disabled,
+                None,
+                MAX_REQUEST_PAYLOAD_SIZE,
+                true,
+            );
+            // The metadata extractor reads the peer address from the request
+            let service = service_fn(move |mut request: hyper::Request<hyper::Body>| {
+                request.extensions_mut().insert(peer);
+                handler.call(request)
+            });
+            let connection = http.serve_connection(stream, service);
+            tokio_02::spawn(async move {
+                if let Err(err) = connection.await {
+                    debug!("JSON RPC connection from {} failed: {:?}", peer, err);
+                }
+            });
+        }
+    }
+
     pub fn exit(&mut self) {
         if let Some(c) = self.close_handle.take() {
             c.close()
@@ -459,6 +674,7 @@ mod tests {
             ip_addr,
             solana_net_utils::find_available_port_in_range(ip_addr, (10000, 65535)).unwrap(),
         );
+        let leader_schedule_cache = Arc::new(LeaderScheduleCache::new_from_bank(&bank));
         let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
         let ledger_path = get_tmp_ledger_path!();
         let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
@@ -480,6 +696,7 @@ mod tests {
             None,
             Arc::new(AtomicBool::new(false)),
             optimistically_confirmed_bank,
+            leader_schedule_cache,
             1000,
             1,
         );
@@ -491,6 +708,7 @@ mod tests {
             rpc_service
                 .request_processor
                 .get_balance(&mint_keypair.pubkey(), None)
+                .unwrap()
                 .value
         );
         rpc_service.exit();
@@ -530,6 +748,7 @@ mod tests {
             PathBuf::from("/"),
             Some(SnapshotConfig {
                 snapshot_interval_slots: 0,
+                incremental_snapshot_interval_slots: 0,
                 snapshot_package_output_path: PathBuf::from("/"),
                 snapshot_path: PathBuf::from("/"),
                 archive_format: ArchiveFormat::TarBzip2,
@@ -561,12 +780,75 @@ mod tests {
         assert!(!rrm.is_file_get_path(
             "/snapshot-notaslotnumber-AvFf9oS8A8U78HdjT9YG2sTTThLHJZmhaMn2g8vkWYnr.tar.bz2"
         ));
+        assert!(rrm_with_snapshot_config.is_file_get_path(
+            "/incremental-snapshot-100-200-AvFf9oS8A8U78HdjT9YG2sTTThLHJZmhaMn2g8vkWYnr.tar.zst"
+        ));
 
         assert!(!rrm.is_file_get_path("/"));
         assert!(!rrm.is_file_get_path(".."));
         assert!(!rrm.is_file_get_path("🎣"));
     }
 
+    #[test]
+    fn test_etag() {
+        let rrm = RpcRequestMiddleware::new(
+            PathBuf::from("/"),
+            None,
+            create_bank_forks(),
+            RpcHealth::stub(),
+        );
+        let dir = tempfile::TempDir::new().unwrap();
+        let filename = dir.path().join("genesis.tar.bz2");
+        std::fs::write(&filename, b"genesis").unwrap();
+
+        // Served without an ETag until the file is hashed
+        assert_eq!(rrm.etag(&filename), None);
+        let expected_etag = format!("\"{}\"", solana_sdk::hash::hash(b"genesis"));
+        let mut etag = None;
+        for _ in 0..100 {
+            etag = rrm.etag(&filename);
+            if etag.is_some() {
+                break;
+            }
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(etag, Some(expected_etag));
+        assert_eq!(rrm.etag(&dir.path().join("missing")), None);
+    }
+
+    #[test]
+    fn test_parse_byte_range() {
+        assert_eq!(
+            RpcRequestMiddleware::parse_byte_range("bytes=0-", 100),
+            Some((0, 99))
+        );
+        assert_eq!(
+            RpcRequestMiddleware::parse_byte_range("bytes=10-19", 100),
+            Some((10, 19))
+        );
+        assert_eq!(
+            RpcRequestMiddleware::parse_byte_range("bytes=90-200", 100),
+            Some((90, 99))
+        );
+        assert_eq!(
+            RpcRequestMiddleware::parse_byte_range("bytes=100-", 100),
+            None
+        );
+        assert_eq!(
+            RpcRequestMiddleware::parse_byte_range("bytes=20-10", 100),
+            None
+        );
+        assert_eq!(
+            RpcRequestMiddleware::parse_byte_range("bytes=-10", 100),
+            None
+        );
+        assert_eq!(
+            RpcRequestMiddleware::parse_byte_range("items=0-10", 100),
+            None
+        );
+        assert_eq!(RpcRequestMiddleware::parse_byte_range("bytes=0-", 0), None);
+    }
+
     #[test]
     fn test_health_check_with_no_trusted_validators() {
         let rm = RpcRequestMiddleware::new(
@@ -3,6 +3,7 @@
 use crate::{
     optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
     rpc::{get_parsed_token_account, get_parsed_token_accounts},
+    rpc_pubsub_service::PubSubConfig,
 };
 use core::hash::Hash;
 use jsonrpc_pubsub::{
@@ -10,15 +11,22 @@ use jsonrpc_pubsub::{
     SubscriptionId,
 };
 use serde::Serialize;
-use solana_account_decoder::{parse_token::spl_token_id_v2_0, UiAccount, UiAccountEncoding};
+use solana_account_decoder::{
+    parse_token::spl_token_id_v2_0, UiAccount, UiAccountEncoding, UiDataSliceConfig,
+};
 use solana_client::{
-    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig},
+    rpc_config::{
+        RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcProgramAccountsConfig,
+        RpcSignatureSubscribeConfig,
+    },
     rpc_filter::RpcFilterType,
     rpc_response::{
-        ProcessedSignatureResult, ReceivedSignatureResult, Response, RpcKeyedAccount,
-        RpcLogsResponse, RpcResponseContext, RpcSignatureResult, SlotInfo,
+        ProcessedSignatureResult, ReceivedSignatureResult, Response, RpcBlockUpdate,
+        RpcBlockUpdateError, RpcKeyedAccount, RpcLogsResponse, RpcProgramNotification,
+        RpcRemovedAccount, RpcResponseContext, RpcSignatureResult, SlotInfo, SlotUpdate,
     },
 };
+use solana_ledger::{blockstore::Blockstore, blockstore_db::BlockstoreError};
 use solana_measure::measure::Measure;
 use solana_runtime::{
     bank::{
@@ -35,6 +43,7 @@ use solana_sdk::{
     signature::Signature,
     transaction,
 };
+use solana_transaction_status::{ConfirmedBlock, TransactionDetails, UiTransactionEncoding};
 use solana_vote_program::vote_state::Vote;
 use std::{
     collections::{HashMap, HashSet},
@@ -50,6 +59,9 @@ use std::{
 
 const RECEIVE_DELAY_MILLIS: u64 = 100;
 
+// Number of notifications a block that fails to read is retried on before it is given up on
+const MAX_BLOCK_READ_RETRIES: usize = 32;
+
 trait BankGetTransactionLogsAdapter {
     fn get_transaction_logs_adapter(
         &self,
@@ -72,6 +84,29 @@ impl BankGetTransactionLogsAdapter for Bank {
     }
 }
 
+trait BankGetProgramAccountsChangedAdapter {
+    fn get_program_accounts_changed_since_parent(
+        &self,
+        program_id: &Pubkey,
+    ) -> (Vec<(Pubkey, Account)>, Vec<Pubkey>);
+}
+
+impl BankGetProgramAccountsChangedAdapter for Bank {
+    // Returns the live accounts owned by the program that were modified in this bank, along with
+    // the accounts it owned in the parent bank that were closed or reassigned in this bank
+    fn get_program_accounts_changed_since_parent(
+        &self,
+        program_id: &Pubkey,
+    ) -> (Vec<(Pubkey, Account)>, Vec<Pubkey>) {
+        let mut accounts = self.get_program_accounts_modified_since_parent(program_id);
+        accounts.retain(|(_, account)| account.lamports > 0);
+        (
+            accounts,
+            self.get_program_accounts_removed_since_parent(program_id),
+        )
+    }
+}
+
 // A more human-friendly version of Vote, with the bank state signature base58 encoded.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RpcVote {
@@ -82,6 +117,7 @@ pub struct RpcVote {
 
 enum NotificationEntry {
     Slot(SlotInfo),
+    SlotUpdate(SlotUpdate),
     Vote(Vote),
     Root(Slot),
     Bank(CommitmentSlots),
@@ -95,6 +131,9 @@ impl std::fmt::Debug for NotificationEntry {
             NotificationEntry::Root(root) => write!(f, "Root({})", root),
             NotificationEntry::Vote(vote) => write!(f, "Vote({:?})", vote),
             NotificationEntry::Slot(slot_info) => write!(f, "Slot({:?})", slot_info),
+            NotificationEntry::SlotUpdate(slot_update) => {
+                write!(f, "SlotUpdate({:?})", slot_update)
+            }
             NotificationEntry::Bank(commitment_slots) => {
                 write!(f, "Bank({{slot: {:?}}})", commitment_slots.slot)
             }
@@ -113,15 +152,31 @@ struct SubscriptionData<S, T> {
     config: Option<T>,
 }
 #[derive(Default, Clone)]
+struct AccountConfig {
+    encoding: Option<UiAccountEncoding>,
+    data_slice: Option<UiDataSliceConfig>,
+}
+#[derive(Default, Clone)]
 struct ProgramConfig {
     filters: Vec<RpcFilterType>,
     encoding: Option<UiAccountEncoding>,
+    data_slice: Option<UiDataSliceConfig>,
+    // Accounts last notified as matching `filters`, shared by every clone of the config so that
+    // an account that stops matching can be reported as removed. An entry is dropped when its
+    // removal is notified, so the set never outgrows the program's accounts that currently match.
+    // It starts out empty: accounts that matched before the subscription and were never notified
+    // since are not reported as removed.
+    matching_accounts: Arc<RwLock<HashSet<Pubkey>>>,
+}
+#[derive(Clone)]
+struct BlockSubscriptionConfig {
+    mentions: Option<Pubkey>,
+    encoding: UiTransactionEncoding,
+    transaction_details: TransactionDetails,
+    show_rewards: bool,
 }
 type RpcAccountSubscriptions = RwLock<
-    HashMap<
-        Pubkey,
-        HashMap<SubscriptionId, SubscriptionData<Response<UiAccount>, UiAccountEncoding>>,
-    >,
+    HashMap<Pubkey, HashMap<SubscriptionId, SubscriptionData<Response<UiAccount>, AccountConfig>>>,
 >;
 type RpcLogsSubscriptions = RwLock<
     HashMap<
@@ -132,7 +187,7 @@ type RpcLogsSubscriptions = RwLock<
 type RpcProgramSubscriptions = RwLock<
     HashMap<
         Pubkey,
-        HashMap<SubscriptionId, SubscriptionData<Response<RpcKeyedAccount>, ProgramConfig>>,
+        HashMap<SubscriptionId, SubscriptionData<Response<RpcProgramNotification>, ProgramConfig>>,
     >,
 >;
 type RpcSignatureSubscriptions = RwLock<
@@ -141,7 +196,11 @@ type RpcSignatureSubscriptions = RwLock<
         HashMap<SubscriptionId, SubscriptionData<Response<RpcSignatureResult>, bool>>,
     >,
 >;
+type RpcBlockSubscriptions = RwLock<
+    HashMap<SubscriptionId, SubscriptionData<Response<RpcBlockUpdate>, BlockSubscriptionConfig>>,
+>;
 type RpcSlotSubscriptions = RwLock<HashMap<SubscriptionId, Sink<SlotInfo>>>;
+type RpcSlotsUpdatesSubscriptions = RwLock<HashMap<SubscriptionId, Sink<SlotUpdate>>>;
 type RpcVoteSubscriptions = RwLock<HashMap<SubscriptionId, Sink<RpcVote>>>;
 type RpcRootSubscriptions = RwLock<HashMap<SubscriptionId, Sink<Slot>>>;
 
@@ -273,7 +332,7 @@ fn filter_account_result(
     result: Option<(Account, Slot)>,
     pubkey: &Pubkey,
     last_notified_slot: Slot,
-    encoding: Option<UiAccountEncoding>,
+    config: Option<AccountConfig>,
     bank: Arc<Bank>,
 ) -> (Box<dyn Iterator<Item = UiAccount>>, Slot) {
     // If the account is not found, `last_modified_slot` will default to zero and
@@ -283,12 +342,17 @@ fn filter_account_result(
     // If last_modified_slot < last_notified_slot this means that we last notified for a fork
     // and should notify that the account state has been reverted.
     let results: Box<dyn Iterator<Item = UiAccount>> = if last_modified_slot != last_notified_slot {
-        let encoding = encoding.unwrap_or(UiAccountEncoding::Binary);
+        let config = config.unwrap_or_default();
+        let encoding = config.encoding.unwrap_or(UiAccountEncoding::Binary);
         if account.owner == spl_token_id_v2_0() && encoding == UiAccountEncoding::JsonParsed {
             Box::new(iter::once(get_parsed_token_account(bank, pubkey, account)))
         } else {
             Box::new(iter::once(UiAccount::encode(
-                pubkey, account, encoding, None, None,
+                pubkey,
+                account,
+                encoding,
+                None,
+                config.data_slice,
             )))
         }
     } else {
@@ -314,36 +378,75 @@ fn filter_signature_result(
 }
 
 fn filter_program_results(
-    accounts: Vec<(Pubkey, Account)>,
+    (accounts, removed_accounts): (Vec<(Pubkey, Account)>, Vec<Pubkey>),
     program_id: &Pubkey,
     last_notified_slot: Slot,
     config: Option<ProgramConfig>,
     bank: Arc<Bank>,
-) -> (Box<dyn Iterator<Item = RpcKeyedAccount>>, Slot) {
-    let config = config.unwrap_or_default();
-    let encoding = config.encoding.unwrap_or(UiAccountEncoding::Binary);
-    let filters = config.filters;
-    let accounts_is_empty = accounts.is_empty();
-    let keyed_accounts = accounts.into_iter().filter(move |(_, account)| {
-        filters.iter().all(|filter_type| match filter_type {
-            RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
-            RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data),
+) -> (Box<dyn Iterator<Item = RpcProgramNotification>>, Slot) {
+    let ProgramConfig {
+        filters,
+        encoding,
+        data_slice,
+        matching_accounts,
+    } = config.unwrap_or_default();
+    let encoding = encoding.unwrap_or(UiAccountEncoding::Binary);
+    let (keyed_accounts, unmatched_accounts): (Vec<_>, Vec<_>) =
+        accounts.into_iter().partition(|(_, account)| {
+            filters.iter().all(|filter_type| match filter_type {
+                RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
+                RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data),
+            })
+        });
+    // Without filters every account owned by the program matches, so there is nothing to track
+    let removed_accounts: Vec<_> = if filters.is_empty() {
+        removed_accounts
+    } else {
+        // Only accounts notified as matching are reported as removed, and forgotten once they are
+        let mut matching_accounts = matching_accounts.write().unwrap();
+        matching_accounts.extend(keyed_accounts.iter().map(|(pubkey, _)| *pubkey));
+        unmatched_accounts
+            .into_iter()
+            .map(|(pubkey, _)| pubkey)
+            .chain(removed_accounts)
+            .filter(|pubkey| matching_accounts.remove(pubkey))
+            .collect()
+    };
+    let removed_accounts = removed_accounts.into_iter().map(|pubkey| {
+        RpcProgramNotification::Removed(RpcRemovedAccount {
+            pubkey: pubkey.to_string(),
+            removed: true,
         })
     });
     let accounts: Box<dyn Iterator<Item = RpcKeyedAccount>> = if program_id == &spl_token_id_v2_0()
         && encoding == UiAccountEncoding::JsonParsed
-        && !accounts_is_empty
+        && !keyed_accounts.is_empty()
     {
-        Box::new(get_parsed_token_accounts(bank, keyed_accounts))
+        Box::new(get_parsed_token_accounts(bank, keyed_accounts.into_iter()))
     } else {
         Box::new(
-            keyed_accounts.map(move |(pubkey, account)| RpcKeyedAccount {
-                pubkey: pubkey.to_string(),
-                account: UiAccount::encode(&pubkey, account, encoding.clone(), None, None),
-            }),
+            keyed_accounts
+                .into_iter()
+                .map(move |(pubkey, account)| RpcKeyedAccount {
+                    pubkey: pubkey.to_string(),
+                    account: UiAccount::encode(
+                        &pubkey,
+                        account,
+                        encoding.clone(),
+                        None,
+                        data_slice,
+                    ),
+                }),
         )
     };
-    (accounts, last_notified_slot)
+    (
+        Box::new(
+            accounts
+                .map(RpcProgramNotification::Account)
+                .chain(removed_accounts),
+        ),
+        last_notified_slot,
+    )
 }
 
 fn filter_logs_results(
@@ -366,6 +469,61 @@ fn filter_logs_results(
     }
 }
 
+// Drops the transactions of `block` that do not reference `mentions`, either directly or through
+// an address lookup; returns `None` if no transaction is left
+fn filter_block_transactions(
+    mut block: ConfirmedBlock,
+    mentions: Option<&Pubkey>,
+) -> Option<ConfirmedBlock> {
+    if let Some(mentions) = mentions {
+        block.transactions.retain(|transaction_with_meta| {
+            transaction_with_meta
+                .transaction
+                .message
+                .static_account_keys()
+                .contains(mentions)
+                || transaction_with_meta
+                    .meta
+                    .as_ref()
+                    .and_then(|meta| meta.loaded_addresses.as_ref())
+                    .map(|loaded_addresses| {
+                        loaded_addresses.writable.contains(mentions)
+                            || loaded_addresses.readonly.contains(mentions)
+                    })
+                    .unwrap_or(false)
+        });
+        if block.transactions.is_empty() {
+            return None;
+        }
+    }
+    Some(block)
+}
+
+fn filter_block_result(
+    block: Option<ConfirmedBlock>,
+    slot: Slot,
+    config: &BlockSubscriptionConfig,
+) -> Option<RpcBlockUpdate> {
+    match block {
+        Some(block) => {
+            filter_block_transactions(block, config.mentions.as_ref()).map(|block| RpcBlockUpdate {
+                slot,
+                block: Some(block.configure(
+                    config.encoding,
+                    config.transaction_details,
+                    config.show_rewards,
+                )),
+                err: None,
+            })
+        }
+        None => Some(RpcBlockUpdate {
+            slot,
+            block: None,
+            err: Some(RpcBlockUpdateError::BlockStoreError),
+        }),
+    }
+}
+
 fn total_nested_subscriptions<K, L, V>(
     subscription_map: &RwLock<HashMap<K, HashMap<L, V>>>,
 ) -> usize {
@@ -386,7 +544,10 @@ struct Subscriptions {
     gossip_logs_subscriptions: Arc<RpcLogsSubscriptions>,
     gossip_program_subscriptions: Arc<RpcProgramSubscriptions>,
     gossip_signature_subscriptions: Arc<RpcSignatureSubscriptions>,
+    block_subscriptions: Arc<RpcBlockSubscriptions>,
+    gossip_block_subscriptions: Arc<RpcBlockSubscriptions>,
     slot_subscriptions: Arc<RpcSlotSubscriptions>,
+    slots_updates_subscriptions: Arc<RpcSlotsUpdatesSubscriptions>,
     vote_subscriptions: Arc<RpcVoteSubscriptions>,
     root_subscriptions: Arc<RpcRootSubscriptions>,
 }
@@ -402,7 +563,10 @@ impl Subscriptions {
         total += total_nested_subscriptions(&self.gossip_logs_subscriptions);
         total += total_nested_subscriptions(&self.gossip_program_subscriptions);
         total += total_nested_subscriptions(&self.gossip_signature_subscriptions);
+        total += self.block_subscriptions.read().unwrap().len();
+        total += self.gossip_block_subscriptions.read().unwrap().len();
         total += self.slot_subscriptions.read().unwrap().len();
+        total += self.slots_updates_subscriptions.read().unwrap().len();
         total += self.vote_subscriptions.read().unwrap().len();
         total += self.root_subscriptions.read().unwrap().len();
         total
@@ -418,6 +582,7 @@ pub struct RpcSubscriptions {
     optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
     exit: Arc<AtomicBool>,
     enable_vote_subscription: bool,
+    blockstore: Option<Arc<Blockstore>>,
 }
 
 impl Drop for RpcSubscriptions {
@@ -451,6 +616,30 @@ impl RpcSubscriptions {
         optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
         enable_vote_subscription: bool,
     ) -> Self {
+        Self::new_with_config(
+            exit,
+            bank_forks,
+            block_commitment_cache,
+            optimistically_confirmed_bank,
+            None,
+            &PubSubConfig {
+                enable_vote_subscription,
+                ..PubSubConfig::default()
+            },
+        )
+    }
+
+    /// Block subscriptions are only available if `config` enables them and a `blockstore` is
+    /// provided to read the blocks from
+    pub fn new_with_config(
+        exit: &Arc<AtomicBool>,
+        bank_forks: Arc<RwLock<BankForks>>,
+        block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+        optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
+        blockstore: Option<Arc<Blockstore>>,
+        config: &PubSubConfig,
+    ) -> Self {
+        let blockstore = blockstore.filter(|_| config.enable_block_subscription);
         let (notification_sender, notification_receiver): (
             Sender<NotificationEntry>,
             Receiver<NotificationEntry>,
@@ -464,13 +653,17 @@ impl RpcSubscriptions {
         let gossip_logs_subscriptions = Arc::new(RpcLogsSubscriptions::default());
         let gossip_program_subscriptions = Arc::new(RpcProgramSubscriptions::default());
         let gossip_signature_subscriptions = Arc::new(RpcSignatureSubscriptions::default());
+        let block_subscriptions = Arc::new(RpcBlockSubscriptions::default());
+        let gossip_block_subscriptions = Arc::new(RpcBlockSubscriptions::default());
         let slot_subscriptions = Arc::new(RpcSlotSubscriptions::default());
+        let slots_updates_subscriptions = Arc::new(RpcSlotsUpdatesSubscriptions::default());
         let vote_subscriptions = Arc::new(RpcVoteSubscriptions::default());
         let root_subscriptions = Arc::new(RpcRootSubscriptions::default());
         let notification_sender = Arc::new(Mutex::new(notification_sender));
 
         let _bank_forks = bank_forks.clone();
         let _block_commitment_cache = block_commitment_cache.clone();
+        let _blockstore = blockstore.clone();
         let exit_clone = exit.clone();
         let subscriptions = Subscriptions {
             account_subscriptions,
@@ -481,7 +674,10 @@ impl RpcSubscriptions {
             gossip_logs_subscriptions,
             gossip_program_subscriptions,
             gossip_signature_subscriptions,
+            block_subscriptions,
+            gossip_block_subscriptions,
             slot_subscriptions,
+            slots_updates_subscriptions,
             vote_subscriptions,
             root_subscriptions,
         };
@@ -497,6 +693,7 @@ impl RpcSubscriptions {
                     notification_receiver,
                     _subscriptions,
                     _bank_forks,
+                    _blockstore,
                 );
             })
             .unwrap();
@@ -509,7 +706,8 @@ impl RpcSubscriptions {
             block_commitment_cache,
             optimistically_confirmed_bank,
             exit: exit.clone(),
-            enable_vote_subscription,
+            enable_vote_subscription: config.enable_vote_subscription,
+            blockstore,
         }
     }
 
@@ -577,7 +775,7 @@ impl RpcSubscriptions {
             program_id,
             bank_forks,
             commitment_slots,
-            Bank::get_program_accounts_modified_since_parent,
+            Bank::get_program_accounts_changed_since_parent,
             filter_program_results,
             notifier,
         )
@@ -668,7 +866,10 @@ impl RpcSubscriptions {
             sub_id,
             subscriber,
             last_notified_slot,
-            config.encoding,
+            Some(AccountConfig {
+                encoding: config.encoding,
+                data_slice: config.data_slice,
+            }),
         );
     }
 
@@ -691,7 +892,7 @@ impl RpcSubscriptions {
         program_id: Pubkey,
         config: Option<RpcProgramAccountsConfig>,
         sub_id: SubscriptionId,
-        subscriber: Subscriber<Response<RpcKeyedAccount>>,
+        subscriber: Subscriber<Response<RpcProgramNotification>>,
     ) {
         let config = config.unwrap_or_default();
         let commitment = config
@@ -718,6 +919,8 @@ impl RpcSubscriptions {
             Some(ProgramConfig {
                 filters: config.filters.unwrap_or_default(),
                 encoding: config.account_config.encoding,
+                data_slice: config.account_config.data_slice,
+                matching_accounts: Arc::default(),
             }),
         );
     }
@@ -903,6 +1106,82 @@ impl RpcSubscriptions {
         self.enqueue_notification(NotificationEntry::Gossip(slot));
     }
 
+    pub fn add_block_subscription(
+        &self,
+        mentions: Option<Pubkey>,
+        config: Option<RpcBlockSubscribeConfig>,
+        sub_id: SubscriptionId,
+        subscriber: Subscriber<Response<RpcBlockUpdate>>,
+    ) {
+        if self.blockstore.is_none() {
+            let _ = subscriber.reject(jsonrpc_core::Error::new(
+                jsonrpc_core::ErrorCode::MethodNotFound,
+            ));
+            return;
+        }
+        let config = config.unwrap_or_default();
+        let commitment = config
+            .commitment
+            .unwrap_or_else(CommitmentConfig::finalized);
+
+        let (mut subscriptions, last_notified_slot) = if commitment.is_confirmed() {
+            (
+                self.subscriptions
+                    .gossip_block_subscriptions
+                    .write()
+                    .unwrap(),
+                self.optimistically_confirmed_bank
+                    .read()
+                    .unwrap()
+                    .bank
+                    .slot(),
+            )
+        } else if commitment.is_finalized() {
+            (
+                self.subscriptions.block_subscriptions.write().unwrap(),
+                self.block_commitment_cache
+                    .read()
+                    .unwrap()
+                    .highest_confirmed_root(),
+            )
+        } else {
+            let _ = subscriber.reject(jsonrpc_core::Error::invalid_params(
+                "Invalid Request: processed commitment is not supported for block subscriptions",
+            ));
+            return;
+        };
+
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        subscriptions.insert(
+            sub_id,
+            SubscriptionData {
+                sink,
+                commitment,
+                last_notified_slot: RwLock::new(last_notified_slot),
+                config: Some(BlockSubscriptionConfig {
+                    mentions,
+                    encoding: config.encoding.unwrap_or(UiTransactionEncoding::Json),
+                    transaction_details: config.transaction_details.unwrap_or_default(),
+                    show_rewards: config.rewards.unwrap_or(true),
+                }),
+            },
+        );
+    }
+
+    pub fn remove_block_subscription(&self, id: &SubscriptionId) -> bool {
+        let mut subscriptions = self.subscriptions.block_subscriptions.write().unwrap();
+        if subscriptions.remove(id).is_some() {
+            true
+        } else {
+            let mut subscriptions = self
+                .subscriptions
+                .gossip_block_subscriptions
+                .write()
+                .unwrap();
+            subscriptions.remove(id).is_some()
+        }
+    }
+
     pub fn add_slot_subscription(&self, sub_id: SubscriptionId, subscriber: Subscriber<SlotInfo>) {
         let sink = subscriber.assign_id(sub_id.clone()).unwrap();
         let mut subscriptions = self.subscriptions.slot_subscriptions.write().unwrap();
@@ -918,6 +1197,33 @@ impl RpcSubscriptions {
         self.enqueue_notification(NotificationEntry::Slot(SlotInfo { slot, parent, root }));
     }
 
+    pub fn add_slots_updates_subscription(
+        &self,
+        sub_id: SubscriptionId,
+        subscriber: Subscriber<SlotUpdate>,
+    ) {
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        let mut subscriptions = self
+            .subscriptions
+            .slots_updates_subscriptions
+            .write()
+            .unwrap();
+        subscriptions.insert(sub_id, sink);
+    }
+
+    pub fn remove_slots_updates_subscription(&self, id: &SubscriptionId) -> bool {
+        let mut subscriptions = self
+            .subscriptions
+            .slots_updates_subscriptions
+            .write()
+            .unwrap();
+        subscriptions.remove(id).is_some()
+    }
+
+    pub fn notify_slot_update(&self, slot_update: SlotUpdate) {
+        self.enqueue_notification(NotificationEntry::SlotUpdate(slot_update));
+    }
+
     pub fn notify_signatures_received(&self, slot_signatures: (Slot, Vec<Signature>)) {
         self.enqueue_notification(NotificationEntry::SignaturesReceived(slot_signatures));
     }
@@ -984,7 +1290,10 @@ impl RpcSubscriptions {
         notification_receiver: Receiver<NotificationEntry>,
         subscriptions: Subscriptions,
         bank_forks: Arc<RwLock<BankForks>>,
+        blockstore: Option<Arc<Blockstore>>,
     ) {
+        let mut block_read_failures = HashMap::new();
+        let mut gossip_block_read_failures = HashMap::new();
         loop {
             if exit.load(Ordering::Relaxed) {
                 break;
@@ -1005,6 +1314,14 @@ impl RpcSubscriptions {
                             notifier.notify(slot_info, sink);
                         }
                     }
+                    NotificationEntry::SlotUpdate(slot_update) => {
+                        let subscriptions =
+                            subscriptions.slots_updates_subscriptions.read().unwrap();
+                        for (_, sink) in subscriptions.iter() {
+                            inc_new_counter_info!("rpc-subscription-notify-slots-updates", 1);
+                            notifier.notify(slot_update.clone(), sink);
+                        }
+                    }
                     // These notifications are only triggered by votes observed on gossip,
                     // unlike `NotificationEntry::Gossip`, which also accounts for slots seen
                     // in VoteState's from bank states built in ReplayStage.
@@ -1053,7 +1370,17 @@ impl RpcSubscriptions {
                             &commitment_slots,
                             &notifier,
                             "bank",
-                        )
+                        );
+                        if let Some(blockstore) = &blockstore {
+                            RpcSubscriptions::notify_blocks(
+                                &subscriptions.block_subscriptions,
+                                commitment_slots.highest_confirmed_root,
+                                &bank_forks,
+                                blockstore,
+                                &mut block_read_failures,
+                                &notifier,
+                            );
+                        }
                     }
                     NotificationEntry::Gossip(slot) => {
                         Self::process_gossip_notification(
@@ -1062,6 +1389,16 @@ impl RpcSubscriptions {
                             &subscriptions,
                             &bank_forks,
                         );
+                        if let Some(blockstore) = &blockstore {
+                            RpcSubscriptions::notify_blocks(
+                                &subscriptions.gossip_block_subscriptions,
+                                slot,
+                                &bank_forks,
+                                blockstore,
+                                &mut gossip_block_read_failures,
+                                &notifier,
+                            );
+                        }
                     }
                     NotificationEntry::SignaturesReceived(slot_signatures) => {
                         RpcSubscriptions::process_signatures_received(
@@ -1104,6 +1441,113 @@ impl RpcSubscriptions {
         );
     }
 
+    /// Slots of the blocks on the fork of `slot` that are newer than `last_notified_slot`, in
+    /// ascending order
+    fn block_slots_since(
+        last_notified_slot: Slot,
+        slot: Slot,
+        bank_forks: &Arc<RwLock<BankForks>>,
+        blockstore: &Blockstore,
+    ) -> Vec<Slot> {
+        if slot <= last_notified_slot {
+            return vec![];
+        }
+        let mut slots: Vec<Slot> = match bank_forks.read().unwrap().get(slot) {
+            Some(bank) => iter::once(slot)
+                .chain(bank.parents().iter().map(|parent| parent.slot()))
+                .take_while(|parent_slot| *parent_slot > last_notified_slot)
+                .collect(),
+            None => vec![],
+        };
+        slots.reverse();
+
+        // Blocks older than the bank forks root are only known to the blockstore as roots
+        let oldest_bank_slot = slots.first().copied().unwrap_or(slot + 1);
+        let rooted_slots: Vec<Slot> = blockstore
+            .rooted_slot_iterator(last_notified_slot + 1)
+            .map(|rooted_slots| {
+                rooted_slots
+                    .take_while(|rooted_slot| *rooted_slot < oldest_bank_slot)
+                    .collect()
+            })
+            .unwrap_or_default();
+        rooted_slots.into_iter().chain(slots).collect()
+    }
+
+    fn notify_blocks(
+        block_subscriptions: &Arc<RpcBlockSubscriptions>,
+        slot: Slot,
+        bank_forks: &Arc<RwLock<BankForks>>,
+        blockstore: &Blockstore,
+        block_read_failures: &mut HashMap<Slot, usize>,
+        notifier: &RpcNotifier,
+    ) {
+        let subscriptions = block_subscriptions.read().unwrap();
+        // `Err` marks a block that couldn't be read yet. It stays pending for each subscription,
+        // which resumes from it on the next notification, until it has failed
+        // `MAX_BLOCK_READ_RETRIES` times. Cleaned up blocks and blocks given up on are skipped
+        // with an error notification.
+        let mut blocks: HashMap<Slot, Result<Option<ConfirmedBlock>, ()>> = HashMap::new();
+        let mut failures = HashMap::new();
+        for SubscriptionData {
+            sink,
+            last_notified_slot,
+            config,
+            ..
+        } in subscriptions.values()
+        {
+            let config = match config {
+                Some(config) => config,
+                None => continue,
+            };
+            let mut w_last_notified_slot = last_notified_slot.write().unwrap();
+            for block_slot in
+                Self::block_slots_since(*w_last_notified_slot, slot, bank_forks, blockstore)
+            {
+                let block = match blocks.entry(block_slot).or_insert_with(|| {
+                    match blockstore.get_complete_block(block_slot) {
+                        Ok(block) => Ok(Some(block)),
+                        Err(BlockstoreError::SlotCleanedUp) => {
+                            warn!("block {} was cleaned up before it was notified", block_slot);
+                            Ok(None)
+                        }
+                        Err(err) => {
+                            let attempts = block_read_failures.get(&block_slot).unwrap_or(&0) + 1;
+                            if attempts > MAX_BLOCK_READ_RETRIES {
+                                warn!(
+                                    "failed to read block {} {} times, giving up: {:?}",
+                                    block_slot, attempts, err
+                                );
+                                inc_new_counter_info!("rpc-subscription-block-read-give-up", 1);
+                                return Ok(None);
+                            }
+                            warn!("failed to read block {}, will retry: {:?}", block_slot, err);
+                            inc_new_counter_info!("rpc-subscription-block-read-retry", 1);
+                            failures.insert(block_slot, attempts);
+                            Err(())
+                        }
+                    }
+                }) {
+                    Ok(block) => block.clone(),
+                    Err(()) => break,
+                };
+                if let Some(update) = filter_block_result(block, block_slot, config) {
+                    inc_new_counter_info!("rpc-subscription-notify-block", 1);
+                    notifier.notify(
+                        Response {
+                            context: RpcResponseContext { slot: block_slot },
+                            value: update,
+                        },
+                        sink,
+                    );
+                }
+                *w_last_notified_slot = block_slot;
+            }
+        }
+        // Only blocks that are still pending are retried again
+        *block_read_failures = failures;
+    }
+
     fn notify_accounts_logs_programs_signatures(
         account_subscriptions: &Arc<RpcAccountSubscriptions>,
         logs_subscriptions: &Arc<RpcLogsSubscriptions>,
@@ -1278,16 +1722,21 @@ pub(crate) mod tests {
     use jsonrpc_core::futures::StreamExt;
     use jsonrpc_pubsub::typed::Subscriber;
     use serial_test::serial;
+    use solana_ledger::{
+        blockstore::entries_to_test_shreds, entry::next_entry, get_tmp_ledger_path,
+    };
     use solana_runtime::{
         commitment::BlockCommitment,
         genesis_utils::{create_genesis_config, GenesisConfigInfo},
     };
     use solana_sdk::{
+        hash::Hash,
         message::Message,
         signature::{Keypair, Signer},
         system_instruction, system_program, system_transaction,
         transaction::Transaction,
     };
+    use solana_transaction_status::TransactionWithStatusMeta;
     use std::{fmt::Debug, sync::mpsc::channel};
     use tokio::{
         runtime::Runtime,
@@ -1359,6 +1808,8 @@ pub(crate) mod tests {
                 commitment: Some(CommitmentConfig::processed()),
                 encoding: None,
                 data_slice: None,
+                min_context_slot: None,
+                slot: None,
             }),
             create_sub_id.clone(),
             create_sub,
@@ -1418,6 +1869,8 @@ pub(crate) mod tests {
                 commitment: Some(CommitmentConfig::processed()),
                 encoding: None,
                 data_slice: None,
+                min_context_slot: None,
+                slot: None,
             }),
             close_sub_id.clone(),
             close_sub,
@@ -1560,6 +2013,115 @@ pub(crate) mod tests {
             .contains_key(&solana_stake_program::id()));
     }
 
+    #[test]
+    #[serial]
+    fn test_check_program_subscribe_removed_accounts() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(100);
+        let program_id = Pubkey::new_unique();
+        let closed = Pubkey::new_unique();
+        let reassigned = Pubkey::new_unique();
+        let recreated = Pubkey::new_unique();
+        let transient = Pubkey::new_unique();
+        let reassigned_and_written = Pubkey::new_unique();
+        let bank0 = Bank::new(&genesis_config);
+        for pubkey in &[closed, reassigned, recreated] {
+            bank0.store_account(pubkey, &Account::new(1, 0, &program_id));
+        }
+        bank0.store_account(&reassigned_and_written, &Account::new(1, 1, &program_id));
+        let bank0 = Arc::new(bank0);
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        bank1.store_account(&closed, &Account::new(0, 0, &program_id));
+        bank1.store_account(&reassigned, &Account::new(1, 0, &system_program::id()));
+        bank1.store_account(&recreated, &Account::new(0, 0, &program_id));
+        bank1.store_account(&recreated, &Account::new(2, 0, &program_id));
+        bank1.store_account(&transient, &Account::new(0, 0, &program_id));
+        // Reassigned, then written by its new owner in the same slot
+        let mut account = Account::new(1, 1, &system_program::id());
+        account.data[0] = 1;
+        bank1.store_account(&reassigned_and_written, &account);
+
+        let (accounts, mut removed_accounts) =
+            bank1.get_program_accounts_changed_since_parent(&program_id);
+        removed_accounts.sort();
+        let mut expected_removed_accounts = vec![closed, reassigned, reassigned_and_written];
+        expected_removed_accounts.sort();
+        assert_eq!(accounts, vec![(recreated, Account::new(2, 0, &program_id))]);
+        assert_eq!(removed_accounts, expected_removed_accounts);
+
+        let mut bank_forks = BankForks::new_from_banks(&[bank0], 0);
+        bank_forks.insert(bank1);
+        let bank_forks = Arc::new(RwLock::new(bank_forks));
+        let (subscriber, _id_receiver, mut transport_receiver) =
+            Subscriber::new_test("programNotification");
+        let exit = Arc::new(AtomicBool::new(false));
+        let optimistically_confirmed_bank =
+            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
+        let subscriptions = RpcSubscriptions::new(
+            &exit,
+            bank_forks,
+            Arc::new(RwLock::new(BlockCommitmentCache::new_for_tests())),
+            optimistically_confirmed_bank,
+        );
+        subscriptions.add_program_subscription(
+            program_id,
+            Some(RpcProgramAccountsConfig {
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(CommitmentConfig::processed()),
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            }),
+            SubscriptionId::Number(0),
+            subscriber,
+        );
+        subscriptions.notify_subscribers(CommitmentSlots {
+            slot: 1,
+            ..CommitmentSlots::default()
+        });
+
+        let notification = |value| {
+            serde_json::to_string(&json!({
+               "jsonrpc": "2.0",
+               "method": "programNotification",
+               "params": {
+                   "result": {
+                       "context": { "slot": 1 },
+                       "value": value,
+                   },
+                   "subscription": 0,
+               }
+            }))
+            .unwrap()
+        };
+        let mut expected = vec![
+            notification(json!({
+                "account": {
+                    "data": "",
+                    "executable": false,
+                    "lamports": 2,
+                    "owner": program_id.to_string(),
+                    "rentEpoch": 0,
+                },
+                "pubkey": recreated.to_string(),
+            })),
+            notification(json!({ "pubkey": closed.to_string(), "removed": true })),
+            notification(json!({ "pubkey": reassigned.to_string(), "removed": true })),
+            notification(json!({
+                "pubkey": reassigned_and_written.to_string(),
+                "removed": true,
+            })),
+        ];
+        let mut responses = vec![];
+        for _ in 0..expected.len() {
+            let (response, receiver) = robust_poll_or_panic(transport_receiver);
+            responses.push(response);
+            transport_receiver = receiver;
+        }
+        responses.sort();
+        expected.sort();
+        assert_eq!(responses, expected);
+    }
+
     #[test]
     #[serial]
     fn test_check_signature_subscribe() {
@@ -1819,6 +2381,57 @@ pub(crate) mod tests {
             .contains_key(&sub_id));
     }
 
+    #[test]
+    #[serial]
+    fn test_check_slots_updates_subscribe() {
+        let (subscriber, _id_receiver, transport_receiver) =
+            Subscriber::new_test("slotsUpdatesNotification");
+        let sub_id = SubscriptionId::Number(0);
+        let exit = Arc::new(AtomicBool::new(false));
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank = Bank::new(&genesis_config);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+        let optimistically_confirmed_bank =
+            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
+        let subscriptions = RpcSubscriptions::new(
+            &exit,
+            bank_forks,
+            Arc::new(RwLock::new(BlockCommitmentCache::new_for_tests())),
+            optimistically_confirmed_bank,
+        );
+        subscriptions.add_slots_updates_subscription(sub_id.clone(), subscriber);
+        assert_eq!(subscriptions.total(), 1);
+
+        subscriptions.notify_slot_update(SlotUpdate::Dead {
+            slot: 2,
+            parent: 1,
+            timestamp: 1_000,
+            err: "error: InvalidBlock".to_string(),
+        });
+        let (response, _) = robust_poll_or_panic(transport_receiver);
+        let expected = json!({
+           "jsonrpc": "2.0",
+           "method": "slotsUpdatesNotification",
+           "params": {
+               "result": {
+                   "type": "dead",
+                   "slot": 2,
+                   "parent": 1,
+                   "timestamp": 1_000,
+                   "err": "error: InvalidBlock",
+               },
+               "subscription": 0,
+           }
+        });
+        assert_eq!(
+            expected,
+            serde_json::from_str::<serde_json::Value>(&response).unwrap()
+        );
+
+        assert!(subscriptions.remove_slots_updates_subscription(&sub_id));
+        assert_eq!(subscriptions.total(), 0);
+    }
+
     #[test]
     #[serial]
     fn test_check_root_subscribe() {
@@ -1967,6 +2580,8 @@ pub(crate) mod tests {
                 commitment: Some(CommitmentConfig::confirmed()),
                 encoding: None,
                 data_slice: None,
+                min_context_slot: None,
+                slot: None,
             }),
             sub_id0.clone(),
             subscriber0,
@@ -2048,6 +2663,8 @@ pub(crate) mod tests {
                 commitment: Some(CommitmentConfig::confirmed()),
                 encoding: None,
                 data_slice: None,
+                min_context_slot: None,
+                slot: None,
             }),
             sub_id1.clone(),
             subscriber1,
@@ -2090,6 +2707,277 @@ pub(crate) mod tests {
             .contains_key(&alice.pubkey()));
     }
 
+    #[test]
+    #[serial]
+    fn test_check_block_subscribe() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(100);
+        let bank = Bank::new(&genesis_config);
+        let blockhash = bank.last_blockhash();
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+        let bank0 = bank_forks.read().unwrap().get(0).unwrap().clone();
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        bank_forks.write().unwrap().insert(bank1);
+
+        let ledger_path = get_tmp_ledger_path!();
+        {
+            let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
+            let alice = Keypair::new();
+            let tx = system_transaction::transfer(&mint_keypair, &alice.pubkey(), 1, blockhash);
+            let entry = next_entry(&blockhash, 1, vec![tx.clone()]);
+            let shreds = entries_to_test_shreds(vec![entry.clone()], 1, 0, true, 0);
+
+            let exit = Arc::new(AtomicBool::new(false));
+            let subscriptions = RpcSubscriptions::new_with_config(
+                &exit,
+                bank_forks.clone(),
+                Arc::new(RwLock::new(BlockCommitmentCache::new_for_tests())),
+                OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
+                Some(blockstore.clone()),
+                &PubSubConfig {
+                    enable_block_subscription: true,
+                    ..PubSubConfig::default()
+                },
+            );
+
+            let (processed_sub, _id_receiver, _processed_recv) =
+                Subscriber::new_test("blockNotification");
+            subscriptions.add_block_subscription(
+                None,
+                Some(RpcBlockSubscribeConfig {
+                    commitment: Some(CommitmentConfig::processed()),
+                    ..RpcBlockSubscribeConfig::default()
+                }),
+                SubscriptionId::Number(0),
+                processed_sub,
+            );
+            assert_eq!(subscriptions.total(), 0);
+
+            let (sub, _id_receiver, recv) = Subscriber::new_test("blockNotification");
+            subscriptions.add_block_subscription(
+                Some(alice.pubkey()),
+                Some(RpcBlockSubscribeConfig {
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    transaction_details: Some(TransactionDetails::Signatures),
+                    ..RpcBlockSubscribeConfig::default()
+                }),
+                SubscriptionId::Number(1),
+                sub,
+            );
+            assert!(subscriptions
+                .subscriptions
+                .gossip_block_subscriptions
+                .read()
+                .unwrap()
+                .contains_key(&SubscriptionId::Number(1)));
+
+            // A block that can't be read yet stays pending
+            let mut block_read_failures = HashMap::new();
+            RpcSubscriptions::notify_blocks(
+                &subscriptions.subscriptions.gossip_block_subscriptions,
+                1,
+                &bank_forks,
+                &blockstore,
+                &mut block_read_failures,
+                &RpcNotifier,
+            );
+            assert_eq!(
+                block_read_failures,
+                vec![(1, 1)].into_iter().collect::<HashMap<_, _>>()
+            );
+            assert_eq!(
+                *subscriptions
+                    .subscriptions
+                    .gossip_block_subscriptions
+                    .read()
+                    .unwrap()[&SubscriptionId::Number(1)]
+                    .last_notified_slot
+                    .read()
+                    .unwrap(),
+                0
+            );
+
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+            subscriptions.notify_gossip_subscribers(1);
+            let (response, _) = robust_poll_or_panic(recv);
+            let expected = json!({
+               "jsonrpc": "2.0",
+               "method": "blockNotification",
+               "params": {
+                   "result": {
+                       "context": { "slot": 1 },
+                       "value": {
+                           "slot": 1,
+                           "block": {
+                               "previousBlockhash": Hash::default().to_string(),
+                               "blockhash": entry.hash.to_string(),
+                               "parentSlot": 0,
+                               "signatures": [tx.signatures[0].to_string()],
+                               "rewards": [],
+                               "blockTime": null,
+                           },
+                           "err": null,
+                       },
+                   },
+                   "subscription": 1,
+               }
+            });
+            assert_eq!(
+                expected,
+                serde_json::from_str::<serde_json::Value>(&response).unwrap()
+            );
+
+            assert!(subscriptions.remove_block_subscription(&SubscriptionId::Number(1)));
+            assert_eq!(subscriptions.total(), 0);
+
+            // A block that keeps failing to be read is eventually skipped with an error
+            let bank1 = bank_forks.read().unwrap().get(1).unwrap().clone();
+            let bank2 = Bank::new_from_parent(&bank1, &Pubkey::default(), 2);
+            bank_forks.write().unwrap().insert(bank2);
+            let (sub, _id_receiver, recv) = Subscriber::new_test("blockNotification");
+            subscriptions.add_block_subscription(
+                None,
+                Some(RpcBlockSubscribeConfig {
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    ..RpcBlockSubscribeConfig::default()
+                }),
+                SubscriptionId::Number(2),
+                sub,
+            );
+            let mut block_read_failures = vec![(2, MAX_BLOCK_READ_RETRIES)].into_iter().collect();
+            RpcSubscriptions::notify_blocks(
+                &subscriptions.subscriptions.gossip_block_subscriptions,
+                2,
+                &bank_forks,
+                &blockstore,
+                &mut block_read_failures,
+                &RpcNotifier,
+            );
+            assert!(block_read_failures.is_empty());
+            let (response, recv) = robust_poll_or_panic(recv);
+            let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+            assert_eq!(response["params"]["result"]["value"]["slot"], 1);
+            let (response, _) = robust_poll_or_panic(recv);
+            let expected = json!({
+               "jsonrpc": "2.0",
+               "method": "blockNotification",
+               "params": {
+                   "result": {
+                       "context": { "slot": 2 },
+                       "value": {
+                           "slot": 2,
+                           "block": null,
+                           "err": "blockStoreError",
+                       },
+                   },
+                   "subscription": 2,
+               }
+            });
+            assert_eq!(
+                expected,
+                serde_json::from_str::<serde_json::Value>(&response).unwrap()
+            );
+        }
+        Blockstore::destroy(&ledger_path).unwrap();
+    }
+
+    #[test]
+    fn test_filter_block_transactions() {
+        let alice = Keypair::new();
+        let bob = Keypair::new();
+        let tx = system_transaction::transfer(&alice, &bob.pubkey(), 1, Hash::default());
+        let block = ConfirmedBlock {
+            previous_blockhash: Hash::default().to_string(),
+            blockhash: Hash::default().to_string(),
+            parent_slot: 0,
+            transactions: vec![TransactionWithStatusMeta {
+                transaction: tx.into(),
+                meta: None,
+            }],
+            rewards: vec![],
+            block_time: None,
+        };
+
+        assert_eq!(
+            filter_block_transactions(block.clone(), None),
+            Some(block.clone())
+        );
+        assert_eq!(
+            filter_block_transactions(block.clone(), Some(&bob.pubkey())),
+            Some(block.clone())
+        );
+        assert_eq!(
+            filter_block_transactions(block, Some(&Pubkey::new_unique())),
+            None
+        );
+    }
+
+    #[test]
+    fn test_filter_program_results() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(100);
+        let bank = Arc::new(Bank::new(&genesis_config));
+        let program_id = Pubkey::new_unique();
+        let pubkey = Pubkey::new_unique();
+        let config = ProgramConfig {
+            filters: vec![RpcFilterType::DataSize(4)],
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: Some(UiDataSliceConfig {
+                offset: 1,
+                length: 2,
+            }),
+            ..ProgramConfig::default()
+        };
+        let notify_accounts = |accounts, removed_accounts| {
+            let (notifications, _) = filter_program_results(
+                (accounts, removed_accounts),
+                &program_id,
+                0,
+                Some(config.clone()),
+                bank.clone(),
+            );
+            notifications
+                .map(|notification| serde_json::to_value(notification).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let notify = |data: Vec<u8>| {
+            let account = Account {
+                lamports: 1,
+                data,
+                owner: program_id,
+                ..Account::default()
+            };
+            notify_accounts(vec![(pubkey, account)], vec![])
+        };
+
+        let notifications = notify(vec![1, 2, 3, 4]);
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0]["pubkey"], json!(pubkey.to_string()));
+        assert_eq!(
+            notifications[0]["account"]["data"],
+            json!(["AgM=", "base64"])
+        );
+
+        // An account that stops matching the filters is reported as removed only once
+        assert_eq!(
+            notify(vec![1, 2]),
+            vec![json!({"pubkey": pubkey.to_string(), "removed": true})]
+        );
+        assert!(notify(vec![1, 2]).is_empty());
+        assert!(config.matching_accounts.read().unwrap().is_empty());
+
+        // So is a matching account that is closed, while closing an account that never matched
+        // goes unnoticed
+        assert_eq!(notify(vec![1, 2, 3, 4]).len(), 1);
+        assert_eq!(
+            notify_accounts(vec![], vec![pubkey, Pubkey::new_unique()]),
+            vec![json!({"pubkey": pubkey.to_string(), "removed": true})]
+        );
+        assert!(config.matching_accounts.read().unwrap().is_empty());
+    }
+
     #[test]
     fn test_total_nested_subscriptions() {
         let mock_subscriptions = RwLock::new(HashMap::new());
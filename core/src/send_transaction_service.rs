@@ -24,7 +24,7 @@ use std::{
 };
 
 /// Maximum size of the transaction queue
-const MAX_TRANSACTION_QUEUE_SIZE: usize = 10_000; // This seems like a lot but maybe it needs to be bigger one day
+pub const MAX_TRANSACTION_QUEUE_SIZE: usize = 10_000; // This seems like a lot but maybe it needs to be bigger one day
 
 pub struct SendTransactionService {
     thread: JoinHandle<()>,
@@ -35,6 +35,10 @@ pub struct TransactionInfo {
     pub wire_transaction: Vec<u8>,
     pub last_valid_slot: Slot,
     pub durable_nonce_info: Option<(Pubkey, Hash)>,
+    /// Maximum number of times the transaction is rebroadcast after the initial send, or `None`
+    /// to rebroadcast it until it expires
+    pub max_retries: Option<usize>,
+    retries: usize,
 }
 
 impl TransactionInfo {
@@ -43,16 +47,78 @@ impl TransactionInfo {
         wire_transaction: Vec<u8>,
         last_valid_slot: Slot,
         durable_nonce_info: Option<(Pubkey, Hash)>,
+        max_retries: Option<usize>,
     ) -> Self {
         Self {
             signature,
             wire_transaction,
             last_valid_slot,
             durable_nonce_info,
+            max_retries,
+            retries: 0,
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TransactionRetryStatus {
+    pub retries: usize,
+    pub max_retries: Option<usize>,
+    pub last_valid_slot: Slot,
+}
+
+impl From<&TransactionInfo> for TransactionRetryStatus {
+    fn from(transaction_info: &TransactionInfo) -> Self {
+        Self {
+            retries: transaction_info.retries,
+            max_retries: transaction_info.max_retries,
+            last_valid_slot: transaction_info.last_valid_slot,
+        }
+    }
+}
+
+/// The transactions queued by a `SendTransactionService`, as last seen by its retry thread.
+/// Shared with the RPC service so that it can turn away transactions while the queue is full,
+/// and report how many times a queued transaction has been rebroadcast.  Transactions not yet
+/// received by the retry thread are not included, the RPC service bounds its channel to the
+/// service instead.
+#[derive(Default)]
+pub struct SendTransactionQueueStatus {
+    transactions: RwLock<HashMap<Signature, TransactionRetryStatus>>,
+}
+
+impl SendTransactionQueueStatus {
+    pub fn len(&self) -> usize {
+        self.transactions.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() >= MAX_TRANSACTION_QUEUE_SIZE
+    }
+
+    pub fn get(&self, signature: &Signature) -> Option<TransactionRetryStatus> {
+        self.transactions.read().unwrap().get(signature).copied()
+    }
+
+    fn insert(&self, transaction_info: &TransactionInfo) {
+        self.transactions
+            .write()
+            .unwrap()
+            .insert(transaction_info.signature, transaction_info.into());
+    }
+
+    fn update(&self, transactions: &HashMap<Signature, TransactionInfo>) {
+        *self.transactions.write().unwrap() = transactions
+            .iter()
+            .map(|(signature, transaction_info)| (*signature, transaction_info.into()))
+            .collect();
+    }
+}
+
 pub struct LeaderInfo {
     cluster_info: Arc<ClusterInfo>,
     poh_recorder: Arc<Mutex<PohRecorder>>,
@@ -102,6 +168,7 @@ struct ProcessTransactionsResult {
     retried: u64,
     failed: u64,
     retained: u64,
+    max_retries_elapsed: u64,
 }
 
 impl SendTransactionService {
@@ -110,12 +177,14 @@ impl SendTransactionService {
         bank_forks: &Arc<RwLock<BankForks>>,
         leader_info: Option<LeaderInfo>,
         receiver: Receiver<TransactionInfo>,
+        queue_status: Arc<SendTransactionQueueStatus>,
         retry_rate_ms: u64,
         leader_forward_count: u64,
     ) -> Self {
         let thread = Self::retry_thread(
             tpu_address,
             receiver,
+            queue_status,
             bank_forks.clone(),
             leader_info,
             retry_rate_ms,
@@ -127,6 +196,7 @@ impl SendTransactionService {
     fn retry_thread(
         tpu_address: SocketAddr,
         receiver: Receiver<TransactionInfo>,
+        queue_status: Arc<SendTransactionQueueStatus>,
         bank_forks: Arc<RwLock<BankForks>>,
         mut leader_info: Option<LeaderInfo>,
         retry_rate_ms: u64,
@@ -168,6 +238,7 @@ impl SendTransactionService {
                             );
                         }
                         if transactions.len() < MAX_TRANSACTION_QUEUE_SIZE {
+                            queue_status.insert(&transaction_info);
                             transactions.insert(transaction_info.signature, transaction_info);
                         } else {
                             datapoint_warn!("send_transaction_service-queue-overflow");
@@ -189,7 +260,7 @@ impl SendTransactionService {
                             )
                         };
 
-                        let _result = Self::process_transactions(
+                        let result = Self::process_transactions(
                             &working_bank,
                             &root_bank,
                             &send_socket,
@@ -198,6 +269,16 @@ impl SendTransactionService {
                             &leader_info,
                             leader_forward_count,
                         );
+                        queue_status.update(&transactions);
+                        datapoint_info!(
+                            "send_transaction_service-process",
+                            ("rooted", result.rooted, i64),
+                            ("expired", result.expired, i64),
+                            ("retried", result.retried, i64),
+                            ("failed", result.failed, i64),
+                            ("retained", result.retained, i64),
+                            ("max_retries_elapsed", result.max_retries_elapsed, i64)
+                        );
                     }
                     last_status_check = Instant::now();
                     if last_leader_refresh.elapsed().as_millis() > 1000 {
@@ -253,9 +334,19 @@ impl SendTransactionService {
             match working_bank.get_signature_status_slot(signature) {
                 None => {
                     // Transaction is unknown to the working bank, it might have been
-                    // dropped or landed in another fork.  Re-send it
+                    // dropped or landed in another fork.  Re-send it, unless it has
+                    // already been rebroadcast as many times as its sender asked for
+                    if let Some(max_retries) = transaction_info.max_retries {
+                        if transaction_info.retries >= max_retries {
+                            info!("Dropping transaction due to max retries: {}", signature);
+                            result.max_retries_elapsed += 1;
+                            inc_new_counter_info!("send_transaction_service-max_retries", 1);
+                            return false;
+                        }
+                    }
                     info!("Retrying transaction: {}", signature);
                     result.retried += 1;
+                    transaction_info.retries += 1;
                     inc_new_counter_info!("send_transaction_service-retry", 1);
                     let addresses = leader_info
                         .as_ref()
@@ -340,8 +431,15 @@ mod test {
         let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
         let (sender, receiver) = channel();
 
-        let send_tranaction_service =
-            SendTransactionService::new(tpu_address, &bank_forks, None, receiver, 1000, 1);
+        let send_tranaction_service = SendTransactionService::new(
+            tpu_address,
+            &bank_forks,
+            None,
+            receiver,
+            Arc::default(),
+            1000,
+            1,
+        );
 
         drop(sender);
         send_tranaction_service.join().unwrap();
@@ -387,7 +485,13 @@ mod test {
         info!("Expired transactions are dropped...");
         transactions.insert(
             Signature::default(),
-            TransactionInfo::new(Signature::default(), vec![], root_bank.slot() - 1, None),
+            TransactionInfo::new(
+                Signature::default(),
+                vec![],
+                root_bank.slot() - 1,
+                None,
+                None,
+            ),
         );
         let result = SendTransactionService::process_transactions(
             &working_bank,
@@ -410,7 +514,7 @@ mod test {
         info!("Rooted transactions are dropped...");
         transactions.insert(
             rooted_signature,
-            TransactionInfo::new(rooted_signature, vec![], working_bank.slot(), None),
+            TransactionInfo::new(rooted_signature, vec![], working_bank.slot(), None, None),
         );
         let result = SendTransactionService::process_transactions(
             &working_bank,
@@ -433,7 +537,7 @@ mod test {
         info!("Failed transactions are dropped...");
         transactions.insert(
             failed_signature,
-            TransactionInfo::new(failed_signature, vec![], working_bank.slot(), None),
+            TransactionInfo::new(failed_signature, vec![], working_bank.slot(), None, None),
         );
         let result = SendTransactionService::process_transactions(
             &working_bank,
@@ -456,7 +560,13 @@ mod test {
         info!("Non-rooted transactions are kept...");
         transactions.insert(
             non_rooted_signature,
-            TransactionInfo::new(non_rooted_signature, vec![], working_bank.slot(), None),
+            TransactionInfo::new(
+                non_rooted_signature,
+                vec![],
+                working_bank.slot(),
+                None,
+                None,
+            ),
         );
         let result = SendTransactionService::process_transactions(
             &working_bank,
@@ -480,7 +590,13 @@ mod test {
         info!("Unknown transactions are retried...");
         transactions.insert(
             Signature::default(),
-            TransactionInfo::new(Signature::default(), vec![], working_bank.slot(), None),
+            TransactionInfo::new(
+                Signature::default(),
+                vec![],
+                working_bank.slot(),
+                None,
+                None,
+            ),
         );
         let result = SendTransactionService::process_transactions(
             &working_bank,
@@ -501,6 +617,73 @@ mod test {
         );
     }
 
+    #[test]
+    fn process_transactions_max_retries() {
+        solana_logger::setup();
+
+        let (genesis_config, _mint_keypair) = create_genesis_config(4);
+        let bank = Bank::new(&genesis_config);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+        let send_socket = UdpSocket::bind("0.0.0.0:0").unwrap();
+        let tpu_address = "127.0.0.1:0".parse().unwrap();
+        let leader_forward_count = 1;
+        let root_bank = bank_forks.read().unwrap().root_bank().clone();
+        let working_bank = bank_forks.read().unwrap().working_bank();
+
+        for max_retries in 0..4 {
+            let mut transactions = HashMap::new();
+            transactions.insert(
+                Signature::default(),
+                TransactionInfo::new(
+                    Signature::default(),
+                    vec![],
+                    working_bank.slot(),
+                    None,
+                    Some(max_retries),
+                ),
+            );
+
+            // Rebroadcast exactly `max_retries` times, then dropped
+            let mut retried = 0;
+            loop {
+                let result = SendTransactionService::process_transactions(
+                    &working_bank,
+                    &root_bank,
+                    &send_socket,
+                    &tpu_address,
+                    &mut transactions,
+                    &None,
+                    leader_forward_count,
+                );
+                if transactions.is_empty() {
+                    assert_eq!(
+                        result,
+                        ProcessTransactionsResult {
+                            max_retries_elapsed: 1,
+                            ..ProcessTransactionsResult::default()
+                        }
+                    );
+                    break;
+                }
+                assert_eq!(result.retried, 1);
+                retried += 1;
+                assert!(retried <= max_retries);
+
+                let queue_status = SendTransactionQueueStatus::default();
+                queue_status.update(&transactions);
+                assert_eq!(
+                    queue_status.get(&Signature::default()),
+                    Some(TransactionRetryStatus {
+                        retries: retried,
+                        max_retries: Some(max_retries),
+                        last_valid_slot: working_bank.slot(),
+                    })
+                );
+            }
+            assert_eq!(retried, max_retries);
+        }
+    }
+
     #[test]
     fn test_retry_durable_nonce_transactions() {
         solana_logger::setup();
@@ -558,6 +741,7 @@ mod test {
                 vec![],
                 last_valid_slot,
                 Some((nonce_address, durable_nonce)),
+                None,
             ),
         );
         let result = SendTransactionService::process_transactions(
@@ -585,6 +769,7 @@ mod test {
                 vec![],
                 last_valid_slot,
                 Some((nonce_address, Hash::new_unique())),
+                None,
             ),
         );
         let result = SendTransactionService::process_transactions(
@@ -614,6 +799,7 @@ mod test {
                 vec![],
                 last_valid_slot,
                 Some((nonce_address, Hash::new_unique())),
+                None,
             ),
         );
         let result = SendTransactionService::process_transactions(
@@ -641,6 +827,7 @@ mod test {
                 vec![],
                 root_bank.slot() - 1,
                 Some((nonce_address, durable_nonce)),
+                None,
             ),
         );
         let result = SendTransactionService::process_transactions(
@@ -669,6 +856,7 @@ mod test {
                 vec![],
                 last_valid_slot,
                 Some((nonce_address, Hash::new_unique())), // runtime should advance nonce on failed transactions
+                None,
             ),
         );
         let result = SendTransactionService::process_transactions(
@@ -697,6 +885,7 @@ mod test {
                 vec![],
                 last_valid_slot,
                 Some((nonce_address, Hash::new_unique())), // runtime advances nonce when transaction lands
+                None,
             ),
         );
         let result = SendTransactionService::process_transactions(
@@ -726,6 +915,7 @@ mod test {
                 vec![],
                 last_valid_slot,
                 Some((nonce_address, durable_nonce)),
+                None,
             ),
         );
         let result = SendTransactionService::process_transactions(
@@ -1,5 +1,8 @@
-use crate::cluster_info::{ClusterInfo, MAX_SNAPSHOT_HASHES};
-use solana_runtime::{snapshot_package::AccountsPackage, snapshot_utils};
+use crate::cluster_info::{ClusterInfo, MAX_INCREMENTAL_SNAPSHOT_HASHES, MAX_SNAPSHOT_HASHES};
+use solana_runtime::{
+    snapshot_package::{AccountsPackage, SnapshotType},
+    snapshot_utils,
+};
 use solana_sdk::{clock::Slot, hash::Hash};
 use std::{
     sync::{
@@ -34,6 +37,11 @@ impl SnapshotPackagerService {
                     hashes.push(starting_snapshot_hash);
                 }
                 cluster_info.push_snapshot_hashes(hashes.clone());
+                // Incremental snapshot hashes are only meaningful relative to the
+                // full snapshot they are based on, so they are reset whenever the
+                // base changes.
+                let mut incremental_base: Option<(Slot, Hash)> = None;
+                let mut incremental_hashes = vec![];
                 loop {
                     if exit.load(Ordering::Relaxed) {
                         break;
@@ -45,12 +53,38 @@ impl SnapshotPackagerService {
                             snapshot_utils::archive_snapshot_package(&snapshot_package)
                         {
                             warn!("Failed to create snapshot archive: {}", err);
+                        } else if let Some(SnapshotType::IncrementalSnapshot(base_slot)) =
+                            snapshot_package.snapshot_type
+                        {
+                            let base = hashes.iter().find(|(slot, _)| *slot == base_slot);
+                            if let Some(base) = base {
+                                if incremental_base.as_ref() != Some(base) {
+                                    incremental_base = Some(*base);
+                                    incremental_hashes.clear();
+                                }
+                                incremental_hashes
+                                    .push((snapshot_package.slot, snapshot_package.hash));
+                                while incremental_hashes.len() > MAX_INCREMENTAL_SNAPSHOT_HASHES {
+                                    incremental_hashes.remove(0);
+                                }
+                                cluster_info.push_incremental_snapshot_hashes(
+                                    *base,
+                                    incremental_hashes.clone(),
+                                );
+                            } else {
+                                warn!(
+                                    "Unknown full snapshot slot {} for incremental snapshot {}",
+                                    base_slot, snapshot_package.slot
+                                );
+                            }
                         } else {
                             hashes.push((snapshot_package.slot, snapshot_package.hash));
                             while hashes.len() > MAX_SNAPSHOT_HASHES {
                                 hashes.remove(0);
                             }
                             cluster_info.push_snapshot_hashes(hashes.clone());
+                            incremental_base = None;
+                            incremental_hashes.clear();
                         }
                     } else {
                         std::thread::sleep(Duration::from_millis(100));
@@ -77,7 +111,7 @@ mod tests {
         accounts_db::AccountStorageEntry,
         bank::BankSlotDelta,
         bank_forks::ArchiveFormat,
-        snapshot_package::AccountsPackage,
+        snapshot_package::{AccountsPackage, SnapshotType},
         snapshot_utils::{self, SnapshotVersion, SNAPSHOT_STATUS_CACHE_FILE_NAME},
     };
     use solana_sdk::hash::Hash;
@@ -170,6 +204,7 @@ mod tests {
             Hash::default(),
             ArchiveFormat::TarBzip2,
             SnapshotVersion::default(),
+            Some(SnapshotType::FullSnapshot),
         );
 
         // Make tarball from packageable snapshot
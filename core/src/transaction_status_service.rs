@@ -1,3 +1,4 @@
+use crate::geyser_plugin_manager::GeyserPluginManagerLock;
 use crossbeam_channel::{Receiver, RecvTimeoutError};
 use itertools::izip;
 use solana_ledger::{blockstore::Blockstore, blockstore_processor::TransactionStatusBatch};
@@ -23,6 +24,8 @@ impl TransactionStatusService {
     #[allow(clippy::new_ret_no_self)]
     pub fn new(
         write_transaction_status_receiver: Receiver<TransactionStatusBatch>,
+        enable_rpc_transaction_history: bool,
+        transaction_notifier: Option<GeyserPluginManagerLock>,
         blockstore: Arc<Blockstore>,
         exit: &Arc<AtomicBool>,
     ) -> Self {
@@ -35,6 +38,8 @@ impl TransactionStatusService {
                 }
                 if let Err(RecvTimeoutError::Disconnected) = Self::write_transaction_status_batch(
                     &write_transaction_status_receiver,
+                    enable_rpc_transaction_history,
+                    transaction_notifier.as_ref(),
                     &blockstore,
                 ) {
                     break;
@@ -46,17 +51,21 @@ impl TransactionStatusService {
 
     fn write_transaction_status_batch(
         write_transaction_status_receiver: &Receiver<TransactionStatusBatch>,
+        enable_rpc_transaction_history: bool,
+        transaction_notifier: Option<&GeyserPluginManagerLock>,
         blockstore: &Arc<Blockstore>,
     ) -> Result<(), RecvTimeoutError> {
         let TransactionStatusBatch {
             bank,
             transactions,
+            loaded_addresses,
             iteration_order,
             statuses,
             balances,
             token_balances,
             inner_instructions,
             transaction_logs,
+            transaction_return_data,
         } = write_transaction_status_receiver.recv_timeout(Duration::from_secs(1))?;
 
         let slot = bank.slot();
@@ -73,7 +82,7 @@ impl TransactionStatusService {
                 Box::new(std::iter::repeat_with(Vec::new))
             };
         for (
-            (_, transaction),
+            (index, transaction),
             (status, nonce_rollback),
             pre_balances,
             post_balances,
@@ -81,6 +90,7 @@ impl TransactionStatusService {
             post_token_balances,
             inner_instructions,
             log_messages,
+            return_data,
         ) in izip!(
             OrderedIterator::new(&transactions, iteration_order.as_deref()),
             statuses,
@@ -89,7 +99,8 @@ impl TransactionStatusService {
             token_balances.pre_token_balances,
             token_balances.post_token_balances,
             inner_instructions_iter,
-            transaction_logs_iter
+            transaction_logs_iter,
+            transaction_return_data
         ) {
             if Bank::can_commit(&status) && !transaction.signatures.is_empty() {
                 let fee_calculator = nonce_rollback
@@ -117,25 +128,41 @@ impl TransactionStatusService {
                 let log_messages = Some(log_messages);
                 let pre_token_balances = Some(pre_token_balances);
                 let post_token_balances = Some(post_token_balances);
+                let loaded_addresses = loaded_addresses[index].clone();
 
-                blockstore
-                    .write_transaction_status(
+                let transaction_status_meta = TransactionStatusMeta {
+                    status,
+                    fee,
+                    pre_balances,
+                    post_balances,
+                    inner_instructions,
+                    log_messages,
+                    pre_token_balances,
+                    post_token_balances,
+                    loaded_addresses,
+                    return_data,
+                };
+
+                if let Some(transaction_notifier) = transaction_notifier {
+                    transaction_notifier.read().unwrap().notify_transaction(
                         slot,
-                        transaction.signatures[0],
-                        writable_keys,
-                        readonly_keys,
-                        &TransactionStatusMeta {
-                            status,
-                            fee,
-                            pre_balances,
-                            post_balances,
-                            inner_instructions,
-                            log_messages,
-                            pre_token_balances,
-                            post_token_balances,
-                        },
-                    )
-                    .expect("Expect database write to succeed");
+                        &transaction.signatures[0],
+                        transaction,
+                        &transaction_status_meta,
+                    );
+                }
+
+                if enable_rpc_transaction_history {
+                    blockstore
+                        .write_transaction_status(
+                            slot,
+                            transaction.signatures[0],
+                            writable_keys,
+                            readonly_keys,
+                            &transaction_status_meta,
+                        )
+                        .expect("Expect database write to succeed");
+                }
             }
         }
         Ok(())
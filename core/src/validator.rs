@@ -11,12 +11,15 @@ use crate::{
     completed_data_sets_service::CompletedDataSetsService,
     consensus::{reconcile_blockstore_roots_with_tower, Tower},
     contact_info::ContactInfo,
+    geyser_plugin_manager::{GeyserPluginManager, GeyserPluginManagerLock},
+    geyser_plugin_postgres::PostgresAccountsPlugin,
     gossip_service::GossipService,
     optimistically_confirmed_bank_tracker::{
         OptimisticallyConfirmedBank, OptimisticallyConfirmedBankTracker,
     },
     poh_recorder::{PohRecorder, GRACE_TICKS_FACTOR, MAX_GRACE_SLOTS},
     poh_service::{self, PohService},
+    postgres_upload_service::PostgresUploadService,
     rewards_recorder_service::{RewardsRecorderSender, RewardsRecorderService},
     rpc::JsonRpcConfig,
     rpc_pubsub_service::{PubSubConfig, PubSubService},
@@ -33,6 +36,7 @@ use crate::{
 };
 use crossbeam_channel::{bounded, unbounded};
 use rand::{thread_rng, Rng};
+use solana_geyser_plugin_interface::geyser_plugin_interface::GeyserPlugin;
 use solana_ledger::{
     bank_forks_utils,
     blockstore::{Blockstore, BlockstoreSignals, CompletedSlotsReceiver, PurgeType},
@@ -45,7 +49,7 @@ use solana_ledger::{
 use solana_measure::measure::Measure;
 use solana_metrics::datapoint_info;
 use solana_runtime::{
-    accounts_index::AccountIndex,
+    accounts_index::{AccountIndex, AccountsIndexConfig},
     bank::Bank,
     bank_forks::{BankForks, SnapshotConfig},
     commitment::BlockCommitmentCache,
@@ -61,6 +65,7 @@ use solana_sdk::{
     signature::{Keypair, Signer},
     timing::timestamp,
 };
+use solana_storage_postgres::PostgresConfig;
 use solana_vote_program::vote_state::VoteState;
 use std::time::Instant;
 use std::{
@@ -120,8 +125,12 @@ pub struct ValidatorConfig {
     pub poh_pinned_cpu_core: usize,
     pub account_indexes: HashSet<AccountIndex>,
     pub accounts_db_caching_enabled: bool,
+    pub accounts_index_config: Option<AccountsIndexConfig>,
     pub warp_slot: Option<Slot>,
     pub accounts_db_test_hash_calculation: bool,
+    pub geyser_plugin_config_files: Vec<PathBuf>,
+    pub geyser_plugins: Vec<Arc<dyn GeyserPlugin>>,
+    pub postgres_config: Option<PostgresConfig>,
 }
 
 impl Default for ValidatorConfig {
@@ -168,8 +177,12 @@ impl Default for ValidatorConfig {
             poh_pinned_cpu_core: poh_service::DEFAULT_PINNED_CPU_CORE,
             account_indexes: HashSet::new(),
             accounts_db_caching_enabled: false,
+            accounts_index_config: None,
             warp_slot: None,
             accounts_db_test_hash_calculation: false,
+            geyser_plugin_config_files: vec![],
+            geyser_plugins: vec![],
+            postgres_config: None,
         }
     }
 }
@@ -219,6 +232,8 @@ pub struct Validator {
     serve_repair_service: ServeRepairService,
     completed_data_sets_service: CompletedDataSetsService,
     snapshot_packager_service: Option<SnapshotPackagerService>,
+    geyser_plugin_manager: Option<GeyserPluginManagerLock>,
+    postgres_upload_service: Option<PostgresUploadService>,
     poh_recorder: Arc<Mutex<PohRecorder>>,
     poh_service: PohService,
     tpu: Tpu,
@@ -310,6 +325,8 @@ impl Validator {
         start.stop();
         info!("done. {}", start);
 
+        let geyser_plugin_manager = new_geyser_plugin_manager(config);
+
         let mut validator_exit = ValidatorExit::default();
         let exit = Arc::new(AtomicBool::new(false));
         let exit_ = exit.clone();
@@ -342,6 +359,7 @@ impl Validator {
             config.poh_verify,
             &exit,
             config.enforce_ulimit_nofile,
+            geyser_plugin_manager.clone(),
         );
 
         let leader_schedule_cache = Arc::new(leader_schedule_cache);
@@ -397,15 +415,25 @@ impl Validator {
         block_commitment_cache.initialize_slots(bank.slot());
         let block_commitment_cache = Arc::new(RwLock::new(block_commitment_cache));
 
+        let postgres_upload_service = config.postgres_config.clone().map(|postgres_config| {
+            PostgresUploadService::new(
+                postgres_config,
+                blockstore.clone(),
+                block_commitment_cache.clone(),
+                exit.clone(),
+            )
+        });
+
         let optimistically_confirmed_bank =
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
 
-        let subscriptions = Arc::new(RpcSubscriptions::new_with_vote_subscription(
+        let subscriptions = Arc::new(RpcSubscriptions::new_with_config(
             &exit,
             bank_forks.clone(),
             block_commitment_cache.clone(),
             optimistically_confirmed_bank.clone(),
-            config.pubsub_config.enable_vote_subscription,
+            Some(blockstore.clone()),
+            &config.pubsub_config,
         ));
 
         let (completed_data_sets_sender, completed_data_sets_receiver) =
@@ -477,6 +505,7 @@ impl Validator {
                         config.trusted_validators.clone(),
                         rpc_override_health_check.clone(),
                         optimistically_confirmed_bank.clone(),
+                        leader_schedule_cache.clone(),
                         config.send_transaction_retry_ms,
                         config.send_transaction_leader_forward_count,
                     ),
@@ -644,6 +673,7 @@ impl Validator {
                 accounts_hash_fault_injection_slots: config.accounts_hash_fault_injection_slots,
                 accounts_db_caching_enabled: config.accounts_db_caching_enabled,
                 test_hash_calculation: config.accounts_db_test_hash_calculation,
+                geyser_plugin_manager: geyser_plugin_manager.clone(),
             },
         );
 
@@ -680,6 +710,8 @@ impl Validator {
             cache_block_time_service,
             sample_performance_service,
             snapshot_packager_service,
+            geyser_plugin_manager,
+            postgres_upload_service,
             completed_data_sets_service,
             tpu,
             tvu,
@@ -770,6 +802,12 @@ impl Validator {
             s.join().expect("snapshot_packager_service");
         }
 
+        if let Some(postgres_upload_service) = self.postgres_upload_service {
+            postgres_upload_service
+                .join()
+                .expect("postgres_upload_service");
+        }
+
         self.gossip_service.join().expect("gossip_service");
         self.serve_repair_service
             .join()
@@ -780,7 +818,40 @@ impl Validator {
             .join()
             .expect("completed_data_sets_service");
         self.ip_echo_server.shutdown_background();
+
+        if let Some(geyser_plugin_manager) = self.geyser_plugin_manager {
+            geyser_plugin_manager.write().unwrap().unload();
+        }
+    }
+}
+
+fn new_geyser_plugin_manager(config: &ValidatorConfig) -> Option<GeyserPluginManagerLock> {
+    let postgres_accounts_config = config
+        .postgres_config
+        .as_ref()
+        .filter(|postgres_config| postgres_config.account_updates);
+    if config.geyser_plugin_config_files.is_empty()
+        && config.geyser_plugins.is_empty()
+        && postgres_accounts_config.is_none()
+    {
+        return None;
     }
+
+    let mut geyser_plugin_manager =
+        GeyserPluginManager::new_from_config_files(&config.geyser_plugin_config_files)
+            .unwrap_or_else(|err| {
+                error!("Failed to load geyser plugin: {}", err);
+                abort();
+            });
+    for plugin in &config.geyser_plugins {
+        geyser_plugin_manager.register_plugin(plugin.clone());
+    }
+    if let Some(postgres_accounts_config) = postgres_accounts_config {
+        geyser_plugin_manager.register_plugin(Arc::new(PostgresAccountsPlugin::new(
+            postgres_accounts_config.clone(),
+        )));
+    }
+    Some(Arc::new(RwLock::new(geyser_plugin_manager)))
 }
 
 fn active_vote_account_exists_in_bank(bank: &Arc<Bank>, vote_account: &Pubkey) -> bool {
@@ -921,6 +992,7 @@ fn new_banks_from_ledger(
     poh_verify: bool,
     exit: &Arc<AtomicBool>,
     enforce_ulimit_nofile: bool,
+    geyser_plugin_manager: Option<GeyserPluginManagerLock>,
 ) -> (
     GenesisConfig,
     BankForks,
@@ -983,15 +1055,28 @@ fn new_banks_from_ledger(
         debug_keys: config.debug_keys.clone(),
         account_indexes: config.account_indexes.clone(),
         accounts_db_caching_enabled: config.accounts_db_caching_enabled,
+        accounts_index_config: config.accounts_index_config.clone(),
         ..blockstore_processor::ProcessOptions::default()
     };
 
     let blockstore = Arc::new(blockstore);
+    let enable_rpc_transaction_history =
+        config.rpc_addrs.is_some() && config.rpc_config.enable_rpc_transaction_history;
+    let transaction_notifier = geyser_plugin_manager
+        .clone()
+        .filter(|geyser_plugin_manager| {
+            geyser_plugin_manager
+                .read()
+                .unwrap()
+                .transaction_notifications_enabled()
+        });
     let transaction_history_services =
-        if config.rpc_addrs.is_some() && config.rpc_config.enable_rpc_transaction_history {
+        if enable_rpc_transaction_history || transaction_notifier.is_some() {
             initialize_rpc_transaction_history_services(
                 blockstore.clone(),
                 exit,
+                enable_rpc_transaction_history,
+                transaction_notifier,
                 config.rpc_config.enable_cpi_and_log_storage,
             )
         } else {
@@ -1074,6 +1159,19 @@ fn new_banks_from_ledger(
     bank_forks.set_snapshot_config(config.snapshot_config.clone());
     bank_forks.set_accounts_hash_interval_slots(config.accounts_hash_interval_slots);
 
+    if let Some(geyser_plugin_manager) = geyser_plugin_manager {
+        if geyser_plugin_manager
+            .read()
+            .unwrap()
+            .account_data_notifications_enabled()
+        {
+            // All banks share one AccountsDB, so this covers every future bank
+            let working_bank = bank_forks.working_bank();
+            working_bank.set_accounts_update_notifier(Some(geyser_plugin_manager));
+            working_bank.notify_account_restore_from_snapshot();
+        }
+    }
+
     (
         genesis_config,
         bank_forks,
@@ -1162,6 +1260,8 @@ fn backup_and_clear_blockstore(ledger_path: &Path, start_slot: Slot, shred_versi
 fn initialize_rpc_transaction_history_services(
     blockstore: Arc<Blockstore>,
     exit: &Arc<AtomicBool>,
+    enable_rpc_transaction_history: bool,
+    transaction_notifier: Option<GeyserPluginManagerLock>,
     enable_cpi_and_log_storage: bool,
 ) -> TransactionHistoryServices {
     let (transaction_status_sender, transaction_status_receiver) = unbounded();
@@ -1171,10 +1271,21 @@ fn initialize_rpc_transaction_history_services(
     });
     let transaction_status_service = Some(TransactionStatusService::new(
         transaction_status_receiver,
+        enable_rpc_transaction_history,
+        transaction_notifier,
         blockstore.clone(),
         exit,
     ));
 
+    if !enable_rpc_transaction_history {
+        // Only the transaction notifier is interested in this validator's history
+        return TransactionHistoryServices {
+            transaction_status_sender,
+            transaction_status_service,
+            ..TransactionHistoryServices::default()
+        };
+    }
+
     let (rewards_recorder_sender, rewards_receiver) = unbounded();
     let rewards_recorder_sender = Some(rewards_recorder_sender);
     let rewards_recorder_service = Some(RewardsRecorderService::new(
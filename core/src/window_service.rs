@@ -9,6 +9,7 @@ use crate::{
     repair_response,
     repair_service::{RepairInfo, RepairService},
     result::{Error, Result},
+    rpc_subscriptions::RpcSubscriptions,
     serve_repair::DEFAULT_NONCE,
 };
 use crossbeam_channel::{
@@ -17,6 +18,7 @@ use crossbeam_channel::{
 use rayon::iter::IntoParallelRefMutIterator;
 use rayon::iter::ParallelIterator;
 use rayon::ThreadPool;
+use solana_client::rpc_response::SlotUpdate;
 use solana_ledger::{
     blockstore::{self, Blockstore, BlockstoreInsertionMetrics, MAX_DATA_SHREDS_PER_SLOT},
     leader_schedule_cache::LeaderScheduleCache,
@@ -26,9 +28,15 @@ use solana_metrics::{inc_new_counter_debug, inc_new_counter_error};
 use solana_perf::packet::Packets;
 use solana_rayon_threadlimit::get_thread_count;
 use solana_runtime::{bank::Bank, bank_forks::BankForks};
-use solana_sdk::{packet::PACKET_DATA_SIZE, pubkey::Pubkey, timing::duration_as_ms};
+use solana_sdk::{
+    clock::Slot,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    timing::{duration_as_ms, timestamp},
+};
 use solana_streamer::streamer::PacketSender;
 use std::{
+    collections::BTreeSet,
     net::{SocketAddr, UdpSocket},
     sync::atomic::{AtomicBool, Ordering},
     sync::{Arc, RwLock},
@@ -123,6 +131,23 @@ fn verify_repair(repair_info: &Option<RepairMeta>) -> bool {
         .unwrap_or(true)
 }
 
+/// Returns the slot and parent of each slot above `root` for which `shreds` holds the first data
+/// shred received
+fn first_shreds_received(
+    shreds: &[Shred],
+    received_slots: &mut BTreeSet<Slot>,
+    root: Slot,
+) -> Vec<(Slot, Slot)> {
+    *received_slots = received_slots.split_off(&root.saturating_add(1));
+    shreds
+        .iter()
+        .filter(|shred| shred.is_data() && shred.slot() > root)
+        .filter(|shred| received_slots.insert(shred.slot()))
+        .map(|shred| (shred.slot(), shred.parent()))
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
 fn run_insert<F>(
     shred_receiver: &CrossbeamReceiver<(Vec<Shred>, Vec<Option<RepairMeta>>)>,
     blockstore: &Arc<Blockstore>,
@@ -130,6 +155,8 @@ fn run_insert<F>(
     handle_duplicate: F,
     metrics: &mut BlockstoreInsertionMetrics,
     completed_data_sets_sender: &CompletedDataSetsSender,
+    received_slots: &mut BTreeSet<Slot>,
+    rpc_subscriptions: &Option<Arc<RpcSubscriptions>>,
 ) -> Result<()>
 where
     F: Fn(Shred),
@@ -147,6 +174,17 @@ where
     repair_infos.retain(|repair_info| verify_repair(&repair_info));
     assert_eq!(shreds.len(), repair_infos.len());
 
+    if let Some(rpc_subscriptions) = rpc_subscriptions {
+        for (slot, parent) in first_shreds_received(&shreds, received_slots, blockstore.last_root())
+        {
+            rpc_subscriptions.notify_slot_update(SlotUpdate::FirstShredReceived {
+                slot,
+                parent,
+                timestamp: timestamp(),
+            });
+        }
+    }
+
     let (completed_data_sets, inserted_indices) = blockstore.insert_shreds_handle_duplicate(
         shreds,
         Some(leader_schedule_cache),
@@ -319,6 +357,7 @@ impl WindowService {
         cluster_slots: Arc<ClusterSlots>,
         verified_vote_receiver: VerifiedVoteReceiver,
         completed_data_sets_sender: CompletedDataSetsSender,
+        rpc_subscriptions: Option<Arc<RpcSubscriptions>>,
     ) -> WindowService
     where
         F: 'static
@@ -355,6 +394,7 @@ impl WindowService {
             insert_receiver,
             duplicate_sender,
             completed_data_sets_sender,
+            rpc_subscriptions,
         );
 
         let t_window = Self::start_recv_window_thread(
@@ -410,6 +450,7 @@ impl WindowService {
         insert_receiver: CrossbeamReceiver<(Vec<Shred>, Vec<Option<RepairMeta>>)>,
         duplicate_sender: CrossbeamSender<Shred>,
         completed_data_sets_sender: CompletedDataSetsSender,
+        rpc_subscriptions: Option<Arc<RpcSubscriptions>>,
     ) -> JoinHandle<()> {
         let exit = exit.clone();
         let blockstore = blockstore.clone();
@@ -426,6 +467,7 @@ impl WindowService {
                     let _ = duplicate_sender.send(shred);
                 };
                 let mut metrics = BlockstoreInsertionMetrics::default();
+                let mut received_slots = BTreeSet::new();
                 let mut last_print = Instant::now();
                 loop {
                     if exit.load(Ordering::Relaxed) {
@@ -439,6 +481,8 @@ impl WindowService {
                         &handle_duplicate,
                         &mut metrics,
                         &completed_data_sets_sender,
+                        &mut received_slots,
+                        &rpc_subscriptions,
                     ) {
                         if Self::should_exit_on_error(e, &mut handle_timeout, &handle_error) {
                             break;
@@ -603,6 +647,31 @@ mod test {
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_first_shreds_received() {
+        let keypair = Arc::new(Keypair::new());
+        let entries = create_ticks(10, 0, Hash::default());
+        let shreds_2 = local_entries_to_shred(&entries, 2, 1, &keypair);
+        let shreds_3 = local_entries_to_shred(&entries, 3, 1, &keypair);
+        let mut received_slots = BTreeSet::new();
+
+        assert_eq!(
+            first_shreds_received(&shreds_2, &mut received_slots, 0),
+            vec![(2, 1)]
+        );
+        assert!(first_shreds_received(&shreds_2, &mut received_slots, 0).is_empty());
+
+        let shreds: Vec<_> = shreds_3.iter().chain(shreds_2.iter()).cloned().collect();
+        assert_eq!(
+            first_shreds_received(&shreds, &mut received_slots, 0),
+            vec![(3, 1)]
+        );
+
+        // Slots at or below the root are neither reported nor tracked
+        assert!(first_shreds_received(&shreds_2, &mut received_slots, 2).is_empty());
+        assert_eq!(received_slots.into_iter().collect::<Vec<_>>(), vec![3]);
+    }
+
     #[test]
     fn test_should_retransmit_and_persist() {
         let me_id = solana_sdk::pubkey::new_rand();
@@ -48,6 +48,7 @@ mod tests {
         bank::{Bank, BankSlotDelta},
         bank_forks::{ArchiveFormat, BankForks, SnapshotConfig},
         genesis_utils::{create_genesis_config, GenesisConfigInfo},
+        snapshot_package::SnapshotType,
         snapshot_utils,
         snapshot_utils::SnapshotVersion,
         status_cache::MAX_CACHE_ENTRIES,
@@ -106,6 +107,7 @@ mod tests {
                 None,
                 HashSet::new(),
                 false,
+                None,
             );
             bank0.freeze();
             let mut bank_forks = BankForks::new(bank0);
@@ -113,6 +115,7 @@ mod tests {
 
             let snapshot_config = SnapshotConfig {
                 snapshot_interval_slots,
+                incremental_snapshot_interval_slots: std::u64::MAX,
                 snapshot_package_output_path: PathBuf::from(snapshot_output_path.path()),
                 snapshot_path: PathBuf::from(snapshot_dir.path()),
                 archive_format: ArchiveFormat::TarBzip2,
@@ -158,11 +161,13 @@ mod tests {
                 ArchiveFormat::TarBzip2,
             ),
             ArchiveFormat::TarBzip2,
+            None,
             old_genesis_config,
             None,
             None,
             HashSet::new(),
             false,
+            None,
         )
         .unwrap();
 
@@ -240,6 +245,7 @@ mod tests {
             ArchiveFormat::TarBzip2,
             snapshot_version,
             None,
+            Some(SnapshotType::FullSnapshot),
         )
         .unwrap();
         let snapshot_package = snapshot_utils::process_accounts_package_pre(snapshot_package);
@@ -362,6 +368,7 @@ mod tests {
                 snapshot_config.snapshot_version,
                 &snapshot_config.archive_format,
                 None,
+                Some(SnapshotType::FullSnapshot),
             )
             .unwrap();
 
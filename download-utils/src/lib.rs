@@ -4,16 +4,23 @@ use log::*;
 use solana_runtime::{bank_forks::ArchiveFormat, snapshot_utils};
 use solana_sdk::clock::Slot;
 use solana_sdk::hash::Hash;
-use std::fs::{self, File};
-use std::io;
-use std::io::Read;
+use std::collections::{HashSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 static TRUCK: Emoji = Emoji("🚚 ", "");
 static SPARKLE: Emoji = Emoji("✨ ", "");
 
+/// Size of the byte ranges a file is split into when it is downloaded from several peers at once
+const DOWNLOAD_CHUNK_SIZE: u64 = 64 * 1024 * 1024;
+/// How long a ranged request may run before its speed is held against the minimum download speed
+const MIN_DOWNLOAD_SPEED_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
 /// Creates a new process bar for processing that will take an unknown amount of time
 fn new_spinner_progress_bar() -> ProgressBar {
     let progress_bar = ProgressBar::new(42);
@@ -23,10 +30,30 @@ fn new_spinner_progress_bar() -> ProgressBar {
     progress_bar
 }
 
+/// Downloads `url` into `destination_file`, picking up where an interrupted download of it left
+/// off if the server still serves the same file and supports ranged requests
 pub fn download_file(
     url: &str,
     destination_file: &Path,
     use_progress_bar: bool,
+) -> Result<(), String> {
+    download_file_sequentially(
+        &reqwest::blocking::Client::new(),
+        url,
+        destination_file,
+        use_progress_bar,
+        0,
+        MIN_DOWNLOAD_SPEED_GRACE_PERIOD,
+    )
+}
+
+fn download_file_sequentially(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    destination_file: &Path,
+    use_progress_bar: bool,
+    min_download_speed: u64,
+    min_download_speed_grace_period: Duration,
 ) -> Result<(), String> {
     if destination_file.is_file() {
         return Err(format!("{:?} already exists", destination_file));
@@ -36,32 +63,84 @@ pub fn download_file(
     fs::create_dir_all(destination_file.parent().unwrap()).map_err(|err| err.to_string())?;
 
     let temp_destination_file = destination_file.with_extension("tmp");
+    let partial_download_file = partial_download_file_of(&temp_destination_file);
+
+    // Resume a previously interrupted download of the same file
+    let resume = match read_partial_download(&partial_download_file) {
+        Some(PartialDownload::Sequential { etag }) => fs::metadata(&temp_destination_file)
+            .ok()
+            .map(|metadata| metadata.len())
+            .filter(|resume_from| *resume_from > 0)
+            .map(|resume_from| (resume_from, etag)),
+        _ => None,
+    };
+    if resume.is_none() {
+        let _ignored = fs::remove_file(&temp_destination_file);
+        let _ignored = fs::remove_file(&partial_download_file);
+    }
+    let start_over = || {
+        let _ignored = fs::remove_file(&temp_destination_file);
+        let _ignored = fs::remove_file(&partial_download_file);
+        download_file_sequentially(
+            client,
+            url,
+            destination_file,
+            use_progress_bar,
+            min_download_speed,
+            min_download_speed_grace_period,
+        )
+    };
 
     let progress_bar = new_spinner_progress_bar();
     if use_progress_bar {
         progress_bar.set_message(&format!("{}Downloading {}...", TRUCK, url));
     }
 
-    let response = reqwest::blocking::Client::new()
-        .get(url)
-        .send()
-        .and_then(|response| response.error_for_status())
-        .map_err(|err| {
+    let mut request = client.get(url);
+    if let Some((resume_from, _)) = &resume {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", resume_from));
+    }
+    let response = request.send().map_err(|err| {
+        progress_bar.finish_and_clear();
+        err.to_string()
+    })?;
+    if resume.is_some() && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // The partial file doesn't match what the server has, start over
+        progress_bar.finish_and_clear();
+        return start_over();
+    }
+    let response = response.error_for_status().map_err(|err| {
+        progress_bar.finish_and_clear();
+        err.to_string()
+    })?;
+
+    let etag = etag_of(&response);
+    let partial_content = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let resuming = match &resume {
+        Some((_, resume_etag)) => partial_content && etag.as_ref() == Some(resume_etag),
+        None => false,
+    };
+    if resume.is_some() && !resuming {
+        info!(
+            "{} does not resume the interrupted download, starting over",
+            url
+        );
+        if partial_content {
+            // Only the rest of a different file was sent
             progress_bar.finish_and_clear();
-            err.to_string()
-        })?;
-
-    let download_size = {
-        response
-            .headers()
-            .get(reqwest::header::CONTENT_LENGTH)
-            .and_then(|content_length| content_length.to_str().ok())
-            .and_then(|content_length| content_length.parse().ok())
-            .unwrap_or(0)
+            return start_over();
+        }
+    }
+    let resumed_size = match resume {
+        Some((resume_from, _)) if resuming => resume_from,
+        _ => 0,
     };
 
+    let download_size = resumed_size + content_length_of(&response).unwrap_or(0);
+
     if use_progress_bar {
         progress_bar.set_length(download_size);
+        progress_bar.set_position(resumed_size);
         progress_bar.set_style(
             ProgressStyle::default_bar()
                 .template(&format!(
@@ -73,6 +152,11 @@ pub fn download_file(
                 ))
                 .progress_chars("=> "),
         );
+    } else if resuming {
+        info!(
+            "Resuming download of {} bytes from {} at byte {}",
+            download_size, url, resumed_size
+        );
     } else {
         info!("Downloading {} bytes from {}", download_size, url);
     }
@@ -85,30 +169,45 @@ pub fn download_file(
         last_print_bytes: usize,
         download_size: f32,
         use_progress_bar: bool,
+        download_start: Instant,
+        downloaded_bytes: u64,
+        min_download_speed: u64,
+        min_download_speed_grace_period: Duration,
     }
 
     impl<R: Read> Read for DownloadProgress<R> {
         fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-            self.response.read(buf).map(|n| {
-                if self.use_progress_bar {
-                    self.progress_bar.inc(n as u64);
-                } else {
-                    self.current_bytes += n;
-                    if self.last_print.elapsed().as_secs() > 5 {
-                        let total_bytes_f32 = self.current_bytes as f32;
-                        let diff_bytes_f32 = (self.current_bytes - self.last_print_bytes) as f32;
-                        info!(
-                            "downloaded {} bytes {:.1}% {:.1} bytes/s",
-                            self.current_bytes,
-                            100f32 * (total_bytes_f32 / self.download_size),
-                            diff_bytes_f32 / self.last_print.elapsed().as_secs_f32(),
-                        );
-                        self.last_print = Instant::now();
-                        self.last_print_bytes = self.current_bytes;
-                    }
+            let n = self.response.read(buf)?;
+            if self.use_progress_bar {
+                self.progress_bar.inc(n as u64);
+            } else {
+                self.current_bytes += n;
+                if self.last_print.elapsed().as_secs() > 5 {
+                    let total_bytes_f32 = self.current_bytes as f32;
+                    let diff_bytes_f32 = (self.current_bytes - self.last_print_bytes) as f32;
+                    info!(
+                        "downloaded {} bytes {:.1}% {:.1} bytes/s",
+                        self.current_bytes,
+                        100f32 * (total_bytes_f32 / self.download_size),
+                        diff_bytes_f32 / self.last_print.elapsed().as_secs_f32(),
+                    );
+                    self.last_print = Instant::now();
+                    self.last_print_bytes = self.current_bytes;
                 }
-                n
-            })
+            }
+
+            self.downloaded_bytes += n as u64;
+            let elapsed = self.download_start.elapsed();
+            let download_speed = self.downloaded_bytes as f64 / elapsed.as_secs_f64();
+            if elapsed > self.min_download_speed_grace_period
+                && download_speed < self.min_download_speed as f64
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("too slow: {:.1} bytes/s", download_speed),
+                ));
+            }
+            Ok(n)
         }
     }
 
@@ -116,17 +215,44 @@ pub fn download_file(
         progress_bar,
         response,
         last_print: Instant::now(),
-        current_bytes: 0,
-        last_print_bytes: 0,
+        current_bytes: resumed_size as usize,
+        last_print_bytes: resumed_size as usize,
         download_size: (download_size as f32).max(1f32),
         use_progress_bar,
+        download_start: Instant::now(),
+        downloaded_bytes: 0,
+        min_download_speed,
+        min_download_speed_grace_period,
     };
 
-    File::create(&temp_destination_file)
+    let temp_file = if resuming {
+        OpenOptions::new().append(true).open(&temp_destination_file)
+    } else {
+        // Without an entity tag, a later attempt can't tell whether it gets the same file
+        match &etag {
+            Some(etag) => write_partial_download(
+                &partial_download_file,
+                &PartialDownload::Sequential { etag: etag.clone() },
+            )
+            .map(|_| ()),
+            None => match fs::remove_file(&partial_download_file) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            },
+        }
+        .and_then(|_| File::create(&temp_destination_file))
+    };
+    let copy_result = temp_file
         .and_then(|mut file| std::io::copy(&mut source, &mut file))
-        .map_err(|err| format!("Unable to write {:?}: {:?}", temp_destination_file, err))?;
-
+        .map_err(|err| {
+            format!(
+                "Unable to download {} to {:?}: {}",
+                url, temp_destination_file, err
+            )
+        });
     source.progress_bar.finish_and_clear();
+    copy_result?;
+
     info!(
         "  {}{}",
         SPARKLE,
@@ -140,6 +266,455 @@ pub fn download_file(
 
     std::fs::rename(temp_destination_file, destination_file)
         .map_err(|err| format!("Unable to rename: {:?}", err))?;
+    let _ignored = fs::remove_file(&partial_download_file);
+
+    Ok(())
+}
+
+fn content_length_of(response: &reqwest::blocking::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|content_length| content_length.to_str().ok())
+        .and_then(|content_length| content_length.parse().ok())
+}
+
+/// The strong entity tag of a response, which the server claims identifies the exact bytes of
+/// the file served.  Nothing checks that claim, so it only guards against mixing up honest
+/// peers' files, not against a peer serving corrupt or forged bytes.
+fn etag_of(response: &reqwest::blocking::Response) -> Option<String> {
+    response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        // Weak entity tags don't promise byte for byte identical files
+        .filter(|etag| !etag.starts_with("W/"))
+        .map(|etag| etag.to_string())
+}
+
+/// What an interrupted download already fetched.  The bytes are only reused for a file served
+/// with the same entity tag, since only then are they expected to belong to it.
+#[derive(Debug, PartialEq)]
+enum PartialDownload {
+    /// A prefix of the file, as long as the temporary file
+    Sequential { etag: String },
+    /// The chunks of a `size` byte file that were written to the temporary file
+    Chunked {
+        etag: String,
+        size: u64,
+        chunks: HashSet<u64>,
+    },
+}
+
+/// File next to a download's temporary file that describes the partial download
+fn partial_download_file_of(temp_destination_file: &Path) -> PathBuf {
+    let mut partial_download_file = temp_destination_file.as_os_str().to_owned();
+    partial_download_file.push(".partial");
+    PathBuf::from(partial_download_file)
+}
+
+/// Reads a partial download file: a `sequential <etag>` or `chunked <size> <etag>` line,
+/// followed by the index of each completed chunk on a line of its own
+fn read_partial_download(partial_download_file: &Path) -> Option<PartialDownload> {
+    let file = File::open(partial_download_file).ok()?;
+    let mut lines = BufReader::new(file).lines().filter_map(|line| line.ok());
+    let header = lines.next()?;
+    let mut fields = header.splitn(2, ' ');
+    match (fields.next()?, fields.next()?) {
+        ("sequential", etag) => Some(PartialDownload::Sequential {
+            etag: etag.to_string(),
+        }),
+        ("chunked", size_and_etag) => {
+            let mut fields = size_and_etag.splitn(2, ' ');
+            let size = fields.next()?.parse().ok()?;
+            let etag = fields.next()?.to_string();
+            Some(PartialDownload::Chunked {
+                etag,
+                size,
+                chunks: lines.filter_map(|line| line.parse().ok()).collect(),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn write_partial_download(
+    partial_download_file: &Path,
+    partial_download: &PartialDownload,
+) -> io::Result<File> {
+    let mut file = File::create(partial_download_file)?;
+    match partial_download {
+        PartialDownload::Sequential { etag } => writeln!(file, "sequential {}", etag)?,
+        PartialDownload::Chunked { etag, size, chunks } => {
+            writeln!(file, "chunked {} {}", size, etag)?;
+            for chunk in chunks {
+                writeln!(file, "{}", chunk)?;
+            }
+        }
+    }
+    file.flush()?;
+    Ok(file)
+}
+
+/// A file served with support for ranged requests
+#[derive(Debug, PartialEq)]
+struct RemoteFile {
+    size: u64,
+    etag: Option<String>,
+}
+
+/// Returns the size and entity tag of the file at `url` if the server supports ranged requests
+/// for it
+fn probe_ranged_download(client: &reqwest::blocking::Client, url: &str) -> Option<RemoteFile> {
+    let response = client
+        .get(url)
+        .header(reqwest::header::RANGE, "bytes=0-0")
+        .send()
+        .ok()?;
+    if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+        return None;
+    }
+    // Content-Range: bytes 0-0/<file size>
+    let size = response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()?;
+    Some(RemoteFile {
+        size,
+        etag: etag_of(&response),
+    })
+}
+
+/// Loads the chunks of an interrupted download of `remote_file` that don't need to be fetched
+/// again
+fn load_completed_chunks(
+    temp_destination_file: &Path,
+    partial_download_file: &Path,
+    (file_size, file_etag): (u64, &str),
+    chunk_size: u64,
+) -> HashSet<u64> {
+    match read_partial_download(partial_download_file) {
+        Some(PartialDownload::Chunked { etag, size, chunks })
+            if etag == file_etag && size == file_size =>
+        {
+            chunks
+        }
+        Some(PartialDownload::Sequential { etag }) if etag == file_etag => {
+            // A sequential download that was interrupted has all the chunks that fit in it
+            let downloaded = fs::metadata(temp_destination_file)
+                .map(|metadata| metadata.len().min(file_size))
+                .unwrap_or(0);
+            (0..downloaded / chunk_size).collect()
+        }
+        _ => HashSet::new(),
+    }
+}
+
+/// Downloads the byte range `[start, end]` of `url` into the same range of `file`, giving up if
+/// the server no longer serves the file tagged `etag`, or if the transfer drops below
+/// `min_download_speed` bytes per second
+#[allow(clippy::too_many_arguments)]
+fn download_chunk(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    etag: &str,
+    file: &mut File,
+    (start, end): (u64, u64),
+    min_download_speed: u64,
+    min_download_speed_grace_period: Duration,
+    progress_bar: &ProgressBar,
+) -> Result<(), String> {
+    let mut response = client
+        .get(url)
+        .header(reqwest::header::RANGE, format!("bytes={}-{}", start, end))
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.to_string())?;
+    if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+        return Err(format!("{} ignored the requested byte range", url));
+    }
+    if etag_of(&response).as_deref() != Some(etag) {
+        return Err(format!("{} no longer serves the same file", url));
+    }
+
+    file.seek(SeekFrom::Start(start))
+        .map_err(|err| err.to_string())?;
+    let chunk_size = end - start + 1;
+    let chunk_start = Instant::now();
+    let mut downloaded = 0;
+    let mut buf = vec![0; 64 * 1024];
+    while downloaded < chunk_size {
+        let n = response.read(&mut buf).map_err(|err| err.to_string())?;
+        if n == 0 {
+            break;
+        }
+        let n = n.min((chunk_size - downloaded) as usize);
+        file.write_all(&buf[..n]).map_err(|err| err.to_string())?;
+        downloaded += n as u64;
+        progress_bar.inc(n as u64);
+
+        let elapsed = chunk_start.elapsed();
+        if elapsed > min_download_speed_grace_period
+            && (downloaded as f64 / elapsed.as_secs_f64()) < min_download_speed as f64
+        {
+            return Err(format!(
+                "{} is too slow: {:.1} bytes/s",
+                url,
+                downloaded as f64 / elapsed.as_secs_f64()
+            ));
+        }
+    }
+    if downloaded != chunk_size {
+        return Err(format!(
+            "{} returned {} of {} bytes",
+            url, downloaded, chunk_size
+        ));
+    }
+    Ok(())
+}
+
+/// Downloads a file from several peers.  Peers that report the same size and entity tag as the
+/// first peer, and so claim to serve byte for byte identical copies, each fetch distinct byte
+/// ranges in parallel; a peer that fails or drops below `min_download_speed` bytes per second is
+/// abandoned and its chunk is picked up by the others.  Completed chunks are recorded next to
+/// the temporary file so that an interrupted download resumes where it left off.
+///
+/// If fewer than two peers claim to serve the same bytes, the file is downloaded whole instead,
+/// switching to the next peer whenever one fails or is too slow.  Either way the downloaded
+/// file is only as trustworthy as its peers: callers must verify its contents.
+pub fn download_file_from_peers(
+    urls: &[String],
+    destination_file: &Path,
+    use_progress_bar: bool,
+    min_download_speed: u64,
+) -> Result<(), String> {
+    download_file_in_chunks(
+        urls,
+        destination_file,
+        use_progress_bar,
+        min_download_speed,
+        MIN_DOWNLOAD_SPEED_GRACE_PERIOD,
+        DOWNLOAD_CHUNK_SIZE,
+    )
+}
+
+/// Downloads a file whole from the first of `urls` that delivers it, moving on to the next peer
+/// when one fails or drops below `min_download_speed` bytes per second
+fn download_file_from_any_peer(
+    client: &reqwest::blocking::Client,
+    urls: &[String],
+    destination_file: &Path,
+    use_progress_bar: bool,
+    min_download_speed: u64,
+    min_download_speed_grace_period: Duration,
+) -> Result<(), String> {
+    for url in urls {
+        match download_file_sequentially(
+            client,
+            url,
+            destination_file,
+            use_progress_bar,
+            min_download_speed,
+            min_download_speed_grace_period,
+        ) {
+            Ok(()) => return Ok(()),
+            Err(err) => warn!("Abandoning peer {}: {}", url, err),
+        }
+    }
+    Err(format!(
+        "Unable to download {:?}, all peers were abandoned",
+        destination_file
+    ))
+}
+
+fn download_file_in_chunks(
+    urls: &[String],
+    destination_file: &Path,
+    use_progress_bar: bool,
+    min_download_speed: u64,
+    min_download_speed_grace_period: Duration,
+    chunk_size: u64,
+) -> Result<(), String> {
+    if urls.is_empty() {
+        return Err("No peers to download from".to_string());
+    }
+    if destination_file.is_file() {
+        return Err(format!("{:?} already exists", destination_file));
+    }
+
+    let client = reqwest::blocking::Client::new();
+    let download_whole_file = || {
+        download_file_from_any_peer(
+            &client,
+            urls,
+            destination_file,
+            use_progress_bar,
+            min_download_speed,
+            min_download_speed_grace_period,
+        )
+    };
+    // Chunks are only mixed from peers that identify the file they serve
+    let remote_file = match probe_ranged_download(&client, &urls[0]) {
+        Some(remote_file) if remote_file.etag.is_some() => remote_file,
+        _ => return download_whole_file(),
+    };
+    let peers: Vec<String> = urls
+        .iter()
+        .filter(|url| {
+            *url == &urls[0] || probe_ranged_download(&client, url).as_ref() == Some(&remote_file)
+        })
+        .cloned()
+        .collect();
+    if peers.len() < 2 {
+        return download_whole_file();
+    }
+    let file_size = remote_file.size;
+    let etag = remote_file.etag.unwrap();
+    let download_start = Instant::now();
+
+    fs::create_dir_all(destination_file.parent().unwrap()).map_err(|err| err.to_string())?;
+    let temp_destination_file = destination_file.with_extension("tmp");
+    let partial_download_file = partial_download_file_of(&temp_destination_file);
+
+    let num_chunks = (file_size + chunk_size - 1) / chunk_size;
+    let completed_chunks = load_completed_chunks(
+        &temp_destination_file,
+        &partial_download_file,
+        (file_size, &etag),
+        chunk_size,
+    );
+    let pending_chunks: VecDeque<u64> = (0..num_chunks)
+        .filter(|chunk| !completed_chunks.contains(chunk))
+        .collect();
+
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .open(&temp_destination_file)
+        .and_then(|file| file.set_len(file_size))
+        .map_err(|err| format!("Unable to create {:?}: {:?}", temp_destination_file, err))?;
+    let chunks_writer = write_partial_download(
+        &partial_download_file,
+        &PartialDownload::Chunked {
+            etag: etag.clone(),
+            size: file_size,
+            chunks: completed_chunks,
+        },
+    )
+    .map_err(|err| format!("Unable to write {:?}: {:?}", partial_download_file, err))?;
+
+    let progress_bar = if use_progress_bar {
+        let progress_bar = ProgressBar::new(file_size);
+        progress_bar.set_style(
+            ProgressStyle::default_bar()
+                .template(&format!(
+                    "{}{}Downloading {} from {} peers {}",
+                    "{spinner:.green} ",
+                    TRUCK,
+                    urls[0],
+                    peers.len(),
+                    "[{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})"
+                ))
+                .progress_chars("=> "),
+        );
+        progress_bar
+    } else {
+        info!(
+            "Downloading {} bytes in {} chunks from {} peers: {:?}",
+            file_size,
+            pending_chunks.len(),
+            peers.len(),
+            peers
+        );
+        ProgressBar::hidden()
+    };
+    progress_bar
+        .set_position(((num_chunks - pending_chunks.len() as u64) * chunk_size).min(file_size));
+
+    let pending_chunks = Arc::new(Mutex::new(pending_chunks));
+    let chunks_writer = Arc::new(Mutex::new(chunks_writer));
+    let etag = Arc::new(etag);
+    let mut peers = peers;
+    while !pending_chunks.lock().unwrap().is_empty() && !peers.is_empty() {
+        let workers: Vec<JoinHandle<Option<String>>> = peers
+            .into_iter()
+            .map(|url| {
+                let client = client.clone();
+                let etag = etag.clone();
+                let temp_destination_file = temp_destination_file.clone();
+                let pending_chunks = pending_chunks.clone();
+                let chunks_writer = chunks_writer.clone();
+                let progress_bar = progress_bar.clone();
+                thread::spawn(move || {
+                    let mut file = OpenOptions::new()
+                        .write(true)
+                        .open(&temp_destination_file)
+                        .ok()?;
+                    loop {
+                        let chunk = pending_chunks.lock().unwrap().pop_front();
+                        let chunk = match chunk {
+                            Some(chunk) => chunk,
+                            // Keep the peer for chunks that others may still give up on
+                            None => return Some(url),
+                        };
+                        let start = chunk * chunk_size;
+                        let end = (start + chunk_size).min(file_size) - 1;
+                        if let Err(err) = download_chunk(
+                            &client,
+                            &url,
+                            &etag,
+                            &mut file,
+                            (start, end),
+                            min_download_speed,
+                            min_download_speed_grace_period,
+                            &progress_bar,
+                        ) {
+                            warn!("Abandoning peer {}: {}", url, err);
+                            pending_chunks.lock().unwrap().push_back(chunk);
+                            return None;
+                        }
+                        // Only record chunks that made it to disk
+                        if file.sync_data().is_ok() {
+                            let mut writer = chunks_writer.lock().unwrap();
+                            let _ = writeln!(writer, "{}", chunk).and_then(|_| writer.flush());
+                        }
+                    }
+                })
+            })
+            .collect();
+        peers = workers
+            .into_iter()
+            .filter_map(|worker| worker.join().unwrap_or(None))
+            .collect();
+    }
+    progress_bar.finish_and_clear();
+
+    let remaining_chunks = pending_chunks.lock().unwrap().len();
+    if remaining_chunks > 0 {
+        return Err(format!(
+            "Unable to download {} of {} chunks of {}, all peers were abandoned",
+            remaining_chunks, num_chunks, urls[0]
+        ));
+    }
+    info!(
+        "  {}{}",
+        SPARKLE,
+        format!(
+            "Downloaded {} ({} bytes) in {:?}",
+            urls[0],
+            file_size,
+            Instant::now().duration_since(download_start),
+        )
+    );
+
+    std::fs::rename(&temp_destination_file, destination_file)
+        .map_err(|err| format!("Unable to rename: {:?}", err))?;
+    let _ignored = fs::remove_file(&partial_download_file);
 
     Ok(())
 }
@@ -171,6 +746,28 @@ pub fn download_snapshot(
     ledger_path: &Path,
     desired_snapshot_hash: (Slot, Hash),
     use_progress_bar: bool,
+) -> Result<(), String> {
+    download_snapshot_from_peers(
+        &[*rpc_addr],
+        ledger_path,
+        desired_snapshot_hash,
+        use_progress_bar,
+        0,
+    )
+}
+
+/// Downloads the snapshot archive for `desired_snapshot_hash` from the RPC services at
+/// `rpc_addrs`, which must all advertise that snapshot hash, preferring the first of them.  The
+/// archive's structure is checked with `snapshot_utils::check_snapshot_archive_structure` before
+/// it is kept.  Its contents are only verified against the snapshot hash once a bank is rebuilt
+/// from it by `snapshot_utils::bank_from_archive`, which rejects an archive holding any other
+/// snapshot.
+pub fn download_snapshot_from_peers(
+    rpc_addrs: &[SocketAddr],
+    ledger_path: &Path,
+    desired_snapshot_hash: (Slot, Hash),
+    use_progress_bar: bool,
+    min_download_speed: u64,
 ) -> Result<(), String> {
     snapshot_utils::purge_old_snapshot_archives(ledger_path);
 
@@ -189,23 +786,395 @@ pub fn download_snapshot(
             return Ok(());
         }
 
-        if download_file(
-            &format!(
-                "http://{}/{}",
-                rpc_addr,
-                desired_snapshot_package
-                    .file_name()
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-            ),
+        let urls: Vec<_> = rpc_addrs
+            .iter()
+            .map(|rpc_addr| {
+                format!(
+                    "http://{}/{}",
+                    rpc_addr,
+                    desired_snapshot_package
+                        .file_name()
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                )
+            })
+            .collect();
+        if download_file_from_peers(
+            &urls,
             &desired_snapshot_package,
             use_progress_bar,
+            min_download_speed,
         )
         .is_ok()
         {
-            return Ok(());
+            return snapshot_utils::check_snapshot_archive_structure(
+                &desired_snapshot_package,
+                desired_snapshot_hash,
+            )
+            .map_err(|err| {
+                let _ignored = fs::remove_file(&desired_snapshot_package);
+                format!("Downloaded snapshot archive is malformed: {}", err)
+            });
         }
     }
     Err("Snapshot couldn't be downloaded".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    struct TestServer {
+        url: String,
+        requested_ranges: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    /// Minimal HTTP server that serves `data` at every path, tagged `etag` if given, optionally
+    /// honoring `Range` headers and pausing for `delay` after every kilobyte sent
+    fn start_test_server(
+        data: Arc<Vec<u8>>,
+        etag: Option<&str>,
+        supports_ranges: bool,
+        delay: Duration,
+    ) -> TestServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/snapshot", listener.local_addr().unwrap());
+        let etag_header = etag
+            .map(|etag| format!("ETag: {}\r\n", etag))
+            .unwrap_or_default();
+        let requested_ranges = Arc::new(Mutex::new(vec![]));
+        let server_requested_ranges = requested_ranges.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let data = data.clone();
+                let etag_header = etag_header.clone();
+                let requested_ranges = server_requested_ranges.clone();
+                thread::spawn(move || {
+                    let mut range = None;
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                            break;
+                        }
+                        let lowercase_line = line.to_lowercase();
+                        if lowercase_line.starts_with("range: bytes=") {
+                            range =
+                                Some(lowercase_line["range: bytes=".len()..].trim().to_string());
+                        }
+                    }
+                    requested_ranges.lock().unwrap().push(range.clone());
+
+                    let (status, start, end) = match range.filter(|_| supports_ranges) {
+                        None => ("200 OK", 0, data.len()),
+                        Some(range) => {
+                            let mut bounds = range.splitn(2, '-');
+                            let start: usize = bounds.next().unwrap().parse().unwrap();
+                            let end = match bounds.next().unwrap() {
+                                "" => data.len(),
+                                end => end.parse::<usize>().unwrap() + 1,
+                            };
+                            if start >= data.len() {
+                                let _ = write!(
+                                    stream,
+                                    "HTTP/1.1 416 Range Not Satisfiable\r\n\
+                                     Content-Length: 0\r\nConnection: close\r\n\r\n"
+                                );
+                                return;
+                            }
+                            ("206 Partial Content", start, end.min(data.len()))
+                        }
+                    };
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 {}\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\n\
+                         {}Connection: close\r\n\r\n",
+                        status,
+                        end - start,
+                        start,
+                        end - 1,
+                        data.len(),
+                        etag_header,
+                    );
+                    for piece in data[start..end].chunks(1024) {
+                        if stream.write_all(piece).is_err() {
+                            return;
+                        }
+                        thread::sleep(delay);
+                    }
+                });
+            }
+        });
+        TestServer {
+            url,
+            requested_ranges,
+        }
+    }
+
+    fn test_data(len: usize) -> Arc<Vec<u8>> {
+        Arc::new((0..len).map(|i| (i % 251) as u8).collect())
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("download-utils-{}-{}", name, std::process::id()));
+        let _ignored = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn interrupt_download(destination_file: &Path, data: &[u8], etag: &str) {
+        let temp_destination_file = destination_file.with_extension("tmp");
+        fs::write(&temp_destination_file, data).unwrap();
+        write_partial_download(
+            &partial_download_file_of(&temp_destination_file),
+            &PartialDownload::Sequential {
+                etag: etag.to_string(),
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_partial_download_file() {
+        let dir = test_dir("partial");
+        let partial_download_file = dir.join("snapshot.tmp.partial");
+        assert_eq!(read_partial_download(&partial_download_file), None);
+
+        for partial_download in vec![
+            PartialDownload::Sequential {
+                etag: "\"a b\"".to_string(),
+            },
+            PartialDownload::Chunked {
+                etag: "\"a b\"".to_string(),
+                size: 100_000,
+                chunks: vec![0, 5].into_iter().collect(),
+            },
+        ] {
+            write_partial_download(&partial_download_file, &partial_download).unwrap();
+            assert_eq!(
+                read_partial_download(&partial_download_file),
+                Some(partial_download)
+            );
+        }
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_download_file_resumes() {
+        let data = test_data(100_000);
+        let server = start_test_server(data.clone(), Some("\"a\""), true, Duration::default());
+        let dir = test_dir("resume");
+        let destination_file = dir.join("snapshot.tar");
+
+        interrupt_download(&destination_file, &data[..40_000], "\"a\"");
+        download_file(&server.url, &destination_file, false).unwrap();
+        assert_eq!(fs::read(&destination_file).unwrap(), *data);
+        assert!(!partial_download_file_of(&destination_file.with_extension("tmp")).exists());
+        assert_eq!(
+            *server.requested_ranges.lock().unwrap(),
+            vec![Some("40000-".to_string())]
+        );
+
+        // A server without range support serves the whole file again
+        let server = start_test_server(data.clone(), Some("\"a\""), false, Duration::default());
+        let destination_file = dir.join("snapshot2.tar");
+        interrupt_download(&destination_file, &data[..40_000], "\"a\"");
+        download_file(&server.url, &destination_file, false).unwrap();
+        assert_eq!(fs::read(&destination_file).unwrap(), *data);
+
+        // So does a server serving a different file
+        let server = start_test_server(data.clone(), Some("\"b\""), true, Duration::default());
+        let destination_file = dir.join("snapshot3.tar");
+        interrupt_download(&destination_file, &[0; 40_000], "\"a\"");
+        download_file(&server.url, &destination_file, false).unwrap();
+        assert_eq!(fs::read(&destination_file).unwrap(), *data);
+        assert_eq!(
+            *server.requested_ranges.lock().unwrap(),
+            vec![Some("40000-".to_string()), None]
+        );
+
+        // Without an entity tag, nothing is known about the interrupted download
+        let server = start_test_server(data.clone(), None, true, Duration::default());
+        let destination_file = dir.join("snapshot4.tar");
+        fs::write(destination_file.with_extension("tmp"), &[0; 40_000]).unwrap();
+        download_file(&server.url, &destination_file, false).unwrap();
+        assert_eq!(fs::read(&destination_file).unwrap(), *data);
+        assert_eq!(*server.requested_ranges.lock().unwrap(), vec![None]);
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_download_file_in_chunks() {
+        let data = test_data(100_000);
+        let servers: Vec<_> = (0..3)
+            .map(|_| start_test_server(data.clone(), Some("\"a\""), true, Duration::default()))
+            .collect();
+        let dir = test_dir("chunks");
+        let destination_file = dir.join("snapshot.tar");
+
+        let urls: Vec<_> = servers.iter().map(|server| server.url.clone()).collect();
+        download_file_in_chunks(
+            &urls,
+            &destination_file,
+            false,
+            0,
+            Duration::default(),
+            8_192,
+        )
+        .unwrap();
+        assert_eq!(fs::read(&destination_file).unwrap(), *data);
+        assert!(!partial_download_file_of(&destination_file.with_extension("tmp")).exists());
+        let chunk_requests: usize = servers
+            .iter()
+            .map(|server| server.requested_ranges.lock().unwrap().len() - 1)
+            .sum();
+        assert_eq!(chunk_requests, 13);
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_download_file_in_chunks_requires_same_file() {
+        let data = test_data(100_000);
+        let dir = test_dir("chunks-same-file");
+
+        // Peers serving differently tagged files, or not tagging them at all, are never mixed
+        for (i, etags) in [(Some("\"a\""), Some("\"b\"")), (None, None)]
+            .iter()
+            .enumerate()
+        {
+            let first_server = start_test_server(data.clone(), etags.0, true, Duration::default());
+            let other_server = start_test_server(data.clone(), etags.1, true, Duration::default());
+            let destination_file = dir.join(format!("snapshot{}.tar", i));
+
+            let urls = vec![first_server.url.clone(), other_server.url.clone()];
+            download_file_in_chunks(
+                &urls,
+                &destination_file,
+                false,
+                0,
+                Duration::default(),
+                8_192,
+            )
+            .unwrap();
+            assert_eq!(fs::read(&destination_file).unwrap(), *data);
+            assert_eq!(
+                *first_server.requested_ranges.lock().unwrap(),
+                vec![Some("0-0".to_string()), None]
+            );
+            assert!(other_server.requested_ranges.lock().unwrap().len() <= 1);
+        }
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_download_file_in_chunks_abandons_slow_peer() {
+        let data = test_data(100_000);
+        let fast_server = start_test_server(data.clone(), Some("\"a\""), true, Duration::default());
+        let slow_server =
+            start_test_server(data.clone(), Some("\"a\""), true, Duration::from_millis(50));
+        // Disagrees on the file size, so it's never used
+        let other_server =
+            start_test_server(test_data(50_000), Some("\"a\""), true, Duration::default());
+        let dir = test_dir("slow");
+        let destination_file = dir.join("snapshot.tar");
+
+        let urls = vec![
+            slow_server.url.clone(),
+            fast_server.url.clone(),
+            other_server.url.clone(),
+        ];
+        download_file_in_chunks(
+            &urls,
+            &destination_file,
+            false,
+            1_000_000,
+            Duration::from_millis(100),
+            8_192,
+        )
+        .unwrap();
+        assert_eq!(fs::read(&destination_file).unwrap(), *data);
+        assert_eq!(slow_server.requested_ranges.lock().unwrap().len(), 2);
+        assert_eq!(other_server.requested_ranges.lock().unwrap().len(), 1);
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_download_whole_file_abandons_slow_peer() {
+        let data = test_data(100_000);
+        let slow_server = start_test_server(data.clone(), None, true, Duration::from_millis(50));
+        let fast_server = start_test_server(data.clone(), None, true, Duration::default());
+        let dir = test_dir("whole-slow");
+        let destination_file = dir.join("snapshot.tar");
+
+        let urls = vec![slow_server.url.clone(), fast_server.url.clone()];
+        download_file_in_chunks(
+            &urls,
+            &destination_file,
+            false,
+            1_000_000,
+            Duration::from_millis(100),
+            8_192,
+        )
+        .unwrap();
+        assert_eq!(fs::read(&destination_file).unwrap(), *data);
+        assert_eq!(
+            *slow_server.requested_ranges.lock().unwrap(),
+            vec![Some("0-0".to_string()), None]
+        );
+        assert_eq!(*fast_server.requested_ranges.lock().unwrap(), vec![None]);
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_download_file_in_chunks_resumes() {
+        let data = test_data(100_000);
+        let servers: Vec<_> = (0..2)
+            .map(|_| start_test_server(data.clone(), Some("\"a\""), true, Duration::default()))
+            .collect();
+        let dir = test_dir("chunks-resume");
+        let destination_file = dir.join("snapshot.tar");
+
+        // Chunks 0 and 5 were downloaded before the interruption
+        let temp_destination_file = destination_file.with_extension("tmp");
+        let mut partial_data = vec![0; data.len()];
+        partial_data[..8_192].copy_from_slice(&data[..8_192]);
+        partial_data[40_960..49_152].copy_from_slice(&data[40_960..49_152]);
+        fs::write(&temp_destination_file, &partial_data).unwrap();
+        write_partial_download(
+            &partial_download_file_of(&temp_destination_file),
+            &PartialDownload::Chunked {
+                etag: "\"a\"".to_string(),
+                size: 100_000,
+                chunks: vec![0, 5].into_iter().collect(),
+            },
+        )
+        .unwrap();
+
+        let urls: Vec<_> = servers.iter().map(|server| server.url.clone()).collect();
+        download_file_in_chunks(
+            &urls,
+            &destination_file,
+            false,
+            0,
+            Duration::default(),
+            8_192,
+        )
+        .unwrap();
+        assert_eq!(fs::read(&destination_file).unwrap(), *data);
+        let requested_ranges: Vec<_> = servers
+            .iter()
+            .flat_map(|server| server.requested_ranges.lock().unwrap().clone())
+            .collect();
+        assert_eq!(requested_ranges.len(), 2 + 11);
+        assert!(!requested_ranges.contains(&Some("0-8191".to_string())));
+        assert!(!requested_ranges.contains(&Some("40960-49151".to_string())));
+        let _ignored = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,169 @@
+//! The `geyser_plugin_interface` module defines the interface a plugin must
+//! implement to receive account, transaction and slot updates from a running
+//! validator.  A plugin is either compiled into the validator and registered
+//! through `ValidatorConfig`, or built as a dynamic library exporting a
+//! `_create_plugin` constructor and loaded from a JSON config file.
+
+use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature, transaction::Transaction};
+use solana_transaction_status::TransactionStatusMeta;
+use std::{any::Any, error, fmt, io};
+use thiserror::Error;
+
+/// Information about an account being updated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplicaAccountInfo<'a> {
+    /// The pubkey of the account.
+    pub pubkey: &'a Pubkey,
+
+    /// The lamports held by the account.
+    pub lamports: u64,
+
+    /// The program that owns the account.
+    pub owner: &'a Pubkey,
+
+    /// Whether the account holds a loaded program.
+    pub executable: bool,
+
+    /// The epoch at which the account will next owe rent.
+    pub rent_epoch: u64,
+
+    /// The account data.
+    pub data: &'a [u8],
+}
+
+/// Information about a transaction that has been committed to a bank.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplicaTransactionInfo<'a> {
+    /// The first signature of the transaction, used as its identifier.
+    pub signature: &'a Signature,
+
+    /// Whether the transaction is a simple vote transaction.
+    pub is_vote: bool,
+
+    /// The transaction itself.
+    pub transaction: &'a Transaction,
+
+    /// The status metadata recorded for the transaction.
+    pub transaction_status_meta: &'a TransactionStatusMeta,
+}
+
+/// The lifecycle stage a slot has reached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotStatus {
+    /// The bank for the slot has been replayed and frozen.
+    Processed,
+
+    /// The slot has been rooted.
+    Rooted,
+
+    /// Replay of the slot failed and the slot was marked dead.
+    Dead,
+}
+
+impl SlotStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SlotStatus::Processed => "processed",
+            SlotStatus::Rooted => "rooted",
+            SlotStatus::Dead => "dead",
+        }
+    }
+}
+
+/// Errors returned by plugin calls.
+#[derive(Error, Debug)]
+pub enum GeyserPluginError {
+    /// Error opening the configuration file; for example, when the file
+    /// is not found or when the validator process has no permission to read it.
+    #[error("Error opening config file. Error detail: ({0}).")]
+    ConfigFileOpenError(#[from] io::Error),
+
+    /// Error in reading the content of the config file or the content
+    /// is not in the expected format.
+    #[error("Error reading config file. Error message: ({msg})")]
+    ConfigFileReadError { msg: String },
+
+    /// Error when updating the account.
+    #[error("Error updating account. Error message: ({msg})")]
+    AccountsUpdateError { msg: String },
+
+    /// Error when updating the slot status.
+    #[error("Error updating slot status. Error message: ({msg})")]
+    SlotStatusUpdateError { msg: String },
+
+    /// Error when notifying a transaction.
+    #[error("Error notifying transaction. Error message: ({msg})")]
+    TransactionUpdateError { msg: String },
+
+    /// Any custom error defined by the plugin.
+    #[error("Plugin-defined custom error. Error message: ({0})")]
+    Custom(Box<dyn error::Error + Send + Sync>),
+}
+
+pub type Result<T> = std::result::Result<T, GeyserPluginError>;
+
+/// Defines a Geyser plugin, to stream data from the runtime.
+///
+/// Notifications are delivered synchronously from the thread that produced
+/// them, so implementations should hand data off to their own worker rather
+/// than block, and must be safe to call from several threads at once.
+pub trait GeyserPlugin: Any + Send + Sync + fmt::Debug {
+    fn name(&self) -> &'static str;
+
+    /// Called once when the plugin is loaded from a config file.  `config_file`
+    /// is the path of the JSON file the plugin was loaded from; the plugin is
+    /// free to read its own settings from it.
+    #[allow(unused_variables)]
+    fn on_load(&mut self, config_file: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called right before the plugin is unloaded.
+    fn on_unload(&self) {}
+
+    /// Called for every account write.  `is_startup` is true while the
+    /// validator streams the accounts it restored from a snapshot or ledger
+    /// replay at boot.
+    #[allow(unused_variables)]
+    fn update_account(
+        &self,
+        account: ReplicaAccountInfo,
+        slot: Slot,
+        is_startup: bool,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when all accounts restored at startup have been notified.
+    fn notify_end_of_startup(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a slot reaches a new stage of its lifecycle.
+    #[allow(unused_variables)]
+    fn update_slot_status(
+        &self,
+        slot: Slot,
+        parent: Option<Slot>,
+        status: SlotStatus,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called for every transaction committed to a bank.
+    #[allow(unused_variables)]
+    fn notify_transaction(&self, transaction: ReplicaTransactionInfo, slot: Slot) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether the plugin wants account notifications.  Returning false lets
+    /// the validator skip copying account data on the store path.
+    fn account_data_notifications_enabled(&self) -> bool {
+        true
+    }
+
+    /// Whether the plugin wants transaction notifications.
+    fn transaction_notifications_enabled(&self) -> bool {
+        false
+    }
+}
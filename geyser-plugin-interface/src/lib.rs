@@ -0,0 +1 @@
+pub mod geyser_plugin_interface;
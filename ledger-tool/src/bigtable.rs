@@ -4,9 +4,10 @@ use solana_clap_utils::{
     input_parsers::pubkey_of,
     input_validators::{is_slot, is_valid_pubkey},
 };
-use solana_cli_output::display::println_transaction;
+use solana_cli_output::display::println_transaction_with_meta;
 use solana_ledger::{blockstore::Blockstore, blockstore_db::AccessType};
 use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
+use solana_storage_bigtable::LedgerStorage;
 use solana_transaction_status::ConfirmedBlock;
 use std::{
     path::Path,
@@ -16,12 +17,13 @@ use std::{
 };
 
 async fn upload(
+    local_storage: Option<&Path>,
     blockstore: Blockstore,
     starting_slot: Slot,
     ending_slot: Option<Slot>,
     allow_missing_metadata: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(false, None)
+    let bigtable = LedgerStorage::new_with_local_path(local_storage, false, None)
         .await
         .map_err(|err| format!("Failed to connect to storage: {:?}", err))?;
 
@@ -36,8 +38,10 @@ async fn upload(
     .await
 }
 
-async fn first_available_block() -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(true, None).await?;
+async fn first_available_block(
+    local_storage: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bigtable = LedgerStorage::new_with_local_path(local_storage, true, None).await?;
     match bigtable.get_first_available_block().await? {
         Some(block) => println!("{}", block),
         None => println!("No blocks available"),
@@ -46,8 +50,8 @@ async fn first_available_block() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-async fn block(slot: Slot) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(false, None)
+async fn block(local_storage: Option<&Path>, slot: Slot) -> Result<(), Box<dyn std::error::Error>> {
+    let bigtable = LedgerStorage::new_with_local_path(local_storage, false, None)
         .await
         .map_err(|err| format!("Failed to connect to storage: {:?}", err))?;
 
@@ -63,20 +67,19 @@ async fn block(slot: Slot) -> Result<(), Box<dyn std::error::Error>> {
     if !block.rewards.is_empty() {
         println!("Rewards: {:?}", block.rewards);
     }
-    for (index, transaction_with_meta) in block.transactions.into_iter().enumerate() {
+    for (index, transaction_with_meta) in block.transactions.iter().enumerate() {
         println!("Transaction {}:", index);
-        println_transaction(
-            &transaction_with_meta.transaction,
-            &transaction_with_meta.meta.map(|meta| meta.into()),
-            "  ",
-            None,
-        );
+        println_transaction_with_meta(transaction_with_meta, "  ");
     }
     Ok(())
 }
 
-async fn blocks(starting_slot: Slot, limit: usize) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(false, None)
+async fn blocks(
+    local_storage: Option<&Path>,
+    starting_slot: Slot,
+    limit: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bigtable = LedgerStorage::new_with_local_path(local_storage, false, None)
         .await
         .map_err(|err| format!("Failed to connect to storage: {:?}", err))?;
 
@@ -87,8 +90,12 @@ async fn blocks(starting_slot: Slot, limit: usize) -> Result<(), Box<dyn std::er
     Ok(())
 }
 
-async fn confirm(signature: &Signature, verbose: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(false, None)
+async fn confirm(
+    local_storage: Option<&Path>,
+    signature: &Signature,
+    verbose: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bigtable = LedgerStorage::new_with_local_path(local_storage, false, None)
         .await
         .map_err(|err| format!("Failed to connect to storage: {:?}", err))?;
 
@@ -101,12 +108,7 @@ async fn confirm(signature: &Signature, verbose: bool) -> Result<(), Box<dyn std
                     "\nTransaction executed in slot {}:",
                     confirmed_transaction.slot
                 );
-                println_transaction(
-                    &confirmed_transaction.transaction.transaction,
-                    &confirmed_transaction.transaction.meta.map(|m| m.into()),
-                    "  ",
-                    None,
-                );
+                println_transaction_with_meta(&confirmed_transaction.transaction, "  ");
             }
             Ok(None) => println!("Finalized transaction details not available"),
             Err(err) => println!("Unable to get finalized transaction details: {}", err),
@@ -122,6 +124,7 @@ async fn confirm(signature: &Signature, verbose: bool) -> Result<(), Box<dyn std
 }
 
 pub async fn transaction_history(
+    local_storage: Option<&Path>,
     address: &Pubkey,
     mut limit: usize,
     mut before: Option<Signature>,
@@ -130,7 +133,7 @@ pub async fn transaction_history(
     show_transactions: bool,
     query_chunk_size: usize,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(true, None).await?;
+    let bigtable = LedgerStorage::new_with_local_path(local_storage, true, None).await?;
 
     let mut loaded_block: Option<(Slot, ConfirmedBlock)> = None;
     while limit > 0 {
@@ -181,12 +184,7 @@ pub async fn transaction_history(
                                     );
                                 }
                                 Some(transaction_with_meta) => {
-                                    println_transaction(
-                                        &transaction_with_meta.transaction,
-                                        &transaction_with_meta.meta.clone().map(|m| m.into()),
-                                        "  ",
-                                        None,
-                                    );
+                                    println_transaction_with_meta(transaction_with_meta, "  ");
                                 }
                             }
                             break;
@@ -219,6 +217,17 @@ impl BigTableSubCommand for App<'_, '_> {
             SubCommand::with_name("bigtable")
                 .about("Ledger data on a BigTable instance")
                 .setting(AppSettings::ArgRequiredElseHelp)
+                .arg(
+                    Arg::with_name("local_storage")
+                        .long("local-storage")
+                        .value_name("DIR")
+                        .takes_value(true)
+                        .global(true)
+                        .help(
+                            "Use the BigTable tables stored in this local directory \
+                             instead of a BigTable instance",
+                        ),
+                )
                 .subcommand(
                     SubCommand::with_name("upload")
                         .about("Upload the ledger to BigTable")
@@ -383,6 +392,7 @@ impl BigTableSubCommand for App<'_, '_> {
 
 pub fn bigtable_process_command(ledger_path: &Path, matches: &ArgMatches<'_>) {
     let runtime = tokio::runtime::Runtime::new().unwrap();
+    let local_storage = matches.value_of("local_storage").map(Path::new);
 
     let future = match matches.subcommand() {
         ("upload", Some(arg_matches)) => {
@@ -393,22 +403,25 @@ pub fn bigtable_process_command(ledger_path: &Path, matches: &ArgMatches<'_>) {
                 crate::open_blockstore(&ledger_path, AccessType::TryPrimaryThenSecondary, None);
 
             runtime.block_on(upload(
+                local_storage,
                 blockstore,
                 starting_slot,
                 ending_slot,
                 allow_missing_metadata,
             ))
         }
-        ("first-available-block", Some(_arg_matches)) => runtime.block_on(first_available_block()),
+        ("first-available-block", Some(_arg_matches)) => {
+            runtime.block_on(first_available_block(local_storage))
+        }
         ("block", Some(arg_matches)) => {
             let slot = value_t_or_exit!(arg_matches, "slot", Slot);
-            runtime.block_on(block(slot))
+            runtime.block_on(block(local_storage, slot))
         }
         ("blocks", Some(arg_matches)) => {
             let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
             let limit = value_t_or_exit!(arg_matches, "limit", usize);
 
-            runtime.block_on(blocks(starting_slot, limit))
+            runtime.block_on(blocks(local_storage, starting_slot, limit))
         }
         ("confirm", Some(arg_matches)) => {
             let signature = arg_matches
@@ -418,7 +431,7 @@ pub fn bigtable_process_command(ledger_path: &Path, matches: &ArgMatches<'_>) {
                 .expect("Invalid signature");
             let verbose = arg_matches.is_present("verbose");
 
-            runtime.block_on(confirm(&signature, verbose))
+            runtime.block_on(confirm(local_storage, &signature, verbose))
         }
         ("transaction-history", Some(arg_matches)) => {
             let address = pubkey_of(arg_matches, "address").unwrap();
@@ -434,6 +447,7 @@ pub fn bigtable_process_command(ledger_path: &Path, matches: &ArgMatches<'_>) {
             let show_transactions = arg_matches.is_present("show_transactions");
 
             runtime.block_on(transaction_history(
+                local_storage,
                 &address,
                 limit,
                 before,
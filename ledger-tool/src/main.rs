@@ -24,8 +24,11 @@ use solana_ledger::{
     shred::Shred,
 };
 use solana_runtime::{
+    accounts_index::AccountsIndexConfig,
     bank::{Bank, RewardCalculationEvent},
     bank_forks::{ArchiveFormat, BankForks, SnapshotConfig},
+    cost_model::CostModel,
+    cost_tracker::CostTracker,
     hardened_unpack::{open_genesis_config, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
     snapshot_utils,
     snapshot_utils::SnapshotVersion,
@@ -39,12 +42,14 @@ use solana_sdk::{
     hash::Hash,
     inflation::Inflation,
     native_token::{lamports_to_sol, sol_to_lamports, Sol},
+    process_instruction::BpfComputeBudget,
     pubkey::Pubkey,
     rent::Rent,
     shred_version::compute_shred_version,
     system_program,
 };
 use solana_stake_program::stake_state::{self, PointValue, StakeState};
+use solana_transaction_status::TransactionWithStatusMeta;
 use solana_vote_program::{
     self,
     vote_state::{self, VoteState},
@@ -107,7 +112,7 @@ fn output_entry(
             );
             for (transactions_index, transaction) in entry.transactions.iter().enumerate() {
                 println!("    Transaction {}", transactions_index);
-                let transaction_status = blockstore
+                let meta = blockstore
                     .read_transaction_status((transaction.signatures[0], slot))
                     .unwrap_or_else(|err| {
                         eprintln!(
@@ -115,14 +120,14 @@ fn output_entry(
                             transaction.signatures[0], slot, err
                         );
                         None
-                    })
-                    .map(|transaction_status| transaction_status.into());
+                    });
 
-                solana_cli_output::display::println_transaction(
-                    &transaction,
-                    &transaction_status,
+                solana_cli_output::display::println_transaction_with_meta(
+                    &TransactionWithStatusMeta {
+                        transaction: transaction.clone(),
+                        meta,
+                    },
                     "      ",
-                    None,
                 );
             }
         }
@@ -184,9 +189,9 @@ fn output_slot(
             transactions += entry.transactions.len();
             hashes += entry.num_hashes;
             for transaction in &entry.transactions {
-                for instruction in &transaction.message().instructions {
-                    let program_id =
-                        transaction.message().account_keys[instruction.program_id_index as usize];
+                for instruction in transaction.message.instructions() {
+                    let program_id = transaction.message.static_account_keys()
+                        [instruction.program_id_index as usize];
                     *program_ids.entry(program_id).or_insert(0) += 1;
                 }
             }
@@ -260,6 +265,71 @@ fn output_ledger(
     }
 }
 
+fn compute_slot_cost(blockstore: &Blockstore, slot: Slot) -> Result<(), String> {
+    if blockstore.is_dead(slot) {
+        return Err("Dead slot".to_string());
+    }
+
+    let (entries, _num_shreds, _is_full) = blockstore
+        .get_slot_entries_with_shred_info(slot, 0, false)
+        .map_err(|err| format!("Failed to load entries for slot {}: {:?}", slot, err))?;
+
+    let cost_model = CostModel::default();
+    let mut cost_tracker = CostTracker::new(&cost_model);
+    let bpf_compute_budget = BpfComputeBudget::default();
+    let mut num_transactions = 0;
+    let mut num_over_limit = 0;
+    for entry in &entries {
+        for transaction in &entry.transactions {
+            num_transactions += 1;
+            // Versioned transactions are costed with the addresses recorded in their status
+            let meta = if transaction.message.address_table_lookups().is_some() {
+                blockstore
+                    .read_transaction_status((transaction.signatures[0], slot))
+                    .ok()
+                    .flatten()
+            } else {
+                None
+            };
+            let transaction = match (TransactionWithStatusMeta {
+                transaction: transaction.clone(),
+                meta,
+            })
+            .resolved_transaction()
+            {
+                Some(transaction) => transaction,
+                None => {
+                    warn!(
+                        "Skipping transaction {}, its loaded addresses were not recorded",
+                        transaction.signatures[0]
+                    );
+                    continue;
+                }
+            };
+            let tx_cost = cost_model.calculate_cost(&transaction, &bpf_compute_budget);
+            if cost_tracker.try_add(&tx_cost).is_err() {
+                // Replayed blocks are not subject to the limits, keep counting
+                num_over_limit += 1;
+                cost_tracker.add_transaction_cost(&tx_cost);
+            }
+        }
+    }
+
+    let (costliest_account, costliest_account_cost) = cost_tracker.costliest_account();
+    println!(
+        "Slot: {}, Entries: {}, Transactions: {}, Block cost: {}, Costliest account: {} ({}), \
+         Transactions over cost limits: {}",
+        slot,
+        entries.len(),
+        num_transactions,
+        cost_tracker.block_cost(),
+        costliest_account,
+        costliest_account_cost,
+        num_over_limit,
+    );
+    Ok(())
+}
+
 fn render_dot(dot: String, output_file: &str, output_format: &str) -> io::Result<()> {
     let mut child = Command::new("dot")
         .arg(format!("-T{}", output_format))
@@ -648,7 +718,7 @@ fn load_bank_forks(
     arg_matches: &ArgMatches,
     ledger_path: &Path,
     genesis_config: &GenesisConfig,
-    process_options: ProcessOptions,
+    mut process_options: ProcessOptions,
     access_type: AccessType,
     wal_recovery_mode: Option<BlockstoreRecoveryMode>,
     snapshot_archive_path: Option<PathBuf>,
@@ -665,7 +735,8 @@ fn load_bank_forks(
         let snapshot_package_output_path =
             snapshot_archive_path.unwrap_or_else(|| ledger_path.to_path_buf());
         Some(SnapshotConfig {
-            snapshot_interval_slots: 0, // Value doesn't matter
+            snapshot_interval_slots: 0,             // Value doesn't matter
+            incremental_snapshot_interval_slots: 0, // Value doesn't matter
             snapshot_package_output_path,
             snapshot_path,
             archive_format: ArchiveFormat::TarBzip2,
@@ -690,6 +761,10 @@ fn load_bank_forks(
         );
         vec![non_primary_accounts_path]
     };
+    if arg_matches.is_present("disk_accounts_index") {
+        process_options.accounts_index_config =
+            Some(AccountsIndexConfig::new(ledger_path.join("accounts_index")));
+    }
 
     bank_forks_utils::load(
         &genesis_config,
@@ -747,6 +822,10 @@ fn main() {
         .value_name("PATHS")
         .takes_value(true)
         .help("Comma separated persistent accounts location");
+    let disk_accounts_index_arg = Arg::with_name("disk_accounts_index")
+        .long("disk-accounts-index")
+        .takes_value(false)
+        .help("Keep the accounts index on disk, caching recently used entries in memory");
     let halt_at_slot_arg = Arg::with_name("halt_at_slot")
         .long("halt-at-slot")
         .value_name("SLOT")
@@ -895,6 +974,20 @@ fn main() {
             )
             .arg(&allow_dead_slots_arg)
         )
+        .subcommand(
+            SubCommand::with_name("compute-slot-cost")
+            .about("Print the estimated cost of the transactions of one or more slots")
+            .arg(
+                Arg::with_name("slots")
+                    .index(1)
+                    .value_name("SLOTS")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .multiple(true)
+                    .required(true)
+                    .help("Slots to compute the cost of"),
+            )
+        )
         .subcommand(
             SubCommand::with_name("dead-slots")
             .arg(&starting_slot_arg)
@@ -997,6 +1090,7 @@ fn main() {
             .about("Verify the ledger")
             .arg(&no_snapshot_arg)
             .arg(&account_paths_arg)
+            .arg(&disk_accounts_index_arg)
             .arg(&halt_at_slot_arg)
             .arg(&hard_forks_arg)
             .arg(&max_genesis_archive_unpacked_size_arg)
@@ -1017,6 +1111,7 @@ fn main() {
             .about("Create a Graphviz rendering of the ledger")
             .arg(&no_snapshot_arg)
             .arg(&account_paths_arg)
+            .arg(&disk_accounts_index_arg)
             .arg(&halt_at_slot_arg)
             .arg(&hard_forks_arg)
             .arg(&max_genesis_archive_unpacked_size_arg)
@@ -1037,6 +1132,7 @@ fn main() {
             .about("Create a new ledger snapshot")
             .arg(&no_snapshot_arg)
             .arg(&account_paths_arg)
+            .arg(&disk_accounts_index_arg)
             .arg(&hard_forks_arg)
             .arg(&max_genesis_archive_unpacked_size_arg)
             .arg(&snapshot_version_arg)
@@ -1148,11 +1244,21 @@ fn main() {
                     .takes_value(false)
                     .help("Remove all existing stake accounts from the new snapshot.")
             )
+            .arg(
+                Arg::with_name("incremental")
+                    .required(false)
+                    .long("incremental")
+                    .takes_value(false)
+                    .conflicts_with("no_snapshot")
+                    .help("Create an incremental snapshot on top of the full snapshot the \
+                           ledger is loaded from, instead of a full snapshot")
+            )
         ).subcommand(
             SubCommand::with_name("accounts")
             .about("Print account contents after processing in the ledger")
             .arg(&no_snapshot_arg)
             .arg(&account_paths_arg)
+            .arg(&disk_accounts_index_arg)
             .arg(&halt_at_slot_arg)
             .arg(&hard_forks_arg)
             .arg(
@@ -1173,6 +1279,7 @@ fn main() {
             .about("Print capitalization (aka, total suppy) while checksumming it")
             .arg(&no_snapshot_arg)
             .arg(&account_paths_arg)
+            .arg(&disk_accounts_index_arg)
             .arg(&halt_at_slot_arg)
             .arg(&hard_forks_arg)
             .arg(&max_genesis_archive_unpacked_size_arg)
@@ -1520,6 +1627,19 @@ fn main() {
                 }
             }
         }
+        ("compute-slot-cost", Some(arg_matches)) => {
+            let slots = values_t_or_exit!(arg_matches, "slots", Slot);
+            let blockstore = open_blockstore(
+                &ledger_path,
+                AccessType::TryPrimaryThenSecondary,
+                wal_recovery_mode,
+            );
+            for slot in slots {
+                if let Err(err) = compute_slot_cost(&blockstore, slot) {
+                    eprintln!("{}", err);
+                }
+            }
+        }
         ("json", Some(arg_matches)) => {
             let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
             let allow_dead_slots = arg_matches.is_present("allow_dead_slots");
@@ -1707,6 +1827,7 @@ fn main() {
             let output_directory = value_t_or_exit!(arg_matches, "output_directory", String);
             let mut warp_slot = value_t!(arg_matches, "warp_slot", Slot).ok();
             let remove_stake_accounts = arg_matches.is_present("remove_stake_accounts");
+            let incremental = arg_matches.is_present("incremental");
             let new_hard_forks = hardforks_of(arg_matches, "hard_forks");
 
             let faucet_pubkey = pubkey_of(&arg_matches, "faucet_pubkey");
@@ -1755,7 +1876,21 @@ fn main() {
                 wal_recovery_mode,
                 snapshot_archive_path,
             ) {
-                Ok((bank_forks, _leader_schedule_cache, _snapshot_hash)) => {
+                Ok((bank_forks, _leader_schedule_cache, snapshot_hash)) => {
+                    let full_snapshot_slot = if incremental {
+                        match snapshot_hash {
+                            Some((full_snapshot_slot, _)) => Some(full_snapshot_slot),
+                            None => {
+                                eprintln!(
+                                    "Error: An incremental snapshot requires the ledger to be \
+                                     loaded from a full snapshot"
+                                );
+                                exit(1);
+                            }
+                        }
+                    } else {
+                        None
+                    };
                     let mut bank = bank_forks
                         .get(snapshot_slot)
                         .unwrap_or_else(|| {
@@ -1921,19 +2056,45 @@ fn main() {
                         bank
                     };
 
-                    println!(
-                        "Creating a version {} snapshot of slot {}",
-                        snapshot_version,
-                        bank.slot(),
-                    );
+                    let archive_file = if let Some(full_snapshot_slot) = full_snapshot_slot {
+                        if bank.slot() <= full_snapshot_slot {
+                            eprintln!(
+                                "Error: Slot {} is not after the full snapshot slot {}",
+                                bank.slot(),
+                                full_snapshot_slot
+                            );
+                            exit(1);
+                        }
+                        println!(
+                            "Creating a version {} incremental snapshot of slot {} on top of slot {}",
+                            snapshot_version,
+                            bank.slot(),
+                            full_snapshot_slot,
+                        );
 
-                    let archive_file = snapshot_utils::bank_to_snapshot_archive(
-                        ledger_path,
-                        &bank,
-                        Some(snapshot_version),
-                        output_directory,
-                        ArchiveFormat::TarZstd,
-                    )
+                        snapshot_utils::bank_to_incremental_snapshot_archive(
+                            ledger_path,
+                            &bank,
+                            full_snapshot_slot,
+                            Some(snapshot_version),
+                            output_directory,
+                            ArchiveFormat::TarZstd,
+                        )
+                    } else {
+                        println!(
+                            "Creating a version {} snapshot of slot {}",
+                            snapshot_version,
+                            bank.slot(),
+                        );
+
+                        snapshot_utils::bank_to_snapshot_archive(
+                            ledger_path,
+                            &bank,
+                            Some(snapshot_version),
+                            output_directory,
+                            ArchiveFormat::TarZstd,
+                        )
+                    }
                     .unwrap_or_else(|err| {
                         eprintln!("Unable to create snapshot: {}", err);
                         exit(1);
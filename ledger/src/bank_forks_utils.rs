@@ -10,7 +10,7 @@ use crate::{
 use log::*;
 use solana_runtime::{
     bank_forks::{BankForks, SnapshotConfig},
-    snapshot_utils,
+    snapshot_utils::{self, SnapshotError},
 };
 use solana_sdk::{clock::Slot, genesis_config::GenesisConfig, hash::Hash};
 use std::{fs, path::PathBuf, process, result};
@@ -59,36 +59,59 @@ pub fn load(
                     process::exit(1);
                 }
 
-                let deserialized_bank = snapshot_utils::bank_from_archive(
+                let incremental_archive =
+                    snapshot_utils::get_highest_incremental_snapshot_archive_path(
+                        &snapshot_config.snapshot_package_output_path,
+                        archive_slot,
+                    );
+                if let Some((incremental_archive_filename, _)) = &incremental_archive {
+                    info!(
+                        "Loading incremental snapshot package: {:?}",
+                        incremental_archive_filename
+                    );
+                }
+
+                let deserialized_bank = match snapshot_utils::bank_from_archive(
                     &account_paths,
                     &process_options.frozen_accounts,
                     &snapshot_config.snapshot_path,
                     &archive_filename,
                     compression,
+                    incremental_archive.as_ref().map(
+                        |(incremental_archive_filename, (_, _, compression))| {
+                            (incremental_archive_filename.as_path(), *compression)
+                        },
+                    ),
                     genesis_config,
                     process_options.debug_keys.clone(),
                     Some(&crate::builtins::get(process_options.bpf_jit)),
                     process_options.account_indexes.clone(),
                     process_options.accounts_db_caching_enabled,
-                )
-                .expect("Load from snapshot failed");
+                    process_options.accounts_index_config.clone(),
+                ) {
+                    Ok(deserialized_bank) => deserialized_bank,
+                    Err(err)
+                        if matches!(
+                            err,
+                            SnapshotError::BankVerificationFailure(_)
+                                | SnapshotError::MismatchedSnapshotHash(_, _)
+                        ) =>
+                    {
+                        // The archives don't hold the snapshot they are named after, so don't
+                        // load them again: a restart fetches the snapshot anew
+                        error!("Snapshot failed to verify, removing its archives: {}", err);
+                        let _ = fs::remove_file(&archive_filename);
+                        if let Some((incremental_archive_filename, _)) = &incremental_archive {
+                            let _ = fs::remove_file(incremental_archive_filename);
+                        }
+                        process::exit(1);
+                    }
+                    Err(err) => panic!("Load from snapshot failed: {:?}", err),
+                };
                 if let Some(shrink_paths) = shrink_paths {
                     deserialized_bank.set_shrink_paths(shrink_paths);
                 }
 
-                let deserialized_snapshot_hash = (
-                    deserialized_bank.slot(),
-                    deserialized_bank.get_accounts_hash(),
-                );
-
-                if deserialized_snapshot_hash != (archive_slot, archive_snapshot_hash) {
-                    error!(
-                        "Snapshot has mismatch:\narchive: {:?}\ndeserialized: {:?}",
-                        archive_snapshot_hash, deserialized_snapshot_hash
-                    );
-                    process::exit(1);
-                }
-
                 return to_loadresult(
                     blockstore_processor::process_blockstore_from_root(
                         blockstore,
@@ -97,7 +120,9 @@ pub fn load(
                         &VerifyRecyclers::default(),
                         transaction_status_sender,
                     ),
-                    Some(deserialized_snapshot_hash),
+                    // Advertise the full snapshot; incremental snapshots are only advertised
+                    // once this node has generated a full snapshot of its own
+                    Some((archive_slot, archive_snapshot_hash)),
                 );
             }
             None => info!("No snapshot package available"),
@@ -39,7 +39,7 @@ use solana_sdk::{
         calculate_stake_weighted_timestamp, EstimateType, TIMESTAMP_SLOT_RANGE,
     },
     timing::timestamp,
-    transaction::Transaction,
+    transaction::VersionedTransaction,
 };
 use solana_storage_proto::StoredExtendedRewards;
 use solana_transaction_status::{
@@ -1771,66 +1771,88 @@ impl Blockstore {
             "blockstore-rpc-api",
             ("method", "get_confirmed_block".to_string(), String)
         );
-        let lowest_cleanup_slot = self.lowest_cleanup_slot.read().unwrap();
-        // lowest_cleanup_slot is the last slot that was not cleaned up by
-        // LedgerCleanupService
-        if *lowest_cleanup_slot > 0 && *lowest_cleanup_slot >= slot {
-            return Err(BlockstoreError::SlotCleanedUp);
+        if !self.is_root(slot) {
+            // Check for cleanup first, as cleaned up slots are no longer marked as roots
+            self.check_lowest_cleanup_slot(slot)?;
+            return Err(BlockstoreError::SlotNotRooted);
         }
-        if self.is_root(slot) {
-            let slot_meta_cf = self.db.column::<cf::SlotMeta>();
-            let slot_meta = match slot_meta_cf.get(slot)? {
-                Some(slot_meta) => slot_meta,
-                None => {
-                    info!("SlotMeta not found for rooted slot {}", slot);
-                    return Err(BlockstoreError::SlotCleanedUp);
-                }
-            };
+        self.get_complete_block(slot).map_err(|err| match err {
+            BlockstoreError::SlotUnavailable => BlockstoreError::SlotNotRooted,
+            err => err,
+        })
+    }
 
-            let slot_entries = self.get_slot_entries(slot, 0)?;
-            if !slot_entries.is_empty() {
-                let slot_transaction_iterator = slot_entries
-                    .iter()
-                    .cloned()
-                    .flat_map(|entry| entry.transactions);
-                let parent_slot_entries = self
-                    .get_slot_entries(slot_meta.parent_slot, 0)
-                    .unwrap_or_default();
-                let previous_blockhash = if !parent_slot_entries.is_empty() {
-                    get_last_hash(parent_slot_entries.iter()).unwrap()
+    /// Get a full block, whether or not it has been rooted. Blocks of slots that are still being
+    /// received or replayed are `SlotUnavailable`
+    pub fn get_complete_block(&self, slot: Slot) -> Result<ConfirmedBlock> {
+        self.check_lowest_cleanup_slot(slot)?;
+        let slot_meta_cf = self.db.column::<cf::SlotMeta>();
+        let slot_meta = match slot_meta_cf.get(slot)? {
+            Some(slot_meta) => slot_meta,
+            None => {
+                info!("SlotMeta not found for slot {}", slot);
+                return Err(if self.is_root(slot) {
+                    BlockstoreError::SlotCleanedUp
                 } else {
-                    Hash::default()
-                };
+                    BlockstoreError::SlotUnavailable
+                });
+            }
+        };
+        if !slot_meta.is_full() && !self.is_root(slot) {
+            return Err(BlockstoreError::SlotUnavailable);
+        }
+
+        let slot_entries = self.get_slot_entries(slot, 0)?;
+        if slot_entries.is_empty() {
+            return Err(BlockstoreError::SlotUnavailable);
+        }
+        let slot_transaction_iterator = slot_entries
+            .iter()
+            .cloned()
+            .flat_map(|entry| entry.transactions);
+        let parent_slot_entries = self
+            .get_slot_entries(slot_meta.parent_slot, 0)
+            .unwrap_or_default();
+        let previous_blockhash = if !parent_slot_entries.is_empty() {
+            get_last_hash(parent_slot_entries.iter()).unwrap()
+        } else {
+            Hash::default()
+        };
 
-                let blockhash = get_last_hash(slot_entries.iter())
-                    .unwrap_or_else(|| panic!("Rooted slot {:?} must have blockhash", slot));
+        let blockhash = get_last_hash(slot_entries.iter())
+            .unwrap_or_else(|| panic!("Full slot {:?} must have blockhash", slot));
 
-                let rewards = self
-                    .rewards_cf
-                    .get_protobuf_or_bincode::<StoredExtendedRewards>(slot)?
-                    .unwrap_or_default()
-                    .into();
-                let block_time = self.blocktime_cf.get(slot)?;
-
-                let block = ConfirmedBlock {
-                    previous_blockhash: previous_blockhash.to_string(),
-                    blockhash: blockhash.to_string(),
-                    parent_slot: slot_meta.parent_slot,
-                    transactions: self
-                        .map_transactions_to_statuses(slot, slot_transaction_iterator),
-                    rewards,
-                    block_time,
-                };
-                return Ok(block);
-            }
+        let rewards = self
+            .rewards_cf
+            .get_protobuf_or_bincode::<StoredExtendedRewards>(slot)?
+            .unwrap_or_default()
+            .into();
+        let block_time = self.blocktime_cf.get(slot)?;
+
+        Ok(ConfirmedBlock {
+            previous_blockhash: previous_blockhash.to_string(),
+            blockhash: blockhash.to_string(),
+            parent_slot: slot_meta.parent_slot,
+            transactions: self.map_transactions_to_statuses(slot, slot_transaction_iterator),
+            rewards,
+            block_time,
+        })
+    }
+
+    fn check_lowest_cleanup_slot(&self, slot: Slot) -> Result<()> {
+        let lowest_cleanup_slot = self.lowest_cleanup_slot.read().unwrap();
+        // lowest_cleanup_slot is the last slot that was not cleaned up by
+        // LedgerCleanupService
+        if *lowest_cleanup_slot > 0 && *lowest_cleanup_slot >= slot {
+            return Err(BlockstoreError::SlotCleanedUp);
         }
-        Err(BlockstoreError::SlotNotRooted)
+        Ok(())
     }
 
     fn map_transactions_to_statuses<'a>(
         &self,
         slot: Slot,
-        iterator: impl Iterator<Item = Transaction> + 'a,
+        iterator: impl Iterator<Item = VersionedTransaction> + 'a,
     ) -> Vec<TransactionWithStatusMeta> {
         iterator
             .map(|transaction| {
@@ -2042,7 +2064,7 @@ impl Blockstore {
         &self,
         slot: Slot,
         signature: Signature,
-    ) -> Result<Option<Transaction>> {
+    ) -> Result<Option<VersionedTransaction>> {
         let slot_entries = self.get_slot_entries(slot, 0)?;
         Ok(slot_entries
             .iter()
@@ -2391,6 +2413,9 @@ impl Blockstore {
             .iter()
             .cloned()
             .flat_map(|entry| entry.transactions)
+            // Only legacy transactions are considered, as the vote account of a versioned
+            // transaction may be loaded from a lookup table
+            .filter_map(|transaction| transaction.into_legacy_transaction())
             .flat_map(|transaction| {
                 let mut timestamps: Vec<(Pubkey, (Slot, UnixTimestamp))> = Vec::new();
                 for instruction in transaction.message.instructions {
@@ -3611,9 +3636,10 @@ pub mod tests {
         instruction::CompiledInstruction,
         message::Message,
         packet::PACKET_DATA_SIZE,
+        process_instruction::TransactionReturnData,
         pubkey::Pubkey,
         signature::Signature,
-        transaction::TransactionError,
+        transaction::{Transaction, TransactionError},
     };
     use solana_storage_proto::convert::generated;
     use solana_transaction_status::{InnerInstructions, Reward, Rewards};
@@ -5863,7 +5889,9 @@ pub mod tests {
             .map(|transaction| {
                 let mut pre_balances: Vec<u64> = vec![];
                 let mut post_balances: Vec<u64> = vec![];
-                for (i, _account_key) in transaction.message.account_keys.iter().enumerate() {
+                for (i, _account_key) in
+                    transaction.message.static_account_keys().iter().enumerate()
+                {
                     pre_balances.push(i as u64 * 10);
                     post_balances.push(i as u64 * 11);
                 }
@@ -5881,6 +5909,8 @@ pub mod tests {
                             log_messages: Some(vec![]),
                             pre_token_balances: Some(vec![]),
                             post_token_balances: Some(vec![]),
+                            loaded_addresses: None,
+                            return_data: None,
                         },
                     )
                     .unwrap();
@@ -5897,6 +5927,8 @@ pub mod tests {
                             log_messages: Some(vec![]),
                             pre_token_balances: Some(vec![]),
                             post_token_balances: Some(vec![]),
+                            loaded_addresses: None,
+                            return_data: None,
                         },
                     )
                     .unwrap();
@@ -5911,6 +5943,8 @@ pub mod tests {
                         log_messages: Some(vec![]),
                         pre_token_balances: Some(vec![]),
                         post_token_balances: Some(vec![]),
+                        loaded_addresses: None,
+                        return_data: None,
                     }),
                 }
             })
@@ -5951,6 +5985,19 @@ pub mod tests {
         let not_root = ledger.get_confirmed_block(slot + 2).unwrap_err();
         assert_matches!(not_root, BlockstoreError::SlotNotRooted);
 
+        // Full blocks are available before they're rooted, partial ones aren't
+        let unrooted_shreds = entries_to_test_shreds(entries.clone(), slot + 2, slot + 1, true, 0);
+        let partial_shreds = entries_to_test_shreds(entries.clone(), slot + 3, slot + 2, false, 0);
+        ledger.insert_shreds(unrooted_shreds, None, false).unwrap();
+        ledger.insert_shreds(partial_shreds, None, false).unwrap();
+        let not_root = ledger.get_confirmed_block(slot + 2).unwrap_err();
+        assert_matches!(not_root, BlockstoreError::SlotNotRooted);
+        let complete_block = ledger.get_complete_block(slot + 2).unwrap();
+        assert_eq!(complete_block.parent_slot, slot + 1);
+        assert_eq!(complete_block.transactions.len(), 100);
+        let partial = ledger.get_complete_block(slot + 3).unwrap_err();
+        assert_matches!(partial, BlockstoreError::SlotUnavailable);
+
         // Test block_time returns, if available
         let timestamp = 1_576_183_541;
         ledger.blocktime_cf.put(slot + 1, &timestamp).unwrap();
@@ -6103,6 +6150,10 @@ pub mod tests {
             let log_messages_vec = vec![String::from("Test message\n")];
             let pre_token_balances_vec = vec![];
             let post_token_balances_vec = vec![];
+            let return_data_value = TransactionReturnData {
+                program_id: Pubkey::new_unique(),
+                data: vec![1, 2, 3],
+            };
 
             // result not found
             assert!(transaction_status_cf
@@ -6124,7 +6175,9 @@ pub mod tests {
                         inner_instructions: Some(inner_instructions_vec.clone()),
                         log_messages: Some(log_messages_vec.clone()),
                         pre_token_balances: Some(pre_token_balances_vec.clone()),
-                        post_token_balances: Some(post_token_balances_vec.clone())
+                        post_token_balances: Some(post_token_balances_vec.clone()),
+                        loaded_addresses: None,
+                        return_data: None,
                     },
                 )
                 .is_ok());
@@ -6139,6 +6192,8 @@ pub mod tests {
                 log_messages,
                 pre_token_balances,
                 post_token_balances,
+                loaded_addresses,
+                return_data,
             } = transaction_status_cf
                 .get((0, Signature::default(), 0))
                 .unwrap()
@@ -6151,6 +6206,8 @@ pub mod tests {
             assert_eq!(log_messages.unwrap(), log_messages_vec);
             assert_eq!(pre_token_balances.unwrap(), pre_token_balances_vec);
             assert_eq!(post_token_balances.unwrap(), post_token_balances_vec);
+            assert_eq!(loaded_addresses, None);
+            assert_eq!(return_data, None);
 
             // insert value
             assert!(transaction_status_cf
@@ -6164,7 +6221,9 @@ pub mod tests {
                         inner_instructions: Some(inner_instructions_vec.clone()),
                         log_messages: Some(log_messages_vec.clone()),
                         pre_token_balances: Some(pre_token_balances_vec.clone()),
-                        post_token_balances: Some(post_token_balances_vec.clone())
+                        post_token_balances: Some(post_token_balances_vec.clone()),
+                        loaded_addresses: None,
+                        return_data: Some(return_data_value.clone()),
                     },
                 )
                 .is_ok());
@@ -6179,6 +6238,8 @@ pub mod tests {
                 log_messages,
                 pre_token_balances,
                 post_token_balances,
+                loaded_addresses,
+                return_data,
             } = transaction_status_cf
                 .get((0, Signature::new(&[2u8; 64]), 9))
                 .unwrap()
@@ -6193,6 +6254,8 @@ pub mod tests {
             assert_eq!(log_messages.unwrap(), log_messages_vec);
             assert_eq!(pre_token_balances.unwrap(), pre_token_balances_vec);
             assert_eq!(post_token_balances.unwrap(), post_token_balances_vec);
+            assert_eq!(loaded_addresses, None);
+            assert_eq!(return_data, Some(return_data_value));
         }
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
@@ -6423,6 +6486,8 @@ pub mod tests {
                 log_messages: Some(vec![]),
                 pre_token_balances: Some(vec![]),
                 post_token_balances: Some(vec![]),
+                loaded_addresses: None,
+                return_data: None,
             };
 
             let signature1 = Signature::new(&[1u8; 64]);
@@ -6548,7 +6613,9 @@ pub mod tests {
             .map(|transaction| {
                 let mut pre_balances: Vec<u64> = vec![];
                 let mut post_balances: Vec<u64> = vec![];
-                for (i, _account_key) in transaction.message.account_keys.iter().enumerate() {
+                for (i, _account_key) in
+                    transaction.message.static_account_keys().iter().enumerate()
+                {
                     pre_balances.push(i as u64 * 10);
                     post_balances.push(i as u64 * 11);
                 }
@@ -6573,6 +6640,8 @@ pub mod tests {
                             log_messages: log_messages.clone(),
                             pre_token_balances: pre_token_balances.clone(),
                             post_token_balances: post_token_balances.clone(),
+                            loaded_addresses: None,
+                            return_data: None,
                         },
                     )
                     .unwrap();
@@ -6587,6 +6656,8 @@ pub mod tests {
                         log_messages,
                         pre_token_balances,
                         post_token_balances,
+                        loaded_addresses: None,
+                        return_data: None,
                     }),
                 }
             })
@@ -6812,7 +6883,7 @@ pub mod tests {
                             .write_transaction_status(
                                 slot,
                                 transaction.signatures[0],
-                                transaction.message.account_keys.iter().collect(),
+                                transaction.message.static_account_keys().iter().collect(),
                                 vec![],
                                 &TransactionStatusMeta::default(),
                             )
@@ -7034,6 +7105,8 @@ pub mod tests {
                             log_messages: Some(vec![]),
                             pre_token_balances: Some(vec![]),
                             post_token_balances: Some(vec![]),
+                            loaded_addresses: None,
+                            return_data: None,
                         },
                     )
                     .unwrap();
@@ -7048,7 +7121,10 @@ pub mod tests {
                 vec![CompiledInstruction::new(1, &(), vec![0])],
             ));
 
-            let map = blockstore.map_transactions_to_statuses(slot, transactions.into_iter());
+            let map = blockstore.map_transactions_to_statuses(
+                slot,
+                transactions.into_iter().map(VersionedTransaction::from),
+            );
             assert_eq!(map.len(), 5);
             for (x, m) in map.iter().take(4).enumerate() {
                 assert_eq!(m.meta.as_ref().unwrap().fee, x as u64);
@@ -7515,6 +7591,7 @@ pub mod tests {
                     lamports: 42 + i,
                     post_balance: std::u64::MAX,
                     reward_type: Some(RewardType::Fee),
+                    commission: None,
                 })
                 .collect();
             let protobuf_rewards: generated::Rewards = rewards.into();
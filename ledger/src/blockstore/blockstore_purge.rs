@@ -1,4 +1,5 @@
 use super::*;
+use solana_sdk::message::v0::LoadedAddresses;
 
 #[derive(Default)]
 pub struct PurgeStats {
@@ -274,9 +275,25 @@ impl Blockstore {
                 .flat_map(|entry| entry.transactions)
             {
                 if let Some(&signature) = transaction.signatures.get(0) {
+                    // Addresses loaded from lookup tables are only recorded in the status meta
+                    let loaded_addresses = if transaction.message.address_table_lookups().is_some()
+                    {
+                        self.read_transaction_status((signature, slot))?
+                            .and_then(|meta| meta.loaded_addresses)
+                            .unwrap_or_default()
+                    } else {
+                        LoadedAddresses::default()
+                    };
                     batch.delete::<cf::TransactionStatus>((0, signature, slot))?;
                     batch.delete::<cf::TransactionStatus>((1, signature, slot))?;
-                    for pubkey in transaction.message.account_keys {
+                    for pubkey in transaction
+                        .message
+                        .static_account_keys()
+                        .iter()
+                        .chain(&loaded_addresses.writable)
+                        .chain(&loaded_addresses.readonly)
+                        .copied()
+                    {
                         batch.delete::<cf::AddressSignatures>((0, pubkey, slot, signature))?;
                         batch.delete::<cf::AddressSignatures>((1, pubkey, slot, signature))?;
                     }
@@ -16,7 +16,7 @@ use solana_measure::{measure::Measure, thread_mem_usage};
 use solana_metrics::{datapoint_error, inc_new_counter_debug};
 use solana_rayon_threadlimit::get_thread_count;
 use solana_runtime::{
-    accounts_index::AccountIndex,
+    accounts_index::{AccountIndex, AccountsIndexConfig},
     bank::{
         Bank, ExecuteTimings, InnerInstructionsList, TransactionBalancesSet,
         TransactionExecutionResult, TransactionLogMessages, TransactionResults,
@@ -33,6 +33,8 @@ use solana_sdk::{
     clock::{Slot, MAX_PROCESSING_AGE},
     genesis_config::GenesisConfig,
     hash::Hash,
+    message::v0::LoadedAddresses,
+    process_instruction::TransactionReturnData,
     pubkey::Pubkey,
     signature::{Keypair, Signature},
     transaction::{Result, Transaction, TransactionError},
@@ -118,7 +120,7 @@ fn execute_batch(
         vec![]
     };
 
-    let (tx_results, balances, inner_instructions, transaction_logs) =
+    let (tx_results, balances, inner_instructions, transaction_logs, transaction_return_data) =
         batch.bank().load_execute_and_commit_transactions(
             batch,
             MAX_PROCESSING_AGE,
@@ -149,12 +151,14 @@ fn execute_batch(
         send_transaction_status_batch(
             bank.clone(),
             batch.transactions(),
+            batch.loaded_addresses(),
             batch.iteration_order_vec(),
             execution_results,
             balances,
             token_balances,
             inner_instructions,
             transaction_logs,
+            transaction_return_data,
             transaction_status_sender,
         );
     }
@@ -271,7 +275,7 @@ fn process_entries_with_callback(
             };
 
             // try to lock the accounts
-            let batch = bank.prepare_batch(&entry.transactions, iteration_order);
+            let batch = bank.prepare_versioned_batch(&entry.transactions, iteration_order);
 
             let first_lock_err = first_err(batch.lock_results());
 
@@ -367,6 +371,7 @@ pub struct ProcessOptions {
     pub debug_keys: Option<Arc<HashSet<Pubkey>>>,
     pub account_indexes: HashSet<AccountIndex>,
     pub accounts_db_caching_enabled: bool,
+    pub accounts_index_config: Option<AccountsIndexConfig>,
 }
 
 pub fn process_blockstore(
@@ -393,6 +398,7 @@ pub fn process_blockstore(
         Some(&crate::builtins::get(opts.bpf_jit)),
         opts.account_indexes.clone(),
         opts.accounts_db_caching_enabled,
+        opts.accounts_index_config.clone(),
     );
     let bank0 = Arc::new(bank0);
     info!("processing ledger for slot 0...");
@@ -1089,12 +1095,15 @@ fn process_single_slot(
 pub struct TransactionStatusBatch {
     pub bank: Arc<Bank>,
     pub transactions: Vec<Transaction>,
+    /// Addresses loaded by versioned transactions, indexed like `transactions`
+    pub loaded_addresses: Vec<Option<LoadedAddresses>>,
     pub iteration_order: Option<Vec<usize>>,
     pub statuses: Vec<TransactionExecutionResult>,
     pub balances: TransactionBalancesSet,
     pub token_balances: TransactionTokenBalancesSet,
     pub inner_instructions: Option<Vec<Option<InnerInstructionsList>>>,
     pub transaction_logs: Option<Vec<TransactionLogMessages>>,
+    pub transaction_return_data: Vec<Option<TransactionReturnData>>,
 }
 
 #[derive(Clone)]
@@ -1106,12 +1115,14 @@ pub struct TransactionStatusSender {
 pub fn send_transaction_status_batch(
     bank: Arc<Bank>,
     transactions: &[Transaction],
+    loaded_addresses: &[Option<LoadedAddresses>],
     iteration_order: Option<Vec<usize>>,
     statuses: Vec<TransactionExecutionResult>,
     balances: TransactionBalancesSet,
     token_balances: TransactionTokenBalancesSet,
     inner_instructions: Vec<Option<InnerInstructionsList>>,
     transaction_logs: Vec<TransactionLogMessages>,
+    transaction_return_data: Vec<Option<TransactionReturnData>>,
     transaction_status_sender: TransactionStatusSender,
 ) {
     let slot = bank.slot();
@@ -1126,12 +1137,14 @@ pub fn send_transaction_status_batch(
         .send(TransactionStatusBatch {
             bank,
             transactions: transactions.to_vec(),
+            loaded_addresses: loaded_addresses.to_vec(),
             iteration_order,
             statuses,
             balances,
             token_balances,
             inner_instructions,
             transaction_logs,
+            transaction_return_data,
         })
     {
         trace!(
@@ -2258,13 +2271,13 @@ pub mod tests {
         // Check all accounts are unlocked
         let txs1 = &entry_1_to_mint.transactions[..];
         let txs2 = &entry_2_to_3_mint_to_1.transactions[..];
-        let batch1 = bank.prepare_batch(txs1, None);
+        let batch1 = bank.prepare_versioned_batch(txs1, None);
         for result in batch1.lock_results() {
             assert!(result.is_ok());
         }
         // txs1 and txs2 have accounts that conflict, so we must drop txs1 first
         drop(batch1);
-        let batch2 = bank.prepare_batch(txs2, None);
+        let batch2 = bank.prepare_versioned_batch(txs2, None);
         for result in batch2.lock_results() {
             assert!(result.is_ok());
         }
@@ -2955,6 +2968,7 @@ pub mod tests {
             None,
             HashSet::new(),
             false,
+            None,
         );
         *bank.epoch_schedule()
     }
@@ -3026,6 +3040,7 @@ pub mod tests {
             _balances,
             _inner_instructions,
             _log_messages,
+            _return_data,
         ) = batch.bank().load_execute_and_commit_transactions(
             &batch,
             MAX_PROCESSING_AGE,
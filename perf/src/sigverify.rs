@@ -12,7 +12,7 @@ use bincode::serialized_size;
 use rayon::ThreadPool;
 use solana_metrics::inc_new_counter_debug;
 use solana_rayon_threadlimit::get_thread_count;
-use solana_sdk::message::MessageHeader;
+use solana_sdk::message::{MessageHeader, MESSAGE_VERSION_PREFIX};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::short_vec::decode_len;
 use solana_sdk::signature::Signature;
@@ -59,6 +59,7 @@ pub enum PacketError {
     InvalidSignatureLen,
     MismatchSignatureLen,
     PayerNotWritable,
+    UnsupportedVersion,
 }
 
 impl std::convert::From<std::boxed::Box<bincode::ErrorKind>> for PacketError {
@@ -143,16 +144,31 @@ fn do_get_packet_offsets(
         return Err(PacketError::InvalidSignatureLen);
     }
 
+    // Versioned messages start with a prefix byte, which is part of the signed message
+    let message_prefix = packet.data[msg_start_offset];
+    let msg_header_offset = if message_prefix & MESSAGE_VERSION_PREFIX != 0 {
+        if message_prefix & !MESSAGE_VERSION_PREFIX != 0 {
+            return Err(PacketError::UnsupportedVersion);
+        }
+        // Packet should also have data for the version prefix
+        if (msg_start_offset + 1 + message_header_size + 1) > packet.meta.size {
+            return Err(PacketError::InvalidSignatureLen);
+        }
+        msg_start_offset + 1
+    } else {
+        msg_start_offset
+    };
+
     // read MessageHeader.num_required_signatures (serialized with u8)
-    let sig_len_maybe_trusted = packet.data[msg_start_offset] as usize;
+    let sig_len_maybe_trusted = packet.data[msg_header_offset] as usize;
 
-    let message_account_keys_len_offset = msg_start_offset + message_header_size;
+    let message_account_keys_len_offset = msg_header_offset + message_header_size;
 
     // This reads and compares the MessageHeader num_required_signatures and
     // num_readonly_signed_accounts bytes. If num_required_signatures is not larger than
     // num_readonly_signed_accounts, the first account is not debitable, and cannot be charged
     // required transaction fees.
-    if packet.data[msg_start_offset] <= packet.data[msg_start_offset + 1] {
+    if packet.data[msg_header_offset] <= packet.data[msg_header_offset + 1] {
         return Err(PacketError::PayerNotWritable);
     }
 
@@ -168,7 +184,7 @@ fn do_get_packet_offsets(
 
     let sig_start = current_offset as usize + sig_size;
     let msg_start = current_offset as usize + msg_start_offset;
-    let pubkey_start = msg_start + message_header_size + pubkey_len_size;
+    let pubkey_start = current_offset as usize + message_account_keys_len_offset + pubkey_len_size;
 
     if sig_len_maybe_trusted != sig_len_untrusted {
         return Err(PacketError::MismatchSignatureLen);
@@ -412,10 +428,12 @@ mod tests {
     use crate::sigverify::PacketOffsets;
     use crate::test_tx::{test_multisig_tx, test_tx};
     use bincode::{deserialize, serialize};
+    use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
     use solana_sdk::hash::Hash;
-    use solana_sdk::message::{Message, MessageHeader};
-    use solana_sdk::signature::Signature;
-    use solana_sdk::transaction::Transaction;
+    use solana_sdk::message::{v0, Message, MessageHeader, VersionedMessage};
+    use solana_sdk::signature::{Keypair, Signature, Signer};
+    use solana_sdk::system_instruction;
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
 
     const SIG_OFFSET: usize = 1;
 
@@ -467,6 +485,51 @@ mod tests {
         assert_eq!(packet_offsets.sig_len, 1);
     }
 
+    #[test]
+    fn test_versioned_transaction_layout() {
+        let keypair = Keypair::new();
+        let recipient = solana_sdk::pubkey::new_rand();
+        let message = v0::Message::new(
+            &[system_instruction::transfer(
+                &keypair.pubkey(),
+                &recipient,
+                1,
+            )],
+            Some(&keypair.pubkey()),
+            &[AddressLookupTableAccount {
+                key: solana_sdk::pubkey::new_rand(),
+                addresses: vec![recipient],
+            }],
+        );
+        let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&keypair]).unwrap();
+        let tx_bytes = serialize(&tx).unwrap();
+        let message_data = tx.message_data();
+        assert_eq!(message_data[0], MESSAGE_VERSION_PREFIX);
+        let mut packet = Packet::default();
+        packet.meta.size = tx_bytes.len();
+        packet.data[..packet.meta.size].copy_from_slice(&tx_bytes);
+
+        let packet_offsets = sigverify::get_packet_offsets(&packet, 0);
+        assert_eq!(
+            memfind(&tx_bytes, &tx.message.static_account_keys()[0].as_ref()),
+            Some(packet_offsets.pubkey_start as usize)
+        );
+        assert_eq!(
+            memfind(&tx_bytes, &message_data),
+            Some(packet_offsets.msg_start as usize)
+        );
+        assert_eq!(packet_offsets.sig_len, 1);
+        assert_eq!(verify_packet(&packet), 1);
+
+        // Only version 0 is supported
+        let msg_start = packet_offsets.msg_start as usize;
+        packet.data[msg_start] = MESSAGE_VERSION_PREFIX | 1;
+        assert_eq!(
+            sigverify::do_get_packet_offsets(&packet, 0),
+            Err(PacketError::UnsupportedVersion)
+        );
+    }
+
     fn packet_from_num_sigs(required_num_sigs: u8, actual_num_sigs: usize) -> Packet {
         let message = Message {
             header: MessageHeader {
@@ -508,9 +571,10 @@ mod tests {
 
         let unsanitized_packet_offsets = sigverify::do_get_packet_offsets(&packet, 0);
 
+        // The first byte of the message has the version prefix bit set
         assert_eq!(
             unsanitized_packet_offsets,
-            Err(PacketError::MismatchSignatureLen)
+            Err(PacketError::UnsupportedVersion)
         );
     }
 
@@ -332,6 +332,22 @@ impl program_stubs::SyscallStubs for SyscallStubs {
         stable_log::program_success(&logger, &program_id);
         Ok(())
     }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        let invoke_context = get_invoke_context();
+        invoke_context
+            .set_return_data(data.to_vec())
+            .expect("set_return_data");
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        let (program_id, data) = get_invoke_context().get_return_data();
+        if data.is_empty() {
+            None
+        } else {
+            Some((program_id, data.to_vec()))
+        }
+    }
 }
 
 pub fn find_file(filename: &str) -> Option<PathBuf> {
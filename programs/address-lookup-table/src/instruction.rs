@@ -0,0 +1,144 @@
+use crate::state::lookup_table_space;
+use serde_derive::{Deserialize, Serialize};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_instruction, sysvar,
+};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum ProgramInstruction {
+    /// Initialize an address lookup table account that was allocated with
+    /// enough space for the addresses it will hold
+    ///
+    /// # Account references
+    ///   0. [WRITE, SIGNER] Uninitialized lookup table account
+    ///   1. [] Lookup table authority
+    InitializeLookupTable,
+
+    /// Append addresses to a lookup table.  Appended addresses may not be
+    /// used by transactions until the following slot.
+    ///
+    /// # Account references
+    ///   0. [WRITE] Lookup table account
+    ///   1. [SIGNER] Lookup table authority
+    ///   2. [] Clock sysvar
+    ExtendLookupTable { new_addresses: Vec<Pubkey> },
+
+    /// Permanently remove the authority of a lookup table, making it
+    /// immutable
+    ///
+    /// # Account references
+    ///   0. [WRITE] Lookup table account
+    ///   1. [SIGNER] Lookup table authority
+    FreezeLookupTable,
+
+    /// Deactivate a lookup table so that it may be closed.  Deactivated
+    /// tables may no longer be used by transactions.
+    ///
+    /// # Account references
+    ///   0. [WRITE] Lookup table account
+    ///   1. [SIGNER] Lookup table authority
+    ///   2. [] Clock sysvar
+    DeactivateLookupTable,
+
+    /// Close a deactivated lookup table and withdraw its lamports.  The
+    /// deactivation slot must have aged out of the slot hashes, so that no
+    /// transaction signed while the table was active can still be processed.
+    ///
+    /// # Account references
+    ///   0. [WRITE] Lookup table account
+    ///   1. [SIGNER] Lookup table authority
+    ///   2. [WRITE] Recipient of the lookup table lamports
+    ///   3. [] Clock sysvar
+    ///   4. [] SlotHashes sysvar
+    CloseLookupTable,
+}
+
+pub fn initialize_lookup_table(lookup_table: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction::new(
+        crate::id(),
+        &ProgramInstruction::InitializeLookupTable,
+        vec![
+            AccountMeta::new(*lookup_table, true),
+            AccountMeta::new_readonly(*authority, false),
+        ],
+    )
+}
+
+/// Create and initialize a lookup table able to store `max_addresses`
+pub fn create_lookup_table(
+    payer: &Pubkey,
+    lookup_table: &Pubkey,
+    authority: &Pubkey,
+    lamports: u64,
+    max_addresses: usize,
+) -> Vec<Instruction> {
+    vec![
+        system_instruction::create_account(
+            payer,
+            lookup_table,
+            lamports,
+            lookup_table_space(max_addresses) as u64,
+            &crate::id(),
+        ),
+        initialize_lookup_table(lookup_table, authority),
+    ]
+}
+
+pub fn extend_lookup_table(
+    lookup_table: &Pubkey,
+    authority: &Pubkey,
+    new_addresses: Vec<Pubkey>,
+) -> Instruction {
+    Instruction::new(
+        crate::id(),
+        &ProgramInstruction::ExtendLookupTable { new_addresses },
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+pub fn freeze_lookup_table(lookup_table: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction::new(
+        crate::id(),
+        &ProgramInstruction::FreezeLookupTable,
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+pub fn deactivate_lookup_table(lookup_table: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction::new(
+        crate::id(),
+        &ProgramInstruction::DeactivateLookupTable,
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+pub fn close_lookup_table(
+    lookup_table: &Pubkey,
+    authority: &Pubkey,
+    recipient: &Pubkey,
+) -> Instruction {
+    Instruction::new(
+        crate::id(),
+        &ProgramInstruction::CloseLookupTable,
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        ],
+    )
+}
@@ -0,0 +1,9 @@
+//! The address lookup table program stores lists of addresses on chain so
+//! that versioned transactions can reference them by a one byte index
+//! instead of including the full 32 byte address in the message.
+
+pub mod instruction;
+pub mod processor;
+pub mod state;
+
+solana_sdk::declare_id!("AddressLookupTab1e1111111111111111111111111");
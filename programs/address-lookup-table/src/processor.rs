@@ -0,0 +1,419 @@
+//! Address lookup table program
+
+use crate::{
+    instruction::ProgramInstruction,
+    state::{
+        lookup_table_capacity, AddressLookupTable, LookupTableMeta, ProgramState,
+        LOOKUP_TABLE_META_SIZE,
+    },
+};
+use solana_sdk::{
+    instruction::InstructionError,
+    keyed_account::{from_keyed_account, next_keyed_account, KeyedAccount},
+    process_instruction::InvokeContext,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    sysvar::{clock::Clock, slot_hashes::SlotHashes},
+};
+use std::mem::size_of;
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    keyed_accounts: &[KeyedAccount],
+    data: &[u8],
+    _invoke_context: &mut dyn InvokeContext,
+) -> Result<(), InstructionError> {
+    let keyed_accounts = &mut keyed_accounts.iter();
+    let lookup_table_account = next_keyed_account(keyed_accounts)?;
+    if lookup_table_account.owner()? != crate::id() {
+        return Err(InstructionError::IncorrectProgramId);
+    }
+
+    match limited_deserialize(data)? {
+        ProgramInstruction::InitializeLookupTable => {
+            let authority_account = next_keyed_account(keyed_accounts)?;
+            initialize_lookup_table(lookup_table_account, authority_account.unsigned_key())
+        }
+        ProgramInstruction::ExtendLookupTable { new_addresses } => {
+            let authority_account = next_keyed_account(keyed_accounts)?;
+            let clock = from_keyed_account::<Clock>(next_keyed_account(keyed_accounts)?)?;
+            extend_lookup_table(
+                lookup_table_account,
+                authority_account,
+                &new_addresses,
+                &clock,
+            )
+        }
+        ProgramInstruction::FreezeLookupTable => {
+            let authority_account = next_keyed_account(keyed_accounts)?;
+            update_meta(lookup_table_account, authority_account, |meta| {
+                if meta.num_addresses == 0 {
+                    return Err(InstructionError::InvalidInstructionData);
+                }
+                meta.authority = None;
+                Ok(())
+            })
+        }
+        ProgramInstruction::DeactivateLookupTable => {
+            let authority_account = next_keyed_account(keyed_accounts)?;
+            let clock = from_keyed_account::<Clock>(next_keyed_account(keyed_accounts)?)?;
+            update_meta(lookup_table_account, authority_account, |meta| {
+                meta.deactivation_slot = clock.slot;
+                Ok(())
+            })
+        }
+        ProgramInstruction::CloseLookupTable => {
+            let authority_account = next_keyed_account(keyed_accounts)?;
+            let recipient_account = next_keyed_account(keyed_accounts)?;
+            let clock = from_keyed_account::<Clock>(next_keyed_account(keyed_accounts)?)?;
+            let slot_hashes =
+                from_keyed_account::<SlotHashes>(next_keyed_account(keyed_accounts)?)?;
+            close_lookup_table(
+                lookup_table_account,
+                authority_account,
+                recipient_account,
+                &clock,
+                &slot_hashes,
+            )
+        }
+    }
+}
+
+fn initialize_lookup_table(
+    lookup_table_account: &KeyedAccount,
+    authority: &Pubkey,
+) -> Result<(), InstructionError> {
+    // Requiring the table signature prevents another party from initializing
+    // a freshly allocated table with their own authority
+    if lookup_table_account.signer_key().is_none() {
+        return Err(InstructionError::MissingRequiredSignature);
+    }
+    let mut account = lookup_table_account.try_account_ref_mut()?;
+    if lookup_table_capacity(account.data.len()).is_none() {
+        return Err(InstructionError::InvalidAccountData);
+    }
+    if AddressLookupTable::deserialize_state(&account.data)? != ProgramState::Uninitialized {
+        return Err(InstructionError::AccountAlreadyInitialized);
+    }
+    AddressLookupTable::serialize_state(
+        &ProgramState::LookupTable(LookupTableMeta::new(*authority)),
+        &mut account.data,
+    )
+}
+
+/// Check that `authority_account` signed for an active, mutable table and
+/// apply `f` to the table metadata
+fn update_meta<F>(
+    lookup_table_account: &KeyedAccount,
+    authority_account: &KeyedAccount,
+    f: F,
+) -> Result<(), InstructionError>
+where
+    F: FnOnce(&mut LookupTableMeta) -> Result<(), InstructionError>,
+{
+    let mut account = lookup_table_account.try_account_ref_mut()?;
+    let mut meta = AddressLookupTable::deserialize(&account.data)?.meta;
+    check_authority(&meta, authority_account)?;
+    if !meta.is_active() {
+        return Err(InstructionError::InvalidArgument);
+    }
+    f(&mut meta)?;
+    AddressLookupTable::serialize_state(&ProgramState::LookupTable(meta), &mut account.data)
+}
+
+fn check_authority(
+    meta: &LookupTableMeta,
+    authority_account: &KeyedAccount,
+) -> Result<(), InstructionError> {
+    match meta.authority {
+        None => Err(InstructionError::Immutable),
+        Some(authority) => match authority_account.signer_key() {
+            None => Err(InstructionError::MissingRequiredSignature),
+            Some(signer) if *signer != authority => Err(InstructionError::IncorrectAuthority),
+            Some(_) => Ok(()),
+        },
+    }
+}
+
+fn extend_lookup_table(
+    lookup_table_account: &KeyedAccount,
+    authority_account: &KeyedAccount,
+    new_addresses: &[Pubkey],
+    clock: &Clock,
+) -> Result<(), InstructionError> {
+    if new_addresses.is_empty() {
+        return Err(InstructionError::InvalidInstructionData);
+    }
+    let mut account = lookup_table_account.try_account_ref_mut()?;
+    let capacity =
+        lookup_table_capacity(account.data.len()).ok_or(InstructionError::InvalidAccountData)?;
+    let mut meta = AddressLookupTable::deserialize(&account.data)?.meta;
+    check_authority(&meta, authority_account)?;
+    if !meta.is_active() {
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    let start_index = meta.num_addresses as usize;
+    let end_index = start_index + new_addresses.len();
+    if end_index > capacity {
+        return Err(InstructionError::AccountDataTooSmall);
+    }
+    for (i, address) in new_addresses.iter().enumerate() {
+        let offset = LOOKUP_TABLE_META_SIZE + (start_index + i) * size_of::<Pubkey>();
+        account.data[offset..offset + size_of::<Pubkey>()].copy_from_slice(address.as_ref());
+    }
+
+    if meta.last_extended_slot != clock.slot {
+        meta.last_extended_slot = clock.slot;
+        meta.last_extended_slot_start_index = meta.num_addresses;
+    }
+    meta.num_addresses = end_index as u16;
+    AddressLookupTable::serialize_state(&ProgramState::LookupTable(meta), &mut account.data)
+}
+
+fn close_lookup_table(
+    lookup_table_account: &KeyedAccount,
+    authority_account: &KeyedAccount,
+    recipient_account: &KeyedAccount,
+    clock: &Clock,
+    slot_hashes: &SlotHashes,
+) -> Result<(), InstructionError> {
+    if lookup_table_account.unsigned_key() == recipient_account.unsigned_key() {
+        return Err(InstructionError::InvalidArgument);
+    }
+    let mut account = lookup_table_account.try_account_ref_mut()?;
+    let meta = AddressLookupTable::deserialize(&account.data)?.meta;
+    check_authority(&meta, authority_account)?;
+    // Transactions signed while the table was active stay valid until their
+    // blockhash expires.  The table address can't be reused, possibly for a
+    // table of other addresses, before the deactivation slot leaves the slot
+    // hashes, which outlive any blockhash still accepted.
+    if meta.is_active()
+        || clock.slot <= meta.deactivation_slot
+        || slot_hashes.get(&meta.deactivation_slot).is_some()
+    {
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    let mut recipient = recipient_account.try_account_ref_mut()?;
+    recipient.lamports = recipient
+        .lamports
+        .checked_add(account.lamports)
+        .ok_or(InstructionError::InsufficientFunds)?;
+    account.lamports = 0;
+    for byte in account.data.iter_mut() {
+        *byte = 0;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::lookup_table_space;
+    use bincode::serialize;
+    use solana_sdk::{
+        account::{create_account, Account},
+        hash::Hash,
+        process_instruction::MockInvokeContext,
+        slot_hashes::MAX_ENTRIES,
+        sysvar,
+    };
+    use std::cell::RefCell;
+
+    fn process(
+        instruction: &ProgramInstruction,
+        keyed_accounts: &[KeyedAccount],
+    ) -> Result<(), InstructionError> {
+        process_instruction(
+            &crate::id(),
+            keyed_accounts,
+            &serialize(instruction).unwrap(),
+            &mut MockInvokeContext::default(),
+        )
+    }
+
+    fn clock_account(slot: u64) -> RefCell<Account> {
+        RefCell::new(create_account(
+            &Clock {
+                slot,
+                ..Clock::default()
+            },
+            1,
+        ))
+    }
+
+    #[test]
+    fn test_lookup_table_lifecycle() {
+        let table_key = Pubkey::new_unique();
+        let table_account = RefCell::new(Account::new(1, lookup_table_space(2), &crate::id()));
+        let authority_key = Pubkey::new_unique();
+        let authority_account = RefCell::new(Account::default());
+        let recipient_key = Pubkey::new_unique();
+        let recipient_account = RefCell::new(Account::default());
+        let clock_key = sysvar::clock::id();
+
+        // Table must sign its own initialization
+        assert_eq!(
+            process(
+                &ProgramInstruction::InitializeLookupTable,
+                &[
+                    KeyedAccount::new(&table_key, false, &table_account),
+                    KeyedAccount::new_readonly(&authority_key, false, &authority_account),
+                ],
+            ),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+        process(
+            &ProgramInstruction::InitializeLookupTable,
+            &[
+                KeyedAccount::new(&table_key, true, &table_account),
+                KeyedAccount::new_readonly(&authority_key, false, &authority_account),
+            ],
+        )
+        .unwrap();
+
+        let extend = |new_addresses: Vec<Pubkey>, is_signer: bool, slot: u64| {
+            let clock = clock_account(slot);
+            process(
+                &ProgramInstruction::ExtendLookupTable { new_addresses },
+                &[
+                    KeyedAccount::new(&table_key, false, &table_account),
+                    KeyedAccount::new_readonly(&authority_key, is_signer, &authority_account),
+                    KeyedAccount::new_readonly(&clock_key, false, &clock),
+                ],
+            )
+        };
+        let addresses = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        assert_eq!(
+            extend(vec![addresses[0]], false, 1),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+        extend(vec![addresses[0]], true, 1).unwrap();
+        extend(vec![addresses[1]], true, 2).unwrap();
+        assert_eq!(
+            extend(vec![Pubkey::new_unique()], true, 2),
+            Err(InstructionError::AccountDataTooSmall)
+        );
+
+        let table = AddressLookupTable::deserialize(&table_account.borrow().data).unwrap();
+        assert_eq!(table.addresses, addresses);
+        assert_eq!(table.meta.last_extended_slot, 2);
+        assert_eq!(table.meta.last_extended_slot_start_index, 1);
+
+        let clock = clock_account(3);
+        process(
+            &ProgramInstruction::DeactivateLookupTable,
+            &[
+                KeyedAccount::new(&table_key, false, &table_account),
+                KeyedAccount::new_readonly(&authority_key, true, &authority_account),
+                KeyedAccount::new_readonly(&clock_key, false, &clock),
+            ],
+        )
+        .unwrap();
+
+        let slot_hashes_key = sysvar::slot_hashes::id();
+        let close = |slot: u64| {
+            let clock = clock_account(slot);
+            // Every slot since genesis was rooted, the slot hashes hold the
+            // most recent of them
+            let slot_hashes = (slot.saturating_sub(MAX_ENTRIES as u64)..slot)
+                .map(|slot| (slot, Hash::default()))
+                .collect::<Vec<_>>();
+            let slot_hashes = RefCell::new(create_account(&SlotHashes::new(&slot_hashes), 1));
+            process(
+                &ProgramInstruction::CloseLookupTable,
+                &[
+                    KeyedAccount::new(&table_key, false, &table_account),
+                    KeyedAccount::new_readonly(&authority_key, true, &authority_account),
+                    KeyedAccount::new(&recipient_key, false, &recipient_account),
+                    KeyedAccount::new_readonly(&clock_key, false, &clock),
+                    KeyedAccount::new_readonly(&slot_hashes_key, false, &slot_hashes),
+                ],
+            )
+        };
+        // Tables can't be closed in the slot they were deactivated, nor while
+        // transactions signed before then may still be processed
+        assert_eq!(close(3), Err(InstructionError::InvalidArgument));
+        assert_eq!(close(4), Err(InstructionError::InvalidArgument));
+        assert_eq!(
+            close(3 + MAX_ENTRIES as u64),
+            Err(InstructionError::InvalidArgument)
+        );
+        close(4 + MAX_ENTRIES as u64).unwrap();
+        assert_eq!(table_account.borrow().lamports, 0);
+        assert_eq!(recipient_account.borrow().lamports, 1);
+    }
+
+    #[test]
+    fn test_frozen_lookup_table() {
+        let table_key = Pubkey::new_unique();
+        let table_account = RefCell::new(Account::new(1, lookup_table_space(2), &crate::id()));
+        let authority_key = Pubkey::new_unique();
+        let authority_account = RefCell::new(Account::default());
+        let clock_key = sysvar::clock::id();
+        let clock = clock_account(1);
+
+        process(
+            &ProgramInstruction::InitializeLookupTable,
+            &[
+                KeyedAccount::new(&table_key, true, &table_account),
+                KeyedAccount::new_readonly(&authority_key, false, &authority_account),
+            ],
+        )
+        .unwrap();
+
+        let freeze = || {
+            process(
+                &ProgramInstruction::FreezeLookupTable,
+                &[
+                    KeyedAccount::new(&table_key, false, &table_account),
+                    KeyedAccount::new_readonly(&authority_key, true, &authority_account),
+                ],
+            )
+        };
+        // Empty tables can't be frozen
+        assert_eq!(freeze(), Err(InstructionError::InvalidInstructionData));
+
+        process(
+            &ProgramInstruction::ExtendLookupTable {
+                new_addresses: vec![Pubkey::new_unique()],
+            },
+            &[
+                KeyedAccount::new(&table_key, false, &table_account),
+                KeyedAccount::new_readonly(&authority_key, true, &authority_account),
+                KeyedAccount::new_readonly(&clock_key, false, &clock),
+            ],
+        )
+        .unwrap();
+        freeze().unwrap();
+        assert_eq!(freeze(), Err(InstructionError::Immutable));
+    }
+
+    #[test]
+    fn test_incorrect_authority() {
+        let table_key = Pubkey::new_unique();
+        let table_account = RefCell::new(Account::new(1, lookup_table_space(1), &crate::id()));
+        let authority_account = RefCell::new(Account::default());
+        let mallory_key = Pubkey::new_unique();
+
+        process(
+            &ProgramInstruction::InitializeLookupTable,
+            &[
+                KeyedAccount::new(&table_key, true, &table_account),
+                KeyedAccount::new_readonly(&Pubkey::new_unique(), false, &authority_account),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            process(
+                &ProgramInstruction::FreezeLookupTable,
+                &[
+                    KeyedAccount::new(&table_key, false, &table_account),
+                    KeyedAccount::new_readonly(&mallory_key, true, &authority_account),
+                ],
+            ),
+            Err(InstructionError::IncorrectAuthority)
+        );
+    }
+}
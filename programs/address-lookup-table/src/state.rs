@@ -0,0 +1,201 @@
+use serde_derive::{Deserialize, Serialize};
+use solana_sdk::{clock::Slot, instruction::InstructionError, pubkey::Pubkey};
+use std::mem::size_of;
+
+/// Maximum number of addresses a single lookup table may store, a message
+/// can only index into a table with a u8
+pub const LOOKUP_TABLE_MAX_ADDRESSES: usize = 256;
+
+/// Number of bytes reserved at the start of the account data for the table
+/// metadata, addresses are stored immediately after
+pub const LOOKUP_TABLE_META_SIZE: usize = 64;
+
+/// Account data size of a lookup table able to store `max_addresses`
+pub fn lookup_table_space(max_addresses: usize) -> usize {
+    LOOKUP_TABLE_META_SIZE + max_addresses * size_of::<Pubkey>()
+}
+
+/// Number of addresses a lookup table account of `data_len` bytes can store
+pub fn lookup_table_capacity(data_len: usize) -> Option<usize> {
+    let addresses_len = data_len.checked_sub(LOOKUP_TABLE_META_SIZE)?;
+    if addresses_len % size_of::<Pubkey>() != 0 {
+        return None;
+    }
+    let capacity = addresses_len / size_of::<Pubkey>();
+    if capacity == 0 || capacity > LOOKUP_TABLE_MAX_ADDRESSES {
+        None
+    } else {
+        Some(capacity)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum ProgramState {
+    /// Account has been allocated but not initialized
+    Uninitialized,
+    /// Initialized lookup table
+    LookupTable(LookupTableMeta),
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct LookupTableMeta {
+    /// Slot in which the table was deactivated, `Slot::MAX` while active
+    pub deactivation_slot: Slot,
+    /// Slot in which the table was last extended
+    pub last_extended_slot: Slot,
+    /// Number of addresses stored before the table was extended in
+    /// `last_extended_slot`
+    pub last_extended_slot_start_index: u16,
+    /// Number of addresses stored in the table
+    pub num_addresses: u16,
+    /// Authority allowed to modify the table, `None` once frozen
+    pub authority: Option<Pubkey>,
+}
+
+impl LookupTableMeta {
+    pub fn new(authority: Pubkey) -> Self {
+        Self {
+            deactivation_slot: Slot::MAX,
+            last_extended_slot: 0,
+            last_extended_slot_start_index: 0,
+            num_addresses: 0,
+            authority: Some(authority),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.deactivation_slot == Slot::MAX
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AddressLookupTable {
+    pub meta: LookupTableMeta,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl AddressLookupTable {
+    /// Read the program state stored in a lookup table account
+    pub fn deserialize_state(data: &[u8]) -> Result<ProgramState, InstructionError> {
+        if data.len() < LOOKUP_TABLE_META_SIZE {
+            return Err(InstructionError::InvalidAccountData);
+        }
+        bincode::deserialize(&data[..LOOKUP_TABLE_META_SIZE])
+            .map_err(|_| InstructionError::InvalidAccountData)
+    }
+
+    /// Write `state` into the metadata section of a lookup table account
+    pub fn serialize_state(state: &ProgramState, data: &mut [u8]) -> Result<(), InstructionError> {
+        if data.len() < LOOKUP_TABLE_META_SIZE {
+            return Err(InstructionError::AccountDataTooSmall);
+        }
+        bincode::serialize_into(&mut data[..LOOKUP_TABLE_META_SIZE], state)
+            .map_err(|_| InstructionError::AccountDataTooSmall)
+    }
+
+    /// Deserialize an initialized lookup table account
+    pub fn deserialize(data: &[u8]) -> Result<Self, InstructionError> {
+        let capacity =
+            lookup_table_capacity(data.len()).ok_or(InstructionError::InvalidAccountData)?;
+        let meta = match Self::deserialize_state(data)? {
+            ProgramState::LookupTable(meta) => meta,
+            ProgramState::Uninitialized => return Err(InstructionError::UninitializedAccount),
+        };
+        let num_addresses = meta.num_addresses as usize;
+        if num_addresses > capacity {
+            return Err(InstructionError::InvalidAccountData);
+        }
+        let addresses = data[LOOKUP_TABLE_META_SIZE..]
+            .chunks(size_of::<Pubkey>())
+            .take(num_addresses)
+            .map(Pubkey::new)
+            .collect();
+        Ok(Self { meta, addresses })
+    }
+
+    /// Resolve `indexes` into addresses.  Returns `None` if any index is out
+    /// of range or refers to an address appended during `current_slot`,
+    /// which may not be used until the next slot.
+    pub fn lookup(&self, current_slot: Slot, indexes: &[u8]) -> Option<Vec<Pubkey>> {
+        let usable_addresses = if self.meta.last_extended_slot == current_slot {
+            self.meta.last_extended_slot_start_index as usize
+        } else {
+            self.addresses.len()
+        };
+        indexes
+            .iter()
+            .map(|index| {
+                let index = *index as usize;
+                if index < usable_addresses {
+                    Some(self.addresses[index])
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_fits_reserved_space() {
+        let meta = LookupTableMeta {
+            deactivation_slot: Slot::MAX,
+            last_extended_slot: Slot::MAX,
+            last_extended_slot_start_index: u16::MAX,
+            num_addresses: u16::MAX,
+            authority: Some(Pubkey::new_unique()),
+        };
+        let size = bincode::serialized_size(&ProgramState::LookupTable(meta)).unwrap();
+        assert!(size as usize <= LOOKUP_TABLE_META_SIZE);
+    }
+
+    #[test]
+    fn test_lookup_table_capacity() {
+        assert_eq!(lookup_table_capacity(0), None);
+        assert_eq!(lookup_table_capacity(LOOKUP_TABLE_META_SIZE), None);
+        assert_eq!(lookup_table_capacity(lookup_table_space(1) - 1), None);
+        assert_eq!(lookup_table_capacity(lookup_table_space(1)), Some(1));
+        assert_eq!(
+            lookup_table_capacity(lookup_table_space(LOOKUP_TABLE_MAX_ADDRESSES)),
+            Some(LOOKUP_TABLE_MAX_ADDRESSES)
+        );
+        assert_eq!(
+            lookup_table_capacity(lookup_table_space(LOOKUP_TABLE_MAX_ADDRESSES + 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_lookup() {
+        let addresses: Vec<_> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let mut meta = LookupTableMeta::new(Pubkey::new_unique());
+        meta.num_addresses = 3;
+        meta.last_extended_slot = 5;
+        meta.last_extended_slot_start_index = 2;
+
+        let mut data = vec![0; lookup_table_space(4)];
+        AddressLookupTable::serialize_state(&ProgramState::LookupTable(meta.clone()), &mut data)
+            .unwrap();
+        for (i, address) in addresses.iter().enumerate() {
+            let offset = LOOKUP_TABLE_META_SIZE + i * size_of::<Pubkey>();
+            data[offset..offset + size_of::<Pubkey>()].copy_from_slice(address.as_ref());
+        }
+
+        let table = AddressLookupTable::deserialize(&data).unwrap();
+        assert_eq!(table.meta, meta);
+        assert_eq!(table.addresses, addresses);
+
+        // Addresses appended in the current slot are not usable yet
+        assert_eq!(
+            table.lookup(5, &[1, 0]),
+            Some(vec![addresses[1], addresses[0]])
+        );
+        assert_eq!(table.lookup(5, &[2]), None);
+        assert_eq!(table.lookup(6, &[2]), Some(vec![addresses[2]]));
+        assert_eq!(table.lookup(6, &[3]), None);
+    }
+}
@@ -264,14 +264,15 @@ fn process_transaction_and_record_inner(
     let signature = tx.signatures.get(0).unwrap().clone();
     let txs = vec![tx];
     let tx_batch = bank.prepare_batch(&txs, None);
-    let (mut results, _, mut inner, _transaction_logs) = bank.load_execute_and_commit_transactions(
-        &tx_batch,
-        MAX_PROCESSING_AGE,
-        false,
-        true,
-        false,
-        &mut ExecuteTimings::default(),
-    );
+    let (mut results, _, mut inner, _transaction_logs, _return_data) = bank
+        .load_execute_and_commit_transactions(
+            &tx_batch,
+            MAX_PROCESSING_AGE,
+            false,
+            true,
+            false,
+            &mut ExecuteTimings::default(),
+        );
     let inner_instructions = if inner.is_empty() {
         Some(vec![vec![]])
     } else {
@@ -303,6 +304,7 @@ fn execute_transactions(bank: &Bank, txs: &[Transaction]) -> Vec<ConfirmedTransa
         },
         mut inner_instructions,
         mut transaction_logs,
+        return_data,
     ) = bank.load_execute_and_commit_transactions(
         &batch,
         std::usize::MAX,
@@ -329,6 +331,7 @@ fn execute_transactions(bank: &Bank, txs: &[Transaction]) -> Vec<ConfirmedTransa
         tx_pre_token_balances.into_iter(),
         tx_post_token_balances.into_iter(),
         transaction_logs.into_iter(),
+        return_data.into_iter(),
     )
     .map(
         |(
@@ -340,6 +343,7 @@ fn execute_transactions(bank: &Bank, txs: &[Transaction]) -> Vec<ConfirmedTransa
             pre_token_balances,
             post_token_balances,
             log_messages,
+            return_data,
         )| {
             let fee_calculator = nonce_rollback
                 .map(|nonce_rollback| nonce_rollback.fee_calculator())
@@ -366,14 +370,16 @@ fn execute_transactions(bank: &Bank, txs: &[Transaction]) -> Vec<ConfirmedTransa
                 post_balances,
                 pre_token_balances: Some(pre_token_balances),
                 post_token_balances: Some(post_token_balances),
+                loaded_addresses: None,
                 inner_instructions,
                 log_messages: Some(log_messages),
+                return_data,
             };
 
             ConfirmedTransaction {
                 slot: bank.slot(),
                 transaction: TransactionWithStatusMeta {
-                    transaction: tx.clone(),
+                    transaction: tx.clone().into(),
                     meta: Some(tx_status_meta),
                 },
                 block_time: None,
@@ -384,7 +390,12 @@ fn execute_transactions(bank: &Bank, txs: &[Transaction]) -> Vec<ConfirmedTransa
 }
 
 fn print_confirmed_tx(name: &str, confirmed_tx: ConfirmedTransaction) {
-    let tx = confirmed_tx.transaction.transaction.clone();
+    let tx = confirmed_tx
+        .transaction
+        .transaction
+        .clone()
+        .into_legacy_transaction()
+        .unwrap();
     let encoded = confirmed_tx.encode(UiTransactionEncoding::JsonParsed);
     println!("EXECUTE {} (slot {})", name, encoded.slot);
     println_transaction(&tx, &encoded.transaction.meta, "  ", None);
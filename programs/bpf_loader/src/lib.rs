@@ -1111,6 +1111,8 @@ mod tests {
                 stack_frame_size: 4096,
                 log_pubkey_units: 100,
                 max_cpi_instruction_size: usize::MAX,
+                syscall_base_cost: 100,
+                return_data_bytes_per_unit: 250,
             },
             Rc::new(RefCell::new(Executors::default())),
             None,
@@ -18,9 +18,9 @@ use solana_sdk::{
     entrypoint::{MAX_PERMITTED_DATA_INCREASE, SUCCESS},
     feature_set::{
         abort_on_all_cpi_failures, limit_cpi_loader_invoke, pubkey_log_syscall_enabled,
-        ristretto_mul_syscall_enabled, sha256_syscall_enabled, sol_log_compute_units_syscall,
-        try_find_program_address_syscall_enabled, use_loaded_executables,
-        use_loaded_program_accounts,
+        return_data_syscall_enabled, ristretto_mul_syscall_enabled, sha256_syscall_enabled,
+        sol_log_compute_units_syscall, try_find_program_address_syscall_enabled,
+        use_loaded_executables, use_loaded_program_accounts,
     },
     hash::{Hasher, HASH_BYTES},
     ic_msg,
@@ -28,6 +28,7 @@ use solana_sdk::{
     keyed_account::KeyedAccount,
     native_loader,
     process_instruction::{stable_log, ComputeMeter, InvokeContext, Logger},
+    program::MAX_RETURN_DATA,
     program_error::ProgramError,
     pubkey::{Pubkey, PubkeyError, MAX_SEEDS},
 };
@@ -72,6 +73,8 @@ pub enum SyscallError {
     InstructionTooLarge(usize, usize),
     #[error("Too many accounts passed to inner instruction")]
     TooManyAccounts,
+    #[error("Return data too large ({0} > {1})")]
+    ReturnDataTooLarge(u64, u64),
 }
 impl From<SyscallError> for EbpfError<BPFError> {
     fn from(error: SyscallError) -> Self {
@@ -144,6 +147,13 @@ pub fn register_syscalls(
         .register_syscall_by_name(b"sol_invoke_signed_rust", SyscallInvokeSignedRust::call)?;
     syscall_registry.register_syscall_by_name(b"sol_alloc_free_", SyscallAllocFree::call)?;
 
+    if invoke_context.is_feature_active(&return_data_syscall_enabled::id()) {
+        syscall_registry
+            .register_syscall_by_name(b"sol_set_return_data", SyscallSetReturnData::call)?;
+        syscall_registry
+            .register_syscall_by_name(b"sol_get_return_data", SyscallGetReturnData::call)?;
+    }
+
     Ok(syscall_registry)
 }
 
@@ -277,6 +287,27 @@ pub fn bind_syscall_context_objects<'a>(
         None,
     )?;
 
+    // Return data syscalls
+
+    bind_feature_gated_syscall_context_object!(
+        vm,
+        invoke_context.borrow(),
+        &return_data_syscall_enabled::id(),
+        Box::new(SyscallSetReturnData {
+            invoke_context: invoke_context.clone(),
+            loader_id,
+        }),
+    );
+    bind_feature_gated_syscall_context_object!(
+        vm,
+        invoke_context.borrow(),
+        &return_data_syscall_enabled::id(),
+        Box::new(SyscallGetReturnData {
+            invoke_context: invoke_context.clone(),
+            loader_id,
+        }),
+    );
+
     // Memory allocator
 
     vm.bind_syscall_context_object(
@@ -1729,6 +1760,108 @@ fn call<'a>(
     Ok(SUCCESS)
 }
 
+/// Set the return data of the running program, replacing any previous value
+pub struct SyscallSetReturnData<'a> {
+    invoke_context: Rc<RefCell<&'a mut dyn InvokeContext>>,
+    loader_id: &'a Pubkey,
+}
+impl<'a> SyscallObject<BPFError> for SyscallSetReturnData<'a> {
+    fn call(
+        &mut self,
+        addr: u64,
+        len: u64,
+        _arg3: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &MemoryMapping,
+        result: &mut Result<u64, EbpfError<BPFError>>,
+    ) {
+        let mut invoke_context = question_mark!(
+            self.invoke_context
+                .try_borrow_mut()
+                .map_err(|_| SyscallError::InvokeContextBorrowFailed),
+            result
+        );
+        let bpf_compute_budget = invoke_context.get_bpf_compute_budget();
+        let cost = len / bpf_compute_budget.return_data_bytes_per_unit
+            + bpf_compute_budget.syscall_base_cost;
+        question_mark!(invoke_context.get_compute_meter().consume(cost), result);
+
+        if len > MAX_RETURN_DATA as u64 {
+            *result = Err(SyscallError::ReturnDataTooLarge(len, MAX_RETURN_DATA as u64).into());
+            return;
+        }
+        let return_data = question_mark!(
+            translate_slice::<u8>(memory_mapping, addr, len, self.loader_id),
+            result
+        )
+        .to_vec();
+        question_mark!(
+            invoke_context
+                .set_return_data(return_data)
+                .map_err(SyscallError::InstructionError),
+            result
+        );
+
+        *result = Ok(0);
+    }
+}
+
+/// Get the return data and the id of the program that set it.  Copies up to `len` bytes into
+/// the buffer at `return_data_addr` and returns the full length of the return data, zero if
+/// there is none.
+pub struct SyscallGetReturnData<'a> {
+    invoke_context: Rc<RefCell<&'a mut dyn InvokeContext>>,
+    loader_id: &'a Pubkey,
+}
+impl<'a> SyscallObject<BPFError> for SyscallGetReturnData<'a> {
+    fn call(
+        &mut self,
+        return_data_addr: u64,
+        len: u64,
+        program_id_addr: u64,
+        _arg4: u64,
+        _arg5: u64,
+        memory_mapping: &MemoryMapping,
+        result: &mut Result<u64, EbpfError<BPFError>>,
+    ) {
+        let invoke_context = question_mark!(
+            self.invoke_context
+                .try_borrow()
+                .map_err(|_| SyscallError::InvokeContextBorrowFailed),
+            result
+        );
+        let bpf_compute_budget = invoke_context.get_bpf_compute_budget();
+        question_mark!(
+            invoke_context
+                .get_compute_meter()
+                .consume(bpf_compute_budget.syscall_base_cost),
+            result
+        );
+
+        let (program_id, return_data) = invoke_context.get_return_data();
+        let length = len.min(return_data.len() as u64);
+        if length != 0 {
+            let cost = length / bpf_compute_budget.return_data_bytes_per_unit;
+            question_mark!(invoke_context.get_compute_meter().consume(cost), result);
+
+            let return_data_result = question_mark!(
+                translate_slice_mut::<u8>(memory_mapping, return_data_addr, length, self.loader_id),
+                result
+            );
+            return_data_result.copy_from_slice(&return_data[..length as usize]);
+
+            let program_id_result = question_mark!(
+                translate_type_mut::<Pubkey>(memory_mapping, program_id_addr, self.loader_id),
+                result
+            );
+            *program_id_result = program_id;
+        }
+
+        *result = Ok(return_data.len() as u64);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1736,7 +1869,7 @@ mod tests {
     use solana_sdk::{
         bpf_loader,
         hash::hashv,
-        process_instruction::{MockComputeMeter, MockLogger},
+        process_instruction::{MockComputeMeter, MockInvokeContext, MockLogger},
     };
     use std::str::FromStr;
 
@@ -2388,4 +2521,93 @@ mod tests {
             result
         );
     }
+
+    #[test]
+    fn test_syscall_return_data() {
+        let program_id = Pubkey::new_unique();
+        let mut mock_invoke_context = MockInvokeContext {
+            key: program_id,
+            ..MockInvokeContext::default()
+        };
+        let invoke_context: Rc<RefCell<&mut dyn InvokeContext>> =
+            Rc::new(RefCell::new(&mut mock_invoke_context));
+
+        let data = [1u8, 2, 3, 4];
+        let mut get_buffer = [0u8; 3];
+        let mut get_program_id = Pubkey::default();
+        let memory_mapping = MemoryMapping::new(
+            vec![
+                MemoryRegion {
+                    host_addr: data.as_ptr() as *const _ as u64,
+                    vm_addr: 4096,
+                    len: data.len() as u64,
+                    vm_gap_shift: 63,
+                    is_writable: false,
+                },
+                MemoryRegion {
+                    host_addr: get_buffer.as_mut_ptr() as *const _ as u64,
+                    vm_addr: 8192,
+                    len: get_buffer.len() as u64,
+                    vm_gap_shift: 63,
+                    is_writable: true,
+                },
+                MemoryRegion {
+                    host_addr: &mut get_program_id as *mut _ as u64,
+                    vm_addr: 12288,
+                    len: size_of::<Pubkey>() as u64,
+                    vm_gap_shift: 63,
+                    is_writable: true,
+                },
+            ],
+            &DEFAULT_CONFIG,
+        );
+        let mut set_syscall = SyscallSetReturnData {
+            invoke_context: invoke_context.clone(),
+            loader_id: &bpf_loader::id(),
+        };
+        let mut get_syscall = SyscallGetReturnData {
+            invoke_context: invoke_context.clone(),
+            loader_id: &bpf_loader::id(),
+        };
+
+        // No return data yet
+        let mut result: Result<u64, EbpfError<BPFError>> = Ok(1);
+        get_syscall.call(8192, 3, 12288, 0, 0, &memory_mapping, &mut result);
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(get_program_id, Pubkey::default());
+
+        let mut result: Result<u64, EbpfError<BPFError>> = Ok(1);
+        set_syscall.call(4096, 4, 0, 0, 0, &memory_mapping, &mut result);
+        assert_eq!(result.unwrap(), 0);
+        assert_eq!(
+            invoke_context.borrow().get_return_data(),
+            (program_id, &data[..])
+        );
+
+        // The buffer is smaller than the return data
+        let mut result: Result<u64, EbpfError<BPFError>> = Ok(0);
+        get_syscall.call(8192, 3, 12288, 0, 0, &memory_mapping, &mut result);
+        assert_eq!(result.unwrap(), 4);
+        assert_eq!(get_buffer, [1, 2, 3]);
+        assert_eq!(get_program_id, program_id);
+
+        // Return data is limited to MAX_RETURN_DATA bytes
+        let mut result: Result<u64, EbpfError<BPFError>> = Ok(0);
+        set_syscall.call(
+            4096,
+            MAX_RETURN_DATA as u64 + 1,
+            0,
+            0,
+            0,
+            &memory_mapping,
+            &mut result,
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            EbpfError::UserError(BPFError::SyscallError(SyscallError::ReturnDataTooLarge(
+                MAX_RETURN_DATA as u64 + 1,
+                MAX_RETURN_DATA as u64
+            )))
+        );
+    }
 }
@@ -0,0 +1,16 @@
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::InstructionError,
+    keyed_account::KeyedAccount, process_instruction::InvokeContext,
+    program_utils::limited_deserialize, pubkey::Pubkey,
+};
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    _keyed_accounts: &[KeyedAccount],
+    data: &[u8],
+    _invoke_context: &mut dyn InvokeContext,
+) -> Result<(), InstructionError> {
+    // The requested budget is applied by the runtime before the transaction
+    // executes, so there is nothing left to do beyond validating the data
+    limited_deserialize::<ComputeBudgetInstruction>(data).map(|_| ())
+}
@@ -53,6 +53,7 @@ fn test_accounts_create(bencher: &mut Bencher) {
         None,
         HashSet::new(),
         false,
+        None,
     );
     bencher.iter(|| {
         let mut pubkeys: Vec<Pubkey> = vec![];
@@ -72,6 +73,7 @@ fn test_accounts_squash(bencher: &mut Bencher) {
         None,
         HashSet::new(),
         false,
+        None,
     ));
     let mut pubkeys: Vec<Pubkey> = vec![];
     deposit_many(&prev_bank, &mut pubkeys, 250_000);
@@ -97,6 +99,7 @@ fn test_accounts_hash_bank_hash(bencher: &mut Bencher) {
         &ClusterType::Development,
         HashSet::new(),
         false,
+        None,
     );
     let mut pubkeys: Vec<Pubkey> = vec![];
     let num_accounts = 60_000;
@@ -107,7 +110,7 @@ fn test_accounts_hash_bank_hash(bencher: &mut Bencher) {
         .accounts_db
         .update_accounts_hash(0, &ancestors, true);
     bencher.iter(|| {
-        assert!(accounts.verify_bank_hash_and_lamports(0, &ancestors, total_lamports, true))
+        assert!(accounts.verify_bank_hash_and_lamports(false, 0, &ancestors, total_lamports, true))
     });
 }
 
@@ -119,6 +122,7 @@ fn test_update_accounts_hash(bencher: &mut Bencher) {
         &ClusterType::Development,
         HashSet::new(),
         false,
+        None,
     );
     let mut pubkeys: Vec<Pubkey> = vec![];
     create_test_accounts(&accounts, &mut pubkeys, 50_000, 0);
@@ -138,6 +142,7 @@ fn test_accounts_delta_hash(bencher: &mut Bencher) {
         &ClusterType::Development,
         HashSet::new(),
         false,
+        None,
     );
     let mut pubkeys: Vec<Pubkey> = vec![];
     create_test_accounts(&accounts, &mut pubkeys, 100_000, 0);
@@ -154,6 +159,7 @@ fn bench_delete_dependencies(bencher: &mut Bencher) {
         &ClusterType::Development,
         HashSet::new(),
         false,
+        None,
     );
     let mut old_pubkey = Pubkey::default();
     let zero_account = Account::new(0, 0, &Account::default().owner);
@@ -186,6 +192,7 @@ fn store_accounts_with_possible_contention<F: 'static>(
         &ClusterType::Development,
         HashSet::new(),
         false,
+        None,
     ));
     let num_keys = 1000;
     let slot = 0;
@@ -309,6 +316,7 @@ fn setup_bench_dashmap_iter() -> (Arc<Accounts>, DashMap<Pubkey, (Account, Hash)
         &ClusterType::Development,
         HashSet::new(),
         false,
+        None,
     ));
 
     let dashmap = DashMap::new();
@@ -1,6 +1,6 @@
 use crate::{
     accounts_db::{AccountsDB, BankHashInfo, ErrorCounters, LoadedAccount, ScanStorageResult},
-    accounts_index::{AccountIndex, Ancestors, IndexKey},
+    accounts_index::{AccountIndex, AccountsIndexConfig, Ancestors, IndexKey},
     bank::{
         NonceRollbackFull, NonceRollbackInfo, TransactionCheckResult, TransactionExecutionResult,
     },
@@ -32,7 +32,7 @@ use solana_sdk::{
 };
 use std::{
     collections::{HashMap, HashSet},
-    ops::RangeBounds,
+    ops::{Bound, RangeBounds},
     path::PathBuf,
     sync::{Arc, Mutex, RwLock},
 };
@@ -83,7 +83,7 @@ pub enum AccountAddressFilter {
 
 impl Accounts {
     pub fn new(paths: Vec<PathBuf>, cluster_type: &ClusterType) -> Self {
-        Self::new_with_config(paths, cluster_type, HashSet::new(), false)
+        Self::new_with_config(paths, cluster_type, HashSet::new(), false, None)
     }
 
     pub fn new_with_config(
@@ -91,6 +91,7 @@ impl Accounts {
         cluster_type: &ClusterType,
         account_indexes: HashSet<AccountIndex>,
         caching_enabled: bool,
+        accounts_index_config: Option<AccountsIndexConfig>,
     ) -> Self {
         Self {
             accounts_db: Arc::new(AccountsDB::new_with_config(
@@ -98,6 +99,7 @@ impl Accounts {
                 cluster_type,
                 account_indexes,
                 caching_enabled,
+                accounts_index_config,
             )),
             account_locks: Mutex::new(HashSet::new()),
             readonly_locks: Arc::new(RwLock::new(Some(HashMap::new()))),
@@ -377,6 +379,8 @@ impl Accounts {
         let fee_config = FeeConfig {
             secp256k1_program_enabled: feature_set
                 .is_active(&feature_set::secp256k1_program_enabled::id()),
+            compute_budget_program_enabled: feature_set
+                .is_active(&feature_set::compute_budget_program_enabled::id()),
         };
         OrderedIterator::new(txs, txs_iteration_order)
             .zip(lock_results.into_iter())
@@ -443,6 +447,19 @@ impl Accounts {
         }
     }
 
+    /// Like `load_slow`, but reads the account as of `max_root` even if later slots have since
+    /// been rooted
+    pub fn load_with_max_root(
+        &self,
+        ancestors: &Ancestors,
+        pubkey: &Pubkey,
+        max_root: Slot,
+    ) -> Option<(Account, Slot)> {
+        self.accounts_db
+            .load_with_max_root(ancestors, pubkey, max_root)
+            .filter(|(account, _)| account.lamports > 0)
+    }
+
     /// scans underlying accounts_db for this delta (slot) with a map function
     ///   from LoadedAccount to B
     /// returns only the latest/current version of B for this slot
@@ -572,17 +589,22 @@ impl Accounts {
     #[must_use]
     pub fn verify_bank_hash_and_lamports(
         &self,
+        do_not_use_index: bool,
         slot: Slot,
         ancestors: &Ancestors,
         total_lamports: u64,
         simple_capitalization_enabled: bool,
     ) -> bool {
-        if let Err(err) = self.accounts_db.verify_bank_hash_and_lamports(
-            slot,
-            ancestors,
-            total_lamports,
-            simple_capitalization_enabled,
-        ) {
+        if let Err(err) = self
+            .accounts_db
+            .verify_bank_hash_and_lamports_with_index_option(
+                do_not_use_index,
+                slot,
+                ancestors,
+                total_lamports,
+                simple_capitalization_enabled,
+            )
+        {
             warn!("verify_bank_hash failed: {:?}", err);
             false
         } else {
@@ -640,6 +662,29 @@ impl Accounts {
         )
     }
 
+    /// Load at most `limit` accounts owned by `program_id` that pass `filter`, in pubkey order,
+    /// starting after `after`
+    pub fn load_by_program_with_filter_from<F: Fn(&Account) -> bool>(
+        &self,
+        ancestors: &Ancestors,
+        program_id: &Pubkey,
+        after: Option<&Pubkey>,
+        limit: usize,
+        filter: F,
+    ) -> Vec<(Pubkey, Account)> {
+        let start_bound = after.map_or(Bound::Unbounded, |after| Bound::Excluded(*after));
+        self.accounts_db.checked_range_scan_accounts(
+            ancestors,
+            (start_bound, Bound::Unbounded),
+            |collector: &mut Vec<(Pubkey, Account)>, some_account_tuple| {
+                Self::load_while_filtering(collector, some_account_tuple, |account| {
+                    account.owner == *program_id && filter(account)
+                });
+                collector.len() < limit
+            },
+        )
+    }
+
     pub fn load_by_index_key_with_filter<F: Fn(&Account) -> bool>(
         &self,
         ancestors: &Ancestors,
@@ -655,6 +700,30 @@ impl Accounts {
         )
     }
 
+    /// Load at most `limit` accounts indexed under `index_key` that pass `filter`, in pubkey
+    /// order, starting after `after`
+    pub fn load_by_index_key_with_filter_from<F: Fn(&Account) -> bool>(
+        &self,
+        ancestors: &Ancestors,
+        index_key: &IndexKey,
+        after: Option<&Pubkey>,
+        limit: usize,
+        filter: F,
+    ) -> Vec<(Pubkey, Account)> {
+        let start_bound = after.map_or(Bound::Unbounded, |after| Bound::Excluded(*after));
+        self.accounts_db.checked_index_scan_accounts(
+            ancestors,
+            *index_key,
+            (start_bound, Bound::Unbounded),
+            |collector: &mut Vec<(Pubkey, Account)>, some_account_tuple| {
+                Self::load_while_filtering(collector, some_account_tuple, |account| {
+                    filter(account)
+                });
+                collector.len() < limit
+            },
+        )
+    }
+
     pub fn load_all(&self, ancestors: &Ancestors) -> Vec<(Pubkey, Account, Slot)> {
         self.accounts_db.scan_accounts(
             ancestors,
@@ -784,6 +853,13 @@ impl Accounts {
             Err(TransactionError::AccountInUse) => (),
             Err(TransactionError::SanitizeFailure) => (),
             Err(TransactionError::AccountLoadedTwice) => (),
+            Err(TransactionError::UnsupportedVersion) => (),
+            Err(TransactionError::AddressLookupTableNotFound) => (),
+            Err(TransactionError::InvalidAddressLookupTableOwner) => (),
+            Err(TransactionError::InvalidAddressLookupTableData) => (),
+            Err(TransactionError::InvalidAddressLookupTableIndex) => (),
+            Err(TransactionError::WouldExceedMaxBlockCostLimit) => (),
+            Err(TransactionError::WouldExceedMaxAccountCostLimit) => (),
             _ => {
                 let (writable_keys, readonly_keys) = &tx.message().get_account_keys_by_lock_type();
                 for k in writable_keys {
@@ -818,10 +894,25 @@ impl Accounts {
         &self,
         txs: &[Transaction],
         txs_iteration_order: Option<&[usize]>,
+    ) -> Vec<Result<()>> {
+        self.lock_accounts_with_results(txs, txs_iteration_order, vec![Ok(()); txs.len()])
+    }
+
+    /// Like `lock_accounts`, but transactions whose entry in `results` is an
+    /// error are not locked and keep that error.  `results` is indexed like
+    /// `txs`, while the returned results follow the iteration order.
+    #[must_use]
+    pub fn lock_accounts_with_results(
+        &self,
+        txs: &[Transaction],
+        txs_iteration_order: Option<&[usize]>,
+        results: Vec<Result<()>>,
     ) -> Vec<Result<()>> {
         use solana_sdk::sanitize::Sanitize;
+        assert_eq!(txs.len(), results.len());
         let keys: Vec<Result<_>> = OrderedIterator::new(txs, txs_iteration_order)
-            .map(|(_, tx)| {
+            .map(|(index, tx)| {
+                results[index].clone()?;
                 tx.sanitize().map_err(TransactionError::from)?;
 
                 if Self::has_duplicates(&tx.message.account_keys) {
@@ -1087,8 +1178,13 @@ mod tests {
     ) -> Vec<TransactionLoadResult> {
         let mut hash_queue = BlockhashQueue::new(100);
         hash_queue.register_hash(&tx.message().recent_blockhash, &fee_calculator);
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            HashSet::new(),
+            false,
+            None,
+        );
         for ka in ka.iter() {
             accounts.store_slow_uncached(0, &ka.0, &ka.1);
         }
@@ -1621,8 +1717,13 @@ mod tests {
 
     #[test]
     fn test_load_by_program_slot() {
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            HashSet::new(),
+            false,
+            None,
+        );
 
         // Load accounts owned by various programs into AccountsDB
         let pubkey0 = solana_sdk::pubkey::new_rand();
@@ -1643,10 +1744,132 @@ mod tests {
         assert_eq!(loaded, vec![]);
     }
 
+    #[test]
+    fn test_load_by_program_with_filter_from() {
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            HashSet::new(),
+            false,
+            None,
+        );
+        let program_id = Pubkey::new_unique();
+        let mut pubkeys: Vec<_> = (0..5).map(|_| solana_sdk::pubkey::new_rand()).collect();
+        for (i, pubkey) in pubkeys.iter().enumerate() {
+            accounts.store_slow_uncached(0, pubkey, &Account::new(1, i, &program_id));
+        }
+        accounts.store_slow_uncached(
+            0,
+            &solana_sdk::pubkey::new_rand(),
+            &Account::new(1, 0, &Pubkey::new_unique()),
+        );
+        accounts.add_root(0);
+        pubkeys.sort();
+        let ancestors = vec![(0, 0)].into_iter().collect();
+
+        // Pages of matching accounts in pubkey order
+        let mut after = None;
+        let mut loaded_pubkeys = vec![];
+        loop {
+            let page = accounts.load_by_program_with_filter_from(
+                &ancestors,
+                &program_id,
+                after,
+                2,
+                |_| true,
+            );
+            assert!(page.len() <= 2);
+            loaded_pubkeys.extend(page.iter().map(|(pubkey, _)| *pubkey));
+            match loaded_pubkeys.last() {
+                Some(last) if page.len() == 2 => after = Some(last),
+                _ => break,
+            }
+        }
+        assert_eq!(loaded_pubkeys, pubkeys);
+
+        // The limit counts accounts that pass the filter
+        let loaded = accounts.load_by_program_with_filter_from(
+            &ancestors,
+            &program_id,
+            None,
+            1,
+            |account| account.data.len() == 4,
+        );
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].1.data.len(), 4);
+    }
+
+    #[test]
+    fn test_load_by_index_key_with_filter_from() {
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            vec![AccountIndex::ProgramId].into_iter().collect(),
+            false,
+            None,
+        );
+        let program_id = Pubkey::new_unique();
+        let mut pubkeys: Vec<_> = (0..5).map(|_| solana_sdk::pubkey::new_rand()).collect();
+        for (i, pubkey) in pubkeys.iter().enumerate() {
+            accounts.store_slow_uncached(0, pubkey, &Account::new(1, i, &program_id));
+        }
+        accounts.store_slow_uncached(
+            0,
+            &solana_sdk::pubkey::new_rand(),
+            &Account::new(1, 0, &Pubkey::new_unique()),
+        );
+        accounts.add_root(0);
+        pubkeys.sort();
+        let ancestors = vec![(0, 0)].into_iter().collect();
+        let index_key = IndexKey::ProgramId(program_id);
+
+        // Pages of matching accounts in pubkey order, loading only the accounts of each page
+        let mut after = None;
+        let mut loaded_pubkeys = vec![];
+        loop {
+            let loaded_count = AtomicU64::new(0);
+            let page = accounts.load_by_index_key_with_filter_from(
+                &ancestors,
+                &index_key,
+                after,
+                2,
+                |_| {
+                    loaded_count.fetch_add(1, Ordering::Relaxed);
+                    true
+                },
+            );
+            assert_eq!(loaded_count.load(Ordering::Relaxed), page.len() as u64);
+            assert!(page.len() <= 2);
+            loaded_pubkeys.extend(page.iter().map(|(pubkey, _)| *pubkey));
+            match loaded_pubkeys.last() {
+                Some(last) if page.len() == 2 => after = Some(last),
+                _ => break,
+            }
+        }
+        assert_eq!(loaded_pubkeys, pubkeys);
+
+        // The limit counts accounts that pass the filter
+        let loaded = accounts.load_by_index_key_with_filter_from(
+            &ancestors,
+            &index_key,
+            Some(&pubkeys[0]),
+            1,
+            |account| account.data.len() % 2 == 0,
+        );
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].1.data.len() % 2, 0);
+        assert!(loaded[0].0 > pubkeys[0]);
+    }
+
     #[test]
     fn test_accounts_account_not_found() {
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            HashSet::new(),
+            false,
+            None,
+        );
         let mut error_counters = ErrorCounters::default();
         let ancestors = vec![(0, 0)].into_iter().collect();
 
@@ -1664,8 +1887,13 @@ mod tests {
     #[test]
     #[should_panic]
     fn test_accounts_empty_bank_hash() {
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            HashSet::new(),
+            false,
+            None,
+        );
         accounts.bank_hash_at(1);
     }
 
@@ -1681,8 +1909,13 @@ mod tests {
         let account2 = Account::new(3, 0, &Pubkey::default());
         let account3 = Account::new(4, 0, &Pubkey::default());
 
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            HashSet::new(),
+            false,
+            None,
+        );
         accounts.store_slow_uncached(0, &keypair0.pubkey(), &account0);
         accounts.store_slow_uncached(0, &keypair1.pubkey(), &account1);
         accounts.store_slow_uncached(0, &keypair2.pubkey(), &account2);
@@ -1794,8 +2027,13 @@ mod tests {
         let account1 = Account::new(2, 0, &Pubkey::default());
         let account2 = Account::new(3, 0, &Pubkey::default());
 
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            HashSet::new(),
+            false,
+            None,
+        );
         accounts.store_slow_uncached(0, &keypair0.pubkey(), &account0);
         accounts.store_slow_uncached(0, &keypair1.pubkey(), &account1);
         accounts.store_slow_uncached(0, &keypair2.pubkey(), &account2);
@@ -1924,8 +2162,13 @@ mod tests {
 
         let mut loaded = vec![loaded0, loaded1];
 
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            HashSet::new(),
+            false,
+            None,
+        );
         {
             let mut readonly_locks = accounts.readonly_locks.write().unwrap();
             let readonly_locks = readonly_locks.as_mut().unwrap();
@@ -1977,8 +2220,13 @@ mod tests {
     #[test]
     fn huge_clean() {
         solana_logger::setup();
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            HashSet::new(),
+            false,
+            None,
+        );
         let mut old_pubkey = Pubkey::default();
         let zero_account = Account::new(0, 0, &Account::default().owner);
         info!("storing..");
@@ -2020,8 +2268,13 @@ mod tests {
     #[test]
     fn test_instructions() {
         solana_logger::setup();
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            HashSet::new(),
+            false,
+            None,
+        );
 
         let instructions_key = solana_sdk::sysvar::instructions::id();
         let keypair = Keypair::new();
@@ -2301,8 +2554,13 @@ mod tests {
         let mut loaded = vec![loaded];
 
         let next_blockhash = Hash::new_unique();
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            HashSet::new(),
+            false,
+            None,
+        );
         let collected_accounts = accounts.collect_accounts_to_store(
             &txs,
             None,
@@ -2412,8 +2670,13 @@ mod tests {
         let mut loaded = vec![loaded];
 
         let next_blockhash = Hash::new_unique();
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            HashSet::new(),
+            false,
+            None,
+        );
         let collected_accounts = accounts.collect_accounts_to_store(
             &txs,
             None,
@@ -5,7 +5,7 @@
 use crate::{
     bank::{Bank, BankSlotDelta, DropCallback},
     bank_forks::{BankForks, SnapshotConfig},
-    snapshot_package::AccountsPackageSender,
+    snapshot_package::{AccountsPackageSender, SnapshotType},
     snapshot_utils,
 };
 use crossbeam_channel::{Receiver, SendError, Sender};
@@ -76,6 +76,23 @@ pub struct SnapshotRequestHandler {
 }
 
 impl SnapshotRequestHandler {
+    // Returns the kind of snapshot to archive for a root bank at `block_height`, if any
+    fn snapshot_type(
+        &self,
+        block_height: Slot,
+        last_full_snapshot_slot: Option<Slot>,
+    ) -> Option<SnapshotType> {
+        if block_height % self.snapshot_config.snapshot_interval_slots == 0 {
+            Some(SnapshotType::FullSnapshot)
+        } else if self.snapshot_config.incremental_snapshots_enabled()
+            && block_height % self.snapshot_config.incremental_snapshot_interval_slots == 0
+        {
+            last_full_snapshot_slot.map(SnapshotType::IncrementalSnapshot)
+        } else {
+            None
+        }
+    }
+
     // Returns the latest requested snapshot slot, if one exists
     pub fn handle_snapshot_requests(
         &self,
@@ -91,6 +108,27 @@ impl SnapshotRequestHandler {
                     status_cache_slot_deltas,
                 } = snapshot_request;
 
+                let accounts_db = &snapshot_root_bank.rc.accounts.accounts_db;
+                // Archives are written by the snapshot packager after this request, so incremental
+                // snapshots are only taken on top of the last full snapshot once a later request
+                // finds its archive
+                let archived_full_snapshot_slot =
+                    accounts_db.last_full_snapshot_slot().filter(|slot| {
+                        snapshot_utils::get_highest_snapshot_archive_path(
+                            &self.snapshot_config.snapshot_package_output_path,
+                        )
+                        .map_or(false, |(_, (archive_slot, _, _))| archive_slot == *slot)
+                    });
+                let snapshot_type = self.snapshot_type(
+                    snapshot_root_bank.block_height(),
+                    archived_full_snapshot_slot,
+                );
+                if snapshot_type == Some(SnapshotType::FullSnapshot) {
+                    // Keep the zero-lamport accounts updated from now on, which the incremental
+                    // snapshots on top of this one need, out of the clean below
+                    accounts_db.set_last_full_snapshot_slot(Some(snapshot_root_bank.slot()));
+                }
+
                 let mut shrink_time = Measure::start("shrink_time");
                 if !accounts_db_caching_enabled {
                     snapshot_root_bank
@@ -154,6 +192,7 @@ impl SnapshotRequestHandler {
                     self.snapshot_config.snapshot_version,
                     &self.snapshot_config.archive_format,
                     hash_for_testing,
+                    snapshot_type,
                 );
                 if r.is_err() {
                     warn!(
@@ -342,6 +381,9 @@ impl AccountsBackgroundService {
                             bank.force_flush_accounts_cache();
                         }
                         bank.clean_accounts(true);
+                        if accounts_db_caching_enabled {
+                            bank.pack_ancient_slots();
+                        }
                         last_cleaned_block_height = bank.block_height();
                     }
                 }
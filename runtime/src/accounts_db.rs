@@ -20,10 +20,12 @@
 
 use crate::{
     accounts_cache::{AccountsCache, CachedAccount, SlotCache},
+    accounts_hash::{self, MAX_IN_MEMORY_HASH_ENTRIES},
     accounts_index::{
-        AccountIndex, AccountsIndex, AccountsIndexRootsStats, Ancestors, IndexKey, IsCached,
-        SlotList, SlotSlice, ZeroLamport,
+        AccountIndex, AccountsIndex, AccountsIndexConfig, AccountsIndexRootsStats, Ancestors,
+        IndexKey, IsCached, SlotList, SlotSlice, ZeroLamport,
     },
+    accounts_update_notifier_interface::AccountsUpdateNotifier,
     append_vec::{AppendVec, StoredAccountMeta, StoredMeta},
     contains::Contains,
 };
@@ -53,7 +55,7 @@ use std::{
     collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet},
     convert::{TryFrom, TryInto},
     io::{Error as IOError, Result as IOResult},
-    ops::RangeBounds,
+    ops::{Range, RangeBounds},
     path::{Path, PathBuf},
     sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
     sync::{Arc, Mutex, MutexGuard, RwLock},
@@ -73,6 +75,14 @@ pub const DEFAULT_NUM_THREADS: u32 = 8;
 pub const DEFAULT_NUM_DIRS: u32 = 4;
 pub const SHRINK_RATIO: f64 = 0.80;
 
+// Size of the ancient storages `pack_ancient_slots()` packs the alive accounts of many old rooted
+// slots into
+pub const ANCIENT_APPEND_VEC_SIZE: u64 = 128 * 1024 * 1024;
+
+/// The slots the accounts of an ancient storage were originally written in, as `(offset, slot)`
+/// runs sorted by offset: the accounts from `offset` up to the next run were written in `slot`.
+pub type OriginalSlots = Vec<(usize, Slot)>;
+
 // A specially reserved storage id just for entries in the cache, so that
 // operations that take a storage entry can maintain a common interface
 // when interacting with cached accounts. This id is "virtual" in that it
@@ -92,7 +102,7 @@ const CACHE_VIRTUAL_WRITE_VERSION: u64 = 0;
 const CACHE_VIRTUAL_OFFSET: usize = 0;
 const CACHE_VIRTUAL_STORED_SIZE: usize = 0;
 
-const MERKLE_FANOUT: usize = 16;
+pub(crate) const MERKLE_FANOUT: usize = 16;
 
 type DashMapVersionHash = DashMap<Pubkey, (u64, Hash)>;
 
@@ -123,9 +133,11 @@ pub struct ErrorCounters {
     pub invalid_account_index: usize,
     pub invalid_program_for_execution: usize,
     pub not_allowed_during_cluster_maintenance: usize,
+    pub would_exceed_max_block_cost_limit: usize,
+    pub would_exceed_max_account_cost_limit: usize,
 }
 
-#[derive(Default, Debug, PartialEq, Clone)]
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct AccountInfo {
     /// index identifying the append storage
     store_id: AppendVecId,
@@ -167,27 +179,6 @@ type ReclaimResult = (AccountSlots, AppendVecOffsets);
 type StorageFinder<'a> = Box<dyn Fn(Slot, usize) -> Arc<AccountStorageEntry> + 'a>;
 type ShrinkCandidates = HashMap<Slot, HashMap<AppendVecId, Arc<AccountStorageEntry>>>;
 
-#[derive(Default, Debug, PartialEq, Clone)]
-struct CalculateHashIntermediate {
-    pub version: u64,
-    pub hash: Hash,
-    pub lamports: u64,
-    pub raw_lamports: u64,
-    pub slot: Slot,
-}
-
-impl CalculateHashIntermediate {
-    pub fn new(version: u64, hash: Hash, lamports: u64, raw_lamports: u64, slot: Slot) -> Self {
-        Self {
-            version,
-            hash,
-            lamports,
-            raw_lamports,
-            slot,
-        }
-    }
-}
-
 trait Versioned {
     fn version(&self) -> u64;
 }
@@ -198,12 +189,6 @@ impl Versioned for (u64, Hash) {
     }
 }
 
-impl Versioned for CalculateHashIntermediate {
-    fn version(&self) -> u64 {
-        self.version
-    }
-}
-
 impl Versioned for (u64, AccountInfo) {
     fn version(&self) -> u64 {
         self.0
@@ -304,6 +289,13 @@ impl<'a> LoadedAccount<'a> {
         }
     }
 
+    pub fn data(&self) -> &[u8] {
+        match self {
+            LoadedAccount::Stored(stored_account_meta) => stored_account_meta.data,
+            LoadedAccount::Cached((_, cached_account)) => &cached_account.account.data,
+        }
+    }
+
     pub fn account(self) -> Account {
         match self {
             LoadedAccount::Stored(stored_account_meta) => stored_account_meta.clone_account(),
@@ -404,6 +396,9 @@ pub struct AccountStorageEntry {
     approx_store_count: AtomicUsize,
 
     alive_bytes: AtomicUsize,
+
+    /// Set for ancient storages only, see `is_ancient()`
+    original_slots: RwLock<Option<OriginalSlots>>,
 }
 
 impl AccountStorageEntry {
@@ -419,6 +414,7 @@ impl AccountStorageEntry {
             count_and_status: RwLock::new((0, AccountStorageStatus::Available)),
             approx_store_count: AtomicUsize::new(0),
             alive_bytes: AtomicUsize::new(0),
+            original_slots: RwLock::new(None),
         }
     }
 
@@ -435,6 +431,7 @@ impl AccountStorageEntry {
             count_and_status: RwLock::new((0, AccountStorageStatus::Available)),
             approx_store_count: AtomicUsize::new(num_accounts),
             alive_bytes: AtomicUsize::new(0),
+            original_slots: RwLock::new(None),
         }
     }
 
@@ -467,6 +464,7 @@ impl AccountStorageEntry {
         self.id.store(id, Ordering::Relaxed);
         self.approx_store_count.store(0, Ordering::Relaxed);
         self.alive_bytes.store(0, Ordering::Relaxed);
+        *self.original_slots.write().unwrap() = None;
     }
 
     pub fn status(&self) -> AccountStorageStatus {
@@ -497,6 +495,45 @@ impl AccountStorageEntry {
         self.count() > 0
     }
 
+    /// Whether this storage holds accounts packed from older slots by `pack_ancient_slots()`.
+    /// The stored hashes of those accounts were computed for the slot they were originally
+    /// written in, see `original_slot()`, not for `slot()`.
+    pub fn is_ancient(&self) -> bool {
+        self.original_slots.read().unwrap().is_some()
+    }
+
+    /// The slot the account stored at `offset` was originally written in
+    pub fn original_slot(&self, offset: usize) -> Slot {
+        match &*self.original_slots.read().unwrap() {
+            Some(original_slots) => {
+                match original_slots.binary_search_by(|(start, _)| start.cmp(&offset)) {
+                    Ok(run) => original_slots[run].1,
+                    Err(0) => self.slot(),
+                    Err(next_run) => original_slots[next_run - 1].1,
+                }
+            }
+            None => self.slot(),
+        }
+    }
+
+    pub(crate) fn original_slots(&self) -> Option<OriginalSlots> {
+        self.original_slots.read().unwrap().clone()
+    }
+
+    /// Marks this storage as ancient, or not, when loaded back from a snapshot
+    pub(crate) fn set_original_slots(&self, original_slots: Option<OriginalSlots>) {
+        *self.original_slots.write().unwrap() = original_slots;
+    }
+
+    // Records that the accounts appended from now on were originally written in `slot`
+    fn add_original_slot(&self, slot: Slot) {
+        let mut original_slots = self.original_slots.write().unwrap();
+        let original_slots = original_slots.get_or_insert_with(Vec::new);
+        if original_slots.last().map(|(_, last)| *last) != Some(slot) {
+            original_slots.push((self.accounts.len(), slot));
+        }
+    }
+
     pub fn slot(&self) -> Slot {
         self.slot.load(Ordering::Acquire)
     }
@@ -634,6 +671,15 @@ struct FrozenAccountInfo {
     pub lamports: u64, // Account balance cannot be lower than this amount
 }
 
+struct FoundStoredAccount {
+    account: Account,
+    account_hash: Hash,
+    account_size: usize,
+    store_id: AppendVecId,
+    offset: usize,
+    write_version: u64,
+}
+
 #[derive(Default)]
 pub struct StoreAccountsTiming {
     store_accounts_elapsed: u64,
@@ -707,6 +753,16 @@ pub struct AccountsDB {
     /// to drive clean_accounts
     /// Generated by get_accounts_delta_hash
     uncleaned_pubkeys: DashMap<Slot, Vec<Pubkey>>,
+
+    /// Notified of every account written through `store()`
+    accounts_update_notifier: RwLock<Option<AccountsUpdateNotifier>>,
+
+    /// Slot of the latest full snapshot, which incremental snapshots are generated on top of,
+    /// set as soon as that snapshot is taken or loaded.
+    /// Zero-lamport accounts updated after this slot are not purged by clean, since
+    /// otherwise an incremental snapshot would not contain them and the older version
+    /// in the full snapshot would be resurrected when loading the pair.
+    last_full_snapshot_slot: RwLock<Option<Slot>>,
 }
 
 #[derive(Debug, Default)]
@@ -1044,13 +1100,15 @@ impl Default for AccountsDB {
             cluster_type: None,
             account_indexes: HashSet::new(),
             caching_enabled: false,
+            accounts_update_notifier: RwLock::new(None),
+            last_full_snapshot_slot: RwLock::new(None),
         }
     }
 }
 
 impl AccountsDB {
     pub fn new(paths: Vec<PathBuf>, cluster_type: &ClusterType) -> Self {
-        AccountsDB::new_with_config(paths, cluster_type, HashSet::new(), false)
+        AccountsDB::new_with_config(paths, cluster_type, HashSet::new(), false, None)
     }
 
     pub fn new_with_config(
@@ -1058,9 +1116,12 @@ impl AccountsDB {
         cluster_type: &ClusterType,
         account_indexes: HashSet<AccountIndex>,
         caching_enabled: bool,
+        accounts_index_config: Option<AccountsIndexConfig>,
     ) -> Self {
+        let accounts_index = AccountsIndex::new(accounts_index_config);
         let new = if !paths.is_empty() {
             Self {
+                accounts_index,
                 paths,
                 temp_paths: None,
                 cluster_type: Some(*cluster_type),
@@ -1073,6 +1134,7 @@ impl AccountsDB {
             // for testing
             let (temp_dirs, paths) = get_temp_accounts_paths(DEFAULT_NUM_DIRS).unwrap();
             Self {
+                accounts_index,
                 paths,
                 temp_paths: Some(temp_dirs),
                 cluster_type: Some(*cluster_type),
@@ -1098,6 +1160,52 @@ impl AccountsDB {
         *shrink_paths = Some(paths);
     }
 
+    pub fn set_accounts_update_notifier(&self, notifier: Option<AccountsUpdateNotifier>) {
+        *self.accounts_update_notifier.write().unwrap() = notifier;
+    }
+
+    pub fn set_last_full_snapshot_slot(&self, slot: Option<Slot>) {
+        *self.last_full_snapshot_slot.write().unwrap() = slot;
+    }
+
+    pub fn last_full_snapshot_slot(&self) -> Option<Slot> {
+        *self.last_full_snapshot_slot.read().unwrap()
+    }
+
+    /// Sends every account visible from `ancestors` to the accounts update
+    /// notifier as a startup account, followed by the end-of-startup marker.
+    pub fn notify_account_restore_from_snapshot(&self, ancestors: &Ancestors) {
+        let notifier = self.accounts_update_notifier.read().unwrap();
+        if let Some(notifier) = notifier.as_ref() {
+            let mut measure = Measure::start("accounts_restore_notify");
+            let notifier = notifier.read().unwrap();
+            let num_accounts: usize =
+                self.scan_accounts(ancestors, |num_accounts: &mut usize, some_account_tuple| {
+                    if let Some((pubkey, account, slot)) = some_account_tuple {
+                        notifier.notify_account_restore_from_snapshot(slot, pubkey, &account);
+                        *num_accounts += 1;
+                    }
+                });
+            notifier.notify_end_of_restore_from_snapshot();
+            measure.stop();
+            datapoint_info!(
+                "accounts_restore_notify",
+                ("num_accounts", num_accounts, i64),
+                ("elapsed_us", measure.as_us(), i64),
+            );
+        }
+    }
+
+    fn notify_account_update(&self, slot: Slot, accounts: &[(&Pubkey, &Account)]) {
+        let notifier = self.accounts_update_notifier.read().unwrap();
+        if let Some(notifier) = notifier.as_ref() {
+            let notifier = notifier.read().unwrap();
+            for (pubkey, account) in accounts {
+                notifier.notify_account_update(slot, pubkey, account);
+            }
+        }
+    }
+
     pub fn file_size(&self) -> u64 {
         self.file_size
     }
@@ -1377,6 +1485,7 @@ impl AccountsDB {
         let pubkeys = self.construct_candidate_clean_keys(max_clean_root, &mut key_timings);
 
         let total_keys_count = pubkeys.len();
+        let last_full_snapshot_slot = self.last_full_snapshot_slot();
         let mut accounts_scan = Measure::start("accounts_scan");
         // parallel scan the index.
         let (mut purges, purges_in_root) = {
@@ -1392,7 +1501,12 @@ impl AccountsDB {
                             {
                                 let slot_list = locked_entry.slot_list();
                                 let (slot, account_info) = &slot_list[index];
-                                if account_info.lamports == 0 {
+                                // Zero-lamport accounts updated after the last full snapshot
+                                // are kept for the incremental snapshots on top of it
+                                if account_info.lamports == 0
+                                    && last_full_snapshot_slot
+                                        .map_or(true, |full_slot| *slot <= full_slot)
+                                {
                                     purges.insert(
                                         *pubkey,
                                         self.accounts_index
@@ -1422,12 +1536,8 @@ impl AccountsDB {
                                     }
                                     purges_in_root.push(*pubkey);
                                 }
-                            } else {
-                                let r_accounts_index =
-                                    self.accounts_index.account_maps.read().unwrap();
-                                if !r_accounts_index.contains_key(pubkey) {
-                                    self.accounts_index.remove_zero_lamport_key(pubkey);
-                                }
+                            } else if !self.accounts_index.contains_key(pubkey) {
+                                self.accounts_index.remove_zero_lamport_key(pubkey);
                             }
                         }
                         (purges, purges_in_root)
@@ -1664,19 +1774,12 @@ impl AccountsDB {
         );
     }
 
-    fn do_shrink_slot_stores<'a, I>(&'a self, slot: Slot, stores: I)
+    // Reads the latest version of every account stored in `stores`, along with the total size of
+    // the stores
+    fn load_stored_accounts<'a, I>(stores: I) -> (HashMap<Pubkey, FoundStoredAccount>, u64)
     where
         I: Iterator<Item = &'a Arc<AccountStorageEntry>>,
     {
-        struct FoundStoredAccount {
-            account: Account,
-            account_hash: Hash,
-            account_size: usize,
-            store_id: AppendVecId,
-            offset: usize,
-            write_version: u64,
-        }
-        debug!("do_shrink_slot_stores: slot: {}", slot);
         let mut stored_accounts: HashMap<Pubkey, FoundStoredAccount> = HashMap::new();
         let mut original_bytes = 0;
         for store in stores {
@@ -1710,8 +1813,17 @@ impl AccountsDB {
                 start = next;
             }
         }
+        (stored_accounts, original_bytes)
+    }
 
-        let mut index_read_elapsed = Measure::start("index_read_elapsed");
+    // Filters `stored_accounts` down to the ones still referenced by the index, returning them
+    // with their total stored size. If `unref_dead` is set, the accounts which are not alive
+    // anymore are unref'ed, as their storage is being rewritten without them.
+    fn filter_alive_accounts<'a>(
+        &self,
+        stored_accounts: &'a HashMap<Pubkey, FoundStoredAccount>,
+        unref_dead: bool,
+    ) -> (Vec<(&'a Pubkey, &'a FoundStoredAccount)>, u64) {
         let mut alive_total = 0;
         let alive_accounts: Vec<_> = {
             stored_accounts
@@ -1733,7 +1845,9 @@ impl AccountsDB {
                             // It would have had a ref to the storage from the initial store, but it will
                             // not exist in the re-written slot. Unref it to keep the index consistent with
                             // rewriting the storage entries.
-                            locked_entry.unref()
+                            if unref_dead {
+                                self.accounts_index.unref_from_storage(pubkey);
+                            }
                         } else {
                             alive_total += *account_size as u64;
                         }
@@ -1744,6 +1858,18 @@ impl AccountsDB {
                 })
                 .collect()
         };
+        (alive_accounts, alive_total)
+    }
+
+    fn do_shrink_slot_stores<'a, I>(&'a self, slot: Slot, stores: I)
+    where
+        I: Iterator<Item = &'a Arc<AccountStorageEntry>>,
+    {
+        debug!("do_shrink_slot_stores: slot: {}", slot);
+        let (stored_accounts, original_bytes) = Self::load_stored_accounts(stores);
+
+        let mut index_read_elapsed = Measure::start("index_read_elapsed");
+        let (alive_accounts, alive_total) = self.filter_alive_accounts(&stored_accounts, true);
         index_read_elapsed.stop();
         let aligned_total: u64 = self.page_align(alive_total);
 
@@ -1904,6 +2030,11 @@ impl AccountsDB {
         if let Some(stores_lock) = self.storage.get_slot_stores(slot) {
             let stores: Vec<Arc<AccountStorageEntry>> =
                 stores_lock.read().unwrap().values().cloned().collect();
+            if stores.iter().any(|store| store.is_ancient()) {
+                // Ancient storages are repacked by `pack_ancient_slots()` instead, a shrunken
+                // storage wouldn't know the original slots of its accounts
+                return 0;
+            }
             let mut alive_count = 0;
             let mut stored_count = 0;
             for store in &stores {
@@ -1959,6 +2090,214 @@ impl AccountsDB {
         }
     }
 
+    fn num_stores(&self) -> usize {
+        self.storage
+            .0
+            .iter()
+            .map(|iter_item| iter_item.value().read().unwrap().len())
+            .sum()
+    }
+
+    /// Packs the alive accounts of the rooted slots older than `oldest_non_ancient_slot` into a
+    /// few large ancient storages, so that a long-running node doesn't keep at least one
+    /// mmapped file around for every old slot.
+    ///
+    /// Accounts are moved into the slot of the ancient storage they are packed into, which is
+    /// never newer than the slot they were found in. They keep their stored hash and write
+    /// version, and the ancient storage records the slot they were originally written in, so
+    /// neither the accounts hash nor snapshots change. The emptied slots are cleaned up like any
+    /// other dead slot.
+    ///
+    /// Only slots up to the last full snapshot slot are packed: an incremental snapshot only
+    /// holds the storages of the slots after it, so accounts moved from there into an older slot
+    /// would be missing from it. For the same reason, the ancient storages of earlier calls,
+    /// which may be part of the last full snapshot, are never appended to.
+    pub fn pack_ancient_slots(&self, oldest_non_ancient_slot: Slot) {
+        let last_full_snapshot_slot = match self.last_full_snapshot_slot() {
+            Some(last_full_snapshot_slot) => last_full_snapshot_slot,
+            None => return,
+        };
+        let mut measure = Measure::start("pack_ancient_slots");
+        let num_stores_before = self.num_stores();
+        let mut slots: Vec<Slot> = self
+            .all_slots_in_storage()
+            .into_iter()
+            .filter(|slot| {
+                *slot < oldest_non_ancient_slot
+                    && *slot <= last_full_snapshot_slot
+                    && self.accounts_index.is_root(*slot)
+            })
+            .collect();
+        slots.sort_unstable();
+
+        // Only ever filled with the slots of this call
+        let mut current_ancient = None;
+        let mut num_slots_packed = 0;
+        let mut num_accounts_packed = 0;
+        for slot in slots {
+            if let Some(num_accounts) = self.pack_ancient_slot(slot, &mut current_ancient) {
+                num_slots_packed += 1;
+                num_accounts_packed += num_accounts;
+            }
+        }
+        measure.stop();
+
+        let num_stores_after = self.num_stores();
+        if num_slots_packed > 0 {
+            info!(
+                "pack_ancient_slots: packed {} slots, stores: {} => {}",
+                num_slots_packed, num_stores_before, num_stores_after
+            );
+        }
+        datapoint_info!(
+            "pack_ancient_slots",
+            ("num_slots_packed", num_slots_packed, i64),
+            ("num_accounts_packed", num_accounts_packed, i64),
+            ("num_stores_before", num_stores_before, i64),
+            ("num_stores_after", num_stores_after, i64),
+            ("elapsed_us", measure.as_us(), i64),
+        );
+    }
+
+    // Moves the alive accounts of `slot` into `current_ancient`, creating a new ancient storage
+    // in `slot` when there is none yet or it is too full. Returns the number of accounts moved,
+    // or `None` if the slot was left as is.
+    fn pack_ancient_slot(
+        &self,
+        slot: Slot,
+        current_ancient: &mut Option<Arc<AccountStorageEntry>>,
+    ) -> Option<usize> {
+        let stores = self.storage.get_slot_storage_entries(slot)?;
+        if let [store] = &stores[..] {
+            let is_sparse =
+                (store.alive_bytes() as f64 / store.written_bytes() as f64) < SHRINK_RATIO;
+            if store.is_ancient() && !is_sparse {
+                // Already packed by an earlier call, sparse ones are repacked below like any
+                // other slot
+                return None;
+            }
+        }
+
+        let (stored_accounts, _original_bytes) = Self::load_stored_accounts(stores.iter());
+        // Whether the dead accounts need to be unref'ed depends on the slot they end up in, see
+        // below
+        let (alive_accounts, alive_total) = self.filter_alive_accounts(&stored_accounts, false);
+        if alive_accounts.is_empty() {
+            return None;
+        }
+
+        let target = match current_ancient {
+            Some(ancient)
+                if ancient.accounts.capacity() - ancient.accounts.len() as u64 >= alive_total =>
+            {
+                ancient.clone()
+            }
+            _ => {
+                let ancient = self.create_and_insert_store(
+                    slot,
+                    std::cmp::max(ANCIENT_APPEND_VEC_SIZE, alive_total),
+                    "ancient",
+                );
+                *current_ancient = Some(ancient.clone());
+                ancient
+            }
+        };
+        let target_slot = target.slot();
+
+        // The accounts are appended grouped by the slot they were originally written in, which
+        // differs from `slot` for the accounts of a sparse ancient storage being repacked
+        let mut alive_accounts_by_original_slot: BTreeMap<Slot, Vec<_>> = BTreeMap::new();
+        for (pubkey, alive_account) in &alive_accounts {
+            let original_slot = stores
+                .iter()
+                .find(|store| store.append_vec_id() == alive_account.store_id)
+                .map(|store| store.original_slot(alive_account.offset))
+                .unwrap_or(slot);
+            alive_accounts_by_original_slot
+                .entry(original_slot)
+                .or_default()
+                .push((*pubkey, *alive_account));
+        }
+
+        let mut reclaims = SlotList::new();
+        let mut unref_pubkeys = vec![];
+        if target_slot != slot {
+            // Drop every older version first, so the moved version is still the latest rooted
+            // one once it lives in the older `target_slot`
+            for (pubkey, _) in &alive_accounts {
+                let num_reclaims = reclaims.len();
+                self.accounts_index.clean_rooted_entries(
+                    pubkey,
+                    &mut reclaims,
+                    Some(slot),
+                    &self.account_indexes,
+                );
+                if reclaims[num_reclaims..]
+                    .iter()
+                    .any(|(reclaimed_slot, _)| *reclaimed_slot == target_slot)
+                {
+                    // `target_slot` already has a ref for an older version of this account
+                    unref_pubkeys.push(**pubkey);
+                }
+            }
+        }
+
+        for (original_slot, alive_accounts) in alive_accounts_by_original_slot {
+            let mut accounts = Vec::with_capacity(alive_accounts.len());
+            let mut hashes = Vec::with_capacity(alive_accounts.len());
+            let mut write_versions = Vec::with_capacity(alive_accounts.len());
+            for (pubkey, alive_account) in alive_accounts {
+                accounts.push((pubkey, &alive_account.account));
+                hashes.push(alive_account.account_hash);
+                write_versions.push(alive_account.write_version);
+            }
+            target.add_original_slot(original_slot);
+            let target = target.clone();
+            self.store_accounts_frozen(
+                target_slot,
+                &accounts,
+                &hashes,
+                Some(Box::new(move |_, _| target.clone())),
+                Some(Box::new(write_versions.into_iter())),
+            );
+        }
+
+        if target_slot == slot {
+            // Same as shrinking: the old storages of the slot are empty now, and the accounts
+            // which were not alive anymore don't exist in the rewritten slot
+            let alive_pubkeys: HashSet<&Pubkey> =
+                alive_accounts.iter().map(|(pubkey, _)| *pubkey).collect();
+            for pubkey in stored_accounts.keys() {
+                if !alive_pubkeys.contains(pubkey) {
+                    self.accounts_index.unref_from_storage(pubkey);
+                }
+            }
+            self.shrink_candidate_slots.lock().unwrap().remove(&slot);
+            if let Some(slot_stores) = self.storage.get_slot_stores(slot) {
+                slot_stores
+                    .write()
+                    .unwrap()
+                    .retain(|_key, store| store.count() > 0);
+            }
+        } else {
+            for pubkey in &unref_pubkeys {
+                self.accounts_index.unref_from_storage(pubkey);
+            }
+            for (pubkey, _) in &alive_accounts {
+                self.accounts_index.purge_exact(
+                    pubkey,
+                    &slot,
+                    &mut reclaims,
+                    &self.account_indexes,
+                );
+            }
+            // Removes `slot` as a dead slot, since none of its accounts are alive anymore. That
+            // unrefs all the accounts stored in it.
+            self.handle_reclaims(&reclaims, None, false, None, false);
+        }
+        Some(alive_accounts.len())
+    }
+
     pub fn scan_accounts<F, A>(&self, ancestors: &Ancestors, scan_func: F) -> A
     where
         F: Fn(&mut A, Option<(&Pubkey, Account, Slot)>),
@@ -2045,6 +2384,38 @@ impl AccountsDB {
         collector
     }
 
+    /// Scan the accounts within `range` in pubkey order, until `scan_func` returns false
+    pub fn checked_range_scan_accounts<F, A, R>(
+        &self,
+        ancestors: &Ancestors,
+        range: R,
+        scan_func: F,
+    ) -> A
+    where
+        F: Fn(&mut A, Option<(&Pubkey, Account, Slot)>) -> bool,
+        A: Default,
+        R: RangeBounds<Pubkey>,
+    {
+        let mut collector = A::default();
+        self.accounts_index.checked_range_scan_accounts(
+            ancestors,
+            range,
+            |pubkey, (account_info, slot)| {
+                let account_slot = self
+                    .get_account_accessor_from_cache_or_storage(
+                        slot,
+                        pubkey,
+                        account_info.store_id,
+                        account_info.offset,
+                    )
+                    .get_loaded_account()
+                    .map(|loaded_account| (pubkey, loaded_account.account(), slot));
+                scan_func(&mut collector, account_slot)
+            },
+        );
+        collector
+    }
+
     pub fn index_scan_accounts<F, A>(
         &self,
         ancestors: &Ancestors,
@@ -2075,6 +2446,41 @@ impl AccountsDB {
         collector
     }
 
+    /// Scan the accounts indexed under `index_key` within `range` in pubkey order, until
+    /// `scan_func` returns false
+    pub fn checked_index_scan_accounts<F, A, R>(
+        &self,
+        ancestors: &Ancestors,
+        index_key: IndexKey,
+        range: R,
+        scan_func: F,
+    ) -> A
+    where
+        F: Fn(&mut A, Option<(&Pubkey, Account, Slot)>) -> bool,
+        A: Default,
+        R: RangeBounds<Pubkey>,
+    {
+        let mut collector = A::default();
+        self.accounts_index.checked_index_scan_accounts(
+            ancestors,
+            index_key,
+            range,
+            |pubkey, (account_info, slot)| {
+                let account_slot = self
+                    .get_account_accessor_from_cache_or_storage(
+                        slot,
+                        pubkey,
+                        account_info.store_id,
+                        account_info.offset,
+                    )
+                    .get_loaded_account()
+                    .map(|loaded_account| (pubkey, loaded_account.account(), slot));
+                scan_func(&mut collector, account_slot)
+            },
+        );
+        collector
+    }
+
     /// Scan a specific slot through all the account storage in parallel
     pub fn scan_account_storage<R, B>(
         &self,
@@ -2208,6 +2614,16 @@ impl AccountsDB {
         self.load(ancestors, pubkey)
     }
 
+    /// Load the account as of `max_root`, ignoring any roots above it
+    pub fn load_with_max_root(
+        &self,
+        ancestors: &Ancestors,
+        pubkey: &Pubkey,
+        max_root: Slot,
+    ) -> Option<(Account, Slot)> {
+        self.do_load(ancestors, pubkey, Some(max_root))
+    }
+
     // Only safe to use the `get_account_accessor_from_cache_or_storage() -> get_loaded_account()`
     // pattern if you're holding the AccountIndex lock for the `pubkey`, otherwise, a cache
     // flush could happen between `get_account_accessor_from_cache_or_storage()` and
@@ -2257,7 +2673,10 @@ impl AccountsDB {
                 min = std::cmp::min(store.accounts.capacity(), min);
                 avail += 1;
 
-                if store.accounts.capacity() >= min_size && store.accounts.capacity() < max_size {
+                if store.accounts.capacity() >= min_size
+                    && store.accounts.capacity() < max_size
+                    && !store.is_ancient()
+                {
                     let ret = recycle_stores.swap_remove(i);
                     drop(recycle_stores);
                     let old_id = ret.append_vec_id();
@@ -3386,7 +3805,7 @@ impl AccountsDB {
     }
 
     // this function avoids an infinite recursion compiler error
-    fn compute_merkle_root_and_capitalization_recurse(
+    pub(crate) fn compute_merkle_root_and_capitalization_recurse(
         hashes: Vec<(Hash, u64)>,
         fanout: usize,
     ) -> (Hash, u64) {
@@ -3532,14 +3951,7 @@ impl AccountsDB {
     ) -> Result<(Hash, u64), BankHashVerificationError> {
         use BankHashVerificationError::*;
         let mut scan = Measure::start("scan");
-        let keys: Vec<_> = self
-            .accounts_index
-            .account_maps
-            .read()
-            .unwrap()
-            .keys()
-            .cloned()
-            .collect();
+        let keys = self.accounts_index.pubkeys();
         let mismatch_found = AtomicU64::new(0);
         let hashes: Vec<(Hash, u64)> = {
             self.thread_pool_clean.install(|| {
@@ -3567,8 +3979,15 @@ impl AccountsDB {
                                     );
 
                                     if check_hash {
+                                        // The stored hashes of packed accounts were computed
+                                        // for the slot they were originally written in
+                                        let original_slot = self
+                                            .storage
+                                            .get_account_storage_entry(*slot, account_info.store_id)
+                                            .map(|store| store.original_slot(account_info.offset))
+                                            .unwrap_or(*slot);
                                         let computed_hash = loaded_account.compute_hash(
-                                            *slot,
+                                            original_slot,
                                             &self.cluster_type.expect(
                                                 "Cluster type must be set at initialization",
                                             ),
@@ -3651,8 +4070,9 @@ impl AccountsDB {
         )
     }
 
-    /// Scan through all the account storage in parallel
-    fn scan_account_storage_no_bank<F, B>(
+    /// Scan through all the account storage in parallel. Every account is passed along with the
+    /// slot it was originally written in, see `AccountStorageEntry::original_slot()`.
+    pub(crate) fn scan_account_storage_no_bank<F, B>(
         snapshot_storages: &[SnapshotStorage],
         scan_func: F,
     ) -> Vec<B>
@@ -3667,74 +4087,14 @@ impl AccountsDB {
                 let accounts = storage.accounts.accounts(0);
                 let mut retval = B::default();
                 accounts.into_iter().for_each(|stored_account| {
-                    scan_func(
-                        LoadedAccount::Stored(stored_account),
-                        &mut retval,
-                        storage.slot(),
-                    )
+                    let slot = storage.original_slot(stored_account.offset);
+                    scan_func(LoadedAccount::Stored(stored_account), &mut retval, slot)
                 });
                 retval
             })
             .collect()
     }
 
-    fn remove_zero_balance_accounts(
-        account_maps: DashMap<Pubkey, CalculateHashIntermediate>,
-    ) -> Vec<(Pubkey, Hash, u64)> {
-        type ShardType = dashmap::lock::RwLock<
-            std::collections::HashMap<
-                solana_sdk::pubkey::Pubkey,
-                dashmap::SharedValue<CalculateHashIntermediate>,
-            >,
-        >;
-        let shards: &[ShardType] = account_maps.shards();
-
-        let hashes: Vec<_> = shards
-            .par_iter()
-            .map(|x| {
-                let a: dashmap::lock::RwLockReadGuard<HashMap<_, _>> = x.read();
-                let res: Vec<_> = a
-                    .iter()
-                    .filter_map(|inp| {
-                        let (pubkey, sv) = inp;
-                        let item = sv.get();
-                        if item.raw_lamports != 0 {
-                            Some((*pubkey, item.hash, item.lamports))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                res
-            })
-            .flatten()
-            .collect();
-        hashes
-    }
-
-    fn rest_of_hash_calculation(
-        accounts: (DashMap<Pubkey, CalculateHashIntermediate>, Measure),
-    ) -> (Hash, u64) {
-        let (account_maps, time_scan) = accounts;
-
-        let mut zeros = Measure::start("eliminate zeros");
-        let hashes = Self::remove_zero_balance_accounts(account_maps);
-        zeros.stop();
-        let hash_total = hashes.len();
-        let (ret, (sort_time, hash_time)) =
-            Self::accumulate_account_hashes_and_capitalization(hashes, Slot::default(), false);
-        datapoint_info!(
-            "calculate_accounts_hash_without_index",
-            ("accounts_scan", time_scan.as_us(), i64),
-            ("eliminate_zeros", zeros.as_us(), i64),
-            ("hash", hash_time.as_us(), i64),
-            ("sort", sort_time.as_us(), i64),
-            ("hash_total", hash_total, i64),
-        );
-
-        ret
-    }
-
     fn calculate_accounts_hash_helper(
         &self,
         do_not_use_index: bool,
@@ -3745,14 +4105,20 @@ impl AccountsDB {
         if do_not_use_index {
             let combined_maps = self.get_snapshot_storages(slot);
 
-            Self::calculate_accounts_hash_without_index(
+            match Self::calculate_accounts_hash_without_index(
                 &combined_maps,
                 simple_capitalization_enabled,
-            )
-        } else {
-            self.calculate_accounts_hash(slot, ancestors, false, simple_capitalization_enabled)
-                .unwrap()
+            ) {
+                Ok(result) => return result,
+                Err(err) => warn!(
+                    "Failed to calculate the accounts hash of slot {} without the index, \
+                     using the index instead: {}",
+                    slot, err
+                ),
+            }
         }
+        self.calculate_accounts_hash(slot, ancestors, false, simple_capitalization_enabled)
+            .unwrap()
     }
 
     pub fn update_accounts_hash_with_index_option(
@@ -3787,72 +4153,21 @@ impl AccountsDB {
         (hash, total_lamports)
     }
 
-    fn handle_one_loaded_account(
-        key: &Pubkey,
-        found_item: CalculateHashIntermediate,
-        map: &DashMap<Pubkey, CalculateHashIntermediate>,
-    ) {
-        match map.entry(*key) {
-            Occupied(mut dest_item) => {
-                let contents = dest_item.get();
-                if contents.slot < found_item.slot
-                    || (contents.slot == found_item.slot
-                        && contents.version() <= found_item.version())
-                {
-                    // replace the item
-                    dest_item.insert(found_item);
-                }
-            }
-            Vacant(v) => {
-                v.insert(found_item);
-            }
-        };
-    }
-
-    fn scan_snapshot_stores(
-        storage: &[SnapshotStorage],
-        simple_capitalization_enabled: bool,
-    ) -> (DashMap<Pubkey, CalculateHashIntermediate>, Measure) {
-        let map: DashMap<Pubkey, CalculateHashIntermediate> = DashMap::new();
-        let mut time = Measure::start("scan all accounts");
-        Self::scan_account_storage_no_bank(
-            &storage,
-            |loaded_account: LoadedAccount,
-             _accum: &mut Vec<(Pubkey, CalculateHashIntermediate)>,
-             slot: Slot| {
-                let version = loaded_account.write_version();
-                let raw_lamports = loaded_account.lamports();
-                let balance = Self::account_balance_for_capitalization(
-                    raw_lamports,
-                    loaded_account.owner(),
-                    loaded_account.executable(),
-                    simple_capitalization_enabled,
-                );
-
-                let source_item = CalculateHashIntermediate::new(
-                    version,
-                    *loaded_account.loaded_hash(),
-                    balance,
-                    raw_lamports,
-                    slot,
-                );
-                Self::handle_one_loaded_account(loaded_account.pubkey(), source_item, &map);
-            },
-        );
-        time.stop();
-
-        (map, time)
-    }
-
-    // modeled after get_accounts_delta_hash
-    // intended to be faster than calculate_accounts_hash
+    /// Calculates the accounts hash and capitalization from the account storages of a rooted
+    /// slot, without the accounts index. See `accounts_hash` for how memory is bounded, and why
+    /// this can fail with an I/O error.
     pub fn calculate_accounts_hash_without_index(
         storages: &[SnapshotStorage],
         simple_capitalization_enabled: bool,
-    ) -> (Hash, u64) {
-        let result = Self::scan_snapshot_stores(storages, simple_capitalization_enabled);
-
-        Self::rest_of_hash_calculation(result)
+    ) -> IOResult<(Hash, u64)> {
+        accounts_hash::calculate_accounts_hash(
+            storages,
+            simple_capitalization_enabled,
+            None,
+            MAX_IN_MEMORY_HASH_ENTRIES,
+        )
+        // No account hashes are checked, so there is no mismatch to report
+        .map(|result| result.unwrap())
     }
 
     pub fn verify_bank_hash_and_lamports(
@@ -3861,11 +4176,51 @@ impl AccountsDB {
         ancestors: &Ancestors,
         total_lamports: u64,
         simple_capitalization_enabled: bool,
+    ) -> Result<(), BankHashVerificationError> {
+        self.verify_bank_hash_and_lamports_with_index_option(
+            false,
+            slot,
+            ancestors,
+            total_lamports,
+            simple_capitalization_enabled,
+        )
+    }
+
+    /// Verifies the accounts hash and capitalization of `slot`, also checking the hash of every
+    /// account. Without the index, `slot` must be a root and all the roots up to it must have
+    /// been flushed to storage.
+    pub fn verify_bank_hash_and_lamports_with_index_option(
+        &self,
+        do_not_use_index: bool,
+        slot: Slot,
+        ancestors: &Ancestors,
+        total_lamports: u64,
+        simple_capitalization_enabled: bool,
     ) -> Result<(), BankHashVerificationError> {
         use BankHashVerificationError::*;
 
-        let (calculated_hash, calculated_lamports) =
-            self.calculate_accounts_hash(slot, ancestors, true, simple_capitalization_enabled)?;
+        let (calculated_hash, calculated_lamports) = if do_not_use_index {
+            accounts_hash::calculate_accounts_hash(
+                &self.get_snapshot_storages(slot),
+                simple_capitalization_enabled,
+                Some(
+                    self.cluster_type
+                        .as_ref()
+                        .expect("Cluster type must be set at initialization"),
+                ),
+                MAX_IN_MEMORY_HASH_ENTRIES,
+            )
+            .unwrap_or_else(|err| {
+                warn!(
+                    "Failed to verify the accounts hash of slot {} without the index, \
+                     using the index instead: {}",
+                    slot, err
+                );
+                self.calculate_accounts_hash(slot, ancestors, true, simple_capitalization_enabled)
+            })?
+        } else {
+            self.calculate_accounts_hash(slot, ancestors, true, simple_capitalization_enabled)?
+        };
 
         if calculated_lamports != total_lamports {
             warn!(
@@ -4020,6 +4375,7 @@ impl AccountsDB {
                 if count == 0 {
                     dead_slots.insert(*slot);
                 } else if self.caching_enabled
+                    && !store.is_ancient()
                     && (self.page_align(store.alive_bytes() as u64) as f64
                         / store.total_bytes() as f64)
                         < SHRINK_RATIO
@@ -4246,6 +4602,7 @@ impl AccountsDB {
             .store_hash_accounts
             .fetch_add(hash_time.as_us(), Ordering::Relaxed);
         self.store_accounts_unfrozen(slot, accounts, &hashes, is_cached_store);
+        self.notify_account_update(slot, accounts);
         self.report_store_timings();
     }
 
@@ -4578,13 +4935,15 @@ impl AccountsDB {
         }
 
         let mut stored_sizes_and_counts = HashMap::new();
-        for account_entry in self.accounts_index.account_maps.read().unwrap().values() {
-            for (_slot, account_entry) in account_entry.slot_list.read().unwrap().iter() {
-                let storage_entry_meta = stored_sizes_and_counts
-                    .entry(account_entry.store_id)
-                    .or_insert((0, 0));
-                storage_entry_meta.0 += account_entry.stored_size;
-                storage_entry_meta.1 += 1;
+        for account_entries in self.accounts_index.iter(None::<Range<Pubkey>>) {
+            for (_pubkey, account_entry) in account_entries {
+                for (_slot, account_entry) in account_entry.slot_list.read().unwrap().iter() {
+                    let storage_entry_meta = stored_sizes_and_counts
+                        .entry(account_entry.store_id)
+                        .or_insert((0, 0));
+                    storage_entry_meta.0 += account_entry.stored_size;
+                    storage_entry_meta.1 += 1;
+                }
             }
         }
         for slot_stores in self.storage.0.iter() {
@@ -4626,12 +4985,14 @@ impl AccountsDB {
         #[allow(clippy::stable_sort_primitive)]
         roots.sort();
         info!("{}: accounts_index roots: {:?}", label, roots,);
-        for (pubkey, account_entry) in self.accounts_index.account_maps.read().unwrap().iter() {
-            info!("  key: {} ref_count: {}", pubkey, account_entry.ref_count(),);
-            info!(
-                "      slots: {:?}",
-                *account_entry.slot_list.read().unwrap()
-            );
+        for account_entries in self.accounts_index.iter(None::<Range<Pubkey>>) {
+            for (pubkey, account_entry) in account_entries {
+                info!("  key: {} ref_count: {}", pubkey, account_entry.ref_count(),);
+                info!(
+                    "      slots: {:?}",
+                    *account_entry.slot_list.read().unwrap()
+                );
+            }
         }
     }
 
@@ -4718,6 +5079,9 @@ impl AccountsDB {
         {
             if let Some(stores_lock) = self.storage.get_slot_stores(slot) {
                 let stores = stores_lock.read().unwrap();
+                if stores.values().any(|store| store.is_ancient()) {
+                    return 0;
+                }
                 let mut alive_count = 0;
                 let mut stored_count = 0;
                 let mut written_bytes = 0;
@@ -4806,7 +5170,7 @@ impl AccountsDB {
                             // It would have had a ref to the storage from the initial store, but it will
                             // not exist in the re-written slot. Unref it to keep the index consistent with
                             // rewriting the storage entries.
-                            locked_entry.unref()
+                            self.accounts_index.unref_from_storage(pubkey);
                         } else {
                             alive_total += *account_size as u64;
                         }
@@ -5080,103 +5444,12 @@ pub mod tests {
         ancestors
     }
 
-    #[test]
-    fn test_accountsdb_rest_of_hash_calculation() {
-        solana_logger::setup();
-
-        let key = Pubkey::new(&[11u8; 32]);
-        let account_maps: DashMap<Pubkey, CalculateHashIntermediate> = DashMap::new();
-        let hash = Hash::new(&[1u8; 32]);
-        let val = CalculateHashIntermediate::new(0, hash, 88, 490, Slot::default());
-        account_maps.insert(key, val);
-
-        // 2nd key - zero lamports, so will be removed
-        let key = Pubkey::new(&[12u8; 32]);
-        let hash = Hash::new(&[2u8; 32]);
-        let val = CalculateHashIntermediate::new(0, hash, 1, 0, Slot::default());
-        account_maps.insert(key, val);
-
-        let result =
-            AccountsDB::rest_of_hash_calculation((account_maps.clone(), Measure::start("")));
-        let expected_hash = Hash::from_str("8j9ARGFv4W2GfML7d3sVJK2MePwrikqYnu6yqer28cCa").unwrap();
-        assert_eq!(result, (expected_hash, 88));
-
-        // 3rd key - with pubkey value before 1st key so it will be sorted first
-        let key = Pubkey::new(&[10u8; 32]);
-        let hash = Hash::new(&[2u8; 32]);
-        let val = CalculateHashIntermediate::new(0, hash, 20, 20, Slot::default());
-        account_maps.insert(key, val);
-
-        let result = AccountsDB::rest_of_hash_calculation((account_maps, Measure::start("")));
-        let expected_hash = Hash::from_str("EHv9C5vX7xQjjMpsJMzudnDTzoTSRwYkqLzY8tVMihGj").unwrap();
-        assert_eq!(result, (expected_hash, 108));
-    }
-
-    #[test]
-    fn test_accountsdb_handle_one_loaded_account() {
-        solana_logger::setup();
-
-        let account_maps: DashMap<Pubkey, CalculateHashIntermediate> = DashMap::new();
-        let key = Pubkey::new_unique();
-        let hash = Hash::new_unique();
-        let val = CalculateHashIntermediate::new(1, hash, 1, 2, 1);
-
-        AccountsDB::handle_one_loaded_account(&key, val.clone(), &account_maps);
-        assert_eq!(*account_maps.get(&key).unwrap(), val);
-
-        // slot same, version <
-        let hash2 = Hash::new_unique();
-        let val2 = CalculateHashIntermediate::new(0, hash2, 4, 5, 1);
-        AccountsDB::handle_one_loaded_account(&key, val2, &account_maps);
-        assert_eq!(*account_maps.get(&key).unwrap(), val);
-
-        // slot same, vers =
-        let hash3 = Hash::new_unique();
-        let val3 = CalculateHashIntermediate::new(1, hash3, 2, 3, 1);
-        AccountsDB::handle_one_loaded_account(&key, val3.clone(), &account_maps);
-        assert_eq!(*account_maps.get(&key).unwrap(), val3);
-
-        // slot same, vers >
-        let hash4 = Hash::new_unique();
-        let val4 = CalculateHashIntermediate::new(2, hash4, 6, 7, 1);
-        AccountsDB::handle_one_loaded_account(&key, val4.clone(), &account_maps);
-        assert_eq!(*account_maps.get(&key).unwrap(), val4);
-
-        // slot >, version <
-        let hash5 = Hash::new_unique();
-        let val5 = CalculateHashIntermediate::new(0, hash5, 8, 9, 2);
-        AccountsDB::handle_one_loaded_account(&key, val5.clone(), &account_maps);
-        assert_eq!(*account_maps.get(&key).unwrap(), val5);
-    }
-
-    #[test]
-    fn test_accountsdb_remove_zero_balance_accounts() {
-        solana_logger::setup();
-
-        let key = Pubkey::new_unique();
-        let hash = Hash::new_unique();
-        let account_maps: DashMap<Pubkey, CalculateHashIntermediate> = DashMap::new();
-        let val = CalculateHashIntermediate::new(0, hash, 1, 2, Slot::default());
-        account_maps.insert(key, val.clone());
-
-        let result = AccountsDB::remove_zero_balance_accounts(account_maps);
-        assert_eq!(result, vec![(key, val.hash, val.lamports)]);
-
-        // zero original lamports
-        let account_maps: DashMap<Pubkey, CalculateHashIntermediate> = DashMap::new();
-        let val = CalculateHashIntermediate::new(0, hash, 1, 0, Slot::default());
-        account_maps.insert(key, val);
-
-        let result = AccountsDB::remove_zero_balance_accounts(account_maps);
-        assert_eq!(result, vec![]);
-    }
-
     #[test]
     fn test_accountsdb_calculate_accounts_hash_without_index_simple() {
         solana_logger::setup();
 
         let (storages, _size, _slot_expected) = sample_storage();
-        let result = AccountsDB::calculate_accounts_hash_without_index(&storages, true);
+        let result = AccountsDB::calculate_accounts_hash_without_index(&storages, true).unwrap();
         let expected_hash = Hash::from_str("GKot5hBsd81kMupNCXHaqbhv3huEbxAFMLnpcX2hniwn").unwrap();
         assert_eq!(result, (expected_hash, 0));
     }
@@ -6033,6 +6306,40 @@ pub mod tests {
         assert!(accounts.accounts_index.get(&pubkey, None, None).is_none());
     }
 
+    #[test]
+    fn test_clean_zero_lamport_after_last_full_snapshot() {
+        solana_logger::setup();
+
+        let accounts = AccountsDB::new(Vec::new(), &ClusterType::Development);
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let account = Account::new(1, 0, &Account::default().owner);
+        let zero_lamport_account = Account::new(0, 0, &Account::default().owner);
+
+        accounts.store_uncached(0, &[(&pubkey, &account)]);
+        accounts.store_uncached(1, &[(&pubkey, &zero_lamport_account)]);
+        accounts.add_root(0);
+        accounts.add_root(1);
+
+        // The zero-lamport update happened after the full snapshot, so it must be kept
+        // for the incremental snapshots on top of it
+        accounts.set_last_full_snapshot_slot(Some(0));
+        accounts.clean_accounts(None);
+
+        // Slot 0 is still cleaned because its only account was updated in slot 1
+        assert_eq!(accounts.alive_account_count_in_slot(0), 0);
+        assert_eq!(accounts.alive_account_count_in_slot(1), 1);
+        let (locked_entry, index) = accounts.accounts_index.get(&pubkey, None, None).unwrap();
+        assert_eq!(locked_entry.slot_list()[index].0, 1);
+        drop(locked_entry);
+
+        // Once a full snapshot includes the zero-lamport update, it can be purged
+        accounts.set_last_full_snapshot_slot(Some(1));
+        accounts.clean_accounts(None);
+
+        assert_eq!(accounts.alive_account_count_in_slot(1), 0);
+        assert!(accounts.accounts_index.get(&pubkey, None, None).is_none());
+    }
+
     #[test]
     fn test_clean_old_with_normal_account() {
         solana_logger::setup();
@@ -6104,6 +6411,7 @@ pub mod tests {
             &ClusterType::Development,
             spl_token_mint_index_enabled(),
             false,
+            None,
         );
         let pubkey1 = solana_sdk::pubkey::new_rand();
         let pubkey2 = solana_sdk::pubkey::new_rand();
@@ -7041,6 +7349,104 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_verify_bank_hash_without_index() {
+        use BankHashVerificationError::*;
+        solana_logger::setup();
+        let db = AccountsDB::new(Vec::new(), &ClusterType::Development);
+
+        let key = solana_sdk::pubkey::new_rand();
+        let some_slot: Slot = 0;
+        let account = Account::new(1, 0, &key);
+        let ancestors = vec![(some_slot, 0)].into_iter().collect();
+
+        db.store_uncached(some_slot, &[(&key, &account)]);
+        db.add_root(some_slot);
+        db.update_accounts_hash_test(some_slot, &ancestors, true);
+        assert_matches!(
+            db.verify_bank_hash_and_lamports_with_index_option(
+                true, some_slot, &ancestors, 1, true
+            ),
+            Ok(_)
+        );
+        assert_matches!(
+            db.verify_bank_hash_and_lamports_with_index_option(
+                true, some_slot, &ancestors, 10, true
+            ),
+            Err(MismatchedTotalLamports(1, 10))
+        );
+
+        let bank_hash_info = BankHashInfo {
+            hash: Hash::new(&[0xca; HASH_BYTES]),
+            snapshot_hash: Hash::new(&[0xca; HASH_BYTES]),
+            stats: BankHashStats::default(),
+        };
+        db.bank_hashes
+            .write()
+            .unwrap()
+            .insert(some_slot, bank_hash_info);
+        assert_matches!(
+            db.verify_bank_hash_and_lamports_with_index_option(
+                true, some_slot, &ancestors, 1, true
+            ),
+            Err(MismatchedBankHash)
+        );
+    }
+
+    #[test]
+    fn test_calculate_accounts_hash_without_index_matches_index() {
+        solana_logger::setup();
+        let db = AccountsDB::new(Vec::new(), &ClusterType::Development);
+
+        let keys: Vec<_> = (0..100).map(|_| solana_sdk::pubkey::new_rand()).collect();
+        let mut ancestors = Ancestors::default();
+        let num_slots = 4;
+        for slot in 0..num_slots {
+            // each slot overwrites a different subset of the keys, zeroing out some of them
+            let accounts: Vec<_> = keys
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i as u64 % (slot + 1) == 0)
+                .map(|(i, key)| {
+                    let lamports = if i % 7 == 0 && slot > 0 {
+                        0
+                    } else {
+                        i as u64 + slot + 1
+                    };
+                    (key, Account::new(lamports, i % 3, key))
+                })
+                .collect();
+            let accounts: Vec<_> = accounts
+                .iter()
+                .map(|(key, account)| (*key, account))
+                .collect();
+            db.store_uncached(slot, &accounts);
+            db.add_root(slot);
+            ancestors.insert(slot, 0);
+        }
+        let slot = num_slots - 1;
+        let expected = db
+            .calculate_accounts_hash(slot, &ancestors, true, true)
+            .unwrap();
+
+        let storages = db.get_snapshot_storages(slot);
+        for max_in_memory_entries in &[MAX_IN_MEMORY_HASH_ENTRIES, 30, 1] {
+            let result = accounts_hash::calculate_accounts_hash(
+                &storages,
+                true,
+                Some(&ClusterType::Development),
+                *max_in_memory_entries,
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(result, expected, "max: {}", max_in_memory_entries);
+        }
+        assert_eq!(
+            AccountsDB::calculate_accounts_hash_without_index(&storages, true).unwrap(),
+            expected
+        );
+    }
+
     #[test]
     fn test_verify_bank_capitalization() {
         use BankHashVerificationError::*;
@@ -7554,6 +7960,216 @@ pub mod tests {
         accounts.clean_stored_dead_slots(&dead_slots, None);
     }
 
+    fn assert_storage_ref_counts(db: &AccountsDB, pubkeys: &[Pubkey]) {
+        for pubkey in pubkeys {
+            let num_slots_storing = db
+                .storage
+                .0
+                .iter()
+                .filter(|slot_stores| {
+                    slot_stores.value().read().unwrap().values().any(|store| {
+                        store
+                            .all_accounts()
+                            .iter()
+                            .any(|account| account.meta.pubkey == *pubkey)
+                    })
+                })
+                .count();
+            assert_eq!(
+                db.accounts_index.ref_count_from_storage(pubkey),
+                num_slots_storing as RefCount
+            );
+        }
+    }
+
+    #[test]
+    fn test_pack_ancient_slots() {
+        solana_logger::setup();
+        let db = AccountsDB::new(Vec::new(), &ClusterType::Development);
+        let owner = Account::default().owner;
+        let pubkeys: Vec<_> = (0..20).map(|_| solana_sdk::pubkey::new_rand()).collect();
+
+        let mut ancestors = Ancestors::default();
+        let num_slots = 10;
+        for slot in 0..num_slots {
+            // every slot overwrites some of the accounts of the previous slots, zeroing some out
+            let accounts: Vec<_> = (slot..slot + 3)
+                .map(|i| {
+                    let lamports = if i == slot + 2 && slot % 3 == 0 {
+                        0
+                    } else {
+                        slot + i + 1
+                    };
+                    (
+                        pubkeys[i as usize],
+                        Account::new(lamports, i as usize % 4, &owner),
+                    )
+                })
+                .collect();
+            let accounts: Vec<_> = accounts
+                .iter()
+                .map(|(pubkey, account)| (pubkey, account))
+                .collect();
+            db.store_uncached(slot, &accounts);
+            db.get_accounts_delta_hash(slot);
+            db.add_root(slot);
+            ancestors.insert(slot, 0);
+        }
+        let last_slot = num_slots - 1;
+        let load_all = |db: &AccountsDB| -> Vec<_> {
+            pubkeys
+                .iter()
+                .map(|pubkey| db.load_slow(&ancestors, pubkey).map(|(account, _)| account))
+                .collect()
+        };
+        let expected_accounts = load_all(&db);
+        let expected_hash = db
+            .calculate_accounts_hash(last_slot, &ancestors, true, true)
+            .unwrap();
+        assert_storage_ref_counts(&db, &pubkeys);
+
+        // nothing is packed before there is a full snapshot
+        let oldest_non_ancient_slot = last_slot - 2;
+        let num_stores_before = db.num_stores();
+        db.pack_ancient_slots(oldest_non_ancient_slot);
+        assert_eq!(db.num_stores(), num_stores_before);
+
+        // pack the slots older than the last 3, up to the full snapshot slot
+        let full_snapshot_slot = oldest_non_ancient_slot - 3;
+        db.set_last_full_snapshot_slot(Some(full_snapshot_slot));
+        let num_packed_stores: usize = (0..=full_snapshot_slot)
+            .map(|slot| db.storage.get_slot_storage_entries(slot).unwrap().len())
+            .sum();
+        db.pack_ancient_slots(oldest_non_ancient_slot);
+
+        let ancient = db.storage.get_slot_storage_entries(0).unwrap();
+        assert_eq!(ancient.len(), 1);
+        assert!(ancient[0].is_ancient());
+        // every packed slot still has an alive account
+        let original_slots: Vec<Slot> = ancient[0]
+            .original_slots()
+            .unwrap()
+            .iter()
+            .map(|(_, slot)| *slot)
+            .collect();
+        assert_eq!(original_slots, (0..=full_snapshot_slot).collect::<Vec<_>>());
+        for slot in 1..=full_snapshot_slot {
+            assert_no_stores(&db, slot);
+            assert!(!db.accounts_index.is_root(slot));
+        }
+        for slot in full_snapshot_slot + 1..num_slots {
+            assert!(!db.storage.get_slot_storage_entries(slot).unwrap()[0].is_ancient());
+        }
+        assert_eq!(db.num_stores(), num_stores_before - num_packed_stores + 1);
+        assert_eq!(load_all(&db), expected_accounts);
+        assert_storage_ref_counts(&db, &pubkeys);
+        assert_eq!(
+            db.calculate_accounts_hash(last_slot, &ancestors, true, true)
+                .unwrap(),
+            expected_hash
+        );
+        assert_eq!(
+            accounts_hash::calculate_accounts_hash(
+                &db.get_snapshot_storages(last_slot),
+                true,
+                Some(&ClusterType::Development),
+                MAX_IN_MEMORY_HASH_ENTRIES,
+            )
+            .unwrap()
+            .unwrap(),
+            expected_hash
+        );
+
+        // the ancient storage is still recognized after a snapshot round trip
+        let daccounts = reconstruct_accounts_db_via_serialization(&db, last_slot);
+        let dancient = daccounts.storage.get_slot_storage_entries(0).unwrap();
+        assert!(dancient[0].is_ancient());
+        assert_eq!(dancient[0].original_slots(), ancient[0].original_slots());
+        assert_eq!(load_all(&daccounts), expected_accounts);
+        assert_eq!(
+            daccounts
+                .calculate_accounts_hash(last_slot, &ancestors, true, true)
+                .unwrap(),
+            expected_hash
+        );
+
+        // packing again doesn't append to the ancient storage, which may be part of the full
+        // snapshot: being sparse, it is repacked into a new one along with the remaining slots.
+        // Clean still works on the packed accounts.
+        let ancient_len = ancient[0].accounts.len();
+        db.set_last_full_snapshot_slot(Some(last_slot));
+        db.pack_ancient_slots(num_slots);
+        assert_eq!(db.storage.all_slots(), vec![0]);
+        assert_eq!(db.num_stores(), 1);
+        assert_eq!(ancient[0].accounts.len(), ancient_len);
+        let repacked = db.storage.get_slot_storage_entries(0).unwrap();
+        assert_ne!(repacked[0].append_vec_id(), ancient[0].append_vec_id());
+        let original_slots: Vec<Slot> = repacked[0]
+            .original_slots()
+            .unwrap()
+            .iter()
+            .map(|(_, slot)| *slot)
+            .collect();
+        assert_eq!(original_slots, (0..num_slots).collect::<Vec<_>>());
+        assert_eq!(load_all(&db), expected_accounts);
+        assert_storage_ref_counts(&db, &pubkeys);
+        db.clean_accounts(None);
+        assert_eq!(load_all(&db), expected_accounts);
+        assert_eq!(
+            accounts_hash::calculate_accounts_hash(
+                &db.get_snapshot_storages(last_slot),
+                true,
+                Some(&ClusterType::Development),
+                MAX_IN_MEMORY_HASH_ENTRIES,
+            )
+            .unwrap()
+            .unwrap(),
+            expected_hash
+        );
+    }
+
+    #[test]
+    fn test_shrink_skips_ancient_slots() {
+        solana_logger::setup();
+        let caching_enabled = true;
+        let db = AccountsDB::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            HashSet::new(),
+            caching_enabled,
+            None,
+        );
+        let owner = Account::default().owner;
+        let pubkeys: Vec<_> = (0..10).map(|_| solana_sdk::pubkey::new_rand()).collect();
+        let account = Account::new(1, 0, &owner);
+        for pubkey in &pubkeys {
+            db.store_uncached(0, &[(pubkey, &account)]);
+        }
+        db.add_root(0);
+        db.set_last_full_snapshot_slot(Some(0));
+        db.pack_ancient_slots(1);
+        let store_id = db.storage.get_slot_storage_entries(0).unwrap()[0].append_vec_id();
+
+        // make most of the ancient storage dead
+        for pubkey in &pubkeys[1..] {
+            db.store_cached(1, &[(pubkey, &account)]);
+        }
+        db.get_accounts_delta_hash(1);
+        db.add_root(1);
+        db.flush_accounts_cache(true, None);
+        db.clean_accounts(None);
+        assert_eq!(
+            db.storage.get_slot_storage_entries(0).unwrap()[0].count(),
+            1
+        );
+        assert!(db.shrink_candidate_slots.lock().unwrap().is_empty());
+        db.shrink_all_slots();
+        let ancient = db.storage.get_slot_storage_entries(0).unwrap();
+        assert_eq!(ancient.len(), 1);
+        assert_eq!(ancient[0].append_vec_id(), store_id);
+        assert!(ancient[0].is_ancient());
+    }
+
     #[test]
     fn test_shrink_all_slots_none() {
         let accounts = AccountsDB::new_single();
@@ -8351,6 +8967,7 @@ pub mod tests {
             &ClusterType::Development,
             HashSet::new(),
             caching_enabled,
+            None,
         ));
 
         let account_key = Pubkey::new_unique();
@@ -8439,6 +9056,7 @@ pub mod tests {
             &ClusterType::Development,
             HashSet::new(),
             caching_enabled,
+            None,
         ));
         let account_key = Pubkey::new_unique();
         let account_key2 = Pubkey::new_unique();
@@ -8522,6 +9140,7 @@ pub mod tests {
             &ClusterType::Development,
             HashSet::new(),
             caching_enabled,
+            None,
         );
         let slot: Slot = 0;
         let num_keys = 10;
@@ -8576,6 +9195,7 @@ pub mod tests {
             &ClusterType::Development,
             HashSet::new(),
             caching_enabled,
+            None,
         ));
         let slots: Vec<_> = (0..num_slots as Slot).into_iter().collect();
         let stall_slot = num_slots as Slot;
@@ -8958,6 +9578,7 @@ pub mod tests {
             &ClusterType::Development,
             HashSet::default(),
             caching_enabled,
+            None,
         );
         let account_key1 = Pubkey::new_unique();
         let account_key2 = Pubkey::new_unique();
@@ -0,0 +1,501 @@
+//! Calculates the accounts hash and capitalization of a rooted slot from its account storages,
+//! without the accounts index, so that no index locks are held while hashing.
+//!
+//! Every stored account is put into one of `PUBKEY_BINS_FOR_CALCULATING_HASHES` bins by the
+//! leading byte of its pubkey, so the bins cover increasing pubkey ranges. The storages are
+//! scanned in batches of roughly `max_in_memory_entries` accounts. If everything fits into a
+//! single batch, the bins stay in memory. Otherwise each batch's bins are sorted, reduced to the
+//! latest version of every account and appended to one file per bin, and the bins are read back
+//! one at a time once all the storages have been scanned. In both cases the hashes are then fed
+//! in pubkey order into the first level of the merkle tree, which is all that is kept in memory
+//! until the root is computed.
+
+use crate::accounts_db::{
+    AccountsDB, BankHashVerificationError, LoadedAccount, SnapshotStorage, MERKLE_FANOUT,
+};
+use log::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use solana_measure::measure::Measure;
+use solana_sdk::{
+    clock::Slot,
+    genesis_config::ClusterType,
+    hash::{Hash, Hasher},
+    pubkey::Pubkey,
+};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Number of bins the scanned accounts are split into, by the leading byte of their pubkey
+pub const PUBKEY_BINS_FOR_CALCULATING_HASHES: usize = 256;
+
+/// Approximate number of scanned accounts held in memory before they are written to disk
+pub const MAX_IN_MEMORY_HASH_ENTRIES: usize = 4_000_000;
+
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub(crate) struct CalculateHashIntermediate {
+    pub version: u64,
+    pub hash: Hash,
+    pub lamports: u64,
+    pub raw_lamports: u64,
+    pub slot: Slot,
+    pub pubkey: Pubkey,
+}
+
+impl CalculateHashIntermediate {
+    pub fn new(
+        version: u64,
+        hash: Hash,
+        lamports: u64,
+        raw_lamports: u64,
+        slot: Slot,
+        pubkey: Pubkey,
+    ) -> Self {
+        Self {
+            version,
+            hash,
+            lamports,
+            raw_lamports,
+            slot,
+            pubkey,
+        }
+    }
+}
+
+type Bins = Vec<Vec<CalculateHashIntermediate>>;
+
+#[derive(Debug, Default)]
+struct HashStats {
+    scan_us: u64,
+    spill_us: u64,
+    hash_us: u64,
+    num_batches: usize,
+    num_scanned: usize,
+    hash_total: usize,
+}
+
+// Builds the merkle tree's first level from leaves fed to it in pubkey order, so the leaves
+// themselves never have to be held in memory all at once
+struct MerkleFirstLevel {
+    hasher: Hasher,
+    lamports: u128,
+    num_in_chunk: usize,
+    chunks: Vec<(Hash, u64)>,
+}
+
+impl MerkleFirstLevel {
+    fn new() -> Self {
+        Self {
+            hasher: Hasher::default(),
+            lamports: 0,
+            num_in_chunk: 0,
+            chunks: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, hash: &Hash, lamports: u64) {
+        self.hasher.hash(hash.as_ref());
+        self.lamports += lamports as u128;
+        self.num_in_chunk += 1;
+        if self.num_in_chunk == MERKLE_FANOUT {
+            self.finish_chunk();
+        }
+    }
+
+    fn finish_chunk(&mut self) {
+        let hasher = std::mem::take(&mut self.hasher);
+        self.chunks.push((
+            hasher.result(),
+            AccountsDB::checked_cast_for_capitalization(self.lamports),
+        ));
+        self.lamports = 0;
+        self.num_in_chunk = 0;
+    }
+
+    fn root(mut self) -> (Hash, u64) {
+        if self.num_in_chunk > 0 {
+            self.finish_chunk();
+        }
+        match self.chunks.len() {
+            0 => (Hasher::default().result(), 0),
+            1 => self.chunks[0],
+            _ => AccountsDB::compute_merkle_root_and_capitalization_recurse(
+                self.chunks,
+                MERKLE_FANOUT,
+            ),
+        }
+    }
+}
+
+// The files holding the sorted runs of every bin, once the scanned accounts don't fit in memory
+struct SpilledBins {
+    _dir: tempfile::TempDir,
+    paths: Vec<PathBuf>,
+    lens: Vec<usize>,
+}
+
+impl SpilledBins {
+    fn new(dir: &Path) -> io::Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("accounts_hash.")
+            .tempdir_in(dir)?;
+        let paths = (0..PUBKEY_BINS_FOR_CALCULATING_HASHES)
+            .map(|bin| dir.path().join(format!("bin.{}", bin)))
+            .collect();
+        Ok(Self {
+            _dir: dir,
+            paths,
+            lens: vec![0; PUBKEY_BINS_FOR_CALCULATING_HASHES],
+        })
+    }
+
+    fn append(&mut self, bins: Bins) -> io::Result<()> {
+        let lens = bins
+            .into_par_iter()
+            .zip(self.paths.par_iter())
+            .map(|(bin, path)| {
+                if !bin.is_empty() {
+                    let file = OpenOptions::new().create(true).append(true).open(path)?;
+                    let mut writer = BufWriter::new(file);
+                    for item in &bin {
+                        bincode::serialize_into(&mut writer, item).map_err(into_io_error)?;
+                    }
+                    writer.flush()?;
+                }
+                Ok(bin.len())
+            })
+            .collect::<io::Result<Vec<usize>>>()?;
+        for (total, len) in self.lens.iter_mut().zip(lens) {
+            *total += len;
+        }
+        Ok(())
+    }
+
+    fn read(&self, bin: usize) -> io::Result<Vec<CalculateHashIntermediate>> {
+        if self.lens[bin] == 0 {
+            return Ok(vec![]);
+        }
+        let file = File::open(&self.paths[bin])?;
+        let mut reader = BufReader::new(file);
+        (0..self.lens[bin])
+            .map(|_| bincode::deserialize_from(&mut reader).map_err(into_io_error))
+            .collect()
+    }
+}
+
+fn into_io_error(err: bincode::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err)
+}
+
+fn bin_of(pubkey: &Pubkey) -> usize {
+    pubkey.as_ref()[0] as usize * PUBKEY_BINS_FOR_CALCULATING_HASHES / 256
+}
+
+fn empty_bins() -> Bins {
+    vec![vec![]; PUBKEY_BINS_FOR_CALCULATING_HASHES]
+}
+
+// Sorts `bin` by pubkey and keeps only the latest version of every account
+fn dedup_bin(bin: &mut Vec<CalculateHashIntermediate>) {
+    bin.par_sort_unstable_by(|a, b| {
+        a.pubkey
+            .cmp(&b.pubkey)
+            .then(b.slot.cmp(&a.slot))
+            .then(b.version.cmp(&a.version))
+    });
+    bin.dedup_by_key(|item| item.pubkey);
+}
+
+// Splits the storages into consecutive batches of roughly `max_in_memory_entries` accounts
+fn batch_storages(
+    storages: &[SnapshotStorage],
+    max_in_memory_entries: usize,
+) -> Vec<&[SnapshotStorage]> {
+    let mut batches = vec![];
+    let mut batch_start = 0;
+    let mut batch_entries = 0;
+    for (i, storage) in storages.iter().enumerate() {
+        let num_entries: usize = storage
+            .iter()
+            .map(|entry| entry.count().max(entry.approx_stored_count()))
+            .sum();
+        if i > batch_start && batch_entries + num_entries > max_in_memory_entries {
+            batches.push(&storages[batch_start..i]);
+            batch_start = i;
+            batch_entries = 0;
+        }
+        batch_entries += num_entries;
+    }
+    if batch_start < storages.len() {
+        batches.push(&storages[batch_start..]);
+    }
+    batches
+}
+
+fn scan_storages(
+    storages: &[SnapshotStorage],
+    simple_capitalization_enabled: bool,
+    check_hash: Option<&ClusterType>,
+    mismatch_found: &AtomicU64,
+) -> Bins {
+    let scanned = AccountsDB::scan_account_storage_no_bank(
+        storages,
+        |loaded_account: LoadedAccount, bins: &mut Bins, slot: Slot| {
+            if bins.is_empty() {
+                *bins = empty_bins();
+            }
+            let pubkey = *loaded_account.pubkey();
+            if let Some(cluster_type) = check_hash {
+                let computed_hash = loaded_account.compute_hash(slot, cluster_type, &pubkey);
+                if computed_hash != *loaded_account.loaded_hash() {
+                    mismatch_found.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            let raw_lamports = loaded_account.lamports();
+            let balance = AccountsDB::account_balance_for_capitalization(
+                raw_lamports,
+                loaded_account.owner(),
+                loaded_account.executable(),
+                simple_capitalization_enabled,
+            );
+            bins[bin_of(&pubkey)].push(CalculateHashIntermediate::new(
+                loaded_account.write_version(),
+                *loaded_account.loaded_hash(),
+                balance,
+                raw_lamports,
+                slot,
+                pubkey,
+            ));
+        },
+    );
+    let mut bins = empty_bins();
+    for storage_bins in scanned {
+        for (bin, storage_bin) in bins.iter_mut().zip(storage_bins) {
+            bin.extend(storage_bin);
+        }
+    }
+    bins
+}
+
+// Feeds the accounts of deduplicated bins to `merkle` in order, skipping the ones with zero
+// lamports
+fn add_to_merkle<'a>(
+    items: impl Iterator<Item = &'a CalculateHashIntermediate>,
+    merkle: &mut MerkleFirstLevel,
+    hash_total: &mut usize,
+) {
+    for item in items {
+        if item.raw_lamports != 0 {
+            merkle.add(&item.hash, item.lamports);
+            *hash_total += 1;
+        }
+    }
+}
+
+/// Calculates the accounts hash and capitalization from the latest version of every account in
+/// `storages`, skipping the accounts with zero lamports. If `check_hash` is given, the hash of
+/// every stored account is also recomputed for that cluster type and compared with the stored
+/// one. Fails with an I/O error if the scanned accounts had to be spilled to disk and that
+/// failed.
+pub(crate) fn calculate_accounts_hash(
+    storages: &[SnapshotStorage],
+    simple_capitalization_enabled: bool,
+    check_hash: Option<&ClusterType>,
+    max_in_memory_entries: usize,
+) -> io::Result<Result<(Hash, u64), BankHashVerificationError>> {
+    let mut stats = HashStats::default();
+    let mismatch_found = AtomicU64::new(0);
+    let batches = batch_storages(storages, max_in_memory_entries);
+    stats.num_batches = batches.len();
+
+    let mut merkle = MerkleFirstLevel::new();
+    if batches.len() <= 1 {
+        let mut scan = Measure::start("scan");
+        let mut bins = batches.first().map_or_else(empty_bins, |batch| {
+            scan_storages(
+                batch,
+                simple_capitalization_enabled,
+                check_hash,
+                &mismatch_found,
+            )
+        });
+        scan.stop();
+        stats.scan_us = scan.as_us();
+        stats.num_scanned = bins.iter().map(|bin| bin.len()).sum();
+
+        let mut hash_time = Measure::start("hash");
+        bins.par_iter_mut().for_each(dedup_bin);
+        add_to_merkle(bins.iter().flatten(), &mut merkle, &mut stats.hash_total);
+        hash_time.stop();
+        stats.hash_us = hash_time.as_us();
+    } else {
+        // Spill next to the account storages, which is where the disk space for accounts is
+        let spill_dir = storages
+            .iter()
+            .flatten()
+            .next()
+            .and_then(|entry| entry.get_path().parent().map(Path::to_path_buf))
+            .unwrap_or_else(std::env::temp_dir);
+        let mut spilled_bins = SpilledBins::new(&spill_dir)?;
+        for batch in &batches {
+            let mut scan = Measure::start("scan");
+            let mut bins = scan_storages(
+                batch,
+                simple_capitalization_enabled,
+                check_hash,
+                &mismatch_found,
+            );
+            scan.stop();
+            stats.scan_us += scan.as_us();
+            stats.num_scanned += bins.iter().map(|bin| bin.len()).sum::<usize>();
+
+            let mut spill = Measure::start("spill");
+            bins.par_iter_mut().for_each(dedup_bin);
+            spilled_bins.append(bins)?;
+            spill.stop();
+            stats.spill_us += spill.as_us();
+        }
+
+        // Only as many bins as can be reduced in parallel are read back at once
+        let mut hash_time = Measure::start("hash");
+        let chunk_size = rayon::current_num_threads().max(1);
+        for start in (0..PUBKEY_BINS_FOR_CALCULATING_HASHES).step_by(chunk_size) {
+            let end = (start + chunk_size).min(PUBKEY_BINS_FOR_CALCULATING_HASHES);
+            let bins = (start..end)
+                .into_par_iter()
+                .map(|bin| {
+                    let mut bin = spilled_bins.read(bin)?;
+                    dedup_bin(&mut bin);
+                    Ok(bin)
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            add_to_merkle(bins.iter().flatten(), &mut merkle, &mut stats.hash_total);
+        }
+        hash_time.stop();
+        stats.hash_us = hash_time.as_us();
+    }
+
+    let mismatch_found = mismatch_found.load(Ordering::Relaxed);
+    if mismatch_found > 0 {
+        warn!("{} mismatched account hash(es) found", mismatch_found);
+        return Ok(Err(BankHashVerificationError::MismatchedAccountHash));
+    }
+
+    let result = merkle.root();
+    datapoint_info!(
+        "calculate_accounts_hash_without_index",
+        ("accounts_scan", stats.scan_us, i64),
+        ("spill", stats.spill_us, i64),
+        ("hash", stats.hash_us, i64),
+        ("num_batches", stats.num_batches, i64),
+        ("num_scanned", stats.num_scanned, i64),
+        ("hash_total", stats.hash_total, i64),
+    );
+    Ok(Ok(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn hash_of(items: Vec<CalculateHashIntermediate>) -> (Hash, u64) {
+        let mut bins = empty_bins();
+        for item in items {
+            bins[bin_of(&item.pubkey)].push(item);
+        }
+        bins.iter_mut().for_each(dedup_bin);
+        let mut merkle = MerkleFirstLevel::new();
+        add_to_merkle(bins.iter().flatten(), &mut merkle, &mut 0);
+        merkle.root()
+    }
+
+    #[test]
+    fn test_accounts_hash_of_bins() {
+        solana_logger::setup();
+
+        let key = Pubkey::new(&[11u8; 32]);
+        let hash = Hash::new(&[1u8; 32]);
+        let mut items = vec![CalculateHashIntermediate::new(0, hash, 88, 490, 0, key)];
+
+        // 2nd key - zero lamports, so will be removed
+        let key = Pubkey::new(&[12u8; 32]);
+        let hash = Hash::new(&[2u8; 32]);
+        items.push(CalculateHashIntermediate::new(0, hash, 1, 0, 0, key));
+
+        let expected_hash = Hash::from_str("8j9ARGFv4W2GfML7d3sVJK2MePwrikqYnu6yqer28cCa").unwrap();
+        assert_eq!(hash_of(items.clone()), (expected_hash, 88));
+
+        // 3rd key - with pubkey value before 1st key so it will be sorted first
+        let key = Pubkey::new(&[10u8; 32]);
+        let hash = Hash::new(&[2u8; 32]);
+        items.push(CalculateHashIntermediate::new(0, hash, 20, 20, 0, key));
+
+        let expected_hash = Hash::from_str("EHv9C5vX7xQjjMpsJMzudnDTzoTSRwYkqLzY8tVMihGj").unwrap();
+        assert_eq!(hash_of(items), (expected_hash, 108));
+    }
+
+    #[test]
+    fn test_accounts_hash_dedup_bin() {
+        let key = Pubkey::new_unique();
+        let other_key = Pubkey::new_unique();
+        let item = |version, slot| {
+            CalculateHashIntermediate::new(version, Hash::new_unique(), 1, 1, slot, key)
+        };
+        let older = item(5, 1);
+        let same_slot_newer = item(6, 1);
+        let newer_slot = item(0, 2);
+        let other = CalculateHashIntermediate::new(0, Hash::new_unique(), 1, 1, 0, other_key);
+
+        let mut bin = vec![same_slot_newer.clone(), other.clone(), older.clone()];
+        dedup_bin(&mut bin);
+        let mut expected = vec![same_slot_newer, other.clone()];
+        expected.sort_by_key(|item| item.pubkey);
+        assert_eq!(bin, expected);
+
+        let mut bin = vec![newer_slot.clone(), older, other.clone()];
+        dedup_bin(&mut bin);
+        let mut expected = vec![newer_slot, other];
+        expected.sort_by_key(|item| item.pubkey);
+        assert_eq!(bin, expected);
+    }
+
+    #[test]
+    fn test_accounts_hash_merkle_first_level() {
+        for num_leaves in &[0, 1, MERKLE_FANOUT - 1, MERKLE_FANOUT, 300] {
+            let leaves: Vec<_> = (0..*num_leaves)
+                .map(|i| (Pubkey::new_unique(), Hash::new_unique(), i as u64))
+                .collect();
+            let mut merkle = MerkleFirstLevel::new();
+            for (_, hash, lamports) in &leaves {
+                merkle.add(hash, *lamports);
+            }
+            assert_eq!(
+                merkle.root(),
+                AccountsDB::compute_merkle_root_and_capitalization(leaves, MERKLE_FANOUT)
+            );
+        }
+    }
+
+    #[test]
+    fn test_accounts_hash_spilled_bins_io_error() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(SpilledBins::new(&dir.path().join("missing")).is_err());
+
+        let mut spilled_bins = SpilledBins::new(dir.path()).unwrap();
+        let item =
+            CalculateHashIntermediate::new(0, Hash::new_unique(), 1, 1, 0, Pubkey::default());
+        let mut bins = empty_bins();
+        bins[bin_of(&item.pubkey)].push(item.clone());
+        spilled_bins.append(bins).unwrap();
+        assert_eq!(spilled_bins.read(bin_of(&item.pubkey)).unwrap(), vec![item]);
+
+        // A spill file that lost its contents must fail the read rather than the validator
+        File::create(&spilled_bins.paths[0]).unwrap();
+        assert!(spilled_bins.read(0).is_err());
+    }
+}
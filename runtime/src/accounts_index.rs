@@ -1,10 +1,12 @@
 use crate::{
+    bucket_map::BucketMap,
     contains::Contains,
     inline_spl_token_v2_0::{self, SPL_TOKEN_ACCOUNT_MINT_OFFSET, SPL_TOKEN_ACCOUNT_OWNER_OFFSET},
     secondary_index::*,
 };
-use dashmap::DashSet;
+use dashmap::{DashMap, DashSet};
 use ouroboros::self_referencing;
+use serde::{de::DeserializeOwned, Serialize};
 use solana_measure::measure::Measure;
 use solana_sdk::{
     clock::Slot,
@@ -20,13 +22,17 @@ use std::{
         Bound::{Excluded, Included, Unbounded},
         Range, RangeBounds,
     },
+    path::PathBuf,
+    str::FromStr,
     sync::{
         atomic::{AtomicU64, Ordering},
-        Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+        Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard,
     },
 };
 
 pub const ITER_BATCH_SIZE: usize = 1000;
+pub const DEFAULT_ACCOUNTS_INDEX_BINS: usize = 256;
+pub const DEFAULT_ACCOUNTS_INDEX_IN_MEMORY_CAPACITY: usize = 1_000_000;
 
 pub type SlotList<T> = Vec<(Slot, T)>;
 pub type SlotSlice<'s, T> = &'s [(Slot, T)];
@@ -55,7 +61,8 @@ impl IsCached for u64 {
 
 enum ScanTypes<R: RangeBounds<Pubkey>> {
     Unindexed(Option<R>),
-    Indexed(IndexKey),
+    // With a range, only the indexed keys within it are scanned, in pubkey order
+    Indexed(IndexKey, Option<R>),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -63,6 +70,7 @@ pub enum IndexKey {
     ProgramId(Pubkey),
     SplTokenMint(Pubkey),
     SplTokenOwner(Pubkey),
+    Memcmp(MemcmpIndex, Pubkey),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -70,6 +78,43 @@ pub enum AccountIndex {
     ProgramId,
     SplTokenMint,
     SplTokenOwner,
+    Memcmp(MemcmpIndex),
+}
+
+/// A custom secondary index over the 32 bytes at `offset` in the data of every account owned by
+/// `program_id`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MemcmpIndex {
+    pub program_id: Pubkey,
+    pub offset: usize,
+}
+
+impl MemcmpIndex {
+    /// The index key of an account owned by `program_id`, or `None` if its data is too short
+    pub fn key(&self, account_data: &[u8]) -> Option<Pubkey> {
+        account_data
+            .get(self.offset..self.offset.checked_add(PUBKEY_BYTES)?)
+            .map(Pubkey::new)
+    }
+}
+
+impl FromStr for MemcmpIndex {
+    type Err = &'static str;
+
+    // Parses `PROGRAM_ID:OFFSET`
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ':');
+        let program_id = parts
+            .next()
+            .and_then(|program_id| program_id.parse().ok())
+            .ok_or("invalid program id")?;
+        let offset = parts
+            .next()
+            .ok_or("expected PROGRAM_ID:OFFSET")?
+            .parse()
+            .map_err(|_| "invalid offset")?;
+        Ok(Self { program_id, offset })
+    }
 }
 
 #[derive(Debug)]
@@ -79,6 +124,13 @@ pub struct AccountMapEntryInner<T> {
 }
 
 impl<T> AccountMapEntryInner<T> {
+    fn new(slot_list: SlotList<T>, ref_count: RefCount) -> Self {
+        Self {
+            ref_count: AtomicU64::new(ref_count),
+            slot_list: RwLock::new(slot_list),
+        }
+    }
+
     pub fn ref_count(&self) -> u64 {
         self.ref_count.load(Ordering::Relaxed)
     }
@@ -107,10 +159,6 @@ impl<T: Clone> ReadAccountMapEntry<T> {
     pub fn ref_count(&self) -> &AtomicU64 {
         &self.borrow_owned_entry_contents().ref_count
     }
-
-    pub fn unref(&self) {
-        self.ref_count().fetch_sub(1, Ordering::Relaxed);
-    }
 }
 
 #[self_referencing]
@@ -188,8 +236,39 @@ pub struct AccountsIndexRootsStats {
     pub previous_uncleaned_roots_len: usize,
 }
 
+/// Keeps only the most recently used entries of an `AccountsIndex` in memory and moves the rest
+/// to memory-mapped buckets on disk
+#[derive(Debug, Clone)]
+pub struct AccountsIndexConfig {
+    /// Directory the buckets are created in
+    pub index_path: PathBuf,
+    /// Number of buckets, rounded up to a power of two
+    pub bins: usize,
+    /// Number of entries kept in memory
+    pub in_memory_capacity: usize,
+}
+
+impl AccountsIndexConfig {
+    pub fn new(index_path: PathBuf) -> Self {
+        Self {
+            index_path,
+            bins: DEFAULT_ACCOUNTS_INDEX_BINS,
+            in_memory_capacity: DEFAULT_ACCOUNTS_INDEX_IN_MEMORY_CAPACITY,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DiskIndex<T> {
+    bucket_map: BucketMap<T>,
+    in_memory_capacity: usize,
+    // Where the next sweep for entries to move to disk starts
+    eviction_cursor: Mutex<Pubkey>,
+}
+
 pub struct AccountsIndexIterator<'a, T> {
     account_maps: &'a RwLock<AccountMap<Pubkey, AccountMapEntry<T>>>,
+    disk_index: Option<&'a DiskIndex<T>>,
     start_bound: Bound<Pubkey>,
     end_bound: Bound<Pubkey>,
     is_finished: bool,
@@ -204,8 +283,9 @@ impl<'a, T> AccountsIndexIterator<'a, T> {
         }
     }
 
-    pub fn new<R>(
+    fn new<R>(
         account_maps: &'a RwLock<AccountMap<Pubkey, AccountMapEntry<T>>>,
+        disk_index: Option<&'a DiskIndex<T>>,
         range: Option<R>,
     ) -> Self
     where
@@ -221,26 +301,48 @@ impl<'a, T> AccountsIndexIterator<'a, T> {
                 .map(|r| Self::clone_bound(r.end_bound()))
                 .unwrap_or(Unbounded),
             account_maps,
+            disk_index,
             is_finished: false,
         }
     }
 }
 
-impl<'a, T: 'static + Clone> Iterator for AccountsIndexIterator<'a, T> {
+impl<'a, T: 'static + Clone + Serialize + DeserializeOwned> Iterator
+    for AccountsIndexIterator<'a, T>
+{
     type Item = Vec<(Pubkey, AccountMapEntry<T>)>;
     fn next(&mut self) -> Option<Self::Item> {
         if self.is_finished {
             return None;
         }
 
-        let chunk: Vec<(Pubkey, AccountMapEntry<T>)> = self
-            .account_maps
-            .read()
-            .unwrap()
+        let account_maps = self.account_maps.read().unwrap();
+        let mut chunk: Vec<(Pubkey, AccountMapEntry<T>)> = account_maps
             .range((self.start_bound, self.end_bound))
             .map(|(pubkey, account_map_entry)| (*pubkey, account_map_entry.clone()))
             .take(ITER_BATCH_SIZE)
             .collect();
+        if let Some(disk_index) = self.disk_index {
+            // Entries only move between memory and disk under the write lock, so holding the
+            // read lock sees every entry exactly once. The entries read from disk are copies,
+            // which is all scans need.
+            let disk_chunk = disk_index
+                .bucket_map
+                .range_batch(&(self.start_bound, self.end_bound), ITER_BATCH_SIZE);
+            chunk.extend(
+                disk_chunk
+                    .into_iter()
+                    .map(|(pubkey, slot_list, ref_count)| {
+                        (
+                            pubkey,
+                            Arc::new(AccountMapEntryInner::new(slot_list, ref_count)),
+                        )
+                    }),
+            );
+            chunk.sort_unstable_by_key(|(pubkey, _)| *pubkey);
+            chunk.truncate(ITER_BATCH_SIZE);
+        }
+        drop(account_maps);
 
         if chunk.is_empty() {
             self.is_finished = true;
@@ -262,19 +364,67 @@ pub struct AccountsIndex<T> {
     program_id_index: SecondaryIndex<DashMapSecondaryIndexEntry>,
     spl_token_mint_index: SecondaryIndex<DashMapSecondaryIndexEntry>,
     spl_token_owner_index: SecondaryIndex<RwLockSecondaryIndexEntry>,
+    memcmp_indexes: DashMap<MemcmpIndex, SecondaryIndex<DashMapSecondaryIndexEntry>>,
     roots_tracker: RwLock<RootsTracker>,
     ongoing_scan_roots: RwLock<BTreeMap<Slot, u64>>,
     zero_lamport_pubkeys: DashSet<Pubkey>,
+    // Entries that don't fit in `account_maps` if a disk index is configured
+    disk_index: Option<DiskIndex<T>>,
 }
 
-impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
-    fn iter<R>(&self, range: Option<R>) -> AccountsIndexIterator<T>
+impl<T: 'static + Clone + IsCached + ZeroLamport + Serialize + DeserializeOwned> AccountsIndex<T> {
+    pub fn new(config: Option<AccountsIndexConfig>) -> Self
+    where
+        T: Default,
+    {
+        Self {
+            disk_index: config.map(|config| DiskIndex {
+                bucket_map: BucketMap::new(&config.index_path, config.bins),
+                in_memory_capacity: config.in_memory_capacity,
+                eviction_cursor: Mutex::default(),
+            }),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn iter<R>(&self, range: Option<R>) -> AccountsIndexIterator<T>
     where
         R: RangeBounds<Pubkey>,
     {
-        AccountsIndexIterator::new(&self.account_maps, range)
+        AccountsIndexIterator::new(&self.account_maps, self.disk_index.as_ref(), range)
+    }
+
+    /// Whether there is an entry for `pubkey`, in memory or on disk
+    pub fn contains_key(&self, pubkey: &Pubkey) -> bool {
+        let account_maps = self.account_maps.read().unwrap();
+        account_maps.contains_key(pubkey)
+            || self.disk_index.as_ref().map_or(false, |disk_index| {
+                disk_index.bucket_map.contains_key(pubkey)
+            })
+    }
+
+    /// All the pubkeys with an entry, in no particular order
+    pub fn pubkeys(&self) -> Vec<Pubkey> {
+        let account_maps = self.account_maps.read().unwrap();
+        let mut pubkeys: Vec<_> = account_maps.keys().cloned().collect();
+        if let Some(disk_index) = &self.disk_index {
+            pubkeys.extend(disk_index.bucket_map.pubkeys());
+        }
+        pubkeys
+    }
+
+    /// Number of entries held in memory and on disk
+    pub fn num_entries(&self) -> (usize, usize) {
+        let account_maps = self.account_maps.read().unwrap();
+        (
+            account_maps.len(),
+            self.disk_index
+                .as_ref()
+                .map_or(0, |disk_index| disk_index.bucket_map.len()),
+        )
     }
 
+    // `func` returns whether to keep scanning
     fn do_checked_scan_accounts<F, R>(
         &self,
         metric_name: &'static str,
@@ -282,7 +432,7 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         func: F,
         scan_type: ScanTypes<R>,
     ) where
-        F: FnMut(&Pubkey, (&T, Slot)),
+        F: FnMut(&Pubkey, (&T, Slot)) -> bool,
         R: RangeBounds<Pubkey>,
     {
         let max_root = {
@@ -423,33 +573,49 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
                 // Pass "" not to log metrics, so RPC doesn't get spammy
                 self.do_scan_accounts(metric_name, ancestors, func, range, Some(max_root));
             }
-            ScanTypes::Indexed(IndexKey::ProgramId(program_id)) => {
+            ScanTypes::Indexed(IndexKey::ProgramId(program_id), range) => {
                 self.do_scan_secondary_index(
                     ancestors,
                     func,
                     &self.program_id_index,
                     &program_id,
+                    range,
                     Some(max_root),
                 );
             }
-            ScanTypes::Indexed(IndexKey::SplTokenMint(mint_key)) => {
+            ScanTypes::Indexed(IndexKey::SplTokenMint(mint_key), range) => {
                 self.do_scan_secondary_index(
                     ancestors,
                     func,
                     &self.spl_token_mint_index,
                     &mint_key,
+                    range,
                     Some(max_root),
                 );
             }
-            ScanTypes::Indexed(IndexKey::SplTokenOwner(owner_key)) => {
+            ScanTypes::Indexed(IndexKey::SplTokenOwner(owner_key), range) => {
                 self.do_scan_secondary_index(
                     ancestors,
                     func,
                     &self.spl_token_owner_index,
                     &owner_key,
+                    range,
                     Some(max_root),
                 );
             }
+            ScanTypes::Indexed(IndexKey::Memcmp(memcmp_index, key), range) => {
+                // Nothing has been indexed yet if no account matched since startup
+                if let Some(index) = self.memcmp_indexes.get(&memcmp_index) {
+                    self.do_scan_secondary_index(
+                        ancestors,
+                        func,
+                        &index,
+                        &key,
+                        range,
+                        Some(max_root),
+                    );
+                }
+            }
         }
 
         {
@@ -466,18 +632,28 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         &self,
         metric_name: &'static str,
         ancestors: &Ancestors,
-        func: F,
+        mut func: F,
         range: Option<R>,
     ) where
         F: FnMut(&Pubkey, (&T, Slot)),
         R: RangeBounds<Pubkey>,
     {
-        self.do_scan_accounts(metric_name, ancestors, func, range, None);
+        self.do_scan_accounts(
+            metric_name,
+            ancestors,
+            |pubkey, index| {
+                func(pubkey, index);
+                true
+            },
+            range,
+            None,
+        );
     }
 
-    // Scan accounts and return latest version of each account that is either:
+    // Scan accounts in pubkey order and return latest version of each account that is either:
     // 1) rooted or
     // 2) present in ancestors
+    // until `func` returns false
     fn do_scan_accounts<F, R>(
         &self,
         metric_name: &'static str,
@@ -486,7 +662,7 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         range: Option<R>,
         max_root: Option<Slot>,
     ) where
-        F: FnMut(&Pubkey, (&T, Slot)),
+        F: FnMut(&Pubkey, (&T, Slot)) -> bool,
         R: RangeBounds<Pubkey>,
     {
         // TODO: expand to use mint index to find the `pubkey_list` below more efficiently
@@ -498,7 +674,7 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         let mut read_lock_elapsed = 0;
         let mut iterator_elapsed = 0;
         let mut iterator_timer = Measure::start("iterator_elapsed");
-        for pubkey_list in self.iter(range) {
+        'outer: for pubkey_list in self.iter(range) {
             iterator_timer.stop();
             iterator_elapsed += iterator_timer.as_us();
             for (pubkey, list) in pubkey_list {
@@ -512,9 +688,12 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
                     latest_slot_timer.stop();
                     latest_slot_elapsed += latest_slot_timer.as_us();
                     let mut load_account_timer = Measure::start("load_account");
-                    func(&pubkey, (&list_r[index].1, list_r[index].0));
+                    let keep_scanning = func(&pubkey, (&list_r[index].1, list_r[index].0));
                     load_account_timer.stop();
                     load_account_elapsed += load_account_timer.as_us();
+                    if !keep_scanning {
+                        break 'outer;
+                    }
                 }
             }
             iterator_timer = Measure::start("iterator_elapsed");
@@ -536,6 +715,7 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
 
     fn do_scan_secondary_index<
         F,
+        R,
         SecondaryIndexEntryType: SecondaryIndexEntry + Default + Sync + Send,
     >(
         &self,
@@ -543,37 +723,61 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         mut func: F,
         index: &SecondaryIndex<SecondaryIndexEntryType>,
         index_key: &Pubkey,
+        range: Option<R>,
         max_root: Option<Slot>,
     ) where
-        F: FnMut(&Pubkey, (&T, Slot)),
+        F: FnMut(&Pubkey, (&T, Slot)) -> bool,
+        R: RangeBounds<Pubkey>,
     {
-        for pubkey in index.get(index_key) {
+        let mut pubkeys = index.get(index_key);
+        if let Some(range) = range {
+            // Only the accounts of keys within the range are loaded, in pubkey order, so the
+            // scan can stop early
+            pubkeys.retain(|pubkey| range.contains(pubkey));
+            pubkeys.sort_unstable();
+        }
+        for pubkey in pubkeys {
             // Maybe these reads from the AccountsIndex can be batched everytime it
             // grabs the read lock as well...
             if let Some((list_r, index)) = self.get(&pubkey, Some(ancestors), max_root) {
-                func(
+                if !func(
                     &pubkey,
                     (&list_r.slot_list()[index].1, list_r.slot_list()[index].0),
-                );
+                ) {
+                    break;
+                }
             }
         }
     }
 
+    // Entries only move between `account_maps` and the disk index under the `account_maps` write
+    // lock, so while holding either lock every entry is in exactly one of them
+    fn get_account_map_entry(&self, pubkey: &Pubkey) -> Option<AccountMapEntry<T>> {
+        let account_maps = self.account_maps.read().unwrap();
+        if let Some(account_entry) = account_maps.get(pubkey) {
+            return Some(account_entry.clone());
+        }
+        if !self.disk_index.as_ref()?.bucket_map.contains_key(pubkey) {
+            return None;
+        }
+        drop(account_maps);
+        self.load_from_disk(pubkey)
+    }
+
+    /// Entries on disk are read in place instead of being loaded into memory, so the returned
+    /// entry may be a copy that updates are lost on
     pub fn get_account_read_entry(&self, pubkey: &Pubkey) -> Option<ReadAccountMapEntry<T>> {
-        self.account_maps
-            .read()
-            .unwrap()
-            .get(pubkey)
-            .cloned()
-            .map(ReadAccountMapEntry::from_account_map_entry)
+        let account_maps = self.account_maps.read().unwrap();
+        let account_entry = account_maps.get(pubkey).cloned().or_else(|| {
+            let (slot_list, ref_count) = self.disk_index.as_ref()?.bucket_map.get(pubkey)?;
+            Some(Arc::new(AccountMapEntryInner::new(slot_list, ref_count)))
+        });
+        drop(account_maps);
+        account_entry.map(ReadAccountMapEntry::from_account_map_entry)
     }
 
     fn get_account_write_entry(&self, pubkey: &Pubkey) -> Option<WriteAccountMapEntry<T>> {
-        self.account_maps
-            .read()
-            .unwrap()
-            .get(pubkey)
-            .cloned()
+        self.get_account_map_entry(pubkey)
             .map(WriteAccountMapEntry::from_account_map_entry)
     }
 
@@ -584,14 +788,82 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         });
         let mut w_account_maps = self.account_maps.write().unwrap();
         let mut is_newly_inserted = false;
-        let account_entry = w_account_maps.entry(*pubkey).or_insert_with(|| {
-            is_newly_inserted = true;
-            new_entry
-        });
-        let w_account_entry = WriteAccountMapEntry::from_account_map_entry(account_entry.clone());
+        let account_entry = w_account_maps
+            .entry(*pubkey)
+            .or_insert_with(|| {
+                self.take_from_disk(pubkey).unwrap_or_else(|| {
+                    is_newly_inserted = true;
+                    new_entry
+                })
+            })
+            .clone();
+        self.evict_to_disk(&mut w_account_maps);
+        let w_account_entry = WriteAccountMapEntry::from_account_map_entry(account_entry);
         (w_account_entry, is_newly_inserted)
     }
 
+    // Removes the entry for `pubkey` from the disk index. The caller must hold the write lock on
+    // `account_maps` and insert the entry there.
+    fn take_from_disk(&self, pubkey: &Pubkey) -> Option<AccountMapEntry<T>> {
+        let (slot_list, ref_count) = self.disk_index.as_ref()?.bucket_map.remove(pubkey)?;
+        Some(Arc::new(AccountMapEntryInner::new(slot_list, ref_count)))
+    }
+
+    // Moves the entry for `pubkey` from the disk index to `account_maps`
+    fn load_from_disk(&self, pubkey: &Pubkey) -> Option<AccountMapEntry<T>> {
+        let mut w_account_maps = self.account_maps.write().unwrap();
+        // Another thread may have loaded the entry since it was looked up on disk
+        let account_entry = match w_account_maps.entry(*pubkey) {
+            btree_map::Entry::Occupied(entry) => entry.get().clone(),
+            btree_map::Entry::Vacant(entry) => entry.insert(self.take_from_disk(pubkey)?).clone(),
+        };
+        self.evict_to_disk(&mut w_account_maps);
+        Some(account_entry)
+    }
+
+    // Once `account_maps` outgrows the in-memory capacity, moves the entries nobody holds a
+    // reference to to disk, sweeping round robin from where the last sweep stopped. Frees an
+    // extra tenth of the capacity so the sweep doesn't run again on every insert.
+    fn evict_to_disk(&self, account_maps: &mut AccountMap<Pubkey, AccountMapEntry<T>>) {
+        let disk_index = match &self.disk_index {
+            Some(disk_index) => disk_index,
+            None => return,
+        };
+        if account_maps.len() <= disk_index.in_memory_capacity {
+            return;
+        }
+        let num_to_evict =
+            account_maps.len() - disk_index.in_memory_capacity + disk_index.in_memory_capacity / 10;
+        let mut eviction_cursor = disk_index.eviction_cursor.lock().unwrap();
+        let evicted: Vec<Pubkey> = account_maps
+            .range((Excluded(*eviction_cursor), Unbounded))
+            .chain(account_maps.range(..=*eviction_cursor))
+            // Bound the sweep in case most entries are in use
+            .take(num_to_evict * 4)
+            .filter(|(_, account_entry)| {
+                // Entries with an empty slot list are left for `handle_dead_keys()`
+                Arc::strong_count(account_entry) == 1
+                    && !account_entry.slot_list.read().unwrap().is_empty()
+            })
+            .map(|(pubkey, _)| *pubkey)
+            .take(num_to_evict)
+            .collect();
+        for pubkey in &evicted {
+            let account_entry = account_maps.remove(pubkey).unwrap();
+            // Nobody else can hold a reference, they are all cloned under the `account_maps` lock
+            let account_entry = Arc::try_unwrap(account_entry)
+                .unwrap_or_else(|_| panic!("accounts index entry {} is still in use", pubkey));
+            disk_index.bucket_map.insert(
+                pubkey,
+                &account_entry.slot_list.into_inner().unwrap(),
+                account_entry.ref_count.into_inner(),
+            );
+        }
+        if let Some(pubkey) = evicted.last() {
+            *eviction_cursor = *pubkey;
+        }
+    }
+
     fn get_account_write_entry_else_create(
         &self,
         pubkey: &Pubkey,
@@ -631,7 +903,7 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
     }
 
     /// call func with every pubkey and index visible from a given set of ancestors
-    pub(crate) fn scan_accounts<F>(&self, ancestors: &Ancestors, func: F)
+    pub(crate) fn scan_accounts<F>(&self, ancestors: &Ancestors, mut func: F)
     where
         F: FnMut(&Pubkey, (&T, Slot)),
     {
@@ -639,11 +911,25 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         self.do_checked_scan_accounts(
             "",
             ancestors,
-            func,
+            |pubkey, index| {
+                func(pubkey, index);
+                true
+            },
             ScanTypes::Unindexed(None::<Range<Pubkey>>),
         );
     }
 
+    /// call func with every pubkey and index visible from a given set of ancestors within
+    /// `range`, in pubkey order, until it returns false
+    pub(crate) fn checked_range_scan_accounts<F, R>(&self, ancestors: &Ancestors, range: R, func: F)
+    where
+        F: FnMut(&Pubkey, (&T, Slot)) -> bool,
+        R: RangeBounds<Pubkey>,
+    {
+        // Pass "" not to log metrics, so RPC doesn't get spammy
+        self.do_checked_scan_accounts("", ancestors, func, ScanTypes::Unindexed(Some(range)));
+    }
+
     pub(crate) fn unchecked_scan_accounts<F>(
         &self,
         metric_name: &'static str,
@@ -671,16 +957,44 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
     }
 
     /// call func with every pubkey and index visible from a given set of ancestors
-    pub(crate) fn index_scan_accounts<F>(&self, ancestors: &Ancestors, index_key: IndexKey, func: F)
-    where
+    pub(crate) fn index_scan_accounts<F>(
+        &self,
+        ancestors: &Ancestors,
+        index_key: IndexKey,
+        mut func: F,
+    ) where
         F: FnMut(&Pubkey, (&T, Slot)),
+    {
+        // Pass "" not to log metrics, so RPC doesn't get spammy
+        self.do_checked_scan_accounts(
+            "",
+            ancestors,
+            |pubkey, index| {
+                func(pubkey, index);
+                true
+            },
+            ScanTypes::<Range<Pubkey>>::Indexed(index_key, None),
+        );
+    }
+
+    /// call func with every pubkey and index visible from a given set of ancestors within
+    /// `range` of the keys indexed under `index_key`, in pubkey order, until it returns false
+    pub(crate) fn checked_index_scan_accounts<F, R>(
+        &self,
+        ancestors: &Ancestors,
+        index_key: IndexKey,
+        range: R,
+        func: F,
+    ) where
+        F: FnMut(&Pubkey, (&T, Slot)) -> bool,
+        R: RangeBounds<Pubkey>,
     {
         // Pass "" not to log metrics, so RPC doesn't get spammy
         self.do_checked_scan_accounts(
             "",
             ancestors,
             func,
-            ScanTypes::<Range<Pubkey>>::Indexed(index_key),
+            ScanTypes::Indexed(index_key, Some(range)),
         );
     }
 
@@ -859,6 +1173,23 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
                 self.spl_token_mint_index.insert(&mint_key, pubkey, slot);
             }
         }
+
+        for account_index in account_indexes {
+            if let AccountIndex::Memcmp(memcmp_index) = account_index {
+                if *account_owner != memcmp_index.program_id {
+                    continue;
+                }
+                if let Some(key) = memcmp_index.key(account_data) {
+                    let index = self.memcmp_indexes.get(memcmp_index).unwrap_or_else(|| {
+                        self.memcmp_indexes
+                            .entry(*memcmp_index)
+                            .or_default()
+                            .downgrade()
+                    });
+                    index.insert(&key, pubkey, slot);
+                }
+            }
+        }
     }
 
     // Same functionally to upsert, but doesn't take the read lock
@@ -930,8 +1261,9 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
     }
 
     pub fn unref_from_storage(&self, pubkey: &Pubkey) {
-        if let Some(locked_entry) = self.get_account_read_entry(pubkey) {
-            locked_entry.unref();
+        // Not a read entry, which may be a copy of an entry on disk
+        if let Some(account_entry) = self.get_account_map_entry(pubkey) {
+            account_entry.ref_count.fetch_sub(1, Ordering::Relaxed);
         }
     }
 
@@ -965,6 +1297,14 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
             self.spl_token_mint_index
                 .remove_by_inner_key(inner_key, slots_to_remove);
         }
+
+        for account_index in account_indexes {
+            if let AccountIndex::Memcmp(memcmp_index) = account_index {
+                if let Some(index) = self.memcmp_indexes.get(memcmp_index) {
+                    index.remove_by_inner_key(inner_key, slots_to_remove);
+                }
+            }
+        }
     }
 
     fn purge_older_root_entries(
@@ -2229,6 +2569,249 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_memcmp_index_from_str() {
+        let program_id = Pubkey::new_unique();
+        assert_eq!(
+            format!("{}:42", program_id).parse::<MemcmpIndex>(),
+            Ok(MemcmpIndex {
+                program_id,
+                offset: 42
+            })
+        );
+        assert_eq!(
+            program_id.to_string().parse::<MemcmpIndex>(),
+            Err("expected PROGRAM_ID:OFFSET")
+        );
+        assert_eq!(
+            format!("{}:-1", program_id).parse::<MemcmpIndex>(),
+            Err("invalid offset")
+        );
+        assert_eq!("bogus:42".parse::<MemcmpIndex>(), Err("invalid program id"));
+    }
+
+    #[test]
+    fn test_memcmp_secondary_index() {
+        let memcmp_index = MemcmpIndex {
+            program_id: Pubkey::new_unique(),
+            offset: 8,
+        };
+        let mut account_indexes = HashSet::new();
+        account_indexes.insert(AccountIndex::Memcmp(memcmp_index));
+        let index = AccountsIndex::<bool>::default();
+
+        let account_key = Pubkey::new_unique();
+        let index_key = Pubkey::new_unique();
+        let slot = 1;
+        let mut account_data = vec![0; 8 + PUBKEY_BYTES];
+        account_data[8..].clone_from_slice(&index_key.to_bytes());
+
+        // Wrong program id
+        index.upsert(
+            slot,
+            &account_key,
+            &Pubkey::default(),
+            &account_data,
+            &account_indexes,
+            true,
+            &mut vec![],
+        );
+        assert!(index.memcmp_indexes.is_empty());
+
+        // Account data too short to hold a key at the offset
+        index.upsert(
+            slot,
+            &account_key,
+            &memcmp_index.program_id,
+            &account_data[1..],
+            &account_indexes,
+            true,
+            &mut vec![],
+        );
+        assert!(index.memcmp_indexes.is_empty());
+
+        index.upsert(
+            slot,
+            &account_key,
+            &memcmp_index.program_id,
+            &account_data,
+            &account_indexes,
+            true,
+            &mut vec![],
+        );
+        check_secondary_index_unique(
+            index.memcmp_indexes.get(&memcmp_index).unwrap().value(),
+            slot,
+            &index_key,
+            &account_key,
+        );
+
+        let ancestors: Ancestors = vec![(0, 0), (slot, 0)].into_iter().collect();
+        let mut found = vec![];
+        index.index_scan_accounts(
+            &ancestors,
+            IndexKey::Memcmp(memcmp_index, index_key),
+            |pubkey, _| found.push(*pubkey),
+        );
+        assert_eq!(found, vec![account_key]);
+
+        // Scanning an index that was never configured finds nothing
+        let mut found = vec![];
+        let other_memcmp_index = MemcmpIndex {
+            offset: 0,
+            ..memcmp_index
+        };
+        index.index_scan_accounts(
+            &ancestors,
+            IndexKey::Memcmp(other_memcmp_index, index_key),
+            |pubkey, _| found.push(*pubkey),
+        );
+        assert!(found.is_empty());
+
+        index
+            .get_account_write_entry(&account_key)
+            .unwrap()
+            .slot_list_mut(|slot_list| slot_list.clear());
+
+        // Everything should be deleted
+        index.handle_dead_keys(&[&account_key], &account_indexes);
+        let secondary_index = index.memcmp_indexes.get(&memcmp_index).unwrap();
+        assert!(secondary_index.index.is_empty());
+        assert!(secondary_index.reverse_index.is_empty());
+    }
+
+    #[test]
+    fn test_disk_index() {
+        let index_dir = tempfile::TempDir::new().unwrap();
+        let index = AccountsIndex::<u64>::new(Some(AccountsIndexConfig {
+            bins: 4,
+            in_memory_capacity: 10,
+            ..AccountsIndexConfig::new(index_dir.path().to_path_buf())
+        }));
+        let mut keys: Vec<_> = (0..100).map(|_| Pubkey::new_unique()).collect();
+        keys.sort();
+        let mut gc = Vec::new();
+        for (i, key) in keys.iter().enumerate() {
+            index.upsert(
+                0,
+                key,
+                &Pubkey::default(),
+                &[],
+                &HashSet::new(),
+                i as u64,
+                &mut gc,
+            );
+        }
+        index.add_root(0, false);
+        let (num_in_memory, num_on_disk) = index.num_entries();
+        assert!(num_in_memory <= 10);
+        assert_eq!(num_in_memory + num_on_disk, keys.len());
+        assert!(index.contains_key(&keys[0]));
+        assert!(!index.contains_key(&Pubkey::new_unique()));
+        let mut pubkeys = index.pubkeys();
+        pubkeys.sort();
+        assert_eq!(pubkeys, keys);
+
+        // Entries on disk are read without loading them into memory
+        let num_entries = index.num_entries();
+        for (i, key) in keys.iter().enumerate() {
+            let (list, idx) = index.get(key, None, None).unwrap();
+            assert_eq!(list.slot_list()[idx], (0, i as u64));
+            assert_eq!(index.ref_count_from_storage(key), 1);
+        }
+        assert_eq!(index.num_entries(), num_entries);
+
+        // But updates load them
+        index.unref_from_storage(&keys[0]);
+        assert_eq!(index.ref_count_from_storage(&keys[0]), 0);
+        index
+            .get_account_write_entry(&keys[0])
+            .unwrap()
+            .ref_count()
+            .fetch_add(1, Ordering::Relaxed);
+        assert_eq!(index.ref_count_from_storage(&keys[0]), 1);
+        assert_eq!(index.num_entries().0 + index.num_entries().1, keys.len());
+
+        // Scans see every entry exactly once and in order
+        let ancestors = HashMap::new();
+        let mut found = vec![];
+        index.unchecked_scan_accounts("", &ancestors, |pubkey, _| found.push(*pubkey));
+        assert_eq!(found, keys);
+        let mut found = vec![];
+        index.range_scan_accounts("", &ancestors, keys[20]..keys[70], |pubkey, _| {
+            found.push(*pubkey)
+        });
+        assert_eq!(found, keys[20..70].to_vec());
+
+        // Updates to entries on disk keep the older slots
+        for (i, key) in keys.iter().enumerate() {
+            index.upsert(
+                1,
+                key,
+                &Pubkey::default(),
+                &[],
+                &HashSet::new(),
+                i as u64 + 100,
+                &mut gc,
+            );
+        }
+        assert!(gc.is_empty());
+        index.add_root(1, false);
+        for (i, key) in keys.iter().enumerate() {
+            let (list, idx) = index.get(key, None, None).unwrap();
+            assert_eq!(list.slot_list().len(), 2);
+            assert_eq!(list.slot_list()[idx], (1, i as u64 + 100));
+        }
+    }
+
+    #[test]
+    fn test_disk_index_concurrent_get_and_evict() {
+        let index_dir = tempfile::TempDir::new().unwrap();
+        let index = Arc::new(AccountsIndex::<u64>::new(Some(AccountsIndexConfig {
+            bins: 4,
+            in_memory_capacity: 1,
+            ..AccountsIndexConfig::new(index_dir.path().to_path_buf())
+        })));
+        let keys: Arc<Vec<_>> = Arc::new((0..8).map(|_| Pubkey::new_unique()).collect());
+        let mut gc = Vec::new();
+        for key in keys.iter() {
+            index.upsert(0, key, &Pubkey::default(), &[], &HashSet::new(), 0, &mut gc);
+        }
+        index.add_root(0, false);
+
+        // Readers must find every entry while the others and new entries keep moving entries
+        // between memory and disk
+        let readers: Vec<_> = (0..8)
+            .map(|_| {
+                let index = index.clone();
+                let keys = keys.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..2000 {
+                        for key in keys.iter() {
+                            assert!(index.get(key, None, None).is_some());
+                            assert!(index.contains_key(key));
+                            assert!(index.get_account_write_entry(key).is_some());
+                        }
+                    }
+                })
+            })
+            .collect();
+        for _ in 0..1000 {
+            index.upsert(
+                1,
+                &Pubkey::new_unique(),
+                &Pubkey::default(),
+                &[],
+                &HashSet::new(),
+                1,
+                &mut gc,
+            );
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+
     impl ZeroLamport for bool {
         fn is_zero_lamport(&self) -> bool {
             false
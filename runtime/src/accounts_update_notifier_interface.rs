@@ -0,0 +1,22 @@
+use solana_sdk::{account::Account, clock::Slot, pubkey::Pubkey};
+use std::{
+    fmt::Debug,
+    sync::{Arc, RwLock},
+};
+
+/// Receives every account write made through `AccountsDB::store_cached` and
+/// `AccountsDB::store_uncached`, so that validator plugins can observe
+/// account state without polling.
+pub trait AccountsUpdateNotifierInterface: Debug {
+    /// Notified when an account is updated at runtime, due to transaction
+    /// activity or rent collection.
+    fn notify_account_update(&self, slot: Slot, pubkey: &Pubkey, account: &Account);
+
+    /// Notified for every account present when the validator boots.
+    fn notify_account_restore_from_snapshot(&self, slot: Slot, pubkey: &Pubkey, account: &Account);
+
+    /// Notified once all accounts present at boot have been notified.
+    fn notify_end_of_restore_from_snapshot(&self);
+}
+
+pub type AccountsUpdateNotifier = Arc<RwLock<dyn AccountsUpdateNotifierInterface + Sync + Send>>;
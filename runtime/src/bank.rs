@@ -8,9 +8,12 @@ use crate::{
         TransactionLoadResult, TransactionLoaders,
     },
     accounts_db::{ErrorCounters, SnapshotStorages},
-    accounts_index::{AccountIndex, Ancestors, IndexKey},
+    accounts_index::{AccountIndex, AccountsIndexConfig, Ancestors, IndexKey},
+    accounts_update_notifier_interface::AccountsUpdateNotifier,
     blockhash_queue::BlockhashQueue,
     builtins::{self, ActivationType},
+    cost_model::{CostModel, TransactionCost},
+    cost_tracker::CostTracker,
     epoch_stakes::{EpochStakes, NodeVoteAccounts},
     inline_spl_token_v2_0,
     instruction_recorder::InstructionRecorder,
@@ -20,13 +23,14 @@ use crate::{
     stakes::Stakes,
     status_cache::{SlotDelta, StatusCache},
     system_instruction_processor::{get_system_account_kind, SystemAccountKind},
-    transaction_batch::TransactionBatch,
+    transaction_batch::{ResolvedTransactions, TransactionBatch},
     transaction_utils::OrderedIterator,
     vote_account::ArcVoteAccount,
 };
 use byteorder::{ByteOrder, LittleEndian};
 use itertools::Itertools;
 use log::*;
+use solana_address_lookup_table_program::state::AddressLookupTable;
 use solana_measure::measure::Measure;
 use solana_metrics::{datapoint_debug, inc_new_counter_debug, inc_new_counter_info};
 use solana_sdk::{
@@ -36,6 +40,7 @@ use solana_sdk::{
         MAX_PROCESSING_AGE, MAX_RECENT_BLOCKHASHES, MAX_TRANSACTION_FORWARDING_DELAY,
         SECONDS_PER_DAY,
     },
+    compute_budget::ComputeBudgetRequest,
     epoch_info::EpochInfo,
     epoch_schedule::EpochSchedule,
     feature,
@@ -47,11 +52,16 @@ use solana_sdk::{
     incinerator,
     inflation::Inflation,
     instruction::CompiledInstruction,
-    message::Message,
+    message::{
+        v0::{LoadedAddresses, MessageAddressTableLookup},
+        Message, VersionedMessage,
+    },
     native_loader,
     native_token::sol_to_lamports,
     nonce, nonce_account,
-    process_instruction::{BpfComputeBudget, Executor, ProcessInstructionWithContext},
+    process_instruction::{
+        BpfComputeBudget, Executor, ProcessInstructionWithContext, TransactionReturnData,
+    },
     program_utils::limited_deserialize,
     pubkey::Pubkey,
     recent_blockhashes_account,
@@ -67,13 +77,14 @@ use solana_sdk::{
     system_transaction,
     sysvar::{self},
     timing::years_as_slots,
-    transaction::{self, Result, Transaction, TransactionError},
+    transaction::{self, Result, Transaction, TransactionError, VersionedTransaction},
 };
 use solana_stake_program::stake_state::{
     self, Delegation, InflationPointCalculationEvent, PointValue,
 };
-use solana_vote_program::vote_instruction::VoteInstruction;
+use solana_vote_program::{vote_instruction::VoteInstruction, vote_state::VoteState};
 use std::{
+    borrow::Cow,
     cell::RefCell,
     collections::{HashMap, HashSet},
     convert::{TryFrom, TryInto},
@@ -109,7 +120,7 @@ impl ExecuteTimings {
 }
 
 type BankStatusCache = StatusCache<Result<()>>;
-#[frozen_abi(digest = "MUmkgPsCRrWL2HEsMEvpkWMis35kbBnaEZtrph5P6bk")]
+#[frozen_abi(digest = "CJLyhiBawSDzJjsaR6GmzAhsQruuHDAyUqueFkhKXipw")]
 pub type BankSlotDelta = SlotDelta<Result<()>>;
 type TransactionAccountRefCells = Vec<Rc<RefCell<Account>>>;
 type TransactionAccountDepRefCells = Vec<(Pubkey, RefCell<Account>)>;
@@ -401,6 +412,23 @@ pub type InnerInstructionsList = Vec<InnerInstructions>;
 /// A list of log messages emitted during a transaction
 pub type TransactionLogMessages = Vec<String>;
 
+/// The compute units consumed by each executed instruction of a transaction
+pub type TransactionComputeUnits = Vec<u64>;
+
+/// The outcome of `Bank::simulate_transaction`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionSimulationResult {
+    pub result: Result<()>,
+    pub logs: TransactionLogMessages,
+    /// The accounts of the transaction after execution, in the order of the message's account
+    /// keys.  The fee is already deducted from the fee payer.  Empty if the transaction could
+    /// not be loaded.
+    pub post_simulation_accounts: Vec<(Pubkey, Account)>,
+    pub units_consumed: TransactionComputeUnits,
+    pub inner_instructions: Option<InnerInstructionsList>,
+    pub return_data: Option<TransactionReturnData>,
+}
+
 #[derive(Serialize, Deserialize, AbiExample, AbiEnumVisitor, Debug, PartialEq)]
 pub enum TransactionLogCollectorFilter {
     All,
@@ -704,8 +732,9 @@ pub trait DropCallback: fmt::Debug {
 #[derive(Debug, PartialEq, Serialize, Deserialize, AbiExample, Clone, Copy)]
 pub struct RewardInfo {
     pub reward_type: RewardType,
-    pub lamports: i64,     // Reward amount
-    pub post_balance: u64, // Account balance in lamports after `lamports` was applied
+    pub lamports: i64,          // Reward amount
+    pub post_balance: u64,      // Account balance in lamports after `lamports` was applied
+    pub commission: Option<u8>, // Vote account commission when the reward was credited, only present for voting and staking rewards
 }
 
 #[derive(Debug, Default)]
@@ -866,6 +895,9 @@ pub struct Bank {
     pub drop_callback: RwLock<OptionalDropCallback>,
 
     pub freeze_started: AtomicBool,
+
+    /// Estimated cost of the transactions packed into this bank by the leader
+    cost_tracker: RwLock<CostTracker>,
 }
 
 impl Default for BlockhashQueue {
@@ -884,6 +916,7 @@ impl Bank {
             None,
             HashSet::new(),
             false,
+            None,
         )
     }
 
@@ -901,6 +934,7 @@ impl Bank {
             None,
             account_indexes,
             accounts_db_caching_enabled,
+            None,
         )
     }
 
@@ -912,6 +946,7 @@ impl Bank {
         additional_builtins: Option<&Builtins>,
         account_indexes: HashSet<AccountIndex>,
         accounts_db_caching_enabled: bool,
+        accounts_index_config: Option<AccountsIndexConfig>,
     ) -> Self {
         let mut bank = Self::default();
         bank.ancestors.insert(bank.slot(), 0);
@@ -923,6 +958,7 @@ impl Bank {
             &genesis_config.cluster_type,
             account_indexes,
             accounts_db_caching_enabled,
+            accounts_index_config,
         ));
         bank.process_genesis_config(genesis_config);
         bank.finish_init(genesis_config, additional_builtins);
@@ -1054,6 +1090,7 @@ impl Bank {
                     .map(|drop_callback| drop_callback.clone_box()),
             )),
             freeze_started: AtomicBool::new(false),
+            cost_tracker: RwLock::new(CostTracker::default()),
         };
 
         datapoint_info!(
@@ -1190,6 +1227,7 @@ impl Bank {
             feature_set: new(),
             drop_callback: RwLock::new(OptionalDropCallback(None)),
             freeze_started: AtomicBool::new(fields.hash != Hash::default()),
+            cost_tracker: RwLock::new(CostTracker::default()),
         };
         bank.finish_init(genesis_config, additional_builtins);
 
@@ -1837,6 +1875,7 @@ impl Bank {
         for (vote_pubkey, (stake_group, vote_account)) in stake_delegation_accounts.iter_mut() {
             let mut vote_account_changed = false;
             let voters_account_pre_balance = vote_account.lamports;
+            let commission = VoteState::from(vote_account).map(|vote_state| vote_state.commission);
 
             for (stake_pubkey, stake_account) in stake_group.iter_mut() {
                 // curry closure to add the contextual stake_pubkey
@@ -1867,6 +1906,7 @@ impl Bank {
                                 reward_type: RewardType::Staking,
                                 lamports: stakers_reward as i64,
                                 post_balance: stake_account.lamports,
+                                commission,
                             },
                         ));
                     }
@@ -1888,6 +1928,7 @@ impl Bank {
                             reward_type: RewardType::Voting,
                             lamports,
                             post_balance,
+                            commission,
                         },
                     ));
                 }
@@ -2001,6 +2042,7 @@ impl Bank {
                         reward_type: RewardType::Fee,
                         lamports: unburned as i64,
                         post_balance,
+                        commission: None,
                     },
                 ));
             }
@@ -2042,6 +2084,11 @@ impl Bank {
             self.freeze_started.store(true, Relaxed);
             *hash = self.hash_internal_state();
             self.rc.accounts.accounts_db.mark_slot_frozen(self.slot());
+
+            let cost_tracker = self.read_cost_tracker();
+            if cost_tracker.transaction_count() > 0 {
+                cost_tracker.report_stats(self.slot());
+            }
         }
     }
 
@@ -2418,6 +2465,41 @@ impl Bank {
         TransactionBatch::new(results, &self, txs, iteration_order)
     }
 
+    /// Resolve and lock versioned transactions, see `resolve_transactions`
+    pub fn prepare_versioned_batch<'a, 'b>(
+        &'a self,
+        txs: &[VersionedTransaction],
+        iteration_order: Option<Vec<usize>>,
+    ) -> TransactionBatch<'a, 'b> {
+        self.prepare_resolved_batch(self.resolve_transactions(txs), iteration_order)
+    }
+
+    /// Lock the resolved transactions, those whose result is an error are not
+    /// locked and fail with that error
+    pub fn prepare_resolved_batch<'a, 'b>(
+        &'a self,
+        resolved: ResolvedTransactions,
+        iteration_order: Option<Vec<usize>>,
+    ) -> TransactionBatch<'a, 'b> {
+        let ResolvedTransactions {
+            transactions,
+            loaded_addresses,
+            results,
+        } = resolved;
+        let results = self.rc.accounts.lock_accounts_with_results(
+            &transactions,
+            iteration_order.as_deref(),
+            results,
+        );
+        TransactionBatch::new_with_loaded_addresses(
+            results,
+            &self,
+            Cow::Owned(transactions),
+            loaded_addresses,
+            iteration_order,
+        )
+    }
+
     pub fn prepare_simulation_batch<'a, 'b>(
         &'a self,
         txs: &'b [Transaction],
@@ -2431,21 +2513,126 @@ impl Bank {
         batch
     }
 
-    /// Run transactions against a frozen bank without committing the results
-    pub fn simulate_transaction(
+    /// Lower versioned transactions into legacy transactions that the rest of
+    /// the runtime can process, see `resolve_transaction`.  A transaction that
+    /// fails to resolve is replaced by one without any accounts, which keeps
+    /// its signatures and recent blockhash, and its error is returned in the
+    /// results, which are indexed like `txs`.
+    pub fn resolve_transactions(&self, txs: &[VersionedTransaction]) -> ResolvedTransactions {
+        let mut resolved = ResolvedTransactions {
+            transactions: Vec::with_capacity(txs.len()),
+            loaded_addresses: Vec::with_capacity(txs.len()),
+            results: Vec::with_capacity(txs.len()),
+        };
+        for tx in txs {
+            let (transaction, loaded_addresses, result) = match self.resolve_transaction(tx) {
+                Ok((transaction, loaded_addresses)) => (transaction, loaded_addresses, Ok(())),
+                Err(err) => {
+                    let transaction = Transaction {
+                        signatures: tx.signatures.clone(),
+                        message: Message {
+                            recent_blockhash: *tx.message.recent_blockhash(),
+                            ..Message::default()
+                        },
+                    };
+                    (transaction, None, Err(err))
+                }
+            };
+            resolved.transactions.push(transaction);
+            resolved.loaded_addresses.push(loaded_addresses);
+            resolved.results.push(result);
+        }
+        resolved
+    }
+
+    /// Lower a versioned transaction into a legacy transaction whose account
+    /// keys include the addresses loaded from its address lookup tables.  The
+    /// lowered transaction keeps the original signatures, which are only valid
+    /// for the original message.
+    pub fn resolve_transaction(
+        &self,
+        tx: &VersionedTransaction,
+    ) -> Result<(Transaction, Option<LoadedAddresses>)> {
+        match &tx.message {
+            VersionedMessage::Legacy(message) => Ok((
+                Transaction {
+                    signatures: tx.signatures.clone(),
+                    message: message.clone(),
+                },
+                None,
+            )),
+            VersionedMessage::V0(message) => {
+                if !self.versioned_tx_message_enabled() {
+                    return Err(TransactionError::UnsupportedVersion);
+                }
+                tx.sanitize()?;
+                let loaded_addresses =
+                    self.load_lookup_table_addresses(&message.address_table_lookups)?;
+                let message = message
+                    .clone()
+                    .into_legacy_message(&loaded_addresses)
+                    .ok_or(TransactionError::SanitizeFailure)?;
+                Ok((
+                    Transaction {
+                        signatures: tx.signatures.clone(),
+                        message,
+                    },
+                    Some(loaded_addresses),
+                ))
+            }
+        }
+    }
+
+    /// Resolve the indexes of `address_table_lookups` against the lookup
+    /// tables stored in this bank.  Only addresses and deactivations from
+    /// earlier slots are taken into account, so every transaction of a block
+    /// resolves the same way no matter where in the block it was executed.
+    pub fn load_lookup_table_addresses(
         &self,
-        transaction: Transaction,
-    ) -> (Result<()>, TransactionLogMessages) {
+        address_table_lookups: &[MessageAddressTableLookup],
+    ) -> Result<LoadedAddresses> {
+        let current_slot = self.slot();
+        let mut loaded_addresses = LoadedAddresses::default();
+        for lookup in address_table_lookups {
+            let account = self
+                .get_account(&lookup.account_key)
+                .ok_or(TransactionError::AddressLookupTableNotFound)?;
+            if account.owner != solana_address_lookup_table_program::id() {
+                return Err(TransactionError::InvalidAddressLookupTableOwner);
+            }
+            let lookup_table = AddressLookupTable::deserialize(&account.data)
+                .map_err(|_| TransactionError::InvalidAddressLookupTableData)?;
+            if lookup_table.meta.deactivation_slot < current_slot {
+                return Err(TransactionError::AddressLookupTableNotFound);
+            }
+            loaded_addresses.writable.extend(
+                lookup_table
+                    .lookup(current_slot, &lookup.writable_indexes)
+                    .ok_or(TransactionError::InvalidAddressLookupTableIndex)?,
+            );
+            loaded_addresses.readonly.extend(
+                lookup_table
+                    .lookup(current_slot, &lookup.readonly_indexes)
+                    .ok_or(TransactionError::InvalidAddressLookupTableIndex)?,
+            );
+        }
+        Ok(loaded_addresses)
+    }
+
+    /// Run transactions against a frozen bank without committing the results
+    pub fn simulate_transaction(&self, transaction: Transaction) -> TransactionSimulationResult {
         assert!(self.is_frozen(), "simulation bank must be frozen");
 
         let txs = &[transaction];
         let batch = self.prepare_simulation_batch(txs);
 
         let (
-            _loaded_accounts,
+            loaded_accounts,
             executed,
-            _inner_instructions,
+            inner_instructions,
             log_messages,
+            compute_units,
+            return_data,
             _retryable_transactions,
             _transaction_count,
             _signature_count,
@@ -2455,17 +2642,37 @@ impl Bank {
             // for processing. During forwarding, the transaction could expire if the
             // delay is not accounted for.
             MAX_PROCESSING_AGE - MAX_TRANSACTION_FORWARDING_DELAY,
-            false,
+            true,
             true,
             &mut ExecuteTimings::default(),
         );
 
-        let transaction_result = executed[0].0.clone().map(|_| ());
-        let log_messages = log_messages
+        let result = executed[0].0.clone().map(|_| ());
+        let logs = log_messages
             .get(0)
             .map_or(vec![], |messages| messages.to_vec());
-
-        (transaction_result, log_messages)
+        let post_simulation_accounts = match &loaded_accounts[0].0 {
+            Ok(loaded_transaction) => batch.transactions()[0]
+                .message
+                .account_keys
+                .iter()
+                .cloned()
+                .zip(loaded_transaction.accounts.iter().cloned())
+                .collect(),
+            Err(_) => vec![],
+        };
+        let units_consumed = compute_units.into_iter().next().unwrap_or_default();
+        let inner_instructions = inner_instructions.into_iter().next().flatten();
+        let return_data = return_data.into_iter().next().flatten();
+
+        TransactionSimulationResult {
+            result,
+            logs,
+            post_simulation_accounts,
+            units_consumed,
+            inner_instructions,
+            return_data,
+        }
     }
 
     pub fn unlock_accounts(&self, batch: &mut TransactionBatch) {
@@ -2487,6 +2694,21 @@ impl Bank {
         self.rc.accounts.accounts_db.set_shrink_paths(paths);
     }
 
+    pub fn set_accounts_update_notifier(&self, notifier: Option<AccountsUpdateNotifier>) {
+        self.rc
+            .accounts
+            .accounts_db
+            .set_accounts_update_notifier(notifier);
+    }
+
+    /// Notify the accounts update notifier of every account visible from this bank
+    pub fn notify_account_restore_from_snapshot(&self) {
+        self.rc
+            .accounts
+            .accounts_db
+            .notify_account_restore_from_snapshot(&self.ancestors);
+    }
+
     fn check_age(
         &self,
         txs: &[Transaction],
@@ -2725,6 +2947,18 @@ impl Bank {
                 error_counters.not_allowed_during_cluster_maintenance
             );
         }
+        if 0 != error_counters.would_exceed_max_block_cost_limit {
+            inc_new_counter_info!(
+                "bank-process_transactions-would_exceed_max_block_cost_limit",
+                error_counters.would_exceed_max_block_cost_limit
+            );
+        }
+        if 0 != error_counters.would_exceed_max_account_cost_limit {
+            inc_new_counter_info!(
+                "bank-process_transactions-would_exceed_max_account_cost_limit",
+                error_counters.would_exceed_max_account_cost_limit
+            );
+        }
     }
 
     /// Converts Accounts into RefCell<Account>, this involves moving
@@ -2855,6 +3089,8 @@ impl Bank {
         Vec<TransactionExecutionResult>,
         Vec<Option<InnerInstructionsList>>,
         Vec<TransactionLogMessages>,
+        Vec<TransactionComputeUnits>,
+        Vec<Option<TransactionReturnData>>,
         Vec<usize>,
         u64,
         u64,
@@ -2873,6 +3109,15 @@ impl Bank {
                         error_counters.account_in_use += 1;
                         Some(index)
                     }
+                    // Retried in a later block once the cost limits reset
+                    Err(TransactionError::WouldExceedMaxBlockCostLimit) => {
+                        error_counters.would_exceed_max_block_cost_limit += 1;
+                        Some(index)
+                    }
+                    Err(TransactionError::WouldExceedMaxAccountCostLimit) => {
+                        error_counters.would_exceed_max_account_cost_limit += 1;
+                        Some(index)
+                    }
                     Ok(_) => None,
                     Err(_) => None,
                 })
@@ -2902,6 +3147,8 @@ impl Bank {
         let mut inner_instructions: Vec<Option<InnerInstructionsList>> =
             Vec::with_capacity(txs.len());
         let mut transaction_log_messages = Vec::with_capacity(txs.len());
+        let mut transaction_compute_units = Vec::with_capacity(txs.len());
+        let mut transaction_return_data = Vec::with_capacity(txs.len());
         let bpf_compute_budget = self
             .bpf_compute_budget
             .unwrap_or_else(|| BpfComputeBudget::new(&self.feature_set));
@@ -2910,7 +3157,12 @@ impl Bank {
             .iter_mut()
             .zip(OrderedIterator::new(txs, batch.iteration_order()))
             .map(|(accs, (_, tx))| match accs {
-                (Err(e), _nonce_rollback) => (Err(e.clone()), None),
+                (Err(e), _nonce_rollback) => {
+                    // Keep compute units aligned with the transactions; none were consumed
+                    transaction_compute_units.push(vec![]);
+                    transaction_return_data.push(None);
+                    (Err(e.clone()), None)
+                }
                 (Ok(loaded_transaction), nonce_rollback) => {
                     signature_count += u64::from(tx.message().header.num_required_signatures);
 
@@ -2938,18 +3190,28 @@ impl Bank {
                         None
                     };
 
-                    let process_result = self.message_processor.process_message(
-                        tx.message(),
-                        &loader_refcells,
-                        &account_refcells,
-                        &account_dep_refcells,
-                        &self.rent_collector,
-                        log_collector.clone(),
-                        executors.clone(),
-                        instruction_recorders.as_deref(),
-                        self.feature_set.clone(),
-                        bpf_compute_budget,
-                    );
+                    let mut compute_units = vec![];
+                    let mut return_data = None;
+                    let process_result = self
+                        .get_transaction_compute_budget(tx, bpf_compute_budget)
+                        .and_then(|bpf_compute_budget| {
+                            self.message_processor.process_message(
+                                tx.message(),
+                                &loader_refcells,
+                                &account_refcells,
+                                &account_dep_refcells,
+                                &self.rent_collector,
+                                log_collector.clone(),
+                                executors.clone(),
+                                instruction_recorders.as_deref(),
+                                self.feature_set.clone(),
+                                bpf_compute_budget,
+                                &mut compute_units,
+                                &mut return_data,
+                            )
+                        });
+                    transaction_compute_units.push(compute_units);
+                    transaction_return_data.push(return_data);
 
                     if enable_log_recording {
                         let log_messages: TransactionLogMessages =
@@ -3082,12 +3344,34 @@ impl Bank {
             executed,
             inner_instructions,
             transaction_log_messages,
+            transaction_compute_units,
+            transaction_return_data,
             retryable_txs,
             tx_count,
             signature_count,
         )
     }
 
+    /// Apply the compute unit limit requested through the compute budget program, if any
+    fn get_transaction_compute_budget(
+        &self,
+        tx: &Transaction,
+        bpf_compute_budget: BpfComputeBudget,
+    ) -> Result<BpfComputeBudget> {
+        if !self.compute_budget_program_enabled() {
+            return Ok(bpf_compute_budget);
+        }
+        let request = ComputeBudgetRequest::from_message(tx.message())
+            .map_err(|(index, err)| TransactionError::InstructionError(index, err))?;
+        Ok(match request.units {
+            Some(units) => BpfComputeBudget {
+                max_units: u64::from(units),
+                ..bpf_compute_budget
+            },
+            None => bpf_compute_budget,
+        })
+    }
+
     fn filter_program_errors_and_collect_fee(
         &self,
         txs: &[Transaction],
@@ -3099,6 +3383,7 @@ impl Bank {
 
         let fee_config = FeeConfig {
             secp256k1_program_enabled: self.secp256k1_program_enabled(),
+            compute_budget_program_enabled: self.compute_budget_program_enabled(),
         };
 
         let results = OrderedIterator::new(txs, iteration_order)
@@ -3311,6 +3596,7 @@ impl Bank {
                             reward_type: RewardType::Rent,
                             lamports: rent_to_be_paid as i64,
                             post_balance: account.lamports,
+                            commission: None,
                         },
                     ));
                 }
@@ -3785,6 +4071,7 @@ impl Bank {
         TransactionBalancesSet,
         Vec<Option<InnerInstructionsList>>,
         Vec<TransactionLogMessages>,
+        Vec<Option<TransactionReturnData>>,
     ) {
         let pre_balances = if collect_balances {
             self.collect_balances(batch)
@@ -3798,6 +4085,8 @@ impl Bank {
             inner_instructions,
             transaction_logs,
             _,
+            transaction_return_data,
+            _,
             tx_count,
             signature_count,
         ) = self.load_and_execute_transactions(
@@ -3827,6 +4116,7 @@ impl Bank {
             TransactionBalancesSet::new(pre_balances, post_balances),
             inner_instructions,
             transaction_logs,
+            transaction_return_data,
         )
     }
 
@@ -3845,6 +4135,21 @@ impl Bank {
         .fee_collection_results
     }
 
+    #[must_use]
+    pub fn process_versioned_transactions(&self, txs: &[VersionedTransaction]) -> Vec<Result<()>> {
+        let batch = self.prepare_versioned_batch(txs, None);
+        self.load_execute_and_commit_transactions(
+            &batch,
+            MAX_PROCESSING_AGE,
+            false,
+            false,
+            false,
+            &mut ExecuteTimings::default(),
+        )
+        .0
+        .fee_collection_results
+    }
+
     /// Create, sign, and process a Transaction from `keypair` to `to` of
     /// `n` lamports where `blockhash` is the last Entry ID observed by the client.
     pub fn transfer(&self, n: u64, keypair: &Keypair, to: &Pubkey) -> Result<Signature> {
@@ -4020,6 +4325,22 @@ impl Bank {
         self.bpf_compute_budget = bpf_compute_budget;
     }
 
+    /// Estimate the cost of `tx` with the compute budget of this bank
+    pub fn calculate_transaction_cost(&self, tx: &Transaction) -> TransactionCost {
+        let bpf_compute_budget = self
+            .bpf_compute_budget
+            .unwrap_or_else(|| BpfComputeBudget::new(&self.feature_set));
+        CostModel::default().calculate_cost(tx, &bpf_compute_budget)
+    }
+
+    pub fn read_cost_tracker(&self) -> RwLockReadGuard<CostTracker> {
+        self.cost_tracker.read().unwrap()
+    }
+
+    pub fn write_cost_tracker(&self) -> RwLockWriteGuard<CostTracker> {
+        self.cost_tracker.write().unwrap()
+    }
+
     pub fn hard_forks(&self) -> Arc<RwLock<HardForks>> {
         self.hard_forks.clone()
     }
@@ -4065,6 +4386,24 @@ impl Bank {
             .load_by_program_with_filter(&self.ancestors, program_id, filter)
     }
 
+    /// At most `limit` accounts owned by `program_id` that pass `filter`, in pubkey order,
+    /// starting after `after`
+    pub fn get_filtered_program_accounts_from<F: Fn(&Account) -> bool>(
+        &self,
+        program_id: &Pubkey,
+        after: Option<&Pubkey>,
+        limit: usize,
+        filter: F,
+    ) -> Vec<(Pubkey, Account)> {
+        self.rc.accounts.load_by_program_with_filter_from(
+            &self.ancestors,
+            program_id,
+            after,
+            limit,
+            filter,
+        )
+    }
+
     pub fn get_filtered_indexed_accounts<F: Fn(&Account) -> bool>(
         &self,
         index_key: &IndexKey,
@@ -4075,6 +4414,24 @@ impl Bank {
             .load_by_index_key_with_filter(&self.ancestors, index_key, filter)
     }
 
+    /// At most `limit` accounts indexed under `index_key` that pass `filter`, in pubkey order,
+    /// starting after `after`
+    pub fn get_filtered_indexed_accounts_from<F: Fn(&Account) -> bool>(
+        &self,
+        index_key: &IndexKey,
+        after: Option<&Pubkey>,
+        limit: usize,
+        filter: F,
+    ) -> Vec<(Pubkey, Account)> {
+        self.rc.accounts.load_by_index_key_with_filter_from(
+            &self.ancestors,
+            index_key,
+            after,
+            limit,
+            filter,
+        )
+    }
+
     pub fn get_all_accounts_with_modified_slots(&self) -> Vec<(Pubkey, Account, Slot)> {
         self.rc.accounts.load_all(&self.ancestors)
     }
@@ -4088,6 +4445,34 @@ impl Bank {
             .load_by_program_slot(self.slot(), Some(program_id))
     }
 
+    /// Returns the accounts owned by `program_id` as of the parent bank that were closed or
+    /// reassigned to another owner in this bank
+    pub fn get_program_accounts_removed_since_parent(&self, program_id: &Pubkey) -> Vec<Pubkey> {
+        // The latest version of each account stored in this slot
+        let candidates = self.rc.accounts.scan_slot(self.slot(), |stored_account| {
+            let is_candidate =
+                stored_account.lamports() == 0 || stored_account.owner() != program_id;
+            Some((*stored_account.pubkey(), is_candidate))
+        });
+        let parent_ancestors: Ancestors = self
+            .ancestors
+            .iter()
+            .filter(|(slot, _)| **slot != self.slot())
+            .map(|(slot, depth)| (*slot, *depth))
+            .collect();
+        candidates
+            .into_iter()
+            .filter(|(_, is_candidate)| *is_candidate)
+            .map(|(pubkey, _)| pubkey)
+            .filter(|pubkey| {
+                self.rc
+                    .accounts
+                    .load_with_max_root(&parent_ancestors, pubkey, self.parent_slot())
+                    .map_or(false, |(account, _)| account.owner == *program_id)
+            })
+            .collect()
+    }
+
     pub fn get_transaction_logs(
         &self,
         address: Option<&Pubkey>,
@@ -4230,9 +4615,18 @@ impl Bank {
 
     /// Recalculate the hash_internal_state from the account stores. Would be used to verify a
     /// snapshot.
+    #[cfg(test)]
     #[must_use]
     fn verify_bank_hash(&self) -> bool {
+        self.verify_bank_hash_with_index_option(false)
+    }
+
+    /// Like `verify_bank_hash`, but can scan the rooted storages directly instead of the
+    /// accounts index. The bank must be rooted and flushed for the storages to be complete.
+    #[must_use]
+    fn verify_bank_hash_with_index_option(&self, do_not_use_index: bool) -> bool {
         self.rc.accounts.verify_bank_hash_and_lamports(
+            do_not_use_index,
             self.slot(),
             &self.ancestors,
             self.capitalization(),
@@ -4331,7 +4725,7 @@ impl Bank {
             self.shrink_all_slots();
         }
         // Order and short-circuiting is significant; verify_hash requires a valid bank hash
-        self.verify_bank_hash() && self.verify_hash()
+        self.verify_bank_hash_with_index_option(true) && self.verify_hash()
     }
 
     /// Return the number of hashes per tick
@@ -4628,11 +5022,33 @@ impl Bank {
         self.rc.accounts.accounts_db.shrink_candidate_slots()
     }
 
+    /// Packs the storages of the rooted slots more than an epoch older than this bank into a
+    /// few ancient append vecs.
+    pub fn pack_ancient_slots(&self) {
+        let oldest_non_ancient_slot = self
+            .slot()
+            .saturating_sub(self.get_slots_in_epoch(self.epoch()));
+        self.rc
+            .accounts
+            .accounts_db
+            .pack_ancient_slots(oldest_non_ancient_slot);
+    }
+
     pub fn secp256k1_program_enabled(&self) -> bool {
         self.feature_set
             .is_active(&feature_set::secp256k1_program_enabled::id())
     }
 
+    pub fn compute_budget_program_enabled(&self) -> bool {
+        self.feature_set
+            .is_active(&feature_set::compute_budget_program_enabled::id())
+    }
+
+    pub fn versioned_tx_message_enabled(&self) -> bool {
+        self.feature_set
+            .is_active(&feature_set::versioned_tx_message_enabled::id())
+    }
+
     pub fn no_overflow_rent_distribution_enabled(&self) -> bool {
         self.feature_set
             .is_active(&feature_set::no_overflow_rent_distribution::id())
@@ -4804,7 +5220,7 @@ impl Bank {
     }
 
     fn adjust_capitalization_for_existing_specially_retained_accounts(&self) {
-        use solana_sdk::{bpf_loader, bpf_loader_deprecated, secp256k1_program};
+        use solana_sdk::{bpf_loader, bpf_loader_deprecated, compute_budget, secp256k1_program};
         let mut existing_sysvar_account_count = 8;
         let mut existing_native_program_account_count = 4;
 
@@ -4824,6 +5240,17 @@ impl Bank {
             existing_native_program_account_count += 1;
         }
 
+        if self.get_account(&compute_budget::id()).is_some() {
+            existing_native_program_account_count += 1;
+        }
+
+        if self
+            .get_account(&solana_address_lookup_table_program::id())
+            .is_some()
+        {
+            existing_native_program_account_count += 1;
+        }
+
         info!(
             "Adjusted capitalization for existing {} sysvars and {} native programs from {}",
             existing_sysvar_account_count,
@@ -6974,6 +7401,7 @@ pub(crate) mod tests {
                     reward_type: RewardType::Staking,
                     lamports: (rewards.validator_point_value * validator_points as f64) as i64,
                     post_balance: bank1.get_balance(&stake_id),
+                    commission: Some(0),
                 }
             )]
         );
@@ -7171,6 +7599,112 @@ pub(crate) mod tests {
         assert_eq!(bank.transaction_count(), 2);
     }
 
+    #[test]
+    fn test_simulate_transaction() {
+        let (mut genesis_config, mint_keypair) = create_genesis_config(10_000);
+        genesis_config.fee_rate_governor = FeeRateGovernor::new(5, 0);
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let bank = Bank::new(&genesis_config);
+        bank.freeze();
+
+        let tx = system_transaction::transfer(&mint_keypair, &pubkey, 1_000, genesis_config.hash());
+        let TransactionSimulationResult {
+            result,
+            post_simulation_accounts,
+            units_consumed,
+            ..
+        } = bank.simulate_transaction(tx);
+        assert_eq!(result, Ok(()));
+        assert_eq!(post_simulation_accounts.len(), 3);
+        assert_eq!(post_simulation_accounts[0].0, mint_keypair.pubkey());
+        // The fee payer is charged the fee
+        assert_eq!(post_simulation_accounts[0].1.lamports, 9_000 - 5);
+        assert_eq!(post_simulation_accounts[1].0, pubkey);
+        assert_eq!(post_simulation_accounts[1].1.lamports, 1_000);
+        assert_eq!(units_consumed.len(), 1);
+
+        // The simulated state is not committed
+        assert_eq!(bank.get_balance(&pubkey), 0);
+
+        // Transactions that fail to load have no post-simulation accounts
+        let tx = system_transaction::transfer(&Keypair::new(), &pubkey, 1, genesis_config.hash());
+        let TransactionSimulationResult {
+            result,
+            post_simulation_accounts,
+            units_consumed,
+            ..
+        } = bank.simulate_transaction(tx);
+        assert_eq!(result, Err(TransactionError::AccountNotFound));
+        assert!(post_simulation_accounts.is_empty());
+        assert!(units_consumed.is_empty());
+
+        // Compute units stay aligned with the transactions of a batch when one fails to load
+        let txs = vec![
+            system_transaction::transfer(&Keypair::new(), &pubkey, 1, genesis_config.hash()),
+            system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash()),
+        ];
+        let batch = bank.prepare_simulation_batch(&txs);
+        let (_, _, _, _, compute_units, ..) = bank.load_and_execute_transactions(
+            &batch,
+            MAX_PROCESSING_AGE,
+            false,
+            false,
+            &mut ExecuteTimings::default(),
+        );
+        assert_eq!(compute_units.len(), 2);
+        assert!(compute_units[0].is_empty());
+        assert_eq!(compute_units[1].len(), 1);
+    }
+
+    #[test]
+    fn test_transaction_return_data() {
+        let (genesis_config, mint_keypair) = create_genesis_config(10_000);
+        let mut bank = Bank::new(&genesis_config);
+
+        fn mock_return_data_processor(
+            _program_id: &Pubkey,
+            _keyed_accounts: &[KeyedAccount],
+            data: &[u8],
+            invoke_context: &mut dyn InvokeContext,
+        ) -> result::Result<(), InstructionError> {
+            invoke_context.set_return_data(data.to_vec())
+        }
+        let mock_program_id = Pubkey::new(&[2u8; 32]);
+        bank.add_builtin("mock_program", mock_program_id, mock_return_data_processor);
+        bank.freeze();
+
+        let simulate = |data: &[&[u8]]| {
+            let instructions: Vec<_> = data
+                .iter()
+                .map(|data| Instruction {
+                    program_id: mock_program_id,
+                    accounts: vec![],
+                    data: data.to_vec(),
+                })
+                .collect();
+            let message = Message::new(&instructions, Some(&mint_keypair.pubkey()));
+            let tx = Transaction::new(&[&mint_keypair], message, bank.last_blockhash());
+            bank.simulate_transaction(tx).return_data
+        };
+
+        assert_eq!(
+            simulate(&[&[1, 2, 3]]),
+            Some(TransactionReturnData {
+                program_id: mock_program_id,
+                data: vec![1, 2, 3],
+            })
+        );
+        // The return data is that of the last instruction
+        assert_eq!(
+            simulate(&[&[1, 2, 3], &[4]]),
+            Some(TransactionReturnData {
+                program_id: mock_program_id,
+                data: vec![4],
+            })
+        );
+        assert_eq!(simulate(&[&[1, 2, 3], &[]]), None);
+    }
+
     #[test]
     fn test_one_source_two_tx_one_batch() {
         let (genesis_config, mint_keypair) = create_genesis_config(1);
@@ -7467,6 +8001,7 @@ pub(crate) mod tests {
                     reward_type: RewardType::Fee,
                     lamports: expected_fee_collected as i64,
                     post_balance: initial_balance + expected_fee_collected,
+                    commission: None,
                 }
             )]
         );
@@ -7502,6 +8037,7 @@ pub(crate) mod tests {
                     reward_type: RewardType::Fee,
                     lamports: expected_fee_collected as i64,
                     post_balance: initial_balance + 2 * expected_fee_collected,
+                    commission: None,
                 }
             )]
         );
@@ -7604,6 +8140,188 @@ pub(crate) mod tests {
         assert_eq!(results[1], Ok(()));
     }
 
+    #[test]
+    fn test_compute_budget_prioritization_fee() {
+        let GenesisConfigInfo {
+            mut genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config_with_leader(1_000_000, &solana_sdk::pubkey::new_rand(), 3);
+        genesis_config.fee_rate_governor = FeeRateGovernor::new(2, 0);
+        let mut bank = Bank::new(&genesis_config);
+        bank.activate_feature(&feature_set::compute_budget_program_enabled::id());
+        bank.add_builtin(
+            "compute_budget_program",
+            solana_sdk::compute_budget::id(),
+            solana_compute_budget_program::process_instruction,
+        );
+        let lamports_per_signature = bank.fee_calculator.lamports_per_signature;
+
+        // 10 micro-lamports for each of the default 200_000 compute units
+        let key = Keypair::new();
+        let message = Message::new(
+            &[
+                solana_sdk::compute_budget::set_compute_unit_price(10),
+                system_instruction::transfer(&mint_keypair.pubkey(), &key.pubkey(), 1),
+            ],
+            Some(&mint_keypair.pubkey()),
+        );
+        let tx = Transaction::new(&[&mint_keypair], message, bank.last_blockhash());
+        let initial_balance = bank.get_balance(&mint_keypair.pubkey());
+        assert_eq!(bank.process_transaction(&tx), Ok(()));
+        assert_eq!(
+            bank.get_balance(&mint_keypair.pubkey()),
+            initial_balance - 1 - lamports_per_signature - 2
+        );
+
+        // A malformed request fails the transaction, which still pays the signature fee
+        let message = Message::new(
+            &[
+                solana_sdk::compute_budget::set_compute_unit_price(10),
+                solana_sdk::compute_budget::set_compute_unit_price(20),
+                system_instruction::transfer(&mint_keypair.pubkey(), &key.pubkey(), 1),
+            ],
+            Some(&mint_keypair.pubkey()),
+        );
+        let tx = Transaction::new(&[&mint_keypair], message, bank.last_blockhash());
+        let initial_balance = bank.get_balance(&mint_keypair.pubkey());
+        assert_eq!(
+            bank.process_transaction(&tx),
+            Err(TransactionError::InstructionError(
+                1,
+                InstructionError::InvalidInstructionData
+            ))
+        );
+        assert_eq!(
+            bank.get_balance(&mint_keypair.pubkey()),
+            initial_balance - lamports_per_signature
+        );
+    }
+
+    fn create_lookup_table_account(
+        addresses: &[Pubkey],
+        last_extended_slot: Slot,
+        deactivation_slot: Slot,
+    ) -> Account {
+        use solana_address_lookup_table_program::state::{
+            lookup_table_space, LookupTableMeta, ProgramState, LOOKUP_TABLE_META_SIZE,
+        };
+
+        let mut meta = LookupTableMeta::new(Pubkey::new_unique());
+        meta.num_addresses = addresses.len() as u16;
+        meta.last_extended_slot = last_extended_slot;
+        meta.deactivation_slot = deactivation_slot;
+        let mut data = vec![0; lookup_table_space(addresses.len())];
+        AddressLookupTable::serialize_state(&ProgramState::LookupTable(meta), &mut data).unwrap();
+        for (address, chunk) in addresses
+            .iter()
+            .zip(data[LOOKUP_TABLE_META_SIZE..].chunks_mut(32))
+        {
+            chunk.copy_from_slice(address.as_ref());
+        }
+        Account {
+            lamports: 1,
+            data,
+            owner: solana_address_lookup_table_program::id(),
+            ..Account::default()
+        }
+    }
+
+    #[test]
+    fn test_versioned_transaction_address_lookup() {
+        use solana_sdk::{address_lookup_table_account::AddressLookupTableAccount, message::v0};
+
+        let (genesis_config, mint_keypair) = create_genesis_config(1_000_000);
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+        let mut bank = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        let recipient = solana_sdk::pubkey::new_rand();
+        let table_key = solana_sdk::pubkey::new_rand();
+        bank.store_account(
+            &table_key,
+            &create_lookup_table_account(&[recipient], bank.slot(), Slot::MAX),
+        );
+
+        let mut message = v0::Message::new(
+            &[system_instruction::transfer(
+                &mint_keypair.pubkey(),
+                &recipient,
+                1,
+            )],
+            Some(&mint_keypair.pubkey()),
+            &[AddressLookupTableAccount {
+                key: table_key,
+                addresses: vec![recipient],
+            }],
+        );
+        assert!(!message.account_keys.contains(&recipient));
+        message.recent_blockhash = bank.last_blockhash();
+        let address_table_lookups = message.address_table_lookups.clone();
+        let tx =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&mint_keypair]).unwrap();
+        // Round trip through the wire format, which never includes loaded addresses
+        let tx: VersionedTransaction =
+            bincode::deserialize(&bincode::serialize(&tx).unwrap()).unwrap();
+        assert!(tx.verify().is_ok());
+
+        // Versioned messages are rejected until the feature is activated
+        assert_eq!(
+            bank.process_versioned_transactions(std::slice::from_ref(&tx)),
+            vec![Err(TransactionError::UnsupportedVersion)]
+        );
+        bank.activate_feature(&feature_set::versioned_tx_message_enabled::id());
+
+        // Addresses appended in the current slot may not be used yet
+        assert_eq!(
+            bank.process_versioned_transactions(std::slice::from_ref(&tx)),
+            vec![Err(TransactionError::InvalidAddressLookupTableIndex)]
+        );
+
+        // Tables deactivated in the current slot remain usable until the next slot
+        bank.store_account(
+            &table_key,
+            &create_lookup_table_account(&[recipient], 0, bank.slot()),
+        );
+        let batch = bank.prepare_versioned_batch(std::slice::from_ref(&tx), None);
+        assert_eq!(batch.lock_results()[0], Ok(()));
+        assert_eq!(
+            batch.loaded_addresses()[0],
+            Some(LoadedAddresses {
+                writable: vec![recipient],
+                readonly: vec![],
+            })
+        );
+        assert_eq!(batch.transactions()[0].message.account_keys[1], recipient);
+        drop(batch);
+        assert_eq!(
+            bank.process_versioned_transactions(std::slice::from_ref(&tx)),
+            vec![Ok(())]
+        );
+        assert_eq!(bank.get_balance(&recipient), 1);
+
+        bank.store_account(
+            &table_key,
+            &create_lookup_table_account(&[recipient], 0, bank0.slot()),
+        );
+        assert_eq!(
+            bank.load_lookup_table_addresses(&address_table_lookups),
+            Err(TransactionError::AddressLookupTableNotFound)
+        );
+
+        // Lookup tables must be owned by the address lookup table program
+        let mut table_account = create_lookup_table_account(&[recipient], 0, Slot::MAX);
+        table_account.owner = solana_sdk::pubkey::new_rand();
+        bank.store_account(&table_key, &table_account);
+        assert_eq!(
+            bank.load_lookup_table_addresses(&address_table_lookups),
+            Err(TransactionError::InvalidAddressLookupTableOwner)
+        );
+        bank.store_account(&table_key, &Account::default());
+        assert_eq!(
+            bank.load_lookup_table_addresses(&address_table_lookups),
+            Err(TransactionError::AddressLookupTableNotFound)
+        );
+    }
+
     #[test]
     fn test_debits_before_credits() {
         let (genesis_config, mint_keypair) = create_genesis_config(2);
@@ -7978,6 +8696,8 @@ pub(crate) mod tests {
         let bank = Bank::new(&genesis_config);
         bank.transfer(1_000, &mint_keypair, &pubkey).unwrap();
         bank.freeze();
+        bank.squash();
+        bank.force_flush_accounts_cache();
         bank.update_accounts_hash();
         assert!(bank.verify_snapshot_bank());
 
@@ -8815,6 +9535,43 @@ pub(crate) mod tests {
         assert_eq!(bank3.get_program_accounts(&program_id).len(), 2);
     }
 
+    #[test]
+    fn test_bank_get_program_accounts_removed_since_parent() {
+        let (genesis_config, _mint_keypair) = create_genesis_config(500);
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+        let program_id = Pubkey::new_unique();
+        let closed = Pubkey::new_unique();
+        let reassigned = Pubkey::new_unique();
+        let rewritten = Pubkey::new_unique();
+        let reassigned_and_written = Pubkey::new_unique();
+        for pubkey in &[closed, reassigned, rewritten, reassigned_and_written] {
+            bank0.store_account(pubkey, &Account::new(1, 1, &program_id));
+        }
+
+        let bank1 = Arc::new(new_from_parent(&bank0));
+        bank1.store_account(&closed, &Account::new(0, 1, &program_id));
+        bank1.store_account(&reassigned, &Account::new(1, 1, &Pubkey::new_unique()));
+        bank1.store_account(&rewritten, &Account::new(2, 1, &program_id));
+        // The new owner may write to the account within the same slot
+        let mut account = Account::new(1, 1, &Pubkey::new_unique());
+        account.data[0] = 1;
+        bank1.store_account(&reassigned_and_written, &account);
+        let mut removed = bank1.get_program_accounts_removed_since_parent(&program_id);
+        removed.sort();
+        let mut expected = vec![closed, reassigned, reassigned_and_written];
+        expected.sort();
+        assert_eq!(removed, expected);
+
+        // Still reported once the bank has been rooted and no longer has a parent
+        bank1.squash();
+        let mut removed = bank1.get_program_accounts_removed_since_parent(&program_id);
+        removed.sort();
+        assert_eq!(removed, expected);
+        assert!(bank1
+            .get_program_accounts_removed_since_parent(&Pubkey::new_unique())
+            .is_empty());
+    }
+
     #[test]
     fn test_get_filtered_indexed_accounts() {
         let (genesis_config, _mint_keypair) = create_genesis_config(500);
@@ -9699,15 +10456,20 @@ pub(crate) mod tests {
         let txs = vec![tx0, tx1, tx2];
 
         let lock_result = bank0.prepare_batch(&txs, None);
-        let (transaction_results, transaction_balances_set, inner_instructions, transaction_logs) =
-            bank0.load_execute_and_commit_transactions(
-                &lock_result,
-                MAX_PROCESSING_AGE,
-                true,
-                false,
-                false,
-                &mut ExecuteTimings::default(),
-            );
+        let (
+            transaction_results,
+            transaction_balances_set,
+            inner_instructions,
+            transaction_logs,
+            _transaction_return_data,
+        ) = bank0.load_execute_and_commit_transactions(
+            &lock_result,
+            MAX_PROCESSING_AGE,
+            true,
+            false,
+            false,
+            &mut ExecuteTimings::default(),
+        );
 
         assert!(inner_instructions[0].iter().all(|ix| ix.is_empty()));
         assert_eq!(transaction_logs.len(), 0);
@@ -11330,6 +12092,7 @@ pub(crate) mod tests {
             Some(&builtins),
             HashSet::new(),
             false,
+            None,
         ));
         // move to next epoch to create now deprecated rewards sysvar intentionally
         let bank1 = Arc::new(Bank::new_from_parent(
@@ -11344,11 +12107,11 @@ pub(crate) mod tests {
             &feature::create_account(&Feature { activated_at: None }, feature_balance),
         );
 
-        // 16 is maximum adjusted cap increase in adjust_capitalization_for_existing_specially_retained_accounts
+        // 18 is maximum adjusted cap increase in adjust_capitalization_for_existing_specially_retained_accounts
         assert_capitalization_diff_with_new_bank(
             &bank1,
             || Bank::new_from_parent(&bank1, &Pubkey::default(), bank1.first_slot_in_next_epoch()),
-            |old, new| assert_eq!(old + 16, new),
+            |old, new| assert_eq!(old + 18, new),
         );
     }
 
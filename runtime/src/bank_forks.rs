@@ -30,6 +30,9 @@ pub struct SnapshotConfig {
     // Generate a new snapshot every this many slots
     pub snapshot_interval_slots: u64,
 
+    // Generate a new incremental snapshot, on top of the last full snapshot, every this many slots
+    pub incremental_snapshot_interval_slots: u64,
+
     // Where to store the latest packaged snapshot
     pub snapshot_package_output_path: PathBuf,
 
@@ -42,6 +45,13 @@ pub struct SnapshotConfig {
     pub snapshot_version: SnapshotVersion,
 }
 
+impl SnapshotConfig {
+    /// Returns true if incremental snapshots are generated between full snapshots
+    pub fn incremental_snapshots_enabled(&self) -> bool {
+        self.incremental_snapshot_interval_slots < self.snapshot_interval_slots
+    }
+}
+
 pub struct BankForks {
     pub banks: HashMap<Slot, Arc<Bank>>,
     root: Slot,
@@ -0,0 +1,703 @@
+//! A map from pubkeys to slot lists kept in memory-mapped files, which `AccountsIndex` uses to
+//! hold the entries that don't fit in its in-memory capacity.
+//!
+//! Pubkeys are spread over a power-of-two number of buckets by their leading bits, so visiting
+//! the buckets in order visits the pubkeys in order. Each bucket is an open-addressing hash table
+//! of fixed size cells in one file, pointing at serialized slot lists in a second file. A third
+//! file keeps the bucket's pubkeys sorted for range reads, with the pubkeys inserted since it was
+//! last rebuilt held in memory. The files double in size as they fill up and are removed when
+//! the map is dropped.
+
+use crate::accounts_index::{RefCount, SlotList, SlotSlice};
+use log::*;
+use memmap2::MmapMut;
+use serde::{de::DeserializeOwned, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::{BTreeSet, HashMap},
+    convert::TryInto,
+    fs::{create_dir_all, File, OpenOptions},
+    marker::PhantomData,
+    ops::{Bound, RangeBounds},
+    path::Path,
+    sync::RwLock,
+};
+use tempfile::TempDir;
+
+// Layout of an index cell: pubkey, ref count, offset, length and capacity of the serialized slot
+// list in the data file, and the cell state
+const CELL_SIZE: usize = 64;
+const PUBKEY_SIZE: usize = 32;
+const REF_COUNT_OFFSET: usize = 32;
+const DATA_OFFSET_OFFSET: usize = 40;
+const DATA_LEN_OFFSET: usize = 48;
+const DATA_CAPACITY_OFFSET: usize = 52;
+const STATE_OFFSET: usize = 56;
+
+const CELL_EMPTY: u8 = 0;
+const CELL_OCCUPIED: u8 = 1;
+const CELL_REMOVED: u8 = 2;
+
+const INITIAL_NUM_CELLS: u64 = 1024;
+const INITIAL_DATA_SIZE: u64 = 64 * 1024;
+const MIN_DATA_BLOCK_SIZE: u32 = 32;
+// The sorted run is rebuilt once the pubkeys inserted or removed since it was last built exceed
+// an eighth of it, or this many
+const MIN_SORTED_RUN_CHANGES: u64 = 1024;
+
+struct Cell {
+    pubkey: Pubkey,
+    ref_count: RefCount,
+    data_offset: u64,
+    data_len: u32,
+    data_capacity: u32,
+}
+
+#[derive(Debug)]
+struct MmapFile {
+    file: File,
+    map: MmapMut,
+}
+
+impl MmapFile {
+    fn new(path: &Path, size: u64) -> Self {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .unwrap_or_else(|e| {
+                panic!("Unable to create bucket file {}: {:?}", path.display(), e);
+            });
+        let map = Self::map(&file, size);
+        Self { file, map }
+    }
+
+    fn map(file: &File, size: u64) -> MmapMut {
+        file.set_len(size).unwrap_or_else(|e| {
+            panic!("Unable to resize bucket file to {} bytes: {:?}", size, e);
+        });
+        //UNSAFE: Required to create a Mmap
+        let map = unsafe { MmapMut::map_mut(file) };
+        map.unwrap_or_else(|e| {
+            error!(
+                "Failed to map the bucket file (size: {}): {}.\n
+                    Please increase sysctl vm.max_map_count or equivalent for your platform.",
+                size, e
+            );
+            std::process::exit(1);
+        })
+    }
+
+    fn len(&self) -> u64 {
+        self.map.len() as u64
+    }
+
+    // Extends the file to `size` bytes, keeping its contents
+    fn grow(&mut self, size: u64) {
+        self.map = Self::map(&self.file, size);
+    }
+
+    // Replaces the contents of the file with `size` zeroed bytes
+    fn reset(&mut self, size: u64) {
+        self.file.set_len(0).unwrap_or_else(|e| {
+            panic!("Unable to truncate bucket file: {:?}", e);
+        });
+        self.map = Self::map(&self.file, size);
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    index: MmapFile,
+    data: MmapFile,
+    num_occupied: u64,
+    num_removed: u64,
+    // End of the last block handed out in the data file
+    data_end: u64,
+    // Freed blocks of the data file by size, reused before the data file grows
+    free_blocks: HashMap<u32, Vec<u64>>,
+    // The pubkeys of the bucket in order as of when the run was last built, including pubkeys
+    // removed since
+    sorted_run: MmapFile,
+    sorted_run_len: u64,
+    // Pubkeys of the sorted run that were removed since it was built
+    num_stale: u64,
+    // Pubkeys inserted since the sorted run was built
+    recent: BTreeSet<Pubkey>,
+}
+
+impl Bucket {
+    fn new(dir: &Path, bucket_ix: usize) -> Self {
+        Self {
+            index: MmapFile::new(
+                &dir.join(format!("{}.index", bucket_ix)),
+                INITIAL_NUM_CELLS * CELL_SIZE as u64,
+            ),
+            data: MmapFile::new(&dir.join(format!("{}.data", bucket_ix)), INITIAL_DATA_SIZE),
+            num_occupied: 0,
+            num_removed: 0,
+            data_end: 0,
+            free_blocks: HashMap::new(),
+            sorted_run: MmapFile::new(
+                &dir.join(format!("{}.sorted", bucket_ix)),
+                INITIAL_NUM_CELLS * PUBKEY_SIZE as u64,
+            ),
+            sorted_run_len: 0,
+            num_stale: 0,
+            recent: BTreeSet::new(),
+        }
+    }
+
+    fn num_cells(&self) -> u64 {
+        self.index.len() / CELL_SIZE as u64
+    }
+
+    fn cell(&self, ix: u64) -> &[u8] {
+        let start = ix as usize * CELL_SIZE;
+        &self.index.map[start..start + CELL_SIZE]
+    }
+
+    fn state(&self, ix: u64) -> u8 {
+        self.cell(ix)[STATE_OFFSET]
+    }
+
+    fn pubkey(&self, ix: u64) -> Pubkey {
+        Pubkey::new(&self.cell(ix)[..REF_COUNT_OFFSET])
+    }
+
+    fn read_cell(&self, ix: u64) -> Cell {
+        let cell = self.cell(ix);
+        Cell {
+            pubkey: Pubkey::new(&cell[..REF_COUNT_OFFSET]),
+            ref_count: u64::from_le_bytes(
+                cell[REF_COUNT_OFFSET..DATA_OFFSET_OFFSET]
+                    .try_into()
+                    .unwrap(),
+            ),
+            data_offset: u64::from_le_bytes(
+                cell[DATA_OFFSET_OFFSET..DATA_LEN_OFFSET]
+                    .try_into()
+                    .unwrap(),
+            ),
+            data_len: u32::from_le_bytes(
+                cell[DATA_LEN_OFFSET..DATA_CAPACITY_OFFSET]
+                    .try_into()
+                    .unwrap(),
+            ),
+            data_capacity: u32::from_le_bytes(
+                cell[DATA_CAPACITY_OFFSET..STATE_OFFSET].try_into().unwrap(),
+            ),
+        }
+    }
+
+    fn write_cell(&mut self, ix: u64, state: u8, cell: &Cell) {
+        let start = ix as usize * CELL_SIZE;
+        let bytes = &mut self.index.map[start..start + CELL_SIZE];
+        bytes[..REF_COUNT_OFFSET].copy_from_slice(cell.pubkey.as_ref());
+        bytes[REF_COUNT_OFFSET..DATA_OFFSET_OFFSET].copy_from_slice(&cell.ref_count.to_le_bytes());
+        bytes[DATA_OFFSET_OFFSET..DATA_LEN_OFFSET].copy_from_slice(&cell.data_offset.to_le_bytes());
+        bytes[DATA_LEN_OFFSET..DATA_CAPACITY_OFFSET].copy_from_slice(&cell.data_len.to_le_bytes());
+        bytes[DATA_CAPACITY_OFFSET..STATE_OFFSET]
+            .copy_from_slice(&cell.data_capacity.to_le_bytes());
+        bytes[STATE_OFFSET] = state;
+    }
+
+    // Buckets are picked by the leading bits of the pubkey, so probe by the following ones
+    fn first_probe(&self, pubkey: &Pubkey) -> u64 {
+        u64::from_le_bytes(pubkey.as_ref()[8..16].try_into().unwrap()) & (self.num_cells() - 1)
+    }
+
+    // The cell holding `pubkey`, or else the first free cell of its probe sequence
+    fn find(&self, pubkey: &Pubkey) -> Result<u64, u64> {
+        let num_cells = self.num_cells();
+        let mut ix = self.first_probe(pubkey);
+        let mut first_removed = None;
+        // The index is kept at most 3/4 full, so the probe always ends at an empty cell
+        loop {
+            match self.state(ix) {
+                CELL_EMPTY => return Err(first_removed.unwrap_or(ix)),
+                CELL_REMOVED => {
+                    first_removed.get_or_insert(ix);
+                }
+                _ => {
+                    if self.cell(ix)[..REF_COUNT_OFFSET] == pubkey.as_ref()[..] {
+                        return Ok(ix);
+                    }
+                }
+            }
+            ix = (ix + 1) & (num_cells - 1);
+        }
+    }
+
+    fn data(&self, cell: &Cell) -> &[u8] {
+        let start = cell.data_offset as usize;
+        &self.data.map[start..start + cell.data_len as usize]
+    }
+
+    fn get(&self, pubkey: &Pubkey) -> Option<(&[u8], RefCount)> {
+        let cell = self.read_cell(self.find(pubkey).ok()?);
+        Some((self.data(&cell), cell.ref_count))
+    }
+
+    fn insert(&mut self, pubkey: &Pubkey, data: &[u8], ref_count: RefCount) {
+        let ix = match self.find(pubkey) {
+            Ok(ix) => {
+                let cell = self.read_cell(ix);
+                self.free(cell.data_offset, cell.data_capacity);
+                ix
+            }
+            Err(_) => {
+                if (self.num_occupied + self.num_removed + 1) * 4 > self.num_cells() * 3 {
+                    self.rehash();
+                }
+                let ix = self.find(pubkey).unwrap_err();
+                if self.state(ix) == CELL_REMOVED {
+                    self.num_removed -= 1;
+                }
+                self.num_occupied += 1;
+                if self.sorted_run_contains(pubkey) {
+                    self.num_stale -= 1;
+                } else {
+                    self.recent.insert(*pubkey);
+                }
+                ix
+            }
+        };
+        let (data_offset, data_capacity) = self.allocate(data.len());
+        let start = data_offset as usize;
+        self.data.map[start..start + data.len()].copy_from_slice(data);
+        let cell = Cell {
+            pubkey: *pubkey,
+            ref_count,
+            data_offset,
+            data_len: data.len() as u32,
+            data_capacity,
+        };
+        self.write_cell(ix, CELL_OCCUPIED, &cell);
+        self.maybe_rebuild_sorted_run();
+    }
+
+    fn remove(&mut self, pubkey: &Pubkey) -> Option<(Vec<u8>, RefCount)> {
+        let ix = self.find(pubkey).ok()?;
+        let cell = self.read_cell(ix);
+        let data = self.data(&cell).to_vec();
+        self.free(cell.data_offset, cell.data_capacity);
+        self.index.map[ix as usize * CELL_SIZE + STATE_OFFSET] = CELL_REMOVED;
+        self.num_occupied -= 1;
+        self.num_removed += 1;
+        if !self.recent.remove(pubkey) {
+            self.num_stale += 1;
+        }
+        self.maybe_rebuild_sorted_run();
+        Some((data, cell.ref_count))
+    }
+
+    fn allocate(&mut self, len: usize) -> (u64, u32) {
+        let capacity = (len as u32).next_power_of_two().max(MIN_DATA_BLOCK_SIZE);
+        if let Some(offset) = self.free_blocks.get_mut(&capacity).and_then(Vec::pop) {
+            return (offset, capacity);
+        }
+        let offset = self.data_end;
+        self.data_end += capacity as u64;
+        if self.data_end > self.data.len() {
+            let mut size = self.data.len() * 2;
+            while size < self.data_end {
+                size *= 2;
+            }
+            self.data.grow(size);
+        }
+        (offset, capacity)
+    }
+
+    fn free(&mut self, offset: u64, capacity: u32) {
+        self.free_blocks.entry(capacity).or_default().push(offset);
+    }
+
+    // Rebuilds the index without removed cells, doubling it unless that leaves it half empty
+    fn rehash(&mut self) {
+        let cells: Vec<_> = (0..self.num_cells())
+            .filter(|ix| self.state(*ix) == CELL_OCCUPIED)
+            .map(|ix| self.read_cell(ix))
+            .collect();
+        let num_cells = if (self.num_occupied + 1) * 2 > self.num_cells() {
+            self.num_cells() * 2
+        } else {
+            self.num_cells()
+        };
+        self.index.reset(num_cells * CELL_SIZE as u64);
+        self.num_removed = 0;
+        for cell in cells {
+            let ix = self.find(&cell.pubkey).unwrap_err();
+            self.write_cell(ix, CELL_OCCUPIED, &cell);
+        }
+    }
+
+    fn pubkeys(&self) -> impl Iterator<Item = Pubkey> + '_ {
+        (0..self.num_cells())
+            .filter(move |ix| self.state(*ix) == CELL_OCCUPIED)
+            .map(move |ix| self.pubkey(ix))
+    }
+
+    fn sorted_run_pubkey(&self, ix: u64) -> Pubkey {
+        let start = ix as usize * PUBKEY_SIZE;
+        Pubkey::new(&self.sorted_run.map[start..start + PUBKEY_SIZE])
+    }
+
+    // Position of the first pubkey of the sorted run that is not before `bound`
+    fn sorted_run_start(&self, bound: Bound<&Pubkey>) -> u64 {
+        let (mut low, mut high) = (0, self.sorted_run_len);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let pubkey = self.sorted_run_pubkey(mid);
+            let is_before = match bound {
+                Bound::Included(start) => pubkey < *start,
+                Bound::Excluded(start) => pubkey <= *start,
+                Bound::Unbounded => false,
+            };
+            if is_before {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    fn sorted_run_contains(&self, pubkey: &Pubkey) -> bool {
+        let ix = self.sorted_run_start(Bound::Included(pubkey));
+        ix < self.sorted_run_len && self.sorted_run_pubkey(ix) == *pubkey
+    }
+
+    /// The first `limit` pubkeys of the bucket within `range`, in order
+    fn range<R>(&self, range: &R, limit: usize) -> Vec<Pubkey>
+    where
+        R: RangeBounds<Pubkey>,
+    {
+        let mut sorted_run = (self.sorted_run_start(range.start_bound())..self.sorted_run_len)
+            .map(|ix| self.sorted_run_pubkey(ix))
+            .take_while(|pubkey| range.contains(pubkey))
+            .filter(|pubkey| self.find(pubkey).is_ok())
+            .peekable();
+        let mut recent = self
+            .recent
+            .range::<Pubkey, _>((range.start_bound(), range.end_bound()))
+            .peekable();
+        // The sorted run and the recent pubkeys never share a pubkey
+        let mut pubkeys = vec![];
+        while pubkeys.len() < limit {
+            let pubkey = match (sorted_run.peek(), recent.peek()) {
+                (Some(run_pubkey), Some(recent_pubkey)) if run_pubkey < *recent_pubkey => {
+                    sorted_run.next()
+                }
+                (_, Some(_)) => recent.next().cloned(),
+                (Some(_), None) => sorted_run.next(),
+                (None, None) => break,
+            };
+            pubkeys.extend(pubkey);
+        }
+        pubkeys
+    }
+
+    fn maybe_rebuild_sorted_run(&mut self) {
+        let num_changes = self.recent.len() as u64 + self.num_stale;
+        if num_changes <= (self.sorted_run_len / 8).max(MIN_SORTED_RUN_CHANGES) {
+            return;
+        }
+        let pubkeys = self.range(&(..), usize::MAX);
+        let size = (pubkeys.len() * PUBKEY_SIZE) as u64;
+        if size > self.sorted_run.len() {
+            self.sorted_run.reset(size.next_power_of_two());
+        }
+        for (ix, pubkey) in pubkeys.iter().enumerate() {
+            let start = ix * PUBKEY_SIZE;
+            self.sorted_run.map[start..start + PUBKEY_SIZE].copy_from_slice(pubkey.as_ref());
+        }
+        self.sorted_run_len = pubkeys.len() as u64;
+        self.num_stale = 0;
+        self.recent.clear();
+    }
+}
+
+#[derive(Debug)]
+pub struct BucketMap<T> {
+    buckets: Vec<RwLock<Bucket>>,
+    // Number of leading pubkey bits that select the bucket
+    bucket_bits: u32,
+    _dir: TempDir,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Serialize + DeserializeOwned> BucketMap<T> {
+    /// Creates an empty map of `num_buckets`, rounded up to a power of two, in a new directory
+    /// under `path`
+    pub fn new(path: &Path, num_buckets: usize) -> Self {
+        let num_buckets = num_buckets.max(1).next_power_of_two();
+        create_dir_all(path).expect("Create directory failed");
+        let dir = tempfile::Builder::new()
+            .prefix("accounts_index.")
+            .tempdir_in(path)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "Unable to create accounts index directory in {}: {:?}",
+                    path.display(),
+                    e
+                );
+            });
+        let buckets = (0..num_buckets)
+            .map(|bucket_ix| RwLock::new(Bucket::new(dir.path(), bucket_ix)))
+            .collect();
+        Self {
+            buckets,
+            bucket_bits: num_buckets.trailing_zeros(),
+            _dir: dir,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn bucket_ix(&self, pubkey: &Pubkey) -> usize {
+        if self.bucket_bits == 0 {
+            0
+        } else {
+            let prefix = u64::from_be_bytes(pubkey.as_ref()[..8].try_into().unwrap());
+            (prefix >> (64 - self.bucket_bits)) as usize
+        }
+    }
+
+    fn bucket(&self, pubkey: &Pubkey) -> &RwLock<Bucket> {
+        &self.buckets[self.bucket_ix(pubkey)]
+    }
+
+    fn deserialize(data: &[u8]) -> SlotList<T> {
+        bincode::deserialize(data).expect("slot list in the accounts index is corrupted")
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets
+            .iter()
+            .map(|bucket| bucket.read().unwrap().num_occupied as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains_key(&self, pubkey: &Pubkey) -> bool {
+        self.bucket(pubkey).read().unwrap().find(pubkey).is_ok()
+    }
+
+    pub fn get(&self, pubkey: &Pubkey) -> Option<(SlotList<T>, RefCount)> {
+        let bucket = self.bucket(pubkey).read().unwrap();
+        let (data, ref_count) = bucket.get(pubkey)?;
+        Some((Self::deserialize(data), ref_count))
+    }
+
+    pub fn insert(&self, pubkey: &Pubkey, slot_list: SlotSlice<T>, ref_count: RefCount) {
+        let data = bincode::serialize(slot_list).unwrap();
+        self.bucket(pubkey)
+            .write()
+            .unwrap()
+            .insert(pubkey, &data, ref_count);
+    }
+
+    pub fn remove(&self, pubkey: &Pubkey) -> Option<(SlotList<T>, RefCount)> {
+        let (data, ref_count) = self.bucket(pubkey).write().unwrap().remove(pubkey)?;
+        Some((Self::deserialize(&data), ref_count))
+    }
+
+    /// All the pubkeys in the map, in no particular order
+    pub fn pubkeys(&self) -> Vec<Pubkey> {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| bucket.read().unwrap().pubkeys().collect::<Vec<_>>())
+            .collect()
+    }
+
+    /// The first `limit` entries within `range`, in pubkey order
+    pub fn range_batch<R>(&self, range: &R, limit: usize) -> Vec<(Pubkey, SlotList<T>, RefCount)>
+    where
+        R: RangeBounds<Pubkey>,
+    {
+        let first_bucket = match range.start_bound() {
+            Bound::Included(pubkey) | Bound::Excluded(pubkey) => self.bucket_ix(pubkey),
+            Bound::Unbounded => 0,
+        };
+        let last_bucket = match range.end_bound() {
+            Bound::Included(pubkey) | Bound::Excluded(pubkey) => self.bucket_ix(pubkey),
+            Bound::Unbounded => self.buckets.len() - 1,
+        };
+        let mut batch = vec![];
+        if first_bucket > last_bucket {
+            return batch;
+        }
+        for bucket in &self.buckets[first_bucket..=last_bucket] {
+            if batch.len() >= limit {
+                break;
+            }
+            let bucket = bucket.read().unwrap();
+            let pubkeys = bucket.range(range, limit - batch.len());
+            batch.extend(pubkeys.into_iter().map(|pubkey| {
+                let (data, ref_count) = bucket.get(&pubkey).unwrap();
+                (pubkey, Self::deserialize(data), ref_count)
+            }));
+        }
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::BTreeMap, ops::Bound::*};
+
+    #[test]
+    fn test_bucket_map_insert_get_remove() {
+        let dir = TempDir::new().unwrap();
+        let map = BucketMap::<u64>::new(dir.path(), 4);
+        let pubkey = solana_sdk::pubkey::new_rand();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&pubkey), None);
+
+        map.insert(&pubkey, &[(1, 10)], 1);
+        assert_eq!(map.get(&pubkey), Some((vec![(1, 10)], 1)));
+        assert!(map.contains_key(&pubkey));
+        assert_eq!(map.len(), 1);
+
+        // Growing the slot list moves it to a bigger block
+        let slot_list: SlotList<u64> = (0..100).map(|slot| (slot, slot * 2)).collect();
+        map.insert(&pubkey, &slot_list, 3);
+        assert_eq!(map.get(&pubkey), Some((slot_list.clone(), 3)));
+        assert_eq!(map.len(), 1);
+
+        assert_eq!(map.remove(&pubkey), Some((slot_list, 3)));
+        assert_eq!(map.remove(&pubkey), None);
+        assert!(!map.contains_key(&pubkey));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_bucket_map_grows() {
+        let dir = TempDir::new().unwrap();
+        let map = BucketMap::<u64>::new(dir.path(), 1);
+        let pubkeys: Vec<_> = (0..INITIAL_NUM_CELLS * 4)
+            .map(|_| solana_sdk::pubkey::new_rand())
+            .collect();
+        for (i, pubkey) in pubkeys.iter().enumerate() {
+            map.insert(pubkey, &vec![(i as u64, i as u64); i % 50], i as u64);
+        }
+        // Removed cells and freed blocks are reused
+        for pubkey in pubkeys.iter().step_by(2) {
+            assert!(map.remove(pubkey).is_some());
+        }
+        for (i, pubkey) in pubkeys.iter().enumerate().step_by(2) {
+            map.insert(pubkey, &vec![(i as u64, i as u64); i % 50], i as u64);
+        }
+        assert_eq!(map.len(), pubkeys.len());
+        for (i, pubkey) in pubkeys.iter().enumerate() {
+            assert_eq!(
+                map.get(pubkey),
+                Some((vec![(i as u64, i as u64); i % 50], i as u64))
+            );
+        }
+    }
+
+    #[test]
+    fn test_bucket_map_range_batch() {
+        let dir = TempDir::new().unwrap();
+        let map = BucketMap::<u64>::new(dir.path(), 16);
+        let expected: BTreeMap<_, _> = (0..1000)
+            .map(|i| (solana_sdk::pubkey::new_rand(), i))
+            .collect();
+        for (pubkey, i) in &expected {
+            map.insert(pubkey, &[(*i, *i)], 1);
+        }
+        let pubkeys: Vec<_> = expected.keys().cloned().collect();
+        let mut pubkeys_sorted = map.pubkeys();
+        pubkeys_sorted.sort();
+        assert_eq!(pubkeys_sorted, pubkeys);
+
+        let ranges = vec![
+            (Unbounded, Unbounded),
+            (Included(pubkeys[100]), Excluded(pubkeys[900])),
+            (Excluded(pubkeys[100]), Included(pubkeys[900])),
+            (Included(pubkeys[500]), Unbounded),
+            (Unbounded, Excluded(pubkeys[10])),
+            (Included(pubkeys[10]), Excluded(pubkeys[10])),
+        ];
+        for range in ranges {
+            for limit in &[0, 1, 37, 2000] {
+                let batch = map.range_batch(&range, *limit);
+                let expected_batch: Vec<_> = expected
+                    .range(range)
+                    .take(*limit)
+                    .map(|(pubkey, i)| (*pubkey, vec![(*i, *i)], 1))
+                    .collect();
+                assert_eq!(batch, expected_batch);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bucket_map_range_batch_sorted_run() {
+        let dir = TempDir::new().unwrap();
+        let map = BucketMap::<u64>::new(dir.path(), 1);
+        let mut expected = BTreeMap::new();
+        let check = |expected: &BTreeMap<Pubkey, u64>| {
+            let pubkeys: Vec<_> = expected.keys().cloned().collect();
+            for range in &[
+                (Unbounded, Unbounded),
+                (Excluded(pubkeys[10]), Included(pubkeys[pubkeys.len() - 10])),
+            ] {
+                let batch = map.range_batch(range, 500);
+                let expected_batch: Vec<_> = expected
+                    .range(*range)
+                    .take(500)
+                    .map(|(pubkey, i)| (*pubkey, vec![(*i, *i)], 1))
+                    .collect();
+                assert_eq!(batch, expected_batch);
+            }
+        };
+
+        // Inserting enough pubkeys builds the sorted run, newer ones are merged with it
+        for i in 0..MIN_SORTED_RUN_CHANGES * 3 {
+            let pubkey = solana_sdk::pubkey::new_rand();
+            map.insert(&pubkey, &[(i, i)], 1);
+            expected.insert(pubkey, i);
+        }
+        assert!(map.buckets[0].read().unwrap().sorted_run_len > 0);
+        check(&expected);
+
+        // Removed pubkeys stay out of ranges, including once they are inserted again
+        let pubkeys: Vec<_> = expected.keys().cloned().collect();
+        for pubkey in pubkeys.iter().step_by(3) {
+            map.remove(pubkey);
+            expected.remove(pubkey);
+        }
+        check(&expected);
+        for pubkey in pubkeys.iter().step_by(6) {
+            map.insert(pubkey, &[(7, 7)], 1);
+            expected.insert(*pubkey, 7);
+        }
+        check(&expected);
+        for i in 0..MIN_SORTED_RUN_CHANGES * 2 {
+            let pubkey = solana_sdk::pubkey::new_rand();
+            map.insert(&pubkey, &[(i, i)], 1);
+            expected.insert(pubkey, i);
+        }
+        check(&expected);
+        let bucket = map.buckets[0].read().unwrap();
+        assert!(bucket.sorted_run_len >= bucket.num_occupied - bucket.recent.len() as u64);
+    }
+
+    #[test]
+    fn test_bucket_map_removes_files_on_drop() {
+        let dir = TempDir::new().unwrap();
+        let map = BucketMap::<u64>::new(dir.path(), 2);
+        map.insert(&solana_sdk::pubkey::new_rand(), &[(0, 0)], 1);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        drop(map);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,240 @@
+//! The `cost_model` module estimates, before execution, how many compute units
+//! a transaction will consume.  Leaders use the estimates to bound the work
+//! packed into a block and into any single writable account, see
+//! `CostTracker`.
+
+use lazy_static::lazy_static;
+use solana_sdk::{
+    bpf_loader, bpf_loader_deprecated, bpf_loader_upgradeable,
+    compute_budget::{self, ComputeBudgetRequest},
+    process_instruction::BpfComputeBudget,
+    pubkey::Pubkey,
+    secp256k1_program, system_program,
+    transaction::Transaction,
+};
+use std::collections::HashMap;
+
+/// Number of compute units that can be processed in one microsecond
+pub const COMPUTE_UNIT_TO_US_RATIO: u64 = 30;
+
+/// Cost of verifying one signature
+pub const SIGNATURE_COST: u64 = COMPUTE_UNIT_TO_US_RATIO * 24;
+
+/// Cost of taking one write lock
+pub const WRITE_LOCK_UNITS: u64 = COMPUTE_UNIT_TO_US_RATIO * 10;
+
+/// Number of instruction data bytes that cost one compute unit
+pub const INSTRUCTION_DATA_BYTES_PER_UNIT: u64 = 140 / COMPUTE_UNIT_TO_US_RATIO;
+
+/// Time budget for replaying a block, in microseconds
+pub const MAX_BLOCK_REPLAY_TIME_US: u64 = 400_000;
+
+/// Number of threads replaying a block in parallel
+pub const MAX_CONCURRENCY: u64 = 4;
+
+/// Most compute units a block may consume
+pub const MAX_BLOCK_UNITS: u64 =
+    MAX_BLOCK_REPLAY_TIME_US * COMPUTE_UNIT_TO_US_RATIO * MAX_CONCURRENCY;
+
+/// Most compute units the transactions writing to one account may consume in
+/// a block, since they have to be replayed serially
+pub const MAX_WRITABLE_ACCOUNT_UNITS: u64 = MAX_BLOCK_REPLAY_TIME_US * COMPUTE_UNIT_TO_US_RATIO;
+
+lazy_static! {
+    /// Fixed execution cost of each instruction of the builtin programs, which
+    /// are not metered like BPF programs
+    pub static ref BUILTIN_INSTRUCTION_COSTS: HashMap<Pubkey, u64> = [
+        (system_program::id(), 150),
+        (compute_budget::id(), 150),
+        (solana_config_program::id(), 450),
+        (solana_stake_program::id(), 750),
+        (solana_vote_program::id(), 2_100),
+        (secp256k1_program::id(), 720),
+        (solana_address_lookup_table_program::id(), 750),
+        (bpf_loader_deprecated::id(), 1_140),
+        (bpf_loader::id(), 570),
+        (bpf_loader_upgradeable::id(), 2_370),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+}
+
+/// Estimated cost of a transaction, in compute units
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TransactionCost {
+    /// Accounts the transaction write locks, each of which is charged the
+    /// full cost of the transaction
+    pub writable_accounts: Vec<Pubkey>,
+    pub signature_cost: u64,
+    pub write_lock_cost: u64,
+    pub data_bytes_cost: u64,
+    pub execution_cost: u64,
+}
+
+impl TransactionCost {
+    pub fn sum(&self) -> u64 {
+        self.signature_cost
+            .saturating_add(self.write_lock_cost)
+            .saturating_add(self.data_bytes_cost)
+            .saturating_add(self.execution_cost)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostModel {
+    /// Most compute units that transactions writing to one account may consume
+    pub account_cost_limit: u64,
+    /// Most compute units that all transactions of a block may consume
+    pub block_cost_limit: u64,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self::new(MAX_WRITABLE_ACCOUNT_UNITS, MAX_BLOCK_UNITS)
+    }
+}
+
+impl CostModel {
+    pub fn new(account_cost_limit: u64, block_cost_limit: u64) -> Self {
+        Self {
+            account_cost_limit,
+            block_cost_limit,
+        }
+    }
+
+    /// Estimate the cost of `transaction`.  Instructions of builtin programs
+    /// have a fixed cost, while any BPF instruction charges the transaction
+    /// the compute unit limit it executes with.
+    pub fn calculate_cost(
+        &self,
+        transaction: &Transaction,
+        bpf_compute_budget: &BpfComputeBudget,
+    ) -> TransactionCost {
+        let message = transaction.message();
+        let (writable_keys, _readonly_keys) = message.get_account_keys_by_lock_type();
+        let writable_accounts: Vec<Pubkey> = writable_keys.into_iter().cloned().collect();
+
+        let mut data_bytes_len = 0u64;
+        let mut builtin_cost = 0u64;
+        let mut has_bpf_instruction = false;
+        for instruction in &message.instructions {
+            data_bytes_len = data_bytes_len.saturating_add(instruction.data.len() as u64);
+            let program_id = message
+                .account_keys
+                .get(instruction.program_id_index as usize);
+            match program_id.and_then(|program_id| BUILTIN_INSTRUCTION_COSTS.get(program_id)) {
+                Some(cost) => builtin_cost = builtin_cost.saturating_add(*cost),
+                None => has_bpf_instruction = true,
+            }
+        }
+
+        let bpf_cost = if has_bpf_instruction {
+            // Malformed requests fail the transaction before any BPF executes
+            ComputeBudgetRequest::from_message(message)
+                .ok()
+                .and_then(|request| request.units)
+                .map(u64::from)
+                .unwrap_or(bpf_compute_budget.max_units)
+        } else {
+            0
+        };
+
+        TransactionCost {
+            signature_cost: SIGNATURE_COST
+                .saturating_mul(u64::from(message.header.num_required_signatures)),
+            write_lock_cost: WRITE_LOCK_UNITS.saturating_mul(writable_accounts.len() as u64),
+            data_bytes_cost: data_bytes_len / INSTRUCTION_DATA_BYTES_PER_UNIT,
+            execution_cost: builtin_cost.saturating_add(bpf_cost),
+            writable_accounts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        message::Message,
+        signature::{Keypair, Signer},
+        system_instruction, system_transaction,
+    };
+
+    #[test]
+    fn test_cost_model_transfer() {
+        let from = Keypair::new();
+        let to = Pubkey::new_unique();
+        let tx = system_transaction::transfer(&from, &to, 1, Hash::default());
+        let bpf_compute_budget = BpfComputeBudget::default();
+
+        let cost = CostModel::default().calculate_cost(&tx, &bpf_compute_budget);
+        assert_eq!(cost.writable_accounts, vec![from.pubkey(), to]);
+        assert_eq!(cost.signature_cost, SIGNATURE_COST);
+        assert_eq!(cost.write_lock_cost, 2 * WRITE_LOCK_UNITS);
+        assert_eq!(
+            cost.data_bytes_cost,
+            tx.message.instructions[0].data.len() as u64 / INSTRUCTION_DATA_BYTES_PER_UNIT
+        );
+        assert_eq!(
+            cost.execution_cost,
+            BUILTIN_INSTRUCTION_COSTS[&system_program::id()]
+        );
+        assert_eq!(
+            cost.sum(),
+            cost.signature_cost + cost.write_lock_cost + cost.data_bytes_cost + cost.execution_cost
+        );
+    }
+
+    #[test]
+    fn test_cost_model_bpf_instruction() {
+        let payer = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let bpf_instruction = Instruction::new(
+            program_id,
+            &[0u8; 32],
+            vec![AccountMeta::new_readonly(account, false)],
+        );
+        let bpf_compute_budget = BpfComputeBudget::default();
+
+        let message = Message::new(&[bpf_instruction.clone()], Some(&payer.pubkey()));
+        let tx = Transaction::new(&[&payer], message, Hash::default());
+        let cost = CostModel::default().calculate_cost(&tx, &bpf_compute_budget);
+        assert_eq!(cost.writable_accounts, vec![payer.pubkey()]);
+        assert_eq!(cost.execution_cost, bpf_compute_budget.max_units);
+
+        // Requested compute unit limits replace the default
+        let message = Message::new(
+            &[compute_budget::request_units(1_000), bpf_instruction],
+            Some(&payer.pubkey()),
+        );
+        let tx = Transaction::new(&[&payer], message, Hash::default());
+        let cost = CostModel::default().calculate_cost(&tx, &bpf_compute_budget);
+        assert_eq!(
+            cost.execution_cost,
+            BUILTIN_INSTRUCTION_COSTS[&compute_budget::id()] + 1_000
+        );
+    }
+
+    #[test]
+    fn test_cost_model_multiple_signatures() {
+        let from = Keypair::new();
+        let nonce = Keypair::new();
+        let instructions = system_instruction::create_nonce_account(
+            &from.pubkey(),
+            &nonce.pubkey(),
+            &from.pubkey(),
+            1,
+        );
+        let message = Message::new(&instructions, Some(&from.pubkey()));
+        let tx = Transaction::new(&[&from, &nonce], message, Hash::default());
+        let cost = CostModel::default().calculate_cost(&tx, &BpfComputeBudget::default());
+        assert_eq!(cost.signature_cost, 2 * SIGNATURE_COST);
+        assert_eq!(
+            cost.execution_cost,
+            2 * BUILTIN_INSTRUCTION_COSTS[&system_program::id()]
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! The `cost_tracker` module accumulates the estimated cost of the
+//! transactions packed into a block, rejecting transactions that would push
+//! the block, or any account it writes, over the limits of the `CostModel`.
+
+use crate::cost_model::{CostModel, TransactionCost};
+use solana_sdk::{clock::Slot, pubkey::Pubkey, transaction::TransactionError};
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CostTrackerError {
+    #[error("would exceed block max limit")]
+    WouldExceedBlockMaxLimit,
+
+    #[error("would exceed account max limit")]
+    WouldExceedAccountMaxLimit,
+}
+
+impl From<CostTrackerError> for TransactionError {
+    fn from(err: CostTrackerError) -> Self {
+        match err {
+            CostTrackerError::WouldExceedBlockMaxLimit => {
+                TransactionError::WouldExceedMaxBlockCostLimit
+            }
+            CostTrackerError::WouldExceedAccountMaxLimit => {
+                TransactionError::WouldExceedMaxAccountCostLimit
+            }
+        }
+    }
+}
+
+#[derive(AbiExample, Debug)]
+pub struct CostTracker {
+    account_cost_limit: u64,
+    block_cost_limit: u64,
+    cost_by_writable_accounts: HashMap<Pubkey, u64>,
+    block_cost: u64,
+    transaction_count: u64,
+}
+
+impl Default for CostTracker {
+    fn default() -> Self {
+        Self::new(&CostModel::default())
+    }
+}
+
+impl CostTracker {
+    pub fn new(cost_model: &CostModel) -> Self {
+        Self {
+            account_cost_limit: cost_model.account_cost_limit,
+            block_cost_limit: cost_model.block_cost_limit,
+            cost_by_writable_accounts: HashMap::new(),
+            block_cost: 0,
+            transaction_count: 0,
+        }
+    }
+
+    /// Check whether `tx_cost` fits within the remaining block and account
+    /// limits, without adding it
+    pub fn would_fit(&self, tx_cost: &TransactionCost) -> Result<(), CostTrackerError> {
+        let cost = tx_cost.sum();
+        if self.block_cost.saturating_add(cost) > self.block_cost_limit {
+            return Err(CostTrackerError::WouldExceedBlockMaxLimit);
+        }
+        // A single transaction may not exceed the account limit either
+        if cost > self.account_cost_limit {
+            return Err(CostTrackerError::WouldExceedAccountMaxLimit);
+        }
+        for account in &tx_cost.writable_accounts {
+            if let Some(account_cost) = self.cost_by_writable_accounts.get(account) {
+                if account_cost.saturating_add(cost) > self.account_cost_limit {
+                    return Err(CostTrackerError::WouldExceedAccountMaxLimit);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add `tx_cost` if it fits within the remaining limits
+    pub fn try_add(&mut self, tx_cost: &TransactionCost) -> Result<u64, CostTrackerError> {
+        self.would_fit(tx_cost)?;
+        self.add_transaction_cost(tx_cost);
+        Ok(self.block_cost)
+    }
+
+    /// Add `tx_cost` regardless of the limits
+    pub fn add_transaction_cost(&mut self, tx_cost: &TransactionCost) {
+        let cost = tx_cost.sum();
+        for account in &tx_cost.writable_accounts {
+            let account_cost = self.cost_by_writable_accounts.entry(*account).or_insert(0);
+            *account_cost = account_cost.saturating_add(cost);
+        }
+        self.block_cost = self.block_cost.saturating_add(cost);
+        self.transaction_count = self.transaction_count.saturating_add(1);
+    }
+
+    /// Remove a previously added `tx_cost`, for transactions that ended up
+    /// not being recorded in the block
+    pub fn remove(&mut self, tx_cost: &TransactionCost) {
+        let cost = tx_cost.sum();
+        for account in &tx_cost.writable_accounts {
+            if let Some(account_cost) = self.cost_by_writable_accounts.get_mut(account) {
+                *account_cost = account_cost.saturating_sub(cost);
+                if *account_cost == 0 {
+                    self.cost_by_writable_accounts.remove(account);
+                }
+            }
+        }
+        self.block_cost = self.block_cost.saturating_sub(cost);
+        self.transaction_count = self.transaction_count.saturating_sub(1);
+    }
+
+    pub fn block_cost(&self) -> u64 {
+        self.block_cost
+    }
+
+    pub fn transaction_count(&self) -> u64 {
+        self.transaction_count
+    }
+
+    /// Cost accumulated by transactions writing to `account`
+    pub fn account_cost(&self, account: &Pubkey) -> u64 {
+        self.cost_by_writable_accounts
+            .get(account)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The writable account with the highest accumulated cost, and its cost
+    pub fn costliest_account(&self) -> (Pubkey, u64) {
+        self.cost_by_writable_accounts
+            .iter()
+            .max_by_key(|(_, cost)| **cost)
+            .map(|(account, cost)| (*account, *cost))
+            .unwrap_or_default()
+    }
+
+    pub fn report_stats(&self, slot: Slot) {
+        let (costliest_account, costliest_account_cost) = self.costliest_account();
+        datapoint_info!(
+            "cost_tracker_stats",
+            ("slot", slot as i64, i64),
+            ("block_cost", self.block_cost as i64, i64),
+            ("transaction_count", self.transaction_count as i64, i64),
+            (
+                "number_of_accounts",
+                self.cost_by_writable_accounts.len() as i64,
+                i64
+            ),
+            ("costliest_account", costliest_account.to_string(), String),
+            ("costliest_account_cost", costliest_account_cost as i64, i64),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_transaction_cost(writable_accounts: Vec<Pubkey>, cost: u64) -> TransactionCost {
+        TransactionCost {
+            writable_accounts,
+            execution_cost: cost,
+            ..TransactionCost::default()
+        }
+    }
+
+    #[test]
+    fn test_cost_tracker_block_limit() {
+        let mut cost_tracker = CostTracker::new(&CostModel::new(10, 15));
+        let tx_cost = test_transaction_cost(vec![Pubkey::new_unique()], 10);
+        assert_eq!(cost_tracker.try_add(&tx_cost), Ok(10));
+
+        let tx_cost = test_transaction_cost(vec![Pubkey::new_unique()], 6);
+        assert_eq!(
+            cost_tracker.try_add(&tx_cost),
+            Err(CostTrackerError::WouldExceedBlockMaxLimit)
+        );
+        assert_eq!(cost_tracker.block_cost(), 10);
+        assert_eq!(cost_tracker.transaction_count(), 1);
+
+        let tx_cost = test_transaction_cost(vec![Pubkey::new_unique()], 5);
+        assert_eq!(cost_tracker.try_add(&tx_cost), Ok(15));
+        assert_eq!(cost_tracker.transaction_count(), 2);
+    }
+
+    #[test]
+    fn test_cost_tracker_account_limit() {
+        let mut cost_tracker = CostTracker::new(&CostModel::new(10, 100));
+        let shared = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        let tx_cost = test_transaction_cost(vec![shared, other], 6);
+        assert!(cost_tracker.try_add(&tx_cost).is_ok());
+        assert_eq!(cost_tracker.account_cost(&shared), 6);
+        assert_eq!(cost_tracker.account_cost(&other), 6);
+
+        let tx_cost = test_transaction_cost(vec![Pubkey::new_unique(), shared], 5);
+        assert_eq!(
+            cost_tracker.would_fit(&tx_cost),
+            Err(CostTrackerError::WouldExceedAccountMaxLimit)
+        );
+
+        // Transactions not writing to the busy account still fit
+        let tx_cost = test_transaction_cost(vec![Pubkey::new_unique()], 10);
+        assert!(cost_tracker.try_add(&tx_cost).is_ok());
+
+        // A single transaction over the account limit never fits
+        let tx_cost = test_transaction_cost(vec![Pubkey::new_unique()], 11);
+        assert_eq!(
+            cost_tracker.would_fit(&tx_cost),
+            Err(CostTrackerError::WouldExceedAccountMaxLimit)
+        );
+    }
+
+    #[test]
+    fn test_cost_tracker_remove() {
+        let mut cost_tracker = CostTracker::new(&CostModel::new(10, 100));
+        let account = Pubkey::new_unique();
+        let first = test_transaction_cost(vec![account], 8);
+        let second = test_transaction_cost(vec![account], 4);
+        assert!(cost_tracker.try_add(&first).is_ok());
+        assert!(cost_tracker.try_add(&second).is_err());
+
+        cost_tracker.remove(&first);
+        assert_eq!(cost_tracker.block_cost(), 0);
+        assert_eq!(cost_tracker.transaction_count(), 0);
+        assert_eq!(cost_tracker.account_cost(&account), 0);
+        assert_eq!(cost_tracker.costliest_account(), (Pubkey::default(), 0));
+        assert!(cost_tracker.try_add(&second).is_ok());
+        assert_eq!(cost_tracker.costliest_account(), (account, 4));
+    }
+}
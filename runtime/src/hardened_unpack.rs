@@ -207,6 +207,7 @@ fn is_valid_snapshot_archive_entry(parts: &[&str], kind: tar::EntryType) -> bool
         (["accounts", file], Regular) if like_storage(file) => true,
         (["snapshots"], Directory) => true,
         (["snapshots", "status_cache"], Regular) => true,
+        (["snapshots", "ancient_storages"], Regular) => true,
         (["snapshots", dir, file], Regular) if all_digits(dir) && all_digits(file) => true,
         (["snapshots", dir], Directory) if all_digits(dir) => true,
         _ => false,
@@ -314,6 +315,10 @@ mod tests {
             &["snapshots", "3", "3"],
             tar::EntryType::Regular
         ));
+        assert!(is_valid_snapshot_archive_entry(
+            &["snapshots", "ancient_storages"],
+            tar::EntryType::Regular
+        ));
         assert!(is_valid_snapshot_archive_entry(
             &["version"],
             tar::EntryType::Regular
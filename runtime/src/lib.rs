@@ -3,7 +3,9 @@ pub mod accounts;
 pub mod accounts_background_service;
 pub mod accounts_cache;
 pub mod accounts_db;
+pub mod accounts_hash;
 pub mod accounts_index;
+pub mod accounts_update_notifier_interface;
 pub mod append_vec;
 pub mod bank;
 pub mod bank_client;
@@ -11,9 +13,12 @@ pub mod bank_forks;
 pub mod bank_utils;
 mod blockhash_queue;
 pub mod bloom;
+pub mod bucket_map;
 pub mod builtins;
 pub mod commitment;
 pub mod contains;
+pub mod cost_model;
+pub mod cost_tracker;
 pub mod epoch_stakes;
 pub mod genesis_utils;
 pub mod hardened_unpack;
@@ -16,7 +16,7 @@ use solana_sdk::{
     native_loader,
     process_instruction::{
         BpfComputeBudget, ComputeMeter, Executor, InvokeContext, Logger,
-        ProcessInstructionWithContext,
+        ProcessInstructionWithContext, TransactionReturnData,
     },
     pubkey::Pubkey,
     rent::Rent,
@@ -222,6 +222,7 @@ pub struct ThisInvokeContext<'a> {
     executors: Rc<RefCell<Executors>>,
     instruction_recorder: Option<InstructionRecorder>,
     feature_set: Arc<FeatureSet>,
+    return_data: (Pubkey, Vec<u8>),
 }
 impl<'a> ThisInvokeContext<'a> {
     #[allow(clippy::too_many_arguments)]
@@ -253,6 +254,7 @@ impl<'a> ThisInvokeContext<'a> {
             executors,
             instruction_recorder,
             feature_set,
+            return_data: (*program_id, Vec::new()),
         }
     }
 }
@@ -266,6 +268,8 @@ impl<'a> InvokeContext for ThisInvokeContext<'a> {
             return Err(InstructionError::ReentrancyNotAllowed);
         }
         self.program_ids.push(*key);
+        // The callee starts without return data, so that its caller only sees what it sets
+        self.return_data = (*key, Vec::new());
         Ok(())
     }
     fn pop(&mut self) {
@@ -346,6 +350,13 @@ impl<'a> InvokeContext for ThisInvokeContext<'a> {
             }
         })
     }
+    fn set_return_data(&mut self, data: Vec<u8>) -> Result<(), InstructionError> {
+        self.return_data = (*self.get_caller()?, data);
+        Ok(())
+    }
+    fn get_return_data(&self) -> (Pubkey, &[u8]) {
+        (self.return_data.0, &self.return_data.1)
+    }
 }
 pub struct ThisLogger {
     log_collector: Option<Rc<LogCollector>>,
@@ -929,6 +940,8 @@ impl MessageProcessor {
     /// This method calls the instruction's program entrypoint method and verifies that the result of
     /// the call does not violate the bank's accounting rules.
     /// The accounts are committed back to the bank only if this function returns Ok(_).
+    /// The compute units consumed by the instruction are stored in `compute_units_consumed`,
+    /// and the return data it left, if any, in `return_data`, whether it succeeds or not.
     #[allow(clippy::too_many_arguments)]
     fn execute_instruction(
         &self,
@@ -944,6 +957,8 @@ impl MessageProcessor {
         instruction_index: usize,
         feature_set: Arc<FeatureSet>,
         bpf_compute_budget: BpfComputeBudget,
+        compute_units_consumed: &mut u64,
+        return_data: &mut Option<TransactionReturnData>,
     ) -> Result<(), InstructionError> {
         // Fixup the special instructions key if present
         // before the account pre-values are taken care of
@@ -976,12 +991,25 @@ impl MessageProcessor {
         );
         let keyed_accounts =
             Self::create_keyed_accounts(message, instruction, executable_accounts, accounts);
-        self.process_instruction(
+        let result = self.process_instruction(
             program_id,
             &keyed_accounts,
             &instruction.data,
             &mut invoke_context,
-        )?;
+        );
+        *compute_units_consumed = bpf_compute_budget
+            .max_units
+            .saturating_sub(invoke_context.get_compute_meter().borrow().get_remaining());
+        let (return_program_id, return_data_bytes) = invoke_context.get_return_data();
+        *return_data = if return_data_bytes.is_empty() {
+            None
+        } else {
+            Some(TransactionReturnData {
+                program_id: return_program_id,
+                data: return_data_bytes.to_vec(),
+            })
+        };
+        result?;
         Self::verify(
             message,
             instruction,
@@ -996,6 +1024,9 @@ impl MessageProcessor {
     /// Process a message.
     /// This method calls each instruction in the message over the set of loaded Accounts
     /// The accounts are committed back to the bank only if every instruction succeeds
+    /// The compute units consumed by each executed instruction are appended to
+    /// `instruction_compute_units`, and the return data left by the last executed instruction
+    /// is stored in `return_data`
     #[allow(clippy::too_many_arguments)]
     pub fn process_message(
         &self,
@@ -1009,12 +1040,15 @@ impl MessageProcessor {
         instruction_recorders: Option<&[InstructionRecorder]>,
         feature_set: Arc<FeatureSet>,
         bpf_compute_budget: BpfComputeBudget,
+        instruction_compute_units: &mut Vec<u64>,
+        return_data: &mut Option<TransactionReturnData>,
     ) -> Result<(), TransactionError> {
         for (instruction_index, instruction) in message.instructions.iter().enumerate() {
             let instruction_recorder = instruction_recorders
                 .as_ref()
                 .map(|recorders| recorders[instruction_index].clone());
-            self.execute_instruction(
+            let mut compute_units_consumed = 0;
+            let result = self.execute_instruction(
                 message,
                 instruction,
                 &loaders[instruction_index],
@@ -1027,8 +1061,12 @@ impl MessageProcessor {
                 instruction_index,
                 feature_set.clone(),
                 bpf_compute_budget,
-            )
-            .map_err(|err| TransactionError::InstructionError(instruction_index as u8, err))?;
+                &mut compute_units_consumed,
+                return_data,
+            );
+            instruction_compute_units.push(compute_units_consumed);
+            result
+                .map_err(|err| TransactionError::InstructionError(instruction_index as u8, err))?;
         }
         Ok(())
     }
@@ -1646,6 +1684,8 @@ mod tests {
             None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(&FeatureSet::all_enabled()),
+            &mut vec![],
+            &mut None,
         );
         assert_eq!(result, Ok(()));
         assert_eq!(accounts[0].borrow().lamports, 100);
@@ -1671,6 +1711,8 @@ mod tests {
             None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(&FeatureSet::all_enabled()),
+            &mut vec![],
+            &mut None,
         );
         assert_eq!(
             result,
@@ -1700,6 +1742,8 @@ mod tests {
             None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(&FeatureSet::all_enabled()),
+            &mut vec![],
+            &mut None,
         );
         assert_eq!(
             result,
@@ -1813,6 +1857,8 @@ mod tests {
             None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(&FeatureSet::all_enabled()),
+            &mut vec![],
+            &mut None,
         );
         assert_eq!(
             result,
@@ -1842,6 +1888,8 @@ mod tests {
             None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(&FeatureSet::all_enabled()),
+            &mut vec![],
+            &mut None,
         );
         assert_eq!(result, Ok(()));
 
@@ -1868,6 +1916,8 @@ mod tests {
             None,
             Arc::new(FeatureSet::all_enabled()),
             BpfComputeBudget::new(&FeatureSet::all_enabled()),
+            &mut vec![],
+            &mut None,
         );
         assert_eq!(result, Ok(()));
         assert_eq!(accounts[0].borrow().lamports, 80);
@@ -1,8 +1,8 @@
 use {
     crate::{
         accounts::Accounts,
-        accounts_db::{AccountStorageEntry, AccountsDB, AppendVecId, BankHashInfo},
-        accounts_index::{AccountIndex, Ancestors},
+        accounts_db::{AccountStorageEntry, AccountsDB, AppendVecId, BankHashInfo, OriginalSlots},
+        accounts_index::{AccountIndex, AccountsIndexConfig, Ancestors},
         append_vec::AppendVec,
         bank::{Bank, BankFieldsToDeserialize, BankRc, Builtins},
         blockhash_queue::BlockhashQueue,
@@ -67,6 +67,19 @@ const MAX_STREAM_SIZE: u64 = 32 * 1024 * 1024 * 1024;
 #[derive(Clone, Debug, Default, Deserialize, Serialize, AbiExample)]
 struct AccountsDbFields<T>(HashMap<Slot, Vec<T>>, u64, Slot, BankHashInfo);
 
+/// The original slots of the accounts in ancient storages, by storage id. Not part of the
+/// serialized accounts db fields, whose format older versions expect: snapshots carry it in a
+/// file of its own.
+pub(crate) type AncientStorages = HashMap<AppendVecId, OriginalSlots>;
+
+pub(crate) fn ancient_storages_of(snapshot_storages: &[SnapshotStorage]) -> AncientStorages {
+    snapshot_storages
+        .iter()
+        .flatten()
+        .filter_map(|storage| Some((storage.append_vec_id(), storage.original_slots()?)))
+        .collect()
+}
+
 trait TypeContext<'a> {
     type SerializableAccountStorageEntry: Serialize
         + DeserializeOwned
@@ -122,6 +135,7 @@ where
 pub(crate) fn bank_from_stream<R, P>(
     serde_style: SerdeStyle,
     stream: &mut BufReader<R>,
+    ancient_storages: &AncientStorages,
     append_vecs_path: P,
     account_paths: &[PathBuf],
     genesis_config: &GenesisConfig,
@@ -130,6 +144,7 @@ pub(crate) fn bank_from_stream<R, P>(
     additional_builtins: Option<&Builtins>,
     account_indexes: HashSet<AccountIndex>,
     caching_enabled: bool,
+    accounts_index_config: Option<AccountsIndexConfig>,
 ) -> std::result::Result<Bank, Error>
 where
     R: Read,
@@ -142,6 +157,7 @@ where
             let bank = reconstruct_bank_from_fields(
                 bank_fields,
                 accounts_db_fields,
+                ancient_storages,
                 genesis_config,
                 frozen_account_pubkeys,
                 account_paths,
@@ -150,6 +166,7 @@ where
                 additional_builtins,
                 account_indexes,
                 caching_enabled,
+                accounts_index_config,
             )?;
             Ok(bank)
         }};
@@ -163,6 +180,97 @@ where
     })
 }
 
+/// Rebuilds a bank from the streams of a full snapshot and an incremental snapshot on top of it.
+/// The storages for slots up to the full snapshot's slot come from the full snapshot, everything
+/// else, including the bank itself, comes from the incremental snapshot.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn bank_from_incremental_streams<R, P>(
+    serde_style: SerdeStyle,
+    full_snapshot_stream: &mut BufReader<R>,
+    incremental_snapshot_stream: &mut BufReader<R>,
+    ancient_storages: &AncientStorages,
+    append_vecs_path: P,
+    account_paths: &[PathBuf],
+    genesis_config: &GenesisConfig,
+    frozen_account_pubkeys: &[Pubkey],
+    debug_keys: Option<Arc<HashSet<Pubkey>>>,
+    additional_builtins: Option<&Builtins>,
+    account_indexes: HashSet<AccountIndex>,
+    caching_enabled: bool,
+    accounts_index_config: Option<AccountsIndexConfig>,
+) -> std::result::Result<Bank, Error>
+where
+    R: Read,
+    P: AsRef<Path>,
+{
+    macro_rules! INTO {
+        ($x:ident) => {{
+            let (full_bank_fields, full_accounts_db_fields) =
+                $x::deserialize_bank_fields(full_snapshot_stream)?;
+            let (bank_fields, accounts_db_fields) =
+                $x::deserialize_bank_fields(incremental_snapshot_stream)?;
+            let accounts_db_fields = merge_incremental_accounts_db_fields(
+                full_bank_fields.slot,
+                full_accounts_db_fields,
+                accounts_db_fields,
+            )?;
+
+            let bank = reconstruct_bank_from_fields(
+                bank_fields,
+                accounts_db_fields,
+                ancient_storages,
+                genesis_config,
+                frozen_account_pubkeys,
+                account_paths,
+                append_vecs_path,
+                debug_keys,
+                additional_builtins,
+                account_indexes,
+                caching_enabled,
+                accounts_index_config,
+            )?;
+            Ok(bank)
+        }};
+    }
+    match serde_style {
+        SerdeStyle::NEWER => INTO!(TypeContextFuture),
+    }
+    .map_err(|err| {
+        warn!("bankrc_from_incremental_streams error: {:?}", err);
+        err
+    })
+}
+
+fn merge_incremental_accounts_db_fields<E>(
+    full_snapshot_slot: Slot,
+    full_accounts_db_fields: AccountsDbFields<E>,
+    incremental_accounts_db_fields: AccountsDbFields<E>,
+) -> Result<AccountsDbFields<E>, Error> {
+    let AccountsDbFields(mut storage, full_version, _, _) = full_accounts_db_fields;
+    let AccountsDbFields(incremental_storage, version, slot, bank_hash_info) =
+        incremental_accounts_db_fields;
+
+    if slot <= full_snapshot_slot
+        || incremental_storage
+            .keys()
+            .any(|storage_slot| *storage_slot <= full_snapshot_slot)
+    {
+        return Err(Box::new(bincode::ErrorKind::Custom(format!(
+            "incremental snapshot for slot {} is not on top of the full snapshot for slot {}",
+            slot, full_snapshot_slot
+        ))));
+    }
+
+    storage.retain(|storage_slot, _| *storage_slot <= full_snapshot_slot);
+    storage.extend(incremental_storage);
+    Ok(AccountsDbFields(
+        storage,
+        std::cmp::max(full_version, version),
+        slot,
+        bank_hash_info,
+    ))
+}
+
 pub(crate) fn bank_to_stream<W>(
     serde_style: SerdeStyle,
     stream: &mut BufWriter<W>,
@@ -231,6 +339,7 @@ impl<'a, C> IgnoreAsHelper for SerializableAccountsDB<'a, C> {}
 fn reconstruct_bank_from_fields<E, P>(
     bank_fields: BankFieldsToDeserialize,
     accounts_db_fields: AccountsDbFields<E>,
+    ancient_storages: &AncientStorages,
     genesis_config: &GenesisConfig,
     frozen_account_pubkeys: &[Pubkey],
     account_paths: &[PathBuf],
@@ -239,6 +348,7 @@ fn reconstruct_bank_from_fields<E, P>(
     additional_builtins: Option<&Builtins>,
     account_indexes: HashSet<AccountIndex>,
     caching_enabled: bool,
+    accounts_index_config: Option<AccountsIndexConfig>,
 ) -> Result<Bank, Error>
 where
     E: SerializableStorage,
@@ -246,11 +356,13 @@ where
 {
     let mut accounts_db = reconstruct_accountsdb_from_fields(
         accounts_db_fields,
+        ancient_storages,
         account_paths,
         append_vecs_path,
         &genesis_config.cluster_type,
         account_indexes,
         caching_enabled,
+        accounts_index_config,
     )?;
     accounts_db.freeze_accounts(&bank_fields.ancestors, frozen_account_pubkeys);
 
@@ -266,13 +378,16 @@ where
     Ok(bank)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn reconstruct_accountsdb_from_fields<E, P>(
     accounts_db_fields: AccountsDbFields<E>,
+    ancient_storages: &AncientStorages,
     account_paths: &[PathBuf],
     stream_append_vecs_path: P,
     cluster_type: &ClusterType,
     account_indexes: HashSet<AccountIndex>,
     caching_enabled: bool,
+    accounts_index_config: Option<AccountsIndexConfig>,
 ) -> Result<AccountsDB, Error>
 where
     E: SerializableStorage,
@@ -283,6 +398,7 @@ where
         cluster_type,
         account_indexes,
         caching_enabled,
+        accounts_index_config,
     );
     let AccountsDbFields(storage, version, slot, bank_hash_info) = accounts_db_fields;
 
@@ -338,6 +454,8 @@ where
                     accounts,
                     num_accounts,
                 );
+                u_storage_entry
+                    .set_original_slots(ancient_storages.get(&storage_entry.id()).cloned());
 
                 new_slot_storage.insert(storage_entry.id(), Arc::new(u_storage_entry));
             }
@@ -55,6 +55,7 @@ fn check_accounts(accounts: &Accounts, pubkeys: &[Pubkey], num: usize) {
 #[cfg(test)]
 fn context_accountsdb_from_stream<'a, C, R, P>(
     stream: &mut BufReader<R>,
+    ancient_storages: &AncientStorages,
     account_paths: &[PathBuf],
     stream_append_vecs_path: P,
 ) -> Result<AccountsDB, Error>
@@ -66,11 +67,13 @@ where
     // read and deserialise the accounts database directly from the stream
     reconstruct_accountsdb_from_fields(
         C::deserialize_accounts_db_fields(stream)?,
+        ancient_storages,
         account_paths,
         stream_append_vecs_path,
         &ClusterType::Development,
         HashSet::new(),
         false,
+        None,
     )
 }
 
@@ -78,6 +81,7 @@ where
 fn accountsdb_from_stream<R, P>(
     serde_style: SerdeStyle,
     stream: &mut BufReader<R>,
+    ancient_storages: &AncientStorages,
     account_paths: &[PathBuf],
     stream_append_vecs_path: P,
 ) -> Result<AccountsDB, Error>
@@ -88,6 +92,7 @@ where
     match serde_style {
         SerdeStyle::NEWER => context_accountsdb_from_stream::<TypeContextFuture, R, P>(
             stream,
+            ancient_storages,
             account_paths,
             stream_append_vecs_path,
         ),
@@ -122,8 +127,13 @@ where
 fn test_accounts_serialize_style(serde_style: SerdeStyle) {
     solana_logger::setup();
     let (_accounts_dir, paths) = get_temp_accounts_paths(4).unwrap();
-    let accounts =
-        Accounts::new_with_config(paths, &ClusterType::Development, HashSet::new(), false);
+    let accounts = Accounts::new_with_config(
+        paths,
+        &ClusterType::Development,
+        HashSet::new(),
+        false,
+        None,
+    );
 
     let mut pubkeys: Vec<Pubkey> = vec![];
     create_test_accounts(&accounts, &mut pubkeys, 100, 0);
@@ -152,6 +162,7 @@ fn test_accounts_serialize_style(serde_style: SerdeStyle) {
         accountsdb_from_stream(
             serde_style,
             &mut reader,
+            &AncientStorages::default(),
             &daccounts_paths,
             copied_accounts.path(),
         )
@@ -211,6 +222,7 @@ fn test_bank_serialize_style(serde_style: SerdeStyle) {
     let mut dbank = crate::serde_snapshot::bank_from_stream(
         serde_style,
         &mut reader,
+        &ancient_storages_of(&snapshot_storages),
         copied_accounts.path(),
         &dbank_paths,
         &genesis_config,
@@ -219,6 +231,7 @@ fn test_bank_serialize_style(serde_style: SerdeStyle) {
         None,
         HashSet::new(),
         false,
+        None,
     )
     .unwrap();
     dbank.src = ref_sc;
@@ -249,7 +262,14 @@ pub(crate) fn reconstruct_accounts_db_via_serialization(
     let copied_accounts = TempDir::new().unwrap();
     // Simulate obtaining a copy of the AppendVecs from a tarball
     copy_append_vecs(&accounts, copied_accounts.path()).unwrap();
-    accountsdb_from_stream(SerdeStyle::NEWER, &mut reader, &[], copied_accounts.path()).unwrap()
+    accountsdb_from_stream(
+        SerdeStyle::NEWER,
+        &mut reader,
+        &ancient_storages_of(&snapshot_storages),
+        &[],
+        copied_accounts.path(),
+    )
+    .unwrap()
 }
 
 #[test]
@@ -13,6 +13,15 @@ pub type AccountsPackageSender = Sender<AccountsPackagePre>;
 pub type AccountsPackageReceiver = Receiver<AccountsPackagePre>;
 pub type AccountsPackageSendError = SendError<AccountsPackagePre>;
 
+/// The kind of snapshot archive an accounts package is turned into
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SnapshotType {
+    /// Contains every storage of the bank
+    FullSnapshot,
+    /// Contains only the storages for slots after the full snapshot at the given slot
+    IncrementalSnapshot(Slot),
+}
+
 #[derive(Debug)]
 pub struct AccountsPackagePre {
     pub slot: Slot,
@@ -27,6 +36,8 @@ pub struct AccountsPackagePre {
     pub expected_capitalization: u64,
     pub hash_for_testing: Option<Hash>,
     pub simple_capitalization_testing: bool,
+    /// `None` if the package is only used to verify the accounts hash
+    pub snapshot_type: Option<SnapshotType>,
 }
 
 impl AccountsPackagePre {
@@ -44,6 +55,7 @@ impl AccountsPackagePre {
         expected_capitalization: u64,
         hash_for_testing: Option<Hash>,
         simple_capitalization_testing: bool,
+        snapshot_type: Option<SnapshotType>,
     ) -> Self {
         Self {
             slot,
@@ -58,6 +70,7 @@ impl AccountsPackagePre {
             expected_capitalization,
             hash_for_testing,
             simple_capitalization_testing,
+            snapshot_type,
         }
     }
 }
@@ -72,9 +85,11 @@ pub struct AccountsPackage {
     pub hash: Hash,
     pub archive_format: ArchiveFormat,
     pub snapshot_version: SnapshotVersion,
+    pub snapshot_type: Option<SnapshotType>,
 }
 
 impl AccountsPackage {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         slot: Slot,
         block_height: u64,
@@ -85,6 +100,7 @@ impl AccountsPackage {
         hash: Hash,
         archive_format: ArchiveFormat,
         snapshot_version: SnapshotVersion,
+        snapshot_type: Option<SnapshotType>,
     ) -> Self {
         Self {
             slot,
@@ -96,6 +112,7 @@ impl AccountsPackage {
             hash,
             archive_format,
             snapshot_version,
+            snapshot_type,
         }
     }
 }
@@ -1,11 +1,24 @@
 use crate::bank::Bank;
-use solana_sdk::transaction::{Result, Transaction};
+use solana_sdk::{
+    message::v0::LoadedAddresses,
+    transaction::{Result, Transaction},
+};
+use std::borrow::Cow;
+
+/// Versioned transactions lowered into legacy transactions, see
+/// `Bank::resolve_transactions`
+pub struct ResolvedTransactions {
+    pub transactions: Vec<Transaction>,
+    pub loaded_addresses: Vec<Option<LoadedAddresses>>,
+    pub results: Vec<Result<()>>,
+}
 
 // Represents the results of trying to lock a set of accounts
 pub struct TransactionBatch<'a, 'b> {
     lock_results: Vec<Result<()>>,
     bank: &'a Bank,
-    transactions: &'b [Transaction],
+    transactions: Cow<'b, [Transaction]>,
+    loaded_addresses: Vec<Option<LoadedAddresses>>,
     iteration_order: Option<Vec<usize>>,
     pub(crate) needs_unlock: bool,
 }
@@ -16,8 +29,27 @@ impl<'a, 'b> TransactionBatch<'a, 'b> {
         bank: &'a Bank,
         transactions: &'b [Transaction],
         iteration_order: Option<Vec<usize>>,
+    ) -> Self {
+        Self::new_with_loaded_addresses(
+            lock_results,
+            bank,
+            Cow::Borrowed(transactions),
+            vec![None; transactions.len()],
+            iteration_order,
+        )
+    }
+
+    /// Create a batch of transactions lowered from versioned transactions,
+    /// along with the addresses each of them loaded from lookup tables
+    pub fn new_with_loaded_addresses(
+        lock_results: Vec<Result<()>>,
+        bank: &'a Bank,
+        transactions: Cow<'b, [Transaction]>,
+        loaded_addresses: Vec<Option<LoadedAddresses>>,
+        iteration_order: Option<Vec<usize>>,
     ) -> Self {
         assert_eq!(lock_results.len(), transactions.len());
+        assert_eq!(loaded_addresses.len(), transactions.len());
         if let Some(iteration_order) = &iteration_order {
             assert_eq!(transactions.len(), iteration_order.len());
         }
@@ -25,6 +57,7 @@ impl<'a, 'b> TransactionBatch<'a, 'b> {
             lock_results,
             bank,
             transactions,
+            loaded_addresses,
             iteration_order,
             needs_unlock: true,
         }
@@ -35,7 +68,12 @@ impl<'a, 'b> TransactionBatch<'a, 'b> {
     }
 
     pub fn transactions(&self) -> &[Transaction] {
-        self.transactions
+        &self.transactions
+    }
+
+    /// Addresses loaded from lookup tables, indexed like `transactions`
+    pub fn loaded_addresses(&self) -> &[Option<LoadedAddresses>] {
+        &self.loaded_addresses
     }
 
     pub fn iteration_order(&self) -> Option<&[usize]> {
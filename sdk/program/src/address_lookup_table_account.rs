@@ -0,0 +1,12 @@
+//! The addresses stored in an address lookup table, as needed by a client to
+//! compile a version 0 message that references the table
+
+use crate::pubkey::Pubkey;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AddressLookupTableAccount {
+    /// Address of the lookup table account
+    pub key: Pubkey,
+    /// The addresses stored in the table
+    pub addresses: Vec<Pubkey>,
+}
@@ -0,0 +1,200 @@
+//! The compute budget native program lets a transaction request a compute unit
+//! limit other than the default and pay a per-unit price to have its execution
+//! prioritized by the leader.
+
+use crate::{
+    instruction::{CompiledInstruction, Instruction, InstructionError},
+    message::Message,
+    pubkey::Pubkey,
+};
+
+crate::declare_id!("ComputeBudget111111111111111111111111111111");
+
+/// Compute unit limit applied to a transaction that does not request one
+pub const DEFAULT_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+/// Largest compute unit limit a transaction may request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Compute unit prices are denominated in micro-lamports
+pub const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeBudgetInstruction {
+    /// Request a specific compute unit limit for the transaction
+    RequestUnits(u32),
+
+    /// Set the price paid for each compute unit of the transaction's limit,
+    /// in micro-lamports.  The resulting prioritization fee is charged on
+    /// top of the signature fee.
+    SetComputeUnitPrice(u64),
+}
+
+pub fn request_units(units: u32) -> Instruction {
+    Instruction::new(id(), &ComputeBudgetInstruction::RequestUnits(units), vec![])
+}
+
+pub fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    Instruction::new(
+        id(),
+        &ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports),
+        vec![],
+    )
+}
+
+/// The compute budget requested by a transaction's compute budget instructions
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudgetRequest {
+    /// Compute unit limit requested by the transaction, if any
+    pub units: Option<u32>,
+    /// Price of each compute unit, in micro-lamports
+    pub compute_unit_price: u64,
+}
+
+impl ComputeBudgetRequest {
+    /// Collect the compute budget instructions of `message`.  On failure, the
+    /// index of the offending instruction is returned alongside the error.
+    pub fn from_message(message: &Message) -> Result<Self, (u8, InstructionError)> {
+        Self::from_instructions(&message.account_keys, &message.instructions)
+    }
+
+    /// Like `from_message`, for instructions whose programs are found in
+    /// `account_keys`
+    pub fn from_instructions(
+        account_keys: &[Pubkey],
+        instructions: &[CompiledInstruction],
+    ) -> Result<Self, (u8, InstructionError)> {
+        let mut units = None;
+        let mut compute_unit_price = None;
+        for (index, instruction) in instructions.iter().enumerate() {
+            let program_index = instruction.program_id_index as usize;
+            // Transaction may not be sanitized here
+            if program_index >= account_keys.len() || !check_id(&account_keys[program_index]) {
+                continue;
+            }
+            let invalid = (index as u8, InstructionError::InvalidInstructionData);
+            match bincode::deserialize(&instruction.data) {
+                Ok(ComputeBudgetInstruction::RequestUnits(requested_units)) => {
+                    if units.is_some() || requested_units > MAX_COMPUTE_UNIT_LIMIT {
+                        return Err(invalid);
+                    }
+                    units = Some(requested_units);
+                }
+                Ok(ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports)) => {
+                    if compute_unit_price.is_some() {
+                        return Err(invalid);
+                    }
+                    compute_unit_price = Some(micro_lamports);
+                }
+                Err(_) => return Err(invalid),
+            }
+        }
+        Ok(Self {
+            units,
+            compute_unit_price: compute_unit_price.unwrap_or_default(),
+        })
+    }
+
+    /// Compute unit limit the transaction executes with
+    pub fn compute_unit_limit(&self) -> u64 {
+        u64::from(self.units.unwrap_or(DEFAULT_COMPUTE_UNIT_LIMIT))
+    }
+
+    /// Lamports paid on top of the signature fee, rounded up to the nearest
+    /// lamport
+    pub fn prioritization_fee(&self) -> u64 {
+        let micro_lamports =
+            u128::from(self.compute_unit_price) * u128::from(self.compute_unit_limit());
+        let lamports = (micro_lamports + u128::from(MICRO_LAMPORTS_PER_LAMPORT) - 1)
+            / u128::from(MICRO_LAMPORTS_PER_LAMPORT);
+        lamports.min(u128::from(u64::MAX)) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pubkey::Pubkey, system_instruction};
+
+    fn request_from_instructions(
+        instructions: &[Instruction],
+    ) -> Result<ComputeBudgetRequest, (u8, InstructionError)> {
+        let payer = Pubkey::new_unique();
+        ComputeBudgetRequest::from_message(&Message::new(instructions, Some(&payer)))
+    }
+
+    #[test]
+    fn test_compute_budget_request_from_message() {
+        let transfer =
+            system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1);
+        assert_eq!(
+            request_from_instructions(&[transfer.clone()]),
+            Ok(ComputeBudgetRequest::default())
+        );
+        assert_eq!(
+            request_from_instructions(&[
+                request_units(400_000),
+                transfer.clone(),
+                set_compute_unit_price(5),
+            ]),
+            Ok(ComputeBudgetRequest {
+                units: Some(400_000),
+                compute_unit_price: 5,
+            })
+        );
+
+        // Duplicate instructions are rejected
+        assert_eq!(
+            request_from_instructions(&[
+                set_compute_unit_price(1),
+                transfer.clone(),
+                set_compute_unit_price(2),
+            ]),
+            Err((2, InstructionError::InvalidInstructionData))
+        );
+
+        // Limits above the maximum are rejected
+        assert_eq!(
+            request_from_instructions(&[transfer, request_units(MAX_COMPUTE_UNIT_LIMIT + 1)]),
+            Err((1, InstructionError::InvalidInstructionData))
+        );
+
+        // Undecodable data is rejected
+        let mut instruction = request_units(1);
+        instruction.data = vec![9];
+        assert_eq!(
+            request_from_instructions(&[instruction]),
+            Err((0, InstructionError::InvalidInstructionData))
+        );
+    }
+
+    #[test]
+    fn test_prioritization_fee() {
+        assert_eq!(ComputeBudgetRequest::default().prioritization_fee(), 0);
+        assert_eq!(
+            ComputeBudgetRequest {
+                units: Some(1_000_000),
+                compute_unit_price: 3,
+            }
+            .prioritization_fee(),
+            3
+        );
+        // Fractional lamports round up
+        assert_eq!(
+            ComputeBudgetRequest {
+                units: None,
+                compute_unit_price: 1,
+            }
+            .prioritization_fee(),
+            1
+        );
+        assert_eq!(
+            ComputeBudgetRequest {
+                units: Some(MAX_COMPUTE_UNIT_LIMIT),
+                compute_unit_price: u64::MAX,
+            }
+            .prioritization_fee(),
+            u64::MAX
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::clock::DEFAULT_MS_PER_SLOT;
+use crate::compute_budget::ComputeBudgetRequest;
 use crate::message::Message;
 use crate::secp256k1_program;
 use log::*;
@@ -21,12 +22,14 @@ impl Default for FeeCalculator {
 
 pub struct FeeConfig {
     pub secp256k1_program_enabled: bool,
+    pub compute_budget_program_enabled: bool,
 }
 
 impl Default for FeeConfig {
     fn default() -> Self {
         Self {
             secp256k1_program_enabled: true,
+            compute_budget_program_enabled: true,
         }
     }
 }
@@ -57,8 +60,19 @@ impl FeeCalculator {
             }
         }
 
-        self.lamports_per_signature
-            * (u64::from(message.header.num_required_signatures) + num_secp256k1_signatures)
+        // Malformed compute budget instructions fail the transaction, which then
+        // only pays for its signatures
+        let prioritization_fee = if fee_config.compute_budget_program_enabled {
+            ComputeBudgetRequest::from_message(message)
+                .map(|request| request.prioritization_fee())
+                .unwrap_or_default()
+        } else {
+            0
+        };
+
+        (self.lamports_per_signature
+            * (u64::from(message.header.num_required_signatures) + num_secp256k1_signatures))
+            .saturating_add(prioritization_fee)
     }
 }
 
@@ -262,7 +276,8 @@ mod tests {
             FeeCalculator::new(1).calculate_fee_with_config(
                 &message,
                 &FeeConfig {
-                    secp256k1_program_enabled: false
+                    secp256k1_program_enabled: false,
+                    compute_budget_program_enabled: true,
                 }
             ),
             1
@@ -274,6 +289,32 @@ mod tests {
         assert_eq!(FeeCalculator::new(1).calculate_fee(&message), 11);
     }
 
+    #[test]
+    fn test_fee_calculator_calculate_fee_compute_budget() {
+        let pubkey0 = Pubkey::new(&[0; 32]);
+        let pubkey1 = Pubkey::new(&[1; 32]);
+        let ix0 = system_instruction::transfer(&pubkey0, &pubkey1, 1);
+        let message = Message::new(
+            &[
+                ix0,
+                crate::compute_budget::request_units(1_000_000),
+                crate::compute_budget::set_compute_unit_price(2_500),
+            ],
+            Some(&pubkey0),
+        );
+        assert_eq!(FeeCalculator::new(5).calculate_fee(&message), 5 + 2_500);
+        assert_eq!(
+            FeeCalculator::new(5).calculate_fee_with_config(
+                &message,
+                &FeeConfig {
+                    secp256k1_program_enabled: true,
+                    compute_budget_program_enabled: false,
+                }
+            ),
+            5
+        );
+    }
+
     #[test]
     fn test_fee_rate_governor_derived_default() {
         solana_logger::setup();
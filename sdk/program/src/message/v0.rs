@@ -0,0 +1,452 @@
+//! Version 0 messages, which reference accounts stored in on-chain address
+//! lookup tables in addition to the account keys they list
+
+use crate::sanitize::{Sanitize, SanitizeError};
+use crate::{
+    address_lookup_table_account::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::{CompiledInstruction, Instruction},
+    message::{compile_instructions, Message as LegacyMessage, MessageHeader},
+    pubkey::Pubkey,
+    short_vec,
+};
+
+/// Largest number of accounts a message can reference, since instructions index
+/// accounts with a `u8`
+pub const MAX_MESSAGE_ACCOUNT_KEYS: usize = 256;
+
+/// A lookup of accounts stored in an on-chain address lookup table
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, AbiExample)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageAddressTableLookup {
+    /// Address of the lookup table
+    pub account_key: Pubkey,
+    /// Indexes of the table's addresses to load as writable accounts
+    #[serde(with = "short_vec")]
+    pub writable_indexes: Vec<u8>,
+    /// Indexes of the table's addresses to load as read-only accounts
+    #[serde(with = "short_vec")]
+    pub readonly_indexes: Vec<u8>,
+}
+
+/// Addresses loaded from the lookup tables of a version 0 message, in the order
+/// of its `address_table_lookups`
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, AbiExample)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedAddresses {
+    pub writable: Vec<Pubkey>,
+    pub readonly: Vec<Pubkey>,
+}
+
+impl LoadedAddresses {
+    pub fn len(&self) -> usize {
+        self.writable.len() + self.readonly.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writable.is_empty() && self.readonly.is_empty()
+    }
+}
+
+/// A message that loads accounts from on-chain address lookup tables.  It is
+/// serialized after a `MESSAGE_VERSION_PREFIX` byte, see `VersionedMessage`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, AbiExample)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+    /// The message header, identifying signed and read-only `account_keys`.
+    /// Accounts loaded from lookup tables are never signers.
+    pub header: MessageHeader,
+
+    /// The account keys listed in the message itself
+    #[serde(with = "short_vec")]
+    pub account_keys: Vec<Pubkey>,
+
+    /// The id of a recent ledger entry.
+    pub recent_blockhash: Hash,
+
+    /// Instructions index `account_keys` first, followed by the writable and
+    /// then the read-only addresses loaded by `address_table_lookups`
+    #[serde(with = "short_vec")]
+    pub instructions: Vec<CompiledInstruction>,
+
+    /// Lookups of additional accounts in on-chain address lookup tables
+    #[serde(with = "short_vec")]
+    pub address_table_lookups: Vec<MessageAddressTableLookup>,
+}
+
+impl Sanitize for Message {
+    fn sanitize(&self) -> std::result::Result<(), SanitizeError> {
+        let num_static_account_keys = self.account_keys.len();
+
+        // signing area and read-only non-signing area should not overlap
+        if self.header.num_required_signatures as usize
+            + self.header.num_readonly_unsigned_accounts as usize
+            > num_static_account_keys
+        {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+
+        // there should be at least 1 RW fee-payer account.
+        if self.header.num_readonly_signed_accounts >= self.header.num_required_signatures {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+
+        // every lookup must load at least one address
+        if self
+            .address_table_lookups
+            .iter()
+            .any(|lookup| lookup.writable_indexes.is_empty() && lookup.readonly_indexes.is_empty())
+        {
+            return Err(SanitizeError::InvalidValue);
+        }
+
+        let num_account_keys = num_static_account_keys + self.num_loaded_addresses();
+        if num_account_keys > MAX_MESSAGE_ACCOUNT_KEYS {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+
+        for ci in &self.instructions {
+            // Programs cannot be loaded from lookup tables
+            if ci.program_id_index as usize >= num_static_account_keys {
+                return Err(SanitizeError::IndexOutOfBounds);
+            }
+            // A program cannot be a payer.
+            if ci.program_id_index == 0 {
+                return Err(SanitizeError::IndexOutOfBounds);
+            }
+            for ai in &ci.accounts {
+                if *ai as usize >= num_account_keys {
+                    return Err(SanitizeError::IndexOutOfBounds);
+                }
+            }
+        }
+        self.account_keys.sanitize()?;
+        self.recent_blockhash.sanitize()?;
+        self.instructions.sanitize()?;
+        Ok(())
+    }
+}
+
+impl Message {
+    /// Create a message that loads the non-signer, non-program accounts found in
+    /// `address_lookup_table_accounts` from those tables rather than listing them
+    pub fn new(
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        address_lookup_table_accounts: &[AddressLookupTableAccount],
+    ) -> Self {
+        let legacy_message = LegacyMessage::new(instructions, payer);
+        let program_ids = legacy_message.program_ids();
+        let mut is_loaded = vec![false; legacy_message.account_keys.len()];
+        let mut address_table_lookups = vec![];
+        let mut loaded_addresses = LoadedAddresses::default();
+        for table in address_lookup_table_accounts {
+            let mut lookup = MessageAddressTableLookup {
+                account_key: table.key,
+                ..MessageAddressTableLookup::default()
+            };
+            for (i, key) in legacy_message.account_keys.iter().enumerate() {
+                if is_loaded[i] || legacy_message.is_signer(i) || program_ids.contains(&key) {
+                    continue;
+                }
+                let table_index = table
+                    .addresses
+                    .iter()
+                    .take(MAX_MESSAGE_ACCOUNT_KEYS)
+                    .position(|address| address == key);
+                if let Some(table_index) = table_index {
+                    is_loaded[i] = true;
+                    if legacy_message.is_writable(i) {
+                        lookup.writable_indexes.push(table_index as u8);
+                        loaded_addresses.writable.push(*key);
+                    } else {
+                        lookup.readonly_indexes.push(table_index as u8);
+                        loaded_addresses.readonly.push(*key);
+                    }
+                }
+            }
+            if !lookup.writable_indexes.is_empty() || !lookup.readonly_indexes.is_empty() {
+                address_table_lookups.push(lookup);
+            }
+        }
+
+        let mut num_readonly_unsigned_accounts = 0;
+        let mut account_keys = vec![];
+        for (i, key) in legacy_message.account_keys.iter().enumerate() {
+            if !is_loaded[i] {
+                if !legacy_message.is_signer(i) && !legacy_message.is_writable(i) {
+                    num_readonly_unsigned_accounts += 1;
+                }
+                account_keys.push(*key);
+            }
+        }
+        let num_static_account_keys = account_keys.len();
+        account_keys.extend(&loaded_addresses.writable);
+        account_keys.extend(&loaded_addresses.readonly);
+        let instructions = compile_instructions(instructions, &account_keys);
+        account_keys.truncate(num_static_account_keys);
+
+        Self {
+            header: MessageHeader {
+                num_readonly_unsigned_accounts,
+                ..legacy_message.header
+            },
+            account_keys,
+            recent_blockhash: legacy_message.recent_blockhash,
+            instructions,
+            address_table_lookups,
+        }
+    }
+
+    /// Number of addresses this message loads from lookup tables
+    pub fn num_loaded_addresses(&self) -> usize {
+        self.address_table_lookups
+            .iter()
+            .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+            .sum()
+    }
+
+    /// Convert this sanitized message into a legacy message that lists the
+    /// addresses loaded from its lookup tables as account keys, so it can be
+    /// processed like any other message.  Loaded writable addresses follow the
+    /// writable unsigned keys and loaded read-only addresses come last.  The
+    /// legacy message does not serialize to the signed message data.
+    ///
+    /// Returns `None` if `loaded_addresses` does not match the lookups.
+    pub fn into_legacy_message(self, loaded_addresses: &LoadedAddresses) -> Option<LegacyMessage> {
+        let num_writable_lookups: usize = self
+            .address_table_lookups
+            .iter()
+            .map(|lookup| lookup.writable_indexes.len())
+            .sum();
+        if loaded_addresses.writable.len() != num_writable_lookups
+            || loaded_addresses.len() != self.num_loaded_addresses()
+        {
+            return None;
+        }
+
+        let Message {
+            header,
+            mut account_keys,
+            recent_blockhash,
+            instructions,
+            ..
+        } = self;
+        let num_static_account_keys = account_keys.len();
+        let num_readonly_unsigned_accounts =
+            header.num_readonly_unsigned_accounts as usize + loaded_addresses.readonly.len();
+        if header.num_readonly_unsigned_accounts as usize > num_static_account_keys
+            || num_readonly_unsigned_accounts > u8::MAX as usize
+            || num_static_account_keys + loaded_addresses.len() > MAX_MESSAGE_ACCOUNT_KEYS
+        {
+            return None;
+        }
+
+        // The static read-only unsigned keys move behind the loaded writable addresses
+        let num_static_writable_keys =
+            num_static_account_keys - header.num_readonly_unsigned_accounts as usize;
+        let num_loaded_writable = loaded_addresses.writable.len();
+        let lowered_index = |index: u8| {
+            let index = index as usize;
+            let index = if index < num_static_writable_keys {
+                index
+            } else if index < num_static_account_keys {
+                index + num_loaded_writable
+            } else if index < num_static_account_keys + num_loaded_writable {
+                index - (num_static_account_keys - num_static_writable_keys)
+            } else {
+                index
+            };
+            index as u8
+        };
+
+        let static_readonly_keys = account_keys.split_off(num_static_writable_keys);
+        account_keys.extend(&loaded_addresses.writable);
+        account_keys.extend(static_readonly_keys);
+        account_keys.extend(&loaded_addresses.readonly);
+        let instructions = instructions
+            .into_iter()
+            .map(|mut instruction| {
+                instruction.program_id_index = lowered_index(instruction.program_id_index);
+                for index in instruction.accounts.iter_mut() {
+                    *index = lowered_index(*index);
+                }
+                instruction
+            })
+            .collect();
+
+        Some(LegacyMessage {
+            header: MessageHeader {
+                num_readonly_unsigned_accounts: num_readonly_unsigned_accounts as u8,
+                ..header
+            },
+            account_keys,
+            recent_blockhash,
+            instructions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::AccountMeta;
+
+    #[test]
+    fn test_new() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let writable_key = Pubkey::new_unique();
+        let readonly_key = Pubkey::new_unique();
+        let static_key = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique(), readonly_key, writable_key, program_id],
+        };
+        let instructions = [Instruction::new(
+            program_id,
+            &0,
+            vec![
+                AccountMeta::new_readonly(readonly_key, false),
+                AccountMeta::new(static_key, false),
+                AccountMeta::new(writable_key, false),
+            ],
+        )];
+
+        let message = Message::new(&instructions, Some(&payer), &[table.clone()]);
+        // Programs are never loaded from lookup tables
+        assert_eq!(message.account_keys, vec![payer, static_key, program_id]);
+        assert_eq!(message.header.num_readonly_unsigned_accounts, 1);
+        assert_eq!(
+            message.address_table_lookups,
+            vec![MessageAddressTableLookup {
+                account_key: table.key,
+                writable_indexes: vec![2],
+                readonly_indexes: vec![1],
+            }]
+        );
+        assert_eq!(
+            message.instructions,
+            vec![CompiledInstruction::new(2, &0, vec![4, 1, 3])]
+        );
+        assert_eq!(message.num_loaded_addresses(), 2);
+        assert_eq!(message.sanitize(), Ok(()));
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let message = Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![payer, program_id],
+            recent_blockhash: Hash::default(),
+            instructions: vec![CompiledInstruction::new(1, &(), vec![0, 2])],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![],
+            }],
+        };
+        assert_eq!(message.sanitize(), Ok(()));
+
+        // Programs cannot be loaded from lookup tables
+        let mut invalid_message = message.clone();
+        invalid_message.instructions[0].program_id_index = 2;
+        assert_eq!(
+            invalid_message.sanitize(),
+            Err(SanitizeError::IndexOutOfBounds)
+        );
+
+        // Instructions cannot reference accounts beyond the loaded addresses
+        let mut invalid_message = message.clone();
+        invalid_message.instructions[0].accounts = vec![0, 3];
+        assert_eq!(
+            invalid_message.sanitize(),
+            Err(SanitizeError::IndexOutOfBounds)
+        );
+
+        // Lookups must load at least one address
+        let mut invalid_message = message.clone();
+        invalid_message
+            .address_table_lookups
+            .push(MessageAddressTableLookup::default());
+        assert_eq!(invalid_message.sanitize(), Err(SanitizeError::InvalidValue));
+
+        // Messages cannot reference more accounts than instructions can index
+        let mut invalid_message = message;
+        invalid_message.address_table_lookups[0].readonly_indexes =
+            vec![0; MAX_MESSAGE_ACCOUNT_KEYS - 2];
+        assert_eq!(
+            invalid_message.sanitize(),
+            Err(SanitizeError::IndexOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn test_into_legacy_message() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let writable_key = Pubkey::new_unique();
+        let readonly_key = Pubkey::new_unique();
+        let static_key = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![readonly_key, writable_key],
+        };
+        let instructions = [Instruction::new(
+            program_id,
+            &0,
+            vec![
+                AccountMeta::new_readonly(readonly_key, false),
+                AccountMeta::new(static_key, false),
+                AccountMeta::new(writable_key, false),
+            ],
+        )];
+        let message = Message::new(&instructions, Some(&payer), &[table]);
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![writable_key],
+            readonly: vec![readonly_key],
+        };
+
+        let legacy_message = message
+            .clone()
+            .into_legacy_message(&loaded_addresses)
+            .unwrap();
+        assert_eq!(
+            legacy_message.account_keys,
+            vec![payer, static_key, writable_key, program_id, readonly_key]
+        );
+        assert_eq!(legacy_message.header.num_required_signatures, 1);
+        assert_eq!(legacy_message.header.num_readonly_unsigned_accounts, 2);
+        assert_eq!(
+            legacy_message.instructions,
+            vec![CompiledInstruction::new(3, &0, vec![4, 1, 2])]
+        );
+        assert_eq!(legacy_message.sanitize(), Ok(()));
+        assert_eq!(
+            legacy_message.get_account_keys_by_lock_type(),
+            (
+                vec![&payer, &static_key, &writable_key],
+                vec![&program_id, &readonly_key]
+            )
+        );
+
+        // The loaded addresses must match the lookups
+        assert_eq!(
+            message.clone().into_legacy_message(&LoadedAddresses {
+                writable: vec![writable_key, readonly_key],
+                readonly: vec![],
+            }),
+            None
+        );
+        assert_eq!(
+            message.into_legacy_message(&LoadedAddresses::default()),
+            None
+        );
+    }
+}
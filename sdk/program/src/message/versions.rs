@@ -0,0 +1,284 @@
+use crate::sanitize::{Sanitize, SanitizeError};
+use crate::{
+    hash::Hash,
+    instruction::CompiledInstruction,
+    message::{
+        v0::{self, MessageAddressTableLookup},
+        Message, MessageHeader,
+    },
+    pubkey::Pubkey,
+    short_vec::{self, ShortVec},
+};
+use serde::{
+    de::{self, Deserializer, SeqAccess, Visitor},
+    ser::{SerializeTuple, Serializer},
+    Deserialize, Serialize,
+};
+use std::fmt;
+
+/// Bit set in the first byte of a versioned message.  The first byte of a legacy
+/// message is its number of required signatures, which never has this bit set.
+pub const MESSAGE_VERSION_PREFIX: u8 = 0x80;
+
+/// Either a legacy message or a versioned message.  Versioned messages are
+/// serialized after a `MESSAGE_VERSION_PREFIX` byte that also carries their
+/// version, legacy messages are serialized exactly like `Message`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum VersionedMessage {
+    Legacy(Message),
+    V0(v0::Message),
+}
+
+impl Default for VersionedMessage {
+    fn default() -> Self {
+        Self::Legacy(Message::default())
+    }
+}
+
+impl From<Message> for VersionedMessage {
+    fn from(message: Message) -> Self {
+        Self::Legacy(message)
+    }
+}
+
+impl Sanitize for VersionedMessage {
+    fn sanitize(&self) -> std::result::Result<(), SanitizeError> {
+        match self {
+            Self::Legacy(message) => message.sanitize(),
+            Self::V0(message) => message.sanitize(),
+        }
+    }
+}
+
+impl VersionedMessage {
+    pub fn header(&self) -> &MessageHeader {
+        match self {
+            Self::Legacy(message) => &message.header,
+            Self::V0(message) => &message.header,
+        }
+    }
+
+    /// The account keys listed in the message itself, excluding any addresses
+    /// loaded from lookup tables
+    pub fn static_account_keys(&self) -> &[Pubkey] {
+        match self {
+            Self::Legacy(message) => &message.account_keys,
+            Self::V0(message) => &message.account_keys,
+        }
+    }
+
+    pub fn recent_blockhash(&self) -> &Hash {
+        match self {
+            Self::Legacy(message) => &message.recent_blockhash,
+            Self::V0(message) => &message.recent_blockhash,
+        }
+    }
+
+    pub fn instructions(&self) -> &[CompiledInstruction] {
+        match self {
+            Self::Legacy(message) => &message.instructions,
+            Self::V0(message) => &message.instructions,
+        }
+    }
+
+    /// Lookups of accounts in address lookup tables, `None` for legacy messages
+    pub fn address_table_lookups(&self) -> Option<&[MessageAddressTableLookup]> {
+        match self {
+            Self::Legacy(_) => None,
+            Self::V0(message) => Some(&message.address_table_lookups),
+        }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+}
+
+impl Serialize for VersionedMessage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Legacy(message) => Serialize::serialize(message, serializer),
+            Self::V0(message) if serializer.is_human_readable() => {
+                Serialize::serialize(message, serializer)
+            }
+            Self::V0(message) => {
+                let mut seq = serializer.serialize_tuple(2)?;
+                seq.serialize_element(&MESSAGE_VERSION_PREFIX)?;
+                seq.serialize_element(message)?;
+                seq.end()
+            }
+        }
+    }
+}
+
+/// Field-wise representation of a message in human-readable formats, where
+/// only versioned messages have address table lookups
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageFields {
+    header: MessageHeader,
+    #[serde(with = "short_vec")]
+    account_keys: Vec<Pubkey>,
+    recent_blockhash: Hash,
+    #[serde(with = "short_vec")]
+    instructions: Vec<CompiledInstruction>,
+    #[serde(default)]
+    address_table_lookups: Option<ShortVec<MessageAddressTableLookup>>,
+}
+
+struct MessageVisitor;
+
+impl<'de> Visitor<'de> for MessageVisitor {
+    type Value = VersionedMessage;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a legacy or version 0 message")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<VersionedMessage, A::Error> {
+        fn next<'de, A: SeqAccess<'de>, T: Deserialize<'de>>(
+            seq: &mut A,
+            index: usize,
+        ) -> Result<T, A::Error> {
+            seq.next_element()?
+                .ok_or_else(|| de::Error::invalid_length(index, &MessageVisitor))
+        }
+
+        let prefix: u8 = next(&mut seq, 0)?;
+        if prefix & MESSAGE_VERSION_PREFIX != 0 {
+            let version = prefix & !MESSAGE_VERSION_PREFIX;
+            if version != 0 {
+                return Err(de::Error::custom(format!(
+                    "unsupported message version: {}",
+                    version
+                )));
+            }
+            Ok(VersionedMessage::V0(next(&mut seq, 1)?))
+        } else {
+            let header = MessageHeader {
+                num_required_signatures: prefix,
+                num_readonly_signed_accounts: next(&mut seq, 1)?,
+                num_readonly_unsigned_accounts: next(&mut seq, 2)?,
+            };
+            let ShortVec(account_keys) = next(&mut seq, 3)?;
+            let recent_blockhash = next(&mut seq, 4)?;
+            let ShortVec(instructions) = next(&mut seq, 5)?;
+            Ok(VersionedMessage::Legacy(Message {
+                header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+            }))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for VersionedMessage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let MessageFields {
+                header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+                address_table_lookups,
+            } = MessageFields::deserialize(deserializer)?;
+            Ok(match address_table_lookups {
+                None => Self::Legacy(Message {
+                    header,
+                    account_keys,
+                    recent_blockhash,
+                    instructions,
+                }),
+                Some(ShortVec(address_table_lookups)) => Self::V0(v0::Message {
+                    header,
+                    account_keys,
+                    recent_blockhash,
+                    instructions,
+                    address_table_lookups,
+                }),
+            })
+        } else {
+            deserializer.deserialize_tuple(std::usize::MAX, MessageVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        address_lookup_table_account::AddressLookupTableAccount,
+        instruction::{AccountMeta, Instruction},
+    };
+
+    fn test_instructions(accounts: &[Pubkey]) -> Vec<Instruction> {
+        vec![Instruction::new(
+            Pubkey::new_unique(),
+            &0,
+            vec![
+                AccountMeta::new(accounts[0], false),
+                AccountMeta::new_readonly(accounts[1], false),
+            ],
+        )]
+    }
+
+    #[test]
+    fn test_legacy_message_serialization() {
+        let payer = Pubkey::new_unique();
+        let accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let message = Message::new(&test_instructions(&accounts), Some(&payer));
+        let versioned_message = VersionedMessage::from(message.clone());
+
+        // Legacy messages serialize without a prefix
+        assert_eq!(versioned_message.serialize(), message.serialize());
+        assert_eq!(
+            bincode::deserialize::<VersionedMessage>(&message.serialize()).unwrap(),
+            versioned_message
+        );
+        assert_eq!(
+            serde_json::to_string(&versioned_message).unwrap(),
+            serde_json::to_string(&message).unwrap()
+        );
+        assert_eq!(
+            serde_json::from_str::<VersionedMessage>(&serde_json::to_string(&message).unwrap())
+                .unwrap(),
+            versioned_message
+        );
+    }
+
+    #[test]
+    fn test_v0_message_serialization() {
+        let payer = Pubkey::new_unique();
+        let accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let message = v0::Message::new(
+            &test_instructions(&accounts),
+            Some(&payer),
+            &[AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: accounts.to_vec(),
+            }],
+        );
+        let versioned_message = VersionedMessage::V0(message.clone());
+
+        let serialized = versioned_message.serialize();
+        assert_eq!(serialized[0], MESSAGE_VERSION_PREFIX);
+        assert_eq!(&serialized[1..], &bincode::serialize(&message).unwrap()[..]);
+        assert_eq!(
+            bincode::deserialize::<VersionedMessage>(&serialized).unwrap(),
+            versioned_message
+        );
+        assert_eq!(
+            serde_json::from_str::<VersionedMessage>(
+                &serde_json::to_string(&versioned_message).unwrap()
+            )
+            .unwrap(),
+            versioned_message
+        );
+
+        // Only version 0 is supported
+        let mut unsupported_version = serialized;
+        unsupported_version[0] = MESSAGE_VERSION_PREFIX | 1;
+        assert!(bincode::deserialize::<VersionedMessage>(&unsupported_version).is_err());
+    }
+}
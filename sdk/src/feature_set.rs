@@ -277,6 +277,18 @@ pub mod matching_buffer_upgrade_authorities {
     solana_sdk::declare_id!("B5PSjDEJvKJEUQSL7q94N7XCEoWJCYum8XfUg7yuugUU");
 }
 
+pub mod compute_budget_program_enabled {
+    solana_sdk::declare_id!("4d5AKtxoh93Dwm1vHXUU3iRATuMndx1c431KgT2td52r");
+}
+
+pub mod versioned_tx_message_enabled {
+    solana_sdk::declare_id!("9BJLxudJjjektJbvAzfCFq2fpbuuyS1Mp9mQdwnpqanv");
+}
+
+pub mod return_data_syscall_enabled {
+    solana_sdk::declare_id!("CB5miLnGCr71dtRPfWN6MJjWmwMBUzTy2Q4KZQs6XdL8");
+}
+
 lazy_static! {
     /// Map of feature identifiers to user-visible description
     pub static ref FEATURE_NAMES: HashMap<Pubkey, &'static str> = [
@@ -318,6 +330,9 @@ lazy_static! {
         (track_writable_deescalation::id(), "track account writable deescalation"),
         (spl_token_v2_self_transfer_fix::id(), "spl-token self-transfer fix"),
         (matching_buffer_upgrade_authorities::id(), "Upgradeable buffer and program authorities must match"),
+        (compute_budget_program_enabled::id(), "compute budget program and prioritization fees"),
+        (versioned_tx_message_enabled::id(), "versioned transaction messages and address lookup tables"),
+        (return_data_syscall_enabled::id(), "enable sol_{set,get}_return_data syscall"),
         (full_inflation::candidate_example::vote::id(), "Community vote allowing candidate_example to enable full inflation"),
         (full_inflation::candidate_example::enable::id(), "Full inflation enabled by candidate_example"),
         (full_inflation::bl::enable::id(), "Full inflation enabled by BL"),
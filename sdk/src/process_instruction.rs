@@ -64,6 +64,18 @@ pub trait InvokeContext {
     fn is_feature_active(&self, feature_id: &Pubkey) -> bool;
     /// Get an account from a pre-account
     fn get_account(&self, pubkey: &Pubkey) -> Option<RefCell<Account>>;
+    /// Set the return data of the currently executing program
+    fn set_return_data(&mut self, data: Vec<u8>) -> Result<(), InstructionError>;
+    /// Get the return data, along with the id of the program that set it
+    fn get_return_data(&self) -> (Pubkey, &[u8]);
+}
+
+/// The return data left by the last instruction of a transaction
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionReturnData {
+    pub program_id: Pubkey,
+    pub data: Vec<u8>,
 }
 
 /// Convenience macro to log a message with an `Rc<RefCell<dyn Logger>>`
@@ -124,6 +136,10 @@ pub struct BpfComputeBudget {
     pub log_pubkey_units: u64,
     /// Maximum cross-program invocation instruction size
     pub max_cpi_instruction_size: usize,
+    /// Base number of compute units consumed by the return data syscalls
+    pub syscall_base_cost: u64,
+    /// Number of return data bytes copied per compute unit consumed
+    pub return_data_bytes_per_unit: u64,
 }
 impl Default for BpfComputeBudget {
     fn default() -> Self {
@@ -147,6 +163,8 @@ impl BpfComputeBudget {
             stack_frame_size: 4_096,
             log_pubkey_units: 0,
             max_cpi_instruction_size: std::usize::MAX,
+            syscall_base_cost: 100,
+            return_data_bytes_per_unit: 250,
         };
 
         if feature_set.is_active(&bpf_compute_budget_balancing::id()) {
@@ -310,6 +328,7 @@ pub struct MockInvokeContext {
     pub compute_meter: MockComputeMeter,
     pub programs: Vec<(Pubkey, ProcessInstructionWithContext)>,
     pub invoke_depth: usize,
+    pub return_data: (Pubkey, Vec<u8>),
 }
 impl Default for MockInvokeContext {
     fn default() -> Self {
@@ -322,6 +341,7 @@ impl Default for MockInvokeContext {
             },
             programs: vec![],
             invoke_depth: 0,
+            return_data: (Pubkey::default(), vec![]),
         }
     }
 }
@@ -371,4 +391,11 @@ impl InvokeContext for MockInvokeContext {
     fn get_account(&self, _pubkey: &Pubkey) -> Option<RefCell<Account>> {
         None
     }
+    fn set_return_data(&mut self, data: Vec<u8>) -> Result<(), InstructionError> {
+        self.return_data = (self.key, data);
+        Ok(())
+    }
+    fn get_return_data(&self) -> (Pubkey, &[u8]) {
+        (self.return_data.0, &self.return_data.1)
+    }
 }
@@ -19,6 +19,10 @@ use crate::{
 use std::result;
 use thiserror::Error;
 
+mod versioned;
+
+pub use versioned::VersionedTransaction;
+
 /// Reasons a transaction might be rejected.
 #[derive(Error, Serialize, Deserialize, Debug, PartialEq, Eq, Clone, AbiExample, AbiEnumVisitor)]
 pub enum TransactionError {
@@ -92,6 +96,34 @@ pub enum TransactionError {
 
     #[error("Transactions are currently disabled due to cluster maintenance")]
     ClusterMaintenance,
+
+    /// Transaction version is unsupported
+    #[error("Transaction version is unsupported")]
+    UnsupportedVersion,
+
+    /// Transaction loads an address table account that doesn't exist
+    #[error("Transaction loads an address table account that doesn't exist")]
+    AddressLookupTableNotFound,
+
+    /// Transaction loads an address table account with an invalid owner
+    #[error("Transaction loads an address table account with an invalid owner")]
+    InvalidAddressLookupTableOwner,
+
+    /// Transaction loads an address table account with invalid data
+    #[error("Transaction loads an address table account with invalid data")]
+    InvalidAddressLookupTableData,
+
+    /// Transaction address table lookup uses an invalid index
+    #[error("Transaction address table lookup uses an invalid index")]
+    InvalidAddressLookupTableIndex,
+
+    /// Transaction would exceed the max block cost limit
+    #[error("Transaction would exceed max block cost limit")]
+    WouldExceedMaxBlockCostLimit,
+
+    /// Transaction would exceed the max cost limit of one of its writable accounts
+    #[error("Transaction would exceed max account cost limit")]
+    WouldExceedMaxAccountCostLimit,
 }
 
 pub type Result<T> = result::Result<T, TransactionError>;
@@ -337,25 +369,7 @@ impl Transaction {
     }
 
     pub fn verify_precompiles(&self) -> Result<()> {
-        for instruction in &self.message().instructions {
-            // The Transaction may not be sanitized at this point
-            if instruction.program_id_index as usize >= self.message().account_keys.len() {
-                return Err(TransactionError::AccountNotFound);
-            }
-            let program_id = &self.message().account_keys[instruction.program_id_index as usize];
-            if crate::secp256k1_program::check_id(program_id) {
-                let instruction_datas: Vec<_> = self
-                    .message()
-                    .instructions
-                    .iter()
-                    .map(|instruction| instruction.data.as_ref())
-                    .collect();
-                let data = &instruction.data;
-                let e = verify_eth_addresses(data, &instruction_datas);
-                e.map_err(|_| TransactionError::InvalidAccountIndex)?;
-            }
-        }
-        Ok(())
+        verify_precompiles(&self.message.account_keys, &self.message.instructions)
     }
 
     /// Get the positions of the pubkeys in `account_keys` associated with signing keypairs
@@ -400,6 +414,26 @@ impl Transaction {
     }
 }
 
+fn verify_precompiles(account_keys: &[Pubkey], instructions: &[CompiledInstruction]) -> Result<()> {
+    for instruction in instructions {
+        // The Transaction may not be sanitized at this point
+        if instruction.program_id_index as usize >= account_keys.len() {
+            return Err(TransactionError::AccountNotFound);
+        }
+        let program_id = &account_keys[instruction.program_id_index as usize];
+        if crate::secp256k1_program::check_id(program_id) {
+            let instruction_datas: Vec<_> = instructions
+                .iter()
+                .map(|instruction| instruction.data.as_ref())
+                .collect();
+            let data = &instruction.data;
+            let e = verify_eth_addresses(data, &instruction_datas);
+            e.map_err(|_| TransactionError::InvalidAccountIndex)?;
+        }
+    }
+    Ok(())
+}
+
 pub fn uses_durable_nonce(tx: &Transaction) -> Option<&CompiledInstruction> {
     let message = tx.message();
     message
@@ -0,0 +1,251 @@
+//! A transaction carrying either a legacy or a versioned message
+
+use super::{verify_precompiles, Result, Transaction, TransactionError};
+use crate::sanitize::{Sanitize, SanitizeError};
+use crate::{
+    message::VersionedMessage,
+    short_vec,
+    signature::{Signature, SignerError},
+    signers::Signers,
+};
+use std::result;
+
+/// An atomic transaction whose message may be versioned.  Transactions with a
+/// legacy message serialize exactly like `Transaction`.
+#[derive(Debug, PartialEq, Default, Eq, Clone, Serialize, Deserialize)]
+pub struct VersionedTransaction {
+    /// Signatures of the serialized message, by the first
+    /// `num_required_signatures` static account keys of the message
+    #[serde(with = "short_vec")]
+    pub signatures: Vec<Signature>,
+
+    /// The message to sign.
+    pub message: VersionedMessage,
+}
+
+impl Sanitize for VersionedTransaction {
+    fn sanitize(&self) -> result::Result<(), SanitizeError> {
+        if self.message.header().num_required_signatures as usize > self.signatures.len() {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+        if self.signatures.len() > self.message.static_account_keys().len() {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+        self.message.sanitize()
+    }
+}
+
+impl From<Transaction> for VersionedTransaction {
+    fn from(transaction: Transaction) -> Self {
+        Self {
+            signatures: transaction.signatures,
+            message: VersionedMessage::Legacy(transaction.message),
+        }
+    }
+}
+
+impl VersionedTransaction {
+    /// Sign `message` with all of its required signers
+    pub fn try_new<T: Signers>(
+        message: VersionedMessage,
+        keypairs: &T,
+    ) -> result::Result<Self, SignerError> {
+        let num_required_signatures = message.header().num_required_signatures as usize;
+        let static_account_keys = message.static_account_keys();
+        if static_account_keys.len() < num_required_signatures {
+            return Err(SignerError::InvalidInput("invalid message".to_string()));
+        }
+        let signer_keys = &static_account_keys[..num_required_signatures];
+        let positions = keypairs
+            .try_pubkeys()?
+            .iter()
+            .map(|pubkey| {
+                signer_keys
+                    .iter()
+                    .position(|signer_key| signer_key == pubkey)
+                    .ok_or(SignerError::KeypairPubkeyMismatch)
+            })
+            .collect::<result::Result<Vec<_>, _>>()?;
+
+        let mut signatures = vec![Signature::default(); num_required_signatures];
+        for (position, signature) in positions
+            .into_iter()
+            .zip(keypairs.try_sign_message(&message.serialize())?)
+        {
+            signatures[position] = signature;
+        }
+        if signatures
+            .iter()
+            .any(|signature| *signature == Signature::default())
+        {
+            return Err(SignerError::NotEnoughSigners);
+        }
+
+        Ok(Self {
+            signatures,
+            message,
+        })
+    }
+
+    /// The legacy transaction, or `None` if the message is versioned
+    pub fn into_legacy_transaction(self) -> Option<Transaction> {
+        match self.message {
+            VersionedMessage::Legacy(message) => Some(Transaction {
+                signatures: self.signatures,
+                message,
+            }),
+            VersionedMessage::V0(_) => None,
+        }
+    }
+
+    /// Return the serialized message data to sign.
+    pub fn message_data(&self) -> Vec<u8> {
+        self.message.serialize()
+    }
+
+    pub fn verify_with_results(&self) -> Vec<bool> {
+        let message_data = self.message_data();
+        self.signatures
+            .iter()
+            .zip(self.message.static_account_keys())
+            .map(|(signature, pubkey)| signature.verify(pubkey.as_ref(), &message_data))
+            .collect()
+    }
+
+    /// Verify the transaction
+    pub fn verify(&self) -> Result<()> {
+        if !self
+            .verify_with_results()
+            .iter()
+            .all(|verify_result| *verify_result)
+        {
+            Err(TransactionError::SignatureFailure)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Precompiled programs are never loaded from lookup tables, so their
+    /// instructions can be verified before the message's addresses are loaded
+    pub fn verify_precompiles(&self) -> Result<()> {
+        verify_precompiles(
+            self.message.static_account_keys(),
+            self.message.instructions(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        address_lookup_table_account::AddressLookupTableAccount,
+        hash::Hash,
+        message::{v0, Message, MESSAGE_VERSION_PREFIX},
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_instruction,
+    };
+
+    fn test_v0_message(payer: &Pubkey) -> v0::Message {
+        let recipient = Pubkey::new_unique();
+        v0::Message::new(
+            &[system_instruction::transfer(payer, &recipient, 1)],
+            Some(payer),
+            &[AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![recipient],
+            }],
+        )
+    }
+
+    #[test]
+    fn test_legacy_transaction_serialization() {
+        let keypair = Keypair::new();
+        let message = Message::new(
+            &[system_instruction::transfer(
+                &keypair.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(&keypair.pubkey()),
+        );
+        let transaction = Transaction::new(&[&keypair], message, Hash::default());
+        let versioned_transaction = VersionedTransaction::from(transaction.clone());
+
+        let serialized = bincode::serialize(&versioned_transaction).unwrap();
+        assert_eq!(serialized, bincode::serialize(&transaction).unwrap());
+        assert_eq!(
+            bincode::deserialize::<VersionedTransaction>(&serialized).unwrap(),
+            versioned_transaction
+        );
+        assert_eq!(
+            versioned_transaction.message_data(),
+            transaction.message_data()
+        );
+        assert_eq!(versioned_transaction.verify(), Ok(()));
+        assert_eq!(
+            versioned_transaction.into_legacy_transaction(),
+            Some(transaction)
+        );
+    }
+
+    #[test]
+    fn test_v0_transaction() {
+        let keypair = Keypair::new();
+        let message = VersionedMessage::V0(test_v0_message(&keypair.pubkey()));
+        let transaction = VersionedTransaction::try_new(message, &[&keypair]).unwrap();
+        assert_eq!(transaction.sanitize(), Ok(()));
+        assert_eq!(transaction.verify(), Ok(()));
+        assert_eq!(transaction.verify_precompiles(), Ok(()));
+
+        let serialized = bincode::serialize(&transaction).unwrap();
+        assert_eq!(serialized[1 + 64], MESSAGE_VERSION_PREFIX);
+        assert_eq!(
+            bincode::deserialize::<VersionedTransaction>(&serialized).unwrap(),
+            transaction
+        );
+        assert_eq!(transaction.clone().into_legacy_transaction(), None);
+
+        // The signature covers the version prefix
+        let mut tampered_transaction = transaction;
+        if let VersionedMessage::V0(message) = &tampered_transaction.message {
+            let message = Message {
+                header: message.header.clone(),
+                account_keys: message.account_keys.clone(),
+                recent_blockhash: message.recent_blockhash,
+                instructions: message.instructions.clone(),
+            };
+            tampered_transaction.message = VersionedMessage::Legacy(message);
+        }
+        assert_eq!(
+            tampered_transaction.verify(),
+            Err(TransactionError::SignatureFailure)
+        );
+    }
+
+    #[test]
+    fn test_try_new_signers() {
+        let payer = Keypair::new();
+        let sender = Keypair::new();
+        let message = VersionedMessage::V0(v0::Message::new(
+            &[system_instruction::transfer(
+                &sender.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(&payer.pubkey()),
+            &[],
+        ));
+        assert_eq!(
+            VersionedTransaction::try_new(message.clone(), &[&payer, &Keypair::new()]).unwrap_err(),
+            SignerError::KeypairPubkeyMismatch
+        );
+        assert_eq!(
+            VersionedTransaction::try_new(message.clone(), &[&payer]).unwrap_err(),
+            SignerError::NotEnoughSigners
+        );
+        let transaction = VersionedTransaction::try_new(message, &[&sender, &payer]).unwrap();
+        assert_eq!(transaction.verify(), Ok(()));
+    }
+}
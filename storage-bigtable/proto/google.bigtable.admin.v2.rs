@@ -0,0 +1,105 @@
+// The subset of `google/bigtable/admin/v2/bigtable_table_admin.proto` and
+// `google/bigtable/admin/v2/table.proto` needed to create tables, in the form generated by
+// tonic-build.  The full service depends on the IAM and long-running operation protos, which
+// are not otherwise required.
+
+/// Request message for
+/// [google.bigtable.admin.v2.BigtableTableAdmin.CreateTable][google.bigtable.admin.v2.BigtableTableAdmin.CreateTable]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateTableRequest {
+    /// Required. The unique name of the instance in which to create the table.
+    /// Values are of the form `projects/{project}/instances/{instance}`.
+    #[prost(string, tag = "1")]
+    pub parent: ::prost::alloc::string::String,
+    /// Required. The name by which the new table should be referred to within the parent
+    /// instance, e.g., `foobar` rather than `{parent}/tables/foobar`.
+    /// Maximum 50 characters.
+    #[prost(string, tag = "2")]
+    pub table_id: ::prost::alloc::string::String,
+    /// Required. The Table to create.
+    #[prost(message, optional, tag = "3")]
+    pub table: ::core::option::Option<Table>,
+}
+/// A collection of user data indexed by row, column, and timestamp.
+/// Each table is served using the resources of its parent cluster.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Table {
+    /// The unique name of the table. Values are of the form
+    /// `projects/{project}/instances/{instance}/tables/[_a-zA-Z0-9][-_.a-zA-Z0-9]*`.
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// The column families configured for this table, mapped by column family ID.
+    #[prost(map = "string, message", tag = "3")]
+    pub column_families:
+        ::std::collections::HashMap<::prost::alloc::string::String, ColumnFamily>,
+}
+/// A set of columns within a table which share a common configuration.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ColumnFamily {
+    /// Garbage collection rule specified as a protobuf.
+    /// Must serialize to at most 500 bytes.
+    #[prost(message, optional, tag = "1")]
+    pub gc_rule: ::core::option::Option<GcRule>,
+}
+/// Rule for determining which cells to delete during garbage collection.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GcRule {
+    /// Garbage collection rules.
+    #[prost(oneof = "gc_rule::Rule", tags = "1, 2")]
+    pub rule: ::core::option::Option<gc_rule::Rule>,
+}
+/// Nested message and enum types in `GcRule`.
+pub mod gc_rule {
+    /// Garbage collection rules.
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Rule {
+        /// Delete all cells in a column except the most recent N.
+        #[prost(int32, tag = "1")]
+        MaxNumVersions(i32),
+        /// Delete cells in a column older than the given age.
+        /// Values must be at least one millisecond, and will be truncated to
+        /// microsecond granularity.
+        #[prost(message, tag = "2")]
+        MaxAge(::prost_types::Duration),
+    }
+}
+#[doc = r" Generated client implementations."]
+pub mod bigtable_table_admin_client {
+    #![allow(unused_variables, dead_code, missing_docs)]
+    use tonic::codegen::*;
+    #[doc = " Service for creating, configuring, and deleting Cloud Bigtable tables."]
+    pub struct BigtableTableAdminClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl<T> BigtableTableAdminClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::ResponseBody: Body + HttpBody + Send + 'static,
+        T::Error: Into<StdError>,
+        <T::ResponseBody as HttpBody>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        #[doc = " Creates a new table in the specified instance."]
+        #[doc = " The table can be created with a full set of initial column families,"]
+        #[doc = " specified in the request."]
+        pub async fn create_table(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateTableRequest>,
+        ) -> Result<tonic::Response<super::Table>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/google.bigtable.admin.v2.BigtableTableAdmin/CreateTable",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
+    }
+}
@@ -0,0 +1,114 @@
+// Storage backends holding the `blocks`, `tx` and `tx-by-addr` tables of a `LedgerStorage`
+
+use crate::{
+    bigtable::{
+        deserialize_bincode_cell_data, deserialize_protobuf_or_bincode_cell_data, CellData, Result,
+        RowData, RowKey,
+    },
+    compression::compress_best,
+};
+use async_trait::async_trait;
+
+/// Row-level access to a set of BigTable-like tables.  Rows are kept in lexical order of their
+/// keys, and each row holds a number of named cells.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Get `table` row keys in lexical order.
+    ///
+    /// If `start_at` is provided, the row key listing will start with key.
+    /// Otherwise the listing will start from the start of the table.
+    ///
+    /// If `end_at` is provided, the row key listing will end at the key. Otherwise it will
+    /// continue until the `rows_limit` is reached or the end of the table, whichever comes first.
+    /// If `rows_limit` is zero, the listing will continue until the end of the table.
+    async fn get_row_keys(
+        &self,
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<RowKey>>;
+
+    /// Get latest data from `table`, with the same range semantics as `get_row_keys`
+    async fn get_row_data(
+        &self,
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<(RowKey, RowData)>>;
+
+    /// Get latest data from a single row of `table`, if that row exists. Returns
+    /// `Error::RowNotFound` if that row does not exist.
+    async fn get_single_row_data(&self, table_name: &str, row_key: RowKey) -> Result<RowData>;
+
+    /// Store the cells of one or more `table` rows.  Cells not named in `row_data` are left as-is.
+    async fn put_row_data(&self, table_name: &str, row_data: &[(&RowKey, RowData)]) -> Result<()>;
+}
+
+pub async fn get_bincode_cell<T>(
+    backend: &dyn StorageBackend,
+    table: &str,
+    key: RowKey,
+) -> Result<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let row_data = backend.get_single_row_data(table, key.clone()).await?;
+    deserialize_bincode_cell_data(&row_data, table, key)
+}
+
+pub async fn get_protobuf_or_bincode_cell<B, P>(
+    backend: &dyn StorageBackend,
+    table: &str,
+    key: RowKey,
+) -> Result<CellData<B, P>>
+where
+    B: serde::de::DeserializeOwned,
+    P: prost::Message + Default,
+{
+    let row_data = backend.get_single_row_data(table, key.clone()).await?;
+    deserialize_protobuf_or_bincode_cell_data(&row_data, table, key)
+}
+
+pub async fn put_bincode_cells<T>(
+    backend: &dyn StorageBackend,
+    table: &str,
+    cells: &[(RowKey, T)],
+) -> Result<usize>
+where
+    T: serde::ser::Serialize,
+{
+    let mut bytes_written = 0;
+    let mut new_row_data = vec![];
+    for (row_key, data) in cells {
+        let data = compress_best(&bincode::serialize(&data).unwrap())?;
+        bytes_written += data.len();
+        new_row_data.push((row_key, vec![("bin".to_string(), data)]));
+    }
+
+    backend.put_row_data(table, &new_row_data).await?;
+    Ok(bytes_written)
+}
+
+pub async fn put_protobuf_cells<T>(
+    backend: &dyn StorageBackend,
+    table: &str,
+    cells: &[(RowKey, T)],
+) -> Result<usize>
+where
+    T: prost::Message,
+{
+    let mut bytes_written = 0;
+    let mut new_row_data = vec![];
+    for (row_key, data) in cells {
+        let mut buf = Vec::with_capacity(data.encoded_len());
+        data.encode(&mut buf).unwrap();
+        let data = compress_best(&buf)?;
+        bytes_written += data.len();
+        new_row_data.push((row_key, vec![("proto".to_string(), data)]));
+    }
+
+    backend.put_row_data(table, &new_row_data).await?;
+    Ok(bytes_written)
+}
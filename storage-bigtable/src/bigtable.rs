@@ -2,9 +2,11 @@
 
 use crate::{
     access_token::{AccessToken, Scope},
-    compression::{compress_best, decompress},
+    backend::StorageBackend,
+    compression::decompress,
     root_ca_certificate,
 };
+use async_trait::async_trait;
 use log::*;
 use std::time::{Duration, Instant};
 use thiserror::Error;
@@ -18,6 +20,14 @@ mod google {
         ));
     }
     pub mod bigtable {
+        pub mod admin {
+            pub mod v2 {
+                include!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    concat!("/proto/google.bigtable.admin.v2.rs")
+                ));
+            }
+        }
         pub mod v2 {
             include!(concat!(
                 env!("CARGO_MANIFEST_DIR"),
@@ -26,7 +36,10 @@ mod google {
         }
     }
 }
-use google::bigtable::v2::*;
+use google::bigtable::{admin::v2 as admin, v2::*};
+
+// Column family holding the cells written by this crate
+const FAMILY_NAME: &str = "x";
 
 pub type RowKey = String;
 pub type RowData = Vec<(CellName, CellValue)>;
@@ -72,6 +85,9 @@ pub enum Error {
 
     #[error("Timeout error")]
     TimeoutError,
+
+    #[error("RocksDB error: {0}")]
+    RocksDbError(rocksdb::Error),
 }
 
 impl std::convert::From<std::io::Error> for Error {
@@ -86,6 +102,12 @@ impl std::convert::From<tonic::transport::Error> for Error {
     }
 }
 
+impl std::convert::From<rocksdb::Error> for Error {
+    fn from(err: rocksdb::Error) -> Self {
+        Self::RocksDbError(err)
+    }
+}
+
 impl std::convert::From<tonic::Status> for Error {
     fn from(err: tonic::Status) -> Self {
         Self::RpcError(err)
@@ -109,7 +131,8 @@ impl BigTableConnection {
     /// The GOOGLE_APPLICATION_CREDENTIALS environment variable will be used to determine the
     /// program name that contains the BigTable instance in addition to access credentials.
     ///
-    /// The BIGTABLE_EMULATOR_HOST environment variable is also respected.
+    /// If the BIGTABLE_EMULATOR_HOST environment variable is set, a plaintext connection is made
+    /// to the emulator at that address instead, without any credentials.
     ///
     pub async fn new(
         instance_name: &str,
@@ -120,11 +143,21 @@ impl BigTableConnection {
             Ok(endpoint) => {
                 info!("Connecting to bigtable emulator at {}", endpoint);
 
+                let endpoint = {
+                    let endpoint =
+                        tonic::transport::Channel::from_shared(format!("http://{}", endpoint))
+                            .map_err(|err| Error::InvalidUri(endpoint, err.to_string()))?;
+
+                    if let Some(timeout) = timeout {
+                        endpoint.timeout(timeout)
+                    } else {
+                        endpoint
+                    }
+                };
+
                 Ok(Self {
                     access_token: None,
-                    channel: tonic::transport::Channel::from_shared(format!("http://{}", endpoint))
-                        .map_err(|err| Error::InvalidUri(endpoint, err.to_string()))?
-                        .connect_lazy()?,
+                    channel: endpoint.connect_lazy()?,
                     table_prefix: format!("projects/emulator/instances/{}/tables/", instance_name),
                     timeout,
                 })
@@ -173,6 +206,49 @@ impl BigTableConnection {
         }
     }
 
+    /// Whether this is a connection to the BigTable emulator
+    pub fn is_emulator(&self) -> bool {
+        self.access_token.is_none()
+    }
+
+    /// Create `table_name` with the column family written by this crate, unless it already
+    /// exists.  Production instances are configured by `init-bigtable.sh` instead, so this is
+    /// only supported by the emulator.
+    pub async fn create_table_if_missing(&self, table_name: &str) -> Result<()> {
+        assert!(self.is_emulator());
+
+        let mut column_families = std::collections::HashMap::new();
+        column_families.insert(
+            FAMILY_NAME.to_string(),
+            admin::ColumnFamily {
+                gc_rule: Some(admin::GcRule {
+                    rule: Some(admin::gc_rule::Rule::MaxNumVersions(1)),
+                }),
+            },
+        );
+
+        let mut client =
+            admin::bigtable_table_admin_client::BigtableTableAdminClient::new(self.channel.clone());
+        let result = client
+            .create_table(admin::CreateTableRequest {
+                parent: self.table_prefix.trim_end_matches("/tables/").to_string(),
+                table_id: table_name.to_string(),
+                table: Some(admin::Table {
+                    column_families,
+                    ..admin::Table::default()
+                }),
+            })
+            .await;
+        match result {
+            Ok(_) => {
+                info!("Created table {}{}", self.table_prefix, table_name);
+                Ok(())
+            }
+            Err(status) if status.code() == tonic::Code::AlreadyExists => Ok(()),
+            Err(status) => Err(status.into()),
+        }
+    }
+
     /// Create a new BigTable client.
     ///
     /// Clients require `&mut self`, due to `Tonic::transport::Channel` limitations, however
@@ -205,38 +281,6 @@ impl BigTableConnection {
             timeout: self.timeout,
         }
     }
-
-    pub async fn put_bincode_cells_with_retry<T>(
-        &self,
-        table: &str,
-        cells: &[(RowKey, T)],
-    ) -> Result<usize>
-    where
-        T: serde::ser::Serialize,
-    {
-        use backoff::{future::retry, ExponentialBackoff};
-        retry(ExponentialBackoff::default(), || async {
-            let mut client = self.client();
-            Ok(client.put_bincode_cells(table, cells).await?)
-        })
-        .await
-    }
-
-    pub async fn put_protobuf_cells_with_retry<T>(
-        &self,
-        table: &str,
-        cells: &[(RowKey, T)],
-    ) -> Result<usize>
-    where
-        T: prost::Message,
-    {
-        use backoff::{future::retry, ExponentialBackoff};
-        retry(ExponentialBackoff::default(), || async {
-            let mut client = self.client();
-            Ok(client.put_protobuf_cells(table, cells).await?)
-        })
-        .await
-    }
 }
 
 pub struct BigTable {
@@ -530,68 +574,47 @@ impl BigTable {
 
         Ok(())
     }
+}
 
-    pub async fn get_bincode_cell<T>(&mut self, table: &str, key: RowKey) -> Result<T>
-    where
-        T: serde::de::DeserializeOwned,
-    {
-        let row_data = self.get_single_row_data(table, key.clone()).await?;
-        deserialize_bincode_cell_data(&row_data, table, key.to_string())
+#[async_trait]
+impl StorageBackend for BigTableConnection {
+    async fn get_row_keys(
+        &self,
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<RowKey>> {
+        self.client()
+            .get_row_keys(table_name, start_at, end_at, rows_limit)
+            .await
     }
 
-    pub async fn get_protobuf_or_bincode_cell<B, P>(
-        &mut self,
-        table: &str,
-        key: RowKey,
-    ) -> Result<CellData<B, P>>
-    where
-        B: serde::de::DeserializeOwned,
-        P: prost::Message + Default,
-    {
-        let row_data = self.get_single_row_data(table, key.clone()).await?;
-        deserialize_protobuf_or_bincode_cell_data(&row_data, table, key)
+    async fn get_row_data(
+        &self,
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<(RowKey, RowData)>> {
+        self.client()
+            .get_row_data(table_name, start_at, end_at, rows_limit)
+            .await
     }
 
-    pub async fn put_bincode_cells<T>(
-        &mut self,
-        table: &str,
-        cells: &[(RowKey, T)],
-    ) -> Result<usize>
-    where
-        T: serde::ser::Serialize,
-    {
-        let mut bytes_written = 0;
-        let mut new_row_data = vec![];
-        for (row_key, data) in cells {
-            let data = compress_best(&bincode::serialize(&data).unwrap())?;
-            bytes_written += data.len();
-            new_row_data.push((row_key, vec![("bin".to_string(), data)]));
-        }
-
-        self.put_row_data(table, "x", &new_row_data).await?;
-        Ok(bytes_written)
+    async fn get_single_row_data(&self, table_name: &str, row_key: RowKey) -> Result<RowData> {
+        self.client().get_single_row_data(table_name, row_key).await
     }
 
-    pub async fn put_protobuf_cells<T>(
-        &mut self,
-        table: &str,
-        cells: &[(RowKey, T)],
-    ) -> Result<usize>
-    where
-        T: prost::Message,
-    {
-        let mut bytes_written = 0;
-        let mut new_row_data = vec![];
-        for (row_key, data) in cells {
-            let mut buf = Vec::with_capacity(data.encoded_len());
-            data.encode(&mut buf).unwrap();
-            let data = compress_best(&buf)?;
-            bytes_written += data.len();
-            new_row_data.push((row_key, vec![("proto".to_string(), data)]));
-        }
-
-        self.put_row_data(table, "x", &new_row_data).await?;
-        Ok(bytes_written)
+    async fn put_row_data(&self, table_name: &str, row_data: &[(&RowKey, RowData)]) -> Result<()> {
+        use backoff::{future::retry, ExponentialBackoff};
+        retry(ExponentialBackoff::default(), || async {
+            let mut client = self.client();
+            Ok(client
+                .put_row_data(table_name, FAMILY_NAME, row_data)
+                .await?)
+        })
+        .await
     }
 }
 
@@ -662,7 +685,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::StoredConfirmedBlock;
+    use crate::{compression::compress_best, StoredConfirmedBlock};
     use prost::Message;
     use solana_sdk::{hash::Hash, signature::Keypair, system_transaction};
     use solana_storage_proto::convert::generated;
@@ -677,7 +700,7 @@ mod tests {
         let recipient = solana_sdk::pubkey::new_rand();
         let transaction = system_transaction::transfer(&from, &recipient, 42, Hash::default());
         let with_meta = TransactionWithStatusMeta {
-            transaction,
+            transaction: transaction.into(),
             meta: Some(TransactionStatusMeta {
                 status: Ok(()),
                 fee: 1,
@@ -687,6 +710,8 @@ mod tests {
                 log_messages: Some(vec![]),
                 pre_token_balances: Some(vec![]),
                 post_token_balances: Some(vec![]),
+                loaded_addresses: None,
+                return_data: None,
             }),
         };
         let block = ConfirmedBlock {
@@ -5,7 +5,7 @@ use solana_sdk::{
     pubkey::Pubkey,
     signature::Signature,
     sysvar::is_sysvar_id,
-    transaction::{Transaction, TransactionError},
+    transaction::{TransactionError, VersionedTransaction},
 };
 use solana_storage_proto::convert::generated;
 use solana_storage_proto::convert::tx_by_addr;
@@ -14,17 +14,21 @@ use solana_transaction_status::{
     TransactionByAddrInfo, TransactionConfirmationStatus, TransactionStatus, TransactionStatusMeta,
     TransactionWithStatusMeta,
 };
-use std::{collections::HashMap, convert::TryInto};
+use std::{collections::HashMap, convert::TryInto, path::Path, sync::Arc};
 use thiserror::Error;
 
 #[macro_use]
 extern crate serde_derive;
 
 mod access_token;
+mod backend;
 mod bigtable;
 mod compression;
+mod local_storage;
 mod root_ca_certificate;
 
+use backend::StorageBackend;
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("BigTable: {0}")]
@@ -57,6 +61,9 @@ impl std::convert::From<std::io::Error> for Error {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+// Tables holding the ledger data
+const TABLES: &[&str] = &["blocks", "tx", "tx-by-addr"];
+
 // Convert a slot to its bucket representation whereby lower slots are always lexically ordered
 // before higher slots
 fn slot_to_key(slot: Slot) -> String {
@@ -136,7 +143,7 @@ impl From<StoredConfirmedBlock> for ConfirmedBlock {
 
 #[derive(Serialize, Deserialize)]
 struct StoredConfirmedBlockTransaction {
-    transaction: Transaction,
+    transaction: VersionedTransaction,
     meta: Option<StoredConfirmedBlockTransactionStatusMeta>,
 }
 
@@ -187,6 +194,8 @@ impl From<StoredConfirmedBlockTransactionStatusMeta> for TransactionStatusMeta {
             log_messages: None,
             pre_token_balances: None,
             post_token_balances: None,
+            loaded_addresses: None,
+            return_data: None,
         }
     }
 }
@@ -225,6 +234,7 @@ impl From<StoredConfirmedBlockReward> for Reward {
             lamports,
             post_balance: 0,
             reward_type: None,
+            commission: None,
         }
     }
 }
@@ -293,19 +303,48 @@ impl From<LegacyTransactionByAddrInfo> for TransactionByAddrInfo {
 
 #[derive(Clone)]
 pub struct LedgerStorage {
-    connection: bigtable::BigTableConnection,
+    backend: Arc<dyn StorageBackend>,
 }
 
 impl LedgerStorage {
+    /// Connect to the `solana-ledger` BigTable instance.  Missing tables are created when
+    /// connecting to the emulator.
     pub async fn new(read_only: bool, timeout: Option<std::time::Duration>) -> Result<Self> {
         let connection =
             bigtable::BigTableConnection::new("solana-ledger", read_only, timeout).await?;
-        Ok(Self { connection })
+        if connection.is_emulator() {
+            for table in TABLES {
+                connection.create_table_if_missing(table).await?;
+            }
+        }
+        Ok(Self {
+            backend: Arc::new(connection),
+        })
+    }
+
+    /// Open the same tables stored in the local RocksDB directory `path`, creating it if needed
+    pub fn new_local(path: &Path) -> Result<Self> {
+        let local_storage = local_storage::LocalStorage::open(path)?;
+        Ok(Self {
+            backend: Arc::new(local_storage),
+        })
+    }
+
+    /// Open the local directory `local_path` if provided, otherwise connect to BigTable
+    pub async fn new_with_local_path(
+        local_path: Option<&Path>,
+        read_only: bool,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<Self> {
+        match local_path {
+            Some(local_path) => Self::new_local(local_path),
+            None => Self::new(read_only, timeout).await,
+        }
     }
 
     /// Return the available slot that contains a block
     pub async fn get_first_available_block(&self) -> Result<Option<Slot>> {
-        let mut bigtable = self.connection.client();
+        let bigtable = self.backend.as_ref();
         let blocks = bigtable.get_row_keys("blocks", None, None, 1).await?;
         if blocks.is_empty() {
             return Ok(None);
@@ -319,7 +358,7 @@ impl LedgerStorage {
     /// limit: stop after this many slots have been found; if limit==0, all records in the table
     /// after start_slot will be read
     pub async fn get_confirmed_blocks(&self, start_slot: Slot, limit: usize) -> Result<Vec<Slot>> {
-        let mut bigtable = self.connection.client();
+        let bigtable = self.backend.as_ref();
         let blocks = bigtable
             .get_row_keys("blocks", Some(slot_to_key(start_slot)), None, limit as i64)
             .await?;
@@ -328,17 +367,16 @@ impl LedgerStorage {
 
     /// Fetch the confirmed block from the desired slot
     pub async fn get_confirmed_block(&self, slot: Slot) -> Result<ConfirmedBlock> {
-        let mut bigtable = self.connection.client();
-        let block_cell_data = bigtable
-            .get_protobuf_or_bincode_cell::<StoredConfirmedBlock, generated::ConfirmedBlock>(
-                "blocks",
-                slot_to_key(slot),
-            )
-            .await
-            .map_err(|err| match err {
-                bigtable::Error::RowNotFound => Error::BlockNotFound(slot),
-                _ => err.into(),
-            })?;
+        let bigtable = self.backend.as_ref();
+        let block_cell_data = backend::get_protobuf_or_bincode_cell::<
+            StoredConfirmedBlock,
+            generated::ConfirmedBlock,
+        >(bigtable, "blocks", slot_to_key(slot))
+        .await
+        .map_err(|err| match err {
+            bigtable::Error::RowNotFound => Error::BlockNotFound(slot),
+            _ => err.into(),
+        })?;
         Ok(match block_cell_data {
             bigtable::CellData::Bincode(block) => block.into(),
             bigtable::CellData::Protobuf(block) => block.try_into().map_err(|_err| {
@@ -348,14 +386,14 @@ impl LedgerStorage {
     }
 
     pub async fn get_signature_status(&self, signature: &Signature) -> Result<TransactionStatus> {
-        let mut bigtable = self.connection.client();
-        let transaction_info = bigtable
-            .get_bincode_cell::<TransactionInfo>("tx", signature.to_string())
-            .await
-            .map_err(|err| match err {
-                bigtable::Error::RowNotFound => Error::SignatureNotFound,
-                _ => err.into(),
-            })?;
+        let bigtable = self.backend.as_ref();
+        let transaction_info =
+            backend::get_bincode_cell::<TransactionInfo>(bigtable, "tx", signature.to_string())
+                .await
+                .map_err(|err| match err {
+                    bigtable::Error::RowNotFound => Error::SignatureNotFound,
+                    _ => err.into(),
+                })?;
         Ok(transaction_info.into())
     }
 
@@ -364,16 +402,16 @@ impl LedgerStorage {
         &self,
         signature: &Signature,
     ) -> Result<Option<ConfirmedTransaction>> {
-        let mut bigtable = self.connection.client();
+        let bigtable = self.backend.as_ref();
 
         // Figure out which block the transaction is located in
-        let TransactionInfo { slot, index, .. } = bigtable
-            .get_bincode_cell("tx", signature.to_string())
-            .await
-            .map_err(|err| match err {
-                bigtable::Error::RowNotFound => Error::SignatureNotFound,
-                _ => err.into(),
-            })?;
+        let TransactionInfo { slot, index, .. } =
+            backend::get_bincode_cell(bigtable, "tx", signature.to_string())
+                .await
+                .map_err(|err| match err {
+                    bigtable::Error::RowNotFound => Error::SignatureNotFound,
+                    _ => err.into(),
+                })?;
 
         // Load the block and return the transaction
         let block = self.get_confirmed_block(slot).await?;
@@ -418,16 +456,15 @@ impl LedgerStorage {
             u32, /*slot index*/
         )>,
     > {
-        let mut bigtable = self.connection.client();
+        let bigtable = self.backend.as_ref();
         let address_prefix = format!("{}/", address);
 
         // Figure out where to start listing from based on `before_signature`
         let (first_slot, before_transaction_index) = match before_signature {
             None => (Slot::MAX, 0),
             Some(before_signature) => {
-                let TransactionInfo { slot, index, .. } = bigtable
-                    .get_bincode_cell("tx", before_signature.to_string())
-                    .await?;
+                let TransactionInfo { slot, index, .. } =
+                    backend::get_bincode_cell(bigtable, "tx", before_signature.to_string()).await?;
 
                 (slot, index)
             }
@@ -437,9 +474,8 @@ impl LedgerStorage {
         let (last_slot, until_transaction_index) = match until_signature {
             None => (0, u32::MAX),
             Some(until_signature) => {
-                let TransactionInfo { slot, index, .. } = bigtable
-                    .get_bincode_cell("tx", until_signature.to_string())
-                    .await?;
+                let TransactionInfo { slot, index, .. } =
+                    backend::get_bincode_cell(bigtable, "tx", until_signature.to_string()).await?;
 
                 (slot, index)
             }
@@ -447,19 +483,20 @@ impl LedgerStorage {
 
         let mut infos = vec![];
 
-        let starting_slot_tx_len = bigtable
-            .get_protobuf_or_bincode_cell::<Vec<LegacyTransactionByAddrInfo>, tx_by_addr::TransactionByAddr>(
-                "tx-by-addr",
-                format!("{}{}", address_prefix, slot_to_key(!first_slot)),
-            )
-            .await
-            .map(|cell_data| {
-                match cell_data {
-                    bigtable::CellData::Bincode(tx_by_addr) => tx_by_addr.len(),
-                    bigtable::CellData::Protobuf(tx_by_addr) => tx_by_addr.tx_by_addrs.len(),
-                }
-            })
-            .unwrap_or(0);
+        let starting_slot_tx_len = backend::get_protobuf_or_bincode_cell::<
+            Vec<LegacyTransactionByAddrInfo>,
+            tx_by_addr::TransactionByAddr,
+        >(
+            bigtable,
+            "tx-by-addr",
+            format!("{}{}", address_prefix, slot_to_key(!first_slot)),
+        )
+        .await
+        .map(|cell_data| match cell_data {
+            bigtable::CellData::Bincode(tx_by_addr) => tx_by_addr.len(),
+            bigtable::CellData::Protobuf(tx_by_addr) => tx_by_addr.tx_by_addrs.len(),
+        })
+        .unwrap_or(0);
 
         // Return the next tx-by-addr data of amount `limit` plus extra to account for the largest
         // number that might be flitered out
@@ -546,7 +583,21 @@ impl LedgerStorage {
             let index = index as u32;
             let signature = transaction.signatures[0];
 
-            for address in &transaction.message.account_keys {
+            let loaded_addresses = meta
+                .as_ref()
+                .and_then(|meta| meta.loaded_addresses.as_ref())
+                .map(|loaded_addresses| {
+                    loaded_addresses
+                        .writable
+                        .iter()
+                        .chain(loaded_addresses.readonly.iter())
+                });
+            for address in transaction
+                .message
+                .static_account_keys()
+                .iter()
+                .chain(loaded_addresses.into_iter().flatten())
+            {
                 if !is_sysvar_id(&address) {
                     by_addr
                         .entry(address)
@@ -588,20 +639,21 @@ impl LedgerStorage {
             .collect();
 
         if !tx_cells.is_empty() {
-            bytes_written += self
-                .connection
-                .put_bincode_cells_with_retry::<TransactionInfo>("tx", &tx_cells)
-                .await?;
+            bytes_written += backend::put_bincode_cells::<TransactionInfo>(
+                self.backend.as_ref(),
+                "tx",
+                &tx_cells,
+            )
+            .await?;
         }
 
         if !tx_by_addr_cells.is_empty() {
-            bytes_written += self
-                .connection
-                .put_protobuf_cells_with_retry::<tx_by_addr::TransactionByAddr>(
-                    "tx-by-addr",
-                    &tx_by_addr_cells,
-                )
-                .await?;
+            bytes_written += backend::put_protobuf_cells::<tx_by_addr::TransactionByAddr>(
+                self.backend.as_ref(),
+                "tx-by-addr",
+                &tx_by_addr_cells,
+            )
+            .await?;
         }
 
         let num_transactions = confirmed_block.transactions.len();
@@ -610,10 +662,12 @@ impl LedgerStorage {
         // successfully stored.  This avoids partial uploaded blocks from becoming visible to
         // `get_confirmed_block()` and `get_confirmed_blocks()`
         let blocks_cells = [(slot_to_key(slot), confirmed_block.into())];
-        bytes_written += self
-            .connection
-            .put_protobuf_cells_with_retry::<generated::ConfirmedBlock>("blocks", &blocks_cells)
-            .await?;
+        bytes_written += backend::put_protobuf_cells::<generated::ConfirmedBlock>(
+            self.backend.as_ref(),
+            "blocks",
+            &blocks_cells,
+        )
+        .await?;
         info!(
             "uploaded block for slot {}: {} transactions, {} bytes",
             slot, num_transactions, bytes_written
@@ -632,4 +686,88 @@ mod test {
         assert_eq!(slot_to_key(0), "0000000000000000");
         assert_eq!(slot_to_key(!0), "ffffffffffffffff");
     }
+
+    #[test]
+    fn test_local_ledger_storage() {
+        use solana_sdk::{hash::Hash, signature::Keypair, system_transaction};
+
+        let ledger_storage_dir = tempfile::TempDir::new().unwrap();
+        let ledger_storage = LedgerStorage::new_local(ledger_storage_dir.path()).unwrap();
+
+        let recipient = solana_sdk::pubkey::new_rand();
+        let transaction =
+            system_transaction::transfer(&Keypair::new(), &recipient, 42, Hash::default());
+        let signature = transaction.signatures[0];
+        let block = ConfirmedBlock {
+            transactions: vec![TransactionWithStatusMeta {
+                transaction: transaction.into(),
+                meta: Some(TransactionStatusMeta {
+                    status: Ok(()),
+                    fee: 1,
+                    pre_balances: vec![43, 0, 1],
+                    post_balances: vec![0, 42, 1],
+                    inner_instructions: Some(vec![]),
+                    log_messages: Some(vec![]),
+                    pre_token_balances: Some(vec![]),
+                    post_token_balances: Some(vec![]),
+                    loaded_addresses: None,
+                    return_data: None,
+                }),
+            }],
+            parent_slot: 1,
+            blockhash: Hash::new_unique().to_string(),
+            previous_blockhash: Hash::new_unique().to_string(),
+            rewards: vec![],
+            block_time: Some(1_234_567_890),
+        };
+
+        futures::executor::block_on(async {
+            assert_eq!(
+                ledger_storage.get_first_available_block().await.unwrap(),
+                None
+            );
+            ledger_storage
+                .upload_confirmed_block(2, block.clone())
+                .await
+                .unwrap();
+
+            assert_eq!(
+                ledger_storage.get_first_available_block().await.unwrap(),
+                Some(2)
+            );
+            assert_eq!(
+                ledger_storage.get_confirmed_blocks(0, 10).await.unwrap(),
+                vec![2]
+            );
+            assert_eq!(ledger_storage.get_confirmed_block(2).await.unwrap(), block);
+            assert!(matches!(
+                ledger_storage.get_confirmed_block(3).await,
+                Err(Error::BlockNotFound(3))
+            ));
+
+            let confirmed_transaction = ledger_storage
+                .get_confirmed_transaction(&signature)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(confirmed_transaction.slot, 2);
+            assert_eq!(confirmed_transaction.transaction, block.transactions[0]);
+            assert_eq!(
+                ledger_storage
+                    .get_signature_status(&signature)
+                    .await
+                    .unwrap()
+                    .slot,
+                2
+            );
+
+            let signatures = ledger_storage
+                .get_confirmed_signatures_for_address(&recipient, None, None, 10)
+                .await
+                .unwrap();
+            assert_eq!(signatures.len(), 1);
+            assert_eq!(signatures[0].0.signature, signature);
+            assert_eq!(signatures[0].1, 0);
+        });
+    }
 }
@@ -0,0 +1,206 @@
+// A `StorageBackend` keeping the BigTable tables in a local RocksDB directory
+
+use crate::{
+    backend::StorageBackend,
+    bigtable::{Error, Result, RowData, RowKey},
+    TABLES,
+};
+use async_trait::async_trait;
+use log::*;
+use rocksdb::{ColumnFamily, Direction, IteratorMode, Options, WriteBatch, DB};
+use std::{path::Path, sync::Arc};
+
+/// Each table is stored in a column family of the same name, with rows stored under their key
+/// and the bincode-serialized `RowData` as value.  As in BigTable, RocksDB keeps keys in lexical
+/// order so range queries behave the same.
+///
+/// RocksDB locks its directory, so only a single process can open it at a time.
+#[derive(Clone)]
+pub struct LocalStorage {
+    db: Arc<DB>,
+}
+
+impl LocalStorage {
+    pub fn open(path: &Path) -> Result<Self> {
+        info!("Opening local ledger storage at {:?}", path);
+        std::fs::create_dir_all(path)?;
+
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let db = DB::open_cf(&options, path, TABLES)?;
+        Ok(Self { db: Arc::new(db) })
+    }
+
+    fn cf_handle(&self, table_name: &str) -> Result<&ColumnFamily> {
+        self.db
+            .cf_handle(table_name)
+            .ok_or_else(|| Error::ObjectNotFound(format!("table {}", table_name)))
+    }
+
+    fn deserialize_row_data(table_name: &str, row_key: &str, value: &[u8]) -> Result<RowData> {
+        bincode::deserialize(value).map_err(|err| {
+            warn!("Failed to deserialize {}/{}: {}", table_name, row_key, err);
+            Error::ObjectCorrupt(format!("{}/{}", table_name, row_key))
+        })
+    }
+
+    // Read rows in key order between `start_at` and `end_at`, both inclusive
+    fn read_rows(
+        &self,
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<(RowKey, Box<[u8]>)>> {
+        let cf = self.cf_handle(table_name)?;
+        let mode = match &start_at {
+            Some(start_at) => IteratorMode::From(start_at.as_bytes(), Direction::Forward),
+            None => IteratorMode::Start,
+        };
+
+        let mut rows = vec![];
+        for (key, value) in self.db.iterator_cf(cf, mode) {
+            let row_key = String::from_utf8(key.into_vec()).map_err(|_| {
+                Error::ObjectCorrupt(format!("{}: row key is not valid UTF-8", table_name))
+            })?;
+            if let Some(end_at) = &end_at {
+                if row_key > *end_at {
+                    break;
+                }
+            }
+            rows.push((row_key, value));
+            if rows_limit > 0 && rows.len() as i64 >= rows_limit {
+                break;
+            }
+        }
+        Ok(rows)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalStorage {
+    async fn get_row_keys(
+        &self,
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<RowKey>> {
+        let rows = self.read_rows(table_name, start_at, end_at, rows_limit)?;
+        Ok(rows.into_iter().map(|(row_key, _)| row_key).collect())
+    }
+
+    async fn get_row_data(
+        &self,
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> Result<Vec<(RowKey, RowData)>> {
+        self.read_rows(table_name, start_at, end_at, rows_limit)?
+            .into_iter()
+            .map(|(row_key, value)| {
+                let row_data = Self::deserialize_row_data(table_name, &row_key, &value)?;
+                Ok((row_key, row_data))
+            })
+            .collect()
+    }
+
+    async fn get_single_row_data(&self, table_name: &str, row_key: RowKey) -> Result<RowData> {
+        let cf = self.cf_handle(table_name)?;
+        let value = self
+            .db
+            .get_cf(cf, row_key.as_bytes())?
+            .ok_or(Error::RowNotFound)?;
+        Self::deserialize_row_data(table_name, &row_key, &value)
+    }
+
+    async fn put_row_data(&self, table_name: &str, row_data: &[(&RowKey, RowData)]) -> Result<()> {
+        let cf = self.cf_handle(table_name)?;
+        let mut batch = WriteBatch::default();
+        for (row_key, new_cells) in row_data {
+            // Like a BigTable `SetCell` mutation, replace the named cells and keep the others
+            let mut cells = match self.db.get_cf(cf, row_key.as_bytes())? {
+                Some(value) => Self::deserialize_row_data(table_name, row_key, &value)?,
+                None => vec![],
+            };
+            for (cell_name, cell_value) in new_cells {
+                cells.retain(|(name, _)| name != cell_name);
+                cells.push((cell_name.clone(), cell_value.clone()));
+            }
+            batch.put_cf(cf, row_key.as_bytes(), bincode::serialize(&cells).unwrap());
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn row_keys(
+        local_storage: &LocalStorage,
+        start_at: Option<&str>,
+        end_at: Option<&str>,
+        rows_limit: i64,
+    ) -> Vec<RowKey> {
+        futures::executor::block_on(local_storage.get_row_keys(
+            "blocks",
+            start_at.map(|key| key.to_string()),
+            end_at.map(|key| key.to_string()),
+            rows_limit,
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_local_storage_rows() {
+        let ledger_storage_dir = TempDir::new().unwrap();
+        let local_storage = LocalStorage::open(ledger_storage_dir.path()).unwrap();
+
+        let keys: Vec<RowKey> = ["01", "02", "03", "10"]
+            .iter()
+            .map(|key| key.to_string())
+            .collect();
+        let row_data: Vec<_> = keys
+            .iter()
+            .map(|key| (key, vec![("bin".to_string(), key.as_bytes().to_vec())]))
+            .collect();
+        futures::executor::block_on(local_storage.put_row_data("blocks", &row_data)).unwrap();
+
+        assert_eq!(row_keys(&local_storage, None, None, 0), keys);
+        assert_eq!(
+            row_keys(&local_storage, Some("02"), None, 2),
+            keys[1..3].to_vec()
+        );
+        assert_eq!(
+            row_keys(&local_storage, Some("015"), Some("03"), 0),
+            keys[1..3].to_vec()
+        );
+        assert!(row_keys(&local_storage, Some("11"), None, 0).is_empty());
+
+        // Writing a new cell keeps the existing ones
+        let key = "01".to_string();
+        futures::executor::block_on(
+            local_storage.put_row_data("blocks", &[(&key, vec![("proto".to_string(), vec![1])])]),
+        )
+        .unwrap();
+        let row =
+            futures::executor::block_on(local_storage.get_single_row_data("blocks", key)).unwrap();
+        assert_eq!(
+            row,
+            vec![
+                ("bin".to_string(), b"01".to_vec()),
+                ("proto".to_string(), vec![1])
+            ]
+        );
+
+        assert!(matches!(
+            futures::executor::block_on(local_storage.get_single_row_data("tx", "01".to_string())),
+            Err(Error::RowNotFound)
+        ));
+    }
+}
@@ -0,0 +1,196 @@
+use solana_sdk::{
+    clock::Slot,
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    system_transaction,
+};
+use solana_storage_bigtable::LedgerStorage;
+use solana_transaction_status::{ConfirmedBlock, TransactionStatusMeta, TransactionWithStatusMeta};
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// Connect to the emulator named by BIGTABLE_EMULATOR_HOST, if any
+async fn connect() -> Option<LedgerStorage> {
+    if std::env::var("BIGTABLE_EMULATOR_HOST").is_err() {
+        eprintln!("BIGTABLE_EMULATOR_HOST is not set, skipping");
+        return None;
+    }
+    Some(LedgerStorage::new(false, None).await.unwrap())
+}
+
+// Slots far above those written by previous test runs against the same emulator, with room
+// for the few blocks uploaded by each test
+fn unique_slot() -> Slot {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let micros = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_micros() as Slot;
+    (micros + COUNTER.fetch_add(1, Ordering::Relaxed)) * 16
+}
+
+// A block with one transfer from a new account to each of `recipients`
+fn test_block(parent_slot: Slot, recipients: &[Pubkey]) -> ConfirmedBlock {
+    let transactions = recipients
+        .iter()
+        .map(|recipient| TransactionWithStatusMeta {
+            transaction: system_transaction::transfer(
+                &Keypair::new(),
+                recipient,
+                42,
+                Hash::new_unique(),
+            )
+            .into(),
+            meta: Some(TransactionStatusMeta {
+                status: Ok(()),
+                fee: 5000,
+                pre_balances: vec![5042, 0, 1],
+                post_balances: vec![0, 42, 1],
+                inner_instructions: Some(vec![]),
+                log_messages: Some(vec![]),
+                pre_token_balances: Some(vec![]),
+                post_token_balances: Some(vec![]),
+                loaded_addresses: None,
+                return_data: None,
+            }),
+        })
+        .collect();
+    ConfirmedBlock {
+        previous_blockhash: Hash::new_unique().to_string(),
+        blockhash: Hash::new_unique().to_string(),
+        parent_slot,
+        transactions,
+        rewards: vec![],
+        block_time: Some(1_600_000_000),
+    }
+}
+
+fn signature(block: &ConfirmedBlock, index: usize) -> Signature {
+    block.transactions[index].transaction.signatures[0]
+}
+
+#[tokio::test]
+async fn test_upload_confirmed_block() {
+    let ledger_storage = match connect().await {
+        Some(ledger_storage) => ledger_storage,
+        None => return,
+    };
+
+    let slot = unique_slot();
+    let block = test_block(slot - 1, &[Pubkey::new_unique(), Pubkey::new_unique()]);
+    ledger_storage
+        .upload_confirmed_block(slot, block.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        ledger_storage.get_confirmed_blocks(slot, 1).await.unwrap(),
+        vec![slot]
+    );
+    assert_eq!(
+        ledger_storage.get_confirmed_block(slot).await.unwrap(),
+        block
+    );
+    assert!(ledger_storage.get_confirmed_block(slot + 1).await.is_err());
+}
+
+#[tokio::test]
+async fn test_get_confirmed_transaction() {
+    let ledger_storage = match connect().await {
+        Some(ledger_storage) => ledger_storage,
+        None => return,
+    };
+
+    let slot = unique_slot();
+    let block = test_block(slot - 1, &[Pubkey::new_unique(), Pubkey::new_unique()]);
+    ledger_storage
+        .upload_confirmed_block(slot, block.clone())
+        .await
+        .unwrap();
+
+    for (index, transaction_with_meta) in block.transactions.iter().enumerate() {
+        let signature = signature(&block, index);
+        let confirmed_transaction = ledger_storage
+            .get_confirmed_transaction(&signature)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(confirmed_transaction.slot, slot);
+        assert_eq!(confirmed_transaction.block_time, block.block_time);
+        assert_eq!(confirmed_transaction.transaction, *transaction_with_meta);
+
+        let transaction_status = ledger_storage
+            .get_signature_status(&signature)
+            .await
+            .unwrap();
+        assert_eq!(transaction_status.slot, slot);
+        assert_eq!(transaction_status.err, None);
+    }
+
+    assert!(ledger_storage
+        .get_confirmed_transaction(&Signature::new(&[1; 64]))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_get_confirmed_signatures_for_address() {
+    let ledger_storage = match connect().await {
+        Some(ledger_storage) => ledger_storage,
+        None => return,
+    };
+
+    // Three blocks, the address receives two transfers in the middle one
+    let address = Pubkey::new_unique();
+    let slot = unique_slot();
+    let blocks = vec![
+        (slot, test_block(slot - 1, &[address])),
+        (slot + 1, test_block(slot, &[address, address])),
+        (slot + 2, test_block(slot + 1, &[address])),
+    ];
+    for (slot, block) in &blocks {
+        ledger_storage
+            .upload_confirmed_block(*slot, block.clone())
+            .await
+            .unwrap();
+    }
+
+    // Newest first
+    let expected: Vec<(Slot, Signature, u32)> = vec![
+        (slot + 2, signature(&blocks[2].1, 0), 0),
+        (slot + 1, signature(&blocks[1].1, 1), 1),
+        (slot + 1, signature(&blocks[1].1, 0), 0),
+        (slot, signature(&blocks[0].1, 0), 0),
+    ];
+    let get_signatures = |before: Option<Signature>, until: Option<Signature>, limit: usize| {
+        let ledger_storage = ledger_storage.clone();
+        async move {
+            ledger_storage
+                .get_confirmed_signatures_for_address(
+                    &address,
+                    before.as_ref(),
+                    until.as_ref(),
+                    limit,
+                )
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|(status, index)| (status.slot, status.signature, index))
+                .collect::<Vec<_>>()
+        }
+    };
+
+    assert_eq!(get_signatures(None, None, 10).await, expected);
+    assert_eq!(get_signatures(None, None, 2).await, expected[..2].to_vec());
+    assert_eq!(
+        get_signatures(Some(expected[0].1), None, 10).await,
+        expected[1..].to_vec()
+    );
+    assert_eq!(
+        get_signatures(Some(expected[1].1), Some(expected[3].1), 10).await,
+        expected[2..3].to_vec()
+    );
+}
@@ -0,0 +1,71 @@
+/**
+ * Schema of the tables written by the PostgreSQL ledger sink.  Statements are
+ * idempotent so the validator can apply the schema on every start.
+ */
+
+-- Confirmed blocks, one row per slot
+CREATE TABLE IF NOT EXISTS block (
+    slot BIGINT PRIMARY KEY,
+    parent_slot BIGINT NOT NULL,
+    blockhash VARCHAR(44) NOT NULL,
+    previous_blockhash VARCHAR(44) NOT NULL,
+    block_time BIGINT,
+    transaction_count INT NOT NULL,
+    updated_on TIMESTAMP NOT NULL DEFAULT now()
+);
+
+-- Transactions of the confirmed blocks with their status metadata
+CREATE TABLE IF NOT EXISTS transaction (
+    signature VARCHAR(88) PRIMARY KEY,
+    slot BIGINT NOT NULL,
+    transaction_index INT NOT NULL,
+    is_vote BOOL NOT NULL,
+    account_keys VARCHAR(44)[] NOT NULL,
+    -- bincode-serialized `Transaction`
+    transaction BYTEA NOT NULL,
+    -- NULL for successful transactions, the error otherwise
+    err TEXT,
+    fee BIGINT,
+    pre_balances BIGINT[],
+    post_balances BIGINT[],
+    log_messages TEXT[],
+    updated_on TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS transaction_slot ON transaction (slot);
+
+-- SPL Token balances of the transactions, before and after execution
+CREATE TABLE IF NOT EXISTS token_balance (
+    signature VARCHAR(88) NOT NULL,
+    account_index SMALLINT NOT NULL,
+    is_post_balance BOOL NOT NULL,
+    account VARCHAR(44) NOT NULL,
+    mint VARCHAR(44) NOT NULL,
+    -- Raw amount, as a decimal string, and the decimals of the mint
+    amount VARCHAR(20) NOT NULL,
+    decimals SMALLINT NOT NULL,
+    PRIMARY KEY (signature, account_index, is_post_balance)
+);
+
+CREATE INDEX IF NOT EXISTS token_balance_account ON token_balance (account);
+
+-- Latest state of each account, only written if account updates are enabled
+CREATE TABLE IF NOT EXISTS account (
+    pubkey VARCHAR(44) PRIMARY KEY,
+    owner VARCHAR(44) NOT NULL,
+    lamports BIGINT NOT NULL,
+    executable BOOL NOT NULL,
+    rent_epoch BIGINT NOT NULL,
+    data BYTEA NOT NULL,
+    slot BIGINT NOT NULL,
+    updated_on TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS account_owner ON account (owner);
+
+-- Last slot written by each uploader, where uploads resume after a restart
+CREATE TABLE IF NOT EXISTS upload_cursor (
+    name VARCHAR(32) PRIMARY KEY,
+    slot BIGINT NOT NULL,
+    updated_on TIMESTAMP NOT NULL DEFAULT now()
+);
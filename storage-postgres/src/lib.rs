@@ -0,0 +1,355 @@
+//! The `solana_storage_postgres` crate writes confirmed blocks, transaction
+//! statuses, token balances and account updates into a PostgreSQL database.
+//! The tables are described by `scripts/create_schema.sql`, which is applied
+//! whenever a connection is opened.
+
+use log::*;
+use postgres::{Client, NoTls, Statement, Transaction as DbTransaction};
+use solana_sdk::{clock::Slot, pubkey::Pubkey};
+use solana_transaction_status::{ConfirmedBlock, TransactionWithStatusMeta};
+use thiserror::Error;
+
+/// Statements creating the tables written by this crate
+pub const SCHEMA: &str = include_str!("../scripts/create_schema.sql");
+
+/// Cursor of the confirmed block uploader
+pub const LEDGER_UPLOAD_CURSOR: &str = "ledger_upload";
+
+/// Cursor of the account update writer
+pub const ACCOUNT_UPDATE_CURSOR: &str = "account_update";
+
+pub const DEFAULT_BATCH_SIZE: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Postgres: {0}")]
+    PostgresError(postgres::Error),
+
+    #[error("Serialization: {0}")]
+    SerializationError(bincode::Error),
+}
+
+impl std::convert::From<postgres::Error> for Error {
+    fn from(err: postgres::Error) -> Self {
+        Self::PostgresError(err)
+    }
+}
+
+impl std::convert::From<bincode::Error> for Error {
+    fn from(err: bincode::Error) -> Self {
+        Self::SerializationError(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PostgresConfig {
+    /// Connection string in either the `key=value` or the `postgresql://`
+    /// URL format, for example `host=localhost user=solana dbname=solana`
+    pub connection_str: String,
+
+    /// Number of blocks, or account updates, written per database transaction
+    pub batch_size: usize,
+
+    /// Whether account updates are written in addition to confirmed blocks
+    pub account_updates: bool,
+}
+
+impl Default for PostgresConfig {
+    fn default() -> Self {
+        Self {
+            connection_str: "host=localhost user=solana dbname=solana".to_string(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            account_updates: false,
+        }
+    }
+}
+
+/// The state of an account as of `slot`
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountUpdate {
+    pub pubkey: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub data: Vec<u8>,
+    pub slot: Slot,
+}
+
+pub struct LedgerStorage {
+    client: Client,
+}
+
+impl LedgerStorage {
+    /// Connect to the database described by `config` and create any missing
+    /// tables
+    pub fn connect(config: &PostgresConfig) -> Result<Self> {
+        let mut client = Client::connect(&config.connection_str, NoTls)?;
+        client.batch_execute(SCHEMA)?;
+        Ok(Self { client })
+    }
+
+    /// Whether the connection was lost, in which case a new `LedgerStorage`
+    /// must be connected
+    pub fn is_closed(&self) -> bool {
+        self.client.is_closed()
+    }
+
+    /// Return the last slot written under the cursor `name`
+    pub fn get_cursor(&mut self, name: &str) -> Result<Option<Slot>> {
+        let row = self
+            .client
+            .query_opt("SELECT slot FROM upload_cursor WHERE name = $1", &[&name])?;
+        Ok(row.map(|row| row.get::<_, i64>(0) as Slot))
+    }
+
+    /// Return up to `limit` slots of the blocks stored at or after
+    /// `start_slot`, in ascending order
+    pub fn get_confirmed_blocks(&mut self, start_slot: Slot, limit: usize) -> Result<Vec<Slot>> {
+        let rows = self.client.query(
+            "SELECT slot FROM block WHERE slot >= $1 ORDER BY slot LIMIT $2",
+            &[&(start_slot as i64), &(limit as i64)],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| row.get::<_, i64>(0) as Slot)
+            .collect())
+    }
+
+    /// Return the number of transactions stored for `slot`
+    pub fn get_transaction_count(&mut self, slot: Slot) -> Result<u64> {
+        let row = self.client.query_one(
+            "SELECT COUNT(*) FROM transaction WHERE slot = $1",
+            &[&(slot as i64)],
+        )?;
+        Ok(row.get::<_, i64>(0) as u64)
+    }
+
+    /// Return the latest stored state of the account `pubkey`
+    pub fn get_account(&mut self, pubkey: &Pubkey) -> Result<Option<AccountUpdate>> {
+        let row = self.client.query_opt(
+            "SELECT owner, lamports, executable, rent_epoch, data, slot FROM account \
+             WHERE pubkey = $1",
+            &[&pubkey.to_string()],
+        )?;
+        Ok(row.map(|row| AccountUpdate {
+            pubkey: *pubkey,
+            owner: row.get::<_, String>(0).parse().unwrap_or_default(),
+            lamports: row.get::<_, i64>(1) as u64,
+            executable: row.get(2),
+            rent_epoch: row.get::<_, i64>(3) as u64,
+            data: row.get(4),
+            slot: row.get::<_, i64>(5) as Slot,
+        }))
+    }
+
+    /// Write `blocks` in a single database transaction and advance the
+    /// ledger upload cursor to the last of them.  Blocks that were already
+    /// written are left as-is, so a batch interrupted by a restart can
+    /// simply be uploaded again.
+    pub fn upload_confirmed_blocks(&mut self, blocks: &[(Slot, ConfirmedBlock)]) -> Result<()> {
+        let last_slot = match blocks.last() {
+            Some((slot, _)) => *slot,
+            None => return Ok(()),
+        };
+
+        let mut db_transaction = self.client.transaction()?;
+        let statements = BlockStatements::prepare(&mut db_transaction)?;
+        for (slot, block) in blocks {
+            statements.insert_block(&mut db_transaction, *slot, block)?;
+        }
+        set_cursor(&mut db_transaction, LEDGER_UPLOAD_CURSOR, last_slot)?;
+        db_transaction.commit()?;
+        debug!(
+            "postgres: uploaded {} blocks up to {}",
+            blocks.len(),
+            last_slot
+        );
+        Ok(())
+    }
+
+    /// Write `account_updates` in a single database transaction, advancing the
+    /// account update cursor to `cursor` if given.  Updates older than the
+    /// stored state of an account are ignored.
+    pub fn upload_account_updates(
+        &mut self,
+        account_updates: &[AccountUpdate],
+        cursor: Option<Slot>,
+    ) -> Result<()> {
+        if account_updates.is_empty() && cursor.is_none() {
+            return Ok(());
+        }
+
+        let mut db_transaction = self.client.transaction()?;
+        let statement = db_transaction.prepare(
+            "INSERT INTO account (pubkey, owner, lamports, executable, rent_epoch, data, slot) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7) \
+             ON CONFLICT (pubkey) DO UPDATE SET owner = excluded.owner, \
+             lamports = excluded.lamports, executable = excluded.executable, \
+             rent_epoch = excluded.rent_epoch, data = excluded.data, slot = excluded.slot, \
+             updated_on = now() \
+             WHERE account.slot <= excluded.slot",
+        )?;
+        for update in account_updates {
+            db_transaction.execute(
+                &statement,
+                &[
+                    &update.pubkey.to_string(),
+                    &update.owner.to_string(),
+                    &(update.lamports as i64),
+                    &update.executable,
+                    &(update.rent_epoch as i64),
+                    &update.data,
+                    &(update.slot as i64),
+                ],
+            )?;
+        }
+        if let Some(cursor) = cursor {
+            set_cursor(&mut db_transaction, ACCOUNT_UPDATE_CURSOR, cursor)?;
+        }
+        db_transaction.commit()?;
+        Ok(())
+    }
+}
+
+fn set_cursor(db_transaction: &mut DbTransaction, name: &str, slot: Slot) -> Result<()> {
+    db_transaction.execute(
+        "INSERT INTO upload_cursor (name, slot) VALUES ($1, $2) \
+         ON CONFLICT (name) DO UPDATE SET slot = excluded.slot, updated_on = now() \
+         WHERE upload_cursor.slot < excluded.slot",
+        &[&name, &(slot as i64)],
+    )?;
+    Ok(())
+}
+
+struct BlockStatements {
+    block: Statement,
+    transaction: Statement,
+    token_balance: Statement,
+}
+
+impl BlockStatements {
+    fn prepare(db_transaction: &mut DbTransaction) -> Result<Self> {
+        Ok(Self {
+            block: db_transaction.prepare(
+                "INSERT INTO block \
+                 (slot, parent_slot, blockhash, previous_blockhash, block_time, transaction_count) \
+                 VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT DO NOTHING",
+            )?,
+            transaction: db_transaction.prepare(
+                "INSERT INTO transaction \
+                 (signature, slot, transaction_index, is_vote, account_keys, transaction, err, \
+                 fee, pre_balances, post_balances, log_messages) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) ON CONFLICT DO NOTHING",
+            )?,
+            token_balance: db_transaction.prepare(
+                "INSERT INTO token_balance \
+                 (signature, account_index, is_post_balance, account, mint, amount, decimals) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT DO NOTHING",
+            )?,
+        })
+    }
+
+    fn insert_block(
+        &self,
+        db_transaction: &mut DbTransaction,
+        slot: Slot,
+        block: &ConfirmedBlock,
+    ) -> Result<()> {
+        db_transaction.execute(
+            &self.block,
+            &[
+                &(slot as i64),
+                &(block.parent_slot as i64),
+                &block.blockhash,
+                &block.previous_blockhash,
+                &block.block_time,
+                &(block.transactions.len() as i32),
+            ],
+        )?;
+        for (index, transaction) in block.transactions.iter().enumerate() {
+            self.insert_transaction(db_transaction, slot, index, transaction)?;
+        }
+        Ok(())
+    }
+
+    fn insert_transaction(
+        &self,
+        db_transaction: &mut DbTransaction,
+        slot: Slot,
+        index: usize,
+        transaction_with_meta: &TransactionWithStatusMeta,
+    ) -> Result<()> {
+        let transaction = &transaction_with_meta.transaction;
+        let signature = transaction.signatures[0].to_string();
+        // Token balances index the account keys of the executed message
+        let account_keys: Vec<String> = match transaction_with_meta.resolved_message() {
+            Some(message) => message.account_keys,
+            None => transaction.message.static_account_keys().to_vec(),
+        }
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+        let is_vote = transaction
+            .clone()
+            .into_legacy_transaction()
+            .map(|transaction| {
+                solana_vote_program::vote_transaction::parse_vote_transaction(&transaction)
+                    .is_some()
+            })
+            .unwrap_or(false);
+        let meta = transaction_with_meta.meta.as_ref();
+        let to_i64 =
+            |balances: &[u64]| -> Vec<i64> { balances.iter().map(|b| *b as i64).collect() };
+
+        db_transaction.execute(
+            &self.transaction,
+            &[
+                &signature,
+                &(slot as i64),
+                &(index as i32),
+                &is_vote,
+                &account_keys,
+                &bincode::serialize(transaction)?,
+                &meta.and_then(|meta| meta.status.as_ref().err().map(|err| err.to_string())),
+                &meta.map(|meta| meta.fee as i64),
+                &meta.map(|meta| to_i64(&meta.pre_balances)),
+                &meta.map(|meta| to_i64(&meta.post_balances)),
+                &meta.and_then(|meta| meta.log_messages.clone()),
+            ],
+        )?;
+
+        let token_balances = meta.into_iter().flat_map(|meta| {
+            let pre = meta.pre_token_balances.iter().flatten().map(|b| (false, b));
+            let post = meta.post_token_balances.iter().flatten().map(|b| (true, b));
+            pre.chain(post)
+        });
+        for (is_post_balance, token_balance) in token_balances {
+            let account = match account_keys.get(token_balance.account_index as usize) {
+                Some(account) => account,
+                None => {
+                    warn!(
+                        "postgres: token balance of transaction {} refers to unknown account {}",
+                        signature, token_balance.account_index
+                    );
+                    continue;
+                }
+            };
+            db_transaction.execute(
+                &self.token_balance,
+                &[
+                    &signature,
+                    &i16::from(token_balance.account_index),
+                    &is_post_balance,
+                    account,
+                    &token_balance.mint,
+                    &token_balance.ui_token_amount.amount,
+                    &i16::from(token_balance.ui_token_amount.decimals),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+}
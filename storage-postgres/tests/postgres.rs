@@ -0,0 +1,144 @@
+use solana_account_decoder::parse_token::UiTokenAmount;
+use solana_sdk::{clock::Slot, hash::Hash, pubkey::Pubkey, signature::Keypair, system_transaction};
+use solana_storage_postgres::{
+    AccountUpdate, LedgerStorage, PostgresConfig, ACCOUNT_UPDATE_CURSOR, LEDGER_UPLOAD_CURSOR,
+};
+use solana_transaction_status::{
+    ConfirmedBlock, TransactionStatusMeta, TransactionTokenBalance, TransactionWithStatusMeta,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Connect to the database named by POSTGRES_TEST_CONNECTION_STR, if any
+fn connect() -> Option<LedgerStorage> {
+    let connection_str = match std::env::var("POSTGRES_TEST_CONNECTION_STR") {
+        Ok(connection_str) => connection_str,
+        Err(_) => {
+            eprintln!("POSTGRES_TEST_CONNECTION_STR is not set, skipping");
+            return None;
+        }
+    };
+    let config = PostgresConfig {
+        connection_str,
+        ..PostgresConfig::default()
+    };
+    Some(LedgerStorage::connect(&config).unwrap())
+}
+
+// Slots far above those written by previous test runs against the same database
+fn unique_slot() -> Slot {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_micros() as Slot
+}
+
+fn test_block(parent_slot: Slot, num_transactions: usize) -> ConfirmedBlock {
+    let mint = Pubkey::new_unique();
+    let transactions = (0..num_transactions)
+        .map(|_| {
+            let transaction = system_transaction::transfer(
+                &Keypair::new(),
+                &Pubkey::new_unique(),
+                1,
+                Hash::new_unique(),
+            );
+            let token_balance = |amount: &str| TransactionTokenBalance {
+                account_index: 1,
+                mint: mint.to_string(),
+                ui_token_amount: UiTokenAmount {
+                    ui_amount: 0.0,
+                    decimals: 2,
+                    amount: amount.to_string(),
+                },
+            };
+            TransactionWithStatusMeta {
+                transaction: transaction.into(),
+                meta: Some(TransactionStatusMeta {
+                    status: Ok(()),
+                    fee: 5000,
+                    pre_balances: vec![10, 0, 1],
+                    post_balances: vec![9, 1, 1],
+                    inner_instructions: None,
+                    log_messages: Some(vec!["Program log: transfer".to_string()]),
+                    pre_token_balances: Some(vec![token_balance("0")]),
+                    post_token_balances: Some(vec![token_balance("100")]),
+                    loaded_addresses: None,
+                    return_data: None,
+                }),
+            }
+        })
+        .collect();
+    ConfirmedBlock {
+        previous_blockhash: Hash::new_unique().to_string(),
+        blockhash: Hash::new_unique().to_string(),
+        parent_slot,
+        transactions,
+        rewards: vec![],
+        block_time: Some(1_600_000_000),
+    }
+}
+
+#[test]
+fn test_upload_confirmed_blocks() {
+    let mut ledger_storage = match connect() {
+        Some(ledger_storage) => ledger_storage,
+        None => return,
+    };
+
+    let slot = unique_slot();
+    let blocks = vec![
+        (slot, test_block(slot - 1, 2)),
+        (slot + 2, test_block(slot, 0)),
+    ];
+    ledger_storage.upload_confirmed_blocks(&blocks).unwrap();
+
+    assert_eq!(
+        ledger_storage.get_confirmed_blocks(slot, 10).unwrap(),
+        vec![slot, slot + 2]
+    );
+    assert_eq!(ledger_storage.get_transaction_count(slot).unwrap(), 2);
+    assert_eq!(ledger_storage.get_transaction_count(slot + 2).unwrap(), 0);
+    assert!(ledger_storage.get_cursor(LEDGER_UPLOAD_CURSOR).unwrap() >= Some(slot + 2));
+
+    // Uploading a batch again, as after a restart, is harmless
+    ledger_storage.upload_confirmed_blocks(&blocks).unwrap();
+    assert_eq!(ledger_storage.get_transaction_count(slot).unwrap(), 2);
+}
+
+#[test]
+fn test_upload_account_updates() {
+    let mut ledger_storage = match connect() {
+        Some(ledger_storage) => ledger_storage,
+        None => return,
+    };
+
+    let slot = unique_slot();
+    let pubkey = Pubkey::new_unique();
+    let account_update = |lamports: u64, slot: Slot| AccountUpdate {
+        pubkey,
+        owner: Pubkey::new_unique(),
+        lamports,
+        executable: false,
+        rent_epoch: 1,
+        data: vec![1, 2, 3],
+        slot,
+    };
+    assert_eq!(ledger_storage.get_account(&pubkey).unwrap(), None);
+
+    let latest = account_update(2, slot + 1);
+    ledger_storage
+        .upload_account_updates(&[account_update(1, slot), latest.clone()], Some(slot + 1))
+        .unwrap();
+    assert_eq!(
+        ledger_storage.get_account(&pubkey).unwrap(),
+        Some(latest.clone())
+    );
+    assert!(ledger_storage.get_cursor(ACCOUNT_UPDATE_CURSOR).unwrap() >= Some(slot + 1));
+
+    // Updates older than the stored state are ignored
+    ledger_storage
+        .upload_account_updates(&[account_update(3, slot)], None)
+        .unwrap();
+    assert_eq!(ledger_storage.get_account(&pubkey).unwrap(), Some(latest));
+    assert!(ledger_storage.get_cursor(ACCOUNT_UPDATE_CURSOR).unwrap() >= Some(slot + 1));
+}
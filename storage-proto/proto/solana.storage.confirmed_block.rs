@@ -37,6 +37,10 @@ pub struct Message {
     pub recent_blockhash: ::prost::alloc::vec::Vec<u8>,
     #[prost(message, repeated, tag = "4")]
     pub instructions: ::prost::alloc::vec::Vec<CompiledInstruction>,
+    #[prost(bool, tag = "5")]
+    pub versioned: bool,
+    #[prost(message, repeated, tag = "6")]
+    pub address_table_lookups: ::prost::alloc::vec::Vec<MessageAddressTableLookup>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct MessageHeader {
@@ -48,6 +52,15 @@ pub struct MessageHeader {
     pub num_readonly_unsigned_accounts: u32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MessageAddressTableLookup {
+    #[prost(bytes = "vec", tag = "1")]
+    pub account_key: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub writable_indexes: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub readonly_indexes: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TransactionStatusMeta {
     #[prost(message, optional, tag = "1")]
     pub err: ::core::option::Option<TransactionError>,
@@ -65,6 +78,12 @@ pub struct TransactionStatusMeta {
     pub pre_token_balances: ::prost::alloc::vec::Vec<TokenBalance>,
     #[prost(message, repeated, tag = "8")]
     pub post_token_balances: ::prost::alloc::vec::Vec<TokenBalance>,
+    #[prost(bytes = "vec", repeated, tag = "9")]
+    pub loaded_writable_addresses: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bytes = "vec", repeated, tag = "10")]
+    pub loaded_readonly_addresses: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(message, optional, tag = "11")]
+    pub return_data: ::core::option::Option<ReturnData>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TransactionError {
@@ -72,6 +91,13 @@ pub struct TransactionError {
     pub err: ::prost::alloc::vec::Vec<u8>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReturnData {
+    #[prost(bytes = "vec", tag = "1")]
+    pub program_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct InnerInstructions {
     #[prost(uint32, tag = "1")]
     pub index: u32,
@@ -115,6 +141,8 @@ pub struct Reward {
     pub post_balance: u64,
     #[prost(enumeration = "RewardType", tag = "4")]
     pub reward_type: i32,
+    #[prost(string, tag = "5")]
+    pub commission: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Rewards {
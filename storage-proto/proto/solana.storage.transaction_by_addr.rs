@@ -66,6 +66,13 @@ pub enum TransactionErrorType {
     InvalidProgramForExecution = 13,
     SanitizeFailure = 14,
     ClusterMaintenance = 15,
+    UnsupportedVersion = 16,
+    AddressLookupTableNotFound = 17,
+    InvalidAddressLookupTableOwner = 18,
+    InvalidAddressLookupTableData = 19,
+    InvalidAddressLookupTableIndex = 20,
+    WouldExceedMaxBlockCostLimit = 21,
+    WouldExceedMaxAccountCostLimit = 22,
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -4,11 +4,15 @@ use solana_sdk::{
     hash::Hash,
     instruction::CompiledInstruction,
     instruction::InstructionError,
-    message::{Message, MessageHeader},
+    message::{
+        v0::{self, LoadedAddresses, MessageAddressTableLookup},
+        Message, MessageHeader, VersionedMessage,
+    },
+    process_instruction::TransactionReturnData,
     pubkey::Pubkey,
     signature::Signature,
-    transaction::Transaction,
     transaction::TransactionError,
+    transaction::VersionedTransaction,
 };
 use solana_transaction_status::{
     ConfirmedBlock, InnerInstructions, Reward, RewardType, TransactionByAddrInfo,
@@ -84,6 +88,7 @@ impl From<Reward> for generated::Reward {
                 Some(RewardType::Staking) => generated::RewardType::Staking,
                 Some(RewardType::Voting) => generated::RewardType::Voting,
             } as i32,
+            commission: reward.commission.map(|c| c.to_string()).unwrap_or_default(),
         }
     }
 }
@@ -102,6 +107,7 @@ impl From<generated::Reward> for Reward {
                 4 => Some(RewardType::Voting),
                 _ => None,
             },
+            commission: reward.commission.parse::<u8>().ok(),
         }
     }
 }
@@ -185,8 +191,8 @@ impl TryFrom<generated::ConfirmedTransaction> for TransactionWithStatusMeta {
     }
 }
 
-impl From<Transaction> for generated::Transaction {
-    fn from(value: Transaction) -> Self {
+impl From<VersionedTransaction> for generated::Transaction {
+    fn from(value: VersionedTransaction) -> Self {
         Self {
             signatures: value
                 .signatures
@@ -198,7 +204,7 @@ impl From<Transaction> for generated::Transaction {
     }
 }
 
-impl From<generated::Transaction> for Transaction {
+impl From<generated::Transaction> for VersionedTransaction {
     fn from(value: generated::Transaction) -> Self {
         Self {
             signatures: value
@@ -211,32 +217,92 @@ impl From<generated::Transaction> for Transaction {
     }
 }
 
-impl From<Message> for generated::Message {
-    fn from(value: Message) -> Self {
+impl From<VersionedMessage> for generated::Message {
+    fn from(value: VersionedMessage) -> Self {
+        let (message, versioned, address_table_lookups) = match value {
+            VersionedMessage::Legacy(message) => (message, false, vec![]),
+            VersionedMessage::V0(message) => (
+                Message {
+                    header: message.header,
+                    account_keys: message.account_keys,
+                    recent_blockhash: message.recent_blockhash,
+                    instructions: message.instructions,
+                },
+                true,
+                message.address_table_lookups,
+            ),
+        };
         Self {
-            header: Some(value.header.into()),
-            account_keys: value
+            header: Some(message.header.into()),
+            account_keys: message
                 .account_keys
                 .into_iter()
                 .map(|key| <Pubkey as AsRef<[u8]>>::as_ref(&key).into())
                 .collect(),
-            recent_blockhash: value.recent_blockhash.to_bytes().into(),
-            instructions: value.instructions.into_iter().map(|ix| ix.into()).collect(),
+            recent_blockhash: message.recent_blockhash.to_bytes().into(),
+            instructions: message
+                .instructions
+                .into_iter()
+                .map(|ix| ix.into())
+                .collect(),
+            versioned,
+            address_table_lookups: address_table_lookups
+                .into_iter()
+                .map(|lookup| lookup.into())
+                .collect(),
         }
     }
 }
 
-impl From<generated::Message> for Message {
+impl From<generated::Message> for VersionedMessage {
     fn from(value: generated::Message) -> Self {
+        let header = value.header.expect("header is required").into();
+        let account_keys = value
+            .account_keys
+            .into_iter()
+            .map(|key| Pubkey::new(&key))
+            .collect();
+        let recent_blockhash = Hash::new(&value.recent_blockhash);
+        let instructions = value.instructions.into_iter().map(|ix| ix.into()).collect();
+        if value.versioned {
+            Self::V0(v0::Message {
+                header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+                address_table_lookups: value
+                    .address_table_lookups
+                    .into_iter()
+                    .map(|lookup| lookup.into())
+                    .collect(),
+            })
+        } else {
+            Self::Legacy(Message {
+                header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+            })
+        }
+    }
+}
+
+impl From<MessageAddressTableLookup> for generated::MessageAddressTableLookup {
+    fn from(value: MessageAddressTableLookup) -> Self {
         Self {
-            header: value.header.expect("header is required").into(),
-            account_keys: value
-                .account_keys
-                .into_iter()
-                .map(|key| Pubkey::new(&key))
-                .collect(),
-            recent_blockhash: Hash::new(&value.recent_blockhash),
-            instructions: value.instructions.into_iter().map(|ix| ix.into()).collect(),
+            account_key: <Pubkey as AsRef<[u8]>>::as_ref(&value.account_key).into(),
+            writable_indexes: value.writable_indexes,
+            readonly_indexes: value.readonly_indexes,
+        }
+    }
+}
+
+impl From<generated::MessageAddressTableLookup> for MessageAddressTableLookup {
+    fn from(value: generated::MessageAddressTableLookup) -> Self {
+        Self {
+            account_key: Pubkey::new(&value.account_key),
+            writable_indexes: value.writable_indexes,
+            readonly_indexes: value.readonly_indexes,
         }
     }
 }
@@ -272,6 +338,8 @@ impl From<TransactionStatusMeta> for generated::TransactionStatusMeta {
             log_messages,
             pre_token_balances,
             post_token_balances,
+            loaded_addresses,
+            return_data,
         } = value;
         let err = match status {
             Ok(()) => None,
@@ -295,6 +363,18 @@ impl From<TransactionStatusMeta> for generated::TransactionStatusMeta {
             .into_iter()
             .map(|balance| balance.into())
             .collect();
+        let loaded_addresses = loaded_addresses.unwrap_or_default();
+        let loaded_writable_addresses = loaded_addresses
+            .writable
+            .into_iter()
+            .map(|key| <Pubkey as AsRef<[u8]>>::as_ref(&key).into())
+            .collect();
+        let loaded_readonly_addresses = loaded_addresses
+            .readonly
+            .into_iter()
+            .map(|key| <Pubkey as AsRef<[u8]>>::as_ref(&key).into())
+            .collect();
+        let return_data = return_data.map(|return_data| return_data.into());
 
         Self {
             err,
@@ -305,6 +385,9 @@ impl From<TransactionStatusMeta> for generated::TransactionStatusMeta {
             log_messages,
             pre_token_balances,
             post_token_balances,
+            loaded_writable_addresses,
+            loaded_readonly_addresses,
+            return_data,
         }
     }
 }
@@ -322,6 +405,9 @@ impl TryFrom<generated::TransactionStatusMeta> for TransactionStatusMeta {
             log_messages,
             pre_token_balances,
             post_token_balances,
+            loaded_writable_addresses,
+            loaded_readonly_addresses,
+            return_data,
         } = value;
         let status = match &err {
             None => Ok(()),
@@ -346,6 +432,22 @@ impl TryFrom<generated::TransactionStatusMeta> for TransactionStatusMeta {
                 .map(|balance| balance.into())
                 .collect(),
         );
+        let loaded_addresses =
+            if loaded_writable_addresses.is_empty() && loaded_readonly_addresses.is_empty() {
+                None
+            } else {
+                Some(LoadedAddresses {
+                    writable: loaded_writable_addresses
+                        .into_iter()
+                        .map(|key| Pubkey::new(&key))
+                        .collect(),
+                    readonly: loaded_readonly_addresses
+                        .into_iter()
+                        .map(|key| Pubkey::new(&key))
+                        .collect(),
+                })
+            };
+        let return_data = return_data.map(|return_data| return_data.into());
         Ok(Self {
             status,
             fee,
@@ -355,10 +457,30 @@ impl TryFrom<generated::TransactionStatusMeta> for TransactionStatusMeta {
             log_messages,
             pre_token_balances,
             post_token_balances,
+            loaded_addresses,
+            return_data,
         })
     }
 }
 
+impl From<TransactionReturnData> for generated::ReturnData {
+    fn from(value: TransactionReturnData) -> Self {
+        Self {
+            program_id: <Pubkey as AsRef<[u8]>>::as_ref(&value.program_id).into(),
+            data: value.data,
+        }
+    }
+}
+
+impl From<generated::ReturnData> for TransactionReturnData {
+    fn from(value: generated::ReturnData) -> Self {
+        Self {
+            program_id: Pubkey::new(&value.program_id),
+            data: value.data,
+        }
+    }
+}
+
 impl From<InnerInstructions> for generated::InnerInstructions {
     fn from(value: InnerInstructions) -> Self {
         Self {
@@ -509,6 +631,13 @@ impl TryFrom<tx_by_addr::TransactionError> for TransactionError {
             13 => TransactionError::InvalidProgramForExecution,
             14 => TransactionError::SanitizeFailure,
             15 => TransactionError::ClusterMaintenance,
+            16 => TransactionError::UnsupportedVersion,
+            17 => TransactionError::AddressLookupTableNotFound,
+            18 => TransactionError::InvalidAddressLookupTableOwner,
+            19 => TransactionError::InvalidAddressLookupTableData,
+            20 => TransactionError::InvalidAddressLookupTableIndex,
+            21 => TransactionError::WouldExceedMaxBlockCostLimit,
+            22 => TransactionError::WouldExceedMaxAccountCostLimit,
             _ => return Err("Invalid TransactionError"),
         })
     }
@@ -561,6 +690,27 @@ impl From<TransactionError> for tx_by_addr::TransactionError {
                 TransactionError::ClusterMaintenance => {
                     tx_by_addr::TransactionErrorType::ClusterMaintenance
                 }
+                TransactionError::UnsupportedVersion => {
+                    tx_by_addr::TransactionErrorType::UnsupportedVersion
+                }
+                TransactionError::AddressLookupTableNotFound => {
+                    tx_by_addr::TransactionErrorType::AddressLookupTableNotFound
+                }
+                TransactionError::InvalidAddressLookupTableOwner => {
+                    tx_by_addr::TransactionErrorType::InvalidAddressLookupTableOwner
+                }
+                TransactionError::InvalidAddressLookupTableData => {
+                    tx_by_addr::TransactionErrorType::InvalidAddressLookupTableData
+                }
+                TransactionError::InvalidAddressLookupTableIndex => {
+                    tx_by_addr::TransactionErrorType::InvalidAddressLookupTableIndex
+                }
+                TransactionError::WouldExceedMaxBlockCostLimit => {
+                    tx_by_addr::TransactionErrorType::WouldExceedMaxBlockCostLimit
+                }
+                TransactionError::WouldExceedMaxAccountCostLimit => {
+                    tx_by_addr::TransactionErrorType::WouldExceedMaxAccountCostLimit
+                }
                 TransactionError::InstructionError(_, _) => {
                     tx_by_addr::TransactionErrorType::InstructionError
                 }
@@ -780,6 +930,47 @@ impl TryFrom<tx_by_addr::TransactionByAddr> for Vec<TransactionByAddrInfo> {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_versioned_transaction_conversion() {
+        use solana_sdk::{
+            address_lookup_table_account::AddressLookupTableAccount,
+            signature::{Keypair, Signer},
+            system_instruction,
+            transaction::Transaction,
+        };
+
+        let keypair = Keypair::new();
+        let recipient = solana_sdk::pubkey::new_rand();
+        let instructions = [system_instruction::transfer(
+            &keypair.pubkey(),
+            &recipient,
+            1,
+        )];
+
+        let transaction = VersionedTransaction::from(Transaction::new(
+            &[&keypair],
+            Message::new(&instructions, Some(&keypair.pubkey())),
+            Hash::default(),
+        ));
+        let gen_transaction: generated::Transaction = transaction.clone().into();
+        assert!(!gen_transaction.message.as_ref().unwrap().versioned);
+        assert_eq!(transaction, gen_transaction.into());
+
+        let message = v0::Message::new(
+            &instructions,
+            Some(&keypair.pubkey()),
+            &[AddressLookupTableAccount {
+                key: solana_sdk::pubkey::new_rand(),
+                addresses: vec![recipient],
+            }],
+        );
+        let transaction =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&keypair]).unwrap();
+        let gen_transaction: generated::Transaction = transaction.clone().into();
+        assert!(gen_transaction.message.as_ref().unwrap().versioned);
+        assert_eq!(transaction, gen_transaction.into());
+    }
+
     #[test]
     fn test_reward_type_encode() {
         let mut reward = Reward {
@@ -787,6 +978,7 @@ mod test {
             lamports: 123,
             post_balance: 321,
             reward_type: None,
+            commission: None,
         };
         let gen_reward: generated::Reward = reward.clone().into();
         assert_eq!(reward, gen_reward.into());
@@ -806,6 +998,10 @@ mod test {
         reward.reward_type = Some(RewardType::Staking);
         let gen_reward: generated::Reward = reward.clone().into();
         assert_eq!(reward, gen_reward.into());
+
+        reward.commission = Some(10);
+        let gen_reward: generated::Reward = reward.clone().into();
+        assert_eq!(reward, gen_reward.into());
     }
 
     #[test]
@@ -872,6 +1068,62 @@ mod test {
             tx_by_addr_transaction_error.try_into().unwrap()
         );
 
+        let transaction_error = TransactionError::UnsupportedVersion;
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
+        let transaction_error = TransactionError::AddressLookupTableNotFound;
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
+        let transaction_error = TransactionError::InvalidAddressLookupTableOwner;
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
+        let transaction_error = TransactionError::InvalidAddressLookupTableData;
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
+        let transaction_error = TransactionError::InvalidAddressLookupTableIndex;
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
+        let transaction_error = TransactionError::WouldExceedMaxBlockCostLimit;
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
+        let transaction_error = TransactionError::WouldExceedMaxAccountCostLimit;
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
         let transaction_error = TransactionError::DuplicateSignature;
         let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
             transaction_error.clone().into();
@@ -23,11 +23,15 @@ use solana_sdk::{
     commitment_config::CommitmentConfig,
     deserialize_utils::default_on_eof,
     instruction::CompiledInstruction,
-    message::{Message, MessageHeader},
+    message::{
+        v0::{LoadedAddresses, MessageAddressTableLookup},
+        Message, MessageHeader, VersionedMessage,
+    },
+    process_instruction::TransactionReturnData,
     pubkey::Pubkey,
     sanitize::Sanitize,
     signature::Signature,
-    transaction::{Result, Transaction, TransactionError},
+    transaction::{Result, Transaction, TransactionError, VersionedTransaction},
 };
 use std::fmt;
 /// A duplicate representation of an Instruction for pretty JSON serialization
@@ -183,6 +187,10 @@ pub struct TransactionStatusMeta {
     pub pre_token_balances: Option<Vec<TransactionTokenBalance>>,
     #[serde(deserialize_with = "default_on_eof")]
     pub post_token_balances: Option<Vec<TransactionTokenBalance>>,
+    #[serde(deserialize_with = "default_on_eof")]
+    pub loaded_addresses: Option<LoadedAddresses>,
+    #[serde(deserialize_with = "default_on_eof")]
+    pub return_data: Option<TransactionReturnData>,
 }
 
 impl Default for TransactionStatusMeta {
@@ -196,6 +204,8 @@ impl Default for TransactionStatusMeta {
             log_messages: None,
             pre_token_balances: None,
             post_token_balances: None,
+            loaded_addresses: None,
+            return_data: None,
         }
     }
 }
@@ -213,6 +223,61 @@ pub struct UiTransactionStatusMeta {
     pub log_messages: Option<Vec<String>>,
     pub pre_token_balances: Option<Vec<UiTransactionTokenBalance>>,
     pub post_token_balances: Option<Vec<UiTransactionTokenBalance>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loaded_addresses: Option<UiLoadedAddresses>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_data: Option<UiTransactionReturnData>,
+}
+
+/// A duplicate representation of LoadedAddresses
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiLoadedAddresses {
+    pub writable: Vec<String>,
+    pub readonly: Vec<String>,
+}
+
+impl From<&LoadedAddresses> for UiLoadedAddresses {
+    fn from(loaded_addresses: &LoadedAddresses) -> Self {
+        Self {
+            writable: loaded_addresses
+                .writable
+                .iter()
+                .map(|pubkey| pubkey.to_string())
+                .collect(),
+            readonly: loaded_addresses
+                .readonly
+                .iter()
+                .map(|pubkey| pubkey.to_string())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UiReturnDataEncoding {
+    Base64,
+}
+
+/// A duplicate representation of TransactionReturnData, with the data base64-encoded
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiTransactionReturnData {
+    pub program_id: String,
+    pub data: (String, UiReturnDataEncoding),
+}
+
+impl From<TransactionReturnData> for UiTransactionReturnData {
+    fn from(return_data: TransactionReturnData) -> Self {
+        Self {
+            program_id: return_data.program_id.to_string(),
+            data: (
+                base64::encode(return_data.data),
+                UiReturnDataEncoding::Base64,
+            ),
+        }
+    }
 }
 
 impl UiTransactionStatusMeta {
@@ -235,6 +300,8 @@ impl UiTransactionStatusMeta {
             post_token_balances: meta
                 .post_token_balances
                 .map(|balance| balance.into_iter().map(|balance| balance.into()).collect()),
+            loaded_addresses: meta.loaded_addresses.as_ref().map(UiLoadedAddresses::from),
+            return_data: meta.return_data.map(|return_data| return_data.into()),
         }
     }
 }
@@ -257,6 +324,8 @@ impl From<TransactionStatusMeta> for UiTransactionStatusMeta {
             post_token_balances: meta
                 .post_token_balances
                 .map(|balance| balance.into_iter().map(|balance| balance.into()).collect()),
+            loaded_addresses: meta.loaded_addresses.as_ref().map(UiLoadedAddresses::from),
+            return_data: meta.return_data.map(|return_data| return_data.into()),
         }
     }
 }
@@ -331,6 +400,7 @@ pub struct Reward {
     pub lamports: i64,
     pub post_balance: u64, // Account balance in lamports after `lamports` was applied
     pub reward_type: Option<RewardType>,
+    pub commission: Option<u8>, // Vote account commission when the reward was credited, only present for voting and staking rewards
 }
 
 pub type Rewards = Vec<Reward>;
@@ -361,6 +431,48 @@ impl ConfirmedBlock {
             block_time: self.block_time,
         }
     }
+
+    pub fn configure(
+        self,
+        encoding: UiTransactionEncoding,
+        transaction_details: TransactionDetails,
+        show_rewards: bool,
+    ) -> UiConfirmedBlock {
+        let (transactions, signatures) = match transaction_details {
+            TransactionDetails::Full => (
+                Some(
+                    self.transactions
+                        .into_iter()
+                        .map(|tx| tx.encode(encoding))
+                        .collect(),
+                ),
+                None,
+            ),
+            TransactionDetails::Signatures => (
+                None,
+                Some(
+                    self.transactions
+                        .into_iter()
+                        .map(|tx| tx.transaction.signatures[0].to_string())
+                        .collect(),
+                ),
+            ),
+            TransactionDetails::None => (None, None),
+        };
+        UiConfirmedBlock {
+            previous_blockhash: self.previous_blockhash,
+            blockhash: self.blockhash,
+            parent_slot: self.parent_slot,
+            transactions,
+            signatures,
+            rewards: if show_rewards {
+                Some(self.rewards)
+            } else {
+                None
+            },
+            block_time: self.block_time,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -374,6 +486,37 @@ pub struct EncodedConfirmedBlock {
     pub block_time: Option<UnixTimestamp>,
 }
 
+/// A block encoded with a `TransactionDetails` level; with full transaction details and rewards
+/// it serializes the same as an `EncodedConfirmedBlock`
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiConfirmedBlock {
+    pub previous_blockhash: String,
+    pub blockhash: String,
+    pub parent_slot: Slot,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transactions: Option<Vec<EncodedTransactionWithStatusMeta>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signatures: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewards: Option<Rewards>,
+    pub block_time: Option<UnixTimestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionDetails {
+    Full,
+    Signatures,
+    None,
+}
+
+impl Default for TransactionDetails {
+    fn default() -> Self {
+        Self::Full
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfirmedTransaction {
@@ -425,6 +568,27 @@ pub struct UiRawMessage {
     pub account_keys: Vec<String>,
     pub recent_blockhash: String,
     pub instructions: Vec<UiCompiledInstruction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_table_lookups: Option<Vec<UiAddressTableLookup>>,
+}
+
+/// A duplicate representation of a MessageAddressTableLookup, in raw format, for pretty JSON serialization
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiAddressTableLookup {
+    pub account_key: String,
+    pub writable_indexes: Vec<u8>,
+    pub readonly_indexes: Vec<u8>,
+}
+
+impl From<&MessageAddressTableLookup> for UiAddressTableLookup {
+    fn from(lookup: &MessageAddressTableLookup) -> Self {
+        Self {
+            account_key: lookup.account_key.to_string(),
+            writable_indexes: lookup.writable_indexes.clone(),
+            readonly_indexes: lookup.readonly_indexes.clone(),
+        }
+    }
 }
 
 /// A duplicate representation of a Message, in parsed format, for pretty JSON serialization
@@ -434,21 +598,50 @@ pub struct UiParsedMessage {
     pub account_keys: Vec<ParsedAccount>,
     pub recent_blockhash: String,
     pub instructions: Vec<UiInstruction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_table_lookups: Option<Vec<UiAddressTableLookup>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TransactionWithStatusMeta {
-    pub transaction: Transaction,
+    pub transaction: VersionedTransaction,
     pub meta: Option<TransactionStatusMeta>,
 }
 
 impl TransactionWithStatusMeta {
+    /// The message as it was executed, with the addresses loaded by a versioned
+    /// message, or `None` if the status meta doesn't record them
+    pub fn resolved_message(&self) -> Option<Message> {
+        match &self.transaction.message {
+            VersionedMessage::Legacy(message) => Some(message.clone()),
+            VersionedMessage::V0(message) => self
+                .meta
+                .as_ref()
+                .and_then(|meta| meta.loaded_addresses.as_ref())
+                .and_then(|loaded_addresses| message.clone().into_legacy_message(loaded_addresses)),
+        }
+    }
+
+    /// The transaction as it was executed, see `resolved_message`
+    pub fn resolved_transaction(&self) -> Option<Transaction> {
+        self.resolved_message().map(|message| Transaction {
+            signatures: self.transaction.signatures.clone(),
+            message,
+        })
+    }
+
     fn encode(self, encoding: UiTransactionEncoding) -> EncodedTransactionWithStatusMeta {
-        let message = self.transaction.message();
-        let meta = self.meta.map(|meta| meta.encode(encoding, message));
+        let message = self.resolved_message();
+        let meta = self
+            .meta
+            .map(|meta| meta.encode(encoding, message.as_ref()));
         EncodedTransactionWithStatusMeta {
-            transaction: EncodedTransaction::encode(self.transaction, encoding),
+            transaction: EncodedTransaction::encode_with_message(
+                self.transaction,
+                encoding,
+                message.as_ref(),
+            ),
             meta,
         }
     }
@@ -462,9 +655,15 @@ pub struct EncodedTransactionWithStatusMeta {
 }
 
 impl TransactionStatusMeta {
-    fn encode(self, encoding: UiTransactionEncoding, message: &Message) -> UiTransactionStatusMeta {
-        match encoding {
-            UiTransactionEncoding::JsonParsed => UiTransactionStatusMeta::parse(self, message),
+    fn encode(
+        self,
+        encoding: UiTransactionEncoding,
+        message: Option<&Message>,
+    ) -> UiTransactionStatusMeta {
+        match (encoding, message) {
+            (UiTransactionEncoding::JsonParsed, Some(message)) => {
+                UiTransactionStatusMeta::parse(self, message)
+            }
             _ => self.into(),
         }
     }
@@ -497,7 +696,22 @@ pub enum EncodedTransaction {
 }
 
 impl EncodedTransaction {
-    pub fn encode(transaction: Transaction, encoding: UiTransactionEncoding) -> Self {
+    pub fn encode(transaction: VersionedTransaction, encoding: UiTransactionEncoding) -> Self {
+        let message = match &transaction.message {
+            VersionedMessage::Legacy(message) => Some(message.clone()),
+            VersionedMessage::V0(_) => None,
+        };
+        Self::encode_with_message(transaction, encoding, message.as_ref())
+    }
+
+    /// Encode `transaction`, parsing its instructions against `message` when it
+    /// is lowered from a versioned message.  Without a `message` to parse
+    /// against, the transaction is encoded in raw JSON instead of parsed JSON.
+    fn encode_with_message(
+        transaction: VersionedTransaction,
+        encoding: UiTransactionEncoding,
+        message: Option<&Message>,
+    ) -> Self {
         match encoding {
             UiTransactionEncoding::Binary => EncodedTransaction::LegacyBinary(
                 bs58::encode(bincode::serialize(&transaction).unwrap()).into_string(),
@@ -511,36 +725,40 @@ impl EncodedTransaction {
                 encoding,
             ),
             UiTransactionEncoding::Json | UiTransactionEncoding::JsonParsed => {
-                let message = if encoding == UiTransactionEncoding::Json {
-                    UiMessage::Raw(UiRawMessage {
-                        header: transaction.message.header,
+                let address_table_lookups = transaction
+                    .message
+                    .address_table_lookups()
+                    .map(|lookups| lookups.iter().map(UiAddressTableLookup::from).collect());
+                let message = match (encoding, message) {
+                    (UiTransactionEncoding::JsonParsed, Some(message)) => {
+                        UiMessage::Parsed(UiParsedMessage {
+                            account_keys: parse_accounts(message),
+                            recent_blockhash: message.recent_blockhash.to_string(),
+                            instructions: message
+                                .instructions
+                                .iter()
+                                .map(|instruction| UiInstruction::parse(instruction, message))
+                                .collect(),
+                            address_table_lookups,
+                        })
+                    }
+                    _ => UiMessage::Raw(UiRawMessage {
+                        header: transaction.message.header().clone(),
                         account_keys: transaction
                             .message
-                            .account_keys
+                            .static_account_keys()
                             .iter()
                             .map(|pubkey| pubkey.to_string())
                             .collect(),
-                        recent_blockhash: transaction.message.recent_blockhash.to_string(),
+                        recent_blockhash: transaction.message.recent_blockhash().to_string(),
                         instructions: transaction
                             .message
-                            .instructions
+                            .instructions()
                             .iter()
                             .map(|instruction| instruction.into())
                             .collect(),
-                    })
-                } else {
-                    UiMessage::Parsed(UiParsedMessage {
-                        account_keys: parse_accounts(&transaction.message),
-                        recent_blockhash: transaction.message.recent_blockhash.to_string(),
-                        instructions: transaction
-                            .message
-                            .instructions
-                            .iter()
-                            .map(|instruction| {
-                                UiInstruction::parse(instruction, &transaction.message)
-                            })
-                            .collect(),
-                    })
+                        address_table_lookups,
+                    }),
                 };
                 EncodedTransaction::Json(UiTransaction {
                     signatures: transaction
@@ -553,8 +771,15 @@ impl EncodedTransaction {
             }
         }
     }
+
+    /// Decode a legacy transaction, `None` for versioned transactions
     pub fn decode(&self) -> Option<Transaction> {
-        let transaction: Option<Transaction> = match self {
+        self.decode_versioned()
+            .and_then(|transaction| transaction.into_legacy_transaction())
+    }
+
+    pub fn decode_versioned(&self) -> Option<VersionedTransaction> {
+        let transaction: Option<VersionedTransaction> = match self {
             EncodedTransaction::Json(_) => None,
             EncodedTransaction::LegacyBinary(blob) => bs58::decode(blob)
                 .into_vec()
@@ -606,6 +831,57 @@ mod test {
         assert!(unsanitary_transaction.decode().is_none());
     }
 
+    #[test]
+    fn test_confirmed_block_configure() {
+        let transaction = Transaction {
+            signatures: vec![Signature::new(&[1; 64])],
+            ..Transaction::default()
+        };
+        let block = ConfirmedBlock {
+            previous_blockhash: "previous".to_string(),
+            blockhash: "blockhash".to_string(),
+            parent_slot: 1,
+            transactions: vec![TransactionWithStatusMeta {
+                transaction: transaction.clone().into(),
+                meta: None,
+            }],
+            rewards: vec![],
+            block_time: Some(2),
+        };
+
+        // Full details with rewards match the legacy encoding
+        assert_eq!(
+            serde_json::to_value(block.clone().configure(
+                UiTransactionEncoding::Base64,
+                TransactionDetails::Full,
+                true
+            ))
+            .unwrap(),
+            serde_json::to_value(block.clone().encode(UiTransactionEncoding::Base64)).unwrap()
+        );
+
+        let ui_block = block.clone().configure(
+            UiTransactionEncoding::Base64,
+            TransactionDetails::Signatures,
+            false,
+        );
+        assert_eq!(
+            ui_block.signatures,
+            Some(vec![transaction.signatures[0].to_string()])
+        );
+        assert!(ui_block.transactions.is_none());
+        assert!(ui_block.rewards.is_none());
+
+        let ui_block = block.configure(
+            UiTransactionEncoding::Base64,
+            TransactionDetails::None,
+            true,
+        );
+        assert!(ui_block.transactions.is_none());
+        assert!(ui_block.signatures.is_none());
+        assert_eq!(ui_block.rewards, Some(vec![]));
+    }
+
     #[test]
     fn test_satisfies_commitment() {
         let status = TransactionStatus {
@@ -684,4 +960,78 @@ mod test {
         };
         assert!(status.satisfies_commitment(CommitmentConfig::confirmed()));
     }
+
+    #[test]
+    fn test_encode_versioned_transaction() {
+        use solana_sdk::{
+            address_lookup_table_account::AddressLookupTableAccount,
+            message::v0,
+            signature::{Keypair, Signer},
+            system_instruction,
+        };
+
+        let keypair = Keypair::new();
+        let recipient = solana_sdk::pubkey::new_rand();
+        let message = v0::Message::new(
+            &[system_instruction::transfer(
+                &keypair.pubkey(),
+                &recipient,
+                1,
+            )],
+            Some(&keypair.pubkey()),
+            &[AddressLookupTableAccount {
+                key: solana_sdk::pubkey::new_rand(),
+                addresses: vec![recipient],
+            }],
+        );
+        let transaction =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&keypair]).unwrap();
+        let encoded =
+            EncodedTransaction::encode(transaction.clone(), UiTransactionEncoding::Base64);
+        assert_eq!(encoded.decode_versioned(), Some(transaction.clone()));
+        assert_eq!(encoded.decode(), None);
+
+        // Without the loaded addresses, instructions can't be parsed
+        let mut transaction_with_meta = TransactionWithStatusMeta {
+            transaction,
+            meta: Some(TransactionStatusMeta::default()),
+        };
+        match transaction_with_meta
+            .clone()
+            .encode(UiTransactionEncoding::JsonParsed)
+            .transaction
+        {
+            EncodedTransaction::Json(UiTransaction {
+                message: UiMessage::Raw(message),
+                ..
+            }) => {
+                assert_eq!(message.account_keys.len(), 2);
+                assert_eq!(message.address_table_lookups.unwrap().len(), 1);
+            }
+            encoded => panic!("unexpected encoding: {:?}", encoded),
+        }
+
+        transaction_with_meta
+            .meta
+            .as_mut()
+            .unwrap()
+            .loaded_addresses = Some(LoadedAddresses {
+            writable: vec![recipient],
+            readonly: vec![],
+        });
+        match transaction_with_meta
+            .encode(UiTransactionEncoding::JsonParsed)
+            .transaction
+        {
+            EncodedTransaction::Json(UiTransaction {
+                message: UiMessage::Parsed(message),
+                ..
+            }) => {
+                assert_eq!(message.account_keys[1].pubkey, recipient.to_string());
+                assert!(message.account_keys[1].writable);
+                assert_eq!(message.address_table_lookups.unwrap().len(), 1);
+            }
+            encoded => panic!("unexpected encoding: {:?}", encoded),
+        }
+    }
 }
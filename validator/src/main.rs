@@ -22,13 +22,16 @@ use solana_core::{
     poh_service,
     rpc::JsonRpcConfig,
     rpc_pubsub_service::PubSubConfig,
+    rpc_rate_limiter::{parse_method_setting, RpcRateLimitConfig},
     validator::{is_snapshot_config_invalid, Validator, ValidatorConfig},
 };
-use solana_download_utils::{download_genesis_if_missing, download_snapshot};
+use solana_download_utils::{download_genesis_if_missing, download_snapshot_from_peers};
 use solana_ledger::blockstore_db::BlockstoreRecoveryMode;
 use solana_perf::recycler::enable_recycler_warming;
 use solana_runtime::{
-    accounts_index::AccountIndex,
+    accounts_index::{
+        AccountIndex, AccountsIndexConfig, MemcmpIndex, DEFAULT_ACCOUNTS_INDEX_IN_MEMORY_CAPACITY,
+    },
     bank_forks::{ArchiveFormat, SnapshotConfig, SnapshotVersion},
     hardened_unpack::{unpack_genesis_archive, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
     snapshot_utils::get_highest_snapshot_archive_path,
@@ -41,9 +44,10 @@ use solana_sdk::{
     pubkey::Pubkey,
     signature::{Keypair, Signer},
 };
+use solana_storage_postgres::PostgresConfig;
 use solana_validator::redirect_stderr_to_file;
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     env,
     fs::{self, File},
     net::{IpAddr, SocketAddr, TcpListener, UdpSocket},
@@ -58,6 +62,9 @@ use std::{
     time::{Duration, Instant},
 };
 
+/// Maximum number of RPC nodes a snapshot is downloaded from in parallel
+const MAX_SNAPSHOT_DOWNLOAD_PEERS: usize = 8;
+
 #[derive(Debug, PartialEq)]
 enum Operation {
     Initialize,
@@ -155,7 +162,7 @@ fn get_rpc_node(
     snapshot_not_required: bool,
     no_untrusted_rpc: bool,
     ledger_path: &std::path::Path,
-) -> Option<(ContactInfo, Option<(Slot, Hash)>)> {
+) -> Option<(ContactInfo, Option<(Slot, Hash)>, Vec<ContactInfo>)> {
     let mut blacklist_timeout = Instant::now();
     let mut newer_cluster_snapshot_timeout = None;
     let mut retry_reason = None;
@@ -316,8 +323,8 @@ fn get_rpc_node(
 
         if !eligible_rpc_peers.is_empty() {
             let contact_info =
-                &eligible_rpc_peers[thread_rng().gen_range(0, eligible_rpc_peers.len())];
-            return Some((contact_info.clone(), highest_snapshot_hash));
+                eligible_rpc_peers[thread_rng().gen_range(0, eligible_rpc_peers.len())].clone();
+            return Some((contact_info, highest_snapshot_hash, eligible_rpc_peers));
         } else {
             retry_reason = Some("No snapshots available".to_owned());
         }
@@ -397,6 +404,14 @@ fn hardforks_of(matches: &ArgMatches<'_>, name: &str) -> Option<Vec<Slot>> {
     }
 }
 
+fn method_settings_of(matches: &ArgMatches<'_>, name: &str) -> HashMap<String, u64> {
+    matches
+        .values_of(name)
+        .unwrap_or_default()
+        .map(|setting| parse_method_setting(setting).unwrap())
+        .collect()
+}
+
 fn validators_set(
     identity_pubkey: &Pubkey,
     matches: &ArgMatches<'_>,
@@ -552,6 +567,7 @@ struct RpcBootstrapConfig {
     no_untrusted_rpc: bool,
     max_genesis_archive_unpacked_size: u64,
     no_check_vote_account: bool,
+    minimal_snapshot_download_speed: u64,
 }
 
 impl Default for RpcBootstrapConfig {
@@ -562,6 +578,7 @@ impl Default for RpcBootstrapConfig {
             no_untrusted_rpc: true,
             max_genesis_archive_unpacked_size: MAX_GENESIS_ARCHIVE_UNPACKED_SIZE,
             no_check_vote_account: true,
+            minimal_snapshot_download_speed: 0,
         }
     }
 }
@@ -624,7 +641,7 @@ fn rpc_bootstrap(
         if rpc_node_details.is_none() {
             return;
         }
-        let (rpc_contact_info, snapshot_hash) = rpc_node_details.unwrap();
+        let (rpc_contact_info, snapshot_hash, eligible_rpc_peers) = rpc_node_details.unwrap();
 
         info!(
             "Using RPC service from node {}: {:?}",
@@ -708,11 +725,23 @@ fn rpc_bootstrap(
                                 gossip.take().unwrap();
                             cluster_info.save_contact_info();
                             gossip_exit_flag.store(true, Ordering::Relaxed);
-                            let ret = download_snapshot(
-                                &rpc_contact_info.rpc,
+                            // Every eligible peer advertises the same snapshot hash, so the
+                            // archive can be fetched from several of them at once
+                            let rpc_addrs: Vec<_> = std::iter::once(rpc_contact_info.rpc)
+                                .chain(
+                                    eligible_rpc_peers
+                                        .iter()
+                                        .filter(|rpc_peer| rpc_peer.id != rpc_contact_info.id)
+                                        .map(|rpc_peer| rpc_peer.rpc),
+                                )
+                                .take(MAX_SNAPSHOT_DOWNLOAD_PEERS)
+                                .collect();
+                            let ret = download_snapshot_from_peers(
+                                &rpc_addrs,
                                 &ledger_path,
                                 snapshot_hash,
                                 use_progress_bar,
+                                bootstrap_config.minimal_snapshot_download_speed,
                             );
                             gossip_service.join().unwrap();
                             ret
@@ -789,6 +818,9 @@ pub fn main() {
         .send_transaction_leader_forward_count
         .to_string();
     let default_rpc_threads = num_cpus::get().to_string();
+    let default_postgres_batch_size = PostgresConfig::default().batch_size.to_string();
+    let default_accounts_index_memory_entries =
+        DEFAULT_ACCOUNTS_INDEX_IN_MEMORY_CAPACITY.to_string();
 
     let matches = App::new(crate_name!()).about(crate_description!())
         .version(solana_version::version!())
@@ -961,6 +993,43 @@ pub fn main() {
                 .takes_value(false)
                 .help("Upload new confirmed blocks into a BigTable instance"),
         )
+        .arg(
+            Arg::with_name("bigtable_ledger_local_storage")
+                .long("bigtable-ledger-local-storage")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Keep the BigTable ledger tables in this local directory instead of a \
+                       BigTable instance, for use with --enable-rpc-bigtable-ledger-storage \
+                       and --enable-bigtable-ledger-upload"),
+        )
+        .arg(
+            Arg::with_name("postgres_ledger_upload")
+                .long("postgres-ledger-upload")
+                .value_name("CONNECTION_STRING")
+                .requires("enable_rpc_transaction_history")
+                .takes_value(true)
+                .help("Write new confirmed blocks, transaction statuses and token balances \
+                       into the PostgreSQL database described by this connection string, \
+                       for example \"host=localhost user=solana dbname=solana\""),
+        )
+        .arg(
+            Arg::with_name("postgres_batch_size")
+                .long("postgres-batch-size")
+                .value_name("COUNT")
+                .requires("postgres_ledger_upload")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value(&default_postgres_batch_size)
+                .help("Number of blocks, or account updates, written to PostgreSQL \
+                       per database transaction"),
+        )
+        .arg(
+            Arg::with_name("postgres_account_updates")
+                .long("postgres-account-updates")
+                .requires("postgres_ledger_upload")
+                .takes_value(false)
+                .help("Also write every rooted account update into PostgreSQL"),
+        )
         .arg(
             Arg::with_name("enable_cpi_and_log_storage")
                 .long("enable-cpi-and-log-storage")
@@ -978,6 +1047,69 @@ pub fn main() {
                 .help("Override the default maximum accounts accepted by \
                        the getMultipleAccounts JSON RPC method")
         )
+        .arg(
+            Arg::with_name("rpc_max_program_accounts_response_bytes")
+                .long("rpc-max-program-accounts-response-bytes")
+                .value_name("BYTES")
+                .validator(is_parsable::<usize>)
+                .takes_value(true)
+                .help("Fail getProgramAccounts JSON RPC requests whose response would exceed \
+                       this many bytes, so that clients page through large results instead \
+                       [default: no limit]")
+        )
+        .arg(
+            Arg::with_name("rpc_rate_limit_per_client")
+                .long("rpc-rate-limit-per-client")
+                .value_name("BUDGET")
+                .validator(is_parsable::<u64>)
+                .takes_value(true)
+                .help("Budget each client may spend on JSON RPC calls per second, where most \
+                       calls cost 1 and heavy calls cost more (see --rpc-method-cost). \
+                       Clients are identified by their address, or by the X-Forwarded-For \
+                       or X-Real-IP header set by a --rpc-trusted-proxy \
+                       [default: no limit]")
+        )
+        .arg(
+            Arg::with_name("rpc_trusted_proxy")
+                .long("rpc-trusted-proxy")
+                .value_name("IP_ADDRESS")
+                .takes_value(true)
+                .multiple(true)
+                .validator(is_parsable::<IpAddr>)
+                .requires("rpc_rate_limit_per_client")
+                .help("Address of a reverse proxy whose X-Forwarded-For and X-Real-IP headers \
+                       identify JSON RPC clients for --rpc-rate-limit-per-client. Headers \
+                       from any other peer are ignored")
+        )
+        .arg(
+            Arg::with_name("rpc_rate_limit_per_client_burst")
+                .long("rpc-rate-limit-per-client-burst")
+                .value_name("BUDGET")
+                .validator(is_parsable::<u64>)
+                .takes_value(true)
+                .requires("rpc_rate_limit_per_client")
+                .help("Budget a client may spend on JSON RPC calls in a burst \
+                       [default: the value of --rpc-rate-limit-per-client]")
+        )
+        .arg(
+            Arg::with_name("rpc_rate_limit_method")
+                .long("rpc-rate-limit-method")
+                .value_name("METHOD=CALLS")
+                .takes_value(true)
+                .multiple(true)
+                .validator(|setting| parse_method_setting(&setting).map(|_| ()))
+                .help("Limit calls to a JSON RPC method to CALLS per second across all clients")
+        )
+        .arg(
+            Arg::with_name("rpc_method_cost")
+                .long("rpc-method-cost")
+                .value_name("METHOD=COST")
+                .takes_value(true)
+                .multiple(true)
+                .validator(|setting| parse_method_setting(&setting).map(|_| ()))
+                .help("Override the budget a call to a JSON RPC method consumes from its \
+                       client's rate limit")
+        )
         .arg(
             Arg::with_name("health_check_slot_distance")
                 .long("health-check-slot-distance")
@@ -1070,6 +1202,18 @@ pub fn main() {
                 .help("Number of slots between generating snapshots, \
                       0 to disable snapshots"),
         )
+        .arg(
+            Arg::with_name("incremental_snapshot_interval_slots")
+                .long("incremental-snapshot-interval-slots")
+                .value_name("INCREMENTAL_SNAPSHOT_INTERVAL_SLOTS")
+                .takes_value(true)
+                .default_value("0")
+                .help("Number of slots between generating incremental snapshots on top of \
+                      the last full snapshot, 0 to disable incremental snapshots. \
+                      Their hashes are published in gossip messages that nodes running \
+                      older releases can't decode, so only enable this once the cluster \
+                      has upgraded"),
+        )
         .arg(
             Arg::with_name("contact_debug_interval")
                 .long("contact-debug-interval")
@@ -1205,6 +1349,17 @@ pub fn main() {
                 .takes_value(false)
                 .help("Use the RPC service of trusted validators only")
         )
+        .arg(
+            Arg::with_name("minimal_snapshot_download_speed")
+                .long("minimal-snapshot-download-speed")
+                .value_name("BYTES_PER_SECOND")
+                .validator(is_parsable::<u64>)
+                .takes_value(true)
+                .default_value("10485760")
+                .help("Abandon an RPC node while downloading a snapshot if its download \
+                       speed drops below this many bytes per second, and continue the \
+                       download from the other RPC nodes serving the snapshot"),
+        )
         .arg(
             Arg::with_name("repair_validators")
                 .long("repair-validator")
@@ -1273,6 +1428,13 @@ pub fn main() {
                 .takes_value(false)
                 .help("Enable the unstable RPC PubSub `voteSubscribe` subscription"),
         )
+        .arg(
+            Arg::with_name("rpc_pubsub_enable_block_subscription")
+                .long("rpc-pubsub-enable-block-subscription")
+                .requires("enable_rpc_transaction_history")
+                .takes_value(false)
+                .help("Enable the unstable RPC PubSub `blockSubscribe` subscription"),
+        )
         .arg(
             Arg::with_name("rpc_pubsub_max_connections")
                 .long("rpc-pubsub-max-connections")
@@ -1413,6 +1575,42 @@ pub fn main() {
                 .value_name("INDEX")
                 .help("Enable an accounts index, indexed by the selected account field"),
         )
+        .arg(
+            Arg::with_name("account_index_memcmp")
+                .long("account-index-memcmp")
+                .takes_value(true)
+                .multiple(true)
+                .validator(is_parsable::<MemcmpIndex>)
+                .value_name("PROGRAM_ID:OFFSET")
+                .help("Enable an accounts index over the accounts owned by PROGRAM_ID, \
+                       indexed by the 32 bytes at OFFSET in the account data. \
+                       getProgramAccounts requests with a matching memcmp filter use the index"),
+        )
+        .arg(
+            Arg::with_name("disk_accounts_index")
+                .long("disk-accounts-index")
+                .help("Keep the accounts index on disk, caching recently used entries in memory. \
+                       Reduces memory use at the cost of slower account lookups"),
+        )
+        .arg(
+            Arg::with_name("accounts_index_path")
+                .long("accounts-index-path")
+                .value_name("PATH")
+                .takes_value(true)
+                .requires("disk_accounts_index")
+                .help("Directory for the on-disk accounts index [default: LEDGER/accounts_index]"),
+        )
+        .arg(
+            Arg::with_name("accounts_index_memory_entries")
+                .long("accounts-index-memory-entries")
+                .value_name("NUMBER")
+                .takes_value(true)
+                .requires("disk_accounts_index")
+                .validator(is_parsable::<usize>)
+                .default_value(&default_accounts_index_memory_entries)
+                .help("Number of accounts index entries to keep in memory with \
+                       --disk-accounts-index"),
+        )
         .arg(
             Arg::with_name("no_accounts_db_caching")
                 .long("no-accounts-db-caching")
@@ -1423,6 +1621,15 @@ pub fn main() {
                 .long("accounts-db-test-hash-calculation")
                 .help("Enables testing of hash calculation using stores in AccountsHashVerifier. This has a computational cost."),
         )
+        .arg(
+            Arg::with_name("geyser_plugin_config")
+                .long("geyser-plugin-config")
+                .value_name("FILE")
+                .takes_value(true)
+                .multiple(true)
+                .help("Load a Geyser plugin described by this JSON config file. \
+                       May be specified multiple times"),
+        )
         .arg(
             // legacy nop argument
             Arg::with_name("accounts_db_caching_enabled")
@@ -1473,6 +1680,11 @@ pub fn main() {
             "max_genesis_archive_unpacked_size",
             u64
         ),
+        minimal_snapshot_download_speed: value_t_or_exit!(
+            matches,
+            "minimal_snapshot_download_speed",
+            u64
+        ),
     };
 
     let private_rpc = matches.is_present("private_rpc");
@@ -1538,6 +1750,12 @@ pub fn main() {
             "spl-token-owner" => AccountIndex::SplTokenOwner,
             _ => unreachable!(),
         })
+        .chain(
+            matches
+                .values_of("account_index_memcmp")
+                .unwrap_or_default()
+                .map(|value| AccountIndex::Memcmp(value.parse().unwrap())),
+        )
         .collect();
 
     let restricted_repair_only_mode = matches.is_present("restricted_repair_only_mode");
@@ -1570,6 +1788,12 @@ pub fn main() {
                 "rpc_max_multiple_accounts",
                 usize
             )),
+            max_program_accounts_response_bytes: value_t!(
+                matches,
+                "rpc_max_program_accounts_response_bytes",
+                usize
+            )
+            .ok(),
             health_check_slot_distance: value_t_or_exit!(
                 matches,
                 "health_check_slot_distance",
@@ -1579,7 +1803,21 @@ pub fn main() {
             rpc_bigtable_timeout: value_t!(matches, "rpc_bigtable_timeout", u64)
                 .ok()
                 .map(Duration::from_secs),
+            rate_limit: RpcRateLimitConfig {
+                per_client_budget_per_second: value_t!(matches, "rpc_rate_limit_per_client", u64)
+                    .ok(),
+                per_client_burst: value_t!(matches, "rpc_rate_limit_per_client_burst", u64).ok(),
+                method_calls_per_second: method_settings_of(&matches, "rpc_rate_limit_method"),
+                method_costs: method_settings_of(&matches, "rpc_method_cost"),
+                trusted_proxies: values_t!(matches, "rpc_trusted_proxy", IpAddr)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect(),
+            },
             account_indexes: account_indexes.clone(),
+            bigtable_ledger_local_storage: matches
+                .value_of("bigtable_ledger_local_storage")
+                .map(PathBuf::from),
         },
         rpc_addrs: value_t!(matches, "rpc_port", u16).ok().map(|rpc_port| {
             (
@@ -1592,6 +1830,7 @@ pub fn main() {
         }),
         pubsub_config: PubSubConfig {
             enable_vote_subscription: matches.is_present("rpc_pubsub_enable_vote_subscription"),
+            enable_block_subscription: matches.is_present("rpc_pubsub_enable_block_subscription"),
             max_connections: value_t_or_exit!(matches, "rpc_pubsub_max_connections", usize),
             max_fragment_size: value_t_or_exit!(matches, "rpc_pubsub_max_fragment_size", usize),
             max_in_buffer_capacity: value_t_or_exit!(
@@ -1629,6 +1868,15 @@ pub fn main() {
         account_indexes,
         accounts_db_caching_enabled: !matches.is_present("no_accounts_db_caching"),
         accounts_db_test_hash_calculation: matches.is_present("accounts_db_test_hash_calculation"),
+        geyser_plugin_config_files: values_t!(matches, "geyser_plugin_config", PathBuf)
+            .unwrap_or_default(),
+        postgres_config: matches
+            .value_of("postgres_ledger_upload")
+            .map(|connection_str| PostgresConfig {
+                connection_str: connection_str.to_string(),
+                batch_size: value_t_or_exit!(matches, "postgres_batch_size", usize),
+                account_updates: matches.is_present("postgres_account_updates"),
+            }),
         ..ValidatorConfig::default()
     };
 
@@ -1654,6 +1902,14 @@ pub fn main() {
         } else {
             vec![ledger_path.join("accounts")]
         };
+    if matches.is_present("disk_accounts_index") {
+        let index_path = value_t!(matches, "accounts_index_path", PathBuf)
+            .unwrap_or_else(|_| ledger_path.join("accounts_index"));
+        validator_config.accounts_index_config = Some(AccountsIndexConfig {
+            in_memory_capacity: value_t_or_exit!(matches, "accounts_index_memory_entries", usize),
+            ..AccountsIndexConfig::new(index_path)
+        });
+    }
     let account_shrink_paths: Option<Vec<PathBuf>> =
         values_t!(matches, "account_shrink_path", String)
             .map(|shrink_paths| shrink_paths.into_iter().map(PathBuf::from).collect())
@@ -1697,6 +1953,8 @@ pub fn main() {
     });
 
     let snapshot_interval_slots = value_t_or_exit!(matches, "snapshot_interval_slots", u64);
+    let incremental_snapshot_interval_slots =
+        value_t_or_exit!(matches, "incremental_snapshot_interval_slots", u64);
     let maximum_local_snapshot_age = value_t_or_exit!(matches, "maximum_local_snapshot_age", u64);
     let snapshot_path = ledger_path.join("snapshot");
     fs::create_dir_all(&snapshot_path).unwrap_or_else(|err| {
@@ -1733,6 +1991,11 @@ pub fn main() {
         } else {
             std::u64::MAX
         },
+        incremental_snapshot_interval_slots: if incremental_snapshot_interval_slots > 0 {
+            incremental_snapshot_interval_slots
+        } else {
+            std::u64::MAX
+        },
         snapshot_path,
         snapshot_package_output_path: ledger_path.clone(),
         archive_format,
@@ -1755,6 +2018,26 @@ pub fn main() {
         );
         exit(1);
     }
+    if is_snapshot_config_invalid(
+        incremental_snapshot_interval_slots,
+        validator_config.accounts_hash_interval_slots,
+    ) {
+        eprintln!("Invalid incremental snapshot interval provided ({}), must be a multiple of accounts_hash_interval_slots ({})",
+            incremental_snapshot_interval_slots,
+            validator_config.accounts_hash_interval_slots,
+        );
+        exit(1);
+    }
+    if incremental_snapshot_interval_slots > 0
+        && (snapshot_interval_slots == 0
+            || incremental_snapshot_interval_slots >= snapshot_interval_slots)
+    {
+        eprintln!("Invalid incremental snapshot interval provided ({}), must be less than the snapshot interval ({})",
+            incremental_snapshot_interval_slots,
+            snapshot_interval_slots,
+        );
+        exit(1);
+    }
 
     if matches.is_present("limit_ledger_size") {
         let limit_ledger_size = match matches.value_of("limit_ledger_size") {